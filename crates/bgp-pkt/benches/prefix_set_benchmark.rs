@@ -0,0 +1,80 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use netgauze_bgp_pkt::matcher::PrefixSet;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+// jemalloc takes precedence when both allocators are enabled, e.g., with
+// `--all-features`
+#[cfg(feature = "bench-jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+#[cfg(all(feature = "bench-mimalloc", not(feature = "bench-jemalloc")))]
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+const PREFIXES: u32 = 1_000_000;
+
+/// Deterministic pseudo random numbers, so runs are comparable
+fn lcg(seed: &mut u64) -> u32 {
+    *seed = seed
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    (*seed >> 32) as u32
+}
+
+fn ipv4_prefix(seed: &mut u64, len: u8) -> IpNet {
+    IpNet::V4(
+        Ipv4Net::new(Ipv4Addr::from(lcg(seed)), len)
+            .unwrap()
+            .trunc(),
+    )
+}
+
+fn ipv6_prefix(seed: &mut u64, len: u8) -> IpNet {
+    let address = ((lcg(seed) as u128) << 96) | ((lcg(seed) as u128) << 64);
+    IpNet::V6(Ipv6Net::new(Ipv6Addr::from(address), len).unwrap().trunc())
+}
+
+/// A million IPv4 prefixes of lengths /16 to /28 and a tenth of that of IPv6
+/// /32 to /48 prefixes, roughly the shape of customer prefix lists
+fn prefix_set() -> PrefixSet {
+    let mut seed = 42;
+    let mut set = PrefixSet::new();
+    for i in 0..PREFIXES {
+        set.insert(ipv4_prefix(&mut seed, 16 + (i % 13) as u8));
+        if i % 10 == 0 {
+            set.insert(ipv6_prefix(&mut seed, 32 + (i % 17) as u8));
+        }
+    }
+    set
+}
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    c.bench_function("prefix set build", |b| b.iter(prefix_set));
+
+    let set = prefix_set();
+    let mut seed = 7;
+    let ipv4_24: Vec<_> = (0..1024).map(|_| ipv4_prefix(&mut seed, 24)).collect();
+    let ipv4_32: Vec<_> = (0..1024).map(|_| ipv4_prefix(&mut seed, 32)).collect();
+    let ipv4_20: Vec<_> = (0..1024).map(|_| ipv4_prefix(&mut seed, 20)).collect();
+    let ipv6_48: Vec<_> = (0..1024).map(|_| ipv6_prefix(&mut seed, 48)).collect();
+    for (name, prefixes) in [
+        ("prefix set contains ipv4 /24", &ipv4_24),
+        ("prefix set contains ipv4 /32", &ipv4_32),
+        ("prefix set contains ipv4 /20", &ipv4_20),
+        ("prefix set contains ipv6 /48", &ipv6_48),
+    ] {
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                prefixes
+                    .iter()
+                    .filter(|prefix| set.contains(black_box(prefix)))
+                    .count()
+            })
+        });
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -2,6 +2,16 @@ use criterion::{criterion_group, criterion_main, Criterion};
 use netgauze_bgp_pkt::{wire::deserializer::BgpParsingContext, BgpMessage};
 use netgauze_parse_utils::{ReadablePduWithOneInput, Span};
 
+// jemalloc takes precedence when both allocators are enabled, e.g., with
+// `--all-features`
+#[cfg(feature = "bench-jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+#[cfg(all(feature = "bench-mimalloc", not(feature = "bench-jemalloc")))]
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
 const OPEN_COMPLEX_NO_PARAMS: [u8; 29] = [
     0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
     0x00, 0x1d, 0x01, 0x04, 0xfe, 0x09, 0x00, 0xb4, 0xc0, 0xa8, 0x00, 0x0f, 0x00,
@@ -39,6 +49,24 @@ const OPEN_COMPLEX_RAW: [u8; 123] = [
     0x02, 0x00, 0x80, 0x01, 0x04, 0x00, 0x19, 0x00, 0x46,
 ];
 
+const UPDATE_RAW: [u8; 75] = [
+    // BGP Marker
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    // Message Length
+    0x00, 0x4b, // Message type
+    0x02, // Withdrawn routes length
+    0x00, 0x08, // Withdrawn routes: 172.16.3.0/24, 172.16.4.0/24
+    0x18, 0xac, 0x10, 0x03, 0x18, 0xac, 0x10, 0x04, // Path attributes length
+    0x00, 0x24, // Origin IGP
+    0x40, 0x01, 0x01, 0x00, // AS Path: 200 100
+    0x50, 0x02, 0x00, 0x0a, 0x02, 0x02, 0x00, 0x00, 0x00, 0xc8, 0x00, 0x00, 0x00, 0x64,
+    // Next hop: 172.16.0.20
+    0x40, 0x03, 0x04, 0xac, 0x10, 0x00, 0x14, // Communities: 65000:100, 65000:200
+    0xc0, 0x08, 0x08, 0xfd, 0xe8, 0x00, 0x64, 0xfd, 0xe8, 0x00, 0xc8,
+    // NLRI: 172.16.1.0/24, 172.16.2.0/24
+    0x18, 0xac, 0x10, 0x01, 0x18, 0xac, 0x10, 0x02,
+];
+
 pub fn test_open_message_no_params(span: Span<'_>) {
     let x = BgpMessage::from_wire(span, &mut BgpParsingContext::default());
     x.unwrap();
@@ -48,6 +76,11 @@ pub fn test_complex_open_message(span: Span<'_>) {
     x.unwrap();
 }
 
+pub fn test_update_message(span: Span<'_>) {
+    let x = BgpMessage::from_wire(span, &mut BgpParsingContext::default());
+    x.unwrap();
+}
+
 pub fn criterion_benchmark(c: &mut Criterion) {
     let no_params_span = Span::new(&OPEN_COMPLEX_NO_PARAMS);
     let complex_span = Span::new(&OPEN_COMPLEX_RAW);
@@ -57,6 +90,9 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("open complex", |b| {
         b.iter(|| test_complex_open_message(complex_span))
     });
+    // Compare with `--features smallvec` to evaluate inlining the short lists
+    let update_span = Span::new(&UPDATE_RAW);
+    c.bench_function("update", |b| b.iter(|| test_update_message(update_span)));
 }
 
 criterion_group!(benches, criterion_benchmark);
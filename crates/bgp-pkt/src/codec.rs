@@ -16,19 +16,47 @@
 use byteorder::{ByteOrder, NetworkEndian};
 use bytes::{Buf, BufMut, BytesMut};
 use nom::Needed;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tokio_util::codec::{Decoder, Encoder};
 
 use crate::{
     capabilities::BgpCapability,
+    iana::BgpMessageType,
     wire::{
         deserializer::{BgpMessageParsingError, BgpParsingContext, BgpParsingIgnoredErrors},
         serializer::BgpMessageWritingError,
     },
     BgpMessage,
 };
-use netgauze_parse_utils::{LocatedParsingError, ReadablePduWithOneInput, Span, WritablePdu};
+use netgauze_parse_utils::{
+    Categorize, ErrorCategory, LocatedParsingError, ReadablePduWithOneInput, Span, WritablePdu,
+};
+
+/// BGP message header: 16-octet marker + 2-octet length + 1-octet type
+pub const BGP_HEADER_LENGTH: usize = 19;
+
+/// Marker at the start of every BGP message
+pub const BGP_MARKER: [u8; 16] = [0xff; 16];
+
+/// Find the offset of the next plausible BGP message in `buf`, skipping the
+/// first byte since the message there is assumed to be broken. A message is
+/// plausible when it starts with [`BGP_MARKER`] followed by a length that
+/// covers at least the header and a defined [`BgpMessageType`]. At most
+/// `max_scan` bytes are scanned.
+pub fn find_next_message(buf: &[u8], max_scan: usize) -> Option<usize> {
+    let end = buf.len().min(max_scan.saturating_add(1));
+    (1..end).find(|&offset| {
+        let header = match buf.get(offset..offset + BGP_HEADER_LENGTH) {
+            Some(header) => header,
+            None => return false,
+        };
+        header[..16] == BGP_MARKER
+            && NetworkEndian::read_u16(&header[16..18]) as usize >= BGP_HEADER_LENGTH
+            && BgpMessageType::from_repr(header[18]).is_some()
+    })
+}
 
 pub trait BgpCodecInitializer<Peer> {
     fn new(peer: &Peer) -> Self;
@@ -39,6 +67,9 @@ pub struct BgpCodec {
     asn4_sent: Option<bool>,
     asn4_received: Option<bool>,
     ctx: BgpParsingContext,
+    /// When set, max number of bytes scanned for the next message after a
+    /// parsing error
+    resync: Option<usize>,
 }
 
 impl BgpCodec {
@@ -55,6 +86,43 @@ impl BgpCodec {
                 false,
                 false,
             ),
+            resync: None,
+        }
+    }
+
+    /// Max number of bytes scanned to resynchronize on the next message after
+    /// a parsing error, if enabled
+    pub const fn resync(&self) -> Option<usize> {
+        self.resync
+    }
+
+    /// Opt in to recover from a desynchronized stream, e.g., in long-lived
+    /// raw BGP captures. After a parsing error, the decoder skips to the next
+    /// plausible message found by [`find_next_message`] within `max_scan`
+    /// bytes. When none is found, the scanned bytes are dropped and the scan
+    /// continues on the next call. The error is still returned.
+    ///
+    /// Without it (the default), the buffer is left untouched on errors.
+    pub fn set_resync(&mut self, max_scan: Option<usize>) {
+        self.resync = max_scan;
+    }
+
+    /// Skip the broken message at the start of `buf`
+    fn resync_buffer(&self, buf: &mut BytesMut, max_scan: usize) {
+        match find_next_message(buf, max_scan) {
+            Some(offset) => {
+                log::info!("Resynchronized on the next BGP message after {offset} bytes");
+                buf.advance(offset);
+            }
+            None => {
+                // Keep the bytes that can still be the start of a header
+                let skip = buf
+                    .len()
+                    .saturating_sub(BGP_HEADER_LENGTH - 1)
+                    .clamp(1, max_scan.max(1));
+                log::info!("No BGP message found to resynchronize, dropping {skip} bytes");
+                buf.advance(skip.min(buf.len()));
+            }
         }
     }
 }
@@ -65,13 +133,39 @@ impl<Peer> BgpCodecInitializer<Peer> for BgpCodec {
     }
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpCodecDecoderError {
     IoError(String),
     Incomplete(Option<usize>),
     BgpMessageParsingError(BgpMessageParsingError),
 }
 
+impl Categorize for BgpCodecDecoderError {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            Self::IoError(_) => ErrorCategory::Io,
+            Self::Incomplete(_) => ErrorCategory::Truncated,
+            Self::BgpMessageParsingError(error) => error.category(),
+        }
+    }
+
+    fn variant(&self) -> &'static str {
+        match self {
+            Self::IoError(_) => "IoError",
+            Self::Incomplete(_) => "Incomplete",
+            Self::BgpMessageParsingError(_) => "BgpMessageParsingError",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn Categorize> {
+        match self {
+            Self::IoError(_) | Self::Incomplete(_) => None,
+            Self::BgpMessageParsingError(error) => Some(error),
+        }
+    }
+}
+
 impl From<std::io::Error> for BgpCodecDecoderError {
     fn from(error: std::io::Error) -> Self {
         Self::IoError(error.to_string())
@@ -83,7 +177,7 @@ impl Decoder for BgpCodec {
     type Error = BgpCodecDecoderError;
 
     fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        if buf.len() >= 19 {
+        if buf.len() >= BGP_HEADER_LENGTH {
             let length: u16 = NetworkEndian::read_u16(&buf[16..19]);
             let length = length as usize;
             if buf.len() < length {
@@ -102,7 +196,6 @@ impl Decoder for BgpCodec {
                         if let BgpMessage::Open(ref open) = msg {
                             let asn4 = open
                                 .capabilities()
-                                .into_iter()
                                 .any(|cap| matches!(cap, BgpCapability::FourOctetAs(_)));
                             log::debug!("Sending ASN4 received to: {asn4}");
                             self.asn4_received = Some(asn4);
@@ -123,6 +216,9 @@ impl Decoder for BgpCodec {
                                 BgpCodecDecoderError::BgpMessageParsingError(error.error().clone())
                             }
                         };
+                        if let Some(max_scan) = self.resync {
+                            self.resync_buffer(buf, max_scan);
+                        }
                         Err(err)
                     }
                 };
@@ -147,7 +243,6 @@ impl Encoder<BgpMessage> for BgpCodec {
         if let BgpMessage::Open(ref open) = msg {
             let asn4 = open
                 .capabilities()
-                .into_iter()
                 .any(|cap| matches!(cap, BgpCapability::FourOctetAs(_)));
             log::debug!("Sending ASN4 sent to: {asn4}");
             self.asn4_sent = Some(asn4);
@@ -155,3 +250,62 @@ impl Encoder<BgpMessage> for BgpCodec {
         msg.write(&mut dst.writer())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEEP_ALIVE: [u8; 19] = [
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0x00, 0x13, 0x04,
+    ];
+
+    #[test]
+    fn test_find_next_message() {
+        let mut wire = vec![0x00, 0x01, 0x02];
+        wire.extend_from_slice(&KEEP_ALIVE);
+        assert_eq!(find_next_message(&wire, 1024), Some(3));
+        assert_eq!(find_next_message(&wire, 2), None);
+        // The message at the start of the buffer is skipped
+        assert_eq!(find_next_message(&KEEP_ALIVE, 1024), None);
+        // Undefined message type
+        let mut undefined = KEEP_ALIVE;
+        undefined[18] = 0;
+        assert_eq!(
+            find_next_message(&[&[0], &undefined[..]].concat(), 1024),
+            None
+        );
+        // Length shorter than the header
+        let mut short = KEEP_ALIVE;
+        short[17] = 0x12;
+        assert_eq!(find_next_message(&[&[0], &short[..]].concat(), 1024), None);
+    }
+
+    #[test]
+    fn test_codec_resync() {
+        // A keep alive with a broken marker followed by a good one
+        let mut broken = KEEP_ALIVE;
+        broken[0] = 0x00;
+        let wire = [&broken[..], &KEEP_ALIVE[..]].concat();
+
+        let mut codec = BgpCodec::new(true);
+        let mut buf = BytesMut::from(wire.as_slice());
+        assert!(codec.decode(&mut buf).is_err());
+        // Without resync, the decoder is stuck on the broken message
+        assert_eq!(buf.len(), wire.len());
+
+        codec.set_resync(Some(1024));
+        assert_eq!(codec.resync(), Some(1024));
+        assert!(codec.decode(&mut buf).is_err());
+        assert_eq!(buf.len(), KEEP_ALIVE.len());
+        assert!(matches!(
+            codec.decode(&mut buf),
+            Ok(Some((BgpMessage::KeepAlive, _)))
+        ));
+
+        // No message to resync on, bytes that can start a header are kept
+        let mut buf = BytesMut::from(&[0u8; 40][..]);
+        assert!(codec.decode(&mut buf).is_err());
+        assert_eq!(buf.len(), BGP_HEADER_LENGTH - 1);
+    }
+}
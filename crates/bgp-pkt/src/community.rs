@@ -17,14 +17,29 @@
 
 #[cfg(feature = "fuzz")]
 use crate::{arbitrary_ipv4, arbitrary_ipv6};
-use crate::{iana::WellKnownCommunity, nlri::MacAddress};
+use crate::{
+    iana::{
+        BgpExtendedCommunityIpv6Type, BgpExtendedCommunityType, EvpnExtendedCommunitySubType,
+        FlowSpecActionSubType, NonTransitiveTwoOctetExtendedCommunitySubType,
+        TransitiveFourOctetExtendedCommunitySubType, TransitiveIpv4ExtendedCommunitySubType,
+        TransitiveIpv6ExtendedCommunitySubType, TransitiveOpaqueExtendedCommunitySubType,
+        TransitiveTwoOctetExtendedCommunitySubType, WellKnownCommunity, AS_TRANS,
+    },
+    nlri::MacAddress,
+};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::{
+    fmt,
+    net::{Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
 
 /// Four octet values to specify a community.
 ///
 /// See [RFC1997](https://datatracker.ietf.org/doc/html/rfc1997)
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Community(u32);
 
@@ -70,7 +85,8 @@ impl Community {
 /// Global Administrator:  A four-octet namespace identifier.
 /// Local Data Part 1:  A four-octet operator-defined value.
 /// Local Data Part 2:  A four-octet operator-defined value.
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct LargeCommunity {
     global_admin: u32,
@@ -124,7 +140,8 @@ pub trait ExtendedCommunityProperties {
 /// ```
 ///
 /// See [RFC4360](https://datatracker.ietf.org/doc/html/rfc4360)
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum ExtendedCommunity {
     /// [RFC4360](https://datatracker.ietf.org/doc/html/rfc4360)
@@ -193,7 +210,8 @@ impl ExtendedCommunityProperties for ExtendedCommunity {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum TransitiveTwoOctetExtendedCommunity {
     /// [RFC4360](https://datatracker.ietf.org/doc/html/rfc4360)
@@ -271,7 +289,8 @@ impl ExtendedCommunityProperties for TransitiveTwoOctetExtendedCommunity {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum NonTransitiveTwoOctetExtendedCommunity {
     LinkBandwidth {
@@ -299,7 +318,213 @@ impl ExtendedCommunityProperties for NonTransitiveTwoOctetExtendedCommunity {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+/// Link bandwidth of the link to an external neighbor, carried in the
+/// non-transitive [`NonTransitiveTwoOctetExtendedCommunity::LinkBandwidth`]
+/// extended community, and used to load balance the traffic across multiple
+/// paths in proportion to their bandwidth.
+///
+/// The community has only two octets for the AS number, four-octet AS numbers
+/// are encoded as [`AS_TRANS`] and resolved to the neighbor AS when extracted
+/// from an update message, see
+/// [`crate::BgpUpdateMessage::link_bandwidths`].
+///
+/// See [draft-ietf-idr-link-bandwidth](https://datatracker.ietf.org/doc/draft-ietf-idr-link-bandwidth/)
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct LinkBandwidth {
+    asn: u32,
+    bandwidth: f32,
+}
+
+impl LinkBandwidth {
+    /// `bandwidth` in bytes per second
+    pub const fn new(asn: u32, bandwidth: f32) -> Self {
+        Self { asn, bandwidth }
+    }
+
+    pub const fn asn(&self) -> u32 {
+        self.asn
+    }
+
+    /// Bandwidth in bytes per second
+    pub const fn bandwidth(&self) -> f32 {
+        self.bandwidth
+    }
+
+    pub fn bits_per_second(&self) -> f64 {
+        f64::from(self.bandwidth) * 8.0
+    }
+
+    /// Returns `None` if the community is not a link bandwidth community
+    pub const fn from_extended_community(community: &ExtendedCommunity) -> Option<Self> {
+        match community {
+            ExtendedCommunity::NonTransitiveTwoOctet(
+                NonTransitiveTwoOctetExtendedCommunity::LinkBandwidth {
+                    global_admin,
+                    local_admin,
+                },
+            ) => Some(Self::new(
+                *global_admin as u32,
+                f32::from_bits(*local_admin),
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn to_extended_community(&self) -> ExtendedCommunity {
+        ExtendedCommunity::NonTransitiveTwoOctet(
+            NonTransitiveTwoOctetExtendedCommunity::LinkBandwidth {
+                global_admin: u16::try_from(self.asn).unwrap_or(AS_TRANS),
+                local_admin: self.bandwidth.to_bits(),
+            },
+        )
+    }
+}
+
+/// Share of the traffic sent over each of the multiple paths to a
+/// destination, in proportion to the total link bandwidth of each path.
+///
+/// Returns `None`, i.e., the traffic is load balanced equally, when any of the
+/// paths has no link bandwidth, or the total bandwidth is not a positive
+/// number.
+pub fn link_bandwidth_shares(bandwidths: &[Option<f32>]) -> Option<Vec<f64>> {
+    let bandwidths = bandwidths
+        .iter()
+        .map(|bandwidth| {
+            bandwidth
+                .map(f64::from)
+                .filter(|bw| bw.is_finite() && *bw >= 0.0)
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let total: f64 = bandwidths.iter().sum();
+    if total <= 0.0 || !total.is_finite() {
+        return None;
+    }
+    Some(
+        bandwidths
+            .iter()
+            .map(|bandwidth| bandwidth / total)
+            .collect(),
+    )
+}
+
+/// Action applied to the traffic matching a Flow Spec NLRI, carried in the
+/// Generic Transitive Experimental Use extended communities, which are
+/// decoded as [`ExtendedCommunity::Unknown`].
+///
+/// See [RFC8955 Section 7](https://datatracker.ietf.org/doc/html/rfc8955#section-7)
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub enum FlowSpecAction {
+    /// Limit the traffic to `rate` bytes per second, a rate of zero discards
+    /// all the traffic
+    TrafficRateBytes { asn: u16, rate: f32 },
+    /// Limit the traffic to `rate` packets per second
+    TrafficRatePackets { asn: u16, rate: f32 },
+    /// `sample` enables the traffic sampling and logging, and `terminal`
+    /// stops the evaluation of the following Flow Spec rules
+    TrafficAction { sample: bool, terminal: bool },
+    /// Redirect the traffic to the VRF importing the route target
+    Redirect { global_admin: u16, local_admin: u32 },
+    /// Rewrite the DSCP of the traffic
+    TrafficMarking { dscp: u8 },
+}
+
+impl FlowSpecAction {
+    /// Type of the Generic Transitive Experimental Use extended communities
+    const EXTENDED_COMMUNITY_TYPE: u8 = BgpExtendedCommunityType::GenericPart1 as u8;
+
+    /// Returns `None` if the community is not a Flow Spec action
+    pub fn from_extended_community(community: &ExtendedCommunity) -> Option<Self> {
+        let community = match community {
+            ExtendedCommunity::Unknown(community)
+                if community.code() == Self::EXTENDED_COMMUNITY_TYPE =>
+            {
+                community
+            }
+            _ => return None,
+        };
+        let value = community.value();
+        let asn = u16::from_be_bytes([value[0], value[1]]);
+        let local = u32::from_be_bytes([value[2], value[3], value[4], value[5]]);
+        match FlowSpecActionSubType::from_repr(community.sub_type())? {
+            FlowSpecActionSubType::TrafficRateBytes => Some(Self::TrafficRateBytes {
+                asn,
+                rate: f32::from_bits(local),
+            }),
+            FlowSpecActionSubType::TrafficRatePackets => Some(Self::TrafficRatePackets {
+                asn,
+                rate: f32::from_bits(local),
+            }),
+            FlowSpecActionSubType::TrafficAction => Some(Self::TrafficAction {
+                sample: value[5] & 0x02 != 0,
+                terminal: value[5] & 0x01 != 0,
+            }),
+            FlowSpecActionSubType::RedirectAsTwoOctet => Some(Self::Redirect {
+                global_admin: asn,
+                local_admin: local,
+            }),
+            FlowSpecActionSubType::TrafficMarking => Some(Self::TrafficMarking {
+                dscp: value[5] & 0x3f,
+            }),
+        }
+    }
+
+    pub fn to_extended_community(&self) -> ExtendedCommunity {
+        let (sub_type, asn, local) = match self {
+            Self::TrafficRateBytes { asn, rate } => (
+                FlowSpecActionSubType::TrafficRateBytes,
+                *asn,
+                rate.to_bits(),
+            ),
+            Self::TrafficRatePackets { asn, rate } => (
+                FlowSpecActionSubType::TrafficRatePackets,
+                *asn,
+                rate.to_bits(),
+            ),
+            Self::TrafficAction { sample, terminal } => (
+                FlowSpecActionSubType::TrafficAction,
+                0,
+                u32::from(*sample) << 1 | u32::from(*terminal),
+            ),
+            Self::Redirect {
+                global_admin,
+                local_admin,
+            } => (
+                FlowSpecActionSubType::RedirectAsTwoOctet,
+                *global_admin,
+                *local_admin,
+            ),
+            Self::TrafficMarking { dscp } => (
+                FlowSpecActionSubType::TrafficMarking,
+                0,
+                u32::from(*dscp & 0x3f),
+            ),
+        };
+        let [a0, a1] = asn.to_be_bytes();
+        let [l0, l1, l2, l3] = local.to_be_bytes();
+        ExtendedCommunity::Unknown(UnknownExtendedCommunity::new(
+            Self::EXTENDED_COMMUNITY_TYPE,
+            sub_type.into(),
+            [a0, a1, l0, l1, l2, l3],
+        ))
+    }
+
+    /// The traffic is discarded, i.e., rate limited to zero
+    pub fn is_discard(&self) -> bool {
+        match self {
+            Self::TrafficRateBytes { rate, .. } | Self::TrafficRatePackets { rate, .. } => {
+                *rate == 0.0
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum TransitiveFourOctetExtendedCommunity {
     /// [RFC5668](https://datatracker.ietf.org/doc/html/rfc5668)
@@ -366,7 +591,8 @@ impl ExtendedCommunityProperties for TransitiveFourOctetExtendedCommunity {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum NonTransitiveFourOctetExtendedCommunity {
     Unassigned {
@@ -382,11 +608,12 @@ impl ExtendedCommunityProperties for NonTransitiveFourOctetExtendedCommunity {
     }
 
     fn transitive(&self) -> bool {
-        true
+        false
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum TransitiveIpv4ExtendedCommunity {
     /// [RFC4360](https://datatracker.ietf.org/doc/html/rfc4360)
@@ -510,7 +737,8 @@ impl ExtendedCommunityProperties for TransitiveIpv4ExtendedCommunity {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum NonTransitiveIpv4ExtendedCommunity {
     Unassigned {
@@ -531,7 +759,8 @@ impl ExtendedCommunityProperties for NonTransitiveIpv4ExtendedCommunity {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum TransitiveOpaqueExtendedCommunity {
     /// The Default Gateway community  It is a transitive community,
@@ -553,11 +782,12 @@ impl ExtendedCommunityProperties for TransitiveOpaqueExtendedCommunity {
     }
 
     fn transitive(&self) -> bool {
-        false
+        true
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum NonTransitiveOpaqueExtendedCommunity {
     Unassigned { sub_type: u8, value: [u8; 6] },
@@ -573,7 +803,8 @@ impl ExtendedCommunityProperties for NonTransitiveOpaqueExtendedCommunity {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct ExperimentalExtendedCommunity {
     code: u8,
@@ -613,7 +844,8 @@ impl ExtendedCommunityProperties for ExperimentalExtendedCommunity {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UnknownExtendedCommunity {
     code: u8,
@@ -671,7 +903,8 @@ impl ExtendedCommunityProperties for UnknownExtendedCommunity {
 /// ```
 ///
 /// See [RFC5701](https://datatracker.ietf.org/doc/html/rfc5701)
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum ExtendedCommunityIpv6 {
     /// [RFC5701](https://datatracker.ietf.org/doc/html/rfc5701)
@@ -701,7 +934,8 @@ impl ExtendedCommunityProperties for ExtendedCommunityIpv6 {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum TransitiveIpv6ExtendedCommunity {
     /// [RFC5701](https://datatracker.ietf.org/doc/html/rfc5701)
@@ -785,7 +1019,8 @@ impl ExtendedCommunityProperties for TransitiveIpv6ExtendedCommunity {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum NonTransitiveIpv6ExtendedCommunity {
     Unassigned {
@@ -806,7 +1041,8 @@ impl ExtendedCommunityProperties for NonTransitiveIpv6ExtendedCommunity {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UnknownExtendedCommunityIpv6 {
     code: u8,
@@ -846,7 +1082,8 @@ impl ExtendedCommunityProperties for UnknownExtendedCommunityIpv6 {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum EvpnExtendedCommunity {
     /// MAC Mobility extended community
@@ -969,9 +1206,1219 @@ impl ExtendedCommunityProperties for EvpnExtendedCommunity {
     }
 }
 
+/// Type and sub-type octets of extended communities that don't have a name in
+/// the textual form, e.g., `0x0042` for an unassigned sub-type of the
+/// Transitive Two-Octet AS-Specific type
+struct RawType(u8, u8);
+
+impl fmt::Display for RawType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:02x}{:02x}", self.0, self.1)
+    }
+}
+
+/// Opaque value of extended communities as a hex string, e.g., `0x0a0b0c0d0e0f`
+struct HexValue<'a>(&'a [u8]);
+
+impl fmt::Display for HexValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x")?;
+        for byte in self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+struct MacValue<'a>(&'a [u8; 6]);
+
+impl fmt::Display for MacValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{a:02x}:{b:02x}:{c:02x}:{d:02x}:{e:02x}:{g:02x}")
+    }
+}
+
+/// Four-octet AS numbers that fit in two octets are written with an `L`
+/// suffix, to distinguish them from the Two-Octet AS-Specific communities
+struct FourOctetAs(u32);
+
+impl fmt::Display for FourOctetAs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 <= u16::MAX as u32 {
+            write!(f, "{}L", self.0)
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+const BANDWIDTH_UNITS: [(&str, f64); 4] = [("T", 1e12), ("G", 1e9), ("M", 1e6), ("k", 1e3)];
+
+/// The link bandwidth is carried as an IEEE floating point number in bytes
+/// per second, and written in bits per second with an optional `k`, `M`, `G`,
+/// or `T` suffix, e.g., `10M`. Values that are not a positive number are
+/// written as the raw `0x`-prefixed hex value.
+struct LinkBandwidthValue(u32);
+
+impl fmt::Display for LinkBandwidthValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bps = f64::from(f32::from_bits(self.0)) * 8.0;
+        if !bps.is_finite() || bps.is_sign_negative() {
+            return write!(f, "{:#010x}", self.0);
+        }
+        for (suffix, unit) in BANDWIDTH_UNITS {
+            if bps >= unit && (bps / unit).fract() == 0.0 {
+                return write!(f, "{}{suffix}", bps / unit);
+            }
+        }
+        write!(f, "{bps}")
+    }
+}
+
+fn parse_link_bandwidth(value: &str) -> Result<u32, ExtendedCommunityFromStrError> {
+    let invalid = || ExtendedCommunityFromStrError::InvalidLocalAdmin(value.to_string());
+    if let Some(hex) = value.strip_prefix("0x") {
+        return u32::from_str_radix(hex, 16).map_err(|_| invalid());
+    }
+    let (number, unit) = BANDWIDTH_UNITS
+        .iter()
+        .chain(&[("K", 1e3)])
+        .find_map(|(suffix, unit)| value.strip_suffix(suffix).map(|number| (number, *unit)))
+        .unwrap_or((value, 1.0));
+    let bps: f64 = number.parse().map_err(|_| invalid())?;
+    if !bps.is_finite() || bps.is_sign_negative() {
+        return Err(invalid());
+    }
+    Ok(((bps * unit / 8.0) as f32).to_bits())
+}
+
+/// Error parsing the textual form of an [`ExtendedCommunity`] or an
+/// [`ExtendedCommunityIpv6`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtendedCommunityFromStrError {
+    /// Not in the `<name>:<value>` form
+    InvalidFormat(String),
+
+    /// The name is not defined for any of the sub-types
+    UnknownName(String),
+
+    /// The global administrator is not valid for the sub-type, e.g., an AS
+    /// number for a sub-type that is only defined for IPv4 addresses
+    InvalidGlobalAdmin(String),
+
+    InvalidLocalAdmin(String),
+
+    InvalidValue(String),
+
+    /// The numerical `0xTTSS` form is used for a sub-type that has a name
+    AssignedSubType {
+        code: u8,
+        sub_type: u8,
+    },
+}
+
+impl fmt::Display for ExtendedCommunityFromStrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidFormat(value) => write!(f, "invalid extended community `{value}`"),
+            Self::UnknownName(name) => write!(f, "unknown extended community name `{name}`"),
+            Self::InvalidGlobalAdmin(value) => write!(f, "invalid global administrator `{value}`"),
+            Self::InvalidLocalAdmin(value) => write!(f, "invalid local administrator `{value}`"),
+            Self::InvalidValue(value) => write!(f, "invalid extended community value `{value}`"),
+            Self::AssignedSubType { code, sub_type } => write!(
+                f,
+                "sub-type {} has a name, the numerical form is only for unassigned sub-types",
+                RawType(*code, *sub_type)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExtendedCommunityFromStrError {}
+
+fn parse_number<T: FromStr>(
+    value: &str,
+    err: fn(String) -> ExtendedCommunityFromStrError,
+) -> Result<T, ExtendedCommunityFromStrError> {
+    value.parse().map_err(|_| err(value.to_string()))
+}
+
+/// Parse `<global_admin>:<local_admin>`, the global administrator can be an
+/// IPv6 address, hence it's split at the last `:`
+fn parse_admin<G: FromStr, L: FromStr>(
+    value: &str,
+) -> Result<(G, L), ExtendedCommunityFromStrError> {
+    let (global, local) = value
+        .rsplit_once(':')
+        .ok_or_else(|| ExtendedCommunityFromStrError::InvalidFormat(value.to_string()))?;
+    Ok((
+        parse_number(global, ExtendedCommunityFromStrError::InvalidGlobalAdmin)?,
+        parse_number(local, ExtendedCommunityFromStrError::InvalidLocalAdmin)?,
+    ))
+}
+
+fn parse_hex_value<const N: usize>(value: &str) -> Result<[u8; N], ExtendedCommunityFromStrError> {
+    let invalid = || ExtendedCommunityFromStrError::InvalidValue(value.to_string());
+    let hex = value.strip_prefix("0x").ok_or_else(invalid)?;
+    if hex.len() != N * 2 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let mut ret = [0u8; N];
+    for (i, byte) in ret.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(ret)
+}
+
+fn parse_mac(value: &str) -> Result<[u8; 6], ExtendedCommunityFromStrError> {
+    let invalid = || ExtendedCommunityFromStrError::InvalidValue(value.to_string());
+    let mut ret = [0u8; 6];
+    let mut octets = value.split(':');
+    for byte in &mut ret {
+        let octet = octets.next().ok_or_else(invalid)?;
+        if octet.len() != 2 || !octet.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        *byte = u8::from_str_radix(octet, 16).map_err(|_| invalid())?;
+    }
+    if octets.next().is_some() {
+        return Err(invalid());
+    }
+    Ok(ret)
+}
+
+/// Parse the `0xTTSS` type and sub-type of the numerical form
+fn parse_raw_type(name: &str) -> Option<(u8, u8)> {
+    let hex = name.strip_prefix("0x")?;
+    if hex.len() != 4 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let [code, sub_type] = u16::from_str_radix(hex, 16).ok()?.to_be_bytes();
+    Some((code, sub_type))
+}
+
+/// Global administrator of the AS and IPv4 Address Specific extended
+/// communities, which one determines the type of the extended community.
+enum GlobalAdmin {
+    TwoOctet(u16),
+    FourOctet(u32),
+    Ipv4(Ipv4Addr),
+}
+
+impl FromStr for GlobalAdmin {
+    type Err = ExtendedCommunityFromStrError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Ok(addr) = value.parse() {
+            return Ok(Self::Ipv4(addr));
+        }
+        if let Some(asn) = value.strip_suffix('L') {
+            return parse_number(asn, ExtendedCommunityFromStrError::InvalidGlobalAdmin)
+                .map(Self::FourOctet);
+        }
+        let asn: u32 = parse_number(value, ExtendedCommunityFromStrError::InvalidGlobalAdmin)?;
+        Ok(u16::try_from(asn).map_or(Self::FourOctet(asn), Self::TwoOctet))
+    }
+}
+
+/// Names of the AS and IPv4 Address Specific sub-types
+const fn is_admin_name(name: &str) -> bool {
+    matches!(
+        name.as_bytes(),
+        b"rt"
+            | b"soo"
+            | b"ospf-domain-id"
+            | b"data-collection"
+            | b"source-as"
+            | b"l2vpn-id"
+            | b"cisco-vpn-id"
+            | b"rt-record"
+            | b"rt-derived"
+            | b"vni"
+            | b"ifit"
+            | b"ospf-router-id"
+            | b"node-target"
+            | b"vrf-import"
+            | b"redirect"
+            | b"p2mp-segmented-nh"
+            | b"vrf-recursive-nh"
+            | b"mvpn-rp"
+    )
+}
+
+fn invalid_admin_name(name: &str, global: &str) -> ExtendedCommunityFromStrError {
+    if is_admin_name(name) {
+        ExtendedCommunityFromStrError::InvalidGlobalAdmin(global.to_string())
+    } else {
+        ExtendedCommunityFromStrError::UnknownName(name.to_string())
+    }
+}
+
+impl fmt::Display for ExtendedCommunity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TransitiveTwoOctet(value) => value.fmt(f),
+            Self::NonTransitiveTwoOctet(value) => value.fmt(f),
+            Self::TransitiveIpv4(value) => value.fmt(f),
+            Self::NonTransitiveIpv4(value) => value.fmt(f),
+            Self::TransitiveFourOctet(value) => value.fmt(f),
+            Self::NonTransitiveFourOctet(value) => value.fmt(f),
+            Self::TransitiveOpaque(value) => value.fmt(f),
+            Self::NonTransitiveOpaque(value) => value.fmt(f),
+            Self::Evpn(value) => value.fmt(f),
+            Self::Experimental(value) => value.fmt(f),
+            Self::Unknown(value) => value.fmt(f),
+        }
+    }
+}
+
+/// Parse the textual form of an extended community, as written by its
+/// [`fmt::Display`] implementation.
+///
+/// The AS and IPv4 Address Specific sub-types are written as
+/// `<name>:<global_admin>:<local_admin>`, the type is derived from the global
+/// administrator: an IPv4 address, a four-octet AS number (AS numbers that
+/// fit in two octets are suffixed with `L`, e.g., `rt:65000L:100`), or a
+/// two-octet AS number.
+///
+/// | Name                | Sub-type                                  |
+/// |---------------------|-------------------------------------------|
+/// | `rt`                | Route Target                              |
+/// | `soo`               | Route Origin                              |
+/// | `ospf-domain-id`    | OSPF Domain Identifier                    |
+/// | `data-collection`   | BGP Data Collection                       |
+/// | `source-as`         | Source AS                                 |
+/// | `l2vpn-id`          | L2VPN Identifier                          |
+/// | `cisco-vpn-id`      | Cisco VPN-Distinguisher                   |
+/// | `rt-record`         | Route-Target Record                       |
+/// | `rt-derived`        | RT-derived-EC                             |
+/// | `vni`               | Virtual-Network Identifier                |
+/// | `nt-vni`            | Non-Transitive Virtual-Network Identifier |
+/// | `ifit`              | IPv4-Address-Specific IFIT Tail           |
+/// | `ospf-router-id`    | OSPF Route ID                             |
+/// | `node-target`       | Node Target                               |
+/// | `vrf-import`        | VRF Route Import                          |
+/// | `redirect`          | Flow-spec Redirect to IPv4                |
+/// | `p2mp-segmented-nh` | Inter-Area P2MP Segmented Next-Hop        |
+/// | `vrf-recursive-nh`  | VRF-Recursive-Next-Hop                    |
+/// | `mvpn-rp`           | MVPN SA RP-address                        |
+///
+/// The other named forms are:
+/// - `lb:<asn>:<bandwidth>` Link Bandwidth in bits per second, e.g.,
+///   `lb:65000:10M`.
+/// - `default-gateway` EVPN Default Gateway.
+/// - `mac-mobility:<flags>:<sequence>` EVPN MAC Mobility.
+/// - `esi-label:<flags>:<label>` EVPN ESI Label.
+/// - `es-import:<mac>` EVPN ES-Import Route Target.
+/// - `router-mac:<mac>` EVPN Router's MAC.
+/// - `l2-attr:<control_flags>:<mtu>` EVPN Layer 2 Attributes.
+///
+/// Sub-types without a name are written in the numerical form
+/// `0x<type><sub-type>:<value>`, the value is the global and local
+/// administrators of the AS and IPv4 Address Specific types, e.g.,
+/// `0x0042:65000:100`, otherwise it's the `0x`-prefixed hex of the six
+/// value octets, e.g., `0x8301:0x000000000064`.
+impl FromStr for ExtendedCommunity {
+    type Err = ExtendedCommunityFromStrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "default-gateway" {
+            return Ok(Self::TransitiveOpaque(
+                TransitiveOpaqueExtendedCommunity::DefaultGateway,
+            ));
+        }
+        let (name, value) = s
+            .split_once(':')
+            .ok_or_else(|| ExtendedCommunityFromStrError::InvalidFormat(s.to_string()))?;
+        if let Some((code, sub_type)) = parse_raw_type(name) {
+            return parse_unassigned(code, sub_type, value);
+        }
+        let evpn = match name {
+            "lb" => {
+                let (global, local) = value
+                    .split_once(':')
+                    .ok_or_else(|| ExtendedCommunityFromStrError::InvalidFormat(s.to_string()))?;
+                return Ok(Self::NonTransitiveTwoOctet(
+                    NonTransitiveTwoOctetExtendedCommunity::LinkBandwidth {
+                        global_admin: parse_number(
+                            global,
+                            ExtendedCommunityFromStrError::InvalidGlobalAdmin,
+                        )?,
+                        local_admin: parse_link_bandwidth(local)?,
+                    },
+                ));
+            }
+            "nt-vni" => {
+                let (global_admin, local_admin) = parse_admin(value)?;
+                return Ok(Self::NonTransitiveTwoOctet(
+                    NonTransitiveTwoOctetExtendedCommunity::VirtualNetworkIdentifier {
+                        global_admin,
+                        local_admin,
+                    },
+                ));
+            }
+            "mac-mobility" => {
+                let (flags, seq_no) = parse_admin(value)?;
+                EvpnExtendedCommunity::MacMobility { flags, seq_no }
+            }
+            "esi-label" => {
+                let (flags, label): (u8, u32) = parse_admin(value)?;
+                if label > 0xffffff {
+                    return Err(ExtendedCommunityFromStrError::InvalidLocalAdmin(
+                        label.to_string(),
+                    ));
+                }
+                let [_, esi_label @ ..] = label.to_be_bytes();
+                EvpnExtendedCommunity::EsiLabel { flags, esi_label }
+            }
+            "es-import" => EvpnExtendedCommunity::EsImportRouteTarget {
+                route_target: parse_mac(value)?,
+            },
+            "router-mac" => EvpnExtendedCommunity::EvpnRoutersMac {
+                mac: MacAddress(parse_mac(value)?),
+            },
+            "l2-attr" => {
+                let (control_flags, l2_mtu) = parse_admin(value)?;
+                EvpnExtendedCommunity::EvpnL2Attribute {
+                    control_flags,
+                    l2_mtu,
+                }
+            }
+            _ => return parse_admin_specific(name, value),
+        };
+        Ok(Self::Evpn(evpn))
+    }
+}
+
+fn parse_admin_specific(
+    name: &str,
+    value: &str,
+) -> Result<ExtendedCommunity, ExtendedCommunityFromStrError> {
+    let (global, local) = value
+        .rsplit_once(':')
+        .ok_or_else(|| ExtendedCommunityFromStrError::InvalidFormat(value.to_string()))?;
+    if !is_admin_name(name) {
+        return Err(ExtendedCommunityFromStrError::UnknownName(name.to_string()));
+    }
+    let ret = match global.parse()? {
+        GlobalAdmin::TwoOctet(global_admin) => {
+            let local_admin =
+                parse_number(local, ExtendedCommunityFromStrError::InvalidLocalAdmin)?;
+            ExtendedCommunity::TransitiveTwoOctet(match name {
+                "rt" => TransitiveTwoOctetExtendedCommunity::RouteTarget {
+                    global_admin,
+                    local_admin,
+                },
+                "soo" => TransitiveTwoOctetExtendedCommunity::RouteOrigin {
+                    global_admin,
+                    local_admin,
+                },
+                "ospf-domain-id" => TransitiveTwoOctetExtendedCommunity::OspfDomainIdentifier {
+                    global_admin,
+                    local_admin,
+                },
+                "data-collection" => TransitiveTwoOctetExtendedCommunity::BgpDataCollection {
+                    global_admin,
+                    local_admin,
+                },
+                "source-as" => TransitiveTwoOctetExtendedCommunity::SourceAs {
+                    global_admin,
+                    local_admin,
+                },
+                "l2vpn-id" => TransitiveTwoOctetExtendedCommunity::L2VpnIdentifier {
+                    global_admin,
+                    local_admin,
+                },
+                "cisco-vpn-id" => TransitiveTwoOctetExtendedCommunity::CiscoVpnDistinguisher {
+                    global_admin,
+                    local_admin,
+                },
+                "rt-record" => TransitiveTwoOctetExtendedCommunity::RouteTargetRecord {
+                    global_admin,
+                    local_admin,
+                },
+                "rt-derived" => TransitiveTwoOctetExtendedCommunity::RtDerivedEc {
+                    global_admin,
+                    local_admin,
+                },
+                "vni" => TransitiveTwoOctetExtendedCommunity::VirtualNetworkIdentifier {
+                    global_admin,
+                    local_admin,
+                },
+                _ => return Err(invalid_admin_name(name, global)),
+            })
+        }
+        GlobalAdmin::FourOctet(global_admin) => {
+            let local_admin =
+                parse_number(local, ExtendedCommunityFromStrError::InvalidLocalAdmin)?;
+            ExtendedCommunity::TransitiveFourOctet(match name {
+                "rt" => TransitiveFourOctetExtendedCommunity::RouteTarget {
+                    global_admin,
+                    local_admin,
+                },
+                "soo" => TransitiveFourOctetExtendedCommunity::RouteOrigin {
+                    global_admin,
+                    local_admin,
+                },
+                "ospf-domain-id" => TransitiveFourOctetExtendedCommunity::OspfDomainIdentifier {
+                    global_admin,
+                    local_admin,
+                },
+                "data-collection" => TransitiveFourOctetExtendedCommunity::BgpDataCollection {
+                    global_admin,
+                    local_admin,
+                },
+                "source-as" => TransitiveFourOctetExtendedCommunity::SourceAs {
+                    global_admin,
+                    local_admin,
+                },
+                "cisco-vpn-id" => TransitiveFourOctetExtendedCommunity::CiscoVpnDistinguisher {
+                    global_admin,
+                    local_admin,
+                },
+                "rt-record" => TransitiveFourOctetExtendedCommunity::RouteTargetRecord {
+                    global_admin,
+                    local_admin,
+                },
+                "rt-derived" => TransitiveFourOctetExtendedCommunity::RtDerivedEc {
+                    global_admin,
+                    local_admin,
+                },
+                _ => return Err(invalid_admin_name(name, global)),
+            })
+        }
+        GlobalAdmin::Ipv4(global_admin) => {
+            let local_admin =
+                parse_number(local, ExtendedCommunityFromStrError::InvalidLocalAdmin)?;
+            ExtendedCommunity::TransitiveIpv4(match name {
+                "rt" => TransitiveIpv4ExtendedCommunity::RouteTarget {
+                    global_admin,
+                    local_admin,
+                },
+                "soo" => TransitiveIpv4ExtendedCommunity::RouteOrigin {
+                    global_admin,
+                    local_admin,
+                },
+                "ifit" => TransitiveIpv4ExtendedCommunity::Ipv4Ifit {
+                    global_admin,
+                    local_admin,
+                },
+                "ospf-domain-id" => TransitiveIpv4ExtendedCommunity::OspfDomainIdentifier {
+                    global_admin,
+                    local_admin,
+                },
+                "ospf-router-id" => TransitiveIpv4ExtendedCommunity::OspfRouteID {
+                    global_admin,
+                    local_admin,
+                },
+                "node-target" => TransitiveIpv4ExtendedCommunity::NodeTarget {
+                    global_admin,
+                    local_admin,
+                },
+                "l2vpn-id" => TransitiveIpv4ExtendedCommunity::L2VpnIdentifier {
+                    global_admin,
+                    local_admin,
+                },
+                "vrf-import" => TransitiveIpv4ExtendedCommunity::VrfRouteImport {
+                    global_admin,
+                    local_admin,
+                },
+                "redirect" => TransitiveIpv4ExtendedCommunity::FlowSpecRedirectToIpv4 {
+                    global_admin,
+                    local_admin,
+                },
+                "cisco-vpn-id" => TransitiveIpv4ExtendedCommunity::CiscoVpnDistinguisher {
+                    global_admin,
+                    local_admin,
+                },
+                "p2mp-segmented-nh" => {
+                    TransitiveIpv4ExtendedCommunity::InterAreaP2MpSegmentedNextHop {
+                        global_admin,
+                        local_admin,
+                    }
+                }
+                "rt-record" => TransitiveIpv4ExtendedCommunity::RouteTargetRecord {
+                    global_admin,
+                    local_admin,
+                },
+                "vrf-recursive-nh" => TransitiveIpv4ExtendedCommunity::VrfRecursiveNextHop {
+                    global_admin,
+                    local_admin,
+                },
+                "rt-derived" => TransitiveIpv4ExtendedCommunity::RtDerivedEc {
+                    global_admin,
+                    local_admin,
+                },
+                "mvpn-rp" => TransitiveIpv4ExtendedCommunity::MulticastVpnRpAddress {
+                    global_admin,
+                    local_admin,
+                },
+                _ => return Err(invalid_admin_name(name, global)),
+            })
+        }
+    };
+    Ok(ret)
+}
+
+/// Parse the numerical form of the sub-types that don't have a name
+fn parse_unassigned(
+    code: u8,
+    sub_type: u8,
+    value: &str,
+) -> Result<ExtendedCommunity, ExtendedCommunityFromStrError> {
+    let assigned = ExtendedCommunityFromStrError::AssignedSubType { code, sub_type };
+    let ret = match BgpExtendedCommunityType::try_from(code) {
+        Ok(BgpExtendedCommunityType::TransitiveTwoOctet) => {
+            if TransitiveTwoOctetExtendedCommunitySubType::try_from(sub_type).is_ok() {
+                return Err(assigned);
+            }
+            let (global_admin, local_admin) = parse_admin(value)?;
+            ExtendedCommunity::TransitiveTwoOctet(TransitiveTwoOctetExtendedCommunity::Unassigned {
+                sub_type,
+                global_admin,
+                local_admin,
+            })
+        }
+        Ok(BgpExtendedCommunityType::NonTransitiveTwoOctet) => {
+            if NonTransitiveTwoOctetExtendedCommunitySubType::try_from(sub_type).is_ok() {
+                return Err(assigned);
+            }
+            let (global_admin, local_admin) = parse_admin(value)?;
+            ExtendedCommunity::NonTransitiveTwoOctet(
+                NonTransitiveTwoOctetExtendedCommunity::Unassigned {
+                    sub_type,
+                    global_admin,
+                    local_admin,
+                },
+            )
+        }
+        Ok(BgpExtendedCommunityType::TransitiveIpv4) => {
+            if TransitiveIpv4ExtendedCommunitySubType::try_from(sub_type).is_ok() {
+                return Err(assigned);
+            }
+            let (global_admin, local_admin) = parse_admin(value)?;
+            ExtendedCommunity::TransitiveIpv4(TransitiveIpv4ExtendedCommunity::Unassigned {
+                sub_type,
+                global_admin,
+                local_admin,
+            })
+        }
+        Ok(BgpExtendedCommunityType::NonTransitiveIpv4) => {
+            let (global_admin, local_admin) = parse_admin(value)?;
+            ExtendedCommunity::NonTransitiveIpv4(NonTransitiveIpv4ExtendedCommunity::Unassigned {
+                sub_type,
+                global_admin,
+                local_admin,
+            })
+        }
+        Ok(BgpExtendedCommunityType::TransitiveFourOctet) => {
+            if TransitiveFourOctetExtendedCommunitySubType::try_from(sub_type).is_ok() {
+                return Err(assigned);
+            }
+            let (global_admin, local_admin) = parse_admin(value)?;
+            ExtendedCommunity::TransitiveFourOctet(
+                TransitiveFourOctetExtendedCommunity::Unassigned {
+                    sub_type,
+                    global_admin,
+                    local_admin,
+                },
+            )
+        }
+        Ok(BgpExtendedCommunityType::NonTransitiveFourOctet) => {
+            let (global_admin, local_admin) = parse_admin(value)?;
+            ExtendedCommunity::NonTransitiveFourOctet(
+                NonTransitiveFourOctetExtendedCommunity::Unassigned {
+                    sub_type,
+                    global_admin,
+                    local_admin,
+                },
+            )
+        }
+        Ok(BgpExtendedCommunityType::TransitiveOpaque) => {
+            if TransitiveOpaqueExtendedCommunitySubType::try_from(sub_type).is_ok() {
+                return Err(assigned);
+            }
+            ExtendedCommunity::TransitiveOpaque(TransitiveOpaqueExtendedCommunity::Unassigned {
+                sub_type,
+                value: parse_hex_value(value)?,
+            })
+        }
+        Ok(BgpExtendedCommunityType::NonTransitiveOpaque) => {
+            ExtendedCommunity::NonTransitiveOpaque(
+                NonTransitiveOpaqueExtendedCommunity::Unassigned {
+                    sub_type,
+                    value: parse_hex_value(value)?,
+                },
+            )
+        }
+        Ok(BgpExtendedCommunityType::Evpn) => {
+            // Only the sub-types up to the Layer 2 Attributes are decoded
+            if sub_type <= EvpnExtendedCommunitySubType::EvpnL2Attribute as u8 {
+                return Err(assigned);
+            }
+            ExtendedCommunity::Evpn(EvpnExtendedCommunity::Unassigned {
+                sub_type,
+                value: parse_hex_value(value)?,
+            })
+        }
+        _ if matches!(code, 0x83..=0x8f | 0xc0..=0xcf) => ExtendedCommunity::Experimental(
+            ExperimentalExtendedCommunity::new(code, sub_type, parse_hex_value(value)?),
+        ),
+        _ => ExtendedCommunity::Unknown(UnknownExtendedCommunity::new(
+            code,
+            sub_type,
+            parse_hex_value(value)?,
+        )),
+    };
+    Ok(ret)
+}
+
+impl fmt::Display for TransitiveTwoOctetExtendedCommunity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, global_admin, local_admin) = match self {
+            Self::RouteTarget {
+                global_admin,
+                local_admin,
+            } => ("rt", global_admin, local_admin),
+            Self::RouteOrigin {
+                global_admin,
+                local_admin,
+            } => ("soo", global_admin, local_admin),
+            Self::OspfDomainIdentifier {
+                global_admin,
+                local_admin,
+            } => ("ospf-domain-id", global_admin, local_admin),
+            Self::BgpDataCollection {
+                global_admin,
+                local_admin,
+            } => ("data-collection", global_admin, local_admin),
+            Self::SourceAs {
+                global_admin,
+                local_admin,
+            } => ("source-as", global_admin, local_admin),
+            Self::L2VpnIdentifier {
+                global_admin,
+                local_admin,
+            } => ("l2vpn-id", global_admin, local_admin),
+            Self::CiscoVpnDistinguisher {
+                global_admin,
+                local_admin,
+            } => ("cisco-vpn-id", global_admin, local_admin),
+            Self::RouteTargetRecord {
+                global_admin,
+                local_admin,
+            } => ("rt-record", global_admin, local_admin),
+            Self::RtDerivedEc {
+                global_admin,
+                local_admin,
+            } => ("rt-derived", global_admin, local_admin),
+            Self::VirtualNetworkIdentifier {
+                global_admin,
+                local_admin,
+            } => ("vni", global_admin, local_admin),
+            Self::Unassigned {
+                sub_type,
+                global_admin,
+                local_admin,
+            } => {
+                let code = BgpExtendedCommunityType::TransitiveTwoOctet as u8;
+                return write!(
+                    f,
+                    "{}:{global_admin}:{local_admin}",
+                    RawType(code, *sub_type)
+                );
+            }
+        };
+        write!(f, "{name}:{global_admin}:{local_admin}")
+    }
+}
+
+impl fmt::Display for NonTransitiveTwoOctetExtendedCommunity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LinkBandwidth {
+                global_admin,
+                local_admin,
+            } => write!(f, "lb:{global_admin}:{}", LinkBandwidthValue(*local_admin)),
+            Self::VirtualNetworkIdentifier {
+                global_admin,
+                local_admin,
+            } => write!(f, "nt-vni:{global_admin}:{local_admin}"),
+            Self::Unassigned {
+                sub_type,
+                global_admin,
+                local_admin,
+            } => {
+                let code = BgpExtendedCommunityType::NonTransitiveTwoOctet as u8;
+                write!(
+                    f,
+                    "{}:{global_admin}:{local_admin}",
+                    RawType(code, *sub_type)
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for TransitiveFourOctetExtendedCommunity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, global_admin, local_admin) = match self {
+            Self::RouteTarget {
+                global_admin,
+                local_admin,
+            } => ("rt", global_admin, local_admin),
+            Self::RouteOrigin {
+                global_admin,
+                local_admin,
+            } => ("soo", global_admin, local_admin),
+            Self::OspfDomainIdentifier {
+                global_admin,
+                local_admin,
+            } => ("ospf-domain-id", global_admin, local_admin),
+            Self::BgpDataCollection {
+                global_admin,
+                local_admin,
+            } => ("data-collection", global_admin, local_admin),
+            Self::SourceAs {
+                global_admin,
+                local_admin,
+            } => ("source-as", global_admin, local_admin),
+            Self::CiscoVpnDistinguisher {
+                global_admin,
+                local_admin,
+            } => ("cisco-vpn-id", global_admin, local_admin),
+            Self::RouteTargetRecord {
+                global_admin,
+                local_admin,
+            } => ("rt-record", global_admin, local_admin),
+            Self::RtDerivedEc {
+                global_admin,
+                local_admin,
+            } => ("rt-derived", global_admin, local_admin),
+            Self::Unassigned {
+                sub_type,
+                global_admin,
+                local_admin,
+            } => {
+                let code = BgpExtendedCommunityType::TransitiveFourOctet as u8;
+                return write!(
+                    f,
+                    "{}:{global_admin}:{local_admin}",
+                    RawType(code, *sub_type)
+                );
+            }
+        };
+        write!(f, "{name}:{}:{local_admin}", FourOctetAs(*global_admin))
+    }
+}
+
+impl fmt::Display for NonTransitiveFourOctetExtendedCommunity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unassigned {
+                sub_type,
+                global_admin,
+                local_admin,
+            } => {
+                let code = BgpExtendedCommunityType::NonTransitiveFourOctet as u8;
+                write!(
+                    f,
+                    "{}:{global_admin}:{local_admin}",
+                    RawType(code, *sub_type)
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for TransitiveIpv4ExtendedCommunity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, global_admin, local_admin) = match self {
+            Self::RouteTarget {
+                global_admin,
+                local_admin,
+            } => ("rt", global_admin, local_admin),
+            Self::RouteOrigin {
+                global_admin,
+                local_admin,
+            } => ("soo", global_admin, local_admin),
+            Self::Ipv4Ifit {
+                global_admin,
+                local_admin,
+            } => ("ifit", global_admin, local_admin),
+            Self::OspfDomainIdentifier {
+                global_admin,
+                local_admin,
+            } => ("ospf-domain-id", global_admin, local_admin),
+            Self::OspfRouteID {
+                global_admin,
+                local_admin,
+            } => ("ospf-router-id", global_admin, local_admin),
+            Self::NodeTarget {
+                global_admin,
+                local_admin,
+            } => ("node-target", global_admin, local_admin),
+            Self::L2VpnIdentifier {
+                global_admin,
+                local_admin,
+            } => ("l2vpn-id", global_admin, local_admin),
+            Self::VrfRouteImport {
+                global_admin,
+                local_admin,
+            } => ("vrf-import", global_admin, local_admin),
+            Self::FlowSpecRedirectToIpv4 {
+                global_admin,
+                local_admin,
+            } => ("redirect", global_admin, local_admin),
+            Self::CiscoVpnDistinguisher {
+                global_admin,
+                local_admin,
+            } => ("cisco-vpn-id", global_admin, local_admin),
+            Self::InterAreaP2MpSegmentedNextHop {
+                global_admin,
+                local_admin,
+            } => ("p2mp-segmented-nh", global_admin, local_admin),
+            Self::RouteTargetRecord {
+                global_admin,
+                local_admin,
+            } => ("rt-record", global_admin, local_admin),
+            Self::VrfRecursiveNextHop {
+                global_admin,
+                local_admin,
+            } => ("vrf-recursive-nh", global_admin, local_admin),
+            Self::RtDerivedEc {
+                global_admin,
+                local_admin,
+            } => ("rt-derived", global_admin, local_admin),
+            Self::MulticastVpnRpAddress {
+                global_admin,
+                local_admin,
+            } => ("mvpn-rp", global_admin, local_admin),
+            Self::Unassigned {
+                sub_type,
+                global_admin,
+                local_admin,
+            } => {
+                let code = BgpExtendedCommunityType::TransitiveIpv4 as u8;
+                return write!(
+                    f,
+                    "{}:{global_admin}:{local_admin}",
+                    RawType(code, *sub_type)
+                );
+            }
+        };
+        write!(f, "{name}:{global_admin}:{local_admin}")
+    }
+}
+
+impl fmt::Display for NonTransitiveIpv4ExtendedCommunity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unassigned {
+                sub_type,
+                global_admin,
+                local_admin,
+            } => {
+                let code = BgpExtendedCommunityType::NonTransitiveIpv4 as u8;
+                write!(
+                    f,
+                    "{}:{global_admin}:{local_admin}",
+                    RawType(code, *sub_type)
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for TransitiveOpaqueExtendedCommunity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DefaultGateway => write!(f, "default-gateway"),
+            Self::Unassigned { sub_type, value } => {
+                let code = BgpExtendedCommunityType::TransitiveOpaque as u8;
+                write!(f, "{}:{}", RawType(code, *sub_type), HexValue(value))
+            }
+        }
+    }
+}
+
+impl fmt::Display for NonTransitiveOpaqueExtendedCommunity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unassigned { sub_type, value } => {
+                let code = BgpExtendedCommunityType::NonTransitiveOpaque as u8;
+                write!(f, "{}:{}", RawType(code, *sub_type), HexValue(value))
+            }
+        }
+    }
+}
+
+impl fmt::Display for EvpnExtendedCommunity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MacMobility { flags, seq_no } => write!(f, "mac-mobility:{flags}:{seq_no}"),
+            Self::EsiLabel { flags, esi_label } => {
+                let [a, b, c] = *esi_label;
+                let label = u32::from_be_bytes([0, a, b, c]);
+                write!(f, "esi-label:{flags}:{label}")
+            }
+            Self::EsImportRouteTarget { route_target } => {
+                write!(f, "es-import:{}", MacValue(route_target))
+            }
+            Self::EvpnRoutersMac { mac } => write!(f, "router-mac:{}", MacValue(&mac.0)),
+            Self::EvpnL2Attribute {
+                control_flags,
+                l2_mtu,
+            } => write!(f, "l2-attr:{control_flags}:{l2_mtu}"),
+            Self::Unassigned { sub_type, value } => {
+                let code = BgpExtendedCommunityType::Evpn as u8;
+                write!(f, "{}:{}", RawType(code, *sub_type), HexValue(value))
+            }
+        }
+    }
+}
+
+impl fmt::Display for ExperimentalExtendedCommunity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}",
+            RawType(self.code, self.sub_type),
+            HexValue(&self.value)
+        )
+    }
+}
+
+impl fmt::Display for UnknownExtendedCommunity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}",
+            RawType(self.code, self.sub_type),
+            HexValue(&self.value)
+        )
+    }
+}
+
+impl fmt::Display for ExtendedCommunityIpv6 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TransitiveIpv6(value) => value.fmt(f),
+            Self::NonTransitiveIpv6(value) => value.fmt(f),
+            Self::Unknown(value) => value.fmt(f),
+        }
+    }
+}
+
+/// Parse the textual form of an IPv6 Address Specific extended community,
+/// as written by its [`fmt::Display`] implementation. The names are the same
+/// as the IPv4 Address Specific sub-types of [`ExtendedCommunity`], in
+/// addition to `rt-redirect` for the Flow-spec RT-Redirect to IPv6, e.g.,
+/// `rt:2001:db8::1:100`.
+impl FromStr for ExtendedCommunityIpv6 {
+    type Err = ExtendedCommunityFromStrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = s
+            .split_once(':')
+            .ok_or_else(|| ExtendedCommunityFromStrError::InvalidFormat(s.to_string()))?;
+        if let Some((code, sub_type)) = parse_raw_type(name) {
+            let assigned = ExtendedCommunityFromStrError::AssignedSubType { code, sub_type };
+            let ret = match BgpExtendedCommunityIpv6Type::try_from(code) {
+                Ok(BgpExtendedCommunityIpv6Type::TransitiveIpv6) => {
+                    if TransitiveIpv6ExtendedCommunitySubType::try_from(sub_type).is_ok() {
+                        return Err(assigned);
+                    }
+                    let (global_admin, local_admin) = parse_admin(value)?;
+                    Self::TransitiveIpv6(TransitiveIpv6ExtendedCommunity::Unassigned {
+                        sub_type,
+                        global_admin,
+                        local_admin,
+                    })
+                }
+                Ok(BgpExtendedCommunityIpv6Type::NonTransitiveIpv6) => {
+                    let (global_admin, local_admin) = parse_admin(value)?;
+                    Self::NonTransitiveIpv6(NonTransitiveIpv6ExtendedCommunity::Unassigned {
+                        sub_type,
+                        global_admin,
+                        local_admin,
+                    })
+                }
+                Err(_) => Self::Unknown(UnknownExtendedCommunityIpv6::new(
+                    code,
+                    sub_type,
+                    parse_hex_value(value)?,
+                )),
+            };
+            return Ok(ret);
+        }
+        let (global_admin, local_admin) = parse_admin(value)?;
+        let ret = match name {
+            "rt" => TransitiveIpv6ExtendedCommunity::RouteTarget {
+                global_admin,
+                local_admin,
+            },
+            "soo" => TransitiveIpv6ExtendedCommunity::RouteOrigin {
+                global_admin,
+                local_admin,
+            },
+            "ifit" => TransitiveIpv6ExtendedCommunity::Ipv6Ifit {
+                global_admin,
+                local_admin,
+            },
+            "vrf-import" => TransitiveIpv6ExtendedCommunity::VrfRouteImport {
+                global_admin,
+                local_admin,
+            },
+            "redirect" => TransitiveIpv6ExtendedCommunity::FlowSpecRedirectToIpv6 {
+                global_admin,
+                local_admin,
+            },
+            "rt-redirect" => TransitiveIpv6ExtendedCommunity::FlowSpecRtRedirectToIpv6 {
+                global_admin,
+                local_admin,
+            },
+            "cisco-vpn-id" => TransitiveIpv6ExtendedCommunity::CiscoVpnDistinguisher {
+                global_admin,
+                local_admin,
+            },
+            "p2mp-segmented-nh" => TransitiveIpv6ExtendedCommunity::InterAreaP2MpSegmentedNextHop {
+                global_admin,
+                local_admin,
+            },
+            "rt-derived" => TransitiveIpv6ExtendedCommunity::RtDerivedEc {
+                global_admin,
+                local_admin,
+            },
+            _ => return Err(ExtendedCommunityFromStrError::UnknownName(name.to_string())),
+        };
+        Ok(Self::TransitiveIpv6(ret))
+    }
+}
+
+impl fmt::Display for TransitiveIpv6ExtendedCommunity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, global_admin, local_admin) = match self {
+            Self::RouteTarget {
+                global_admin,
+                local_admin,
+            } => ("rt", global_admin, local_admin),
+            Self::RouteOrigin {
+                global_admin,
+                local_admin,
+            } => ("soo", global_admin, local_admin),
+            Self::Ipv6Ifit {
+                global_admin,
+                local_admin,
+            } => ("ifit", global_admin, local_admin),
+            Self::VrfRouteImport {
+                global_admin,
+                local_admin,
+            } => ("vrf-import", global_admin, local_admin),
+            Self::FlowSpecRedirectToIpv6 {
+                global_admin,
+                local_admin,
+            } => ("redirect", global_admin, local_admin),
+            Self::FlowSpecRtRedirectToIpv6 {
+                global_admin,
+                local_admin,
+            } => ("rt-redirect", global_admin, local_admin),
+            Self::CiscoVpnDistinguisher {
+                global_admin,
+                local_admin,
+            } => ("cisco-vpn-id", global_admin, local_admin),
+            Self::InterAreaP2MpSegmentedNextHop {
+                global_admin,
+                local_admin,
+            } => ("p2mp-segmented-nh", global_admin, local_admin),
+            Self::RtDerivedEc {
+                global_admin,
+                local_admin,
+            } => ("rt-derived", global_admin, local_admin),
+            Self::Unassigned {
+                sub_type,
+                global_admin,
+                local_admin,
+            } => {
+                let code = BgpExtendedCommunityIpv6Type::TransitiveIpv6 as u8;
+                return write!(
+                    f,
+                    "{}:{global_admin}:{local_admin}",
+                    RawType(code, *sub_type)
+                );
+            }
+        };
+        write!(f, "{name}:{global_admin}:{local_admin}")
+    }
+}
+
+impl fmt::Display for NonTransitiveIpv6ExtendedCommunity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unassigned {
+                sub_type,
+                global_admin,
+                local_admin,
+            } => {
+                let code = BgpExtendedCommunityIpv6Type::NonTransitiveIpv6 as u8;
+                write!(
+                    f,
+                    "{}:{global_admin}:{local_admin}",
+                    RawType(code, *sub_type)
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for UnknownExtendedCommunityIpv6 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}",
+            RawType(self.code, self.sub_type),
+            HexValue(&self.value)
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_communities_ordering() {
+        let mut communities = vec![
+            Community::new(0xFFFFFF04),
+            Community::new(0x00010002),
+            Community::new(0x00010001),
+        ];
+        communities.sort();
+        assert_eq!(
+            communities,
+            vec![
+                Community::new(0x00010001),
+                Community::new(0x00010002),
+                Community::new(0xFFFFFF04),
+            ]
+        );
+
+        let route_target = |local_admin| {
+            ExtendedCommunity::TransitiveTwoOctet(
+                TransitiveTwoOctetExtendedCommunity::RouteTarget {
+                    global_admin: 65000,
+                    local_admin,
+                },
+            )
+        };
+        let set = BTreeSet::from([route_target(200), route_target(100), route_target(200)]);
+        assert_eq!(
+            set.into_iter().collect::<Vec<_>>(),
+            vec![route_target(100), route_target(200)]
+        );
+    }
 
     #[test]
     fn test_community_into_well_known() {
@@ -989,4 +2436,232 @@ mod tests {
         assert_eq!(comm.collection_asn(), 0x1001);
         assert_eq!(comm.collection_value(), 0x2003);
     }
+
+    #[test]
+    fn test_extended_community_transitive() {
+        assert!(BgpExtendedCommunityType::TransitiveTwoOctet.transitive());
+        assert!(!BgpExtendedCommunityType::NonTransitiveFourOctet.transitive());
+        assert!(BgpExtendedCommunityType::Evpn.transitive());
+        assert!(!BgpExtendedCommunityType::ExperimentalC0.transitive());
+        assert!(!BgpExtendedCommunityIpv6Type::NonTransitiveIpv6.transitive());
+        let non_transitive_four_octet = ExtendedCommunity::NonTransitiveFourOctet(
+            NonTransitiveFourOctetExtendedCommunity::Unassigned {
+                sub_type: 0x01,
+                global_admin: 65536,
+                local_admin: 1,
+            },
+        );
+        let transitive_opaque =
+            ExtendedCommunity::TransitiveOpaque(TransitiveOpaqueExtendedCommunity::DefaultGateway);
+        assert!(!non_transitive_four_octet.transitive());
+        assert!(transitive_opaque.transitive());
+    }
+
+    #[test]
+    fn test_extended_community_display() {
+        let rt = ExtendedCommunity::TransitiveTwoOctet(
+            TransitiveTwoOctetExtendedCommunity::RouteTarget {
+                global_admin: 65000,
+                local_admin: 100,
+            },
+        );
+        let rt4 = ExtendedCommunity::TransitiveFourOctet(
+            TransitiveFourOctetExtendedCommunity::RouteTarget {
+                global_admin: 65000,
+                local_admin: 100,
+            },
+        );
+        let soo = ExtendedCommunity::TransitiveIpv4(TransitiveIpv4ExtendedCommunity::RouteOrigin {
+            global_admin: Ipv4Addr::new(192, 0, 2, 1),
+            local_admin: 10,
+        });
+        let lb = ExtendedCommunity::NonTransitiveTwoOctet(
+            NonTransitiveTwoOctetExtendedCommunity::LinkBandwidth {
+                global_admin: 65000,
+                local_admin: 1_250_000f32.to_bits(),
+            },
+        );
+        let mac = ExtendedCommunity::Evpn(EvpnExtendedCommunity::EvpnRoutersMac {
+            mac: MacAddress([0x00, 0x1b, 0x21, 0x3c, 0x4d, 0x5e]),
+        });
+        let experimental = ExtendedCommunity::Experimental(ExperimentalExtendedCommunity::new(
+            0x83,
+            0x01,
+            [0, 0, 0, 0, 0, 100],
+        ));
+        let rt6 =
+            ExtendedCommunityIpv6::TransitiveIpv6(TransitiveIpv6ExtendedCommunity::RouteTarget {
+                global_admin: Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1),
+                local_admin: 100,
+            });
+        assert_eq!(rt.to_string(), "rt:65000:100");
+        assert_eq!(rt4.to_string(), "rt:65000L:100");
+        assert_eq!(soo.to_string(), "soo:192.0.2.1:10");
+        assert_eq!(lb.to_string(), "lb:65000:10M");
+        assert_eq!(mac.to_string(), "router-mac:00:1b:21:3c:4d:5e");
+        assert_eq!(experimental.to_string(), "0x8301:0x000000000064");
+        assert_eq!(rt6.to_string(), "rt:2001:db8::1:100");
+    }
+
+    #[test]
+    fn test_extended_community_from_str() {
+        let values = [
+            "rt:65000:100",
+            "rt:65000L:100",
+            "rt:4200000000:100",
+            "rt:192.0.2.1:100",
+            "soo:65000:4294967295",
+            "ospf-domain-id:192.0.2.1:0",
+            "data-collection:65000:1",
+            "source-as:65536:0",
+            "l2vpn-id:65000:10",
+            "cisco-vpn-id:192.0.2.1:10",
+            "rt-record:65000:10",
+            "rt-derived:65536:10",
+            "vni:65000:5000",
+            "nt-vni:65000:5000",
+            "ifit:192.0.2.1:1",
+            "ospf-router-id:192.0.2.1:0",
+            "node-target:192.0.2.1:0",
+            "vrf-import:192.0.2.1:7",
+            "redirect:192.0.2.1:7",
+            "p2mp-segmented-nh:192.0.2.1:0",
+            "vrf-recursive-nh:192.0.2.1:0",
+            "mvpn-rp:192.0.2.1:0",
+            "lb:65000:10M",
+            "lb:65000:1500M",
+            "lb:65000:100",
+            "lb:65000:0xffffffff",
+            "default-gateway",
+            "mac-mobility:1:20",
+            "esi-label:0:1000",
+            "es-import:00:1b:21:3c:4d:5e",
+            "router-mac:00:1b:21:3c:4d:5e",
+            "l2-attr:2:1500",
+            "0x0042:65000:100",
+            "0x4042:65000:100",
+            "0x0142:192.0.2.1:100",
+            "0x4142:192.0.2.1:100",
+            "0x0242:65000:100",
+            "0x4242:65000:100",
+            "0x0301:0x010203040506",
+            "0x4301:0x010203040506",
+            "0x0642:0x010203040506",
+            "0x8301:0x000000000064",
+            "0x0401:0x010203040506",
+        ];
+        for value in values {
+            let parsed = ExtendedCommunity::from_str(value);
+            assert!(parsed.is_ok(), "failed to parse {value}: {parsed:?}");
+            assert_eq!(parsed.unwrap().to_string(), value);
+        }
+        assert_eq!(
+            ExtendedCommunity::from_str("lb:65000:1.25G"),
+            Ok(ExtendedCommunity::NonTransitiveTwoOctet(
+                NonTransitiveTwoOctetExtendedCommunity::LinkBandwidth {
+                    global_admin: 65000,
+                    local_admin: 156_250_000f32.to_bits(),
+                }
+            ))
+        );
+        let values_ipv6 = [
+            "rt:2001:db8::1:100",
+            "soo:2001:db8::1:100",
+            "rt-redirect:2001:db8::1:100",
+            "0x0042:2001:db8::1:100",
+            "0x4042:2001:db8::1:100",
+            "0x0142:0x000102030405060708090a0b0c0d0e0f1011",
+        ];
+        for value in values_ipv6 {
+            let parsed = ExtendedCommunityIpv6::from_str(value);
+            assert!(parsed.is_ok(), "failed to parse {value}: {parsed:?}");
+            assert_eq!(parsed.unwrap().to_string(), value);
+        }
+    }
+
+    #[test]
+    fn test_extended_community_from_str_errors() {
+        assert_eq!(
+            ExtendedCommunity::from_str("rt"),
+            Err(ExtendedCommunityFromStrError::InvalidFormat(
+                "rt".to_string()
+            ))
+        );
+        assert_eq!(
+            ExtendedCommunity::from_str("foo:65000:100"),
+            Err(ExtendedCommunityFromStrError::UnknownName(
+                "foo".to_string()
+            ))
+        );
+        assert_eq!(
+            ExtendedCommunity::from_str("ospf-router-id:65000:100"),
+            Err(ExtendedCommunityFromStrError::InvalidGlobalAdmin(
+                "65000".to_string()
+            ))
+        );
+        assert_eq!(
+            ExtendedCommunity::from_str("rt:65536:65536"),
+            Err(ExtendedCommunityFromStrError::InvalidLocalAdmin(
+                "65536".to_string()
+            ))
+        );
+        assert_eq!(
+            ExtendedCommunity::from_str("lb:65000:10X"),
+            Err(ExtendedCommunityFromStrError::InvalidLocalAdmin(
+                "10X".to_string()
+            ))
+        );
+        assert_eq!(
+            ExtendedCommunity::from_str("0x0002:65000:100"),
+            Err(ExtendedCommunityFromStrError::AssignedSubType {
+                code: 0x00,
+                sub_type: 0x02
+            })
+        );
+        assert_eq!(
+            ExtendedCommunity::from_str("0x8301:0x0102"),
+            Err(ExtendedCommunityFromStrError::InvalidValue(
+                "0x0102".to_string()
+            ))
+        );
+        assert_eq!(
+            ExtendedCommunity::from_str("router-mac:00:1b:21:3c:4d"),
+            Err(ExtendedCommunityFromStrError::InvalidValue(
+                "00:1b:21:3c:4d".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_link_bandwidth() {
+        let two_octet = LinkBandwidth::new(65000, 1_250_000.0);
+        let four_octet = LinkBandwidth::new(4200000000, 1_250_000.0);
+        assert_eq!(two_octet.bits_per_second(), 10_000_000.0);
+        assert_eq!(
+            LinkBandwidth::from_extended_community(&two_octet.to_extended_community()),
+            Some(two_octet)
+        );
+        assert_eq!(
+            LinkBandwidth::from_extended_community(&four_octet.to_extended_community()),
+            Some(LinkBandwidth::new(AS_TRANS as u32, 1_250_000.0))
+        );
+        assert_eq!(
+            LinkBandwidth::from_extended_community(&ExtendedCommunity::TransitiveOpaque(
+                TransitiveOpaqueExtendedCommunity::DefaultGateway
+            )),
+            None
+        );
+    }
+
+    #[test]
+    fn test_link_bandwidth_shares() {
+        assert_eq!(
+            link_bandwidth_shares(&[Some(300.0), Some(100.0)]),
+            Some(vec![0.75, 0.25])
+        );
+        assert_eq!(link_bandwidth_shares(&[Some(300.0), None]), None);
+        assert_eq!(link_bandwidth_shares(&[Some(0.0), Some(0.0)]), None);
+        assert_eq!(link_bandwidth_shares(&[Some(f32::NAN), Some(1.0)]), None);
+        assert_eq!(link_bandwidth_shares(&[]), None);
+    }
 }
@@ -15,12 +15,14 @@
 
 //! Contains BGP codes that are registered at IANA [BGP Parameters](https://www.iana.org/assignments/bgp-parameters/bgp-parameters.xhtml)
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use strum_macros::{Display, FromRepr};
 
 /// BGP Message types as registered in IANA [BGP Message Types](https://www.iana.org/assignments/bgp-parameters/bgp-parameters.xhtml#bgp-parameters-1)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BgpMessageType {
     Open = 1,
@@ -33,7 +35,8 @@ pub enum BgpMessageType {
 
 /// BGP Message type is not one of [`BgpMessageType`], the carried value is the
 /// undefined code.
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedBgpMessageType(pub u8);
 
@@ -56,7 +59,8 @@ impl TryFrom<u8> for BgpMessageType {
 
 /// BGP Path Attributes as defined by IANA [BGP Path Attributes](https://www.iana.org/assignments/bgp-parameters/bgp-parameters.xhtml#bgp-parameters-2)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum PathAttributeType {
     /// [RFC4271](https://datatracker.ietf.org/doc/html/rfc4271)
@@ -159,7 +163,8 @@ impl From<PathAttributeType> for u8 {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedPathAttributeType(pub u8);
 
@@ -176,7 +181,8 @@ impl TryFrom<u8> for PathAttributeType {
 
 /// BGP Error (Notification) Codes as defined by IANA [BGP Error (Notification) Codes](https://www.iana.org/assignments/bgp-parameters/bgp-parameters.xhtml#bgp-parameters-3)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BgpErrorNotificationCode {
     /// [RFC4271](https://datatracker.ietf.org/doc/html/rfc4271)
@@ -207,7 +213,8 @@ impl From<BgpErrorNotificationCode> for u8 {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedBgpErrorNotificationCode(pub u8);
 
@@ -224,7 +231,8 @@ impl TryFrom<u8> for BgpErrorNotificationCode {
 
 /// Message Header Error sub-codes for [`BgpErrorNotificationCode::MessageHeaderError`] as defined by IANA [Message Header Error subcodes](https://www.iana.org/assignments/bgp-parameters/bgp-parameters.xhtml#bgp-parameters-5)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum MessageHeaderErrorSubCode {
     /// [RFC Errata 4493](https://www.rfc-editor.org/errata_search.php?eid=4493)
@@ -240,7 +248,8 @@ impl From<MessageHeaderErrorSubCode> for u8 {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedMessageHeaderErrorSubCode(pub u8);
 
@@ -257,7 +266,8 @@ impl TryFrom<u8> for MessageHeaderErrorSubCode {
 
 /// OPEN Message Error sub-codes for [`BgpErrorNotificationCode::OpenMessageError`] as defined by IANA [OPEN Message Error subcodes](https://www.iana.org/assignments/bgp-parameters/bgp-parameters.xhtml#bgp-parameters-6)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum OpenMessageErrorSubCode {
     /// [RFC Errata 4493](https://www.rfc-editor.org/errata_search.php?eid=4493)
@@ -281,7 +291,8 @@ impl From<OpenMessageErrorSubCode> for u8 {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedOpenMessageErrorSubCode(pub u8);
 
@@ -298,7 +309,8 @@ impl TryFrom<u8> for OpenMessageErrorSubCode {
 
 /// UPDATE Message Error sub-codes for [`BgpErrorNotificationCode::UpdateMessageError`] as defined by IANA [UPDATE Message Error subcodes](https://www.iana.org/assignments/bgp-parameters/bgp-parameters.xhtml#bgp-parameters-7)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum UpdateMessageErrorSubCode {
     /// [RFC Errata 4493](https://www.rfc-editor.org/errata_search.php?eid=4493)
@@ -321,7 +333,8 @@ impl From<UpdateMessageErrorSubCode> for u8 {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedUpdateMessageErrorSubCode(pub u8);
 
@@ -338,7 +351,8 @@ impl TryFrom<u8> for UpdateMessageErrorSubCode {
 
 /// BGP Finite State Machine Error sub-codes for [`BgpErrorNotificationCode::FiniteStateMachineError`] as defined by IANA [BGP Finite State Machine Error Subcodes](https://www.iana.org/assignments/bgp-parameters/bgp-parameters.xhtml#bgp-finite-state-machine-error-subcodes)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum FiniteStateMachineErrorSubCode {
     /// [RFC6608](https://datatracker.ietf.org/doc/html/rfc6608)
@@ -360,7 +374,8 @@ impl From<FiniteStateMachineErrorSubCode> for u8 {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedFiniteStateMachineErrorSubCode(pub u8);
 
@@ -377,7 +392,8 @@ impl TryFrom<u8> for FiniteStateMachineErrorSubCode {
 
 /// BGP Cease NOTIFICATION message Error sub-codes for [`BgpErrorNotificationCode::Cease]` as defined by IANA [BGP Cease NOTIFICATION message subcodes](https://www.iana.org/assignments/bgp-parameters/bgp-parameters.xhtml#bgp-parameters-8)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum CeaseErrorSubCode {
     /// [RFC4486](https://datatracker.ietf.org/doc/html/rfc4486)
@@ -417,7 +433,8 @@ impl From<CeaseErrorSubCode> for u8 {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedCeaseErrorSubCode(pub u8);
 
@@ -434,7 +451,8 @@ impl TryFrom<u8> for CeaseErrorSubCode {
 
 /// BGP ROUTE-REFRESH Message Error subcodes for [`BgpErrorNotificationCode::RouteRefreshMessageError`] as defined by IANA [BGP ROUTE-REFRESH Message Error subcodes](https://www.iana.org/assignments/bgp-parameters/bgp-parameters.xhtml#route-refresh-error-subcodes)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum RouteRefreshMessageErrorSubCode {
     /// [RFC7313](https://datatracker.ietf.org/doc/html/rfc7313)
@@ -447,7 +465,8 @@ impl From<RouteRefreshMessageErrorSubCode> for u8 {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedRouteRefreshMessageError(pub u8);
 
@@ -464,7 +483,8 @@ impl TryFrom<u8> for RouteRefreshMessageErrorSubCode {
 
 /// [BGP OPEN Optional Parameter Types](https://www.iana.org/assignments/bgp-parameters/bgp-parameters.xhtml#bgp-parameters-11)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BgpOpenMessageParameterType {
     /// [RFC5492](https://datatracker.ietf.org/doc/html/rfc5492)
@@ -480,7 +500,8 @@ impl From<BgpOpenMessageParameterType> for u8 {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedBgpOpenMessageParameterType(pub u8);
 
@@ -497,7 +518,8 @@ impl TryFrom<u8> for BgpOpenMessageParameterType {
 
 /// [BGP Capabilities Codes](https://www.iana.org/assignments/capability-codes/capability-codes.xhtml)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, Hash, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, Hash, PartialEq, Eq, Debug, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpCapabilityCode {
     /// [RFC4760](https://datatracker.ietf.org/doc/html/rfc4760)
     MultiProtocolExtensions = 1,
@@ -607,7 +629,8 @@ impl From<BgpCapabilityCode> for u8 {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedBgpCapabilityCode(pub u8);
 
@@ -624,7 +647,8 @@ impl TryFrom<u8> for BgpCapabilityCode {
 
 /// [BGP Route Refresh Subcodes](https://www.iana.org/assignments/bgp-parameters/bgp-parameters.xhtml#route-refresh-subcodes)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum RouteRefreshSubcode {
     NormalRequest = 0,
@@ -638,7 +662,8 @@ impl From<RouteRefreshSubcode> for u8 {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedRouteRefreshSubcode(pub u8);
 
@@ -655,7 +680,8 @@ impl TryFrom<u8> for RouteRefreshSubcode {
 
 /// [Route Distinguisher Type Field](https://www.iana.org/assignments/route-distinguisher-types/route-distinguisher-types.xhtml)
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum RouteDistinguisherTypeCode {
     As2Administrator = 0,
@@ -671,7 +697,8 @@ impl From<RouteDistinguisherTypeCode> for u16 {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedRouteDistinguisherTypeCode(pub u16);
 
@@ -696,7 +723,8 @@ impl TryFrom<u16> for RouteDistinguisherTypeCode {
 /// the first two octets encoding the autonomous system value as described by
 /// the RFC.
 #[repr(u32)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum WellKnownCommunity {
     /// [RFC8326](https://datatracker.ietf.org/doc/html/rfc8326)
@@ -726,7 +754,8 @@ pub enum WellKnownCommunity {
 /// Standard (outbound) communities and their encodings for export to BGP route
 /// collectors defined by [RFC4384](https://datatracker.ietf.org/doc/html/rfc4384)
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BgpDataCollectionCommunityValueCode {
     CustomerRoutes = 0b0000000000000001,
@@ -741,7 +770,8 @@ pub enum BgpDataCollectionCommunityValueCode {
 ///
 /// Region Identifiers defined [RFC4384](https://datatracker.ietf.org/doc/html/rfc4384)
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BgpDataCollectionCommunityRegionIdentifierCode {
     Africa = 0b00001,
@@ -754,7 +784,8 @@ pub enum BgpDataCollectionCommunityRegionIdentifierCode {
 }
 
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BgpExtendedCommunityType {
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
@@ -882,7 +913,16 @@ pub enum BgpExtendedCommunityType {
     ExperimentalCf = 0xcf,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+impl BgpExtendedCommunityType {
+    /// The Transitive bit (0x40) of the type high octet is not set, i.e., the
+    /// extended community can be advertised to other Autonomous Systems.
+    pub const fn transitive(&self) -> bool {
+        (*self as u8) & 0x40 == 0
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedBgpExtendedCommunityType(pub u8);
 
@@ -898,7 +938,8 @@ impl TryFrom<u8> for BgpExtendedCommunityType {
 }
 
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BgpExtendedCommunityIpv6Type {
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
@@ -908,7 +949,16 @@ pub enum BgpExtendedCommunityIpv6Type {
     NonTransitiveIpv6 = 0x40,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+impl BgpExtendedCommunityIpv6Type {
+    /// The Transitive bit (0x40) of the type high octet is not set, i.e., the
+    /// extended community can be advertised to other Autonomous Systems.
+    pub const fn transitive(&self) -> bool {
+        (*self as u8) & 0x40 == 0
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedBgpExtendedCommunityIpv6Type(pub u8);
 
@@ -924,7 +974,8 @@ impl TryFrom<u8> for BgpExtendedCommunityIpv6Type {
 }
 
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum TransitiveTwoOctetExtendedCommunitySubType {
     /// [RFC4360](https://datatracker.ietf.org/doc/html/rfc4360)
@@ -955,7 +1006,8 @@ pub enum TransitiveTwoOctetExtendedCommunitySubType {
     VirtualNetworkIdentifier = 0x80,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedTransitiveTwoOctetExtendedCommunitySubType(pub u8);
 
@@ -971,7 +1023,8 @@ impl TryFrom<u8> for TransitiveTwoOctetExtendedCommunitySubType {
 }
 
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum NonTransitiveTwoOctetExtendedCommunitySubType {
     /// [draft-ietf-idr-link-bandwidth](https://datatracker.ietf.org/doc/draft-ietf-idr-link-bandwidth/)
@@ -981,7 +1034,8 @@ pub enum NonTransitiveTwoOctetExtendedCommunitySubType {
     VirtualNetworkIdentifier = 0x80,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedNonTransitiveTwoOctetExtendedCommunitySubType(pub u8);
 
@@ -999,7 +1053,8 @@ impl TryFrom<u8> for NonTransitiveTwoOctetExtendedCommunitySubType {
 }
 
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum TransitiveFourOctetExtendedCommunitySubType {
     /// [RFC5668](https://datatracker.ietf.org/doc/html/rfc5668)
@@ -1026,7 +1081,8 @@ pub enum TransitiveFourOctetExtendedCommunitySubType {
     RtDerivedEc = 0x15,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedTransitiveFourOctetExtendedCommunitySubType(pub u8);
 
@@ -1042,7 +1098,8 @@ impl TryFrom<u8> for TransitiveFourOctetExtendedCommunitySubType {
 }
 
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum TransitiveIpv4ExtendedCommunitySubType {
     /// [RFC4360](https://datatracker.ietf.org/doc/html/rfc4360)
@@ -1088,7 +1145,8 @@ pub enum TransitiveIpv4ExtendedCommunitySubType {
     MulticastVpnRpAddress = 0x80,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedTransitiveIpv4ExtendedCommunitySubType(pub u8);
 
@@ -1104,7 +1162,8 @@ impl TryFrom<u8> for TransitiveIpv4ExtendedCommunitySubType {
 }
 
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum TransitiveIpv6ExtendedCommunitySubType {
     /// [RFC5701](https://datatracker.ietf.org/doc/html/rfc5701)
@@ -1135,7 +1194,8 @@ pub enum TransitiveIpv6ExtendedCommunitySubType {
     RtDerivedEc = 0x15,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedTransitiveIpv6ExtendedCommunitySubType(pub u8);
 
@@ -1152,7 +1212,8 @@ impl TryFrom<u8> for TransitiveIpv6ExtendedCommunitySubType {
 
 /// EVPN Route Types [RFC7432](https://datatracker.ietf.org/doc/html/rfc7432)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum L2EvpnRouteTypeCode {
     /// [RFC7432](https://datatracker.ietf.org/doc/html/rfc7432)
@@ -1180,7 +1241,8 @@ pub enum L2EvpnRouteTypeCode {
     MulticastLeaveSynchRoute = 0x08,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedL2EvpnRouteTypeCode(pub u8);
 
@@ -1195,9 +1257,51 @@ impl TryFrom<u8> for L2EvpnRouteTypeCode {
     }
 }
 
+/// Ethernet Segment Identifier (ESI) Types [RFC7432](https://datatracker.ietf.org/doc/html/rfc7432#section-5)
+#[repr(u8)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub enum EthernetSegmentIdentifierType {
+    /// Arbitrary value configured by the operator
+    Arbitrary = 0x00,
+
+    /// Auto-generated from the IEEE 802.1AX LACP of the CE
+    Lacp = 0x01,
+
+    /// Auto-generated from the bridge protocol data units of the CE
+    RootBridge = 0x02,
+
+    /// Auto-generated from the MAC address of the PE
+    Mac = 0x03,
+
+    /// Auto-generated from the router ID of the PE
+    RouterId = 0x04,
+
+    /// Auto-generated from the autonomous system number of the PE
+    As = 0x05,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct UndefinedEthernetSegmentIdentifierType(pub u8);
+
+impl TryFrom<u8> for EthernetSegmentIdentifierType {
+    type Error = UndefinedEthernetSegmentIdentifierType;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match Self::from_repr(value) {
+            Some(val) => Ok(val),
+            None => Err(UndefinedEthernetSegmentIdentifierType(value)),
+        }
+    }
+}
+
 /// EVPN Extended Community Sub-Types [IANA](https://www.iana.org/assignments/bgp-extended-communities/bgp-extended-communities.xhtml#evpn)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum EvpnExtendedCommunitySubType {
     /// [RFC7432](https://datatracker.ietf.org/doc/html/rfc7432)
@@ -1240,7 +1344,8 @@ pub enum EvpnExtendedCommunitySubType {
     EviRtType3 = 0x0d,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedEvpnExtendedCommunitySubType(pub u8);
 
@@ -1257,14 +1362,16 @@ impl TryFrom<u8> for EvpnExtendedCommunitySubType {
 
 /// Transitive Opaque Extended Community Sub-Types [IANA](https://www.iana.org/assignments/bgp-extended-communities/bgp-extended-communities.xhtml#trans-opaque)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum TransitiveOpaqueExtendedCommunitySubType {
     /// [RFC7432](https://datatracker.ietf.org/doc/html/rfc7432)
     DefaultGateway = 0x0d,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedTransitiveOpaqueExtendedCommunitySubType(pub u8);
 
@@ -1282,7 +1389,8 @@ impl TryFrom<u8> for TransitiveOpaqueExtendedCommunitySubType {
 /// BGP Role Values used in the route leak prevention and detection procedures
 /// [RFC9234](https://datatracker.ietf.org/doc/html/rfc9234)
 #[repr(u8)]
-#[derive(Display, FromRepr, Hash, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Hash, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BgpRoleValue {
     /// The local AS is a transit provider of the remote AS
@@ -1309,7 +1417,8 @@ impl From<BgpRoleValue> for u8 {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UndefinedBgpRoleValue(pub u8);
 
 impl TryFrom<u8> for BgpRoleValue {
@@ -1325,7 +1434,8 @@ impl TryFrom<u8> for BgpRoleValue {
 
 /// Accumulated IGP Type [RFC7311](https://datatracker.ietf.org/doc/html/rfc7311)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum AigpAttributeType {
     /// Accumulated IGP Metric
     AccumulatedIgpMetric = 0x01,
@@ -1337,7 +1447,8 @@ impl From<AigpAttributeType> for u8 {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UndefinedAigpAttributeType(pub u8);
 
 impl TryFrom<u8> for AigpAttributeType {
@@ -1351,12 +1462,87 @@ impl TryFrom<u8> for AigpAttributeType {
     }
 }
 
+/// Flow Spec Component Types [IANA](https://www.iana.org/assignments/flow-spec/flow-spec.xhtml#flow-spec-component-types)
+///
+/// The same registry is used for IPv4
+/// [RFC8955](https://datatracker.ietf.org/doc/html/rfc8955) and IPv6
+/// [RFC8956](https://datatracker.ietf.org/doc/html/rfc8956), where the IP
+/// protocol component matches the upper layer protocol (last Next Header).
+#[repr(u8)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub enum FlowSpecComponentType {
+    DestinationPrefix = 1,
+    SourcePrefix = 2,
+    IpProtocol = 3,
+    Port = 4,
+    DestinationPort = 5,
+    SourcePort = 6,
+    IcmpType = 7,
+    IcmpCode = 8,
+    TcpFlags = 9,
+    PacketLength = 10,
+    Dscp = 11,
+    Fragment = 12,
+    /// IPv6 only [RFC8956](https://datatracker.ietf.org/doc/html/rfc8956)
+    FlowLabel = 13,
+}
+
+impl From<FlowSpecComponentType> for u8 {
+    fn from(value: FlowSpecComponentType) -> Self {
+        value as u8
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UndefinedFlowSpecComponentType(pub u8);
+
+impl TryFrom<u8> for FlowSpecComponentType {
+    type Error = UndefinedFlowSpecComponentType;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match Self::from_repr(value) {
+            Some(val) => Ok(val),
+            None => Err(UndefinedFlowSpecComponentType(value)),
+        }
+    }
+}
+
+/// Sub-types of the Flow Spec actions carried in the Generic Transitive
+/// Experimental Use extended communities (type `0x80`)
+/// [IANA](https://www.iana.org/assignments/bgp-extended-communities/bgp-extended-communities.xhtml#generic-transitive-experimental-use)
+#[repr(u8)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub enum FlowSpecActionSubType {
+    /// [RFC8955](https://datatracker.ietf.org/doc/html/rfc8955)
+    TrafficRateBytes = 0x06,
+    /// [RFC8955](https://datatracker.ietf.org/doc/html/rfc8955)
+    TrafficAction = 0x07,
+    /// [RFC8955](https://datatracker.ietf.org/doc/html/rfc8955)
+    RedirectAsTwoOctet = 0x08,
+    /// [RFC8955](https://datatracker.ietf.org/doc/html/rfc8955)
+    TrafficMarking = 0x09,
+    /// [RFC8955](https://datatracker.ietf.org/doc/html/rfc8955)
+    TrafficRatePackets = 0x0c,
+}
+
+impl From<FlowSpecActionSubType> for u8 {
+    fn from(value: FlowSpecActionSubType) -> Self {
+        value as u8
+    }
+}
+
 /// Reserved by RFC6793 for AS4 that are non-mappable to AS2
 pub const AS_TRANS: u16 = 23456;
 
 /// BGP-LS NLRI Types [IANA](https://www.iana.org/assignments/bgp-ls-parameters/bgp-ls-parameters.xhtml#nlri-types)
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpLsNlriType {
     /// [RFC7752](https://datatracker.ietf.org/doc/html/rfc7752)
     Node = 1,
@@ -1372,7 +1558,8 @@ pub enum BgpLsNlriType {
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UnknownBgpLsNlriType(pub u16);
 
 impl From<BgpLsNlriType> for u16 {
@@ -1394,7 +1581,8 @@ impl TryFrom<u16> for BgpLsNlriType {
 
 /// BGP-LS Protocol IDs [IANA](https://www.iana.org/assignments/bgp-ls-parameters/bgp-ls-parameters.xhtml#protocol-ids)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BgpLsProtocolId {
     IsIsLevel1 = 1,
@@ -1409,7 +1597,8 @@ pub enum BgpLsProtocolId {
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BgpLsProtocolIdError(pub BgpLsIanaValueError<u8>);
 
 impl From<BgpLsProtocolId> for u8 {
@@ -1435,14 +1624,16 @@ impl TryFrom<u8> for BgpLsProtocolId {
     }
 }
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpLsNodeDescriptorType {
     LocalNodeDescriptor = 256,
     RemoteNodeDescriptor = 257,
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BgpLsNodeDescriptorTypeError(pub BgpLsIanaValueError<u16>);
 
 impl From<BgpLsNodeDescriptorType> for u16 {
@@ -1474,7 +1665,8 @@ impl TryFrom<u16> for BgpLsNodeDescriptorType {
 
 /// BGP-LS Node Descriptor Sub-TLVs [IANA](https://www.iana.org/assignments/bgp-ls-parameters/bgp-ls-parameters.xhtml#node-descriptor-link-descriptor-prefix-descriptor-attribute-tlv)
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpLsNodeDescriptorSubType {
     AutonomousSystem = 512,
     BgpLsIdentifier = 513,
@@ -1485,7 +1677,8 @@ pub enum BgpLsNodeDescriptorSubType {
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NodeDescriptorSubTypeError(pub BgpLsIanaValueError<u16>);
 
 impl From<BgpLsNodeDescriptorSubType> for u16 {
@@ -1516,7 +1709,8 @@ impl TryFrom<u16> for BgpLsNodeDescriptorSubType {
 }
 
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpLsPrefixDescriptorType {
     MultiTopologyIdentifier = 263,
     OspfRouteType = 264,
@@ -1524,7 +1718,8 @@ pub enum BgpLsPrefixDescriptorType {
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PrefixDescriptorTypeError(pub BgpLsIanaValueError<u16>);
 
 impl From<BgpLsPrefixDescriptorType> for u16 {
@@ -1555,7 +1750,8 @@ impl TryFrom<u16> for BgpLsPrefixDescriptorType {
 }
 
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpLsLinkDescriptorType {
     LinkLocalRemoteIdentifiers = 258,
     IPv4InterfaceAddress = 259,
@@ -1566,7 +1762,8 @@ pub enum BgpLsLinkDescriptorType {
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct LinkDescriptorTypeError(pub BgpLsIanaValueError<u16>);
 
 impl From<BgpLsLinkDescriptorType> for u16 {
@@ -1595,7 +1792,8 @@ impl TryFrom<u16> for BgpLsLinkDescriptorType {
 }
 
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpLsDescriptorTlvType {
     LocalNodeDescriptor = 256,
     RemoteNodeDescriptor = 257,
@@ -1610,7 +1808,8 @@ pub enum BgpLsDescriptorTlvType {
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DescriptorTlvTypeError(pub BgpLsIanaValueError<u16>);
 
 impl From<BgpLsDescriptorTlvType> for u16 {
@@ -1638,7 +1837,8 @@ impl TryFrom<u16> for BgpLsDescriptorTlvType {
 /// Aggregate of [BgpLsLinkAttributeType] [BgpLsNodeAttributeType]
 /// [BgpLsPrefixAttributeType]
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpLsAttributeType {
     MultiTopologyIdentifier = 263,
     NodeFlagBits = 1024,
@@ -1672,7 +1872,8 @@ pub enum BgpLsAttributeType {
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Display)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpLsIanaValueError<T> {
     /// Reserved Values
     Reserved(T),
@@ -1682,7 +1883,8 @@ pub enum BgpLsIanaValueError<T> {
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BgpLsAttributeTypeError(pub BgpLsIanaValueError<u16>);
 
 impl From<BgpLsAttributeType> for u16 {
@@ -1711,7 +1913,8 @@ impl TryFrom<u16> for BgpLsAttributeType {
 }
 
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpLsNodeAttributeType {
     MultiTopologyIdentifier = 263,
     NodeFlagBits = 1024,
@@ -1722,7 +1925,8 @@ pub enum BgpLsNodeAttributeType {
     LocalNodeIpv6RouterId = 1029,
 }
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpLsLinkAttributeType {
     LocalNodeIpv4RouterId = 1028,
     LocalNodeIpv6RouterId = 1029,
@@ -1742,7 +1946,8 @@ pub enum BgpLsLinkAttributeType {
 }
 
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpLsPrefixAttributeType {
     IgpFlags = 1152,
     IgpRouteTag = 1153,
@@ -1777,7 +1982,8 @@ pub enum BgpLsPrefixAttributeType {
 /// - Rsvd bits: Reserved for future use and MUST be zero when originated and
 ///   ignored when received.
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpLsSidAttributeFlags {
     ValueFlag = 0b_1000_0000,
     LocalFlag = 0b_0100_0000,
@@ -1800,7 +2006,8 @@ pub enum BgpLsSidAttributeFlags {
 /// ```
 /// see [RFC7752 Section 3.2.3.2](https://www.rfc-editor.org/rfc/rfc7752#section-3.2.3.2)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpLsNodeFlagsBits {
     Overload = 0b_1000_0000,
     Attached = 0b_0100_0000,
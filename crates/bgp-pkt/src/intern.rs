@@ -0,0 +1,249 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interning of path attribute sets.
+//!
+//! Full table feeds carry the same set of path attributes for many prefixes.
+//! [`PathAttributesInterner`] hands out a shared [`PathAttributes`] for equal
+//! sets, so consumers keeping routes in memory store each distinct set once.
+//!
+//! ```
+//! use netgauze_bgp_pkt::{
+//!     intern::PathAttributesInterner,
+//!     path_attribute::{Origin, PathAttribute, PathAttributeValue},
+//! };
+//! use std::sync::Arc;
+//!
+//! let origin = PathAttribute::from(
+//!     false,
+//!     true,
+//!     false,
+//!     false,
+//!     PathAttributeValue::Origin(Origin::IGP),
+//! )
+//! .unwrap();
+//! let mut interner = PathAttributesInterner::new();
+//! let first = interner.intern(&[origin.clone()]).unwrap();
+//! let second = interner.intern(&[origin]).unwrap();
+//! assert!(Arc::ptr_eq(&first, &second));
+//! assert_eq!(interner.stats().hits(), 1);
+//! ```
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::{Arc, Weak},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use netgauze_parse_utils::WritablePdu;
+
+use crate::{
+    path_attribute::PathAttribute, update::BgpUpdateMessage,
+    wire::serializer::path_attribute::PathAttributeWritingError,
+};
+
+/// Shared, immutable set of path attributes
+pub type PathAttributes = Arc<[PathAttribute]>;
+
+/// Counters of a [`PathAttributesInterner`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InternerStats {
+    hits: u64,
+    misses: u64,
+    entries: usize,
+}
+
+impl InternerStats {
+    /// Number of lookups that returned an already interned set
+    pub const fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Number of lookups that interned a new set
+    pub const fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    pub const fn lookups(&self) -> u64 {
+        self.hits + self.misses
+    }
+
+    /// Ratio of lookups that returned an already interned set, zero when
+    /// nothing was looked up yet
+    pub fn hit_rate(&self) -> f64 {
+        match self.lookups() {
+            0 => 0.0,
+            lookups => self.hits as f64 / lookups as f64,
+        }
+    }
+
+    /// Number of tracked sets, including the ones no longer referenced that
+    /// haven't been purged yet
+    pub const fn entries(&self) -> usize {
+        self.entries
+    }
+}
+
+/// Hash-consing store of path attribute sets.
+///
+/// Sets are identified by their wire encoding, only a hash of it is kept along
+/// with a weak reference to the set. Hence, the interner doesn't keep sets
+/// alive, they're freed once the last [`PathAttributes`] handed out is
+/// dropped and [`PathAttributesInterner::purge`] reclaims their entries.
+#[derive(Debug, Default)]
+pub struct PathAttributesInterner {
+    entries: HashMap<u64, Vec<Weak<[PathAttribute]>>>,
+    buf: Vec<u8>,
+    hits: u64,
+    misses: u64,
+}
+
+impl PathAttributesInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the shared set equal to `attributes`, interning it if it's not
+    /// known yet.
+    pub fn intern(
+        &mut self,
+        attributes: &[PathAttribute],
+    ) -> Result<PathAttributes, PathAttributeWritingError> {
+        self.buf.clear();
+        for attribute in attributes {
+            attribute.write(&mut self.buf)?;
+        }
+        let mut hasher = DefaultHasher::new();
+        self.buf.hash(&mut hasher);
+        let bucket = self.entries.entry(hasher.finish()).or_default();
+        if let Some(interned) = bucket
+            .iter()
+            .filter_map(Weak::upgrade)
+            .find(|interned| interned.as_ref() == attributes)
+        {
+            self.hits += 1;
+            return Ok(interned);
+        }
+        self.misses += 1;
+        let interned: PathAttributes = attributes.into();
+        bucket.retain(|weak| weak.strong_count() > 0);
+        bucket.push(Arc::downgrade(&interned));
+        Ok(interned)
+    }
+
+    /// Intern the path attributes of an update message
+    pub fn intern_update(
+        &mut self,
+        update: &BgpUpdateMessage,
+    ) -> Result<PathAttributes, PathAttributeWritingError> {
+        self.intern(update.path_attributes())
+    }
+
+    /// Drop the entries of the sets that are no longer referenced, returns the
+    /// number of dropped entries.
+    pub fn purge(&mut self) -> usize {
+        let before = self.len();
+        self.entries.retain(|_, bucket| {
+            bucket.retain(|weak| weak.strong_count() > 0);
+            !bucket.is_empty()
+        });
+        before - self.len()
+    }
+
+    /// Number of tracked sets, see [`InternerStats::entries`]
+    pub fn len(&self) -> usize {
+        self.entries.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn stats(&self) -> InternerStats {
+        InternerStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path_attribute::{LocalPreference, Origin, PathAttributeValue};
+
+    fn attributes(local_pref: u32) -> Vec<PathAttribute> {
+        vec![
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::Origin(Origin::IGP),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::LocalPreference(LocalPreference::new(local_pref)),
+            )
+            .unwrap(),
+        ]
+    }
+
+    #[test]
+    fn test_intern() {
+        let mut interner = PathAttributesInterner::new();
+        let first = interner.intern(&attributes(100)).unwrap();
+        let second = interner.intern(&attributes(100)).unwrap();
+        let other = interner.intern(&attributes(200)).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(!Arc::ptr_eq(&first, &other));
+        assert_eq!(first.as_ref(), attributes(100).as_slice());
+        assert_eq!(interner.stats().hits(), 1);
+        assert_eq!(interner.stats().misses(), 2);
+        assert_eq!(interner.stats().hit_rate(), 1.0 / 3.0);
+        assert_eq!(interner.len(), 2);
+
+        // Entries are kept until purged, while the sets are freed with their
+        // last reference
+        drop(other);
+        assert_eq!(interner.len(), 2);
+        assert_eq!(interner.purge(), 1);
+        assert_eq!(interner.stats().entries(), 1);
+
+        // A freed set is interned again
+        let other = interner.intern(&attributes(200)).unwrap();
+        assert_eq!(Arc::strong_count(&other), 1);
+        assert_eq!(interner.stats().misses(), 3);
+    }
+
+    #[test]
+    fn test_intern_empty() {
+        let mut interner = PathAttributesInterner::new();
+        assert_eq!(interner.stats().hit_rate(), 0.0);
+        let first = interner.intern(&[]).unwrap();
+        let second = interner.intern(&[]).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(first.is_empty());
+    }
+}
@@ -19,23 +19,42 @@ use crate::{
     iana::BgpMessageType, notification::BgpNotificationMessage, open::BgpOpenMessage,
     route_refresh::BgpRouteRefreshMessage, update::BgpUpdateMessage,
 };
+#[cfg(feature = "serde")]
 use ::serde::{Deserialize, Serialize};
 
 pub mod capabilities;
 pub mod community;
 pub mod iana;
+#[cfg(feature = "wire")]
+pub mod intern;
+#[cfg(feature = "matcher")]
+pub mod matcher;
 pub mod nlri;
 pub mod notification;
 pub mod open;
 pub mod path_attribute;
+pub mod policy;
+pub mod reflection;
 pub mod route_refresh;
+#[cfg(any(all(test, feature = "wire"), feature = "test-vectors"))]
+pub mod test_vectors;
 pub mod update;
-#[cfg(feature = "serde")]
+#[cfg(feature = "wire")]
 pub mod wire;
 
 #[cfg(feature = "codec")]
 pub mod codec;
 
+/// Storage of the lists in BGP messages that usually carry only a handful of
+/// elements, such as path attributes, communities, and IPv4 NLRI. With the
+/// `smallvec` feature, up to `N` elements are kept inline to save a heap
+/// allocation per list, at the cost of a larger struct. Otherwise, it's a
+/// plain [`Vec`].
+#[cfg(feature = "smallvec")]
+pub(crate) type SmallList<T, const N: usize> = smallvec::SmallVec<[T; N]>;
+#[cfg(not(feature = "smallvec"))]
+pub(crate) type SmallList<T, const N: usize> = Vec<T>;
+
 /// BGP message wire format as defined by [RFC4271](https://datatracker.ietf.org/doc/html/rfc4271#section-4.1)
 /// Here we don't keep the length and type in memory. The type is inferred by
 /// the enum value, while the length is computed a serialization time.
@@ -55,7 +74,8 @@ pub mod codec;
 /// |          Length               |      Type     |
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BgpMessage {
     Open(BgpOpenMessage),
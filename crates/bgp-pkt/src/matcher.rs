@@ -0,0 +1,760 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Matchers for BGP communities, AS paths, and prefixes that are compiled once
+//! and then matched against parsed path attributes and routes.
+//!
+//! ```
+//! use netgauze_bgp_pkt::{
+//!     community::Community,
+//!     matcher::{AsPathRegex, CommunityMatcher, PrefixSet},
+//!     path_attribute::{AsPath, As4PathSegment, AsPathSegmentType},
+//! };
+//!
+//! let community = CommunityMatcher::compile("65000:*").unwrap();
+//! assert!(community.matches(&Community::new(0xfde8_0064)));
+//!
+//! let as_path = AsPathRegex::compile("_3356_").unwrap();
+//! assert!(as_path.matches(&AsPath::As4PathSegments(vec![As4PathSegment::new(
+//!     AsPathSegmentType::AsSequence,
+//!     vec![65000, 3356, 174],
+//! )])));
+//!
+//! let bogons = PrefixSet::compile(["10.0.0.0/8", "192.168.0.0/16"]).unwrap();
+//! assert!(bogons.contains(&"10.1.2.0/24".parse().unwrap()));
+//! assert!(!bogons.contains(&"11.0.0.0/8".parse().unwrap()));
+//! ```
+
+use crate::{
+    community::{Community, LargeCommunity},
+    path_attribute::{AsPath, AsPathSegmentType, PathAttribute, PathAttributeValue},
+    update::Route,
+};
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use regex::Regex;
+use roaring::RoaringBitmap;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write,
+    hash::Hash,
+    net::{Ipv4Addr, Ipv6Addr},
+    ops::RangeInclusive,
+    str::FromStr,
+};
+
+/// Error compiling a [`CommunityMatcher`], [`LargeCommunityMatcher`],
+/// [`AsPathRegex`], or [`PrefixSet`]. The value carried is the offending (part
+/// of the) pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MatcherCompileError {
+    /// Pattern doesn't have the expected number of `:` separated parts
+    InvalidFormat(String),
+
+    /// A part of the pattern is neither `*`, a number, nor a `low-high` range
+    InvalidValue(String),
+
+    /// AS path expression is not a valid regular expression
+    InvalidRegex(String),
+
+    /// Not a valid IPv4 or IPv6 prefix in the `address/length` notation
+    InvalidPrefix(String),
+}
+
+/// Matches one numeric part of a community
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ValueMatcher<T> {
+    /// `*`, matches any value
+    Any,
+    /// A number that must match exactly
+    Exact(T),
+    /// `low-high`, matches any value within the inclusive range
+    Range(T, T),
+}
+
+impl<T: PartialOrd + Copy> ValueMatcher<T> {
+    pub fn matches(&self, value: T) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Exact(expected) => value == *expected,
+            Self::Range(low, high) => *low <= value && value <= *high,
+        }
+    }
+}
+
+impl<T: FromStr + PartialOrd> ValueMatcher<T> {
+    fn compile(part: &str) -> Result<Self, MatcherCompileError> {
+        let invalid = || MatcherCompileError::InvalidValue(part.to_string());
+        if part == "*" {
+            return Ok(Self::Any);
+        }
+        match part.split_once('-') {
+            Some((low, high)) => {
+                let low = low.parse().map_err(|_| invalid())?;
+                let high = high.parse().map_err(|_| invalid())?;
+                if low > high {
+                    return Err(invalid());
+                }
+                Ok(Self::Range(low, high))
+            }
+            None => Ok(Self::Exact(part.parse().map_err(|_| invalid())?)),
+        }
+    }
+}
+
+/// Matches [RFC1997](https://datatracker.ietf.org/doc/html/rfc1997) communities
+/// against a pattern in the `ASN:VALUE` notation, where each part is either a
+/// number, `*` for any value, or an inclusive `low-high` range. For example,
+/// `65000:*` or `65000:100-199`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CommunityMatcher {
+    asn: ValueMatcher<u16>,
+    value: ValueMatcher<u16>,
+}
+
+impl CommunityMatcher {
+    pub const fn new(asn: ValueMatcher<u16>, value: ValueMatcher<u16>) -> Self {
+        Self { asn, value }
+    }
+
+    pub fn compile(pattern: &str) -> Result<Self, MatcherCompileError> {
+        let (asn, value) = pattern
+            .split_once(':')
+            .ok_or_else(|| MatcherCompileError::InvalidFormat(pattern.to_string()))?;
+        Ok(Self::new(
+            ValueMatcher::compile(asn)?,
+            ValueMatcher::compile(value)?,
+        ))
+    }
+
+    pub const fn asn(&self) -> ValueMatcher<u16> {
+        self.asn
+    }
+
+    pub const fn value(&self) -> ValueMatcher<u16> {
+        self.value
+    }
+
+    pub fn matches(&self, community: &Community) -> bool {
+        self.asn.matches(community.collection_asn())
+            && self.value.matches(community.collection_value())
+    }
+
+    /// Check if any of the communities carried in the COMMUNITIES attribute
+    /// matches
+    pub fn matches_path_attributes(&self, path_attributes: &[PathAttribute]) -> bool {
+        path_attributes.iter().any(|attr| match attr.value() {
+            PathAttributeValue::Communities(communities) => communities
+                .communities()
+                .iter()
+                .any(|community| self.matches(community)),
+            _ => false,
+        })
+    }
+}
+
+/// Matches [RFC8092](https://www.rfc-editor.org/rfc/rfc8092) large communities
+/// against a pattern in the `GLOBAL:LOCAL1:LOCAL2` notation, with the same
+/// syntax for each part as [`CommunityMatcher`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LargeCommunityMatcher {
+    global_admin: ValueMatcher<u32>,
+    local_data1: ValueMatcher<u32>,
+    local_data2: ValueMatcher<u32>,
+}
+
+impl LargeCommunityMatcher {
+    pub const fn new(
+        global_admin: ValueMatcher<u32>,
+        local_data1: ValueMatcher<u32>,
+        local_data2: ValueMatcher<u32>,
+    ) -> Self {
+        Self {
+            global_admin,
+            local_data1,
+            local_data2,
+        }
+    }
+
+    pub fn compile(pattern: &str) -> Result<Self, MatcherCompileError> {
+        let parts = pattern.split(':').collect::<Vec<_>>();
+        if let [global_admin, local_data1, local_data2] = parts[..] {
+            Ok(Self::new(
+                ValueMatcher::compile(global_admin)?,
+                ValueMatcher::compile(local_data1)?,
+                ValueMatcher::compile(local_data2)?,
+            ))
+        } else {
+            Err(MatcherCompileError::InvalidFormat(pattern.to_string()))
+        }
+    }
+
+    pub const fn global_admin(&self) -> ValueMatcher<u32> {
+        self.global_admin
+    }
+
+    pub const fn local_data1(&self) -> ValueMatcher<u32> {
+        self.local_data1
+    }
+
+    pub const fn local_data2(&self) -> ValueMatcher<u32> {
+        self.local_data2
+    }
+
+    pub fn matches(&self, community: &LargeCommunity) -> bool {
+        self.global_admin.matches(community.global_admin())
+            && self.local_data1.matches(community.local_data1())
+            && self.local_data2.matches(community.local_data2())
+    }
+
+    /// Check if any of the communities carried in the LARGE_COMMUNITIES
+    /// attribute matches
+    pub fn matches_path_attributes(&self, path_attributes: &[PathAttribute]) -> bool {
+        path_attributes.iter().any(|attr| match attr.value() {
+            PathAttributeValue::LargeCommunities(communities) => communities
+                .communities()
+                .iter()
+                .any(|community| self.matches(community)),
+            _ => false,
+        })
+    }
+}
+
+/// Regular expression over the AS path, using the syntax common in router
+/// configurations: `_` matches an AS number boundary (start or end of the
+/// path, a space, or an AS_SET delimiter) and everything else is a regular
+/// expression. For instance, `_3356_` matches any path traversing AS3356 and
+/// `^65000_` matches paths learned from AS65000.
+///
+/// The AS path is matched in its textual form, AS_SEQUENCE numbers are
+/// separated by a space and AS_SETs are written as `{1,2,3}`.
+#[derive(Debug, Clone)]
+pub struct AsPathRegex {
+    pattern: String,
+    regex: Regex,
+}
+
+/// Expansion of `_` in [`AsPathRegex`]
+const AS_PATH_BOUNDARY: &str = "(?:^|$|[ ,{}])";
+
+impl AsPathRegex {
+    pub fn compile(pattern: &str) -> Result<Self, MatcherCompileError> {
+        let mut expanded = String::with_capacity(pattern.len());
+        let mut escaped = false;
+        let mut in_class = false;
+        for c in pattern.chars() {
+            match c {
+                '_' if !escaped && !in_class => expanded.push_str(AS_PATH_BOUNDARY),
+                '[' if !escaped => {
+                    in_class = true;
+                    expanded.push(c);
+                }
+                ']' if !escaped => {
+                    in_class = false;
+                    expanded.push(c);
+                }
+                _ => expanded.push(c),
+            }
+            escaped = c == '\\' && !escaped;
+        }
+        let regex = Regex::new(&expanded)
+            .map_err(|err| MatcherCompileError::InvalidRegex(err.to_string()))?;
+        Ok(Self {
+            pattern: pattern.to_string(),
+            regex,
+        })
+    }
+
+    /// The expression as given to [`AsPathRegex::compile`]
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn matches(&self, as_path: &AsPath) -> bool {
+        self.regex.is_match(&as_path_string(as_path))
+    }
+
+    /// Check the AS_PATH attribute, paths without AS_PATH are matched as an
+    /// empty path.
+    pub fn matches_path_attributes(&self, path_attributes: &[PathAttribute]) -> bool {
+        path_attributes
+            .iter()
+            .find_map(|attr| match attr.value() {
+                PathAttributeValue::AsPath(as_path) => Some(self.matches(as_path)),
+                _ => None,
+            })
+            .unwrap_or_else(|| self.regex.is_match(""))
+    }
+}
+
+impl PartialEq for AsPathRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+    }
+}
+
+impl Eq for AsPathRegex {}
+
+/// Textual representation of the AS path that [`AsPathRegex`] is matched
+/// against
+fn as_path_string(as_path: &AsPath) -> String {
+    fn push_segment<T: std::fmt::Display>(
+        out: &mut String,
+        segment_type: AsPathSegmentType,
+        as_numbers: &[T],
+    ) {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        let separator = match segment_type {
+            AsPathSegmentType::AsSet => {
+                out.push('{');
+                ','
+            }
+            AsPathSegmentType::AsSequence => ' ',
+        };
+        for (i, asn) in as_numbers.iter().enumerate() {
+            if i > 0 {
+                out.push(separator);
+            }
+            // Writing to a String never fails
+            let _ = write!(out, "{asn}");
+        }
+        if segment_type == AsPathSegmentType::AsSet {
+            out.push('}');
+        }
+    }
+
+    let mut out = String::new();
+    match as_path {
+        AsPath::As2PathSegments(segments) => {
+            for segment in segments {
+                push_segment(&mut out, segment.segment_type(), segment.as_numbers());
+            }
+        }
+        AsPath::As4PathSegments(segments) => {
+            for segment in segments {
+                push_segment(&mut out, segment.segment_type(), segment.as_numbers());
+            }
+        }
+    }
+    out
+}
+
+/// Set of IPv4 and IPv6 prefixes for fast membership tests of routes against
+/// large prefix lists, such as bogons or customer prefixes. A prefix is
+/// matched when it's equal to or more specific than a prefix in the set.
+///
+/// IPv4 prefixes of length 24 and shorter are also kept as a bitmap of the
+/// `/24` blocks they cover, hence matching the typical `/24` or longer IPv4
+/// route takes a single bitmap lookup. Otherwise, the prefixes are looked up
+/// in a hash set per prefix length.
+///
+/// With the `serde` feature, the set is serialized as the sorted list of its
+/// prefixes.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(into = "Vec<IpNet>", from = "Vec<IpNet>")
+)]
+pub struct PrefixSet {
+    v4_blocks: RoaringBitmap,
+    v4: PrefixLengthSets<u32>,
+    v6: PrefixLengthSets<u128>,
+}
+
+impl PrefixSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compile a set from prefixes in the `address/length` notation
+    pub fn compile<'a>(
+        prefixes: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, MatcherCompileError> {
+        let mut set = Self::new();
+        for prefix in prefixes {
+            let prefix = IpNet::from_str(prefix.trim())
+                .map_err(|_| MatcherCompileError::InvalidPrefix(prefix.to_string()))?;
+            set.insert(prefix);
+        }
+        Ok(set)
+    }
+
+    /// Add a prefix to the set, host bits of the address are ignored. Returns
+    /// `false` if the prefix is already in the set.
+    pub fn insert(&mut self, prefix: IpNet) -> bool {
+        match prefix {
+            IpNet::V4(prefix) => {
+                let network = u32::from(prefix.network());
+                let len = prefix.prefix_len();
+                if len <= 24 {
+                    let first = network >> 8;
+                    self.v4_blocks
+                        .insert_range(first..=first + ((1 << (24 - len)) - 1));
+                }
+                self.v4.insert(len, network)
+            }
+            IpNet::V6(prefix) => self
+                .v6
+                .insert(prefix.prefix_len(), u128::from(prefix.network())),
+        }
+    }
+
+    /// Number of prefixes in the set
+    pub fn len(&self) -> usize {
+        self.v4.len() + self.v6.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Prefixes of the set, in no particular order
+    pub fn prefixes(&self) -> impl Iterator<Item = IpNet> + '_ {
+        let v4 = self.v4.iter().map(|(len, network)| {
+            IpNet::V4(Ipv4Net::new(Ipv4Addr::from(network), len).expect("valid IPv4 length"))
+        });
+        let v6 = self.v6.iter().map(|(len, network)| {
+            IpNet::V6(Ipv6Net::new(Ipv6Addr::from(network), len).expect("valid IPv6 length"))
+        });
+        v4.chain(v6)
+    }
+
+    /// Check if the prefix is equal to or more specific than a prefix in the
+    /// set
+    pub fn contains(&self, prefix: &IpNet) -> bool {
+        match prefix {
+            IpNet::V4(prefix) => self.contains_v4(prefix),
+            IpNet::V6(prefix) => self.contains_v6(prefix),
+        }
+    }
+
+    fn contains_v4(&self, prefix: &Ipv4Net) -> bool {
+        let address = u32::from(prefix.network());
+        let len = prefix.prefix_len();
+        let lengths = if len >= 24 {
+            // Prefixes up to /24 are all accounted for in the bitmap
+            if self.v4_blocks.contains(address >> 8) {
+                return true;
+            }
+            if len == 24 {
+                return false;
+            }
+            25..=len
+        } else {
+            0..=len
+        };
+        self.v4.contains(lengths, |len| mask_v4(address, len))
+    }
+
+    fn contains_v6(&self, prefix: &Ipv6Net) -> bool {
+        let address = u128::from(prefix.network());
+        self.v6
+            .contains(0..=prefix.prefix_len(), |len| mask_v6(address, len))
+    }
+
+    /// Check the IP prefix of the route, routes that don't carry an IP prefix,
+    /// such as EVPN, never match
+    pub fn matches_route(&self, route: &Route<'_>) -> bool {
+        route
+            .prefix()
+            .ip_net()
+            .map(|prefix| self.contains(&prefix))
+            .unwrap_or(false)
+    }
+}
+
+impl Extend<IpNet> for PrefixSet {
+    fn extend<T: IntoIterator<Item = IpNet>>(&mut self, iter: T) {
+        for prefix in iter {
+            self.insert(prefix);
+        }
+    }
+}
+
+impl FromIterator<IpNet> for PrefixSet {
+    fn from_iter<T: IntoIterator<Item = IpNet>>(iter: T) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Eq for PrefixSet {}
+
+impl From<Vec<IpNet>> for PrefixSet {
+    fn from(prefixes: Vec<IpNet>) -> Self {
+        prefixes.into_iter().collect()
+    }
+}
+
+impl From<PrefixSet> for Vec<IpNet> {
+    fn from(set: PrefixSet) -> Self {
+        let mut prefixes = set.prefixes().collect::<Vec<_>>();
+        prefixes.sort();
+        prefixes
+    }
+}
+
+/// Networks of a [`PrefixSet`] grouped by their prefix length
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PrefixLengthSets<T: Eq + Hash> {
+    networks: BTreeMap<u8, HashSet<T>>,
+}
+
+impl<T: Eq + Hash> Default for PrefixLengthSets<T> {
+    fn default() -> Self {
+        Self {
+            networks: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: Eq + Hash> PrefixLengthSets<T> {
+    fn insert(&mut self, len: u8, network: T) -> bool {
+        self.networks.entry(len).or_default().insert(network)
+    }
+
+    fn len(&self) -> usize {
+        self.networks.values().map(HashSet::len).sum()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (u8, T)> + '_
+    where
+        T: Copy,
+    {
+        self.networks
+            .iter()
+            .flat_map(|(len, networks)| networks.iter().map(|network| (*len, *network)))
+    }
+
+    /// Check if any of the given lengths has the network returned by `masked`
+    /// for that length
+    fn contains(&self, lengths: RangeInclusive<u8>, masked: impl Fn(u8) -> T) -> bool {
+        self.networks
+            .range(lengths)
+            .any(|(len, networks)| networks.contains(&masked(*len)))
+    }
+}
+
+const fn mask_v4(address: u32, len: u8) -> u32 {
+    match u32::MAX.checked_shl(32 - len as u32) {
+        Some(mask) => address & mask,
+        None => 0,
+    }
+}
+
+const fn mask_v6(address: u128, len: u8) -> u128 {
+    match u128::MAX.checked_shl(128 - len as u32) {
+        Some(mask) => address & mask,
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+        path_attribute::{As2PathSegment, As4PathSegment, Communities, Origin},
+        BgpUpdateMessage,
+    };
+
+    #[test]
+    fn test_community_matcher() {
+        let any_value = CommunityMatcher::compile("65000:*").unwrap();
+        let range = CommunityMatcher::compile("*:100-199").unwrap();
+        let exact = CommunityMatcher::compile("65000:100").unwrap();
+        assert_eq!(
+            any_value,
+            CommunityMatcher::new(ValueMatcher::Exact(65000), ValueMatcher::Any)
+        );
+        assert_eq!(range.value(), ValueMatcher::Range(100, 199));
+
+        let community = Community::new((65000 << 16) | 100);
+        let other = Community::new((65001 << 16) | 200);
+        assert!(any_value.matches(&community));
+        assert!(!any_value.matches(&other));
+        assert!(range.matches(&community));
+        assert!(!range.matches(&other));
+        assert!(exact.matches(&community));
+
+        let attrs = vec![
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::Origin(Origin::IGP),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                true,
+                true,
+                false,
+                false,
+                PathAttributeValue::Communities(Communities::new(vec![other, community])),
+            )
+            .unwrap(),
+        ];
+        assert!(exact.matches_path_attributes(&attrs));
+        assert!(!CommunityMatcher::compile("1:1")
+            .unwrap()
+            .matches_path_attributes(&attrs));
+    }
+
+    #[test]
+    fn test_community_matcher_errors() {
+        assert_eq!(
+            CommunityMatcher::compile("65000"),
+            Err(MatcherCompileError::InvalidFormat("65000".to_string()))
+        );
+        assert_eq!(
+            CommunityMatcher::compile("65000:x"),
+            Err(MatcherCompileError::InvalidValue("x".to_string()))
+        );
+        assert_eq!(
+            CommunityMatcher::compile("70000:1"),
+            Err(MatcherCompileError::InvalidValue("70000".to_string()))
+        );
+        assert_eq!(
+            CommunityMatcher::compile("1:200-100"),
+            Err(MatcherCompileError::InvalidValue("200-100".to_string()))
+        );
+        assert_eq!(
+            LargeCommunityMatcher::compile("1:2"),
+            Err(MatcherCompileError::InvalidFormat("1:2".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_large_community_matcher() {
+        let matcher = LargeCommunityMatcher::compile("4200000000:*:1-10").unwrap();
+        assert!(matcher.matches(&LargeCommunity::new(4200000000, 7, 5)));
+        assert!(!matcher.matches(&LargeCommunity::new(4200000000, 7, 11)));
+        assert!(!matcher.matches(&LargeCommunity::new(65000, 7, 5)));
+    }
+
+    #[test]
+    fn test_as_path_string() {
+        let as_path = AsPath::As4PathSegments(vec![
+            As4PathSegment::new(AsPathSegmentType::AsSequence, vec![65000, 3356]),
+            As4PathSegment::new(AsPathSegmentType::AsSet, vec![174, 1299]),
+        ]);
+        assert_eq!(as_path_string(&as_path), "65000 3356 {174,1299}");
+        let as_path = AsPath::As2PathSegments(vec![As2PathSegment::new(
+            AsPathSegmentType::AsSequence,
+            vec![100, 200],
+        )]);
+        assert_eq!(as_path_string(&as_path), "100 200");
+    }
+
+    #[test]
+    fn test_as_path_regex() {
+        let as_path = AsPath::As4PathSegments(vec![
+            As4PathSegment::new(AsPathSegmentType::AsSequence, vec![65000, 3356]),
+            As4PathSegment::new(AsPathSegmentType::AsSet, vec![174, 1299]),
+        ]);
+        assert!(AsPathRegex::compile("_3356_").unwrap().matches(&as_path));
+        assert!(AsPathRegex::compile("_174_").unwrap().matches(&as_path));
+        assert!(AsPathRegex::compile("^65000_").unwrap().matches(&as_path));
+        assert!(AsPathRegex::compile("_65000_3356_")
+            .unwrap()
+            .matches(&as_path));
+        assert!(!AsPathRegex::compile("_335_").unwrap().matches(&as_path));
+        assert!(!AsPathRegex::compile("^3356_").unwrap().matches(&as_path));
+        assert!(AsPathRegex::compile("_6500[0-9]_")
+            .unwrap()
+            .matches(&as_path));
+
+        let empty = AsPathRegex::compile("^$").unwrap();
+        assert!(empty.matches(&AsPath::As4PathSegments(vec![])));
+        assert!(empty.matches_path_attributes(&[]));
+        assert!(matches!(
+            AsPathRegex::compile("_(3356_"),
+            Err(MatcherCompileError::InvalidRegex(_))
+        ));
+    }
+
+    #[test]
+    fn test_prefix_set() {
+        let set = PrefixSet::compile([
+            "10.0.0.0/8",
+            "192.168.1.0/24",
+            "192.0.2.128/25",
+            "0.0.0.0/32",
+            "2001:db8::/32",
+        ])
+        .unwrap();
+        let contains = |prefix: &str| set.contains(&IpNet::from_str(prefix).unwrap());
+        assert_eq!(set.len(), 5);
+
+        assert!(contains("10.0.0.0/8"));
+        assert!(contains("10.1.0.0/16"));
+        assert!(contains("10.255.255.0/24"));
+        assert!(contains("10.1.2.3/32"));
+        assert!(!contains("10.0.0.0/7"));
+        assert!(!contains("11.0.0.0/24"));
+
+        assert!(contains("192.168.1.0/24"));
+        assert!(contains("192.168.1.128/26"));
+        assert!(!contains("192.168.0.0/16"));
+        assert!(!contains("192.168.2.0/24"));
+
+        assert!(contains("192.0.2.128/25"));
+        assert!(contains("192.0.2.200/32"));
+        assert!(!contains("192.0.2.0/24"));
+        assert!(!contains("192.0.2.0/25"));
+
+        assert!(contains("0.0.0.0/32"));
+        assert!(!contains("0.0.0.0/0"));
+
+        assert!(contains("2001:db8:1::/48"));
+        assert!(!contains("2001:db9::/32"));
+        assert!(!contains("2001::/16"));
+
+        // The default route matches everything
+        let default = PrefixSet::compile(["0.0.0.0/0", "::/0"]).unwrap();
+        assert!(default.contains(&IpNet::from_str("203.0.113.0/24").unwrap()));
+        assert!(default.contains(&IpNet::from_str("1.2.3.4/32").unwrap()));
+        assert!(default.contains(&IpNet::from_str("2001:db8::/64").unwrap()));
+    }
+
+    #[test]
+    fn test_prefix_set_routes() {
+        let nlri = [Ipv4UnicastAddress::new_no_path_id(
+            Ipv4Unicast::from_net(Ipv4Net::from_str("10.1.0.0/16").unwrap()).unwrap(),
+        )];
+        let update = BgpUpdateMessage::new(vec![], vec![], nlri.to_vec());
+        let set: PrefixSet = [IpNet::from_str("10.0.0.0/8").unwrap()]
+            .into_iter()
+            .collect();
+        assert!(update.announced().all(|route| set.matches_route(&route)));
+        assert!(!PrefixSet::new().matches_route(&update.announced().next().unwrap()));
+        assert_eq!(
+            PrefixSet::compile(["10.0.0.0/33"]),
+            Err(MatcherCompileError::InvalidPrefix(
+                "10.0.0.0/33".to_string()
+            ))
+        );
+    }
+}
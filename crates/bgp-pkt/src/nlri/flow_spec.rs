@@ -0,0 +1,316 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::iana::FlowSpecComponentType;
+use ipnet::{Ipv4Net, Ipv6Net};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Flow Specification NLRI, a filter matching the packets of a traffic flow
+/// by a list of components ordered by their type. The actions applied to the
+/// matching traffic are carried in the extended communities, see
+/// [`crate::community::FlowSpecAction`].
+///
+/// ```text
+/// +-------------------------------+
+/// |    length (0xnn or 0xfnnn)    |
+/// +-------------------------------+
+/// |    NLRI value   (variable)    |
+/// +-------------------------------+
+/// ```
+///
+/// See [RFC8955](https://datatracker.ietf.org/doc/html/rfc8955) for IPv4 and
+/// [RFC8956](https://datatracker.ietf.org/doc/html/rfc8956) for IPv6
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct FlowSpecNlri {
+    components: Vec<FlowSpecComponent>,
+}
+
+impl FlowSpecNlri {
+    /// The components are sorted by their type, as required on the wire
+    pub fn new(mut components: Vec<FlowSpecComponent>) -> Self {
+        components.sort_by_key(FlowSpecComponent::component_type);
+        Self { components }
+    }
+
+    pub fn components(&self) -> &[FlowSpecComponent] {
+        &self.components
+    }
+
+    /// Destination prefix component, if any
+    pub fn destination_prefix(&self) -> Option<&FlowSpecPrefix> {
+        self.components
+            .iter()
+            .find_map(|component| match component {
+                FlowSpecComponent::DestinationPrefix(prefix) => Some(prefix),
+                _ => None,
+            })
+    }
+
+    /// Source prefix component, if any
+    pub fn source_prefix(&self) -> Option<&FlowSpecPrefix> {
+        self.components
+            .iter()
+            .find_map(|component| match component {
+                FlowSpecComponent::SourcePrefix(prefix) => Some(prefix),
+                _ => None,
+            })
+    }
+}
+
+/// A single match criteria of a [`FlowSpecNlri`]
+///
+/// ```text
+/// +--------------------+--------------------+
+/// | type (1 octet)     | value (variable)   |
+/// +--------------------+--------------------+
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub enum FlowSpecComponent {
+    DestinationPrefix(FlowSpecPrefix),
+    SourcePrefix(FlowSpecPrefix),
+    /// IP protocol for IPv4, and the upper layer protocol for IPv6
+    IpProtocol(Vec<NumericOperation>),
+    /// Source or destination TCP/UDP port
+    Port(Vec<NumericOperation>),
+    DestinationPort(Vec<NumericOperation>),
+    SourcePort(Vec<NumericOperation>),
+    IcmpType(Vec<NumericOperation>),
+    IcmpCode(Vec<NumericOperation>),
+    TcpFlags(Vec<BitmaskOperation>),
+    /// Total IP packet length, excluding the layer 2 header
+    PacketLength(Vec<NumericOperation>),
+    /// 6-bit DiffServ Code Point
+    Dscp(Vec<NumericOperation>),
+    /// Fragmentation bits, see [`FlowSpecComponent::FRAGMENT_DONT_FRAGMENT`]
+    /// and the related constants
+    Fragment(Vec<BitmaskOperation>),
+    /// 20-bit IPv6 flow label
+    FlowLabel(Vec<NumericOperation>),
+}
+
+impl FlowSpecComponent {
+    /// Don't Fragment (DF) bit of the [`FlowSpecComponent::Fragment`]
+    /// component, IPv4 only
+    pub const FRAGMENT_DONT_FRAGMENT: u64 = 0x01;
+    /// Is a Fragment other than the first (IsF) bit of the
+    /// [`FlowSpecComponent::Fragment`] component
+    pub const FRAGMENT_IS_FRAGMENT: u64 = 0x02;
+    /// First Fragment (FF) bit of the [`FlowSpecComponent::Fragment`]
+    /// component
+    pub const FRAGMENT_FIRST_FRAGMENT: u64 = 0x04;
+    /// Last Fragment (LF) bit of the [`FlowSpecComponent::Fragment`] component
+    pub const FRAGMENT_LAST_FRAGMENT: u64 = 0x08;
+
+    pub const fn component_type(&self) -> FlowSpecComponentType {
+        match self {
+            Self::DestinationPrefix(_) => FlowSpecComponentType::DestinationPrefix,
+            Self::SourcePrefix(_) => FlowSpecComponentType::SourcePrefix,
+            Self::IpProtocol(_) => FlowSpecComponentType::IpProtocol,
+            Self::Port(_) => FlowSpecComponentType::Port,
+            Self::DestinationPort(_) => FlowSpecComponentType::DestinationPort,
+            Self::SourcePort(_) => FlowSpecComponentType::SourcePort,
+            Self::IcmpType(_) => FlowSpecComponentType::IcmpType,
+            Self::IcmpCode(_) => FlowSpecComponentType::IcmpCode,
+            Self::TcpFlags(_) => FlowSpecComponentType::TcpFlags,
+            Self::PacketLength(_) => FlowSpecComponentType::PacketLength,
+            Self::Dscp(_) => FlowSpecComponentType::Dscp,
+            Self::Fragment(_) => FlowSpecComponentType::Fragment,
+            Self::FlowLabel(_) => FlowSpecComponentType::FlowLabel,
+        }
+    }
+}
+
+/// Prefix matched by the destination and source prefix components.
+///
+/// IPv6 prefixes match only the bits starting at `offset`, the bits before the
+/// offset are always zero.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub enum FlowSpecPrefix {
+    Ipv4(#[cfg_attr(feature = "fuzz", arbitrary(with = crate::arbitrary_ipv4net))] Ipv4Net),
+    Ipv6 {
+        #[cfg_attr(feature = "fuzz", arbitrary(with = crate::arbitrary_ipv6net))]
+        prefix: Ipv6Net,
+        offset: u8,
+    },
+}
+
+/// Width of the value of a [`NumericOperation`] or [`BitmaskOperation`], it's
+/// kept as received to encode back the same value on the wire
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub enum OperationValue {
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+}
+
+impl OperationValue {
+    /// Encode the value in the fewest octets
+    pub const fn new(value: u64) -> Self {
+        if value <= u8::MAX as u64 {
+            Self::U8(value as u8)
+        } else if value <= u16::MAX as u64 {
+            Self::U16(value as u16)
+        } else if value <= u32::MAX as u64 {
+            Self::U32(value as u32)
+        } else {
+            Self::U64(value)
+        }
+    }
+
+    pub const fn value(&self) -> u64 {
+        match self {
+            Self::U8(value) => *value as u64,
+            Self::U16(value) => *value as u64,
+            Self::U32(value) => *value as u64,
+            Self::U64(value) => *value,
+        }
+    }
+}
+
+/// Numeric comparison of a component value, combined with the previous
+/// operation of the list with a logical AND when `and` is set, or otherwise a
+/// logical OR.
+///
+/// ```text
+///   0   1   2   3   4   5   6   7
+/// +---+---+---+---+---+---+---+---+
+/// | e | a |  len  | 0 |lt |gt |eq |
+/// +---+---+---+---+---+---+---+---+
+/// ```
+/// The end-of-list bit `e` is set on the last operation of the component.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct NumericOperation {
+    and: bool,
+    lt: bool,
+    gt: bool,
+    eq: bool,
+    value: OperationValue,
+}
+
+impl NumericOperation {
+    pub const fn new(and: bool, lt: bool, gt: bool, eq: bool, value: OperationValue) -> Self {
+        Self {
+            and,
+            lt,
+            gt,
+            eq,
+            value,
+        }
+    }
+
+    /// Matches `value` exactly
+    pub const fn equal(and: bool, value: u64) -> Self {
+        Self::new(and, false, false, true, OperationValue::new(value))
+    }
+
+    pub const fn and(&self) -> bool {
+        self.and
+    }
+
+    pub const fn lt(&self) -> bool {
+        self.lt
+    }
+
+    pub const fn gt(&self) -> bool {
+        self.gt
+    }
+
+    pub const fn eq(&self) -> bool {
+        self.eq
+    }
+
+    pub const fn value(&self) -> OperationValue {
+        self.value
+    }
+
+    /// Evaluate the comparison for the value of a packet
+    pub const fn matches(&self, value: u64) -> bool {
+        let operand = self.value.value();
+        (self.lt && value < operand)
+            || (self.gt && value > operand)
+            || (self.eq && value == operand)
+    }
+}
+
+/// Bitwise match of a component value, combined with the previous operation
+/// of the list with a logical AND when `and` is set, or otherwise a logical
+/// OR. The bits of the value are all set in the packet when `matched` is set,
+/// or any of them otherwise, and the result is negated when `not` is set.
+///
+/// ```text
+///   0   1   2   3   4   5   6   7
+/// +---+---+---+---+---+---+---+---+
+/// | e | a |  len  | 0 | 0 |not| m |
+/// +---+---+---+---+---+---+---+---+
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct BitmaskOperation {
+    and: bool,
+    not: bool,
+    matched: bool,
+    value: OperationValue,
+}
+
+impl BitmaskOperation {
+    pub const fn new(and: bool, not: bool, matched: bool, value: OperationValue) -> Self {
+        Self {
+            and,
+            not,
+            matched,
+            value,
+        }
+    }
+
+    pub const fn and(&self) -> bool {
+        self.and
+    }
+
+    pub const fn not(&self) -> bool {
+        self.not
+    }
+
+    pub const fn matched(&self) -> bool {
+        self.matched
+    }
+
+    pub const fn value(&self) -> OperationValue {
+        self.value
+    }
+
+    /// Evaluate the bitmask for the bits of a packet
+    pub const fn matches(&self, bits: u64) -> bool {
+        let mask = self.value.value();
+        let result = if self.matched {
+            bits & mask == mask
+        } else {
+            bits & mask != 0
+        };
+        result != self.not
+    }
+}
@@ -14,9 +14,11 @@
 // limitations under the License.
 
 mod bgp_ls;
+mod flow_spec;
 
 #[allow(clippy::module_inception)]
 mod nlri;
 
 pub use bgp_ls::*;
+pub use flow_spec::*;
 pub use nlri::*;
@@ -16,9 +16,13 @@
 //! Data types to represent various Network Layer Reachability Information
 //! (`NLRI`)
 
-use crate::iana::{L2EvpnRouteTypeCode, RouteDistinguisherTypeCode};
-use ipnet::{Ipv4Net, Ipv6Net};
+use crate::iana::{
+    EthernetSegmentIdentifierType, L2EvpnRouteTypeCode, RouteDistinguisherTypeCode,
+    UndefinedEthernetSegmentIdentifierType,
+};
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
 use netgauze_iana::address_family::AddressType;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
@@ -27,8 +31,147 @@ pub trait NlriAddressType {
     fn address_type() -> AddressType;
 }
 
+/// How IP prefixes with bits set beyond the prefix length, e.g.,
+/// `192.0.2.1/24`, are handled. Such prefixes are semantically invalid but
+/// appear in real feeds.
+///
+/// The policy is applied when parsing, see
+/// [`crate::wire::deserializer::BgpParsingContext::host_bits_policy`], and by
+/// [`crate::update::BgpUpdateMessage::new_with_host_bits_policy`]. The other
+/// constructors, e.g., [`Ipv4Unicast::from_net`], keep the prefixes as is.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HostBitsPolicy {
+    /// Keep the prefix as is
+    #[default]
+    Accept,
+
+    /// Clear the host bits, e.g., `192.0.2.1/24` becomes `192.0.2.0/24`
+    Normalize,
+
+    /// Reject the prefix
+    Reject,
+}
+
+impl HostBitsPolicy {
+    /// Apply the policy to a single prefix, the prefix is returned as error
+    /// when it's rejected
+    pub fn apply(self, net: IpNet) -> Result<IpNet, IpNet> {
+        match self {
+            _ if net.trunc() == net => Ok(net),
+            Self::Accept => Ok(net),
+            Self::Normalize => Ok(net.trunc()),
+            Self::Reject => Err(net),
+        }
+    }
+}
+
+/// NLRI carrying an IP prefix that can have host bits set
+pub(crate) trait NormalizeHostBits {
+    /// Clear the bits beyond the prefix length, the original prefix is
+    /// returned if any was set
+    fn normalize_host_bits(&mut self) -> Option<IpNet>;
+}
+
+/// Normalize all the prefixes, returning the original ones that had host bits
+/// set
+pub(crate) fn normalize_host_bits<T: NormalizeHostBits>(nlri: &mut [T]) -> Vec<IpNet> {
+    nlri.iter_mut()
+        .filter_map(NormalizeHostBits::normalize_host_bits)
+        .collect()
+}
+
+impl NormalizeHostBits for Ipv4Net {
+    fn normalize_host_bits(&mut self) -> Option<IpNet> {
+        let original = *self;
+        *self = original.trunc();
+        (original != *self).then_some(IpNet::V4(original))
+    }
+}
+
+impl NormalizeHostBits for Ipv6Net {
+    fn normalize_host_bits(&mut self) -> Option<IpNet> {
+        let original = *self;
+        *self = original.trunc();
+        (original != *self).then_some(IpNet::V6(original))
+    }
+}
+
+impl NormalizeHostBits for Ipv4Unicast {
+    fn normalize_host_bits(&mut self) -> Option<IpNet> {
+        self.0.normalize_host_bits()
+    }
+}
+
+impl NormalizeHostBits for Ipv4Multicast {
+    fn normalize_host_bits(&mut self) -> Option<IpNet> {
+        self.0.normalize_host_bits()
+    }
+}
+
+impl NormalizeHostBits for Ipv6Unicast {
+    fn normalize_host_bits(&mut self) -> Option<IpNet> {
+        self.0.normalize_host_bits()
+    }
+}
+
+impl NormalizeHostBits for Ipv6Multicast {
+    fn normalize_host_bits(&mut self) -> Option<IpNet> {
+        self.0.normalize_host_bits()
+    }
+}
+
+impl NormalizeHostBits for Ipv4UnicastAddress {
+    fn normalize_host_bits(&mut self) -> Option<IpNet> {
+        self.network.normalize_host_bits()
+    }
+}
+
+impl NormalizeHostBits for Ipv4MulticastAddress {
+    fn normalize_host_bits(&mut self) -> Option<IpNet> {
+        self.network.normalize_host_bits()
+    }
+}
+
+impl NormalizeHostBits for Ipv4MplsVpnUnicastAddress {
+    fn normalize_host_bits(&mut self) -> Option<IpNet> {
+        self.network.normalize_host_bits()
+    }
+}
+
+impl NormalizeHostBits for Ipv6UnicastAddress {
+    fn normalize_host_bits(&mut self) -> Option<IpNet> {
+        self.network.normalize_host_bits()
+    }
+}
+
+impl NormalizeHostBits for Ipv6MulticastAddress {
+    fn normalize_host_bits(&mut self) -> Option<IpNet> {
+        self.network.normalize_host_bits()
+    }
+}
+
+impl NormalizeHostBits for Ipv6MplsVpnUnicastAddress {
+    fn normalize_host_bits(&mut self) -> Option<IpNet> {
+        self.network.normalize_host_bits()
+    }
+}
+
+impl NormalizeHostBits for Ipv4NlriMplsLabelsAddress {
+    fn normalize_host_bits(&mut self) -> Option<IpNet> {
+        self.prefix.normalize_host_bits()
+    }
+}
+
+impl NormalizeHostBits for Ipv6NlriMplsLabelsAddress {
+    fn normalize_host_bits(&mut self) -> Option<IpNet> {
+        self.prefix.normalize_host_bits()
+    }
+}
+
 /// Temporary representation of MPLS Labels
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct MplsLabel([u8; 3]);
 
@@ -52,7 +195,8 @@ impl MplsLabel {
 /// Route Distinguisher (RD) is a 8-byte value and encoded as follows:
 ///     - Type Field: 2 bytes
 ///     - Value Field: 6 bytes
-#[derive(Hash, Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Hash, Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum RouteDistinguisher {
     /// The Value field consists of two subfields:
@@ -117,7 +261,8 @@ impl From<RouteDistinguisher> for u64 {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct LabeledIpv4NextHop {
     rd: RouteDistinguisher,
@@ -139,7 +284,8 @@ impl LabeledIpv4NextHop {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct LabeledIpv6NextHop {
     rd: RouteDistinguisher,
@@ -175,7 +321,8 @@ impl LabeledIpv6NextHop {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum LabeledNextHop {
     Ipv4(LabeledIpv4NextHop),
@@ -200,14 +347,16 @@ impl LabeledNextHop {
 
 /// A more restricted version of [`Ipv4Net`] that allows only unicast
 /// networks
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Ipv4Unicast(
     #[cfg_attr(feature = "fuzz", arbitrary(with = crate::arbitrary_ipv4net))] Ipv4Net,
 );
 
 /// Raised when the network is not a unicast range
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 
 pub struct InvalidIpv4UnicastNetwork(
@@ -215,6 +364,7 @@ pub struct InvalidIpv4UnicastNetwork(
 );
 
 impl Ipv4Unicast {
+    /// Host bits are kept, see [`HostBitsPolicy`]
     pub fn from_net(net: Ipv4Net) -> Result<Self, InvalidIpv4UnicastNetwork> {
         if net.addr().is_broadcast() || net.addr().is_multicast() {
             return Err(InvalidIpv4UnicastNetwork(net));
@@ -241,7 +391,8 @@ impl TryFrom<Ipv4Net> for Ipv4Unicast {
 }
 
 /// Ipv4 Network address in NLRI
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Ipv4UnicastAddress {
     path_id: Option<u32>,
@@ -275,7 +426,8 @@ impl NlriAddressType for Ipv4UnicastAddress {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Ipv4MplsVpnUnicastAddress {
     path_id: Option<u32>,
@@ -337,13 +489,15 @@ impl NlriAddressType for Ipv4MplsVpnUnicastAddress {
 
 /// A more restricted version of [`Ipv4Net`] that allows only multicast
 /// networks
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Ipv4Multicast(
     #[cfg_attr(feature = "fuzz", arbitrary(with = crate::arbitrary_ipv4net))] Ipv4Net,
 );
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct InvalidIpv4MulticastNetwork(
     #[cfg_attr(feature = "fuzz", arbitrary(with = crate::arbitrary_ipv4net))] pub Ipv4Net,
@@ -372,7 +526,8 @@ impl TryFrom<Ipv4Net> for Ipv4Multicast {
 }
 
 /// Ipv4 Multicast Network address in NLRI
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Ipv4MulticastAddress {
     path_id: Option<u32>,
@@ -408,19 +563,22 @@ impl NlriAddressType for Ipv4MulticastAddress {
 
 /// A more restricted version of [`Ipv6Net`] that allows only unicast
 /// networks
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Ipv6Unicast(
     #[cfg_attr(feature = "fuzz", arbitrary(with = crate::arbitrary_ipv6net))] Ipv6Net,
 );
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct InvalidIpv6UnicastNetwork(
     #[cfg_attr(feature = "fuzz", arbitrary(with = crate::arbitrary_ipv6net))] pub Ipv6Net,
 );
 
 impl Ipv6Unicast {
+    /// Host bits are kept, see [`HostBitsPolicy`]
     pub fn from_net(net: Ipv6Net) -> Result<Self, InvalidIpv6UnicastNetwork> {
         if net.addr().is_multicast() || net.hosts().last().map(|x| x.is_multicast()) == Some(true) {
             return Err(InvalidIpv6UnicastNetwork(net));
@@ -442,7 +600,8 @@ impl TryFrom<Ipv6Net> for Ipv6Unicast {
 }
 
 /// Ipv6 Network address in NLRI
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Ipv6UnicastAddress {
     path_id: Option<u32>,
@@ -469,7 +628,8 @@ impl NlriAddressType for Ipv6UnicastAddress {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Ipv6MplsVpnUnicastAddress {
     path_id: Option<u32>,
@@ -531,13 +691,15 @@ impl NlriAddressType for Ipv6MplsVpnUnicastAddress {
 
 /// A more restricted version of [`Ipv6Net`] that allows only multicast
 /// networks
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Ipv6Multicast(
     #[cfg_attr(feature = "fuzz", arbitrary(with = crate::arbitrary_ipv6net))] Ipv6Net,
 );
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct InvalidIpv6MulticastNetwork(
     #[cfg_attr(feature = "fuzz", arbitrary(with = crate::arbitrary_ipv6net))] pub Ipv6Net,
@@ -570,7 +732,8 @@ impl TryFrom<Ipv6Net> for Ipv6Multicast {
 }
 
 /// Ipv4 Multicast Network address in NLRI
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Ipv6MulticastAddress {
     path_id: Option<u32>,
@@ -604,19 +767,213 @@ impl NlriAddressType for Ipv6MulticastAddress {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct EthernetSegmentIdentifier(pub [u8; 10]);
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+impl EthernetSegmentIdentifier {
+    /// The reserved ESI of single-homed sites
+    pub const ZERO: Self = Self([0x00; 10]);
+
+    /// The reserved ESI, named MAX-ESI, of all ones
+    pub const MAX: Self = Self([0xff; 10]);
+
+    /// The type encoded in the first octet of the ESI
+    pub const fn esi_type(
+        &self,
+    ) -> Result<EthernetSegmentIdentifierType, UndefinedEthernetSegmentIdentifierType> {
+        match EthernetSegmentIdentifierType::from_repr(self.0[0]) {
+            Some(value) => Ok(value),
+            None => Err(UndefinedEthernetSegmentIdentifierType(self.0[0])),
+        }
+    }
+
+    /// Decode the ESI value according to its type
+    pub const fn value(&self) -> EthernetSegmentIdentifierValue {
+        let v = &self.0;
+        match self.esi_type() {
+            Ok(EthernetSegmentIdentifierType::Arbitrary) => {
+                EthernetSegmentIdentifierValue::Arbitrary([
+                    v[1], v[2], v[3], v[4], v[5], v[6], v[7], v[8], v[9],
+                ])
+            }
+            Ok(EthernetSegmentIdentifierType::Lacp) => EthernetSegmentIdentifierValue::Lacp {
+                system_mac: MacAddress([v[1], v[2], v[3], v[4], v[5], v[6]]),
+                port_key: u16::from_be_bytes([v[7], v[8]]),
+                reserved: v[9],
+            },
+            Ok(EthernetSegmentIdentifierType::RootBridge) => {
+                EthernetSegmentIdentifierValue::RootBridge {
+                    mac: MacAddress([v[1], v[2], v[3], v[4], v[5], v[6]]),
+                    priority: u16::from_be_bytes([v[7], v[8]]),
+                    reserved: v[9],
+                }
+            }
+            Ok(EthernetSegmentIdentifierType::Mac) => EthernetSegmentIdentifierValue::Mac {
+                system_mac: MacAddress([v[1], v[2], v[3], v[4], v[5], v[6]]),
+                local_discriminator: u32::from_be_bytes([0, v[7], v[8], v[9]]),
+            },
+            Ok(EthernetSegmentIdentifierType::RouterId) => {
+                EthernetSegmentIdentifierValue::RouterId {
+                    router_id: Ipv4Addr::new(v[1], v[2], v[3], v[4]),
+                    local_discriminator: u32::from_be_bytes([v[5], v[6], v[7], v[8]]),
+                    reserved: v[9],
+                }
+            }
+            Ok(EthernetSegmentIdentifierType::As) => EthernetSegmentIdentifierValue::As {
+                asn: u32::from_be_bytes([v[1], v[2], v[3], v[4]]),
+                local_discriminator: u32::from_be_bytes([v[5], v[6], v[7], v[8]]),
+                reserved: v[9],
+            },
+            Err(UndefinedEthernetSegmentIdentifierType(code)) => {
+                EthernetSegmentIdentifierValue::Unknown {
+                    code,
+                    value: [v[1], v[2], v[3], v[4], v[5], v[6], v[7], v[8], v[9]],
+                }
+            }
+        }
+    }
+}
+
+/// Raised when the local discriminator of a
+/// [`EthernetSegmentIdentifierValue::Mac`] doesn't fit in 3 octets
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InvalidEthernetSegmentIdentifierValue(pub EthernetSegmentIdentifierValue);
+
+impl TryFrom<EthernetSegmentIdentifierValue> for EthernetSegmentIdentifier {
+    type Error = InvalidEthernetSegmentIdentifierValue;
+
+    fn try_from(value: EthernetSegmentIdentifierValue) -> Result<Self, Self::Error> {
+        let mut esi = [0u8; 10];
+        match value {
+            EthernetSegmentIdentifierValue::Arbitrary(value) => {
+                esi[0] = EthernetSegmentIdentifierType::Arbitrary as u8;
+                esi[1..].copy_from_slice(&value);
+            }
+            EthernetSegmentIdentifierValue::Lacp {
+                system_mac,
+                port_key,
+                reserved,
+            } => {
+                esi[0] = EthernetSegmentIdentifierType::Lacp as u8;
+                esi[1..7].copy_from_slice(&system_mac.0);
+                esi[7..9].copy_from_slice(&port_key.to_be_bytes());
+                esi[9] = reserved;
+            }
+            EthernetSegmentIdentifierValue::RootBridge {
+                mac,
+                priority,
+                reserved,
+            } => {
+                esi[0] = EthernetSegmentIdentifierType::RootBridge as u8;
+                esi[1..7].copy_from_slice(&mac.0);
+                esi[7..9].copy_from_slice(&priority.to_be_bytes());
+                esi[9] = reserved;
+            }
+            EthernetSegmentIdentifierValue::Mac {
+                local_discriminator,
+                ..
+            } if local_discriminator > 0xff_ffff => {
+                return Err(InvalidEthernetSegmentIdentifierValue(value));
+            }
+            EthernetSegmentIdentifierValue::Mac {
+                system_mac,
+                local_discriminator,
+            } => {
+                esi[0] = EthernetSegmentIdentifierType::Mac as u8;
+                esi[1..7].copy_from_slice(&system_mac.0);
+                esi[7..].copy_from_slice(&local_discriminator.to_be_bytes()[1..]);
+            }
+            EthernetSegmentIdentifierValue::RouterId {
+                router_id,
+                local_discriminator,
+                reserved,
+            } => {
+                esi[0] = EthernetSegmentIdentifierType::RouterId as u8;
+                esi[1..5].copy_from_slice(&router_id.octets());
+                esi[5..9].copy_from_slice(&local_discriminator.to_be_bytes());
+                esi[9] = reserved;
+            }
+            EthernetSegmentIdentifierValue::As {
+                asn,
+                local_discriminator,
+                reserved,
+            } => {
+                esi[0] = EthernetSegmentIdentifierType::As as u8;
+                esi[1..5].copy_from_slice(&asn.to_be_bytes());
+                esi[5..9].copy_from_slice(&local_discriminator.to_be_bytes());
+                esi[9] = reserved;
+            }
+            EthernetSegmentIdentifierValue::Unknown { code, value } => {
+                esi[0] = code;
+                esi[1..].copy_from_slice(&value);
+            }
+        }
+        Ok(Self(esi))
+    }
+}
+
+/// Decoded value of an [`EthernetSegmentIdentifier`], see
+/// [RFC7432](https://datatracker.ietf.org/doc/html/rfc7432#section-5)
+///
+/// ```text
+/// +---+---+---+---+---+---+---+---+---+---+
+/// | T |          ESI Value                |
+/// +---+---+---+---+---+---+---+---+---+---+
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum EthernetSegmentIdentifierValue {
+    Arbitrary([u8; 9]),
+    /// `reserved` is the last octet, it should be zero
+    Lacp {
+        system_mac: MacAddress,
+        port_key: u16,
+        reserved: u8,
+    },
+    /// `reserved` is the last octet, it should be zero
+    RootBridge {
+        mac: MacAddress,
+        priority: u16,
+        reserved: u8,
+    },
+    /// The local discriminator is 3 octets
+    Mac {
+        system_mac: MacAddress,
+        local_discriminator: u32,
+    },
+    /// `reserved` is the last octet, it should be zero
+    RouterId {
+        router_id: Ipv4Addr,
+        local_discriminator: u32,
+        reserved: u8,
+    },
+    /// `reserved` is the last octet, it should be zero
+    As {
+        asn: u32,
+        local_discriminator: u32,
+        reserved: u8,
+    },
+    Unknown {
+        code: u8,
+        value: [u8; 9],
+    },
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct EthernetTag(pub u32);
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct MacAddress(pub [u8; 6]);
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct L2EvpnAddress {
     path_id: Option<u32>,
@@ -637,7 +994,8 @@ impl L2EvpnAddress {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum L2EvpnRoute {
     EthernetAutoDiscovery(EthernetAutoDiscovery),
@@ -681,7 +1039,8 @@ impl NlriAddressType for L2EvpnAddress {
 /// |  MPLS Label (3 octets)                |
 /// +---------------------------------------+
 /// ```
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct EthernetAutoDiscovery {
     rd: RouteDistinguisher,
@@ -745,7 +1104,8 @@ impl EthernetAutoDiscovery {
 /// |  MPLS Label2 (0 or 3 octets)          |
 /// +---------------------------------------+
 /// ```
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct MacIpAdvertisement {
     rd: RouteDistinguisher,
@@ -823,7 +1183,8 @@ impl MacIpAdvertisement {
 /// |          (4 or 16 octets)             |
 /// +---------------------------------------+
 /// ```
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct InclusiveMulticastEthernetTagRoute {
     rd: RouteDistinguisher,
@@ -865,7 +1226,8 @@ impl InclusiveMulticastEthernetTagRoute {
 /// |          (4 or 16 octets)             |
 /// +---------------------------------------+
 /// ```
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct EthernetSegmentRoute {
     rd: RouteDistinguisher,
@@ -897,7 +1259,8 @@ impl EthernetSegmentRoute {
 }
 
 /// The BGP EVPN IPv4 or IPv6 Prefix Route
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum L2EvpnIpPrefixRoute {
     V4(L2EvpnIpv4PrefixRoute),
@@ -923,7 +1286,8 @@ pub enum L2EvpnIpPrefixRoute {
 /// |  MPLS Label (3 octets)                |
 /// +---------------------------------------+
 /// ```
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct L2EvpnIpv4PrefixRoute {
     rd: RouteDistinguisher,
@@ -997,7 +1361,8 @@ impl L2EvpnIpv4PrefixRoute {
 /// |  MPLS Label (3 octets)                |
 /// +---------------------------------------+
 /// ```
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct L2EvpnIpv6PrefixRoute {
     rd: RouteDistinguisher,
@@ -1052,7 +1417,8 @@ impl L2EvpnIpv6PrefixRoute {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct RouteTargetMembershipAddress {
     path_id: Option<u32>,
@@ -1087,7 +1453,8 @@ impl RouteTargetMembershipAddress {
 /// |                               |
 /// +-------------------------------+
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct RouteTargetMembership {
     origin_as: u32,
@@ -1120,7 +1487,8 @@ impl NlriAddressType for RouteTargetMembershipAddress {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum InvalidIpv4NlriMplsLabelsAddress {
     /// Total length should not exceed 255, each MPLS Label is 24 bit and
@@ -1159,7 +1527,8 @@ pub enum InvalidIpv4NlriMplsLabelsAddress {
 /// ~                                                               |
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Ipv4NlriMplsLabelsAddress {
     path_id: Option<u32>,
@@ -1215,7 +1584,8 @@ impl NlriAddressType for Ipv4NlriMplsLabelsAddress {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum InvalidIpv6NlriMplsLabelsAddress {
     /// Total length should not exceed 255, each MPLS Label is 24 bit and
@@ -1254,7 +1624,8 @@ pub enum InvalidIpv6NlriMplsLabelsAddress {
 /// ~                                                               |
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Ipv6NlriMplsLabelsAddress {
     path_id: Option<u32>,
@@ -1315,6 +1686,78 @@ mod tests {
     use super::*;
     use std::str::FromStr;
 
+    #[test]
+    fn test_ethernet_segment_identifier_value() {
+        let lacp =
+            EthernetSegmentIdentifier([0x01, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x01, 0x02, 0x00]);
+        let router_id =
+            EthernetSegmentIdentifier([0x04, 0x0a, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x64, 0x00]);
+        let mac =
+            EthernetSegmentIdentifier([0x03, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x00, 0x01, 0x00]);
+        let unknown =
+            EthernetSegmentIdentifier([0x07, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09]);
+        let as_reserved =
+            EthernetSegmentIdentifier([0x05, 0x00, 0x00, 0xfd, 0xe8, 0x00, 0x00, 0x00, 0x01, 0xff]);
+
+        assert_eq!(
+            lacp.value(),
+            EthernetSegmentIdentifierValue::Lacp {
+                system_mac: MacAddress([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]),
+                port_key: 0x0102,
+                reserved: 0,
+            }
+        );
+        assert_eq!(
+            router_id.value(),
+            EthernetSegmentIdentifierValue::RouterId {
+                router_id: Ipv4Addr::new(10, 0, 0, 1),
+                local_discriminator: 100,
+                reserved: 0,
+            }
+        );
+        assert_eq!(
+            mac.value(),
+            EthernetSegmentIdentifierValue::Mac {
+                system_mac: MacAddress([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]),
+                local_discriminator: 0x000100,
+            }
+        );
+        assert_eq!(
+            unknown.esi_type(),
+            Err(UndefinedEthernetSegmentIdentifierType(0x07))
+        );
+        assert_eq!(
+            EthernetSegmentIdentifier::ZERO.esi_type(),
+            Ok(EthernetSegmentIdentifierType::Arbitrary)
+        );
+        assert_eq!(
+            as_reserved.value(),
+            EthernetSegmentIdentifierValue::As {
+                asn: 65000,
+                local_discriminator: 1,
+                reserved: 0xff,
+            }
+        );
+        for esi in [
+            lacp,
+            router_id,
+            mac,
+            unknown,
+            as_reserved,
+            EthernetSegmentIdentifier::MAX,
+        ] {
+            assert_eq!(EthernetSegmentIdentifier::try_from(esi.value()), Ok(esi));
+        }
+        let large_discriminator = EthernetSegmentIdentifierValue::Mac {
+            system_mac: MacAddress([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]),
+            local_discriminator: 0x0100_0000,
+        };
+        assert_eq!(
+            EthernetSegmentIdentifier::try_from(large_discriminator),
+            Err(InvalidEthernetSegmentIdentifierValue(large_discriminator))
+        );
+    }
+
     #[test]
     fn test_ipv4_unicast() {
         let unicast_addr = Ipv4Net::new(Ipv4Addr::new(192, 168, 56, 1), 4).unwrap();
@@ -1378,4 +1821,20 @@ mod tests {
         );
         assert_eq!(unicast, Err(InvalidIpv6MulticastNetwork(unicast_addr)));
     }
+
+    #[test]
+    fn test_host_bits_policy() {
+        let clean = IpNet::from_str("192.0.2.0/24").unwrap();
+        let dirty = IpNet::from_str("192.0.2.1/24").unwrap();
+        let dirty_v6 = IpNet::from_str("2001:db8::1/32").unwrap();
+
+        assert_eq!(HostBitsPolicy::Accept.apply(dirty), Ok(dirty));
+        assert_eq!(HostBitsPolicy::Normalize.apply(dirty), Ok(clean));
+        assert_eq!(
+            HostBitsPolicy::Normalize.apply(dirty_v6),
+            Ok(IpNet::from_str("2001:db8::/32").unwrap())
+        );
+        assert_eq!(HostBitsPolicy::Reject.apply(dirty), Err(dirty));
+        assert_eq!(HostBitsPolicy::Reject.apply(clean), Ok(clean));
+    }
 }
@@ -15,6 +15,7 @@
 
 //! Representations for BGP Notification message
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 /// BGP Notification message
@@ -26,7 +27,8 @@ use serde::{Deserialize, Serialize};
 ///  | Error code    | Error subcode |   Data (variable)             |
 ///  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BgpNotificationMessage {
     MessageHeaderError(MessageHeaderError),
@@ -38,7 +40,8 @@ pub enum BgpNotificationMessage {
     RouteRefreshError(RouteRefreshError),
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum MessageHeaderError {
     Unspecific { value: Vec<u8> },
@@ -48,7 +51,8 @@ pub enum MessageHeaderError {
 }
 
 /// See [`crate::iana::OpenMessageErrorSubCode`] for full documentation
-#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum OpenMessageError {
     Unspecific { value: Vec<u8> },
@@ -62,7 +66,8 @@ pub enum OpenMessageError {
 }
 
 /// See [`crate::iana::UpdateMessageErrorSubCode`] for full documentation
-#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum UpdateMessageError {
     Unspecific { value: Vec<u8> },
@@ -78,14 +83,16 @@ pub enum UpdateMessageError {
     MalformedAsPath { value: Vec<u8> },
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum HoldTimerExpiredError {
     Unspecific { sub_code: u8, value: Vec<u8> },
 }
 
 /// See [`crate::iana::FiniteStateMachineErrorSubCode`] for full documentation
-#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum FiniteStateMachineError {
     Unspecific { value: Vec<u8> },
@@ -95,7 +102,8 @@ pub enum FiniteStateMachineError {
 }
 
 /// See [`crate::iana::CeaseErrorSubCode`] for full documentation
-#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum CeaseError {
     MaximumNumberOfPrefixesReached { value: Vec<u8> },
@@ -111,7 +119,8 @@ pub enum CeaseError {
 }
 
 /// See [`crate::iana::RouteRefreshMessageErrorSubCode`] for full documentation
-#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum RouteRefreshError {
     InvalidMessageLength { value: Vec<u8> },
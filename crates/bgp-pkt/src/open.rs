@@ -14,7 +14,9 @@
 // limitations under the License.
 
 //! Representations for BGP Open message
-use crate::{capabilities::BgpCapability, Deserialize, Serialize};
+use crate::capabilities::BgpCapability;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::net::Ipv4Addr;
 
 pub const BGP_VERSION: u8 = 4;
@@ -39,7 +41,8 @@ pub const BGP_VERSION: u8 = 4;
 /// |                                                               |
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct BgpOpenMessage {
     version: u8,
@@ -82,14 +85,13 @@ impl BgpOpenMessage {
         self.bgp_id
     }
 
-    pub const fn params(&self) -> &Vec<BgpOpenMessageParameter> {
+    pub fn params(&self) -> &[BgpOpenMessageParameter] {
         &self.params
     }
 
     /// Read the ASN4 value
     pub fn my_asn4(&self) -> u32 {
         self.capabilities()
-            .into_iter()
             .filter_map(|cap| {
                 if let BgpCapability::FourOctetAs(asn4) = cap {
                     Some(asn4.asn4())
@@ -97,20 +99,15 @@ impl BgpOpenMessage {
                     None
                 }
             })
-            .collect::<Vec<u32>>()
-            .pop()
+            .last()
             .unwrap_or(self.my_as as u32)
     }
 
-    /// Shortcut to get a list of all the capabilities from all the parameters
-    pub fn capabilities(&self) -> Vec<&BgpCapability> {
-        return self
-            .params
-            .iter()
-            .flat_map(|x| match x {
-                BgpOpenMessageParameter::Capabilities(capabilities_vec) => capabilities_vec,
-            })
-            .collect();
+    /// Shortcut to iterate over the capabilities of all the parameters
+    pub fn capabilities(&self) -> impl Iterator<Item = &BgpCapability> + Clone + '_ {
+        self.params.iter().flat_map(|x| match x {
+            BgpOpenMessageParameter::Capabilities(capabilities_vec) => capabilities_vec.iter(),
+        })
     }
 }
 
@@ -122,7 +119,8 @@ impl BgpOpenMessage {
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-...
 /// |  Parm. Type   | Parm. Length  |  Parameter Value (variable)
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-...
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BgpOpenMessageParameter {
     /// Capabilities Advertisement
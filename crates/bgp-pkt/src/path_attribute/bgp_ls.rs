@@ -18,6 +18,7 @@ use crate::{
     nlri::{MplsLabel, MultiTopologyIdData, SharedRiskLinkGroupValue},
     path_attribute::PathAttributeValueProperties,
 };
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::{
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
@@ -26,7 +27,8 @@ use std::{
 use strum_macros::{Display, FromRepr};
 
 /// The BGP Link-State Attribute. see [RFC7752 Section 3.3](https://www.rfc-editor.org/rfc/rfc7752#section-3.3)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct BgpLsAttribute {
     pub attributes: Vec<BgpLsAttributeValue>,
@@ -49,7 +51,8 @@ impl PathAttributeValueProperties for BgpLsAttribute {
     }
 }
 
-#[derive(Display, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Display, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BgpLsAttributeValue {
     /// see [RFC7752 Section 3.3.1.4](https://www.rfc-editor.org/rfc/rfc7752#section-3.3.1.4)
@@ -438,7 +441,8 @@ pub enum BgpLsAttributeValue {
 ///
 /// see [RFC5307 Section 1.2](https://www.rfc-editor.org/rfc/rfc5307#section-1.2)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum LinkProtectionType {
     ExtraTraffic = 0x01,
     Unprotected = 0x02,
@@ -462,7 +466,8 @@ pub enum LinkProtectionType {
 ///
 /// see [RFC9086](https://datatracker.ietf.org/doc/html/rfc9086#section-5)
 
-#[derive(Display, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Display, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BgpLsPeerSid {
     LabelValue {
@@ -20,15 +20,23 @@
 use crate::arbitrary_ip;
 use crate::{
     community::{Community, ExtendedCommunity, ExtendedCommunityIpv6, LargeCommunity},
-    iana::PathAttributeType,
+    iana::{PathAttributeType, AS_TRANS},
     nlri::*,
     path_attribute::BgpLsAttribute,
+    SmallList,
 };
+use ipnet::IpNet;
 use netgauze_iana::address_family::{AddressFamily, AddressType, SubsequentAddressFamily};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddrV6};
 use strum_macros::{Display, FromRepr};
 
+pub(crate) type CommunityList = SmallList<Community, 4>;
+pub(crate) type ExtendedCommunityList = SmallList<ExtendedCommunity, 2>;
+pub(crate) type ExtendedCommunityIpv6List = SmallList<ExtendedCommunityIpv6, 2>;
+pub(crate) type LargeCommunityList = SmallList<LargeCommunity, 2>;
+
 /// General properties to check the validity of a given path attribute value
 pub trait PathAttributeValueProperties {
     /// Check the validity of the `optional` bit in the [`PathAttribute`]:
@@ -50,7 +58,8 @@ pub trait PathAttributeValueProperties {
     fn can_be_partial() -> Option<bool>;
 }
 
-#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum InvalidPathAttribute {
     InvalidOptionalFlagValue(bool),
@@ -67,7 +76,8 @@ pub enum InvalidPathAttribute {
 /// |  Attr. Flags  |Attr. Type Code| Path value (variable)
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct PathAttribute {
     /// Optional bit defines whether the attribute is optional (if set to
@@ -135,6 +145,10 @@ impl PathAttribute {
         &self.value
     }
 
+    pub(crate) fn value_mut(&mut self) -> &mut PathAttributeValue {
+        &mut self.value
+    }
+
     pub const fn optional(&self) -> bool {
         self.optional
     }
@@ -162,9 +176,23 @@ impl PathAttribute {
     pub const fn path_attribute_type(&self) -> Result<PathAttributeType, u8> {
         self.value.path_attribute_type()
     }
+
+    /// Create a new attribute with the same flags as this attribute, but
+    /// carrying a different value. The caller must ensure that the new value
+    /// is of the same type, so the flags remain valid.
+    pub(crate) fn with_value(&self, value: PathAttributeValue) -> PathAttribute {
+        PathAttribute {
+            optional: self.optional,
+            transitive: self.transitive,
+            partial: self.partial,
+            extended_length: self.extended_length,
+            value,
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum PathAttributeValue {
     Origin(Origin),
@@ -175,6 +203,8 @@ pub enum PathAttributeValue {
     LocalPreference(LocalPreference),
     AtomicAggregate(AtomicAggregate),
     Aggregator(Aggregator),
+    /// AS4_AGGREGATOR sent along a 2-octet AGGREGATOR by 4-octet ASN speakers
+    As4Aggregator(As4Aggregator),
     Communities(Communities),
     ExtendedCommunities(ExtendedCommunities),
     ExtendedCommunitiesIpv6(ExtendedCommunitiesIpv6),
@@ -201,6 +231,7 @@ impl PathAttributeValue {
             Self::LocalPreference(_) => LocalPreference::can_be_optional(),
             Self::AtomicAggregate(_) => AtomicAggregate::can_be_optional(),
             Self::Aggregator(_) => Aggregator::can_be_optional(),
+            Self::As4Aggregator(_) => As4Aggregator::can_be_optional(),
             Self::Communities(_) => Communities::can_be_optional(),
             Self::ExtendedCommunities(_) => ExtendedCommunities::can_be_optional(),
             Self::ExtendedCommunitiesIpv6(_) => ExtendedCommunitiesIpv6::can_be_optional(),
@@ -226,6 +257,7 @@ impl PathAttributeValue {
             Self::LocalPreference(_) => LocalPreference::can_be_transitive(),
             Self::AtomicAggregate(_) => AtomicAggregate::can_be_transitive(),
             Self::Aggregator(_) => Aggregator::can_be_transitive(),
+            Self::As4Aggregator(_) => As4Aggregator::can_be_transitive(),
             Self::Communities(_) => Communities::can_be_transitive(),
             Self::ExtendedCommunities(_) => ExtendedCommunities::can_be_transitive(),
             Self::ExtendedCommunitiesIpv6(_) => ExtendedCommunitiesIpv6::can_be_transitive(),
@@ -251,6 +283,7 @@ impl PathAttributeValue {
             Self::LocalPreference(_) => LocalPreference::can_be_partial(),
             Self::AtomicAggregate(_) => AtomicAggregate::can_be_partial(),
             Self::Aggregator(_) => Aggregator::can_be_partial(),
+            Self::As4Aggregator(_) => As4Aggregator::can_be_partial(),
             Self::Communities(_) => Communities::can_be_partial(),
             Self::ExtendedCommunities(_) => ExtendedCommunities::can_be_partial(),
             Self::ExtendedCommunitiesIpv6(_) => ExtendedCommunitiesIpv6::can_be_partial(),
@@ -278,6 +311,7 @@ impl PathAttributeValue {
             PathAttributeValue::LocalPreference(_) => Ok(PathAttributeType::LocalPreference),
             PathAttributeValue::AtomicAggregate(_) => Ok(PathAttributeType::AtomicAggregate),
             PathAttributeValue::Aggregator(_) => Ok(PathAttributeType::Aggregator),
+            PathAttributeValue::As4Aggregator(_) => Ok(PathAttributeType::As4Aggregator),
             PathAttributeValue::Communities(_) => Ok(PathAttributeType::Communities),
             PathAttributeValue::ExtendedCommunities(_) => {
                 Ok(PathAttributeType::ExtendedCommunities)
@@ -309,7 +343,8 @@ impl PathAttributeValue {
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum Origin {
     IGP = 0,
@@ -339,7 +374,8 @@ impl From<Origin> for u8 {
 
 /// Error type used in [`TryFrom`] for [`Origin`].
 /// The value carried is the undefined value being parsed
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedOrigin(pub u8);
 
@@ -358,13 +394,57 @@ impl TryFrom<u8> for Origin {
 /// of a sequence of AS path segments.  Each AS path segment is
 /// represented by a triple <path segment type, path segment
 /// length, path segment value>.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum AsPath {
     As2PathSegments(Vec<As2PathSegment>),
     As4PathSegments(Vec<As4PathSegment>),
 }
 
+impl AsPath {
+    /// ASN that originated the route, i.e., the last ASN of the path when it
+    /// ends with an `AS_SEQUENCE` segment.
+    ///
+    /// `None` when the path is empty, i.e., the route originated in the
+    /// neighbor's AS, or when it ends with an `AS_SET` segment, as the origin
+    /// is ambiguous, see [RFC6811](https://datatracker.ietf.org/doc/html/rfc6811#section-2).
+    pub fn origin_as(&self) -> Option<u32> {
+        match self {
+            Self::As2PathSegments(segments) => segments
+                .last()
+                .filter(|segment| segment.segment_type() == AsPathSegmentType::AsSequence)
+                .and_then(|segment| segment.as_numbers().last())
+                .map(|asn| *asn as u32),
+            Self::As4PathSegments(segments) => segments
+                .last()
+                .filter(|segment| segment.segment_type() == AsPathSegmentType::AsSequence)
+                .and_then(|segment| segment.as_numbers().last())
+                .copied(),
+        }
+    }
+
+    /// ASN of the neighbor that advertised the route, i.e., the first ASN of
+    /// the path when it starts with an `AS_SEQUENCE` segment.
+    ///
+    /// `None` when the path is empty, i.e., the route was received from an
+    /// internal peer that originated it, or when it starts with an `AS_SET`.
+    pub fn neighbor_as(&self) -> Option<u32> {
+        match self {
+            Self::As2PathSegments(segments) => segments
+                .first()
+                .filter(|segment| segment.segment_type() == AsPathSegmentType::AsSequence)
+                .and_then(|segment| segment.as_numbers().first())
+                .map(|asn| *asn as u32),
+            Self::As4PathSegments(segments) => segments
+                .first()
+                .filter(|segment| segment.segment_type() == AsPathSegmentType::AsSequence)
+                .and_then(|segment| segment.as_numbers().first())
+                .copied(),
+        }
+    }
+}
+
 impl PathAttributeValueProperties for AsPath {
     fn can_be_optional() -> Option<bool> {
         Some(false)
@@ -389,7 +469,8 @@ impl PathAttributeValueProperties for AsPath {
 /// +-+-+-+-+-+-+-+-+
 /// ```
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum AsPathSegmentType {
     AsSet = 1,
@@ -404,7 +485,8 @@ impl From<AsPathSegmentType> for u8 {
 
 /// Error type used in [`TryFrom`] for [`AsPathSegmentType`].
 /// The value carried is the undefined value being parsed
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedAsPathSegmentType(pub u8);
 
@@ -435,7 +517,8 @@ impl TryFrom<u8> for AsPathSegmentType {
 /// | len.  as number (2 octets)    |
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct As2PathSegment {
     segment_type: AsPathSegmentType,
@@ -461,7 +544,8 @@ impl As2PathSegment {
 
 ///  Each AS path segment is represented by a triple:
 /// <path segment type, path segment length, path segment value>.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct As4PathSegment {
     segment_type: AsPathSegmentType,
@@ -485,7 +569,8 @@ impl As4PathSegment {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct As4Path {
     segments: Vec<As4PathSegment>,
@@ -505,6 +590,24 @@ impl As4Path {
     pub const fn segments(&self) -> &Vec<As4PathSegment> {
         &self.segments
     }
+
+    /// Same as [`AsPath::origin_as`]
+    pub fn origin_as(&self) -> Option<u32> {
+        self.segments
+            .last()
+            .filter(|segment| segment.segment_type() == AsPathSegmentType::AsSequence)
+            .and_then(|segment| segment.as_numbers().last())
+            .copied()
+    }
+
+    /// Same as [`AsPath::neighbor_as`]
+    pub fn neighbor_as(&self) -> Option<u32> {
+        self.segments
+            .first()
+            .filter(|segment| segment.segment_type() == AsPathSegmentType::AsSequence)
+            .and_then(|segment| segment.as_numbers().first())
+            .copied()
+    }
 }
 
 impl PathAttributeValueProperties for As4Path {
@@ -525,7 +628,8 @@ impl PathAttributeValueProperties for As4Path {
 /// (unicast) IP address of the router that SHOULD be used as
 /// the next hop to the destinations listed in the Network Layer
 /// Reachability Information field of the UPDATE message.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct NextHop {
     next_hop: Ipv4Addr,
@@ -559,7 +663,8 @@ impl PathAttributeValueProperties for NextHop {
 /// MAY be used by a BGP speaker's Decision Process to
 /// discriminate among multiple entry points to a neighboring
 /// autonomous system.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct MultiExitDiscriminator {
     metric: u32,
@@ -593,7 +698,8 @@ impl PathAttributeValueProperties for MultiExitDiscriminator {
 /// unsigned integer. A BGP speaker uses it to inform its other
 /// internal peers of the advertising speaker's degree of
 /// preference for an advertised route.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct LocalPreference {
     metric: u32,
@@ -624,13 +730,17 @@ impl PathAttributeValueProperties for LocalPreference {
 }
 
 /// `ATOMIC_AGGREGATE` is a well-known discretionary attribute of length 0.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+///
+/// The optional bit is accepted either way, since the attribute is found
+/// with both values in the wild.
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct AtomicAggregate;
 
 impl PathAttributeValueProperties for AtomicAggregate {
     fn can_be_optional() -> Option<bool> {
-        Some(true)
+        None
     }
 
     fn can_be_transitive() -> Option<bool> {
@@ -647,7 +757,8 @@ impl PathAttributeValueProperties for AtomicAggregate {
 /// address of the BGP speaker that formed the aggregate route
 /// (encoded as 4 octets). This SHOULD be the same address as
 /// the one used for the BGP Identifier of the speaker.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct As2Aggregator {
     asn: u16,
@@ -667,7 +778,12 @@ impl As2Aggregator {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+/// AGGREGATOR of a 4-octet ASN session, or the AS4_AGGREGATOR attribute
+/// carrying the 4-octet ASN of the aggregating speaker in a 2-octet ASN
+/// session when the AGGREGATOR carries [`AS_TRANS`]
+/// [RFC6793](https://datatracker.ietf.org/doc/html/rfc6793#section-4.2.2).
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct As4Aggregator {
     asn: u32,
@@ -687,18 +803,65 @@ impl As4Aggregator {
     }
 }
 
+impl PathAttributeValueProperties for As4Aggregator {
+    fn can_be_optional() -> Option<bool> {
+        Some(true)
+    }
+
+    fn can_be_transitive() -> Option<bool> {
+        Some(true)
+    }
+
+    fn can_be_partial() -> Option<bool> {
+        None
+    }
+}
+
 /// AGGREGATOR is an optional transitive attribute. The attribute contains the
 /// last AS number that formed the aggregate route, followed by the IP
 /// address of the BGP speaker that formed the aggregate route.
 /// This SHOULD be the same address as the one used for the BGP Identifier of
 /// the speaker.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum Aggregator {
     As2Aggregator(As2Aggregator),
     As4Aggregator(As4Aggregator),
 }
 
+impl Aggregator {
+    /// ASN of the aggregating speaker, whatever the size it's encoded with
+    pub const fn asn(&self) -> u32 {
+        match self {
+            Self::As2Aggregator(aggregator) => aggregator.asn as u32,
+            Self::As4Aggregator(aggregator) => aggregator.asn,
+        }
+    }
+
+    pub const fn origin(&self) -> Ipv4Addr {
+        match self {
+            Self::As2Aggregator(aggregator) => aggregator.origin,
+            Self::As4Aggregator(aggregator) => aggregator.origin,
+        }
+    }
+
+    /// Aggregator reconciled with the AS4_AGGREGATOR received along in a
+    /// 2-octet ASN session: the AS4_AGGREGATOR is used only when the
+    /// AGGREGATOR carries [`AS_TRANS`], otherwise it's ignored
+    /// [RFC6793](https://datatracker.ietf.org/doc/html/rfc6793#section-4.2.3).
+    pub fn reconcile(&self, as4_aggregator: Option<&As4Aggregator>) -> Self {
+        match (self, as4_aggregator) {
+            (Self::As2Aggregator(aggregator), Some(as4_aggregator))
+                if aggregator.asn == AS_TRANS =>
+            {
+                Self::As4Aggregator(as4_aggregator.clone())
+            }
+            _ => self.clone(),
+        }
+    }
+}
+
 impl PathAttributeValueProperties for Aggregator {
     fn can_be_optional() -> Option<bool> {
         Some(true)
@@ -714,7 +877,8 @@ impl PathAttributeValueProperties for Aggregator {
 }
 
 /// Path attribute can be of size `u8` or `u16` based on `extended_length` bit.
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum PathAttributeLength {
     U8(u8),
@@ -736,18 +900,23 @@ impl From<PathAttributeLength> for u16 {
 /// communities listed in the attribute.
 ///
 /// See [RFC1997](https://datatracker.ietf.org/doc/html/rfc1997)
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Communities {
-    communities: Vec<Community>,
+    communities: CommunityList,
 }
 
 impl Communities {
-    pub const fn new(communities: Vec<Community>) -> Self {
+    pub fn new(communities: Vec<Community>) -> Self {
+        Self::from_list(communities.into_iter().collect())
+    }
+
+    pub(crate) const fn from_list(communities: CommunityList) -> Self {
         Self { communities }
     }
 
-    pub const fn communities(&self) -> &Vec<Community> {
+    pub fn communities(&self) -> &[Community] {
         &self.communities
     }
 }
@@ -766,18 +935,23 @@ impl PathAttributeValueProperties for Communities {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct ExtendedCommunities {
-    communities: Vec<ExtendedCommunity>,
+    communities: ExtendedCommunityList,
 }
 
 impl ExtendedCommunities {
-    pub const fn new(communities: Vec<ExtendedCommunity>) -> Self {
+    pub fn new(communities: Vec<ExtendedCommunity>) -> Self {
+        Self::from_list(communities.into_iter().collect())
+    }
+
+    pub(crate) const fn from_list(communities: ExtendedCommunityList) -> Self {
         Self { communities }
     }
 
-    pub const fn communities(&self) -> &Vec<ExtendedCommunity> {
+    pub fn communities(&self) -> &[ExtendedCommunity] {
         &self.communities
     }
 }
@@ -796,18 +970,23 @@ impl PathAttributeValueProperties for ExtendedCommunities {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct ExtendedCommunitiesIpv6 {
-    communities: Vec<ExtendedCommunityIpv6>,
+    communities: ExtendedCommunityIpv6List,
 }
 
 impl ExtendedCommunitiesIpv6 {
-    pub const fn new(communities: Vec<ExtendedCommunityIpv6>) -> Self {
+    pub fn new(communities: Vec<ExtendedCommunityIpv6>) -> Self {
+        Self::from_list(communities.into_iter().collect())
+    }
+
+    pub(crate) const fn from_list(communities: ExtendedCommunityIpv6List) -> Self {
         Self { communities }
     }
 
-    pub const fn communities(&self) -> &Vec<ExtendedCommunityIpv6> {
+    pub fn communities(&self) -> &[ExtendedCommunityIpv6] {
         &self.communities
     }
 }
@@ -826,18 +1005,23 @@ impl PathAttributeValueProperties for ExtendedCommunitiesIpv6 {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct LargeCommunities {
-    communities: Vec<LargeCommunity>,
+    communities: LargeCommunityList,
 }
 
 impl LargeCommunities {
-    pub const fn new(communities: Vec<LargeCommunity>) -> Self {
+    pub fn new(communities: Vec<LargeCommunity>) -> Self {
+        Self::from_list(communities.into_iter().collect())
+    }
+
+    pub(crate) const fn from_list(communities: LargeCommunityList) -> Self {
         Self { communities }
     }
 
-    pub const fn communities(&self) -> &Vec<LargeCommunity> {
+    pub fn communities(&self) -> &[LargeCommunity] {
         &self.communities
     }
 }
@@ -865,7 +1049,8 @@ impl PathAttributeValueProperties for LargeCommunities {
 /// Identifier as the `ORIGINATOR_ID`.
 ///
 /// [RFC4456](https://datatracker.ietf.org/doc/html/rfc4456) defines this value
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Originator(Ipv4Addr);
 
@@ -898,7 +1083,8 @@ impl PathAttributeValueProperties for Originator {
 /// route has passed.
 ///
 /// [RFC4456](https://datatracker.ietf.org/doc/html/rfc4456) defines this value
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct ClusterList(Vec<ClusterId>);
 
@@ -926,7 +1112,8 @@ impl PathAttributeValueProperties for ClusterList {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct ClusterId(Ipv4Addr);
 
@@ -966,7 +1153,8 @@ impl ClusterId {
 /// | Network Layer Reachability Information (variable)       |
 /// +---------------------------------------------------------+
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum MpReach {
     Ipv4Unicast {
@@ -1037,6 +1225,18 @@ pub enum MpReach {
         next_hop: LabeledNextHop,
         nlri: Vec<BgpLsVpnNlri>,
     },
+    /// The next hop is usually empty, the traffic is dropped or redirected
+    /// according to the [`crate::community::FlowSpecAction`]s
+    Ipv4FlowSpec {
+        #[cfg_attr(feature = "fuzz", arbitrary(with = arbitrary_ext::arbitrary_option(crate::arbitrary_ip)))]
+        next_hop: Option<IpAddr>,
+        nlri: Vec<FlowSpecNlri>,
+    },
+    Ipv6FlowSpec {
+        #[cfg_attr(feature = "fuzz", arbitrary(with = arbitrary_ext::arbitrary_option(crate::arbitrary_ip)))]
+        next_hop: Option<IpAddr>,
+        nlri: Vec<FlowSpecNlri>,
+    },
     Unknown {
         afi: AddressFamily,
         safi: SubsequentAddressFamily,
@@ -1045,6 +1245,28 @@ pub enum MpReach {
 }
 
 impl MpReach {
+    /// Clear the host bits of the IP prefixes, the original prefixes that had
+    /// host bits set are returned
+    pub(crate) fn normalize_host_bits(&mut self) -> Vec<IpNet> {
+        match self {
+            Self::Ipv4Unicast { nlri, .. } => normalize_host_bits(nlri),
+            Self::Ipv4Multicast { nlri, .. } => normalize_host_bits(nlri),
+            Self::Ipv4NlriMplsLabels { nlri, .. } => normalize_host_bits(nlri),
+            Self::Ipv4MplsVpnUnicast { nlri, .. } => normalize_host_bits(nlri),
+            Self::Ipv6Unicast { nlri, .. } => normalize_host_bits(nlri),
+            Self::Ipv6Multicast { nlri, .. } => normalize_host_bits(nlri),
+            Self::Ipv6NlriMplsLabels { nlri, .. } => normalize_host_bits(nlri),
+            Self::Ipv6MplsVpnUnicast { nlri, .. } => normalize_host_bits(nlri),
+            Self::L2Evpn { .. }
+            | Self::RouteTargetMembership { .. }
+            | Self::BgpLs { .. }
+            | Self::BgpLsVpn { .. }
+            | Self::Ipv4FlowSpec { .. }
+            | Self::Ipv6FlowSpec { .. }
+            | Self::Unknown { .. } => vec![],
+        }
+    }
+
     /// [AddressType] of the MP Reach message.
     /// Error with the individual AFI/SAIF values for [MpReach::Unknown] is
     /// returned.
@@ -1064,6 +1286,8 @@ impl MpReach {
             MpReach::RouteTargetMembership { .. } => Ok(AddressType::RouteTargetConstrains),
             MpReach::BgpLs { .. } => Ok(AddressType::BgpLs),
             MpReach::BgpLsVpn { .. } => Ok(AddressType::BgpLsVpn),
+            MpReach::Ipv4FlowSpec { .. } => Ok(AddressType::Ipv4FlowSpec),
+            MpReach::Ipv6FlowSpec { .. } => Ok(AddressType::Ipv6FlowSpec),
             MpReach::Unknown { afi, safi, .. } => Err((*afi, *safi)),
         }
     }
@@ -1085,6 +1309,8 @@ impl MpReach {
             }
             MpReach::BgpLs { .. } => AddressType::BgpLs.address_family(),
             MpReach::BgpLsVpn { .. } => AddressType::BgpLsVpn.address_family(),
+            MpReach::Ipv4FlowSpec { .. } => AddressType::Ipv4FlowSpec.address_family(),
+            MpReach::Ipv6FlowSpec { .. } => AddressType::Ipv6FlowSpec.address_family(),
             MpReach::Unknown { afi, .. } => *afi,
         }
     }
@@ -1114,11 +1340,139 @@ impl MpReach {
             }
             MpReach::BgpLs { .. } => AddressType::BgpLs.subsequent_address_family(),
             MpReach::BgpLsVpn { .. } => AddressType::BgpLsVpn.subsequent_address_family(),
+            MpReach::Ipv4FlowSpec { .. } => AddressType::Ipv4FlowSpec.subsequent_address_family(),
+            MpReach::Ipv6FlowSpec { .. } => AddressType::Ipv6FlowSpec.subsequent_address_family(),
             MpReach::Unknown {
                 afi: _afi, safi, ..
             } => *safi,
         }
     }
+
+    /// Next hop of the announced routes, along with the link-local IPv6 next
+    /// hop when it's advertised. `None` for [MpReach::Unknown] and the Flow
+    /// Spec routes advertised without a next hop.
+    pub const fn next_hops(&self) -> Option<NextHopPair> {
+        match self {
+            MpReach::Ipv4Unicast {
+                next_hop,
+                next_hop_local,
+                ..
+            }
+            | MpReach::Ipv4Multicast {
+                next_hop,
+                next_hop_local,
+                ..
+            }
+            | MpReach::Ipv4NlriMplsLabels {
+                next_hop,
+                next_hop_local,
+                ..
+            }
+            | MpReach::Ipv6NlriMplsLabels {
+                next_hop,
+                next_hop_local,
+                ..
+            } => Some(NextHopPair::new(*next_hop, *next_hop_local)),
+            MpReach::Ipv6Unicast {
+                next_hop_global,
+                next_hop_local,
+                ..
+            }
+            | MpReach::Ipv6Multicast {
+                next_hop_global,
+                next_hop_local,
+                ..
+            } => Some(NextHopPair::new(
+                IpAddr::V6(*next_hop_global),
+                *next_hop_local,
+            )),
+            MpReach::Ipv4MplsVpnUnicast { next_hop, .. }
+            | MpReach::Ipv6MplsVpnUnicast { next_hop, .. }
+            | MpReach::BgpLsVpn { next_hop, .. } => Some(match next_hop {
+                LabeledNextHop::Ipv4(next_hop) => {
+                    NextHopPair::new(IpAddr::V4(next_hop.next_hop()), None)
+                }
+                LabeledNextHop::Ipv6(next_hop) => {
+                    NextHopPair::new(IpAddr::V6(next_hop.next_hop()), next_hop.next_hop_local())
+                }
+            }),
+            MpReach::L2Evpn { next_hop, .. }
+            | MpReach::RouteTargetMembership { next_hop, .. }
+            | MpReach::BgpLs { next_hop, .. } => Some(NextHopPair::new(*next_hop, None)),
+            MpReach::Ipv4FlowSpec { next_hop, .. } | MpReach::Ipv6FlowSpec { next_hop, .. } => {
+                match next_hop {
+                    Some(next_hop) => Some(NextHopPair::new(*next_hop, None)),
+                    None => None,
+                }
+            }
+            MpReach::Unknown { .. } => None,
+        }
+    }
+}
+
+/// Global next hop of a route, and the link-local IPv6 next hop advertised
+/// along when the peers share a link
+/// [RFC2545](https://datatracker.ietf.org/doc/html/rfc2545#section-3).
+///
+/// The link-local address is only meaningful on the link the route is
+/// received on, and it doesn't carry the scope of that link on the wire. The
+/// receiver has to supply the interface of the BGP session with
+/// [`NextHopPair::with_interface`] before using it, e.g., as the `scope_id` of
+/// [`NextHopPair::link_local_socket_addr`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NextHopPair {
+    global: IpAddr,
+    link_local: Option<Ipv6Addr>,
+    interface: Option<u32>,
+}
+
+impl NextHopPair {
+    pub const fn new(global: IpAddr, link_local: Option<Ipv6Addr>) -> Self {
+        Self {
+            global,
+            link_local,
+            interface: None,
+        }
+    }
+
+    /// Index of the interface the link-local next hop is reachable on
+    pub const fn with_interface(mut self, interface: u32) -> Self {
+        self.interface = Some(interface);
+        self
+    }
+
+    pub const fn global(&self) -> IpAddr {
+        self.global
+    }
+
+    pub const fn link_local(&self) -> Option<Ipv6Addr> {
+        self.link_local
+    }
+
+    pub const fn interface(&self) -> Option<u32> {
+        self.interface
+    }
+
+    /// Address to forward to: the link-local next hop when it's advertised,
+    /// otherwise the global next hop
+    pub const fn forwarding(&self) -> IpAddr {
+        match self.link_local {
+            Some(link_local) => IpAddr::V6(link_local),
+            None => self.global,
+        }
+    }
+
+    /// Link-local next hop scoped to its interface, `None` when either the
+    /// link-local next hop or the interface is unknown
+    pub const fn link_local_socket_addr(&self, port: u16) -> Option<SocketAddrV6> {
+        match (self.link_local, self.interface) {
+            (Some(link_local), Some(interface)) => {
+                Some(SocketAddrV6::new(link_local, port, 0, interface))
+            }
+            _ => None,
+        }
+    }
 }
 impl PathAttributeValueProperties for MpReach {
     fn can_be_optional() -> Option<bool> {
@@ -1149,7 +1503,8 @@ impl PathAttributeValueProperties for MpReach {
 /// | Withdrawn Routes (variable)                             |
 /// +---------------------------------------------------------+
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum MpUnreach {
     Ipv4Unicast {
@@ -1188,6 +1543,12 @@ pub enum MpUnreach {
     BgpLsVpn {
         nlri: Vec<BgpLsVpnNlri>,
     },
+    Ipv4FlowSpec {
+        nlri: Vec<FlowSpecNlri>,
+    },
+    Ipv6FlowSpec {
+        nlri: Vec<FlowSpecNlri>,
+    },
     Unknown {
         afi: AddressFamily,
         safi: SubsequentAddressFamily,
@@ -1196,6 +1557,28 @@ pub enum MpUnreach {
 }
 
 impl MpUnreach {
+    /// Clear the host bits of the IP prefixes, the original prefixes that had
+    /// host bits set are returned
+    pub(crate) fn normalize_host_bits(&mut self) -> Vec<IpNet> {
+        match self {
+            Self::Ipv4Unicast { nlri, .. } => normalize_host_bits(nlri),
+            Self::Ipv4Multicast { nlri, .. } => normalize_host_bits(nlri),
+            Self::Ipv4NlriMplsLabels { nlri, .. } => normalize_host_bits(nlri),
+            Self::Ipv4MplsVpnUnicast { nlri, .. } => normalize_host_bits(nlri),
+            Self::Ipv6Unicast { nlri, .. } => normalize_host_bits(nlri),
+            Self::Ipv6Multicast { nlri, .. } => normalize_host_bits(nlri),
+            Self::Ipv6NlriMplsLabels { nlri, .. } => normalize_host_bits(nlri),
+            Self::Ipv6MplsVpnUnicast { nlri, .. } => normalize_host_bits(nlri),
+            Self::L2Evpn { .. }
+            | Self::RouteTargetMembership { .. }
+            | Self::BgpLs { .. }
+            | Self::BgpLsVpn { .. }
+            | Self::Ipv4FlowSpec { .. }
+            | Self::Ipv6FlowSpec { .. }
+            | Self::Unknown { .. } => vec![],
+        }
+    }
+
     /// [AddressType] of the MP Unreach message.
     /// Error with the individual AFI/SAIF values for [MpUnreach::Unknown] is
     /// returned.
@@ -1215,6 +1598,8 @@ impl MpUnreach {
             MpUnreach::RouteTargetMembership { .. } => Ok(AddressType::RouteTargetConstrains),
             MpUnreach::BgpLs { .. } => Ok(AddressType::BgpLs),
             MpUnreach::BgpLsVpn { .. } => Ok(AddressType::BgpLsVpn),
+            MpUnreach::Ipv4FlowSpec { .. } => Ok(AddressType::Ipv4FlowSpec),
+            MpUnreach::Ipv6FlowSpec { .. } => Ok(AddressType::Ipv6FlowSpec),
             MpUnreach::Unknown { afi, safi, .. } => Err((*afi, *safi)),
         }
     }
@@ -1244,6 +1629,8 @@ impl MpUnreach {
             }
             MpUnreach::BgpLs { .. } => AddressType::BgpLs.address_family(),
             MpUnreach::BgpLsVpn { .. } => AddressType::BgpLsVpn.address_family(),
+            MpUnreach::Ipv4FlowSpec { .. } => AddressType::Ipv4FlowSpec.address_family(),
+            MpUnreach::Ipv6FlowSpec { .. } => AddressType::Ipv6FlowSpec.address_family(),
             MpUnreach::Unknown { afi, .. } => *afi,
         }
     }
@@ -1277,6 +1664,8 @@ impl MpUnreach {
             }
             MpUnreach::BgpLs { .. } => AddressType::BgpLs.subsequent_address_family(),
             MpUnreach::BgpLsVpn { .. } => AddressType::BgpLsVpn.subsequent_address_family(),
+            MpUnreach::Ipv4FlowSpec { .. } => AddressType::Ipv4FlowSpec.subsequent_address_family(),
+            MpUnreach::Ipv6FlowSpec { .. } => AddressType::Ipv6FlowSpec.subsequent_address_family(),
             MpUnreach::Unknown {
                 afi: _afi, safi, ..
             } => *safi,
@@ -1301,16 +1690,33 @@ impl PathAttributeValueProperties for MpUnreach {
 /// Path Attribute that is not recognized.
 /// BGP Allows parsing unrecognized attributes as is, and then only consider
 /// the transitive and partial bits of the attribute.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+///
+/// The flags, type code and value are kept as received, and written back
+/// verbatim, so a speaker relaying the attribute doesn't drop it.
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UnknownAttribute {
     code: u8,
     value: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    unused_flags: u8,
 }
 
 impl UnknownAttribute {
     pub const fn new(code: u8, value: Vec<u8>) -> Self {
-        Self { code, value }
+        Self {
+            code,
+            value,
+            unused_flags: 0,
+        }
+    }
+
+    /// Set the lower-order four bits of the Attribute Flags octet, the other
+    /// bits are ignored
+    pub const fn with_unused_flags(mut self, unused_flags: u8) -> Self {
+        self.unused_flags = unused_flags & 0x0f;
+        self
     }
 
     /// Attribute Type code
@@ -1318,6 +1724,13 @@ impl UnknownAttribute {
         self.code
     }
 
+    /// Lower-order four bits of the Attribute Flags octet. They must be zero
+    /// when sent by the originator, and are kept as received to relay the
+    /// attribute byte for byte.
+    pub const fn unused_flags(&self) -> u8 {
+        self.unused_flags
+    }
+
     /// Raw u8 vector of the value carried in the attribute
     pub const fn value(&self) -> &Vec<u8> {
         &self.value
@@ -1338,7 +1751,8 @@ impl PathAttributeValueProperties for UnknownAttribute {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct OnlyToCustomer(u32);
 
@@ -1367,7 +1781,8 @@ impl PathAttributeValueProperties for OnlyToCustomer {
 }
 
 /// Accumulated IGP Metric Attribute [RFC7311](https://datatracker.ietf.org/doc/html/rfc7311)
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum Aigp {
     AccumulatedIgpMetric(u64),
@@ -1418,6 +1833,26 @@ mod tests {
         assert_eq!(defined_u8, defined_code);
     }
 
+    #[test]
+    fn test_as_path_origin_as() {
+        let as2 = AsPath::As2PathSegments(vec![As2PathSegment::new(
+            AsPathSegmentType::AsSequence,
+            vec![100, 200],
+        )]);
+        let as4 = AsPath::As4PathSegments(vec![
+            As4PathSegment::new(AsPathSegmentType::AsSequence, vec![100, 200]),
+            As4PathSegment::new(AsPathSegmentType::AsSequence, vec![4200000000]),
+        ]);
+        let as_set = AsPath::As4PathSegments(vec![
+            As4PathSegment::new(AsPathSegmentType::AsSequence, vec![100]),
+            As4PathSegment::new(AsPathSegmentType::AsSet, vec![200, 300]),
+        ]);
+        assert_eq!(as2.origin_as(), Some(200));
+        assert_eq!(as4.origin_as(), Some(4200000000));
+        assert_eq!(as_set.origin_as(), None);
+        assert_eq!(AsPath::As4PathSegments(vec![]).origin_as(), None);
+    }
+
     #[test]
     fn test_path_attributes_well_known_mandatory() {
         assert!(!Origin::can_be_optional().unwrap_or(false));
@@ -1442,12 +1877,69 @@ mod tests {
         assert!(As4Path::can_be_transitive().unwrap_or(false));
         assert!(Aggregator::can_be_optional().unwrap_or(false));
         assert!(Aggregator::can_be_transitive().unwrap_or(false));
+        assert!(As4Aggregator::can_be_optional().unwrap_or(false));
+        assert!(As4Aggregator::can_be_transitive().unwrap_or(false));
         assert!(MpReach::can_be_optional().unwrap_or(false));
         assert!(!MpReach::can_be_transitive().unwrap_or(false));
         assert!(OnlyToCustomer::can_be_optional().unwrap_or(false));
         assert!(OnlyToCustomer::can_be_transitive().unwrap_or(false));
     }
 
+    #[test]
+    fn test_mp_reach_next_hops() {
+        let global = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+        let link_local = Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+        let ipv6_unicast = MpReach::Ipv6Unicast {
+            next_hop_global: global,
+            next_hop_local: Some(link_local),
+            nlri: vec![],
+        };
+        let ipv4_unicast = MpReach::Ipv4Unicast {
+            next_hop: IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)),
+            next_hop_local: None,
+            nlri: vec![],
+        };
+        let ipv6_mpls_vpn_unicast = MpReach::Ipv6MplsVpnUnicast {
+            next_hop: LabeledNextHop::Ipv6(LabeledIpv6NextHop::new(
+                RouteDistinguisher::As2Administrator {
+                    asn2: 13,
+                    number: 34,
+                },
+                global,
+                Some(link_local),
+            )),
+            nlri: vec![],
+        };
+        let unknown = MpReach::Unknown {
+            afi: AddressFamily::IPv6,
+            safi: SubsequentAddressFamily::Unicast,
+            value: vec![],
+        };
+
+        let next_hops = ipv6_unicast.next_hops().unwrap();
+        assert_eq!(next_hops.global(), IpAddr::V6(global));
+        assert_eq!(next_hops.link_local(), Some(link_local));
+        assert_eq!(next_hops.forwarding(), IpAddr::V6(link_local));
+        assert_eq!(next_hops.link_local_socket_addr(179), None);
+        let next_hops = next_hops.with_interface(3);
+        assert_eq!(next_hops.interface(), Some(3));
+        assert_eq!(
+            next_hops.link_local_socket_addr(179),
+            Some(SocketAddrV6::new(link_local, 179, 0, 3))
+        );
+        assert_eq!(
+            ipv6_mpls_vpn_unicast.next_hops(),
+            Some(NextHopPair::new(IpAddr::V6(global), Some(link_local)))
+        );
+        let next_hops = ipv4_unicast.next_hops().unwrap();
+        assert_eq!(next_hops.link_local(), None);
+        assert_eq!(
+            next_hops.forwarding(),
+            IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1))
+        );
+        assert_eq!(unknown.next_hops(), None);
+    }
+
     #[test]
     fn test_mp_reach_address() {
         let ipv4_unicast = MpReach::Ipv4Unicast {
@@ -0,0 +1,731 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Simulation of the AS path manipulations done by common routing policies,
+//! e.g., for what-if analysis of collected routes or to generate post-policy
+//! test streams.
+//!
+//! ```
+//! use netgauze_bgp_pkt::{
+//!     path_attribute::{As4PathSegment, AsPath, AsPathSegmentType},
+//!     policy::AsPathPolicy,
+//! };
+//!
+//! let policy = AsPathPolicy::compile("remove-private-as; prepend 65000 2").unwrap();
+//! let as_path = AsPath::As4PathSegments(vec![As4PathSegment::new(
+//!     AsPathSegmentType::AsSequence,
+//!     vec![64512, 3356],
+//! )]);
+//! assert_eq!(
+//!     policy.apply_as_path(&as_path),
+//!     AsPath::As4PathSegments(vec![As4PathSegment::new(
+//!         AsPathSegmentType::AsSequence,
+//!         vec![65000, 65000, 3356],
+//!     )])
+//! );
+//! ```
+//!
+//! With the `matcher` feature, the policy can be restricted to the updates
+//! announcing a prefix of a [`PrefixSet`], e.g.,
+//! `match-prefix 10.0.0.0/8 192.0.2.0/24; prepend 65000 2`.
+
+#[cfg(feature = "matcher")]
+use crate::matcher::PrefixSet;
+use crate::{
+    iana::AS_TRANS,
+    path_attribute::{
+        As2PathSegment, As4Path, As4PathSegment, AsPath, AsPathSegmentType, PathAttribute,
+        PathAttributeValue,
+    },
+    BgpUpdateMessage,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Max number of AS numbers in one AS path segment, longer AS_SEQUENCEs are
+/// split and longer AS_SETs are capped
+const MAX_SEGMENT_LENGTH: usize = u8::MAX as usize;
+
+/// Error compiling an [`AsPathPolicy`]. The value carried is the offending
+/// action of the description.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PolicyCompileError {
+    /// The action is not one of `prepend`, `remove-private-as`,
+    /// `replace-as`, or `match-prefix`
+    UnknownAction(String),
+
+    /// The action doesn't have the expected number of arguments, or they
+    /// are not valid numbers or prefixes
+    InvalidArguments(String),
+}
+
+/// Check if the ASN is reserved for private use by
+/// [RFC6996](https://datatracker.ietf.org/doc/html/rfc6996)
+pub const fn is_private_asn(asn: u32) -> bool {
+    matches!(asn, 64512..=65534 | 4200000000..=4294967294)
+}
+
+/// One AS path manipulation of an [`AsPathPolicy`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AsPathAction {
+    /// Add `asn` `count` times at the beginning of the path
+    Prepend { asn: u32, count: u8 },
+
+    /// Remove the private ASNs, see [`is_private_asn`]
+    RemovePrivateAs,
+
+    /// Replace every occurrence of the `from` ASN with `to`, e.g., to hide the
+    /// real ASN behind the `local-as` of a migration
+    ReplaceAs { from: u32, to: u32 },
+}
+
+impl AsPathAction {
+    fn apply(&self, segments: &mut Vec<(AsPathSegmentType, Vec<u32>)>) {
+        match *self {
+            Self::Prepend { asn, count } => {
+                let prepended = vec![asn; count.into()];
+                match segments.first_mut() {
+                    Some((AsPathSegmentType::AsSequence, as_numbers)) => {
+                        as_numbers.splice(0..0, prepended);
+                    }
+                    _ => segments.insert(0, (AsPathSegmentType::AsSequence, prepended)),
+                }
+            }
+            Self::RemovePrivateAs => {
+                for (_, as_numbers) in segments.iter_mut() {
+                    as_numbers.retain(|asn| !is_private_asn(*asn));
+                }
+                segments.retain(|(_, as_numbers)| !as_numbers.is_empty());
+            }
+            Self::ReplaceAs { from, to } => {
+                for asn in segments
+                    .iter_mut()
+                    .flat_map(|(_, as_numbers)| as_numbers.iter_mut())
+                {
+                    if *asn == from {
+                        *asn = to;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Ordered list of [`AsPathAction`]s applied to the AS path of routes.
+///
+/// Policies are described as `;` separated actions that are applied in order:
+/// - `prepend <asn> <count>`
+/// - `remove-private-as`
+/// - `replace-as <from> <to>`
+///
+/// With the `matcher` feature, `match-prefix <prefix> [<prefix>...]`
+/// restricts the policy to the updates announcing at least one of the
+/// prefixes, or a more specific one. The prefixes of several `match-prefix`
+/// statements are merged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AsPathPolicy {
+    actions: Vec<AsPathAction>,
+    #[cfg(feature = "matcher")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    prefixes: Option<PrefixSet>,
+}
+
+impl AsPathPolicy {
+    pub const fn new(actions: Vec<AsPathAction>) -> Self {
+        Self {
+            actions,
+            #[cfg(feature = "matcher")]
+            prefixes: None,
+        }
+    }
+
+    /// Apply the policy only to the updates announcing a prefix of `prefixes`
+    #[cfg(feature = "matcher")]
+    pub fn with_prefixes(mut self, prefixes: PrefixSet) -> Self {
+        self.prefixes = Some(prefixes);
+        self
+    }
+
+    pub fn compile(description: &str) -> Result<Self, PolicyCompileError> {
+        let mut policy = Self::default();
+        for action in description.split(';').map(str::trim) {
+            if action.is_empty() {
+                continue;
+            }
+            let mut parts = action.split_whitespace();
+            let name = parts.next().unwrap_or_default();
+            #[cfg(feature = "matcher")]
+            if name == "match-prefix" {
+                let prefixes = PrefixSet::compile(parts)
+                    .map_err(|_| PolicyCompileError::InvalidArguments(action.to_string()))?;
+                if prefixes.is_empty() {
+                    return Err(PolicyCompileError::InvalidArguments(action.to_string()));
+                }
+                policy
+                    .prefixes
+                    .get_or_insert_with(PrefixSet::new)
+                    .extend(prefixes.prefixes());
+                continue;
+            }
+            let args = parts
+                .map(str::parse::<u32>)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| PolicyCompileError::InvalidArguments(action.to_string()))?;
+            let invalid = || PolicyCompileError::InvalidArguments(action.to_string());
+            let action = match (name, args.as_slice()) {
+                ("prepend", [asn, count]) => AsPathAction::Prepend {
+                    asn: *asn,
+                    count: u8::try_from(*count).map_err(|_| invalid())?,
+                },
+                ("remove-private-as", []) => AsPathAction::RemovePrivateAs,
+                ("replace-as", [from, to]) => AsPathAction::ReplaceAs {
+                    from: *from,
+                    to: *to,
+                },
+                ("prepend" | "remove-private-as" | "replace-as", _) => return Err(invalid()),
+                _ => return Err(PolicyCompileError::UnknownAction(action.to_string())),
+            };
+            policy.actions.push(action);
+        }
+        Ok(policy)
+    }
+
+    pub fn actions(&self) -> &[AsPathAction] {
+        &self.actions
+    }
+
+    /// Prefixes the policy is restricted to, if any
+    #[cfg(feature = "matcher")]
+    pub const fn prefixes(&self) -> Option<&PrefixSet> {
+        self.prefixes.as_ref()
+    }
+
+    /// Check if the policy applies to the update, i.e., it isn't restricted
+    /// to a [`PrefixSet`] or the update announces one of its prefixes
+    #[cfg(feature = "matcher")]
+    pub fn matches(&self, update: &BgpUpdateMessage) -> bool {
+        match &self.prefixes {
+            None => true,
+            Some(prefixes) => update
+                .announced()
+                .any(|route| prefixes.matches_route(&route)),
+        }
+    }
+
+    /// Check if the policy applies to the update, always true without the
+    /// `matcher` feature
+    #[cfg(not(feature = "matcher"))]
+    pub const fn matches(&self, _update: &BgpUpdateMessage) -> bool {
+        true
+    }
+
+    fn apply_segments(
+        &self,
+        segments: impl Iterator<Item = (AsPathSegmentType, Vec<u32>)>,
+    ) -> Vec<(AsPathSegmentType, Vec<u32>)> {
+        let mut segments = segments.collect();
+        for action in &self.actions {
+            action.apply(&mut segments);
+        }
+        segments
+            .into_iter()
+            .flat_map(|(segment_type, mut as_numbers)| match segment_type {
+                // An AS_SET counts as one AS in the path length, splitting it
+                // would make the path longer. Its order doesn't matter, so the
+                // duplicates are dropped before capping it.
+                AsPathSegmentType::AsSet => {
+                    let mut seen = HashSet::new();
+                    as_numbers.retain(|asn| seen.insert(*asn));
+                    as_numbers.truncate(MAX_SEGMENT_LENGTH);
+                    vec![(segment_type, as_numbers)]
+                }
+                AsPathSegmentType::AsSequence => as_numbers
+                    .chunks(MAX_SEGMENT_LENGTH)
+                    .map(|chunk| (segment_type, chunk.to_vec()))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Apply the actions to a path. When a two-octet path gets an ASN that
+    /// doesn't fit in two octets, it's replaced by [`AS_TRANS`], see
+    /// [`AsPathPolicy::apply`] to carry the four-octet ASNs in an AS4_PATH.
+    pub fn apply_as_path(&self, as_path: &AsPath) -> AsPath {
+        match as_path {
+            AsPath::As2PathSegments(segments) => {
+                AsPath::As2PathSegments(to_as2_segments(&self.apply_as2_segments(segments)))
+            }
+            AsPath::As4PathSegments(segments) => {
+                AsPath::As4PathSegments(self.apply_as4_segments(segments))
+            }
+        }
+    }
+
+    /// Apply the actions to a two-octet path, keeping the four-octet ASNs
+    fn apply_as2_segments(&self, segments: &[As2PathSegment]) -> Vec<As4PathSegment> {
+        self.apply_segments(segments.iter().map(|segment| {
+            (
+                segment.segment_type(),
+                segment.as_numbers().iter().map(|asn| *asn as u32).collect(),
+            )
+        }))
+        .into_iter()
+        .map(|(segment_type, as_numbers)| As4PathSegment::new(segment_type, as_numbers))
+        .collect()
+    }
+
+    fn apply_as4_segments(&self, segments: &[As4PathSegment]) -> Vec<As4PathSegment> {
+        self.apply_segments(
+            segments
+                .iter()
+                .map(|segment| (segment.segment_type(), segment.as_numbers().clone())),
+        )
+        .into_iter()
+        .map(|(segment_type, as_numbers)| As4PathSegment::new(segment_type, as_numbers))
+        .collect()
+    }
+
+    /// Apply the actions to the AS_PATH and AS4_PATH attributes of the update,
+    /// the other attributes and the NLRI are copied as is. Updates that don't
+    /// [`AsPathPolicy::matches`] the policy are copied unchanged.
+    ///
+    /// When a two-octet AS_PATH gets an ASN that doesn't fit in two octets
+    /// and the update has no AS4_PATH, an AS4_PATH with the four-octet path is
+    /// added after the AS_PATH, as done by
+    /// [RFC6793](https://datatracker.ietf.org/doc/html/rfc6793#section-4.2.2).
+    pub fn apply(&self, update: &BgpUpdateMessage) -> BgpUpdateMessage {
+        if !self.matches(update) {
+            return update.clone();
+        }
+        let has_as4_path = update
+            .path_attributes()
+            .iter()
+            .any(|attr| matches!(attr.value(), PathAttributeValue::As4Path(_)));
+        let mut path_attributes = Vec::with_capacity(update.path_attributes().len() + 1);
+        for attr in update.path_attributes() {
+            let (value, value_len) = match attr.value() {
+                PathAttributeValue::AsPath(AsPath::As2PathSegments(segments)) => {
+                    let as4_segments = self.apply_as2_segments(segments);
+                    let as2_segments = to_as2_segments(&as4_segments);
+                    let value_len = as2_segments
+                        .iter()
+                        .map(|segment| 2 + 2 * segment.as_numbers().len())
+                        .sum();
+                    path_attributes.push(with_value(
+                        attr,
+                        PathAttributeValue::AsPath(AsPath::As2PathSegments(as2_segments)),
+                        value_len,
+                    ));
+                    let needs_as4_path = as4_segments.iter().any(|segment| {
+                        segment
+                            .as_numbers()
+                            .iter()
+                            .any(|asn| u16::try_from(*asn).is_err())
+                    });
+                    if needs_as4_path && !has_as4_path {
+                        let value_len = as4_segments_len(&as4_segments);
+                        path_attributes.push(
+                            PathAttribute::from(
+                                true,
+                                true,
+                                false,
+                                value_len > u8::MAX as usize,
+                                PathAttributeValue::As4Path(As4Path::new(as4_segments)),
+                            )
+                            .expect("AS4_PATH is optional transitive"),
+                        );
+                    }
+                    continue;
+                }
+                PathAttributeValue::AsPath(AsPath::As4PathSegments(segments)) => {
+                    let segments = self.apply_as4_segments(segments);
+                    let value_len = as4_segments_len(&segments);
+                    (
+                        PathAttributeValue::AsPath(AsPath::As4PathSegments(segments)),
+                        value_len,
+                    )
+                }
+                PathAttributeValue::As4Path(as4_path) => {
+                    let segments = self.apply_as4_segments(as4_path.segments());
+                    let value_len = as4_segments_len(&segments);
+                    (
+                        PathAttributeValue::As4Path(As4Path::new(segments)),
+                        value_len,
+                    )
+                }
+                _ => {
+                    path_attributes.push(attr.clone());
+                    continue;
+                }
+            };
+            path_attributes.push(with_value(attr, value, value_len));
+        }
+        BgpUpdateMessage::new(
+            update.withdraw_routes().to_vec(),
+            path_attributes,
+            update.nlri().to_vec(),
+        )
+    }
+}
+
+/// Copy the flags of `attr` to an attribute of the same type with `value`
+fn with_value(attr: &PathAttribute, value: PathAttributeValue, value_len: usize) -> PathAttribute {
+    PathAttribute::from(
+        attr.optional(),
+        attr.transitive(),
+        attr.partial(),
+        attr.extended_length() || value_len > u8::MAX as usize,
+        value,
+    )
+    .expect("flags are copied from an attribute of the same type")
+}
+
+/// Two-octet segments, replacing the ASNs that don't fit by [`AS_TRANS`]
+fn to_as2_segments(segments: &[As4PathSegment]) -> Vec<As2PathSegment> {
+    segments
+        .iter()
+        .map(|segment| {
+            As2PathSegment::new(
+                segment.segment_type(),
+                segment
+                    .as_numbers()
+                    .iter()
+                    .map(|asn| u16::try_from(*asn).unwrap_or(AS_TRANS))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+fn as4_segments_len(segments: &[As4PathSegment]) -> usize {
+    segments
+        .iter()
+        .map(|segment| 2 + 4 * segment.as_numbers().len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+        path_attribute::Origin,
+    };
+
+    fn seq(as_numbers: Vec<u32>) -> As4PathSegment {
+        As4PathSegment::new(AsPathSegmentType::AsSequence, as_numbers)
+    }
+
+    #[test]
+    fn test_compile() {
+        assert_eq!(
+            AsPathPolicy::compile("prepend 65000 3; remove-private-as ;replace-as 64512 65000;"),
+            Ok(AsPathPolicy::new(vec![
+                AsPathAction::Prepend {
+                    asn: 65000,
+                    count: 3
+                },
+                AsPathAction::RemovePrivateAs,
+                AsPathAction::ReplaceAs {
+                    from: 64512,
+                    to: 65000
+                },
+            ]))
+        );
+        assert_eq!(AsPathPolicy::compile(""), Ok(AsPathPolicy::default()));
+        assert_eq!(
+            AsPathPolicy::compile("prepend 65000"),
+            Err(PolicyCompileError::InvalidArguments(
+                "prepend 65000".to_string()
+            ))
+        );
+        assert_eq!(
+            AsPathPolicy::compile("prepend 65000 256"),
+            Err(PolicyCompileError::InvalidArguments(
+                "prepend 65000 256".to_string()
+            ))
+        );
+        assert_eq!(
+            AsPathPolicy::compile("replace-as x 1"),
+            Err(PolicyCompileError::InvalidArguments(
+                "replace-as x 1".to_string()
+            ))
+        );
+        assert_eq!(
+            AsPathPolicy::compile("set-med 10"),
+            Err(PolicyCompileError::UnknownAction("set-med 10".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_apply_as_path() {
+        let as_path = AsPath::As4PathSegments(vec![
+            seq(vec![64512, 3356]),
+            As4PathSegment::new(AsPathSegmentType::AsSet, vec![4200000000]),
+        ]);
+        let policy = AsPathPolicy::compile("remove-private-as").unwrap();
+        assert_eq!(
+            policy.apply_as_path(&as_path),
+            AsPath::As4PathSegments(vec![seq(vec![3356])])
+        );
+
+        // Paths starting with a set get a new sequence
+        let as_path = AsPath::As4PathSegments(vec![As4PathSegment::new(
+            AsPathSegmentType::AsSet,
+            vec![3356, 174],
+        )]);
+        let policy = AsPathPolicy::compile("prepend 65000 1").unwrap();
+        assert_eq!(
+            policy.apply_as_path(&as_path),
+            AsPath::As4PathSegments(vec![
+                seq(vec![65000]),
+                As4PathSegment::new(AsPathSegmentType::AsSet, vec![3356, 174]),
+            ])
+        );
+
+        // Long sequences are split
+        let as_path = AsPath::As4PathSegments(vec![seq(vec![3356; 200])]);
+        let policy = AsPathPolicy::compile("prepend 65000 100").unwrap();
+        let mut expected = vec![65000; 100];
+        expected.extend(vec![3356; 200]);
+        assert_eq!(
+            policy.apply_as_path(&as_path),
+            AsPath::As4PathSegments(vec![
+                seq(expected[..255].to_vec()),
+                seq(expected[255..].to_vec())
+            ])
+        );
+
+        // Long sets are deduplicated and capped, not split
+        let mut set = (1..=300).collect::<Vec<u32>>();
+        set.extend(1..=10);
+        let as_path =
+            AsPath::As4PathSegments(vec![As4PathSegment::new(AsPathSegmentType::AsSet, set)]);
+        let policy = AsPathPolicy::compile("remove-private-as").unwrap();
+        assert_eq!(
+            policy.apply_as_path(&as_path),
+            AsPath::As4PathSegments(vec![As4PathSegment::new(
+                AsPathSegmentType::AsSet,
+                (1..=255).collect(),
+            )])
+        );
+
+        // Two-octet paths use AS_TRANS for four-octet ASNs
+        let as_path = AsPath::As2PathSegments(vec![As2PathSegment::new(
+            AsPathSegmentType::AsSequence,
+            vec![64512, 3356],
+        )]);
+        let policy = AsPathPolicy::compile("replace-as 64512 4200000001; prepend 174 1").unwrap();
+        assert_eq!(
+            policy.apply_as_path(&as_path),
+            AsPath::As2PathSegments(vec![As2PathSegment::new(
+                AsPathSegmentType::AsSequence,
+                vec![174, AS_TRANS, 3356],
+            )])
+        );
+    }
+
+    #[test]
+    fn test_apply_update() {
+        let nlri = vec![Ipv4UnicastAddress::new_no_path_id(
+            Ipv4Unicast::from_net("192.0.2.0/24".parse().unwrap()).unwrap(),
+        )];
+        let origin = PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::Origin(Origin::IGP),
+        )
+        .unwrap();
+        let update = BgpUpdateMessage::new(
+            vec![],
+            vec![
+                origin.clone(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![seq(vec![3356])])),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    true,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::As4Path(As4Path::new(vec![seq(vec![3356])])),
+                )
+                .unwrap(),
+            ],
+            nlri.clone(),
+        );
+        let policy = AsPathPolicy::compile("prepend 65000 100").unwrap();
+        let prepended = seq([vec![65000; 100], vec![3356]].concat());
+        assert_eq!(
+            policy.apply(&update),
+            BgpUpdateMessage::new(
+                vec![],
+                vec![
+                    origin,
+                    PathAttribute::from(
+                        false,
+                        true,
+                        false,
+                        true,
+                        PathAttributeValue::AsPath(AsPath::As4PathSegments(
+                            vec![prepended.clone()]
+                        )),
+                    )
+                    .unwrap(),
+                    PathAttribute::from(
+                        true,
+                        true,
+                        false,
+                        true,
+                        PathAttributeValue::As4Path(As4Path::new(vec![prepended])),
+                    )
+                    .unwrap(),
+                ],
+                nlri,
+            )
+        );
+    }
+
+    #[cfg(feature = "matcher")]
+    #[test]
+    fn test_match_prefix() {
+        let policy = AsPathPolicy::compile(
+            "match-prefix 10.0.0.0/8; match-prefix 192.0.2.0/24; prepend 65000 1",
+        )
+        .unwrap();
+        assert_eq!(
+            policy.prefixes(),
+            Some(&PrefixSet::compile(["10.0.0.0/8", "192.0.2.0/24"]).unwrap())
+        );
+        assert_eq!(
+            AsPathPolicy::compile("match-prefix 10.0.0.0/33"),
+            Err(PolicyCompileError::InvalidArguments(
+                "match-prefix 10.0.0.0/33".to_string()
+            ))
+        );
+        assert_eq!(
+            AsPathPolicy::compile("match-prefix"),
+            Err(PolicyCompileError::InvalidArguments(
+                "match-prefix".to_string()
+            ))
+        );
+
+        let update = |prefix: &str| {
+            BgpUpdateMessage::new(
+                vec![],
+                vec![PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![seq(vec![3356])])),
+                )
+                .unwrap()],
+                vec![Ipv4UnicastAddress::new_no_path_id(
+                    Ipv4Unicast::from_net(prefix.parse().unwrap()).unwrap(),
+                )],
+            )
+        };
+        let matching = update("10.1.0.0/16");
+        assert!(policy.matches(&matching));
+        assert_ne!(policy.apply(&matching), matching);
+        let other = update("198.51.100.0/24");
+        assert!(!policy.matches(&other));
+        assert_eq!(policy.apply(&other), other);
+        assert!(AsPathPolicy::default().matches(&other));
+    }
+
+    #[test]
+    fn test_apply_update_as4_path() {
+        let nlri = vec![Ipv4UnicastAddress::new_no_path_id(
+            Ipv4Unicast::from_net("192.0.2.0/24".parse().unwrap()).unwrap(),
+        )];
+        let as2_path = |as_numbers| {
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::AsPath(AsPath::As2PathSegments(vec![As2PathSegment::new(
+                    AsPathSegmentType::AsSequence,
+                    as_numbers,
+                )])),
+            )
+            .unwrap()
+        };
+        let as4_path = |as_numbers| {
+            PathAttribute::from(
+                true,
+                true,
+                false,
+                false,
+                PathAttributeValue::As4Path(As4Path::new(vec![seq(as_numbers)])),
+            )
+            .unwrap()
+        };
+        let update = BgpUpdateMessage::new(vec![], vec![as2_path(vec![3356])], nlri.clone());
+
+        // AS4_PATH is added along a new AS_TRANS
+        let policy = AsPathPolicy::compile("prepend 4200000001 1").unwrap();
+        assert_eq!(
+            policy.apply(&update),
+            BgpUpdateMessage::new(
+                vec![],
+                vec![
+                    as2_path(vec![AS_TRANS, 3356]),
+                    as4_path(vec![4200000001, 3356])
+                ],
+                nlri.clone(),
+            )
+        );
+
+        // An existing AS4_PATH is updated
+        let update = BgpUpdateMessage::new(
+            vec![],
+            vec![as2_path(vec![AS_TRANS]), as4_path(vec![4200000002])],
+            nlri.clone(),
+        );
+        assert_eq!(
+            policy.apply(&update),
+            BgpUpdateMessage::new(
+                vec![],
+                vec![
+                    as2_path(vec![AS_TRANS, AS_TRANS]),
+                    as4_path(vec![4200000001, 4200000002])
+                ],
+                nlri.clone(),
+            )
+        );
+
+        // Two-octet ASNs don't need an AS4_PATH
+        let update = BgpUpdateMessage::new(vec![], vec![as2_path(vec![3356])], nlri.clone());
+        let policy = AsPathPolicy::compile("prepend 65000 1").unwrap();
+        assert_eq!(
+            policy.apply(&update),
+            BgpUpdateMessage::new(vec![], vec![as2_path(vec![65000, 3356])], nlri)
+        );
+    }
+}
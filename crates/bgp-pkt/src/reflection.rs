@@ -0,0 +1,244 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Validation of the route reflection attributes, `ORIGINATOR_ID` and
+//! `CLUSTER_LIST`, defined in
+//! [RFC4456](https://datatracker.ietf.org/doc/html/rfc4456).
+//!
+//! A router discards the routes it originated itself or that already went
+//! through its cluster, so seeing such routes in the updates received by a
+//! router points to a reflection misconfiguration, e.g., two reflectors of
+//! different clusters configured with the same cluster ID.
+//!
+//! ```
+//! use netgauze_bgp_pkt::{
+//!     path_attribute::{Originator, PathAttribute, PathAttributeValue},
+//!     reflection::{ReflectionValidator, ReflectionViolation},
+//! };
+//! use std::net::Ipv4Addr;
+//!
+//! let router_id = Ipv4Addr::new(192, 0, 2, 1);
+//! let attrs = vec![PathAttribute::from(
+//!     true,
+//!     false,
+//!     false,
+//!     false,
+//!     PathAttributeValue::Originator(Originator::new(router_id)),
+//! )
+//! .unwrap()];
+//! let validator = ReflectionValidator::new().with_router_id(router_id);
+//! assert_eq!(
+//!     validator.validate_attributes(&attrs),
+//!     vec![ReflectionViolation::OriginatorIsReceiver(router_id)]
+//! );
+//! ```
+
+use std::net::Ipv4Addr;
+
+use crate::{
+    path_attribute::{ClusterId, PathAttribute, PathAttributeValue},
+    BgpUpdateMessage,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Route reflection attributes that the receiving router would reject, or
+/// that no compliant reflector would send
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ReflectionViolation {
+    /// `CLUSTER_LIST` contains a cluster ID of the receiver, the route looped
+    /// back to the cluster it was reflected from
+    ClusterLoop(ClusterId),
+
+    /// `ORIGINATOR_ID` is the BGP identifier of the receiver, the route is
+    /// reflected back to the router that originated it
+    OriginatorIsReceiver(Ipv4Addr),
+
+    /// The same cluster ID appears more than once in `CLUSTER_LIST`
+    DuplicateClusterId(ClusterId),
+
+    /// `CLUSTER_LIST` is present without `ORIGINATOR_ID`, while reflectors
+    /// must set both when reflecting a route
+    MissingOriginator,
+}
+
+/// Checks the reflection attributes of the routes received by a router,
+/// identified by its BGP identifier and the cluster IDs it's configured with.
+/// When no cluster ID is given, the BGP identifier is used as the cluster ID,
+/// which is the default of most implementations.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ReflectionValidator {
+    router_id: Option<Ipv4Addr>,
+    cluster_ids: Vec<ClusterId>,
+}
+
+impl ReflectionValidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// BGP identifier of the receiving router
+    pub fn with_router_id(mut self, router_id: Ipv4Addr) -> Self {
+        self.router_id = Some(router_id);
+        self
+    }
+
+    /// Cluster ID the receiving router is part of, can be called more than
+    /// once for routers reflecting for many clusters
+    pub fn with_cluster_id(mut self, cluster_id: ClusterId) -> Self {
+        self.cluster_ids.push(cluster_id);
+        self
+    }
+
+    pub const fn router_id(&self) -> Option<Ipv4Addr> {
+        self.router_id
+    }
+
+    pub fn cluster_ids(&self) -> &[ClusterId] {
+        &self.cluster_ids
+    }
+
+    fn is_own_cluster(&self, cluster_id: &ClusterId) -> bool {
+        if self.cluster_ids.is_empty() {
+            self.router_id == Some(cluster_id.id())
+        } else {
+            self.cluster_ids.contains(cluster_id)
+        }
+    }
+
+    /// Violations found in the path attributes of a route, in the order of
+    /// the attributes
+    pub fn validate_attributes(
+        &self,
+        path_attributes: &[PathAttribute],
+    ) -> Vec<ReflectionViolation> {
+        let mut violations = vec![];
+        let mut has_originator = false;
+        let mut has_cluster_list = false;
+        for attr in path_attributes {
+            match attr.value() {
+                PathAttributeValue::Originator(originator) => {
+                    has_originator = true;
+                    if self.router_id == Some(originator.id()) {
+                        violations.push(ReflectionViolation::OriginatorIsReceiver(originator.id()));
+                    }
+                }
+                PathAttributeValue::ClusterList(cluster_list) => {
+                    has_cluster_list = true;
+                    let cluster_list = cluster_list.cluster_list();
+                    for (index, cluster_id) in cluster_list.iter().enumerate() {
+                        if self.is_own_cluster(cluster_id) {
+                            violations.push(ReflectionViolation::ClusterLoop(cluster_id.clone()));
+                        }
+                        // Report each duplicate once, on its first repetition
+                        if cluster_list[..index]
+                            .iter()
+                            .filter(|id| *id == cluster_id)
+                            .count()
+                            == 1
+                        {
+                            violations
+                                .push(ReflectionViolation::DuplicateClusterId(cluster_id.clone()));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if has_cluster_list && !has_originator {
+            violations.push(ReflectionViolation::MissingOriginator);
+        }
+        violations
+    }
+
+    /// Violations found in the path attributes of an update message
+    pub fn validate_update(&self, update: &BgpUpdateMessage) -> Vec<ReflectionViolation> {
+        self.validate_attributes(update.path_attributes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path_attribute::{ClusterList, Originator};
+
+    fn originator(id: Ipv4Addr) -> PathAttribute {
+        PathAttribute::from(
+            true,
+            false,
+            false,
+            false,
+            PathAttributeValue::Originator(Originator::new(id)),
+        )
+        .unwrap()
+    }
+
+    fn cluster_list(ids: &[Ipv4Addr]) -> PathAttribute {
+        PathAttribute::from(
+            true,
+            false,
+            false,
+            false,
+            PathAttributeValue::ClusterList(ClusterList::new(
+                ids.iter().map(|id| ClusterId::new(*id)).collect(),
+            )),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_reflection_validator() {
+        let router_id = Ipv4Addr::new(192, 0, 2, 1);
+        let other = Ipv4Addr::new(192, 0, 2, 2);
+        let cluster = Ipv4Addr::new(198, 51, 100, 1);
+        let validator = ReflectionValidator::new().with_router_id(router_id);
+
+        // Valid reflected route
+        assert_eq!(
+            validator.validate_attributes(&[originator(other), cluster_list(&[cluster])]),
+            vec![]
+        );
+        // The router ID is the default cluster ID
+        assert_eq!(
+            validator
+                .validate_attributes(&[originator(router_id), cluster_list(&[cluster, router_id])]),
+            vec![
+                ReflectionViolation::OriginatorIsReceiver(router_id),
+                ReflectionViolation::ClusterLoop(ClusterId::new(router_id)),
+            ]
+        );
+        assert_eq!(
+            validator.validate_attributes(&[cluster_list(&[cluster, other, cluster, cluster])]),
+            vec![
+                ReflectionViolation::DuplicateClusterId(ClusterId::new(cluster)),
+                ReflectionViolation::MissingOriginator,
+            ]
+        );
+
+        // Explicit cluster IDs replace the router ID
+        let validator = validator.with_cluster_id(ClusterId::new(cluster));
+        assert_eq!(
+            validator
+                .validate_attributes(&[originator(other), cluster_list(&[router_id, cluster])]),
+            vec![ReflectionViolation::ClusterLoop(ClusterId::new(cluster))]
+        );
+        assert_eq!(
+            validator.validate_update(&BgpUpdateMessage::new(vec![], vec![], vec![])),
+            vec![]
+        );
+    }
+}
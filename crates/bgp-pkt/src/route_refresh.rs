@@ -17,6 +17,7 @@
 
 use crate::iana::RouteRefreshSubcode;
 use netgauze_iana::address_family::AddressType;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 /// Route Refresh message as defined in
@@ -30,7 +31,8 @@ use serde::{Deserialize, Serialize};
 /// |      AFI      | S. typ.| SAFI  |
 /// +-------+-------+-------+-------+
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct BgpRouteRefreshMessage {
     address_type: AddressType,
@@ -0,0 +1,282 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! BGP conformance test vectors, each one is checked to round trip against
+//! this crate's wire encoder and decoder.
+//! Vectors carry a complete BGP message (including the BGP header) and are
+//! decoded with [`crate::wire::deserializer::BgpParsingContext::default()`],
+//! i.e., four-octet ASNs and no Add Path, unless stated otherwise.
+
+use std::net::Ipv4Addr;
+
+use ipnet::Ipv4Net;
+use netgauze_iana::address_family::{AddressFamily, AddressType};
+pub use netgauze_parse_utils::test_vectors::TestVector;
+
+use crate::{
+    capabilities::{
+        BgpCapability, ExtendedNextHopEncoding, ExtendedNextHopEncodingCapability,
+        FourOctetAsCapability, MultiProtocolExtensionsCapability,
+    },
+    iana::{RouteRefreshSubcode, AS_TRANS},
+    nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+    notification::CeaseError,
+    open::BgpOpenMessageParameter,
+    path_attribute::{
+        Aggregator, As2Aggregator, As4Aggregator, As4PathSegment, AsPath, AsPathSegmentType,
+        AtomicAggregate, NextHop, Origin, PathAttribute, PathAttributeValue,
+    },
+    BgpMessage, BgpNotificationMessage, BgpOpenMessage, BgpRouteRefreshMessage, BgpUpdateMessage,
+};
+
+const BGP_MARKER: [u8; 16] = [0xff; 16];
+
+fn with_marker(value: &[u8]) -> Vec<u8> {
+    let mut wire = BGP_MARKER.to_vec();
+    wire.extend_from_slice(value);
+    wire
+}
+
+fn ipv4_unicast(addr: Ipv4Addr, prefix_len: u8) -> Ipv4UnicastAddress {
+    // Test vectors use well-known valid unicast prefixes
+    Ipv4UnicastAddress::new_no_path_id(
+        Ipv4Unicast::from_net(Ipv4Net::new(addr, prefix_len).unwrap()).unwrap(),
+    )
+}
+
+fn well_known_attribute(extended_length: bool, value: PathAttributeValue) -> PathAttribute {
+    // Flags are always valid for the well-known attributes used in the vectors
+    PathAttribute::from(false, true, false, extended_length, value).unwrap()
+}
+
+fn optional_transitive_attribute(value: PathAttributeValue) -> PathAttribute {
+    // Flags are always valid for the optional attributes used in the vectors
+    PathAttribute::from(true, true, false, false, value).unwrap()
+}
+
+/// Test vectors for [`BgpMessage`]
+pub fn bgp_messages() -> Vec<TestVector<BgpMessage>> {
+    vec![
+        TestVector::new(
+            "keepalive",
+            with_marker(&[0x00, 0x13, 0x04]),
+            BgpMessage::KeepAlive,
+        ),
+        TestVector::new(
+            "open with capabilities",
+            with_marker(&[
+                0x00, 0x53, 0x01, 0x04, 0x00, 0x64, 0x00, 0xb4, 0x05, 0x05, 0x05, 0x05, 0x36, 0x02,
+                0x06, 0x01, 0x04, 0x00, 0x01, 0x00, 0x01, 0x02, 0x06, 0x01, 0x04, 0x00, 0x01, 0x00,
+                0x80, 0x02, 0x02, 0x80, 0x00, 0x02, 0x02, 0x02, 0x00, 0x02, 0x06, 0x41, 0x04, 0x00,
+                0x00, 0x00, 0x64, 0x02, 0x14, 0x05, 0x12, 0x00, 0x01, 0x00, 0x01, 0x00, 0x02, 0x00,
+                0x01, 0x00, 0x02, 0x00, 0x02, 0x00, 0x01, 0x00, 0x80, 0x00, 0x02,
+            ]),
+            BgpMessage::Open(BgpOpenMessage::new(
+                100,
+                180,
+                Ipv4Addr::new(5, 5, 5, 5),
+                vec![
+                    BgpOpenMessageParameter::Capabilities(vec![
+                        BgpCapability::MultiProtocolExtensions(
+                            MultiProtocolExtensionsCapability::new(AddressType::Ipv4Unicast),
+                        ),
+                    ]),
+                    BgpOpenMessageParameter::Capabilities(vec![
+                        BgpCapability::MultiProtocolExtensions(
+                            MultiProtocolExtensionsCapability::new(AddressType::Ipv4MplsLabeledVpn),
+                        ),
+                    ]),
+                    BgpOpenMessageParameter::Capabilities(vec![BgpCapability::CiscoRouteRefresh]),
+                    BgpOpenMessageParameter::Capabilities(vec![BgpCapability::RouteRefresh]),
+                    BgpOpenMessageParameter::Capabilities(vec![BgpCapability::FourOctetAs(
+                        FourOctetAsCapability::new(100),
+                    )]),
+                    BgpOpenMessageParameter::Capabilities(vec![
+                        BgpCapability::ExtendedNextHopEncoding(
+                            ExtendedNextHopEncodingCapability::new(vec![
+                                ExtendedNextHopEncoding::new(
+                                    AddressType::Ipv4Unicast,
+                                    AddressFamily::IPv6,
+                                ),
+                                ExtendedNextHopEncoding::new(
+                                    AddressType::Ipv4Multicast,
+                                    AddressFamily::IPv6,
+                                ),
+                                ExtendedNextHopEncoding::new(
+                                    AddressType::Ipv4MplsLabeledVpn,
+                                    AddressFamily::IPv6,
+                                ),
+                            ]),
+                        ),
+                    ]),
+                ],
+            )),
+        ),
+        TestVector::new(
+            "notification cease hard reset",
+            with_marker(&[0x00, 0x17, 0x03, 0x06, 0x09, 0x06, 0x03]),
+            BgpMessage::Notification(BgpNotificationMessage::CeaseError(CeaseError::HardReset {
+                value: vec![6, 3],
+            })),
+        ),
+        TestVector::new(
+            "route refresh",
+            with_marker(&[0x00, 0x17, 0x05, 0x00, 0x01, 0x00, 0x01]),
+            BgpMessage::RouteRefresh(BgpRouteRefreshMessage::new(
+                AddressType::Ipv4Unicast,
+                RouteRefreshSubcode::NormalRequest,
+            )),
+        ),
+        TestVector::new(
+            "empty update",
+            with_marker(&[0x00, 0x17, 0x02, 0x00, 0x00, 0x00, 0x00]),
+            BgpMessage::Update(BgpUpdateMessage::new(vec![], vec![], vec![])),
+        ),
+        TestVector::new(
+            "update withdraw",
+            with_marker(&[
+                0x00, 0x1b, 0x02, 0x00, 0x04, 0x18, 0xac, 0x10, 0x01, 0x00, 0x00,
+            ]),
+            BgpMessage::Update(BgpUpdateMessage::new(
+                vec![ipv4_unicast(Ipv4Addr::new(172, 16, 1, 0), 24)],
+                vec![],
+                vec![],
+            )),
+        ),
+        TestVector::new(
+            "update ipv4 unicast",
+            with_marker(&[
+                0x00, 0x40, 0x02, 0x00, 0x08, 0x18, 0xac, 0x10, 0x03, 0x18, 0xac, 0x10, 0x04, 0x00,
+                0x19, 0x40, 0x01, 0x01, 0x00, 0x50, 0x02, 0x00, 0x0a, 0x02, 0x02, 0x00, 0x00, 0x00,
+                0xc8, 0x00, 0x00, 0x00, 0x64, 0x40, 0x03, 0x04, 0xac, 0x10, 0x00, 0x14, 0x18, 0xac,
+                0x10, 0x01, 0x18, 0xac, 0x10, 0x02,
+            ]),
+            BgpMessage::Update(BgpUpdateMessage::new(
+                vec![
+                    ipv4_unicast(Ipv4Addr::new(172, 16, 3, 0), 24),
+                    ipv4_unicast(Ipv4Addr::new(172, 16, 4, 0), 24),
+                ],
+                vec![
+                    well_known_attribute(false, PathAttributeValue::Origin(Origin::IGP)),
+                    well_known_attribute(
+                        true,
+                        PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![
+                            As4PathSegment::new(AsPathSegmentType::AsSequence, vec![200, 100]),
+                        ])),
+                    ),
+                    well_known_attribute(
+                        false,
+                        PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(172, 16, 0, 20))),
+                    ),
+                ],
+                vec![
+                    ipv4_unicast(Ipv4Addr::new(172, 16, 1, 0), 24),
+                    ipv4_unicast(Ipv4Addr::new(172, 16, 2, 0), 24),
+                ],
+            )),
+        ),
+        TestVector::new(
+            "update aggregator",
+            with_marker(&[
+                0x00, 0x25, 0x02, 0x00, 0x00, 0x00, 0x0e, 0x40, 0x06, 0x00, 0xc0, 0x07, 0x08, 0x00,
+                0x01, 0x00, 0x0f, 0x0a, 0x00, 0x00, 0x01,
+            ]),
+            BgpMessage::Update(BgpUpdateMessage::new(
+                vec![],
+                vec![
+                    well_known_attribute(
+                        false,
+                        PathAttributeValue::AtomicAggregate(AtomicAggregate),
+                    ),
+                    optional_transitive_attribute(PathAttributeValue::Aggregator(
+                        Aggregator::As4Aggregator(As4Aggregator::new(
+                            65551,
+                            Ipv4Addr::new(10, 0, 0, 1),
+                        )),
+                    )),
+                ],
+                vec![],
+            )),
+        ),
+    ]
+}
+
+/// Test vectors for [`BgpMessage`] of sessions with two-octet ASNs, decoded
+/// with [`crate::wire::deserializer::BgpParsingContext::asn2_default()`]
+pub fn bgp_asn2_messages() -> Vec<TestVector<BgpMessage>> {
+    vec![TestVector::new(
+        "update aggregator with as4 aggregator",
+        with_marker(&[
+            0x00, 0x2e, 0x02, 0x00, 0x00, 0x00, 0x17, 0x40, 0x06, 0x00, 0xc0, 0x07, 0x06, 0x5b,
+            0xa0, 0x0a, 0x00, 0x00, 0x01, 0xc0, 0x12, 0x08, 0xfa, 0x56, 0xea, 0x00, 0x0a, 0x00,
+            0x00, 0x01,
+        ]),
+        BgpMessage::Update(BgpUpdateMessage::new(
+            vec![],
+            vec![
+                well_known_attribute(false, PathAttributeValue::AtomicAggregate(AtomicAggregate)),
+                optional_transitive_attribute(PathAttributeValue::Aggregator(
+                    Aggregator::As2Aggregator(As2Aggregator::new(
+                        AS_TRANS,
+                        Ipv4Addr::new(10, 0, 0, 1),
+                    )),
+                )),
+                optional_transitive_attribute(PathAttributeValue::As4Aggregator(
+                    As4Aggregator::new(4200000000, Ipv4Addr::new(10, 0, 0, 1)),
+                )),
+            ],
+            vec![],
+        )),
+    )]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wire::deserializer::BgpParsingContext;
+    use netgauze_parse_utils::test_helpers::{test_parsed_completely_with_one_input, test_write};
+
+    #[test]
+    fn test_bgp_messages_vectors() {
+        for vector in bgp_messages() {
+            test_parsed_completely_with_one_input(
+                vector.wire(),
+                &mut BgpParsingContext::default(),
+                vector.expected(),
+            );
+            assert!(
+                test_write(vector.expected(), vector.wire()).is_ok(),
+                "Failed writing vector: {}",
+                vector.name()
+            );
+        }
+    }
+
+    #[test]
+    fn test_bgp_asn2_messages_vectors() {
+        for vector in bgp_asn2_messages() {
+            test_parsed_completely_with_one_input(
+                vector.wire(),
+                &mut BgpParsingContext::asn2_default(),
+                vector.expected(),
+            );
+            assert!(
+                test_write(vector.expected(), vector.wire()).is_ok(),
+                "Failed writing vector: {}",
+                vector.name()
+            );
+        }
+    }
+}
@@ -15,11 +15,24 @@
 
 //! Representations for BGP Update message
 
-use crate::nlri::Ipv4UnicastAddress;
+use crate::nlri::*;
+use ipnet::IpNet;
 use netgauze_iana::address_family::AddressType;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::path_attribute::{MpUnreach, PathAttribute, PathAttributeValue};
+use crate::{
+    community::{FlowSpecAction, LinkBandwidth},
+    iana::{PathAttributeType, AS_TRANS},
+    path_attribute::{Aggregator, MpReach, MpUnreach, PathAttribute, PathAttributeValue},
+    SmallList,
+};
+#[cfg(feature = "wire")]
+use netgauze_parse_utils::WritablePdu;
+use std::net::IpAddr;
+
+pub(crate) type Ipv4NlriList = SmallList<Ipv4UnicastAddress, 2>;
+pub(crate) type PathAttributeList = SmallList<PathAttribute, 4>;
 
 /// UPDATE messages are used to transfer routing information between BGP peers
 /// as defined by [RFC4271](https://datatracker.ietf.org/doc/html/RFC4271).
@@ -37,20 +50,52 @@ use crate::path_attribute::{MpUnreach, PathAttribute, PathAttributeValue};
 /// |   Network Layer Reachability Information (variable) |
 /// +-----------------------------------------------------+
 /// ```
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct BgpUpdateMessage {
-    withdrawn_routes: Vec<Ipv4UnicastAddress>,
-    path_attributes: Vec<PathAttribute>,
-    nlri: Vec<Ipv4UnicastAddress>,
+    withdrawn_routes: Ipv4NlriList,
+    path_attributes: PathAttributeList,
+    nlri: Ipv4NlriList,
 }
 
 impl BgpUpdateMessage {
+    /// Prefixes are kept as is, including those with host bits set, use
+    /// [`Self::new_with_host_bits_policy`] to check them.
     #[inline]
     pub fn new(
         withdrawn_routes: Vec<Ipv4UnicastAddress>,
         path_attributes: Vec<PathAttribute>,
         nlri: Vec<Ipv4UnicastAddress>,
+    ) -> Self {
+        // Collecting keeps the vector's allocation when the `smallvec` feature is
+        // disabled
+        Self::from_lists(
+            withdrawn_routes.into_iter().collect(),
+            path_attributes.into_iter().collect(),
+            nlri.into_iter().collect(),
+        )
+    }
+
+    /// Same as [`Self::new`], then apply `policy` to all the IP prefixes of the
+    /// update, see [`Self::apply_host_bits_policy`]. Returns the first
+    /// rejected prefix as error.
+    pub fn new_with_host_bits_policy(
+        withdrawn_routes: Vec<Ipv4UnicastAddress>,
+        path_attributes: Vec<PathAttribute>,
+        nlri: Vec<Ipv4UnicastAddress>,
+        policy: HostBitsPolicy,
+    ) -> Result<Self, IpNet> {
+        let mut update = Self::new(withdrawn_routes, path_attributes, nlri);
+        update.apply_host_bits_policy(policy)?;
+        Ok(update)
+    }
+
+    #[inline]
+    pub(crate) const fn from_lists(
+        withdrawn_routes: Ipv4NlriList,
+        path_attributes: PathAttributeList,
+        nlri: Ipv4NlriList,
     ) -> Self {
         BgpUpdateMessage {
             withdrawn_routes,
@@ -58,19 +103,233 @@ impl BgpUpdateMessage {
             nlri,
         }
     }
-    pub const fn withdraw_routes(&self) -> &Vec<Ipv4UnicastAddress> {
+
+    pub fn withdraw_routes(&self) -> &[Ipv4UnicastAddress] {
         &self.withdrawn_routes
     }
 
-    pub const fn path_attributes(&self) -> &Vec<PathAttribute> {
+    /// Path attributes in the order they're carried in the message. Parsing
+    /// and writing never reorder them, so an update that is passed through is
+    /// written back byte for byte, see [`Self::sort_path_attributes`] for the
+    /// canonical order.
+    pub fn path_attributes(&self) -> &[PathAttribute] {
         &self.path_attributes
     }
 
     #[inline]
-    pub const fn nlri(&self) -> &Vec<Ipv4UnicastAddress> {
+    pub fn nlri(&self) -> &[Ipv4UnicastAddress] {
         &self.nlri
     }
 
+    /// Return the well-known mandatory attributes that are missing from the
+    /// update.
+    ///
+    /// RFC4271: ORIGIN and AS_PATH are required in every UPDATE message that
+    /// carries reachable NLRI (in the NLRI field or in MP_REACH_NLRI
+    /// [RFC4760](https://datatracker.ietf.org/doc/html/RFC4760)). NEXT_HOP is
+    /// only required when the NLRI field isn't empty, since MP_REACH_NLRI
+    /// carries its own next hop.
+    pub fn missing_mandatory_attributes(&self) -> impl Iterator<Item = PathAttributeType> + '_ {
+        let has_mp_reach = self
+            .path_attributes
+            .iter()
+            .any(|attr| matches!(attr.value(), PathAttributeValue::MpReach(_)));
+        let reachable = !self.nlri.is_empty() || has_mp_reach;
+        [
+            PathAttributeType::Origin,
+            PathAttributeType::AsPath,
+            PathAttributeType::NextHop,
+        ]
+        .into_iter()
+        .filter(move |attr_type| {
+            reachable
+                && (*attr_type != PathAttributeType::NextHop || !self.nlri.is_empty())
+                && !self
+                    .path_attributes
+                    .iter()
+                    .any(|attr| attr.path_attribute_type() == Ok(*attr_type))
+        })
+    }
+
+    /// Order the path attributes in ascending order of their type code, as
+    /// [RFC4271](https://datatracker.ietf.org/doc/html/RFC4271) recommends
+    /// for senders. Attributes of the same type keep their relative order.
+    pub fn sort_path_attributes(&mut self) {
+        self.path_attributes
+            .sort_by_key(|attr| match attr.path_attribute_type() {
+                Ok(attr_type) => u8::from(attr_type),
+                Err(code) => code,
+            });
+    }
+
+    /// IP prefixes of the announced and withdrawn routes that have bits set
+    /// beyond their prefix length, see [`HostBitsPolicy`]
+    pub fn prefixes_with_host_bits(&self) -> impl Iterator<Item = IpNet> + '_ {
+        self.announced()
+            .chain(self.withdrawn())
+            .filter_map(|route| route.prefix().ip_net())
+            .filter(|net| net.trunc() != *net)
+    }
+
+    /// Apply the policy to all the IP prefixes of the update, both in the NLRI
+    /// fields and in the MP_REACH_NLRI/MP_UNREACH_NLRI attributes. Returns the
+    /// original prefixes that were normalized, or the first rejected prefix as
+    /// error.
+    pub fn apply_host_bits_policy(&mut self, policy: HostBitsPolicy) -> Result<Vec<IpNet>, IpNet> {
+        match policy {
+            HostBitsPolicy::Accept => Ok(vec![]),
+            HostBitsPolicy::Reject => match self.prefixes_with_host_bits().next() {
+                Some(net) => Err(net),
+                None => Ok(vec![]),
+            },
+            HostBitsPolicy::Normalize => {
+                let mut normalized = normalize_host_bits(&mut self.withdrawn_routes);
+                normalized.extend(normalize_host_bits(&mut self.nlri));
+                for attr in &mut self.path_attributes {
+                    match attr.value_mut() {
+                        PathAttributeValue::MpReach(mp_reach) => {
+                            normalized.extend(mp_reach.normalize_host_bits())
+                        }
+                        PathAttributeValue::MpUnreach(mp_unreach) => {
+                            normalized.extend(mp_unreach.normalize_host_bits())
+                        }
+                        _ => {}
+                    }
+                }
+                Ok(normalized)
+            }
+        }
+    }
+
+    /// Iterate over all the routes announced by the update, both in the NLRI
+    /// field and in the MP_REACH_NLRI attributes.
+    ///
+    /// Routes in the NLRI field take their next hop from the NEXT_HOP
+    /// attribute. MP_REACH_NLRI with an unknown address family are skipped.
+    pub fn announced(&self) -> impl Iterator<Item = Route<'_>> {
+        let next_hop = self
+            .path_attributes
+            .iter()
+            .find_map(|attr| match attr.value() {
+                PathAttributeValue::NextHop(next_hop) => Some(IpAddr::V4(next_hop.next_hop())),
+                _ => None,
+            });
+        let mp_reach = self
+            .path_attributes
+            .iter()
+            .flat_map(|attr| match attr.value() {
+                PathAttributeValue::MpReach(mp_reach) => mp_reach_routes(mp_reach),
+                _ => Box::new(std::iter::empty()),
+            });
+        routes(
+            AddressType::Ipv4Unicast,
+            next_hop,
+            &self.nlri,
+            RoutePrefix::Ipv4Unicast,
+        )
+        .chain(mp_reach)
+    }
+
+    /// Iterate over all the routes withdrawn by the update, both in the
+    /// withdrawn routes field and in the MP_UNREACH_NLRI attributes.
+    ///
+    /// MP_UNREACH_NLRI with an unknown address family are skipped.
+    pub fn withdrawn(&self) -> impl Iterator<Item = Route<'_>> {
+        let mp_unreach = self
+            .path_attributes
+            .iter()
+            .flat_map(|attr| match attr.value() {
+                PathAttributeValue::MpUnreach(mp_unreach) => mp_unreach_routes(mp_unreach),
+                _ => Box::new(std::iter::empty()),
+            });
+        routes(
+            AddressType::Ipv4Unicast,
+            None,
+            &self.withdrawn_routes,
+            RoutePrefix::Ipv4Unicast,
+        )
+        .chain(mp_unreach)
+    }
+
+    /// Link bandwidth extended communities carried by the update.
+    ///
+    /// Neighbors with four-octet AS numbers advertise the link bandwidth with
+    /// [`AS_TRANS`] in place of their AS number, which is replaced by the
+    /// neighbor AS from the AS_PATH (or AS4_PATH) when it's known.
+    pub fn link_bandwidths(&self) -> impl Iterator<Item = LinkBandwidth> + '_ {
+        let mut as_path_neighbor = None;
+        let mut as4_path_neighbor = None;
+        for attr in &self.path_attributes {
+            match attr.value() {
+                PathAttributeValue::AsPath(as_path) => as_path_neighbor = as_path.neighbor_as(),
+                PathAttributeValue::As4Path(as4_path) => as4_path_neighbor = as4_path.neighbor_as(),
+                _ => {}
+            }
+        }
+        let neighbor_as = as_path_neighbor
+            .filter(|asn| *asn != AS_TRANS as u32)
+            .or(as4_path_neighbor);
+        self.path_attributes
+            .iter()
+            .flat_map(|attr| match attr.value() {
+                PathAttributeValue::ExtendedCommunities(communities) => communities.communities(),
+                _ => &[],
+            })
+            .filter_map(LinkBandwidth::from_extended_community)
+            .map(move |link_bandwidth| match neighbor_as {
+                Some(asn) if link_bandwidth.asn() == AS_TRANS as u32 => {
+                    LinkBandwidth::new(asn, link_bandwidth.bandwidth())
+                }
+                _ => link_bandwidth,
+            })
+    }
+
+    /// Flow Spec actions carried in the extended communities of the update,
+    /// they apply to the traffic matched by the announced Flow Spec NLRI
+    pub fn flow_spec_actions(&self) -> impl Iterator<Item = FlowSpecAction> + '_ {
+        self.path_attributes
+            .iter()
+            .flat_map(|attr| match attr.value() {
+                PathAttributeValue::ExtendedCommunities(communities) => communities.communities(),
+                _ => &[],
+            })
+            .filter_map(FlowSpecAction::from_extended_community)
+    }
+
+    /// Total link bandwidth in bytes per second of the path advertised by the
+    /// update, to weight it against the other paths to the same destination
+    /// with [`crate::community::link_bandwidth_shares`]. `None` when the
+    /// update doesn't carry any link bandwidth.
+    pub fn link_bandwidth(&self) -> Option<f32> {
+        self.link_bandwidths()
+            .map(|link_bandwidth| link_bandwidth.bandwidth())
+            .reduce(|total, bandwidth| total + bandwidth)
+    }
+
+    /// AGGREGATOR carried by the update, reconciled with the AS4_AGGREGATOR
+    /// for updates received in 2-octet ASN sessions, see
+    /// [`Aggregator::reconcile`].
+    pub fn aggregator(&self) -> Option<Aggregator> {
+        let mut aggregator = None;
+        let mut as4_aggregator = None;
+        for attr in &self.path_attributes {
+            match attr.value() {
+                PathAttributeValue::Aggregator(value) => aggregator = Some(value),
+                PathAttributeValue::As4Aggregator(value) => as4_aggregator = Some(value),
+                _ => {}
+            }
+        }
+        aggregator.map(|aggregator| aggregator.reconcile(as4_aggregator))
+    }
+
+    /// The route is an aggregate that lost some of the AS_PATH information of
+    /// the aggregated routes, i.e., the update carries ATOMIC_AGGREGATE
+    pub fn atomic_aggregate(&self) -> bool {
+        self.path_attributes
+            .iter()
+            .any(|attr| matches!(attr.value(), PathAttributeValue::AtomicAggregate(_)))
+    }
+
     /// Return address family of End-Of-RIB (EoR) messages or `None` if the
     /// update message is not EoR
     ///
@@ -164,6 +423,16 @@ impl BgpUpdateMessage {
                             current = Some(AddressType::BgpLsVpn)
                         }
                     }
+                    MpUnreach::Ipv4FlowSpec { nlri } => {
+                        if nlri.is_empty() {
+                            current = Some(AddressType::Ipv4FlowSpec)
+                        }
+                    }
+                    MpUnreach::Ipv6FlowSpec { nlri } => {
+                        if nlri.is_empty() {
+                            current = Some(AddressType::Ipv6FlowSpec)
+                        }
+                    }
                     MpUnreach::Unknown { .. } => {
                         // For unknown address families we assume it's not EoR, as they might have
                         // different semantics defined.
@@ -176,6 +445,335 @@ impl BgpUpdateMessage {
     }
 }
 
+/// A single route carried in a [`BgpUpdateMessage`], see
+/// [`BgpUpdateMessage::announced`] and [`BgpUpdateMessage::withdrawn`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Route<'a> {
+    address_type: AddressType,
+    prefix: RoutePrefix<'a>,
+    next_hop: Option<IpAddr>,
+}
+
+impl<'a> Route<'a> {
+    pub const fn new(
+        address_type: AddressType,
+        prefix: RoutePrefix<'a>,
+        next_hop: Option<IpAddr>,
+    ) -> Self {
+        Self {
+            address_type,
+            prefix,
+            next_hop,
+        }
+    }
+
+    pub const fn address_type(&self) -> AddressType {
+        self.address_type
+    }
+
+    /// [RFC7911](https://datatracker.ietf.org/doc/html/rfc7911) ADD-PATH path identifier
+    pub fn path_id(&self) -> Option<u32> {
+        self.prefix.path_id()
+    }
+
+    pub const fn prefix(&self) -> RoutePrefix<'a> {
+        self.prefix
+    }
+
+    /// Next hop of announced routes, `None` for withdrawn routes
+    pub const fn next_hop(&self) -> Option<IpAddr> {
+        self.next_hop
+    }
+}
+
+/// NLRI of a [`Route`] in any of the supported encodings
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum RoutePrefix<'a> {
+    Ipv4Unicast(&'a Ipv4UnicastAddress),
+    Ipv4Multicast(&'a Ipv4MulticastAddress),
+    Ipv4NlriMplsLabels(&'a Ipv4NlriMplsLabelsAddress),
+    Ipv4MplsVpnUnicast(&'a Ipv4MplsVpnUnicastAddress),
+    Ipv6Unicast(&'a Ipv6UnicastAddress),
+    Ipv6Multicast(&'a Ipv6MulticastAddress),
+    Ipv6NlriMplsLabels(&'a Ipv6NlriMplsLabelsAddress),
+    Ipv6MplsVpnUnicast(&'a Ipv6MplsVpnUnicastAddress),
+    L2Evpn(&'a L2EvpnAddress),
+    RouteTargetMembership(&'a RouteTargetMembershipAddress),
+    BgpLs(&'a BgpLsNlri),
+    BgpLsVpn(&'a BgpLsVpnNlri),
+    FlowSpec(&'a FlowSpecNlri),
+}
+
+impl RoutePrefix<'_> {
+    pub fn path_id(&self) -> Option<u32> {
+        match self {
+            Self::Ipv4Unicast(addr) => addr.path_id(),
+            Self::Ipv4Multicast(addr) => addr.path_id(),
+            Self::Ipv4NlriMplsLabels(addr) => addr.path_id(),
+            Self::Ipv4MplsVpnUnicast(addr) => addr.path_id(),
+            Self::Ipv6Unicast(addr) => addr.path_id(),
+            Self::Ipv6Multicast(addr) => addr.path_id(),
+            Self::Ipv6NlriMplsLabels(addr) => addr.path_id(),
+            Self::Ipv6MplsVpnUnicast(addr) => addr.path_id(),
+            Self::L2Evpn(addr) => addr.path_id().copied(),
+            Self::RouteTargetMembership(addr) => addr.path_id(),
+            Self::BgpLs(nlri) => nlri.path_id,
+            Self::BgpLsVpn(nlri) => nlri.path_id,
+            Self::FlowSpec(_) => None,
+        }
+    }
+
+    /// IP prefix of the route, `None` for the NLRI that don't carry one, such
+    /// as EVPN, route target membership, BGP-LS, and Flow Spec
+    pub const fn ip_net(&self) -> Option<IpNet> {
+        match self {
+            Self::Ipv4Unicast(addr) => Some(IpNet::V4(addr.network().address())),
+            Self::Ipv4Multicast(addr) => Some(IpNet::V4(addr.network().address())),
+            Self::Ipv4NlriMplsLabels(addr) => Some(IpNet::V4(addr.prefix())),
+            Self::Ipv4MplsVpnUnicast(addr) => Some(IpNet::V4(addr.network().address())),
+            Self::Ipv6Unicast(addr) => Some(IpNet::V6(addr.network().address())),
+            Self::Ipv6Multicast(addr) => Some(IpNet::V6(addr.network().address())),
+            Self::Ipv6NlriMplsLabels(addr) => Some(IpNet::V6(addr.prefix())),
+            Self::Ipv6MplsVpnUnicast(addr) => Some(IpNet::V6(addr.network().address())),
+            Self::L2Evpn(_)
+            | Self::RouteTargetMembership(_)
+            | Self::BgpLs(_)
+            | Self::BgpLsVpn(_)
+            | Self::FlowSpec(_) => None,
+        }
+    }
+
+    /// Route distinguisher of the VPN IP prefixes
+    pub const fn rd(&self) -> Option<RouteDistinguisher> {
+        match self {
+            Self::Ipv4MplsVpnUnicast(addr) => Some(addr.rd()),
+            Self::Ipv6MplsVpnUnicast(addr) => Some(addr.rd()),
+            _ => None,
+        }
+    }
+
+    /// Number of octets the NLRI takes on the wire, including the ADD-PATH
+    /// path identifier when present
+    #[cfg(feature = "wire")]
+    pub fn wire_len(&self) -> usize {
+        match self {
+            Self::Ipv4Unicast(addr) => addr.len(),
+            Self::Ipv4Multicast(addr) => addr.len(),
+            Self::Ipv4NlriMplsLabels(addr) => addr.len(),
+            Self::Ipv4MplsVpnUnicast(addr) => addr.len(),
+            Self::Ipv6Unicast(addr) => addr.len(),
+            Self::Ipv6Multicast(addr) => addr.len(),
+            Self::Ipv6NlriMplsLabels(addr) => addr.len(),
+            Self::Ipv6MplsVpnUnicast(addr) => addr.len(),
+            Self::L2Evpn(addr) => addr.len(),
+            Self::RouteTargetMembership(addr) => addr.len(),
+            Self::BgpLs(nlri) => nlri.len(),
+            Self::BgpLsVpn(nlri) => nlri.len(),
+            Self::FlowSpec(nlri) => nlri.len(),
+        }
+    }
+}
+
+type Routes<'a> = Box<dyn Iterator<Item = Route<'a>> + 'a>;
+
+fn routes<'a, T>(
+    address_type: AddressType,
+    next_hop: Option<IpAddr>,
+    nlri: &'a [T],
+    prefix: fn(&'a T) -> RoutePrefix<'a>,
+) -> impl Iterator<Item = Route<'a>> {
+    nlri.iter()
+        .map(move |nlri| Route::new(address_type, prefix(nlri), next_hop))
+}
+
+fn mp_reach_routes(mp_reach: &MpReach) -> Routes<'_> {
+    match mp_reach {
+        MpReach::Ipv4Unicast { next_hop, nlri, .. } => Box::new(routes(
+            AddressType::Ipv4Unicast,
+            Some(*next_hop),
+            nlri,
+            RoutePrefix::Ipv4Unicast,
+        )),
+        MpReach::Ipv4Multicast { next_hop, nlri, .. } => Box::new(routes(
+            AddressType::Ipv4Multicast,
+            Some(*next_hop),
+            nlri,
+            RoutePrefix::Ipv4Multicast,
+        )),
+        MpReach::Ipv4NlriMplsLabels { next_hop, nlri, .. } => Box::new(routes(
+            AddressType::Ipv4NlriMplsLabels,
+            Some(*next_hop),
+            nlri,
+            RoutePrefix::Ipv4NlriMplsLabels,
+        )),
+        MpReach::Ipv4MplsVpnUnicast { next_hop, nlri } => Box::new(routes(
+            AddressType::Ipv4MplsLabeledVpn,
+            Some(next_hop.next_hop()),
+            nlri,
+            RoutePrefix::Ipv4MplsVpnUnicast,
+        )),
+        MpReach::Ipv6Unicast {
+            next_hop_global,
+            nlri,
+            ..
+        } => Box::new(routes(
+            AddressType::Ipv6Unicast,
+            Some(IpAddr::V6(*next_hop_global)),
+            nlri,
+            RoutePrefix::Ipv6Unicast,
+        )),
+        MpReach::Ipv6Multicast {
+            next_hop_global,
+            nlri,
+            ..
+        } => Box::new(routes(
+            AddressType::Ipv6Multicast,
+            Some(IpAddr::V6(*next_hop_global)),
+            nlri,
+            RoutePrefix::Ipv6Multicast,
+        )),
+        MpReach::Ipv6NlriMplsLabels { next_hop, nlri, .. } => Box::new(routes(
+            AddressType::Ipv6NlriMplsLabels,
+            Some(*next_hop),
+            nlri,
+            RoutePrefix::Ipv6NlriMplsLabels,
+        )),
+        MpReach::Ipv6MplsVpnUnicast { next_hop, nlri } => Box::new(routes(
+            AddressType::Ipv6MplsLabeledVpn,
+            Some(next_hop.next_hop()),
+            nlri,
+            RoutePrefix::Ipv6MplsVpnUnicast,
+        )),
+        MpReach::L2Evpn { next_hop, nlri } => Box::new(routes(
+            AddressType::L2VpnBgpEvpn,
+            Some(*next_hop),
+            nlri,
+            RoutePrefix::L2Evpn,
+        )),
+        MpReach::RouteTargetMembership { next_hop, nlri } => Box::new(routes(
+            AddressType::RouteTargetConstrains,
+            Some(*next_hop),
+            nlri,
+            RoutePrefix::RouteTargetMembership,
+        )),
+        MpReach::BgpLs { next_hop, nlri } => Box::new(routes(
+            AddressType::BgpLs,
+            Some(*next_hop),
+            nlri,
+            RoutePrefix::BgpLs,
+        )),
+        MpReach::BgpLsVpn { next_hop, nlri } => Box::new(routes(
+            AddressType::BgpLsVpn,
+            Some(next_hop.next_hop()),
+            nlri,
+            RoutePrefix::BgpLsVpn,
+        )),
+        MpReach::Ipv4FlowSpec { next_hop, nlri } => Box::new(routes(
+            AddressType::Ipv4FlowSpec,
+            *next_hop,
+            nlri,
+            RoutePrefix::FlowSpec,
+        )),
+        MpReach::Ipv6FlowSpec { next_hop, nlri } => Box::new(routes(
+            AddressType::Ipv6FlowSpec,
+            *next_hop,
+            nlri,
+            RoutePrefix::FlowSpec,
+        )),
+        MpReach::Unknown { .. } => Box::new(std::iter::empty()),
+    }
+}
+
+fn mp_unreach_routes(mp_unreach: &MpUnreach) -> Routes<'_> {
+    match mp_unreach {
+        MpUnreach::Ipv4Unicast { nlri } => Box::new(routes(
+            AddressType::Ipv4Unicast,
+            None,
+            nlri,
+            RoutePrefix::Ipv4Unicast,
+        )),
+        MpUnreach::Ipv4Multicast { nlri } => Box::new(routes(
+            AddressType::Ipv4Multicast,
+            None,
+            nlri,
+            RoutePrefix::Ipv4Multicast,
+        )),
+        MpUnreach::Ipv4NlriMplsLabels { nlri } => Box::new(routes(
+            AddressType::Ipv4NlriMplsLabels,
+            None,
+            nlri,
+            RoutePrefix::Ipv4NlriMplsLabels,
+        )),
+        MpUnreach::Ipv4MplsVpnUnicast { nlri } => Box::new(routes(
+            AddressType::Ipv4MplsLabeledVpn,
+            None,
+            nlri,
+            RoutePrefix::Ipv4MplsVpnUnicast,
+        )),
+        MpUnreach::Ipv6Unicast { nlri } => Box::new(routes(
+            AddressType::Ipv6Unicast,
+            None,
+            nlri,
+            RoutePrefix::Ipv6Unicast,
+        )),
+        MpUnreach::Ipv6Multicast { nlri } => Box::new(routes(
+            AddressType::Ipv6Multicast,
+            None,
+            nlri,
+            RoutePrefix::Ipv6Multicast,
+        )),
+        // MP_UNREACH_NLRI represents the IPv6 labeled NLRI with the IPv4 type
+        MpUnreach::Ipv6NlriMplsLabels { nlri } => Box::new(routes(
+            AddressType::Ipv6NlriMplsLabels,
+            None,
+            nlri,
+            RoutePrefix::Ipv4NlriMplsLabels,
+        )),
+        MpUnreach::Ipv6MplsVpnUnicast { nlri } => Box::new(routes(
+            AddressType::Ipv6MplsLabeledVpn,
+            None,
+            nlri,
+            RoutePrefix::Ipv6MplsVpnUnicast,
+        )),
+        MpUnreach::L2Evpn { nlri } => Box::new(routes(
+            AddressType::L2VpnBgpEvpn,
+            None,
+            nlri,
+            RoutePrefix::L2Evpn,
+        )),
+        MpUnreach::RouteTargetMembership { nlri } => Box::new(routes(
+            AddressType::RouteTargetConstrains,
+            None,
+            nlri,
+            RoutePrefix::RouteTargetMembership,
+        )),
+        MpUnreach::BgpLs { nlri } => {
+            Box::new(routes(AddressType::BgpLs, None, nlri, RoutePrefix::BgpLs))
+        }
+        MpUnreach::BgpLsVpn { nlri } => Box::new(routes(
+            AddressType::BgpLsVpn,
+            None,
+            nlri,
+            RoutePrefix::BgpLsVpn,
+        )),
+        MpUnreach::Ipv4FlowSpec { nlri } => Box::new(routes(
+            AddressType::Ipv4FlowSpec,
+            None,
+            nlri,
+            RoutePrefix::FlowSpec,
+        )),
+        MpUnreach::Ipv6FlowSpec { nlri } => Box::new(routes(
+            AddressType::Ipv6FlowSpec,
+            None,
+            nlri,
+            RoutePrefix::FlowSpec,
+        )),
+        MpUnreach::Unknown { .. } => Box::new(std::iter::empty()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -184,8 +782,81 @@ mod tests {
             Ipv4MplsVpnUnicastAddress, Ipv4Unicast, LabeledIpv6NextHop, LabeledNextHop, MplsLabel,
             RouteDistinguisher,
         },
-        path_attribute::MpReach,
+        path_attribute::{
+            As2Aggregator, As4Aggregator, As4PathSegment, AsPath, AsPathSegmentType,
+            AtomicAggregate, ExtendedCommunities, MpReach, NextHop, Origin,
+        },
     };
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_missing_mandatory_attributes() {
+        let withdraw = BgpUpdateMessage::new(
+            vec![Ipv4UnicastAddress::new_no_path_id(
+                Ipv4Unicast::from_net("192.168.0.0/24".parse().unwrap()).unwrap(),
+            )],
+            vec![],
+            vec![],
+        );
+        let mp_reach = BgpUpdateMessage::new(
+            vec![],
+            vec![
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::Origin(Origin::IGP),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    true,
+                    false,
+                    false,
+                    false,
+                    PathAttributeValue::MpReach(MpReach::Ipv6Unicast {
+                        next_hop_global: "fc00::1".parse().unwrap(),
+                        next_hop_local: None,
+                        nlri: vec![],
+                    }),
+                )
+                .unwrap(),
+            ],
+            vec![],
+        );
+        assert_eq!(withdraw.missing_mandatory_attributes().count(), 0);
+        // NEXT_HOP is not required for MP_REACH_NLRI
+        assert_eq!(
+            mp_reach.missing_mandatory_attributes().collect::<Vec<_>>(),
+            vec![PathAttributeType::AsPath]
+        );
+    }
+
+    #[test]
+    fn test_new_with_host_bits_policy() {
+        let dirty = Ipv4UnicastAddress::new_no_path_id(
+            Ipv4Unicast::from_net("192.0.2.1/24".parse().unwrap()).unwrap(),
+        );
+        let clean = Ipv4UnicastAddress::new_no_path_id(
+            Ipv4Unicast::from_net("192.0.2.0/24".parse().unwrap()).unwrap(),
+        );
+        let new = |policy| {
+            BgpUpdateMessage::new_with_host_bits_policy(vec![dirty], vec![], vec![], policy)
+        };
+
+        assert_eq!(
+            new(HostBitsPolicy::Accept),
+            Ok(BgpUpdateMessage::new(vec![dirty], vec![], vec![]))
+        );
+        assert_eq!(
+            new(HostBitsPolicy::Normalize),
+            Ok(BgpUpdateMessage::new(vec![clean], vec![], vec![]))
+        );
+        assert_eq!(
+            new(HostBitsPolicy::Reject),
+            Err("192.0.2.1/24".parse().unwrap())
+        );
+    }
 
     #[test]
     fn test_end_of_rib() {
@@ -320,4 +991,223 @@ mod tests {
             Some(AddressType::Ipv6Multicast)
         );
     }
+
+    #[test]
+    fn test_announced_and_withdrawn() {
+        let update = BgpUpdateMessage::new(
+            vec![Ipv4UnicastAddress::new_no_path_id(
+                Ipv4Unicast::from_net("192.0.2.0/24".parse().unwrap()).unwrap(),
+            )],
+            vec![
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::NextHop(NextHop::new("198.51.100.1".parse().unwrap())),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    true,
+                    false,
+                    false,
+                    false,
+                    PathAttributeValue::MpReach(MpReach::Ipv6Unicast {
+                        next_hop_global: "fc00::1".parse().unwrap(),
+                        next_hop_local: None,
+                        nlri: vec![Ipv6UnicastAddress::new(
+                            Some(2),
+                            Ipv6Unicast::from_net("2001:db8::/32".parse().unwrap()).unwrap(),
+                        )],
+                    }),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    true,
+                    false,
+                    false,
+                    false,
+                    PathAttributeValue::MpUnreach(MpUnreach::Ipv6Unicast {
+                        nlri: vec![Ipv6UnicastAddress::new(
+                            None,
+                            Ipv6Unicast::from_net("2001:db8:1::/48".parse().unwrap()).unwrap(),
+                        )],
+                    }),
+                )
+                .unwrap(),
+            ],
+            vec![Ipv4UnicastAddress::new(
+                Some(1),
+                Ipv4Unicast::from_net("203.0.113.0/24".parse().unwrap()).unwrap(),
+            )],
+        );
+        let view = |route: Route<'_>| {
+            (
+                route.address_type(),
+                route.path_id(),
+                route.prefix().ip_net(),
+                route.next_hop(),
+            )
+        };
+        assert_eq!(
+            update.announced().map(view).collect::<Vec<_>>(),
+            vec![
+                (
+                    AddressType::Ipv4Unicast,
+                    Some(1),
+                    Some("203.0.113.0/24".parse().unwrap()),
+                    Some("198.51.100.1".parse().unwrap()),
+                ),
+                (
+                    AddressType::Ipv6Unicast,
+                    Some(2),
+                    Some("2001:db8::/32".parse().unwrap()),
+                    Some("fc00::1".parse().unwrap()),
+                ),
+            ]
+        );
+        assert_eq!(
+            update.withdrawn().map(view).collect::<Vec<_>>(),
+            vec![
+                (
+                    AddressType::Ipv4Unicast,
+                    None,
+                    Some("192.0.2.0/24".parse().unwrap()),
+                    None,
+                ),
+                (
+                    AddressType::Ipv6Unicast,
+                    None,
+                    Some("2001:db8:1::/48".parse().unwrap()),
+                    None,
+                ),
+            ]
+        );
+        #[cfg(feature = "wire")]
+        {
+            let wire_len = |route: Route<'_>| route.prefix().wire_len();
+            assert_eq!(
+                update.announced().map(wire_len).collect::<Vec<_>>(),
+                vec![8, 9]
+            );
+            assert_eq!(
+                update.withdrawn().map(wire_len).collect::<Vec<_>>(),
+                vec![4, 7]
+            );
+        }
+        assert_eq!(
+            BgpUpdateMessage::new(vec![], vec![], vec![])
+                .announced()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_link_bandwidths() {
+        let as_path = |asns: Vec<u32>| {
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![As4PathSegment::new(
+                    AsPathSegmentType::AsSequence,
+                    asns,
+                )])),
+            )
+            .unwrap()
+        };
+        let communities = PathAttribute::from(
+            true,
+            true,
+            false,
+            false,
+            PathAttributeValue::ExtendedCommunities(ExtendedCommunities::new(vec![
+                LinkBandwidth::new(65000, 1_250_000.0).to_extended_community(),
+                LinkBandwidth::new(4200000000, 125_000.0).to_extended_community(),
+            ])),
+        )
+        .unwrap();
+        let update = BgpUpdateMessage::new(
+            vec![],
+            vec![as_path(vec![4200000000, 65000]), communities.clone()],
+            vec![],
+        );
+        let no_as_path = BgpUpdateMessage::new(vec![], vec![communities], vec![]);
+        let no_link_bandwidth = BgpUpdateMessage::new(vec![], vec![as_path(vec![65000])], vec![]);
+
+        assert_eq!(
+            update.link_bandwidths().collect::<Vec<_>>(),
+            vec![
+                LinkBandwidth::new(65000, 1_250_000.0),
+                LinkBandwidth::new(4200000000, 125_000.0),
+            ]
+        );
+        assert_eq!(update.link_bandwidth(), Some(1_375_000.0));
+        assert_eq!(
+            no_as_path.link_bandwidths().collect::<Vec<_>>(),
+            vec![
+                LinkBandwidth::new(65000, 1_250_000.0),
+                LinkBandwidth::new(AS_TRANS as u32, 125_000.0),
+            ]
+        );
+        assert_eq!(no_link_bandwidth.link_bandwidths().count(), 0);
+        assert_eq!(no_link_bandwidth.link_bandwidth(), None);
+    }
+
+    #[test]
+    fn test_aggregator() {
+        let origin = Ipv4Addr::new(192, 0, 2, 1);
+        let attribute = |value| PathAttribute::from(true, true, false, false, value).unwrap();
+        let as2_aggregator = |asn| {
+            attribute(PathAttributeValue::Aggregator(Aggregator::As2Aggregator(
+                As2Aggregator::new(asn, origin),
+            )))
+        };
+        let as4_aggregator = attribute(PathAttributeValue::As4Aggregator(As4Aggregator::new(
+            4200000000, origin,
+        )));
+
+        let as4_session = BgpUpdateMessage::new(
+            vec![],
+            vec![
+                attribute(PathAttributeValue::AtomicAggregate(AtomicAggregate)),
+                attribute(PathAttributeValue::Aggregator(Aggregator::As4Aggregator(
+                    As4Aggregator::new(4200000000, origin),
+                ))),
+            ],
+            vec![],
+        );
+        let as_trans = BgpUpdateMessage::new(
+            vec![],
+            vec![as2_aggregator(AS_TRANS), as4_aggregator.clone()],
+            vec![],
+        );
+        let as2_only = BgpUpdateMessage::new(
+            vec![],
+            vec![as2_aggregator(65000), as4_aggregator.clone()],
+            vec![],
+        );
+        let no_aggregator = BgpUpdateMessage::new(vec![], vec![as4_aggregator], vec![]);
+
+        assert!(as4_session.atomic_aggregate());
+        assert!(!as_trans.atomic_aggregate());
+        let aggregator = as4_session.aggregator().unwrap();
+        assert_eq!(aggregator.asn(), 4200000000);
+        assert_eq!(aggregator.origin(), origin);
+        assert_eq!(
+            as_trans.aggregator(),
+            Some(Aggregator::As4Aggregator(As4Aggregator::new(
+                4200000000, origin
+            )))
+        );
+        // AS4_AGGREGATOR is ignored when AGGREGATOR isn't AS_TRANS
+        assert_eq!(
+            as2_only.aggregator(),
+            Some(Aggregator::As2Aggregator(As2Aggregator::new(65000, origin)))
+        );
+        assert_eq!(as2_only.aggregator().map(|x| x.asn()), Some(65000));
+        assert_eq!(no_aggregator.aggregator(), None);
+    }
 }
@@ -22,14 +22,14 @@ use netgauze_iana::address_family::{
     UndefinedAddressFamily, UndefinedSubsequentAddressFamily,
 };
 use netgauze_parse_utils::{
-    parse_into_located, parse_till_empty, parse_till_empty_into_located, ErrorKindSerdeDeref,
-    ReadablePdu, Span,
+    parse_into_located, parse_till_empty, parse_till_empty_into_located, ReadablePdu, Span,
 };
 use nom::{
     error::{ErrorKind, FromExternalError, ParseError},
     number::complete::{be_u16, be_u32, be_u8},
     IResult,
 };
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -44,12 +44,17 @@ use crate::{
 use netgauze_serde_macros::LocatedError;
 
 /// BGP Capability Parsing errors
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpCapabilityParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedCapabilityCode(#[from_external] UndefinedBgpCapabilityCode),
     InvalidRouteRefreshLength(u8),
     InvalidEnhancedRouteRefreshLength(u8),
@@ -260,11 +265,15 @@ impl<'a> ReadablePdu<'a, LocatedBgpCapabilityParsingError<'a>> for BgpCapability
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FourOctetAsCapabilityParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidLength(u8),
 }
@@ -281,14 +290,20 @@ impl<'a> ReadablePdu<'a, LocatedFourOctetAsCapabilityParsingError<'a>> for FourO
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MultiProtocolExtensionsCapabilityParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidLength(u8),
+    #[category(UnknownCode)]
     AddressFamilyError(#[from_external] UndefinedAddressFamily),
+    #[category(UnknownCode)]
     SubsequentAddressFamilyError(#[from_external] UndefinedSubsequentAddressFamily),
     AddressTypeError(InvalidAddressType),
 }
@@ -322,13 +337,19 @@ impl<'a> ReadablePdu<'a, LocatedMultiProtocolExtensionsCapabilityParsingError<'a
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum GracefulRestartCapabilityParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     AddressFamilyError(#[from_external] UndefinedAddressFamily),
+    #[category(UnknownCode)]
     SubsequentAddressFamilyError(#[from_external] UndefinedSubsequentAddressFamily),
     AddressTypeError(InvalidAddressType),
 }
@@ -386,13 +407,19 @@ impl<'a> ReadablePdu<'a, LocatedGracefulRestartCapabilityParsingError<'a>>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum AddPathCapabilityParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     AddressFamilyError(#[from_external] UndefinedAddressFamily),
+    #[category(UnknownCode)]
     SubsequentAddressFamilyError(#[from_external] UndefinedSubsequentAddressFamily),
     AddressTypeError(InvalidAddressType),
     InvalidAddPathSendReceiveValue(u8),
@@ -440,13 +467,19 @@ impl<'a> ReadablePdu<'a, LocatedAddPathCapabilityParsingError<'a>> for AddPathAd
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ExtendedNextHopEncodingCapabilityParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     AddressFamilyError(#[from_external] UndefinedAddressFamily),
+    #[category(UnknownCode)]
     SubsequentAddressFamilyError(#[from_external] UndefinedSubsequentAddressFamily),
     AddressTypeError(InvalidAddressType),
 }
@@ -500,11 +533,17 @@ impl<'a> ReadablePdu<'a, LocatedExtendedNextHopEncodingCapabilityParsingError<'a
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MultipleLabelParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     AddressFamilyError(#[from_external] UndefinedAddressFamily),
+    #[category(UnknownCode)]
     SubsequentAddressFamilyError(#[from_external] UndefinedSubsequentAddressFamily),
     AddressTypeError(InvalidAddressType),
 }
@@ -528,11 +567,16 @@ impl<'a> ReadablePdu<'a, LocatedMultipleLabelParsingError<'a>> for MultipleLabel
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpRoleCapabilityParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidLength(u8),
+    #[category(UnknownCode)]
     UndefinedBgpRoleValue(#[from_external] UndefinedBgpRoleValue),
 }
 
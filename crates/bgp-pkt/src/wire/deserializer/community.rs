@@ -18,22 +18,26 @@ use nom::{
     number::complete::{be_u128, be_u16, be_u32, be_u8},
     IResult,
 };
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::net::{Ipv4Addr, Ipv6Addr};
 
 use netgauze_parse_utils::{
-    parse_into_located, parse_into_located_one_input, ErrorKindSerdeDeref, ReadablePdu,
-    ReadablePduWithOneInput, Span,
+    parse_into_located, parse_into_located_one_input, ReadablePdu, ReadablePduWithOneInput, Span,
 };
 use netgauze_serde_macros::LocatedError;
 
 use crate::{community::*, iana::*, wire::deserializer::nlri::MacAddressParsingError};
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum CommunityParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -44,9 +48,13 @@ impl<'a> ReadablePdu<'a, LocatedCommunityParsingError<'a>> for Community {
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ExtendedCommunityParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     TransitiveTwoOctetExtendedCommunityError(
         #[from_located(module = "self")] TransitiveTwoOctetExtendedCommunityParsingError,
@@ -213,9 +221,13 @@ impl<'a> ReadablePdu<'a, LocatedExtendedCommunityParsingError<'a>> for ExtendedC
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum LargeCommunityParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -231,9 +243,13 @@ impl<'a> ReadablePdu<'a, LocatedLargeCommunityParsingError<'a>> for LargeCommuni
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TransitiveTwoOctetExtendedCommunityParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -317,9 +333,13 @@ impl<'a> ReadablePdu<'a, LocatedTransitiveTwoOctetExtendedCommunityParsingError<
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ExtendedCommunityIpv6ParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     TransitiveIpv6ExtendedCommunityError(
         #[from_located(module = "self")] TransitiveIpv6ExtendedCommunityParsingError,
@@ -356,9 +376,13 @@ impl<'a> ReadablePdu<'a, LocatedExtendedCommunityIpv6ParsingError<'a>> for Exten
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum NonTransitiveTwoOctetExtendedCommunityParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -395,9 +419,13 @@ impl<'a> ReadablePdu<'a, LocatedNonTransitiveTwoOctetExtendedCommunityParsingErr
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TransitiveIpv4ExtendedCommunityParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -512,9 +540,13 @@ impl<'a> ReadablePdu<'a, LocatedTransitiveIpv4ExtendedCommunityParsingError<'a>>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum NonTransitiveIpv4ExtendedCommunityParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -537,9 +569,13 @@ impl<'a> ReadablePdu<'a, LocatedNonTransitiveIpv4ExtendedCommunityParsingError<'
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TransitiveFourOctetExtendedCommunityParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -611,9 +647,13 @@ impl<'a> ReadablePdu<'a, LocatedTransitiveFourOctetExtendedCommunityParsingError
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum NonTransitiveFourOctetExtendedCommunityParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -636,9 +676,13 @@ impl<'a> ReadablePdu<'a, LocatedNonTransitiveFourOctetExtendedCommunityParsingEr
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TransitiveOpaqueExtendedCommunityParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -671,9 +715,13 @@ impl<'a> ReadablePdu<'a, LocatedTransitiveOpaqueExtendedCommunityParsingError<'a
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum NonTransitiveOpaqueExtendedCommunityParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidValueLength(usize),
 }
@@ -703,9 +751,13 @@ impl<'a> ReadablePdu<'a, LocatedNonTransitiveOpaqueExtendedCommunityParsingError
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum EvpnExtendedCommunityParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     MacAddressError(
         #[from_located(module = "crate::wire::deserializer::nlri")] MacAddressParsingError,
@@ -774,9 +826,13 @@ impl<'a> ReadablePdu<'a, LocatedEvpnExtendedCommunityParsingError<'a>> for EvpnE
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ExperimentalExtendedCommunityParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidValueLength(usize),
 }
@@ -805,9 +861,13 @@ impl<'a> ReadablePduWithOneInput<'a, u8, LocatedExperimentalExtendedCommunityPar
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum UnknownExtendedCommunityParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidValueLength(usize),
 }
@@ -833,9 +893,13 @@ impl<'a> ReadablePduWithOneInput<'a, u8, LocatedUnknownExtendedCommunityParsingE
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TransitiveIpv6ExtendedCommunityParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -914,9 +978,13 @@ impl<'a> ReadablePdu<'a, LocatedTransitiveIpv6ExtendedCommunityParsingError<'a>>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum NonTransitiveIpv6ExtendedCommunityParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -939,9 +1007,13 @@ impl<'a> ReadablePdu<'a, LocatedNonTransitiveIpv6ExtendedCommunityParsingError<'
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum UnknownExtendedCommunityIpv6ParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidValueLength(usize),
 }
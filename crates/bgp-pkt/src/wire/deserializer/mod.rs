@@ -24,7 +24,7 @@ pub mod path_attribute;
 pub mod route_refresh;
 pub mod update;
 
-use ipnet::{Ipv4Net, Ipv6Net};
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
 use std::{
     collections::HashMap,
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
@@ -36,15 +36,17 @@ use nom::{
     number::complete::{be_u128, be_u16, be_u32, be_u8},
     IResult,
 };
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use netgauze_parse_utils::{
-    parse_into_located, parse_into_located_one_input, ErrorKindSerdeDeref, ReadablePdu,
-    ReadablePduWithOneInput, ReadablePduWithTwoInputs, Span,
+    parse_into_located, parse_into_located_one_input, ReadablePdu, ReadablePduWithOneInput,
+    ReadablePduWithTwoInputs, Span, StringDecodingPolicy,
 };
 
 use crate::{
-    iana::{BgpMessageType, UndefinedBgpMessageType},
+    iana::{BgpMessageType, PathAttributeType, UndefinedBgpMessageType},
+    nlri::HostBitsPolicy,
     notification::{BgpNotificationMessage, FiniteStateMachineError, MessageHeaderError},
     wire::{
         deserializer::{
@@ -70,12 +72,32 @@ pub const BGP_MIN_MESSAGE_LENGTH: u16 = 19;
 /// [RFC8654 Extended Message Support for BGP](https://datatracker.ietf.org/doc/html/rfc8654)
 pub const BGP_MAX_MESSAGE_LENGTH: u16 = 4096;
 
+/// [RFC8654 Extended Message Support for BGP](https://datatracker.ietf.org/doc/html/rfc8654)
+/// raises the max length of messages other than [`BgpMessage::Open`] and
+/// [`BgpMessage::KeepAlive`] to 65535 when the capability is negotiated.
+pub const BGP_MAX_EXTENDED_MESSAGE_LENGTH: u16 = u16::MAX;
+
+/// Deviation from the all-ones 16-octet marker of the BGP message header that
+/// was tolerated because [`BgpParsingContext::relax_markers`] is set
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BgpMarkerAnomaly {
+    /// The marker is present but not all set to `1`, the invalid value is
+    /// carried as [`u128`]
+    Mangled(u128),
+
+    /// The message starts directly with the length field
+    Omitted,
+}
+
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct BgpParsingIgnoredErrors {
     non_unicast_withdraw_nlri: Vec<Ipv4Net>,
     non_unicast_update_nlri: Vec<Ipv4Net>,
     capability_errors: Vec<BgpCapabilityParsingError>,
     path_attr_errors: Vec<PathAttributeParsingError>,
+    missing_mandatory_attrs: Vec<PathAttributeType>,
+    marker_anomalies: Vec<BgpMarkerAnomaly>,
+    normalized_host_bits_prefixes: Vec<IpNet>,
 }
 
 impl BgpParsingIgnoredErrors {
@@ -94,17 +116,60 @@ impl BgpParsingIgnoredErrors {
     pub const fn path_attr_errors(&self) -> &Vec<PathAttributeParsingError> {
         &self.path_attr_errors
     }
+
+    /// Well-known mandatory attributes missing from parsed update messages,
+    /// see [`crate::BgpUpdateMessage::missing_mandatory_attributes`]
+    pub const fn missing_mandatory_attrs(&self) -> &Vec<PathAttributeType> {
+        &self.missing_mandatory_attrs
+    }
+
+    /// Invalid markers of parsed messages, see
+    /// [`BgpParsingContext::relax_markers`]
+    pub const fn marker_anomalies(&self) -> &Vec<BgpMarkerAnomaly> {
+        &self.marker_anomalies
+    }
+
+    /// Original prefixes that had host bits set and were normalized, see
+    /// [`BgpParsingContext::host_bits_policy`]
+    pub const fn normalized_host_bits_prefixes(&self) -> &Vec<IpNet> {
+        &self.normalized_host_bits_prefixes
+    }
+}
+
+/// Direction of the messages being parsed, selects which of the Add-Path maps
+/// of [`BgpParsingContext`] applies. Add-Path can be negotiated for only one
+/// direction of a BGP session, see
+/// [RFC7911](https://datatracker.ietf.org/doc/html/rfc7911#section-4).
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AddPathDirection {
+    /// Messages received from the peer, e.g., BMP Adj-RIB-In
+    #[default]
+    Receive,
+
+    /// Messages sent to the peer, e.g., BMP Adj-RIB-Out
+    Send,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BgpParsingContext {
     asn4: bool,
     multiple_labels: HashMap<AddressType, u8>,
     add_path: HashMap<AddressType, bool>,
+    add_path_send: HashMap<AddressType, bool>,
+    add_path_direction: AddPathDirection,
     fail_on_non_unicast_withdraw_nlri: bool,
     fail_on_non_unicast_update_nlri: bool,
     fail_on_capability_error: bool,
     fail_on_malformed_path_attr: bool,
+    fail_on_missing_mandatory_attr: bool,
+    string_decoding_policy: StringDecodingPolicy,
+    relax_markers: bool,
+    skip_legacy_snpa: bool,
+    host_bits_policy: HostBitsPolicy,
+    /// Errors collected while parsing, not part of the parsing state
+    #[cfg_attr(feature = "serde", serde(skip))]
     parsing_errors: BgpParsingIgnoredErrors,
 }
 
@@ -122,10 +187,17 @@ impl BgpParsingContext {
             asn4,
             multiple_labels,
             add_path,
+            add_path_send: HashMap::new(),
+            add_path_direction: AddPathDirection::default(),
             fail_on_non_unicast_withdraw_nlri,
             fail_on_non_unicast_update_nlri,
             fail_on_capability_error,
             fail_on_malformed_path_attr,
+            fail_on_missing_mandatory_attr: false,
+            string_decoding_policy: StringDecodingPolicy::default(),
+            relax_markers: false,
+            skip_legacy_snpa: false,
+            host_bits_policy: HostBitsPolicy::default(),
             parsing_errors: BgpParsingIgnoredErrors::default(),
         }
     }
@@ -158,6 +230,8 @@ impl BgpParsingContext {
         &mut self.multiple_labels
     }
 
+    /// Address types with path identifiers in the messages received from the
+    /// peer
     pub const fn add_path(&self) -> &HashMap<AddressType, bool> {
         &self.add_path
     }
@@ -166,6 +240,32 @@ impl BgpParsingContext {
         &mut self.add_path
     }
 
+    /// Address types with path identifiers in the messages sent to the peer
+    pub const fn add_path_send(&self) -> &HashMap<AddressType, bool> {
+        &self.add_path_send
+    }
+
+    pub fn add_path_send_mut(&mut self) -> &mut HashMap<AddressType, bool> {
+        &mut self.add_path_send
+    }
+
+    pub const fn add_path_direction(&self) -> AddPathDirection {
+        self.add_path_direction
+    }
+
+    pub fn set_add_path_direction(&mut self, value: AddPathDirection) {
+        self.add_path_direction = value
+    }
+
+    /// Add-Path map of the direction being parsed, see
+    /// [`BgpParsingContext::add_path_direction`]
+    pub const fn active_add_path(&self) -> &HashMap<AddressType, bool> {
+        match self.add_path_direction {
+            AddPathDirection::Receive => &self.add_path,
+            AddPathDirection::Send => &self.add_path_send,
+        }
+    }
+
     pub const fn fail_on_non_unicast_withdraw_nlri(&self) -> bool {
         self.fail_on_non_unicast_withdraw_nlri
     }
@@ -182,6 +282,70 @@ impl BgpParsingContext {
         self.fail_on_malformed_path_attr
     }
 
+    /// When `false` (the default), updates missing well-known mandatory
+    /// attributes are parsed and the missing attributes are recorded in
+    /// [`BgpParsingIgnoredErrors::missing_mandatory_attrs`]. This is useful
+    /// for buggy peers and BMP Loc-RIB feeds.
+    pub const fn fail_on_missing_mandatory_attr(&self) -> bool {
+        self.fail_on_missing_mandatory_attr
+    }
+
+    pub fn set_fail_on_missing_mandatory_attr(&mut self, value: bool) {
+        self.fail_on_missing_mandatory_attr = value
+    }
+
+    /// How strings that are not valid UTF-8 are decoded, e.g., by
+    /// [`crate::notification::CeaseError::shutdown_communication`] or the
+    /// string TLVs of BMP messages carrying BGP messages
+    pub const fn string_decoding_policy(&self) -> StringDecodingPolicy {
+        self.string_decoding_policy
+    }
+
+    pub fn set_string_decoding_policy(&mut self, value: StringDecodingPolicy) {
+        self.string_decoding_policy = value
+    }
+
+    /// When `true`, messages with a marker that is not all set to `1` or
+    /// missing altogether are parsed instead of failing with
+    /// [`BgpMessageParsingError::ConnectionNotSynchronized`], and the anomaly
+    /// is recorded in [`BgpParsingIgnoredErrors::marker_anomalies`]. Some
+    /// routers mangle the marker of BGP messages embedded in BMP, it should
+    /// not be relaxed for BGP sessions.
+    pub const fn relax_markers(&self) -> bool {
+        self.relax_markers
+    }
+
+    pub fn set_relax_markers(&mut self, value: bool) {
+        self.relax_markers = value
+    }
+
+    /// When `true`, the reserved octet of
+    /// [`crate::path_attribute::MpReach`] is parsed as the number of Subnetwork
+    /// Points of Attachment (SNPAs) defined in the obsoleted
+    /// [RFC2858](https://datatracker.ietf.org/doc/html/rfc2858) and the SNPAs
+    /// are skipped. Needed for archived MRT data from old speakers, it should
+    /// not be set for BGP sessions.
+    pub const fn skip_legacy_snpa(&self) -> bool {
+        self.skip_legacy_snpa
+    }
+
+    pub fn set_skip_legacy_snpa(&mut self, value: bool) {
+        self.skip_legacy_snpa = value
+    }
+
+    /// How the prefixes of update messages with bits set beyond the prefix
+    /// length are handled. With [`HostBitsPolicy::Normalize`], the original
+    /// prefixes are recorded in
+    /// [`BgpParsingIgnoredErrors::normalized_host_bits_prefixes`]. The
+    /// default accepts them as is.
+    pub const fn host_bits_policy(&self) -> HostBitsPolicy {
+        self.host_bits_policy
+    }
+
+    pub fn set_host_bits_policy(&mut self, value: HostBitsPolicy) {
+        self.host_bits_policy = value
+    }
+
     pub const fn parsing_errors(&self) -> &BgpParsingIgnoredErrors {
         &self.parsing_errors
     }
@@ -198,11 +362,15 @@ impl Default for BgpParsingContext {
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Ipv4PrefixParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidIpv4PrefixLen(u8),
 }
@@ -249,11 +417,15 @@ impl<'a> ReadablePduWithTwoInputs<'a, u8, Span<'a>, LocatedIpv4PrefixParsingErro
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Ipv6PrefixParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidIpv6PrefixLen(u8),
 }
@@ -298,9 +470,13 @@ impl<'a> ReadablePduWithTwoInputs<'a, u8, Span<'a>, LocatedIpv6PrefixParsingErro
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum IpAddrParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidIpAddressType(u8),
     InvalidIpAddressLength(u8),
@@ -331,11 +507,15 @@ impl<'a> ReadablePdu<'a, LocatedIpAddrParsingError<'a>> for IpAddr {
 }
 
 /// BGP Message Parsing errors
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpMessageParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 
     /// The first 16-bytes of a BGP message is NOT all set to `1`
@@ -345,6 +525,7 @@ pub enum BgpMessageParsingError {
 
     /// Couldn't recognize the type octet in the BGPMessage, see
     /// [UndefinedBgpMessageType]
+    #[category(UnknownCode)]
     UndefinedBgpMessageType(#[from_external] UndefinedBgpMessageType),
 
     /// BGP Message length is not in the defined \[min, max\] range for the
@@ -372,15 +553,20 @@ pub enum BgpMessageParsingError {
 
 /// Smaller error variant of BgpMessageParsingError for small stack allocations
 /// in parse_bgp_message_length_and_type
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpMessageOpenAndLengthParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 
     /// Couldn't recognize the type octet in the BGPMessage, see
     /// [UndefinedBgpMessageType]
+    #[category(UnknownCode)]
     UndefinedBgpMessageType(#[from_external] UndefinedBgpMessageType),
 
     /// BGP Message length is not in the defined \[min, max\] range for the
@@ -486,13 +672,34 @@ impl<'a> ReadablePduWithOneInput<'a, &mut BgpParsingContext, LocatedBgpMessagePa
         buf: Span<'a>,
         ctx: &mut BgpParsingContext,
     ) -> IResult<Span<'a>, Self, LocatedBgpMessageParsingError<'a>> {
-        let (buf, _) = nom::combinator::map_res(be_u128, |x| {
-            if x == u128::MAX {
-                Ok(x)
-            } else {
-                Err(BgpMessageParsingError::ConnectionNotSynchronized(x))
+        // When markers are relaxed, an invalid marker is accepted if a valid length
+        // and type follow it, otherwise the marker is considered omitted if the
+        // message starts with a valid length and type. The length is assumed to
+        // still account for an omitted marker.
+        let buf = match be_u128::<_, LocatedBgpMessageParsingError<'_>>(buf) {
+            Ok((rest, marker)) if marker == u128::MAX => rest,
+            Ok((rest, marker))
+                if ctx.relax_markers && parse_bgp_message_length_and_type(rest).is_ok() =>
+            {
+                ctx.parsing_errors
+                    .marker_anomalies
+                    .push(BgpMarkerAnomaly::Mangled(marker));
+                rest
+            }
+            _ if ctx.relax_markers && parse_bgp_message_length_and_type(buf).is_ok() => {
+                ctx.parsing_errors
+                    .marker_anomalies
+                    .push(BgpMarkerAnomaly::Omitted);
+                buf
             }
-        })(buf)?;
+            Ok((_, marker)) => {
+                return Err(nom::Err::Error(LocatedBgpMessageParsingError::new(
+                    buf,
+                    BgpMessageParsingError::ConnectionNotSynchronized(marker),
+                )))
+            }
+            Err(err) => return Err(err),
+        };
 
         // Parse both length and type together, since we need to do input validation on
         // the length based on the type of the message
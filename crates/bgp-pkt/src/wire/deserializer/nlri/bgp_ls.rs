@@ -36,8 +36,7 @@ use crate::{
 use ipnet::IpNet;
 use netgauze_parse_utils::{
     parse_into_located, parse_into_located_one_input, parse_till_empty_into_located,
-    parse_till_empty_into_with_one_input_located, ErrorKindSerdeDeref, ReadablePdu,
-    ReadablePduWithOneInput, Span,
+    parse_till_empty_into_with_one_input_located, ReadablePdu, ReadablePduWithOneInput, Span,
 };
 use netgauze_serde_macros::LocatedError;
 use nom::{
@@ -45,26 +44,38 @@ use nom::{
     number::complete::{be_u128, be_u16, be_u32, be_u64, be_u8},
     IResult,
 };
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::net::{Ipv4Addr, Ipv6Addr};
 
 /// BGP Link-State NLRI Parsing Errors
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpLsNlriParsingError {
     /// Errors triggered by the nom parser, see [nom::error::ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UnknownNlriType(#[from_external] UnknownBgpLsNlriType),
     RouteDistinguisherParsingError(
         #[from_located(module = "crate::wire::deserializer::nlri")] RouteDistinguisherParsingError,
     ),
+    #[category(UnknownCode)]
     UnknownProtocolId(#[from_external] BgpLsProtocolIdError),
+    #[category(UnknownCode)]
     UnknownDescriptorTlvType(#[from_external] BgpLsNodeDescriptorTypeError),
+    #[category(UnknownCode)]
     UnknownNodeDescriptorSubTlvType(#[from_external] NodeDescriptorSubTypeError),
+    #[category(UnknownCode)]
     UnknownPrefixDescriptorTlvType(#[from_external] PrefixDescriptorTypeError),
+    #[category(UnknownCode)]
     UnknownOspfRouteType(#[from_external] UnknownOspfRouteType),
     BadNodeDescriptorTlvType(BgpLsNodeDescriptorType),
+    #[category(UnknownCode)]
     UnknownLinkDescriptorTlvType(#[from_external] LinkDescriptorTypeError),
     BadTlvTypeInNlri(BgpLsNlriType),
     Ipv4PrefixError(#[from_located(module = "crate::wire::deserializer")] Ipv4PrefixParsingError),
@@ -0,0 +1,283 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    iana::{FlowSpecComponentType, UndefinedFlowSpecComponentType},
+    nlri::{
+        BitmaskOperation, FlowSpecComponent, FlowSpecNlri, FlowSpecPrefix, NumericOperation,
+        OperationValue,
+    },
+};
+use ipnet::{Ipv4Net, Ipv6Net};
+use netgauze_iana::address_family::AddressFamily;
+use netgauze_parse_utils::{ReadablePduWithOneInput, Span};
+use netgauze_serde_macros::LocatedError;
+use nom::{
+    error::ErrorKind,
+    number::complete::{be_u16, be_u32, be_u64, be_u8},
+    IResult,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// Flow Spec NLRI Parsing Errors
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FlowSpecNlriParsingError {
+    /// Errors triggered by the nom parser, see [nom::error::ErrorKind] for
+    /// additional information.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
+    NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
+    UndefinedComponentType(#[from_external] UndefinedFlowSpecComponentType),
+    /// The components are not in a strictly increasing order of their type, or
+    /// the component is not defined for the address family
+    UnexpectedComponentType(FlowSpecComponentType),
+    InvalidPrefixLength(u8),
+    InvalidPrefixOffset(u8),
+}
+
+impl<'a> ReadablePduWithOneInput<'a, AddressFamily, LocatedFlowSpecNlriParsingError<'a>>
+    for FlowSpecNlri
+{
+    fn from_wire(
+        buf: Span<'a>,
+        afi: AddressFamily,
+    ) -> IResult<Span<'a>, Self, LocatedFlowSpecNlriParsingError<'a>> {
+        // Lengths of 240 octets or more are encoded in two octets as 0xfnnn
+        let (buf, first) = be_u8(buf)?;
+        let (buf, len) = if first >= 0xf0 {
+            let (buf, second) = be_u8(buf)?;
+            (buf, u16::from(first & 0x0f) << 8 | u16::from(second))
+        } else {
+            (buf, u16::from(first))
+        };
+        let (buf, mut data) = nom::bytes::complete::take(len)(buf)?;
+        let mut components = vec![];
+        let mut previous = None;
+        while !data.is_empty() {
+            let input = data;
+            let (tmp, component) = FlowSpecComponent::from_wire(data, afi)?;
+            let component_type = component.component_type();
+            if previous.is_some_and(|previous| previous >= component_type) {
+                return Err(nom::Err::Error(LocatedFlowSpecNlriParsingError::new(
+                    input,
+                    FlowSpecNlriParsingError::UnexpectedComponentType(component_type),
+                )));
+            }
+            previous = Some(component_type);
+            components.push(component);
+            data = tmp;
+        }
+        Ok((buf, FlowSpecNlri::new(components)))
+    }
+}
+
+impl<'a> ReadablePduWithOneInput<'a, AddressFamily, LocatedFlowSpecNlriParsingError<'a>>
+    for FlowSpecComponent
+{
+    fn from_wire(
+        buf: Span<'a>,
+        afi: AddressFamily,
+    ) -> IResult<Span<'a>, Self, LocatedFlowSpecNlriParsingError<'a>> {
+        let input = buf;
+        let (buf, component_type) =
+            nom::combinator::map_res(be_u8, FlowSpecComponentType::try_from)(buf)?;
+        match component_type {
+            FlowSpecComponentType::DestinationPrefix => {
+                let (buf, prefix) = FlowSpecPrefix::from_wire(buf, afi)?;
+                Ok((buf, FlowSpecComponent::DestinationPrefix(prefix)))
+            }
+            FlowSpecComponentType::SourcePrefix => {
+                let (buf, prefix) = FlowSpecPrefix::from_wire(buf, afi)?;
+                Ok((buf, FlowSpecComponent::SourcePrefix(prefix)))
+            }
+            FlowSpecComponentType::IpProtocol => {
+                let (buf, ops) = parse_numeric_operations(buf)?;
+                Ok((buf, FlowSpecComponent::IpProtocol(ops)))
+            }
+            FlowSpecComponentType::Port => {
+                let (buf, ops) = parse_numeric_operations(buf)?;
+                Ok((buf, FlowSpecComponent::Port(ops)))
+            }
+            FlowSpecComponentType::DestinationPort => {
+                let (buf, ops) = parse_numeric_operations(buf)?;
+                Ok((buf, FlowSpecComponent::DestinationPort(ops)))
+            }
+            FlowSpecComponentType::SourcePort => {
+                let (buf, ops) = parse_numeric_operations(buf)?;
+                Ok((buf, FlowSpecComponent::SourcePort(ops)))
+            }
+            FlowSpecComponentType::IcmpType => {
+                let (buf, ops) = parse_numeric_operations(buf)?;
+                Ok((buf, FlowSpecComponent::IcmpType(ops)))
+            }
+            FlowSpecComponentType::IcmpCode => {
+                let (buf, ops) = parse_numeric_operations(buf)?;
+                Ok((buf, FlowSpecComponent::IcmpCode(ops)))
+            }
+            FlowSpecComponentType::TcpFlags => {
+                let (buf, ops) = parse_bitmask_operations(buf)?;
+                Ok((buf, FlowSpecComponent::TcpFlags(ops)))
+            }
+            FlowSpecComponentType::PacketLength => {
+                let (buf, ops) = parse_numeric_operations(buf)?;
+                Ok((buf, FlowSpecComponent::PacketLength(ops)))
+            }
+            FlowSpecComponentType::Dscp => {
+                let (buf, ops) = parse_numeric_operations(buf)?;
+                Ok((buf, FlowSpecComponent::Dscp(ops)))
+            }
+            FlowSpecComponentType::Fragment => {
+                let (buf, ops) = parse_bitmask_operations(buf)?;
+                Ok((buf, FlowSpecComponent::Fragment(ops)))
+            }
+            FlowSpecComponentType::FlowLabel if afi == AddressFamily::IPv6 => {
+                let (buf, ops) = parse_numeric_operations(buf)?;
+                Ok((buf, FlowSpecComponent::FlowLabel(ops)))
+            }
+            FlowSpecComponentType::FlowLabel => {
+                Err(nom::Err::Error(LocatedFlowSpecNlriParsingError::new(
+                    input,
+                    FlowSpecNlriParsingError::UnexpectedComponentType(component_type),
+                )))
+            }
+        }
+    }
+}
+
+impl<'a> ReadablePduWithOneInput<'a, AddressFamily, LocatedFlowSpecNlriParsingError<'a>>
+    for FlowSpecPrefix
+{
+    fn from_wire(
+        buf: Span<'a>,
+        afi: AddressFamily,
+    ) -> IResult<Span<'a>, Self, LocatedFlowSpecNlriParsingError<'a>> {
+        let input = buf;
+        let (buf, prefix_len) = be_u8(buf)?;
+        if afi == AddressFamily::IPv6 {
+            let (buf, offset) = be_u8(buf)?;
+            if prefix_len > 128 {
+                return Err(nom::Err::Error(LocatedFlowSpecNlriParsingError::new(
+                    input,
+                    FlowSpecNlriParsingError::InvalidPrefixLength(prefix_len),
+                )));
+            }
+            if offset > prefix_len {
+                return Err(nom::Err::Error(LocatedFlowSpecNlriParsingError::new(
+                    input,
+                    FlowSpecNlriParsingError::InvalidPrefixOffset(offset),
+                )));
+            }
+            // The pattern holds only the bits from the offset to the prefix length
+            let (buf, pattern) =
+                nom::bytes::complete::take((prefix_len - offset).div_ceil(8))(buf)?;
+            let mut octets = [0u8; 16];
+            octets[..pattern.len()].copy_from_slice(pattern.fragment());
+            let addr = u128::from_be_bytes(octets)
+                .checked_shr(u32::from(offset))
+                .unwrap_or_default();
+            let prefix = Ipv6Net::new(Ipv6Addr::from(addr), prefix_len).map_err(|_| {
+                nom::Err::Error(LocatedFlowSpecNlriParsingError::new(
+                    input,
+                    FlowSpecNlriParsingError::InvalidPrefixLength(prefix_len),
+                ))
+            })?;
+            Ok((buf, FlowSpecPrefix::Ipv6 { prefix, offset }))
+        } else {
+            if prefix_len > 32 {
+                return Err(nom::Err::Error(LocatedFlowSpecNlriParsingError::new(
+                    input,
+                    FlowSpecNlriParsingError::InvalidPrefixLength(prefix_len),
+                )));
+            }
+            let (buf, prefix) = nom::bytes::complete::take(prefix_len.div_ceil(8))(buf)?;
+            let mut octets = [0u8; 4];
+            octets[..prefix.len()].copy_from_slice(prefix.fragment());
+            let prefix = Ipv4Net::new(Ipv4Addr::from(octets), prefix_len).map_err(|_| {
+                nom::Err::Error(LocatedFlowSpecNlriParsingError::new(
+                    input,
+                    FlowSpecNlriParsingError::InvalidPrefixLength(prefix_len),
+                ))
+            })?;
+            Ok((buf, FlowSpecPrefix::Ipv4(prefix)))
+        }
+    }
+}
+
+/// Parse the value following an operator, the width is encoded in the `len`
+/// bits of the operator
+#[inline]
+fn parse_operation_value(
+    buf: Span<'_>,
+    operator: u8,
+) -> IResult<Span<'_>, OperationValue, LocatedFlowSpecNlriParsingError<'_>> {
+    match (operator >> 4) & 0x03 {
+        0 => nom::combinator::map(be_u8, OperationValue::U8)(buf),
+        1 => nom::combinator::map(be_u16, OperationValue::U16)(buf),
+        2 => nom::combinator::map(be_u32, OperationValue::U32)(buf),
+        _ => nom::combinator::map(be_u64, OperationValue::U64)(buf),
+    }
+}
+
+/// Parse the operations of a component till the one with the end-of-list bit
+fn parse_operations<T>(
+    mut buf: Span<'_>,
+    operation: fn(u8, OperationValue) -> T,
+) -> IResult<Span<'_>, Vec<T>, LocatedFlowSpecNlriParsingError<'_>> {
+    let mut operations = vec![];
+    loop {
+        let (tmp, operator) = be_u8(buf)?;
+        let (tmp, value) = parse_operation_value(tmp, operator)?;
+        operations.push(operation(operator, value));
+        buf = tmp;
+        if operator & 0x80 != 0 {
+            return Ok((buf, operations));
+        }
+    }
+}
+
+#[inline]
+fn parse_numeric_operations(
+    buf: Span<'_>,
+) -> IResult<Span<'_>, Vec<NumericOperation>, LocatedFlowSpecNlriParsingError<'_>> {
+    parse_operations(buf, |operator, value| {
+        NumericOperation::new(
+            operator & 0x40 != 0,
+            operator & 0x04 != 0,
+            operator & 0x02 != 0,
+            operator & 0x01 != 0,
+            value,
+        )
+    })
+}
+
+#[inline]
+fn parse_bitmask_operations(
+    buf: Span<'_>,
+) -> IResult<Span<'_>, Vec<BitmaskOperation>, LocatedFlowSpecNlriParsingError<'_>> {
+    parse_operations(buf, |operator, value| {
+        BitmaskOperation::new(
+            operator & 0x40 != 0,
+            operator & 0x02 != 0,
+            operator & 0x01 != 0,
+            value,
+        )
+    })
+}
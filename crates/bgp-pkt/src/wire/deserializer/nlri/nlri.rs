@@ -26,9 +26,9 @@ use crate::{
 };
 use ipnet::{Ipv4Net, Ipv6Net};
 use netgauze_parse_utils::{
-    parse_into_located, parse_into_located_one_input, parse_into_located_two_inputs,
-    ErrorKindSerdeDeref, ReadablePdu, ReadablePduWithOneInput, ReadablePduWithThreeInputs,
-    ReadablePduWithTwoInputs, Span,
+    bounded::parse_u8_length_prefixed, parse_into_located, parse_into_located_one_input,
+    parse_into_located_two_inputs, ReadablePdu, ReadablePduWithOneInput,
+    ReadablePduWithThreeInputs, ReadablePduWithTwoInputs, Span,
 };
 use netgauze_serde_macros::LocatedError;
 use nom::{
@@ -36,6 +36,7 @@ use nom::{
     number::complete::{be_u128, be_u16, be_u32, be_u8},
     IResult,
 };
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
@@ -46,9 +47,13 @@ pub(crate) const L2_EVPN_IPV4_PREFIX_ROUTE_LEN: usize = 34;
 /// [RFC9136](https://datatracker.ietf.org/doc/html/rfc9136)
 pub(crate) const L2_EVPN_IPV6_PREFIX_ROUTE_LEN: usize = 58;
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MplsLabelParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -61,10 +66,15 @@ impl<'a> ReadablePdu<'a, LocatedMplsLabelParsingError<'a>> for MplsLabel {
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RouteDistinguisherParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedRouteDistinguisherTypeCode(#[from_external] UndefinedRouteDistinguisherTypeCode),
     /// LeafAdRoutes is expected to be all `1`
     InvalidLeafAdRoutes(u16, u32),
@@ -110,9 +120,13 @@ impl<'a> ReadablePdu<'a, LocatedRouteDistinguisherParsingError<'a>> for RouteDis
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum LabeledIpv4NextHopParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     RouteDistinguisherError(#[from_located(module = "self")] RouteDistinguisherParsingError),
 }
@@ -128,9 +142,13 @@ impl<'a> ReadablePdu<'a, LocatedLabeledIpv4NextHopParsingError<'a>> for LabeledI
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum LabeledIpv6NextHopParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     RouteDistinguisherError(#[from_located(module = "self")] RouteDistinguisherParsingError),
 }
@@ -152,9 +170,13 @@ impl<'a> ReadablePdu<'a, LocatedLabeledIpv6NextHopParsingError<'a>> for LabeledI
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum LabeledNextHopParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidLength(u8),
     LabeledIpv4NextHopError(#[from_located(module = "self")] LabeledIpv4NextHopParsingError),
@@ -181,9 +203,13 @@ impl<'a> ReadablePdu<'a, LocatedLabeledNextHopParsingError<'a>> for LabeledNextH
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Ipv4MplsVpnUnicastAddressParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidPrefixLength(u8),
     RouteDistinguisherError(#[from_located(module = "self")] RouteDistinguisherParsingError),
@@ -257,9 +283,13 @@ impl<'a>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Ipv6MplsVpnUnicastAddressParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     MplsLabelError(#[from_located(module = "self")] MplsLabelParsingError),
     RouteDistinguisherError(#[from_located(module = "self")] RouteDistinguisherParsingError),
@@ -333,9 +363,13 @@ impl<'a>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Ipv6UnicastParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     Ipv6PrefixError(
         #[from_external]
@@ -368,9 +402,13 @@ impl<'a> ReadablePduWithTwoInputs<'a, u8, Span<'a>, LocatedIpv6UnicastParsingErr
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Ipv6UnicastAddressParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     Ipv6UnicastError(#[from_located(module = "self")] Ipv6UnicastParsingError),
 }
@@ -393,7 +431,8 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedIpv6UnicastAddressParsingError
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Ipv6MulticastParsingError {
     Ipv6PrefixError(
         #[from_external]
@@ -410,9 +449,13 @@ impl<'a> ReadablePdu<'a, LocatedIpv6MulticastParsingError<'a>> for Ipv6Multicast
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Ipv6MulticastAddressParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     Ipv6MulticastError(#[from_located(module = "self")] Ipv6MulticastParsingError),
 }
@@ -435,7 +478,8 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedIpv6MulticastAddressParsingErr
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Ipv4UnicastParsingError {
     Ipv4PrefixError(
         #[from_external]
@@ -468,11 +512,15 @@ impl<'a> ReadablePduWithTwoInputs<'a, u8, Span<'a>, LocatedIpv4UnicastParsingErr
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Ipv4UnicastAddressParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     Ipv4UnicastError(#[from_located(module = "self")] Ipv4UnicastParsingError),
 }
@@ -495,7 +543,8 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedIpv4UnicastAddressParsingError
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Ipv4MulticastParsingError {
     Ipv4PrefixError(
         #[from_external]
@@ -512,9 +561,13 @@ impl<'a> ReadablePdu<'a, LocatedIpv4MulticastParsingError<'a>> for Ipv4Multicast
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Ipv4MulticastAddressParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     Ipv4MulticastError(#[from_located(module = "self")] Ipv4MulticastParsingError),
 }
@@ -537,9 +590,13 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedIpv4MulticastAddressParsingErr
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MacAddressParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 impl<'a> ReadablePdu<'a, LocatedMacAddressParsingError<'a>> for MacAddress {
@@ -554,9 +611,13 @@ impl<'a> ReadablePdu<'a, LocatedMacAddressParsingError<'a>> for MacAddress {
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum EthernetSegmentIdentifierParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -585,9 +646,13 @@ impl<'a> ReadablePdu<'a, LocatedEthernetSegmentIdentifierParsingError<'a>>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum EthernetTagParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -598,9 +663,13 @@ impl<'a> ReadablePdu<'a, LocatedEthernetTagParsingError<'a>> for EthernetTag {
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum EthernetAutoDiscoveryParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     RouteDistinguisherError(#[from_located(module = "self")] RouteDistinguisherParsingError),
     EthernetSegmentIdentifierError(
@@ -625,9 +694,13 @@ impl<'a> ReadablePdu<'a, LocatedEthernetAutoDiscoveryParsingError<'a>> for Ether
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MacIpAdvertisementParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidMacAddressLength(u8),
     InvalidIpAddressAddressLength(u8),
@@ -690,9 +763,13 @@ impl<'a> ReadablePdu<'a, LocatedMacIpAdvertisementParsingError<'a>> for MacIpAdv
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum InclusiveMulticastEthernetTagRouteParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidIpAddressAddressLength(u8),
     RouteDistinguisherError(#[from_located(module = "self")] RouteDistinguisherParsingError),
@@ -731,9 +808,13 @@ impl<'a> ReadablePdu<'a, LocatedInclusiveMulticastEthernetTagRouteParsingError<'
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum EthernetSegmentRouteParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidIpAddressAddressLength(u8),
     RouteDistinguisherError(#[from_located(module = "self")] RouteDistinguisherParsingError),
@@ -772,9 +853,13 @@ impl<'a> ReadablePdu<'a, LocatedEthernetSegmentRouteParsingError<'a>> for Ethern
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum L2EvpnRouteParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     EthernetAutoDiscoveryError(#[from_located(module = "self")] EthernetAutoDiscoveryParsingError),
     MacIpAdvertisementError(#[from_located(module = "self")] MacIpAdvertisementParsingError),
@@ -788,8 +873,7 @@ pub enum L2EvpnRouteParsingError {
 impl<'a> ReadablePdu<'a, LocatedL2EvpnRouteParsingError<'a>> for L2EvpnRoute {
     fn from_wire(buf: Span<'a>) -> IResult<Span<'a>, Self, LocatedL2EvpnRouteParsingError<'a>> {
         let (buf, typ_code) = be_u8(buf)?;
-        let (buf, len) = be_u8(buf)?;
-        let (buf, route_buf) = nom::bytes::complete::take(len)(buf)?;
+        let (buf, route_buf) = parse_u8_length_prefixed(buf)?;
         let typ = L2EvpnRouteTypeCode::try_from(typ_code);
         let (_buf, value) = match typ {
             Ok(L2EvpnRouteTypeCode::EthernetAutoDiscovery) => {
@@ -828,9 +912,13 @@ impl<'a> ReadablePdu<'a, LocatedL2EvpnRouteParsingError<'a>> for L2EvpnRoute {
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum L2EvpnAddressParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     L2EvpnRouteError(#[from_located(module = "self")] L2EvpnRouteParsingError),
 }
@@ -851,9 +939,13 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedL2EvpnAddressParsingError<'a>>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum L2EvpnIpv4PrefixRouteParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     RouteDistinguisherError(#[from_located(module = "self")] RouteDistinguisherParsingError),
     EthernetSegmentIdentifierError(
@@ -897,9 +989,13 @@ impl<'a> ReadablePdu<'a, LocatedL2EvpnIpv4PrefixRouteParsingError<'a>> for L2Evp
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum L2EvpnIpv6PrefixRouteParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     RouteDistinguisherError(#[from_located(module = "self")] RouteDistinguisherParsingError),
     EthernetSegmentIdentifierError(
@@ -943,7 +1039,8 @@ impl<'a> ReadablePdu<'a, LocatedL2EvpnIpv6PrefixRouteParsingError<'a>> for L2Evp
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum L2EvpnIpPrefixRouteParsingError {
     InvalidBufferLength(usize),
     L2EvpnIpv4PrefixRouteError(#[from_located(module = "self")] L2EvpnIpv4PrefixRouteParsingError),
@@ -973,9 +1070,13 @@ impl<'a> ReadablePdu<'a, LocatedL2EvpnIpPrefixRouteParsingError<'a>> for L2EvpnI
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RouteTargetMembershipAddressParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidPrefixLen(u8),
     LocatedRouteTargetMembershipParsingError(
@@ -1015,9 +1116,13 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedRouteTargetMembershipAddressPa
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RouteTargetMembershipParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -1034,9 +1139,13 @@ impl<'a> ReadablePduWithOneInput<'a, u8, LocatedRouteTargetMembershipParsingErro
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Ipv4NlriMplsLabelsAddressParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     MplsLabelError(#[from_located(module = "self")] MplsLabelParsingError),
     Ipv4PrefixError(#[from_located(module = "crate::wire::deserializer")] Ipv4PrefixParsingError),
@@ -1098,9 +1207,13 @@ impl<'a>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Ipv6NlriMplsLabelsAddressParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     MplsLabelError(#[from_located(module = "self")] MplsLabelParsingError),
     Ipv6PrefixError(#[from_located(module = "crate::wire::deserializer")] Ipv6PrefixParsingError),
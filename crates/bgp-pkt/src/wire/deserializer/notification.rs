@@ -30,18 +30,25 @@ use crate::{
     },
     BgpNotificationMessage,
 };
-use netgauze_parse_utils::{parse_into_located, ErrorKindSerdeDeref, ReadablePdu, Span};
+use netgauze_parse_utils::{parse_into_located, ReadablePdu, Span, StringDecodingPolicy};
 use netgauze_serde_macros::LocatedError;
 use nom::{error::ErrorKind, number::complete::be_u8, IResult};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+use std::string::FromUtf8Error;
 
 /// BGP Notification Message Parsing errors
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpNotificationMessageParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedBgpErrorNotificationCode(#[from_external] UndefinedBgpErrorNotificationCode),
     MessageHeaderError(#[from_located(module = "self")] MessageHeaderErrorParsingError),
     OpenMessageError(#[from_located(module = "self")] OpenMessageErrorParsingError),
@@ -91,12 +98,17 @@ impl<'a> ReadablePdu<'a, LocatedBgpNotificationMessageParsingError<'a>> for BgpN
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MessageHeaderErrorParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedMessageHeaderErrorType(#[from_external] UndefinedMessageHeaderErrorSubCode),
 }
 
@@ -137,12 +149,17 @@ impl<'a> ReadablePdu<'a, LocatedMessageHeaderErrorParsingError<'a>> for MessageH
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum OpenMessageErrorParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedOpenMessageErrorSubCode(#[from_external] UndefinedOpenMessageErrorSubCode),
 }
 
@@ -207,12 +224,17 @@ impl<'a> ReadablePdu<'a, LocatedOpenMessageErrorParsingError<'a>> for OpenMessag
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum UpdateMessageErrorParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedUpdateMessageErrorSubCode(#[from_external] UndefinedUpdateMessageErrorSubCode),
 }
 
@@ -295,11 +317,15 @@ impl<'a> ReadablePdu<'a, LocatedUpdateMessageErrorParsingError<'a>> for UpdateMe
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum HoldTimerExpiredErrorParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -319,12 +345,17 @@ impl<'a> ReadablePdu<'a, LocatedHoldTimerExpiredErrorParsingError<'a>> for HoldT
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FiniteStateMachineErrorParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     Undefined(#[from_external] UndefinedFiniteStateMachineErrorSubCode),
 }
 
@@ -367,12 +398,17 @@ impl<'a> ReadablePdu<'a, LocatedFiniteStateMachineErrorParsingError<'a>>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum CeaseErrorParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     Undefined(#[from_external] UndefinedCeaseErrorSubCode),
 }
 
@@ -446,12 +482,17 @@ impl<'a> ReadablePdu<'a, LocatedCeaseErrorParsingError<'a>> for CeaseError {
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RouteRefreshErrorParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     Undefined(#[from_external] UndefinedRouteRefreshMessageError),
 }
 
@@ -473,3 +514,29 @@ impl<'a> ReadablePdu<'a, LocatedRouteRefreshErrorParsingError<'a>> for RouteRefr
         }
     }
 }
+
+impl CeaseError {
+    /// Shutdown Communication carried by Administrative Shutdown and
+    /// Administrative Reset, see
+    /// [RFC9003](https://datatracker.ietf.org/doc/html/rfc9003), decoded
+    /// according to `string_decoding_policy`.
+    ///
+    /// `Ok(None)` is returned for other subcodes, when no communication is
+    /// included, or when it's not valid UTF-8 and the raw bytes are to be
+    /// kept, they are then only available in the value of the error. A length
+    /// exceeding the data is truncated to the available data.
+    pub fn shutdown_communication(
+        &self,
+        string_decoding_policy: StringDecodingPolicy,
+    ) -> Result<Option<String>, FromUtf8Error> {
+        let value = match self {
+            Self::AdministrativeShutdown { value } | Self::AdministrativeReset { value } => value,
+            _ => return Ok(None),
+        };
+        let (length, communication) = match value.split_first() {
+            Some((length, communication)) if *length > 0 => (*length as usize, communication),
+            _ => return Ok(None),
+        };
+        string_decoding_policy.decode(&communication[..length.min(communication.len())])
+    }
+}
@@ -22,8 +22,7 @@ use crate::{
     BgpOpenMessage,
 };
 use netgauze_parse_utils::{
-    parse_into_located_one_input, ErrorKindSerdeDeref, LocatedParsingError, ReadablePdu,
-    ReadablePduWithOneInput, Span,
+    parse_into_located_one_input, LocatedParsingError, ReadablePdu, ReadablePduWithOneInput, Span,
 };
 use netgauze_serde_macros::LocatedError;
 use nom::{
@@ -31,16 +30,22 @@ use nom::{
     number::complete::{be_u16, be_u32, be_u8},
     IResult,
 };
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::net::Ipv4Addr;
 
 /// BGP Open Message Parsing errors
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpOpenMessageParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(Unsupported)]
     UnsupportedVersionNumber(u8),
     UnacceptableHoldTime(u16),
     /// RFC 4271 specifies that BGP ID must be a valid unicast IP host address.
@@ -49,12 +54,17 @@ pub enum BgpOpenMessageParsingError {
 }
 
 /// BGP Open Message Parsing errors
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpParameterParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedParameterType(#[from_external] UndefinedBgpOpenMessageParameterType),
     CapabilityError(
         #[from_located(module = "crate::wire::deserializer::capabilities")]
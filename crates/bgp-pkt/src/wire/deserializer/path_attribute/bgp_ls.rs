@@ -25,8 +25,8 @@ use crate::{
     },
 };
 use netgauze_parse_utils::{
-    parse_into_located, parse_into_located_one_input, parse_till_empty_into_located,
-    ErrorKindSerdeDeref, ReadablePdu, ReadablePduWithOneInput, Span,
+    parse_into_located, parse_into_located_one_input, parse_till_empty_into_located, ReadablePdu,
+    ReadablePduWithOneInput, Span,
 };
 use netgauze_serde_macros::LocatedError;
 use nom::{
@@ -34,6 +34,7 @@ use nom::{
     number::complete::{be_f32, be_u128, be_u16, be_u32, be_u64, be_u8},
     IResult,
 };
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::{
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
@@ -42,12 +43,17 @@ use std::{
 };
 
 /// BGP Link-State Attribute Parsing Errors
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpLsAttributeParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UnknownTlvType(#[from_external] BgpLsAttributeTypeError),
     Utf8Error(String),
     WrongIpAddrLength(usize),
@@ -17,6 +17,7 @@
 //! Deserializer for BGP Path Attributes
 
 use crate::{
+    community::{Community, ExtendedCommunity, ExtendedCommunityIpv6, LargeCommunity},
     iana::{
         AigpAttributeType, PathAttributeType, UndefinedAigpAttributeType,
         UndefinedPathAttributeType,
@@ -25,8 +26,8 @@ use crate::{
     path_attribute::*,
     wire::{
         deserializer::{
-            community::*, nlri::*, path_attribute::BgpLsAttributeParsingError, BgpParsingContext,
-            IpAddrParsingError,
+            community::*, nlri::*, path_attribute::BgpLsAttributeParsingError, AddPathDirection,
+            BgpParsingContext, IpAddrParsingError,
         },
         serializer::nlri::{IPV4_LEN, IPV6_LEN, IPV6_WITH_LINK_LOCAL_LEN},
         ACCUMULATED_IGP_METRIC,
@@ -37,11 +38,12 @@ use netgauze_iana::address_family::{
     UndefinedSubsequentAddressFamily,
 };
 use netgauze_parse_utils::{
-    parse_into_located_one_input, parse_into_located_three_inputs, parse_into_located_two_inputs,
-    parse_till_empty, parse_till_empty_into_located, parse_till_empty_into_with_one_input_located,
-    parse_till_empty_into_with_three_inputs_located, ErrorKindSerdeDeref, LocatedParsingError,
-    ReadablePdu, ReadablePduWithOneInput, ReadablePduWithThreeInputs, ReadablePduWithTwoInputs,
-    Span,
+    parse_into_located_four_inputs, parse_into_located_one_input, parse_into_located_three_inputs,
+    parse_into_located_two_inputs, parse_till_empty, parse_till_empty_into_collection_located,
+    parse_till_empty_into_located, parse_till_empty_into_with_one_input_located,
+    parse_till_empty_into_with_three_inputs_located, LocatedParsingError, ReadablePdu,
+    ReadablePduWithFourInputs, ReadablePduWithOneInput, ReadablePduWithThreeInputs,
+    ReadablePduWithTwoInputs, Span,
 };
 use netgauze_serde_macros::LocatedError;
 use nom::{
@@ -49,6 +51,7 @@ use nom::{
     number::complete::{be_u128, be_u16, be_u32, be_u64, be_u8},
     IResult,
 };
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
@@ -59,6 +62,7 @@ const OPTIONAL_PATH_ATTRIBUTE_MASK: u8 = 0x80;
 const TRANSITIVE_PATH_ATTRIBUTE_MASK: u8 = 0x40;
 const PARTIAL_PATH_ATTRIBUTE_MASK: u8 = 0x20;
 pub(crate) const EXTENDED_LENGTH_PATH_ATTRIBUTE_MASK: u8 = 0x10;
+const UNUSED_PATH_ATTRIBUTE_MASK: u8 = 0x0f;
 const ORIGIN_LEN: u16 = 1;
 const NEXT_HOP_LEN: u16 = 4;
 const MULTI_EXIT_DISCRIMINATOR_LEN: u16 = 4;
@@ -75,11 +79,15 @@ const fn check_length(attr_len: PathAttributeLength, expected: u16) -> bool {
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PathAttributeParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     OriginError(#[from_located(module = "self")] OriginParsingError),
     AsPathError(#[from_located(module = "self")] AsPathParsingError),
@@ -121,7 +129,12 @@ impl<'a> ReadablePduWithOneInput<'a, &mut BgpParsingContext, LocatedPathAttribut
         buf: Span<'a>,
         ctx: &mut BgpParsingContext,
     ) -> IResult<Span<'a>, Self, LocatedPathAttributeParsingError<'a>> {
-        let (asn4, multiple_labels, add_path_map) = (ctx.asn4, &ctx.multiple_labels, &ctx.add_path);
+        let (asn4, multiple_labels) = (ctx.asn4, &ctx.multiple_labels);
+        let add_path_map = match ctx.add_path_direction {
+            AddPathDirection::Receive => &ctx.add_path,
+            AddPathDirection::Send => &ctx.add_path_send,
+        };
+        let skip_legacy_snpa = ctx.skip_legacy_snpa;
         let (buf, attributes) = be_u8(buf)?;
         let buf_before_code = buf;
         let (buf, code) = be_u8(buf)?;
@@ -172,6 +185,11 @@ impl<'a> ReadablePduWithOneInput<'a, &mut BgpParsingContext, LocatedPathAttribut
                 let value = PathAttributeValue::Aggregator(value);
                 (buf, value)
             }
+            Ok(PathAttributeType::As4Aggregator) => {
+                let (buf, value) = parse_into_located_one_input(buf, extended_length)?;
+                let value = PathAttributeValue::As4Aggregator(value);
+                (buf, value)
+            }
             Ok(PathAttributeType::Communities) => {
                 let (buf, value) = parse_into_located_one_input(buf, extended_length)?;
                 let value = PathAttributeValue::Communities(value);
@@ -203,11 +221,12 @@ impl<'a> ReadablePduWithOneInput<'a, &mut BgpParsingContext, LocatedPathAttribut
                 (buf, value)
             }
             Ok(PathAttributeType::MpReachNlri) => {
-                let (buf, value) = parse_into_located_three_inputs(
+                let (buf, value) = parse_into_located_four_inputs(
                     buf,
                     extended_length,
                     multiple_labels,
                     add_path_map,
+                    skip_legacy_snpa,
                 )?;
                 let value = PathAttributeValue::MpReach(value);
                 (buf, value)
@@ -237,14 +256,12 @@ impl<'a> ReadablePduWithOneInput<'a, &mut BgpParsingContext, LocatedPathAttribut
                 let value = PathAttributeValue::BgpLs(value);
                 (buf, value)
             }
-            Ok(_code) => {
-                let (buf, value) = parse_into_located_one_input(buf_before_code, extended_length)?;
-                let value = PathAttributeValue::UnknownAttribute(value);
-                (buf, value)
-            }
-            Err(UndefinedPathAttributeType(_code)) => {
-                let (buf, value) = parse_into_located_one_input(buf_before_code, extended_length)?;
-                let value = PathAttributeValue::UnknownAttribute(value);
+            Ok(_) | Err(UndefinedPathAttributeType(_)) => {
+                let (buf, value): (_, UnknownAttribute) =
+                    parse_into_located_one_input(buf_before_code, extended_length)?;
+                let value = PathAttributeValue::UnknownAttribute(
+                    value.with_unused_flags(attributes & UNUSED_PATH_ATTRIBUTE_MASK),
+                );
                 (buf, value)
             }
         };
@@ -262,13 +279,18 @@ impl<'a> ReadablePduWithOneInput<'a, &mut BgpParsingContext, LocatedPathAttribut
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum OriginParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidOriginLength(#[from_external] PathAttributeLength),
+    #[category(UnknownCode)]
     UndefinedOrigin(#[from_external] UndefinedOrigin),
 }
 
@@ -296,15 +318,20 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedOriginParsingError<'a>> for Or
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum AsPathParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     /// RFC 7606: An AS_PATH is considered malformed, if it has a Path Segment
     /// Length field of zero.
     ZeroSegmentLength,
+    #[category(UnknownCode)]
     UndefinedAsPathSegmentType(#[from_external] UndefinedAsPathSegmentType),
 }
 
@@ -380,11 +407,15 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedAsPathParsingError<'a>> for As
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum NextHopParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidNextHopLength(PathAttributeLength),
 }
@@ -415,11 +446,15 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedNextHopParsingError<'a>> for N
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MultiExitDiscriminatorParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidLength(PathAttributeLength),
 }
@@ -453,11 +488,15 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedMultiExitDiscriminatorParsingE
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum LocalPreferenceParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidLength(PathAttributeLength),
 }
@@ -489,11 +528,15 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedLocalPreferenceParsingError<'a
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum AtomicAggregateParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidLength(PathAttributeLength),
 }
@@ -523,11 +566,15 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedAtomicAggregateParsingError<'a
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum AggregatorParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidLength(PathAttributeLength),
 }
@@ -602,13 +649,19 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedAggregatorParsingError<'a>> fo
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MpReachParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedAddressFamily(#[from_external] UndefinedAddressFamily),
+    #[category(UnknownCode)]
     UndefinedSubsequentAddressFamily(#[from_external] UndefinedSubsequentAddressFamily),
     IpAddrError(AddressType, IpAddrParsingError),
     LabeledNextHopError(AddressType, LabeledNextHopParsingError),
@@ -652,6 +705,9 @@ pub enum MpReachParsingError {
     BgpLsNlriParsingError(
         #[from_located(module = "crate::wire::deserializer::nlri")] BgpLsNlriParsingError,
     ),
+    FlowSpecNlriError(
+        #[from_located(module = "crate::wire::deserializer::nlri")] FlowSpecNlriParsingError,
+    ),
 }
 
 impl<'a>
@@ -668,6 +724,36 @@ impl<'a>
         extended_length: bool,
         multiple_labels: &HashMap<AddressType, u8>,
         add_path_map: &HashMap<AddressType, bool>,
+    ) -> IResult<Span<'a>, Self, LocatedMpReachParsingError<'a>> {
+        <Self as ReadablePduWithFourInputs<_, _, _, _, _>>::from_wire(
+            buf,
+            extended_length,
+            multiple_labels,
+            add_path_map,
+            false,
+        )
+    }
+}
+
+/// The last input skips the SNPAs encoded by old
+/// [RFC2858](https://datatracker.ietf.org/doc/html/rfc2858) speakers in place
+/// of the reserved octet, see [`BgpParsingContext::skip_legacy_snpa`]
+impl<'a>
+    ReadablePduWithFourInputs<
+        'a,
+        bool,
+        &HashMap<AddressType, u8>,
+        &HashMap<AddressType, bool>,
+        bool,
+        LocatedMpReachParsingError<'a>,
+    > for MpReach
+{
+    fn from_wire(
+        buf: Span<'a>,
+        extended_length: bool,
+        multiple_labels: &HashMap<AddressType, u8>,
+        add_path_map: &HashMap<AddressType, bool>,
+        skip_legacy_snpa: bool,
     ) -> IResult<Span<'a>, Self, LocatedMpReachParsingError<'a>> {
         let (buf, mp_buf) = if extended_length {
             nom::multi::length_data(be_u16)(buf)?
@@ -681,7 +767,7 @@ impl<'a>
             Ok(AddressType::Ipv4Unicast) => {
                 let (mp_buf, (next_hop, next_hop_local)) =
                     parse_ip4_or_ipv6_next_hop(mp_buf, AddressType::Ipv4Unicast)?;
-                let (mp_buf, _) = be_u8(mp_buf)?;
+                let (mp_buf, _) = parse_reserved_or_legacy_snpa(mp_buf, skip_legacy_snpa)?;
                 let add_path = add_path_map
                     .get(&AddressType::Ipv4Unicast)
                     .map_or(false, |x| *x);
@@ -698,7 +784,7 @@ impl<'a>
             Ok(AddressType::Ipv4Multicast) => {
                 let (mp_buf, (next_hop, next_hop_local)) =
                     parse_ip4_or_ipv6_next_hop(mp_buf, AddressType::Ipv4Unicast)?;
-                let (mp_buf, _) = be_u8(mp_buf)?;
+                let (mp_buf, _) = parse_reserved_or_legacy_snpa(mp_buf, skip_legacy_snpa)?;
                 let add_path = add_path_map
                     .get(&AddressType::Ipv4Multicast)
                     .map_or(false, |x| *x);
@@ -715,7 +801,7 @@ impl<'a>
             Ok(AddressType::Ipv4NlriMplsLabels) => {
                 let (mp_buf, (next_hop, next_hop_local)) =
                     parse_ip4_or_ipv6_next_hop(mp_buf, AddressType::Ipv4NlriMplsLabels)?;
-                let (mp_buf, _) = be_u8(mp_buf)?;
+                let (mp_buf, _) = parse_reserved_or_legacy_snpa(mp_buf, skip_legacy_snpa)?;
                 let add_path = add_path_map
                     .get(&AddressType::Ipv4NlriMplsLabels)
                     .map_or(false, |x| *x);
@@ -739,7 +825,7 @@ impl<'a>
             Ok(AddressType::Ipv4MplsLabeledVpn) => {
                 let (mp_buf, next_hop) =
                     parse_labeled_next_hop(mp_buf, AddressType::Ipv4MplsLabeledVpn)?;
-                let (mp_buf, _) = be_u8(mp_buf)?;
+                let (mp_buf, _) = parse_reserved_or_legacy_snpa(mp_buf, skip_legacy_snpa)?;
                 let add_path = add_path_map
                     .get(&AddressType::Ipv4MplsLabeledVpn)
                     .map_or(false, |x| *x);
@@ -763,7 +849,7 @@ impl<'a>
                 } else {
                     (mp_buf, None)
                 };
-                let (mp_buf, _) = be_u8(mp_buf)?;
+                let (mp_buf, _) = parse_reserved_or_legacy_snpa(mp_buf, skip_legacy_snpa)?;
                 let add_path = add_path_map
                     .get(&AddressType::Ipv6Unicast)
                     .map_or(false, |x| *x);
@@ -787,7 +873,7 @@ impl<'a>
                 } else {
                     (mp_buf, None)
                 };
-                let (mp_buf, _) = be_u8(mp_buf)?;
+                let (mp_buf, _) = parse_reserved_or_legacy_snpa(mp_buf, skip_legacy_snpa)?;
                 let add_path = add_path_map
                     .get(&AddressType::Ipv6Multicast)
                     .map_or(false, |x| *x);
@@ -804,7 +890,7 @@ impl<'a>
             Ok(AddressType::Ipv6NlriMplsLabels) => {
                 let (mp_buf, (next_hop, next_hop_local)) =
                     parse_ip4_or_ipv6_next_hop(mp_buf, AddressType::Ipv4NlriMplsLabels)?;
-                let (mp_buf, _) = be_u8(mp_buf)?;
+                let (mp_buf, _) = parse_reserved_or_legacy_snpa(mp_buf, skip_legacy_snpa)?;
                 let add_path = add_path_map
                     .get(&AddressType::Ipv6NlriMplsLabels)
                     .map_or(false, |x| *x);
@@ -828,7 +914,7 @@ impl<'a>
             Ok(AddressType::Ipv6MplsLabeledVpn) => {
                 let (mp_buf, next_hop) =
                     parse_labeled_next_hop(mp_buf, AddressType::Ipv6MplsLabeledVpn)?;
-                let (mp_buf, _) = be_u8(mp_buf)?;
+                let (mp_buf, _) = parse_reserved_or_legacy_snpa(mp_buf, skip_legacy_snpa)?;
                 let add_path = add_path_map
                     .get(&AddressType::Ipv6MplsLabeledVpn)
                     .map_or(false, |x| *x);
@@ -844,7 +930,7 @@ impl<'a>
             }
             Ok(AddressType::L2VpnBgpEvpn) => {
                 let (mp_buf, next_hop) = parse_ip_next_hop(mp_buf, AddressType::L2VpnBgpEvpn)?;
-                let (mp_buf, _) = be_u8(mp_buf)?;
+                let (mp_buf, _) = parse_reserved_or_legacy_snpa(mp_buf, skip_legacy_snpa)?;
                 let add_path = add_path_map
                     .get(&AddressType::L2VpnBgpEvpn)
                     .map_or(false, |x| *x);
@@ -854,7 +940,7 @@ impl<'a>
             Ok(AddressType::RouteTargetConstrains) => {
                 let (mp_buf, next_hop) =
                     parse_ip_next_hop(mp_buf, AddressType::RouteTargetConstrains)?;
-                let (mp_buf, _) = be_u8(mp_buf)?;
+                let (mp_buf, _) = parse_reserved_or_legacy_snpa(mp_buf, skip_legacy_snpa)?;
                 let add_path = add_path_map
                     .get(&AddressType::L2VpnBgpEvpn)
                     .map_or(false, |x| *x);
@@ -863,20 +949,36 @@ impl<'a>
             }
             Ok(AddressType::BgpLs) => {
                 let (mp_buf, next_hop) = parse_ip_next_hop(mp_buf, AddressType::BgpLs)?;
-                let (mp_buf, _) = be_u8(mp_buf)?;
+                let (mp_buf, _) = parse_reserved_or_legacy_snpa(mp_buf, skip_legacy_snpa)?;
                 let add_path = add_path_map.get(&AddressType::BgpLs).map_or(false, |x| *x);
                 let (_, nlri) = parse_till_empty_into_with_one_input_located(mp_buf, add_path)?;
                 Ok((buf, MpReach::BgpLs { next_hop, nlri }))
             }
             Ok(AddressType::BgpLsVpn) => {
                 let (mp_buf, next_hop) = parse_labeled_next_hop(mp_buf, AddressType::BgpLsVpn)?;
-                let (mp_buf, _) = be_u8(mp_buf)?;
+                let (mp_buf, _) = parse_reserved_or_legacy_snpa(mp_buf, skip_legacy_snpa)?;
                 let add_path = add_path_map
                     .get(&AddressType::BgpLsVpn)
                     .map_or(false, |x| *x);
                 let (_, nlri) = parse_till_empty_into_with_one_input_located(mp_buf, add_path)?;
                 Ok((buf, MpReach::BgpLsVpn { next_hop, nlri }))
             }
+            Ok(AddressType::Ipv4FlowSpec) => {
+                let (mp_buf, next_hop) =
+                    parse_optional_ip_next_hop(mp_buf, AddressType::Ipv4FlowSpec)?;
+                let (mp_buf, _) = parse_reserved_or_legacy_snpa(mp_buf, skip_legacy_snpa)?;
+                let (_, nlri) =
+                    parse_till_empty_into_with_one_input_located(mp_buf, AddressFamily::IPv4)?;
+                Ok((buf, MpReach::Ipv4FlowSpec { next_hop, nlri }))
+            }
+            Ok(AddressType::Ipv6FlowSpec) => {
+                let (mp_buf, next_hop) =
+                    parse_optional_ip_next_hop(mp_buf, AddressType::Ipv6FlowSpec)?;
+                let (mp_buf, _) = parse_reserved_or_legacy_snpa(mp_buf, skip_legacy_snpa)?;
+                let (_, nlri) =
+                    parse_till_empty_into_with_one_input_located(mp_buf, AddressFamily::IPv6)?;
+                Ok((buf, MpReach::Ipv6FlowSpec { next_hop, nlri }))
+            }
             Ok(_) | Err(_) => Ok((
                 buf,
                 MpReach::Unknown {
@@ -919,6 +1021,43 @@ fn parse_ip_next_hop(
     Ok((mp_buf, next_hop))
 }
 
+/// Flow Spec routes are usually advertised with an empty (zero length) next
+/// hop, see [RFC8955](https://datatracker.ietf.org/doc/html/rfc8955#section-4)
+#[inline]
+fn parse_optional_ip_next_hop(
+    mp_buf: Span<'_>,
+    address_type: AddressType,
+) -> IResult<Span<'_>, Option<IpAddr>, LocatedMpReachParsingError<'_>> {
+    let (tmp, len) = be_u8(mp_buf)?;
+    if len == 0 {
+        return Ok((tmp, None));
+    }
+    let (mp_buf, next_hop) = parse_ip_next_hop(mp_buf, address_type)?;
+    Ok((mp_buf, Some(next_hop)))
+}
+
+/// Parse the octet following the next hop, which is reserved since
+/// [RFC4760](https://datatracker.ietf.org/doc/html/rfc4760). In
+/// [RFC2858](https://datatracker.ietf.org/doc/html/rfc2858) it was the number of
+/// Subnetwork Points of Attachment (SNPAs) that follow it, each encoded as a
+/// one octet length in semi-octets and the SNPA value. When `skip_legacy_snpa`
+/// is set, the SNPAs are skipped, otherwise the octet is ignored.
+#[inline]
+fn parse_reserved_or_legacy_snpa(
+    mp_buf: Span<'_>,
+    skip_legacy_snpa: bool,
+) -> IResult<Span<'_>, (), LocatedMpReachParsingError<'_>> {
+    let (mut mp_buf, snpa_count) = be_u8(mp_buf)?;
+    if skip_legacy_snpa {
+        for _ in 0..snpa_count {
+            let (buf, snpa_len) = be_u8(mp_buf)?;
+            let (buf, _) = nom::bytes::complete::take(snpa_len.div_ceil(2))(buf)?;
+            mp_buf = buf;
+        }
+    }
+    Ok((mp_buf, ()))
+}
+
 #[inline]
 fn parse_ip4_or_ipv6_next_hop(
     mp_buf: Span<'_>,
@@ -986,13 +1125,19 @@ fn parse_labeled_next_hop(
     Ok((mp_buf, next_hop))
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MpUnreachParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedAddressFamily(#[from_external] UndefinedAddressFamily),
+    #[category(UnknownCode)]
     UndefinedSubsequentAddressFamily(#[from_external] UndefinedSubsequentAddressFamily),
     Ipv4UnicastAddressError(
         #[from_located(module = "crate::wire::deserializer::nlri")] Ipv4UnicastAddressParsingError,
@@ -1032,6 +1177,9 @@ pub enum MpUnreachParsingError {
         RouteTargetMembershipAddressParsingError,
     ),
     BgpLsError(#[from_located(module = "crate::wire::deserializer::nlri")] BgpLsNlriParsingError),
+    FlowSpecNlriError(
+        #[from_located(module = "crate::wire::deserializer::nlri")] FlowSpecNlriParsingError,
+    ),
 }
 
 impl<'a>
@@ -1168,6 +1316,16 @@ impl<'a>
                 let (_, nlri) = parse_till_empty_into_with_one_input_located(mp_buf, add_path)?;
                 Ok((buf, MpUnreach::BgpLsVpn { nlri }))
             }
+            Ok(AddressType::Ipv4FlowSpec) => {
+                let (_, nlri) =
+                    parse_till_empty_into_with_one_input_located(mp_buf, AddressFamily::IPv4)?;
+                Ok((buf, MpUnreach::Ipv4FlowSpec { nlri }))
+            }
+            Ok(AddressType::Ipv6FlowSpec) => {
+                let (_, nlri) =
+                    parse_till_empty_into_with_one_input_located(mp_buf, AddressFamily::IPv6)?;
+                Ok((buf, MpUnreach::Ipv6FlowSpec { nlri }))
+            }
             Ok(_) | Err(_) => Ok((
                 buf,
                 MpUnreach::Unknown {
@@ -1180,11 +1338,15 @@ impl<'a>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum UnknownAttributeParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -1210,11 +1372,15 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedUnknownAttributeParsingError<'
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum CommunitiesParsingError {
     /// Errors triggered by the nom parser, see [ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     CommunityError(
         #[from_located(module = "crate::wire::deserializer::community")] CommunityParsingError,
@@ -1231,14 +1397,19 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedCommunitiesParsingError<'a>> f
         } else {
             nom::multi::length_data(be_u8)(buf)?
         };
-        let (_, communities) = parse_till_empty_into_located(communities_buf)?;
-        Ok((buf, Communities::new(communities)))
+        let (_, communities) =
+            parse_till_empty_into_collection_located::<_, _, Community, _>(communities_buf)?;
+        Ok((buf, Communities::from_list(communities)))
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ExtendedCommunitiesParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     ExtendedCommunityError(
         #[from_located(module = "crate::wire::deserializer::community")]
@@ -1258,14 +1429,21 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedExtendedCommunitiesParsingErro
         } else {
             nom::multi::length_data(be_u8)(buf)?
         };
-        let (_, communities) = parse_till_empty_into_located(communities_buf)?;
-        Ok((buf, ExtendedCommunities::new(communities)))
+        let (_, communities) =
+            parse_till_empty_into_collection_located::<_, _, ExtendedCommunity, _>(
+                communities_buf,
+            )?;
+        Ok((buf, ExtendedCommunities::from_list(communities)))
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ExtendedCommunitiesIpv6ParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     ExtendedCommunityIpv6Error(
         #[from_located(module = "crate::wire::deserializer::community")]
@@ -1285,14 +1463,21 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedExtendedCommunitiesIpv6Parsing
         } else {
             nom::multi::length_data(be_u8)(buf)?
         };
-        let (_, communities) = parse_till_empty_into_located(communities_buf)?;
-        Ok((buf, ExtendedCommunitiesIpv6::new(communities)))
+        let (_, communities) =
+            parse_till_empty_into_collection_located::<_, _, ExtendedCommunityIpv6, _>(
+                communities_buf,
+            )?;
+        Ok((buf, ExtendedCommunitiesIpv6::from_list(communities)))
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum LargeCommunitiesParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     LargeCommunityError(
         #[from_located(module = "crate::wire::deserializer::community")] LargeCommunityParsingError,
@@ -1311,14 +1496,19 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedLargeCommunitiesParsingError<'
         } else {
             nom::multi::length_data(be_u8)(buf)?
         };
-        let (_, communities) = parse_till_empty_into_located(communities_buf)?;
-        Ok((buf, LargeCommunities::new(communities)))
+        let (_, communities) =
+            parse_till_empty_into_collection_located::<_, _, LargeCommunity, _>(communities_buf)?;
+        Ok((buf, LargeCommunities::from_list(communities)))
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum OriginatorParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -1337,9 +1527,13 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedOriginatorParsingError<'a>> fo
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ClusterIdParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -1350,9 +1544,13 @@ impl<'a> ReadablePdu<'a, LocatedClusterIdParsingError<'a>> for ClusterId {
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ClusterListParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     ClusterIdError(#[from_located(module = "self")] ClusterIdParsingError),
 }
@@ -1372,10 +1570,15 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedClusterListParsingError<'a>> f
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum AigpParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedAigpAttributeType(#[from_external] UndefinedAigpAttributeType),
     InvalidLength(u16),
 }
@@ -1409,9 +1612,13 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedAigpParsingError<'a>> for Aigp
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum OnlyToCustomerParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
 }
 
@@ -29,20 +29,27 @@ use nom::{
     number::complete::{be_u16, be_u8},
     IResult,
 };
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use netgauze_serde_macros::LocatedError;
 
 use crate::notification::RouteRefreshError;
-use netgauze_parse_utils::ErrorKindSerdeDeref;
 
 /// BGP Route Refresh Message Parsing errors
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpRouteRefreshMessageParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedOperation(#[from_external] UndefinedRouteRefreshSubcode),
+    #[category(UnknownCode)]
     UndefinedAddressFamily(#[from_external] UndefinedAddressFamily),
+    #[category(UnknownCode)]
     UndefinedSubsequentAddressFamily(#[from_external] UndefinedSubsequentAddressFamily),
     InvalidAddressType(InvalidAddressType),
 }
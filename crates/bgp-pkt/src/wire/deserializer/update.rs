@@ -21,7 +21,7 @@ use crate::{
     },
     BgpUpdateMessage,
 };
-use ipnet::Ipv4Net;
+use ipnet::{IpNet, Ipv4Net};
 use netgauze_iana::address_family::AddressType;
 use netgauze_parse_utils::{
     parse_into_located, LocatedParsingError, ReadablePduWithOneInput, Span,
@@ -30,26 +30,32 @@ use nom::{
     number::complete::{be_u16, be_u32, be_u8},
     IResult,
 };
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    iana::PathAttributeType,
     nlri::{InvalidIpv4UnicastNetwork, Ipv4Unicast, Ipv4UnicastAddress},
     notification::UpdateMessageError,
     path_attribute::PathAttribute,
+    update::{Ipv4NlriList, PathAttributeList},
     wire::deserializer::{
         path_attribute::{OriginParsingError, EXTENDED_LENGTH_PATH_ATTRIBUTE_MASK},
         BgpParsingContext, Ipv4PrefixParsingError,
     },
 };
-use netgauze_parse_utils::ErrorKindSerdeDeref;
 use netgauze_serde_macros::LocatedError;
 
 /// BGP Open Message Parsing errors
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpUpdateMessageParsingError {
     /// Errors triggered by the nom parser, see [nom::error::ErrorKind] for
     /// additional information.
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] nom::error::ErrorKind),
     PathAttributeError(
         #[from_located(module = "crate::wire::deserializer::path_attribute")]
@@ -57,6 +63,10 @@ pub enum BgpUpdateMessageParsingError {
     ),
     Ipv4PrefixError(#[from_located(module = "crate::wire::deserializer")] Ipv4PrefixParsingError),
     InvalidIpv4UnicastNetwork(InvalidIpv4UnicastNetwork),
+    MissingMandatoryAttribute(PathAttributeType),
+    /// The prefix has bits set beyond its prefix length, raised when
+    /// [`BgpParsingContext::host_bits_policy`] is [`crate::nlri::HostBitsPolicy::Reject`]
+    PrefixHostBitsSet(IpNet),
 }
 
 #[inline]
@@ -65,9 +75,9 @@ fn parse_nlri<'a>(
     add_path: bool,
     is_update: bool,
     ctx: &mut BgpParsingContext,
-) -> IResult<Span<'a>, Vec<Ipv4UnicastAddress>, LocatedBgpUpdateMessageParsingError<'a>> {
+) -> IResult<Span<'a>, Ipv4NlriList, LocatedBgpUpdateMessageParsingError<'a>> {
     let mut buf = buf;
-    let mut nlri_vec = Vec::new();
+    let mut nlri_vec = Ipv4NlriList::new();
     while !buf.is_empty() {
         let (tmp, path_id) = if add_path {
             let (tmp, add_path) = be_u32(buf)?;
@@ -153,14 +163,16 @@ impl<'a>
         buf: Span<'a>,
         ctx: &mut BgpParsingContext,
     ) -> IResult<Span<'a>, Self, LocatedBgpUpdateMessageParsingError<'a>> {
+        let input = buf;
         let add_path = ctx
-            .add_path
+            .active_add_path()
             .get(&AddressType::Ipv4Unicast)
             .map_or(false, |x| *x);
         let (buf, withdrawn_buf) = nom::multi::length_data(be_u16)(buf)?;
         let (_, withdrawn_routes) = parse_nlri(withdrawn_buf, add_path, false, ctx)?;
         let (buf, mut path_attributes_buf) = nom::multi::length_data(be_u16)(buf)?;
-        let mut path_attributes = Vec::new();
+        let path_attributes_begin = path_attributes_buf;
+        let mut path_attributes = PathAttributeList::new();
         while !path_attributes_buf.is_empty() {
             match PathAttribute::from_wire(path_attributes_buf, &mut *ctx) {
                 Ok((tmp, element)) => {
@@ -195,10 +207,31 @@ impl<'a>
             };
         }
         let (buf, nlri_vec) = parse_nlri(buf, add_path, true, ctx)?;
-        Ok((
-            buf,
-            BgpUpdateMessage::new(withdrawn_routes, path_attributes, nlri_vec),
-        ))
+        let mut update = BgpUpdateMessage::from_lists(withdrawn_routes, path_attributes, nlri_vec);
+        match update.apply_host_bits_policy(ctx.host_bits_policy) {
+            Ok(normalized) => ctx
+                .parsing_errors
+                .normalized_host_bits_prefixes
+                .extend(normalized),
+            Err(net) => {
+                return Err(nom::Err::Error(LocatedBgpUpdateMessageParsingError::new(
+                    input,
+                    BgpUpdateMessageParsingError::PrefixHostBitsSet(net),
+                )));
+            }
+        }
+        if let Some(missing) = update.missing_mandatory_attributes().next() {
+            if ctx.fail_on_missing_mandatory_attr {
+                return Err(nom::Err::Error(LocatedBgpUpdateMessageParsingError::new(
+                    path_attributes_begin,
+                    BgpUpdateMessageParsingError::MissingMandatoryAttribute(missing),
+                )));
+            }
+            ctx.parsing_errors
+                .missing_mandatory_attrs
+                .extend(update.missing_mandatory_attributes());
+        }
+        Ok((buf, update))
     }
 }
 
@@ -402,6 +435,18 @@ impl From<BgpUpdateMessageParsingError> for UpdateMessageError {
                 // Unspecific error
                 UpdateMessageError::Unspecific { value: vec![] }
             }
+            BgpUpdateMessageParsingError::PrefixHostBitsSet(_) => {
+                // Only raised when the parser is configured to reject prefixes with host
+                // bits set, treat it like any other syntactically incorrect NLRI field.
+                UpdateMessageError::InvalidNetworkField { value: vec![] }
+            }
+            BgpUpdateMessageParsingError::MissingMandatoryAttribute(attr_type) => {
+                // RFC 4271: The Data field contains the Attribute Type Code of the missing,
+                // well-known attribute.
+                UpdateMessageError::MissingWellKnownAttribute {
+                    value: vec![attr_type.into()],
+                }
+            }
         }
     }
 }
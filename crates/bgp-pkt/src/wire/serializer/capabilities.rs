@@ -23,7 +23,7 @@ use crate::{
     },
 };
 use byteorder::{NetworkEndian, WriteBytesExt};
-use netgauze_parse_utils::WritablePdu;
+use netgauze_parse_utils::{bounded::write_u8_length, WritablePdu};
 use netgauze_serde_macros::WritingError;
 use std::io::Write;
 
@@ -67,28 +67,28 @@ impl WritablePdu<BGPCapabilityWritingError> for BgpCapability {
     }
 
     fn write<T: Write>(&self, writer: &mut T) -> Result<(), BGPCapabilityWritingError> {
-        let len = (self.len() - Self::BASE_LENGTH) as u8;
+        let len = self.len() - Self::BASE_LENGTH;
         match self {
             Self::MultiProtocolExtensions(value) => {
                 writer.write_u8(self.code().unwrap().into())?;
-                writer.write_u8(value.len() as u8)?;
+                write_u8_length(writer, value.len())?;
                 value.write(writer)?;
             }
             Self::RouteRefresh => {
                 writer.write_u8(self.code().unwrap().into())?;
-                writer.write_u8(len)?;
+                write_u8_length(writer, len)?;
             }
             Self::EnhancedRouteRefresh => {
                 writer.write_u8(self.code().unwrap().into())?;
-                writer.write_u8(len)?;
+                write_u8_length(writer, len)?;
             }
             Self::CiscoRouteRefresh => {
                 writer.write_u8(self.code().unwrap().into())?;
-                writer.write_u8(len)?;
+                write_u8_length(writer, len)?;
             }
             Self::ExtendedMessage => {
                 writer.write_u8(self.code().unwrap().into())?;
-                writer.write_u8(len)?;
+                write_u8_length(writer, len)?;
             }
             Self::MultipleLabels(value) => {
                 writer.write_u8(self.code().unwrap().into())?;
@@ -98,22 +98,22 @@ impl WritablePdu<BGPCapabilityWritingError> for BgpCapability {
             }
             Self::BgpRole(value) => {
                 writer.write_u8(self.code().unwrap().into())?;
-                writer.write_u8(len)?;
+                write_u8_length(writer, len)?;
                 value.write(writer)?;
             }
             Self::GracefulRestartCapability(value) => {
                 writer.write_u8(self.code().unwrap().into())?;
-                writer.write_u8(len)?;
+                write_u8_length(writer, len)?;
                 value.write(writer)?;
             }
             Self::AddPath(value) => {
                 writer.write_u8(self.code().unwrap().into())?;
-                writer.write_u8(len)?;
+                write_u8_length(writer, len)?;
                 value.write(writer)?;
             }
             Self::FourOctetAs(value) => {
                 writer.write_u8(self.code().unwrap().into())?;
-                writer.write_u8(value.len() as u8)?;
+                write_u8_length(writer, value.len())?;
                 value.write(writer)?;
             }
             Self::ExtendedNextHopEncoding(value) => {
@@ -122,12 +122,12 @@ impl WritablePdu<BGPCapabilityWritingError> for BgpCapability {
             }
             Self::Experimental(value) => {
                 writer.write_u8(value.code() as u8)?;
-                writer.write_u8(len)?;
+                write_u8_length(writer, len)?;
                 writer.write_all(value.value())?;
             }
             Self::Unrecognized(value) => {
                 writer.write_u8(*value.code())?;
-                writer.write_u8(len)?;
+                write_u8_length(writer, len)?;
                 writer.write_all(value.value())?;
             }
         }
@@ -301,7 +301,7 @@ impl WritablePdu<ExtendedNextHopEncodingCapabilityWritingError>
         &self,
         writer: &mut T,
     ) -> Result<(), ExtendedNextHopEncodingCapabilityWritingError> {
-        writer.write_u8(self.len() as u8 - 1)?;
+        write_u8_length(writer, self.len() - 1)?;
         for encoding in self.encodings() {
             encoding.write(writer)?;
         }
@@ -0,0 +1,235 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::nlri::{
+    BitmaskOperation, FlowSpecComponent, FlowSpecNlri, FlowSpecPrefix, NumericOperation,
+    OperationValue,
+};
+use byteorder::{NetworkEndian, WriteBytesExt};
+use netgauze_parse_utils::WritablePdu;
+use netgauze_serde_macros::WritingError;
+use std::io::Write;
+
+/// Largest value length that can be encoded in the 0xfnnn form
+const MAX_FLOW_SPEC_LEN: usize = 0x0fff;
+
+/// Value lengths from 240 octets are encoded in two octets
+const FLOW_SPEC_EXTENDED_LEN: usize = 240;
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum FlowSpecNlriWritingError {
+    StdIOError(#[from_std_io_error] String),
+    /// The components are longer than 4095 octets
+    NlriTooLong(usize),
+}
+
+impl WritablePdu<FlowSpecNlriWritingError> for FlowSpecNlri {
+    const BASE_LENGTH: usize = 1;
+
+    fn len(&self) -> usize {
+        let value_len: usize = self.components().iter().map(|x| x.len()).sum();
+        let len_len = if value_len < FLOW_SPEC_EXTENDED_LEN {
+            Self::BASE_LENGTH
+        } else {
+            2
+        };
+        len_len + value_len
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), FlowSpecNlriWritingError> {
+        let value_len: usize = self.components().iter().map(|x| x.len()).sum();
+        if value_len > MAX_FLOW_SPEC_LEN {
+            return Err(FlowSpecNlriWritingError::NlriTooLong(value_len));
+        }
+        if value_len < FLOW_SPEC_EXTENDED_LEN {
+            writer.write_u8(value_len as u8)?;
+        } else {
+            writer.write_u16::<NetworkEndian>(0xf000 | value_len as u16)?;
+        }
+        for component in self.components() {
+            component.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl WritablePdu<FlowSpecNlriWritingError> for FlowSpecComponent {
+    // Component type
+    const BASE_LENGTH: usize = 1;
+
+    fn len(&self) -> usize {
+        let value_len = match self {
+            Self::DestinationPrefix(prefix) | Self::SourcePrefix(prefix) => prefix.len(),
+            Self::IpProtocol(ops)
+            | Self::Port(ops)
+            | Self::DestinationPort(ops)
+            | Self::SourcePort(ops)
+            | Self::IcmpType(ops)
+            | Self::IcmpCode(ops)
+            | Self::PacketLength(ops)
+            | Self::Dscp(ops)
+            | Self::FlowLabel(ops) => ops.iter().map(|x| x.len()).sum(),
+            Self::TcpFlags(ops) | Self::Fragment(ops) => ops.iter().map(|x| x.len()).sum(),
+        };
+        Self::BASE_LENGTH + value_len
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), FlowSpecNlriWritingError> {
+        writer.write_u8(self.component_type().into())?;
+        match self {
+            Self::DestinationPrefix(prefix) | Self::SourcePrefix(prefix) => prefix.write(writer)?,
+            Self::IpProtocol(ops)
+            | Self::Port(ops)
+            | Self::DestinationPort(ops)
+            | Self::SourcePort(ops)
+            | Self::IcmpType(ops)
+            | Self::IcmpCode(ops)
+            | Self::PacketLength(ops)
+            | Self::Dscp(ops)
+            | Self::FlowLabel(ops) => {
+                for (index, op) in ops.iter().enumerate() {
+                    op.write(writer, index + 1 == ops.len())?;
+                }
+            }
+            Self::TcpFlags(ops) | Self::Fragment(ops) => {
+                for (index, op) in ops.iter().enumerate() {
+                    op.write(writer, index + 1 == ops.len())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl WritablePdu<FlowSpecNlriWritingError> for FlowSpecPrefix {
+    // Prefix length
+    const BASE_LENGTH: usize = 1;
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Ipv4(prefix) => Self::BASE_LENGTH + usize::from(prefix.prefix_len().div_ceil(8)),
+            Self::Ipv6 { prefix, offset } => {
+                let pattern_len = prefix.prefix_len().saturating_sub(*offset);
+                // Prefix length, offset, and the pattern
+                Self::BASE_LENGTH + 1 + usize::from(pattern_len.div_ceil(8))
+            }
+        }
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), FlowSpecNlriWritingError> {
+        match self {
+            Self::Ipv4(prefix) => {
+                writer.write_u8(prefix.prefix_len())?;
+                let len = usize::from(prefix.prefix_len().div_ceil(8));
+                writer.write_all(&prefix.addr().octets()[..len])?;
+            }
+            Self::Ipv6 { prefix, offset } => {
+                let pattern_len = prefix.prefix_len().saturating_sub(*offset);
+                writer.write_u8(prefix.prefix_len())?;
+                writer.write_u8(*offset)?;
+                let pattern = u128::from(prefix.addr())
+                    .checked_shl(u32::from(*offset))
+                    .unwrap_or_default();
+                let len = usize::from(pattern_len.div_ceil(8));
+                writer.write_all(&pattern.to_be_bytes()[..len])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl OperationValue {
+    /// Number of octets of the value
+    const fn width(&self) -> usize {
+        match self {
+            Self::U8(_) => 1,
+            Self::U16(_) => 2,
+            Self::U32(_) => 4,
+            Self::U64(_) => 8,
+        }
+    }
+
+    /// Operator bits encoding the width of the value
+    const fn len_bits(&self) -> u8 {
+        match self {
+            Self::U8(_) => 0x00,
+            Self::U16(_) => 0x10,
+            Self::U32(_) => 0x20,
+            Self::U64(_) => 0x30,
+        }
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), std::io::Error> {
+        match self {
+            Self::U8(value) => writer.write_u8(*value),
+            Self::U16(value) => writer.write_u16::<NetworkEndian>(*value),
+            Self::U32(value) => writer.write_u32::<NetworkEndian>(*value),
+            Self::U64(value) => writer.write_u64::<NetworkEndian>(*value),
+        }
+    }
+}
+
+impl NumericOperation {
+    fn len(&self) -> usize {
+        1 + self.value().width()
+    }
+
+    /// `end_of_list` is set for the last operation of the component
+    fn write<T: Write>(&self, writer: &mut T, end_of_list: bool) -> Result<(), std::io::Error> {
+        let mut operator = self.value().len_bits();
+        if end_of_list {
+            operator |= 0x80;
+        }
+        if self.and() {
+            operator |= 0x40;
+        }
+        if self.lt() {
+            operator |= 0x04;
+        }
+        if self.gt() {
+            operator |= 0x02;
+        }
+        if self.eq() {
+            operator |= 0x01;
+        }
+        writer.write_u8(operator)?;
+        self.value().write(writer)
+    }
+}
+
+impl BitmaskOperation {
+    fn len(&self) -> usize {
+        1 + self.value().width()
+    }
+
+    /// `end_of_list` is set for the last operation of the component
+    fn write<T: Write>(&self, writer: &mut T, end_of_list: bool) -> Result<(), std::io::Error> {
+        let mut operator = self.value().len_bits();
+        if end_of_list {
+            operator |= 0x80;
+        }
+        if self.and() {
+            operator |= 0x40;
+        }
+        if self.not() {
+            operator |= 0x02;
+        }
+        if self.matched() {
+            operator |= 0x01;
+        }
+        writer.write_u8(operator)?;
+        self.value().write(writer)
+    }
+}
@@ -14,8 +14,10 @@
 // limitations under the License.
 
 pub mod bgp_ls;
+pub mod flow_spec;
 #[allow(clippy::module_inception)]
 mod nlri;
 
 pub use bgp_ls::*;
+pub use flow_spec::*;
 pub use nlri::*;
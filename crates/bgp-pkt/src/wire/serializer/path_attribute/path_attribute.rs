@@ -27,7 +27,7 @@ use crate::{
     },
 };
 use byteorder::{NetworkEndian, WriteBytesExt};
-use netgauze_parse_utils::{WritablePdu, WritablePduWithOneInput};
+use netgauze_parse_utils::{bounded::write_u8_length, WritablePdu, WritablePduWithOneInput};
 use netgauze_serde_macros::WritingError;
 use std::net::IpAddr;
 
@@ -68,6 +68,7 @@ impl WritablePdu<PathAttributeWritingError> for PathAttribute {
             PathAttributeValue::LocalPreference(value) => value.len(self.extended_length()),
             PathAttributeValue::AtomicAggregate(value) => value.len(self.extended_length()),
             PathAttributeValue::Aggregator(value) => value.len(self.extended_length()),
+            PathAttributeValue::As4Aggregator(value) => value.len(self.extended_length()),
             PathAttributeValue::Communities(value) => value.len(self.extended_length()),
             PathAttributeValue::ExtendedCommunities(value) => value.len(self.extended_length()),
             PathAttributeValue::ExtendedCommunitiesIpv6(value) => value.len(self.extended_length()),
@@ -98,6 +99,9 @@ impl WritablePdu<PathAttributeWritingError> for PathAttribute {
         if self.extended_length() {
             attributes |= 0b00010000;
         }
+        if let PathAttributeValue::UnknownAttribute(value) = self.value() {
+            attributes |= value.unused_flags();
+        }
         writer.write_u8(attributes)?;
         if let Ok(path_attribute_type) = self.path_attribute_type() {
             writer.write_u8(path_attribute_type.into())?;
@@ -127,6 +131,9 @@ impl WritablePdu<PathAttributeWritingError> for PathAttribute {
             PathAttributeValue::Aggregator(value) => {
                 value.write(writer, self.extended_length())?;
             }
+            PathAttributeValue::As4Aggregator(value) => {
+                value.write(writer, self.extended_length())?;
+            }
             PathAttributeValue::Communities(value) => {
                 value.write(writer, self.extended_length())?;
             }
@@ -212,7 +219,7 @@ impl WritablePdu<AsPathWritingError> for As2PathSegment {
 
     fn write<T: std::io::Write>(&self, writer: &mut T) -> Result<(), AsPathWritingError> {
         writer.write_u8(self.segment_type() as u8)?;
-        writer.write_u8(self.as_numbers().len() as u8)?;
+        write_u8_length(writer, self.as_numbers().len())?;
         for as_num in self.as_numbers() {
             writer.write_u16::<NetworkEndian>(*as_num)?;
         }
@@ -231,7 +238,7 @@ impl WritablePdu<AsPathWritingError> for As4PathSegment {
 
     fn write<T: std::io::Write>(&self, writer: &mut T) -> Result<(), AsPathWritingError> {
         writer.write_u8(self.segment_type() as u8)?;
-        writer.write_u8(self.as_numbers().len() as u8)?;
+        write_u8_length(writer, self.as_numbers().len())?;
         for as_num in self.as_numbers() {
             writer.write_u32::<NetworkEndian>(*as_num)?;
         }
@@ -563,6 +570,9 @@ impl WritablePduWithOneInput<bool, ClusterListWritingError> for ClusterList {
 #[derive(WritingError, Eq, PartialEq, Clone, Debug)]
 pub enum UnknownAttributeWritingError {
     StdIOError(#[from_std_io_error] String),
+    /// The value doesn't fit in the attribute length field, the extended
+    /// length flag must be set for values longer than 255 octets
+    ValueLengthOverflow(usize),
 }
 
 impl WritablePduWithOneInput<bool, UnknownAttributeWritingError> for UnknownAttribute {
@@ -578,12 +588,16 @@ impl WritablePduWithOneInput<bool, UnknownAttributeWritingError> for UnknownAttr
         writer: &mut T,
         extended_length: bool,
     ) -> Result<(), UnknownAttributeWritingError> {
+        // Written verbatim, so the attribute is relayed as received even when
+        // its type code isn't supported
         writer.write_u8(self.code())?;
-        let len = self.len(extended_length) - Self::BASE_LENGTH;
-        if extended_length || len > u8::MAX.into() {
-            writer.write_u16::<NetworkEndian>((len - 1) as u16)?;
-        } else {
+        let len = self.value().len();
+        if extended_length && len <= u16::MAX.into() {
+            writer.write_u16::<NetworkEndian>(len as u16)?;
+        } else if !extended_length && len <= u8::MAX.into() {
             writer.write_u8(len as u8)?;
+        } else {
+            return Err(UnknownAttributeWritingError::ValueLengthOverflow(len));
         }
         writer.write_all(self.value())?;
         Ok(())
@@ -725,6 +739,7 @@ pub enum MpReachWritingError {
     RouteTargetMembershipAddressError(#[from] RouteTargetMembershipAddressWritingError),
     BgpLsNlriWritingError(#[from] BgpLsNlriWritingError),
     RouteDistinguisherWritingError(#[from] RouteDistinguisherWritingError),
+    FlowSpecNlriError(#[from] FlowSpecNlriWritingError),
 }
 
 impl WritablePduWithOneInput<bool, MpReachWritingError> for MpReach {
@@ -869,6 +884,15 @@ impl WritablePduWithOneInput<bool, MpReachWritingError> for MpReach {
 
                 next_hop_len + ls_nlri_len
             }
+            Self::Ipv4FlowSpec { next_hop, nlri } | Self::Ipv6FlowSpec { next_hop, nlri } => {
+                let next_hop_len = match next_hop {
+                    None => 0,
+                    Some(IpAddr::V4(_)) => IPV4_LEN as usize,
+                    Some(IpAddr::V6(_)) => IPV6_LEN as usize,
+                };
+                let nlri_len: usize = nlri.iter().map(|x| x.len()).sum();
+                next_hop_len + 1 + nlri_len
+            }
             Self::Unknown {
                 afi: _,
                 safi: _,
@@ -1121,6 +1145,18 @@ impl WritablePduWithOneInput<bool, MpReachWritingError> for MpReach {
                     nlri.write(writer)?
                 }
             }
+            Self::Ipv4FlowSpec { next_hop, nlri } | Self::Ipv6FlowSpec { next_hop, nlri } => {
+                writer.write_u16::<NetworkEndian>(self.afi().into())?;
+                writer.write_u8(self.safi().into())?;
+                match next_hop {
+                    Some(next_hop) => next_hop.write(writer)?,
+                    None => writer.write_u8(0)?,
+                }
+                writer.write_u8(0)?;
+                for nlri in nlri {
+                    nlri.write(writer)?
+                }
+            }
             Self::Unknown { value, .. } => {
                 writer.write_u16::<NetworkEndian>(self.afi().into())?;
                 writer.write_u8(self.safi().into())?;
@@ -1145,6 +1181,7 @@ pub enum MpUnreachWritingError {
     L2EvpnAddressError(#[from] L2EvpnAddressWritingError),
     RouteTargetMembershipAddressError(#[from] RouteTargetMembershipAddressWritingError),
     BgpLsError(#[from] BgpLsNlriWritingError),
+    FlowSpecNlriError(#[from] FlowSpecNlriWritingError),
 }
 
 impl WritablePduWithOneInput<bool, MpUnreachWritingError> for MpUnreach {
@@ -1166,6 +1203,8 @@ impl WritablePduWithOneInput<bool, MpUnreachWritingError> for MpUnreach {
             Self::RouteTargetMembership { nlri } => nlri.iter().map(|x| x.len()).sum(),
             Self::BgpLs { nlri } => nlri.iter().map(|x| x.len()).sum(),
             Self::BgpLsVpn { nlri } => nlri.iter().map(|x| x.len()).sum(),
+            Self::Ipv4FlowSpec { nlri } => nlri.iter().map(|x| x.len()).sum(),
+            Self::Ipv6FlowSpec { nlri } => nlri.iter().map(|x| x.len()).sum(),
             Self::Unknown {
                 afi: _,
                 safi: _,
@@ -1266,6 +1305,13 @@ impl WritablePduWithOneInput<bool, MpUnreachWritingError> for MpUnreach {
                     nlri.write(writer)?
                 }
             }
+            Self::Ipv4FlowSpec { nlri } | Self::Ipv6FlowSpec { nlri } => {
+                writer.write_u16::<NetworkEndian>(self.afi().into())?;
+                writer.write_u8(self.safi().into())?;
+                for nlri in nlri {
+                    nlri.write(writer)?
+                }
+            }
             Self::Unknown {
                 afi: _afi,
                 safi: _safi,
@@ -16,10 +16,14 @@
 //! Serializer for BGP Update message
 
 use crate::{
-    wire::serializer::{
-        nlri::Ipv4UnicastAddressWritingError, path_attribute::PathAttributeWritingError,
+    path_attribute::{MpReach, MpUnreach, PathAttribute, PathAttributeValue},
+    wire::{
+        deserializer::BGP_MIN_MESSAGE_LENGTH,
+        serializer::{
+            nlri::Ipv4UnicastAddressWritingError, path_attribute::PathAttributeWritingError,
+        },
     },
-    BgpUpdateMessage,
+    BgpMessage, BgpUpdateMessage,
 };
 use byteorder::{NetworkEndian, WriteBytesExt};
 use netgauze_parse_utils::WritablePdu;
@@ -30,6 +34,10 @@ pub enum BgpUpdateMessageWritingError {
     StdIOError(#[from_std_io_error] String),
     Ipv4UnicastAddressError(#[from] Ipv4UnicastAddressWritingError),
     PathAttributeError(#[from] PathAttributeWritingError),
+    /// The update message cannot be split into messages that fit the given
+    /// max message length, for instance when the path attributes alone are
+    /// larger than the max length. The value carried is the max length.
+    UpdateCannotBeSplit(u16),
 }
 
 impl WritablePdu<BgpUpdateMessageWritingError> for BgpUpdateMessage {
@@ -75,3 +83,423 @@ impl WritablePdu<BgpUpdateMessageWritingError> for BgpUpdateMessage {
         Ok(())
     }
 }
+
+impl BgpUpdateMessage {
+    /// Split the update into one or more [`BgpMessage::Update`] messages, such
+    /// that each message (including the BGP header) fits in
+    /// `max_message_length` octets. Use
+    /// [`crate::wire::deserializer::BGP_MAX_MESSAGE_LENGTH`] by default, or
+    /// [`crate::wire::deserializer::BGP_MAX_EXTENDED_MESSAGE_LENGTH`] when
+    /// the extended message capability is negotiated with the peer.
+    ///
+    /// Withdrawn routes (both in the withdrawn routes field and in
+    /// `MP_UNREACH_NLRI`) are sent in messages that carry no other path
+    /// attributes. Announced routes (both in the NLRI field and in
+    /// `MP_REACH_NLRI`) are sent along with a copy of all the other path
+    /// attributes. If the update already fits, it's returned as is.
+    pub fn split(
+        &self,
+        max_message_length: u16,
+    ) -> Result<Vec<BgpMessage>, BgpUpdateMessageWritingError> {
+        if BGP_MIN_MESSAGE_LENGTH as usize + self.len() <= max_message_length as usize {
+            return Ok(vec![BgpMessage::Update(self.clone())]);
+        }
+        let budget = (max_message_length as usize)
+            .checked_sub(BGP_MIN_MESSAGE_LENGTH as usize + Self::BASE_LENGTH)
+            .ok_or(BgpUpdateMessageWritingError::UpdateCannotBeSplit(
+                max_message_length,
+            ))?;
+        let is_mp = |attr: &&PathAttribute| {
+            matches!(
+                attr.value(),
+                PathAttributeValue::MpReach(_) | PathAttributeValue::MpUnreach(_)
+            )
+        };
+        let common_len: usize = self
+            .path_attributes()
+            .iter()
+            .filter(|attr| !is_mp(attr))
+            .map(|attr| attr.len())
+            .sum();
+        let reach_budget = budget.checked_sub(common_len).ok_or(
+            BgpUpdateMessageWritingError::UpdateCannotBeSplit(max_message_length),
+        )?;
+        // Copy all the path attributes, while replacing the `MP_REACH_NLRI` at
+        // the given index with a new one and dropping any other MP attributes
+        let with_mp_reach = |index: usize, mp_reach: PathAttribute| {
+            self.path_attributes()
+                .iter()
+                .enumerate()
+                .filter_map(|(i, attr)| {
+                    if i == index {
+                        Some(mp_reach.clone())
+                    } else if is_mp(&attr) {
+                        None
+                    } else {
+                        Some(attr.clone())
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut messages = vec![];
+        if !self.withdraw_routes().is_empty() {
+            for chunk in split_nlri(self.withdraw_routes(), budget, max_message_length)? {
+                messages.push(BgpMessage::Update(BgpUpdateMessage::new(
+                    chunk,
+                    vec![],
+                    vec![],
+                )));
+            }
+        }
+        for attr in self.path_attributes() {
+            if let PathAttributeValue::MpUnreach(mp_unreach) = attr.value() {
+                for chunk in split_mp_unreach(attr, mp_unreach, budget, max_message_length)? {
+                    messages.push(BgpMessage::Update(BgpUpdateMessage::new(
+                        vec![],
+                        vec![attr.with_value(PathAttributeValue::MpUnreach(chunk))],
+                        vec![],
+                    )));
+                }
+            }
+        }
+        for (index, attr) in self.path_attributes().iter().enumerate() {
+            if let PathAttributeValue::MpReach(mp_reach) = attr.value() {
+                for chunk in split_mp_reach(attr, mp_reach, reach_budget, max_message_length)? {
+                    let mp_reach = attr.with_value(PathAttributeValue::MpReach(chunk));
+                    messages.push(BgpMessage::Update(BgpUpdateMessage::new(
+                        vec![],
+                        with_mp_reach(index, mp_reach),
+                        vec![],
+                    )));
+                }
+            }
+        }
+        if !self.nlri().is_empty() {
+            let common = self
+                .path_attributes()
+                .iter()
+                .filter(|attr| !is_mp(attr))
+                .cloned()
+                .collect::<Vec<_>>();
+            for chunk in split_nlri(self.nlri(), reach_budget, max_message_length)? {
+                messages.push(BgpMessage::Update(BgpUpdateMessage::new(
+                    vec![],
+                    common.clone(),
+                    chunk,
+                )));
+            }
+        }
+        Ok(messages)
+    }
+}
+
+/// Split a list of NLRI into chunks each doesn't exceed `budget` octets when
+/// serialized. An empty input results in a single empty chunk.
+fn split_nlri<T: WritablePdu<E> + Clone, E>(
+    nlri: &[T],
+    budget: usize,
+    max_message_length: u16,
+) -> Result<Vec<Vec<T>>, BgpUpdateMessageWritingError> {
+    let mut chunks = vec![];
+    let mut current = vec![];
+    let mut current_len = 0;
+    for item in nlri {
+        let len = item.len();
+        if len > budget {
+            return Err(BgpUpdateMessageWritingError::UpdateCannotBeSplit(
+                max_message_length,
+            ));
+        }
+        if current_len + len > budget {
+            chunks.push(std::mem::take(&mut current));
+            current_len = 0;
+        }
+        current_len += len;
+        current.push(item.clone());
+    }
+    if !current.is_empty() || chunks.is_empty() {
+        chunks.push(current);
+    }
+    Ok(chunks)
+}
+
+/// Number of octets left for the NLRI in an MP attribute after accounting for
+/// the attribute header, AFI/SAFI, and next hop
+fn mp_nlri_budget<T: WritablePdu<E>, E>(
+    attr: &PathAttribute,
+    nlri: &[T],
+    budget: usize,
+    max_message_length: u16,
+) -> Result<usize, BgpUpdateMessageWritingError> {
+    let nlri_len: usize = nlri.iter().map(|x| x.len()).sum();
+    budget.checked_sub(attr.len() - nlri_len).ok_or(
+        BgpUpdateMessageWritingError::UpdateCannotBeSplit(max_message_length),
+    )
+}
+
+fn split_mp_reach(
+    attr: &PathAttribute,
+    mp_reach: &MpReach,
+    budget: usize,
+    max_message_length: u16,
+) -> Result<Vec<MpReach>, BgpUpdateMessageWritingError> {
+    let max = max_message_length;
+    let chunks = match mp_reach {
+        MpReach::Ipv4Unicast {
+            next_hop,
+            next_hop_local,
+            nlri,
+        } => split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+            .into_iter()
+            .map(|nlri| MpReach::Ipv4Unicast {
+                next_hop: *next_hop,
+                next_hop_local: *next_hop_local,
+                nlri,
+            })
+            .collect(),
+        MpReach::Ipv4Multicast {
+            next_hop,
+            next_hop_local,
+            nlri,
+        } => split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+            .into_iter()
+            .map(|nlri| MpReach::Ipv4Multicast {
+                next_hop: *next_hop,
+                next_hop_local: *next_hop_local,
+                nlri,
+            })
+            .collect(),
+        MpReach::Ipv4NlriMplsLabels {
+            next_hop,
+            next_hop_local,
+            nlri,
+        } => split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+            .into_iter()
+            .map(|nlri| MpReach::Ipv4NlriMplsLabels {
+                next_hop: *next_hop,
+                next_hop_local: *next_hop_local,
+                nlri,
+            })
+            .collect(),
+        MpReach::Ipv4MplsVpnUnicast { next_hop, nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpReach::Ipv4MplsVpnUnicast {
+                    next_hop: *next_hop,
+                    nlri,
+                })
+                .collect()
+        }
+        MpReach::Ipv6Unicast {
+            next_hop_global,
+            next_hop_local,
+            nlri,
+        } => split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+            .into_iter()
+            .map(|nlri| MpReach::Ipv6Unicast {
+                next_hop_global: *next_hop_global,
+                next_hop_local: *next_hop_local,
+                nlri,
+            })
+            .collect(),
+        MpReach::Ipv6Multicast {
+            next_hop_global,
+            next_hop_local,
+            nlri,
+        } => split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+            .into_iter()
+            .map(|nlri| MpReach::Ipv6Multicast {
+                next_hop_global: *next_hop_global,
+                next_hop_local: *next_hop_local,
+                nlri,
+            })
+            .collect(),
+        MpReach::Ipv6NlriMplsLabels {
+            next_hop,
+            next_hop_local,
+            nlri,
+        } => split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+            .into_iter()
+            .map(|nlri| MpReach::Ipv6NlriMplsLabels {
+                next_hop: *next_hop,
+                next_hop_local: *next_hop_local,
+                nlri,
+            })
+            .collect(),
+        MpReach::Ipv6MplsVpnUnicast { next_hop, nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpReach::Ipv6MplsVpnUnicast {
+                    next_hop: *next_hop,
+                    nlri,
+                })
+                .collect()
+        }
+        MpReach::L2Evpn { next_hop, nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpReach::L2Evpn {
+                    next_hop: *next_hop,
+                    nlri,
+                })
+                .collect()
+        }
+        MpReach::RouteTargetMembership { next_hop, nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpReach::RouteTargetMembership {
+                    next_hop: *next_hop,
+                    nlri,
+                })
+                .collect()
+        }
+        MpReach::BgpLs { next_hop, nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpReach::BgpLs {
+                    next_hop: *next_hop,
+                    nlri,
+                })
+                .collect()
+        }
+        MpReach::BgpLsVpn { next_hop, nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpReach::BgpLsVpn {
+                    next_hop: *next_hop,
+                    nlri,
+                })
+                .collect()
+        }
+        MpReach::Ipv4FlowSpec { next_hop, nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpReach::Ipv4FlowSpec {
+                    next_hop: *next_hop,
+                    nlri,
+                })
+                .collect()
+        }
+        MpReach::Ipv6FlowSpec { next_hop, nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpReach::Ipv6FlowSpec {
+                    next_hop: *next_hop,
+                    nlri,
+                })
+                .collect()
+        }
+        MpReach::Unknown { .. } => {
+            // Opaque value, cannot be split
+            if attr.len() > budget {
+                return Err(BgpUpdateMessageWritingError::UpdateCannotBeSplit(max));
+            }
+            vec![mp_reach.clone()]
+        }
+    };
+    Ok(chunks)
+}
+
+fn split_mp_unreach(
+    attr: &PathAttribute,
+    mp_unreach: &MpUnreach,
+    budget: usize,
+    max_message_length: u16,
+) -> Result<Vec<MpUnreach>, BgpUpdateMessageWritingError> {
+    let max = max_message_length;
+    let chunks = match mp_unreach {
+        MpUnreach::Ipv4Unicast { nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpUnreach::Ipv4Unicast { nlri })
+                .collect()
+        }
+        MpUnreach::Ipv4Multicast { nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpUnreach::Ipv4Multicast { nlri })
+                .collect()
+        }
+        MpUnreach::Ipv4NlriMplsLabels { nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpUnreach::Ipv4NlriMplsLabels { nlri })
+                .collect()
+        }
+        MpUnreach::Ipv4MplsVpnUnicast { nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpUnreach::Ipv4MplsVpnUnicast { nlri })
+                .collect()
+        }
+        MpUnreach::Ipv6Unicast { nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpUnreach::Ipv6Unicast { nlri })
+                .collect()
+        }
+        MpUnreach::Ipv6Multicast { nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpUnreach::Ipv6Multicast { nlri })
+                .collect()
+        }
+        MpUnreach::Ipv6NlriMplsLabels { nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpUnreach::Ipv6NlriMplsLabels { nlri })
+                .collect()
+        }
+        MpUnreach::Ipv6MplsVpnUnicast { nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpUnreach::Ipv6MplsVpnUnicast { nlri })
+                .collect()
+        }
+        MpUnreach::L2Evpn { nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpUnreach::L2Evpn { nlri })
+                .collect()
+        }
+        MpUnreach::RouteTargetMembership { nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpUnreach::RouteTargetMembership { nlri })
+                .collect()
+        }
+        MpUnreach::BgpLs { nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpUnreach::BgpLs { nlri })
+                .collect()
+        }
+        MpUnreach::BgpLsVpn { nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpUnreach::BgpLsVpn { nlri })
+                .collect()
+        }
+        MpUnreach::Ipv4FlowSpec { nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpUnreach::Ipv4FlowSpec { nlri })
+                .collect()
+        }
+        MpUnreach::Ipv6FlowSpec { nlri } => {
+            split_nlri(nlri, mp_nlri_budget(attr, nlri, budget, max)?, max)?
+                .into_iter()
+                .map(|nlri| MpUnreach::Ipv6FlowSpec { nlri })
+                .collect()
+        }
+        MpUnreach::Unknown { .. } => {
+            // Opaque value, cannot be split
+            if attr.len() > budget {
+                return Err(BgpUpdateMessageWritingError::UpdateCannotBeSplit(max));
+            }
+            vec![mp_unreach.clone()]
+        }
+    };
+    Ok(chunks)
+}
@@ -0,0 +1,323 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    community::{ExtendedCommunity, FlowSpecAction},
+    iana::FlowSpecComponentType,
+    nlri::{
+        BitmaskOperation, FlowSpecComponent, FlowSpecNlri, FlowSpecPrefix, NumericOperation,
+        OperationValue,
+    },
+    path_attribute::{MpReach, MpUnreach},
+    wire::{
+        deserializer::nlri::{FlowSpecNlriParsingError, LocatedFlowSpecNlriParsingError},
+        serializer::{
+            nlri::FlowSpecNlriWritingError,
+            path_attribute::{MpReachWritingError, MpUnreachWritingError},
+        },
+    },
+};
+use ipnet::{Ipv4Net, Ipv6Net};
+use netgauze_iana::address_family::AddressFamily;
+use netgauze_parse_utils::{
+    test_helpers::{
+        test_parse_error_with_one_input, test_parsed_completely,
+        test_parsed_completely_with_one_input, test_parsed_completely_with_three_inputs,
+        test_write, test_write_with_one_input,
+    },
+    Span,
+};
+use std::{
+    collections::HashMap,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+#[test]
+fn test_ipv4_flow_spec_nlri() -> Result<(), FlowSpecNlriWritingError> {
+    // Packets to 192.0.2.0/24 and TCP port 25, see RFC8955 Section 4.3
+    let good_wire = [
+        0x0b, 0x01, 0x18, 0xc0, 0x00, 0x02, 0x03, 0x81, 0x06, 0x04, 0x81, 0x19,
+    ];
+    let good = FlowSpecNlri::new(vec![
+        FlowSpecComponent::DestinationPrefix(FlowSpecPrefix::Ipv4(
+            Ipv4Net::new(Ipv4Addr::new(192, 0, 2, 0), 24).unwrap(),
+        )),
+        FlowSpecComponent::IpProtocol(vec![NumericOperation::equal(false, 6)]),
+        FlowSpecComponent::Port(vec![NumericOperation::equal(false, 25)]),
+    ]);
+
+    test_parsed_completely_with_one_input(&good_wire, AddressFamily::IPv4, &good);
+    test_write(&good, &good_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_ipv4_flow_spec_operations() -> Result<(), FlowSpecNlriWritingError> {
+    let good_wire = [
+        0x13, // NLRI length
+        0x02, 0x20, 0xc6, 0x33, 0x64, 0x01, // source 198.51.100.1/32
+        0x05, 0x13, 0x04, 0x00, 0xd5, 0xff, 0xff, // destination port >= 1024 && <= 65535
+        0x0b, 0x81, 0x2e, // DSCP == 46
+        0x0c, 0x80, 0x02, // is a fragment
+    ];
+    let good = FlowSpecNlri::new(vec![
+        FlowSpecComponent::SourcePrefix(FlowSpecPrefix::Ipv4(
+            Ipv4Net::new(Ipv4Addr::new(198, 51, 100, 1), 32).unwrap(),
+        )),
+        FlowSpecComponent::DestinationPort(vec![
+            NumericOperation::new(false, false, true, true, OperationValue::U16(1024)),
+            NumericOperation::new(true, true, false, true, OperationValue::U16(65535)),
+        ]),
+        FlowSpecComponent::Dscp(vec![NumericOperation::equal(false, 46)]),
+        FlowSpecComponent::Fragment(vec![BitmaskOperation::new(
+            false,
+            false,
+            false,
+            OperationValue::new(FlowSpecComponent::FRAGMENT_IS_FRAGMENT),
+        )]),
+    ]);
+
+    test_parsed_completely_with_one_input(&good_wire, AddressFamily::IPv4, &good);
+    test_write(&good, &good_wire)?;
+
+    let ports = match &good.components()[1] {
+        FlowSpecComponent::DestinationPort(ops) => ops,
+        _ => unreachable!(),
+    };
+    assert!(ports[0].matches(1024) && ports[1].matches(1024));
+    assert!(!ports[0].matches(80));
+    let fragment = match &good.components()[3] {
+        FlowSpecComponent::Fragment(ops) => ops[0],
+        _ => unreachable!(),
+    };
+    assert!(fragment.matches(
+        FlowSpecComponent::FRAGMENT_IS_FRAGMENT | FlowSpecComponent::FRAGMENT_LAST_FRAGMENT
+    ));
+    assert!(!fragment.matches(FlowSpecComponent::FRAGMENT_DONT_FRAGMENT));
+    Ok(())
+}
+
+#[test]
+fn test_ipv6_flow_spec_nlri() -> Result<(), FlowSpecNlriWritingError> {
+    let good_wire = [
+        0x15, // NLRI length
+        0x01, 0x20, 0x00, 0x20, 0x01, 0x0d, 0xb8, // destination 2001:db8::/32
+        0x02, 0x50, 0x40, 0xab, 0xcd, // source bits 64 to 80 of ::abcd:0:0:0/80
+        0x03, 0x81, 0x3a, // next header == 58
+        0x0d, 0xa1, 0x00, 0x01, 0x23, 0x45, // flow label == 0x12345
+    ];
+    let good = FlowSpecNlri::new(vec![
+        FlowSpecComponent::DestinationPrefix(FlowSpecPrefix::Ipv6 {
+            prefix: Ipv6Net::new(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32).unwrap(),
+            offset: 0,
+        }),
+        FlowSpecComponent::SourcePrefix(FlowSpecPrefix::Ipv6 {
+            prefix: Ipv6Net::new(Ipv6Addr::new(0, 0, 0, 0, 0xabcd, 0, 0, 0), 80).unwrap(),
+            offset: 64,
+        }),
+        FlowSpecComponent::IpProtocol(vec![NumericOperation::equal(false, 58)]),
+        FlowSpecComponent::FlowLabel(vec![NumericOperation::new(
+            false,
+            false,
+            false,
+            true,
+            OperationValue::U32(0x12345),
+        )]),
+    ]);
+
+    test_parsed_completely_with_one_input(&good_wire, AddressFamily::IPv6, &good);
+    test_write(&good, &good_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_flow_spec_nlri_errors() {
+    let unordered_wire = [0x08, 0x03, 0x81, 0x06, 0x01, 0x18, 0xc0, 0x00, 0x02];
+    let unordered = LocatedFlowSpecNlriParsingError::new(
+        unsafe { Span::new_from_raw_offset(4, &unordered_wire[4..]) },
+        FlowSpecNlriParsingError::UnexpectedComponentType(FlowSpecComponentType::DestinationPrefix),
+    );
+    test_parse_error_with_one_input::<
+        FlowSpecNlri,
+        AddressFamily,
+        LocatedFlowSpecNlriParsingError<'_>,
+    >(&unordered_wire, AddressFamily::IPv4, &unordered);
+
+    // Flow label is defined only for IPv6
+    let flow_label_wire = [0x03, 0x0d, 0x81, 0x01];
+    let flow_label = LocatedFlowSpecNlriParsingError::new(
+        unsafe { Span::new_from_raw_offset(1, &flow_label_wire[1..]) },
+        FlowSpecNlriParsingError::UnexpectedComponentType(FlowSpecComponentType::FlowLabel),
+    );
+    test_parse_error_with_one_input::<
+        FlowSpecNlri,
+        AddressFamily,
+        LocatedFlowSpecNlriParsingError<'_>,
+    >(&flow_label_wire, AddressFamily::IPv4, &flow_label);
+
+    let bad_prefix_wire = [0x03, 0x01, 0x21, 0xc0];
+    let bad_prefix = LocatedFlowSpecNlriParsingError::new(
+        unsafe { Span::new_from_raw_offset(2, &bad_prefix_wire[2..]) },
+        FlowSpecNlriParsingError::InvalidPrefixLength(33),
+    );
+    test_parse_error_with_one_input::<
+        FlowSpecNlri,
+        AddressFamily,
+        LocatedFlowSpecNlriParsingError<'_>,
+    >(&bad_prefix_wire, AddressFamily::IPv4, &bad_prefix);
+
+    let bad_offset_wire = [0x04, 0x01, 0x08, 0x10, 0x20];
+    let bad_offset = LocatedFlowSpecNlriParsingError::new(
+        unsafe { Span::new_from_raw_offset(2, &bad_offset_wire[2..]) },
+        FlowSpecNlriParsingError::InvalidPrefixOffset(16),
+    );
+    test_parse_error_with_one_input::<
+        FlowSpecNlri,
+        AddressFamily,
+        LocatedFlowSpecNlriParsingError<'_>,
+    >(&bad_offset_wire, AddressFamily::IPv6, &bad_offset);
+}
+
+#[test]
+fn test_flow_spec_extended_length() -> Result<(), FlowSpecNlriWritingError> {
+    // The components take 241 octets, the length is encoded in two octets
+    let ports = (0..79)
+        .map(|port| NumericOperation::equal(false, 1000 + port))
+        .collect::<Vec<_>>();
+    let good = FlowSpecNlri::new(vec![
+        FlowSpecComponent::Port(ports),
+        FlowSpecComponent::PacketLength(vec![NumericOperation::new(
+            false,
+            true,
+            false,
+            false,
+            OperationValue::U8(100),
+        )]),
+    ]);
+    let mut good_wire = vec![0xf0, 0xf1, 0x04];
+    for port in 0..79u16 {
+        good_wire.push(if port == 78 { 0x91 } else { 0x11 });
+        good_wire.extend_from_slice(&(1000 + port).to_be_bytes());
+    }
+    good_wire.extend_from_slice(&[0x0a, 0x84, 100]);
+
+    test_parsed_completely_with_one_input(&good_wire, AddressFamily::IPv4, &good);
+    test_write(&good, &good_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_ipv4_flow_spec_mp_reach() -> Result<(), MpReachWritingError> {
+    let good_wire = [
+        0x11, 0x00, 0x01, 0x85, 0x00, 0x00, 0x0b, 0x01, 0x18, 0xc0, 0x00, 0x02, 0x03, 0x81, 0x06,
+        0x04, 0x81, 0x19,
+    ];
+    let good = MpReach::Ipv4FlowSpec {
+        next_hop: None,
+        nlri: vec![FlowSpecNlri::new(vec![
+            FlowSpecComponent::DestinationPrefix(FlowSpecPrefix::Ipv4(
+                Ipv4Net::new(Ipv4Addr::new(192, 0, 2, 0), 24).unwrap(),
+            )),
+            FlowSpecComponent::IpProtocol(vec![NumericOperation::equal(false, 6)]),
+            FlowSpecComponent::Port(vec![NumericOperation::equal(false, 25)]),
+        ])],
+    };
+
+    test_parsed_completely_with_three_inputs(
+        &good_wire,
+        false,
+        &HashMap::new(),
+        &HashMap::new(),
+        &good,
+    );
+    test_write_with_one_input(&good, false, &good_wire)?;
+    assert_eq!(good.next_hops(), None);
+    Ok(())
+}
+
+#[test]
+fn test_ipv6_flow_spec_mp_unreach() -> Result<(), MpUnreachWritingError> {
+    let good_wire = [
+        0x0e, 0x00, 0x02, 0x85, 0x0a, 0x01, 0x20, 0x00, 0x20, 0x01, 0x0d, 0xb8, 0x03, 0x81, 0x3a,
+    ];
+    let good = MpUnreach::Ipv6FlowSpec {
+        nlri: vec![FlowSpecNlri::new(vec![
+            FlowSpecComponent::DestinationPrefix(FlowSpecPrefix::Ipv6 {
+                prefix: Ipv6Net::new(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32).unwrap(),
+                offset: 0,
+            }),
+            FlowSpecComponent::IpProtocol(vec![NumericOperation::equal(false, 58)]),
+        ])],
+    };
+
+    test_parsed_completely_with_three_inputs(
+        &good_wire,
+        false,
+        &HashMap::new(),
+        &HashMap::new(),
+        &good,
+    );
+    test_write_with_one_input(&good, false, &good_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_flow_spec_actions() {
+    let discard_wire = [0x80, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    let action_wire = [0x80, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03];
+    let redirect_wire = [0x80, 0x08, 0xfd, 0xe8, 0x00, 0x00, 0x00, 0x64];
+    let marking_wire = [0x80, 0x09, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2e];
+    let cases = [
+        (
+            &discard_wire,
+            FlowSpecAction::TrafficRateBytes { asn: 0, rate: 0.0 },
+        ),
+        (
+            &action_wire,
+            FlowSpecAction::TrafficAction {
+                sample: true,
+                terminal: true,
+            },
+        ),
+        (
+            &redirect_wire,
+            FlowSpecAction::Redirect {
+                global_admin: 65000,
+                local_admin: 100,
+            },
+        ),
+        (&marking_wire, FlowSpecAction::TrafficMarking { dscp: 46 }),
+    ];
+    for (wire, action) in cases {
+        let community = action.to_extended_community();
+        test_parsed_completely(wire, &community);
+        assert_eq!(
+            FlowSpecAction::from_extended_community(&community),
+            Some(action)
+        );
+    }
+    assert!(FlowSpecAction::TrafficRateBytes { asn: 0, rate: 0.0 }.is_discard());
+    assert!(!FlowSpecAction::TrafficRatePackets {
+        asn: 0,
+        rate: 1000.0
+    }
+    .is_discard());
+    assert_eq!(
+        FlowSpecAction::from_extended_community(&ExtendedCommunity::Unknown(
+            crate::community::UnknownExtendedCommunity::new(0x80, 0x01, [0; 6])
+        )),
+        None
+    );
+}
@@ -58,7 +58,8 @@ use crate::{
                 PathAttributeParsingError,
             },
             route_refresh::BgpRouteRefreshMessageParsingError,
-            BgpMessageParsingError, BgpParsingContext, LocatedBgpMessageParsingError,
+            BgpMarkerAnomaly, BgpMessageParsingError, BgpParsingContext,
+            LocatedBgpMessageParsingError,
         },
         serializer::BgpMessageWritingError,
     },
@@ -68,6 +69,7 @@ use crate::{
 mod bgp_ls;
 mod capabilities;
 mod community;
+mod flow_spec;
 mod keepalive;
 mod nlri;
 mod notification;
@@ -77,6 +79,8 @@ mod path_attribute;
 mod pcap_tests;
 mod route_refresh;
 mod update;
+#[cfg(feature = "serde")]
+mod yaml_tests;
 
 pub(crate) const BGP_MARKER: &[u8] = &[0xff; 16];
 pub(crate) const MY_AS: &[u8] = &[0x01, 0x02];
@@ -103,6 +107,34 @@ fn test_bgp_message_not_synchronized_marker() {
     );
 }
 
+#[test]
+fn test_bgp_message_relaxed_marker() {
+    let mangled_marker = [0x00; 16];
+    let good_mangled_wire = combine(vec![&mangled_marker, &[0x00, 0x13, 0x04]]);
+    let good_omitted_wire = [0x00, 0x13, 0x04];
+    let bad_wire = combine(vec![&mangled_marker, &[0x00, 0x13, 0xff]]);
+
+    let good = BgpMessage::KeepAlive;
+    let bad = LocatedBgpMessageParsingError::new(
+        unsafe { Span::new_from_raw_offset(0, &bad_wire[0..]) },
+        BgpMessageParsingError::ConnectionNotSynchronized(0u128),
+    );
+
+    let mut ctx = BgpParsingContext::asn2_default();
+    ctx.set_relax_markers(true);
+    test_parsed_completely_with_one_input(&good_mangled_wire, &mut ctx, &good);
+    test_parsed_completely_with_one_input(&good_omitted_wire, &mut ctx, &good);
+    assert_eq!(
+        ctx.reset_parsing_errors().marker_anomalies(),
+        &vec![BgpMarkerAnomaly::Mangled(0), BgpMarkerAnomaly::Omitted]
+    );
+    test_parse_error_with_one_input::<
+        BgpMessage,
+        &mut BgpParsingContext,
+        LocatedBgpMessageParsingError<'_>,
+    >(&bad_wire, &mut ctx, &bad);
+}
+
 #[test]
 fn test_bgp_message_length_bounds() {
     // The shortest message is a keepalive message to test with
@@ -29,7 +29,7 @@ use crate::{
 };
 use netgauze_parse_utils::{
     test_helpers::{test_parse_error, test_parsed_completely, test_write},
-    Span,
+    Span, StringDecodingPolicy,
 };
 use nom::error::ErrorKind;
 
@@ -738,3 +738,48 @@ fn test_bgp_notification_route_refresh_error() -> Result<(), BgpNotificationMess
     test_write(&good, &good_wire)?;
     Ok(())
 }
+
+#[test]
+fn test_cease_shutdown_communication() {
+    let good = CeaseError::AdministrativeShutdown {
+        value: vec![0x03, b'b', b'y', b'e'],
+    };
+    let truncated = CeaseError::AdministrativeReset {
+        value: vec![0x05, b'b', b'y', b'e'],
+    };
+    let non_utf8 = CeaseError::AdministrativeShutdown {
+        value: vec![0x03, b'b', 0xff, b'e'],
+    };
+    let empty = CeaseError::AdministrativeShutdown { value: vec![] };
+    let other = CeaseError::PeerDeConfigured {
+        value: vec![0x03, b'b', b'y', b'e'],
+    };
+
+    assert_eq!(
+        good.shutdown_communication(StringDecodingPolicy::Strict),
+        Ok(Some("bye".to_string()))
+    );
+    assert_eq!(
+        truncated.shutdown_communication(StringDecodingPolicy::Strict),
+        Ok(Some("bye".to_string()))
+    );
+    assert!(non_utf8
+        .shutdown_communication(StringDecodingPolicy::Strict)
+        .is_err());
+    assert_eq!(
+        non_utf8.shutdown_communication(StringDecodingPolicy::Lossy),
+        Ok(Some("b\u{fffd}e".to_string()))
+    );
+    assert_eq!(
+        non_utf8.shutdown_communication(StringDecodingPolicy::Raw),
+        Ok(None)
+    );
+    assert_eq!(
+        empty.shutdown_communication(StringDecodingPolicy::Strict),
+        Ok(None)
+    );
+    assert_eq!(
+        other.shutdown_communication(StringDecodingPolicy::Strict),
+        Ok(None)
+    );
+}
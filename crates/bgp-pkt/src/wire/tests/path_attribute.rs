@@ -33,7 +33,9 @@ use netgauze_iana::address_family::{
     AddressFamily, AddressType, SubsequentAddressFamily, UndefinedAddressFamily,
     UndefinedSubsequentAddressFamily,
 };
-use netgauze_parse_utils::{test_helpers::*, Span};
+use netgauze_parse_utils::{
+    test_helpers::*, ReadablePduWithOneInput, Span, WritablePduWithOneInput,
+};
 
 use crate::{
     community::*,
@@ -1403,6 +1405,51 @@ fn test_mp_reach_nlri_ipv4_ipv6_next_hop() -> Result<(), MpReachWritingError> {
     Ok(())
 }
 
+#[test]
+fn test_mp_reach_legacy_snpa() {
+    let good_wire = [
+        0x80, 0x0e, 0x10, 0x00, 0x01, 0x01, 0x04, 0xc0, 0x00, 0x02, 0x01, 0x01, 0x03, 0xab, 0xc0,
+        0x18, 0xc0, 0x00, 0x02,
+    ];
+
+    let mp_reach = MpReach::Ipv4Unicast {
+        next_hop: IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+        next_hop_local: None,
+        nlri: vec![Ipv4UnicastAddress::new(
+            None,
+            Ipv4Unicast::from_net(Ipv4Net::from_str("192.0.2.0/24").unwrap()).unwrap(),
+        )],
+    };
+    let good = PathAttribute::from(
+        true,
+        false,
+        false,
+        false,
+        PathAttributeValue::MpReach(mp_reach.clone()),
+    )
+    .unwrap();
+
+    test_parsed_completely_with_four_inputs(
+        &good_wire[2..],
+        false,
+        &HashMap::new(),
+        &HashMap::new(),
+        true,
+        &mp_reach,
+    );
+
+    let mut ctx = BgpParsingContext::asn2_default();
+    let parsed =
+        <PathAttribute as ReadablePduWithOneInput<'_, &mut BgpParsingContext, _>>::from_wire(
+            Span::new(&good_wire),
+            &mut ctx,
+        );
+    assert!(parsed.is_err());
+
+    ctx.set_skip_legacy_snpa(true);
+    test_parsed_completely_with_one_input(&good_wire, &mut ctx, &good);
+}
+
 #[test]
 fn test_mp_reach_nlri_ipv4_mpls_labels_ipv6_next_hop() -> Result<(), MpReachWritingError> {
     let good_no_link_local_wire = [
@@ -2369,6 +2416,15 @@ fn test_unknown_attribute() -> Result<(), UnknownAttributeWritingError> {
     test_parsed_completely_with_one_input(&good_extended_wire, true, &good_extended);
     test_write_with_one_input(&good, false, &good_wire)?;
     test_write_with_one_input(&good_extended, true, &good_extended_wire)?;
+
+    let long = UnknownAttribute::new(0, vec![0; 256]);
+    let mut long_extended_wire = vec![0x00, 0x01, 0x00];
+    long_extended_wire.extend_from_slice(&[0; 256]);
+    test_write_with_one_input(&long, true, &long_extended_wire)?;
+    assert_eq!(
+        long.write(&mut Vec::new(), false),
+        Err(UnknownAttributeWritingError::ValueLengthOverflow(256))
+    );
     Ok(())
 }
 
@@ -2424,6 +2480,28 @@ fn test_path_attribute_unknown_attribute() -> Result<(), PathAttributeWritingErr
     Ok(())
 }
 
+#[test]
+fn test_path_attribute_unknown_attribute_unused_flags() -> Result<(), PathAttributeWritingError> {
+    // Optional transitive partial attribute of an unassigned type, with the
+    // unused lower-order flag bits set by a misbehaving speaker
+    let good_wire = [0xe5, 0xfe, 0x02, 0xab, 0xcd];
+
+    let good = PathAttribute::from(
+        true,
+        true,
+        true,
+        false,
+        PathAttributeValue::UnknownAttribute(
+            UnknownAttribute::new(254, vec![0xab, 0xcd]).with_unused_flags(0x05),
+        ),
+    )
+    .unwrap();
+
+    test_parsed_completely_with_one_input(&good_wire, &mut BgpParsingContext::default(), &good);
+    test_write(&good, &good_wire)?;
+    Ok(())
+}
+
 #[test]
 fn test_path_attr_route_target_membership() -> Result<(), PathAttributeWritingError> {
     let good_wire = [
@@ -14,10 +14,14 @@
 // limitations under the License.
 
 use crate::{
-    nlri::{InvalidIpv4UnicastNetwork, Ipv4Unicast, Ipv4UnicastAddress},
+    iana::PathAttributeType,
+    nlri::{
+        HostBitsPolicy, InvalidIpv4UnicastNetwork, Ipv4Unicast, Ipv4UnicastAddress, Ipv6Unicast,
+        Ipv6UnicastAddress,
+    },
     path_attribute::{
-        As4PathSegment, AsPath, AsPathSegmentType, NextHop, Origin, PathAttribute,
-        PathAttributeValue,
+        As4PathSegment, AsPath, AsPathSegmentType, MpReach, MpUnreach, NextHop, Origin,
+        PathAttribute, PathAttributeValue, UnknownAttribute,
     },
     wire::{
         deserializer::{
@@ -27,22 +31,29 @@ use crate::{
             },
             update::BgpUpdateMessageParsingError,
             BgpMessageParsingError, BgpParsingContext, Ipv4PrefixParsingError,
-            LocatedBgpMessageParsingError,
+            LocatedBgpMessageParsingError, BGP_MAX_EXTENDED_MESSAGE_LENGTH, BGP_MAX_MESSAGE_LENGTH,
+        },
+        serializer::{
+            nlri::Ipv4UnicastAddressWritingError, update::BgpUpdateMessageWritingError,
+            BgpMessageWritingError,
         },
-        serializer::{nlri::Ipv4UnicastAddressWritingError, BgpMessageWritingError},
     },
     BgpMessage, BgpUpdateMessage,
 };
-use ipnet::Ipv4Net;
+use ipnet::{IpNet, Ipv4Net};
 use netgauze_parse_utils::{
     test_helpers::{
         test_parse_error_with_one_input, test_parsed_completely,
         test_parsed_completely_with_one_input, test_write,
     },
-    Span,
+    ReadablePduWithOneInput, Span, WritablePdu,
 };
 use nom::error::ErrorKind;
-use std::{collections::HashMap, net::Ipv4Addr, str::FromStr};
+use std::{
+    collections::HashMap,
+    net::{Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
 
 #[test]
 fn test_withdraw_route() -> Result<(), Ipv4UnicastAddressWritingError> {
@@ -329,3 +340,437 @@ fn test_update_bad_length() -> Result<(), BgpMessageWritingError> {
     );
     Ok(())
 }
+
+fn ipv4_unicast_routes(count: u32) -> Vec<Ipv4UnicastAddress> {
+    (0..count)
+        .map(|i| {
+            let net = Ipv4Net::new(Ipv4Addr::from((10 << 24) | (i << 8)), 24).unwrap();
+            Ipv4UnicastAddress::new_no_path_id(Ipv4Unicast::from_net(net).unwrap())
+        })
+        .collect()
+}
+
+/// Write and parse back each message, checking it fits in `max_len`
+fn check_split_messages(messages: &[BgpMessage], max_len: u16) -> Vec<BgpUpdateMessage> {
+    messages
+        .iter()
+        .map(|msg| {
+            let mut buf = vec![];
+            msg.write(&mut buf).unwrap();
+            assert!(buf.len() <= max_len as usize);
+            assert_eq!(buf.len(), msg.len());
+            let (_, parsed) =
+                BgpMessage::from_wire(Span::new(&buf), &mut BgpParsingContext::asn2_default())
+                    .unwrap();
+            assert_eq!(&parsed, msg);
+            match parsed {
+                BgpMessage::Update(update) => update,
+                other => panic!("Expected update message, got {other:?}"),
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_update_split_fits() -> Result<(), BgpUpdateMessageWritingError> {
+    let update = BgpUpdateMessage::new(ipv4_unicast_routes(2), vec![], vec![]);
+    assert_eq!(
+        update.split(BGP_MAX_MESSAGE_LENGTH)?,
+        vec![BgpMessage::Update(update)]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_update_split_ipv4() -> Result<(), BgpUpdateMessageWritingError> {
+    let attrs = vec![
+        PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::Origin(Origin::IGP),
+        )
+        .unwrap(),
+        PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::AsPath(AsPath::As2PathSegments(vec![])),
+        )
+        .unwrap(),
+        PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(172, 16, 0, 20))),
+        )
+        .unwrap(),
+    ];
+    let withdrawn = ipv4_unicast_routes(1500);
+    let nlri = ipv4_unicast_routes(3000);
+    let update = BgpUpdateMessage::new(withdrawn.clone(), attrs.clone(), nlri.clone());
+
+    let messages = update.split(BGP_MAX_MESSAGE_LENGTH)?;
+    let updates = check_split_messages(&messages, BGP_MAX_MESSAGE_LENGTH);
+    assert_eq!(messages.len(), 5);
+    assert!(updates
+        .iter()
+        .all(|update| update.withdraw_routes().is_empty() || update.path_attributes().is_empty()));
+    assert!(updates
+        .iter()
+        .filter(|update| !update.nlri().is_empty())
+        .all(|update| update.path_attributes() == attrs));
+    let split_withdrawn = updates
+        .iter()
+        .flat_map(|update| update.withdraw_routes().to_vec())
+        .collect::<Vec<_>>();
+    let split_nlri = updates
+        .iter()
+        .flat_map(|update| update.nlri().to_vec())
+        .collect::<Vec<_>>();
+    assert_eq!(split_withdrawn, withdrawn);
+    assert_eq!(split_nlri, nlri);
+
+    // Fewer messages are needed with the extended message length
+    let messages = update.split(BGP_MAX_EXTENDED_MESSAGE_LENGTH)?;
+    check_split_messages(&messages, BGP_MAX_EXTENDED_MESSAGE_LENGTH);
+    assert_eq!(messages.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_update_split_mp() -> Result<(), BgpUpdateMessageWritingError> {
+    let routes = (0..1000u128)
+        .map(|i| {
+            let net =
+                ipnet::Ipv6Net::new(Ipv6Addr::from((0xfc00u128 << 112) | (i << 64)), 64).unwrap();
+            Ipv6UnicastAddress::new(None, Ipv6Unicast::from_net(net).unwrap())
+        })
+        .collect::<Vec<_>>();
+    let origin = PathAttribute::from(
+        false,
+        true,
+        false,
+        false,
+        PathAttributeValue::Origin(Origin::IGP),
+    )
+    .unwrap();
+    let mp_reach = PathAttribute::from(
+        true,
+        false,
+        false,
+        true,
+        PathAttributeValue::MpReach(MpReach::Ipv6Unicast {
+            next_hop_global: Ipv6Addr::from_str("fc00::1").unwrap(),
+            next_hop_local: None,
+            nlri: routes.clone(),
+        }),
+    )
+    .unwrap();
+    let mp_unreach = PathAttribute::from(
+        true,
+        false,
+        false,
+        true,
+        PathAttributeValue::MpUnreach(MpUnreach::Ipv6Unicast {
+            nlri: routes.clone(),
+        }),
+    )
+    .unwrap();
+    let update = BgpUpdateMessage::new(vec![], vec![mp_reach, origin.clone(), mp_unreach], vec![]);
+
+    let messages = update.split(BGP_MAX_MESSAGE_LENGTH)?;
+    let updates = check_split_messages(&messages, BGP_MAX_MESSAGE_LENGTH);
+    let mut reach_nlri = vec![];
+    let mut unreach_nlri = vec![];
+    for update in &updates {
+        match update.path_attributes() {
+            [attr] => match attr.value() {
+                PathAttributeValue::MpUnreach(MpUnreach::Ipv6Unicast { nlri }) => {
+                    unreach_nlri.extend(nlri.clone())
+                }
+                other => panic!("Unexpected attribute {other:?}"),
+            },
+            [attr, second] => {
+                // The order of the attributes is preserved
+                assert_eq!(second, &origin);
+                match attr.value() {
+                    PathAttributeValue::MpReach(MpReach::Ipv6Unicast { nlri, .. }) => {
+                        reach_nlri.extend(nlri.clone())
+                    }
+                    other => panic!("Unexpected attribute {other:?}"),
+                }
+            }
+            other => panic!("Unexpected attributes {other:?}"),
+        }
+    }
+    assert_eq!(reach_nlri, routes);
+    assert_eq!(unreach_nlri, routes);
+    Ok(())
+}
+
+#[test]
+fn test_update_split_error() {
+    let update = BgpUpdateMessage::new(ipv4_unicast_routes(10), vec![], vec![]);
+    assert_eq!(
+        update.split(25),
+        Err(BgpUpdateMessageWritingError::UpdateCannotBeSplit(25))
+    );
+    assert_eq!(
+        update.split(10),
+        Err(BgpUpdateMessageWritingError::UpdateCannotBeSplit(10))
+    );
+}
+
+#[test]
+fn test_update_missing_mandatory_attributes() {
+    let wire = [
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0x00, 0x1f, 0x02, 0x00, 0x00, 0x00, 0x04, 0x40, 0x01, 0x01, 0x00, 0x18, 0xac, 0x10,
+        0x01,
+    ];
+    let good = BgpMessage::Update(BgpUpdateMessage::new(
+        vec![],
+        vec![PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::Origin(Origin::IGP),
+        )
+        .unwrap()],
+        vec![Ipv4UnicastAddress::new_no_path_id(
+            Ipv4Unicast::from_net(Ipv4Net::from_str("172.16.1.0/24").unwrap()).unwrap(),
+        )],
+    ));
+    let missing = LocatedBgpMessageParsingError::new(
+        unsafe { Span::new_from_raw_offset(23, &wire[23..27]) },
+        BgpMessageParsingError::BgpUpdateMessageParsingError(
+            BgpUpdateMessageParsingError::MissingMandatoryAttribute(PathAttributeType::AsPath),
+        ),
+    );
+
+    let mut lenient_ctx = BgpParsingContext::default();
+    test_parsed_completely_with_one_input(&wire, &mut lenient_ctx, &good);
+    assert_eq!(
+        lenient_ctx.reset_parsing_errors().missing_mandatory_attrs(),
+        &vec![PathAttributeType::AsPath, PathAttributeType::NextHop]
+    );
+
+    let mut strict_ctx = BgpParsingContext::default();
+    strict_ctx.set_fail_on_missing_mandatory_attr(true);
+    test_parse_error_with_one_input::<
+        BgpMessage,
+        &mut BgpParsingContext,
+        LocatedBgpMessageParsingError<'_>,
+    >(&wire, &mut strict_ctx, &missing);
+}
+
+#[test]
+fn test_update_host_bits_policy() {
+    // NLRI 172.16.1.0/23 has a host bit set
+    let wire = [
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0x00, 0x29, 0x02, 0x00, 0x00, 0x00, 0x0e, 0x40, 0x01, 0x01, 0x00, 0x40, 0x02, 0x00,
+        0x40, 0x03, 0x04, 0xac, 0x10, 0x00, 0x14, 0x17, 0xac, 0x10, 0x01,
+    ];
+    let path_attributes = vec![
+        PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::Origin(Origin::IGP),
+        )
+        .unwrap(),
+        PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![])),
+        )
+        .unwrap(),
+        PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(172, 16, 0, 20))),
+        )
+        .unwrap(),
+    ];
+    let nlri = |net: &str| {
+        vec![Ipv4UnicastAddress::new_no_path_id(
+            Ipv4Unicast::from_net(Ipv4Net::from_str(net).unwrap()).unwrap(),
+        )]
+    };
+    let accepted = BgpMessage::Update(BgpUpdateMessage::new(
+        vec![],
+        path_attributes.clone(),
+        nlri("172.16.1.0/23"),
+    ));
+    let normalized = BgpMessage::Update(BgpUpdateMessage::new(
+        vec![],
+        path_attributes,
+        nlri("172.16.0.0/23"),
+    ));
+    let rejected = LocatedBgpMessageParsingError::new(
+        unsafe { Span::new_from_raw_offset(19, &wire[19..]) },
+        BgpMessageParsingError::BgpUpdateMessageParsingError(
+            BgpUpdateMessageParsingError::PrefixHostBitsSet(
+                IpNet::from_str("172.16.1.0/23").unwrap(),
+            ),
+        ),
+    );
+
+    let mut accept_ctx = BgpParsingContext::default();
+    test_parsed_completely_with_one_input(&wire, &mut accept_ctx, &accepted);
+    assert!(accept_ctx
+        .reset_parsing_errors()
+        .normalized_host_bits_prefixes()
+        .is_empty());
+
+    let mut normalize_ctx = BgpParsingContext::default();
+    normalize_ctx.set_host_bits_policy(HostBitsPolicy::Normalize);
+    test_parsed_completely_with_one_input(&wire, &mut normalize_ctx, &normalized);
+    assert_eq!(
+        normalize_ctx
+            .reset_parsing_errors()
+            .normalized_host_bits_prefixes(),
+        &vec![IpNet::from_str("172.16.1.0/23").unwrap()]
+    );
+
+    let mut reject_ctx = BgpParsingContext::default();
+    reject_ctx.set_host_bits_policy(HostBitsPolicy::Reject);
+    test_parse_error_with_one_input::<
+        BgpMessage,
+        &mut BgpParsingContext,
+        LocatedBgpMessageParsingError<'_>,
+    >(&wire, &mut reject_ctx, &rejected);
+}
+
+#[test]
+fn test_update_path_attributes_order() -> Result<(), BgpMessageWritingError> {
+    // NEXT_HOP, ORIGIN then AS_PATH, not in ascending order of type code
+    let good_wire = [
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0x00, 0x29, 0x02, 0x00, 0x00, 0x00, 0x0e, 0x40, 0x03, 0x04, 0xac, 0x10, 0x00, 0x14,
+        0x40, 0x01, 0x01, 0x00, 0x40, 0x02, 0x00, 0x18, 0xac, 0x10, 0x01,
+    ];
+    let next_hop = PathAttribute::from(
+        false,
+        true,
+        false,
+        false,
+        PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(172, 16, 0, 20))),
+    )
+    .unwrap();
+    let origin = PathAttribute::from(
+        false,
+        true,
+        false,
+        false,
+        PathAttributeValue::Origin(Origin::IGP),
+    )
+    .unwrap();
+    let as_path = PathAttribute::from(
+        false,
+        true,
+        false,
+        false,
+        PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![])),
+    )
+    .unwrap();
+    let nlri = vec![Ipv4UnicastAddress::new_no_path_id(
+        Ipv4Unicast::from_net(Ipv4Net::from_str("172.16.1.0/24").unwrap()).unwrap(),
+    )];
+    let good = BgpUpdateMessage::new(
+        vec![],
+        vec![next_hop.clone(), origin.clone(), as_path.clone()],
+        nlri.clone(),
+    );
+
+    let mut ctx = BgpParsingContext::default();
+    test_parsed_completely_with_one_input(&good_wire, &mut ctx, &BgpMessage::Update(good.clone()));
+    test_write(&BgpMessage::Update(good.clone()), &good_wire)?;
+
+    let mut sorted = good;
+    sorted.sort_path_attributes();
+    assert_eq!(
+        sorted,
+        BgpUpdateMessage::new(vec![], vec![origin, as_path, next_hop], nlri)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_update_unknown_attributes_passthrough() -> Result<(), BgpMessageWritingError> {
+    // PMSI_TUNNEL isn't supported and type code 254 is undefined, both are
+    // expected to be written back exactly as received, the latter with the
+    // partial and extended length flags set
+    let good_wire = [
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0x00, 0x37, 0x02, 0x00, 0x00, 0x00, 0x1c, 0x40, 0x01, 0x01, 0x00, 0x40, 0x02, 0x00,
+        0x40, 0x03, 0x04, 0xac, 0x10, 0x00, 0x14, 0xc0, 0x16, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0xf0, 0xfe, 0x00, 0x02, 0xab, 0xcd, 0x18, 0xac, 0x10, 0x01,
+    ];
+    let good = BgpMessage::Update(BgpUpdateMessage::new(
+        vec![],
+        vec![
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::Origin(Origin::IGP),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![])),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(172, 16, 0, 20))),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                true,
+                true,
+                false,
+                false,
+                PathAttributeValue::UnknownAttribute(UnknownAttribute::new(
+                    PathAttributeType::PmsiTunnel.into(),
+                    vec![0x00, 0x00, 0x00, 0x00, 0x00],
+                )),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                true,
+                true,
+                true,
+                true,
+                PathAttributeValue::UnknownAttribute(UnknownAttribute::new(254, vec![0xab, 0xcd])),
+            )
+            .unwrap(),
+        ],
+        vec![Ipv4UnicastAddress::new_no_path_id(
+            Ipv4Unicast::from_net(Ipv4Net::from_str("172.16.1.0/24").unwrap()).unwrap(),
+        )],
+    ));
+
+    test_parsed_completely_with_one_input(&good_wire, &mut BgpParsingContext::default(), &good);
+    test_write(&good, &good_wire)?;
+    Ok(())
+}
@@ -0,0 +1,30 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{wire::deserializer::BgpParsingContext, BgpMessage};
+use netgauze_parse_utils::{
+    test_helpers::{parsed_completely, test_yaml_vectors},
+    ReadablePduWithOneInput, Span,
+};
+use rstest::*;
+use std::path::PathBuf;
+
+#[rstest]
+fn test_yaml_bgp(#[files("../../assets/vectors/bgp/*.yaml")] path: PathBuf) {
+    let mut ctx = BgpParsingContext::default();
+    test_yaml_vectors(&path, |wire| {
+        parsed_completely(BgpMessage::from_wire(Span::new(wire), &mut ctx))
+    });
+}
@@ -418,8 +418,7 @@ impl<
     fn read_open_msg(&mut self, open: &BgpOpenMessage) {
         self.peer_asn = Some(open.my_asn4());
         self.peer_bgp_id = Some(open.bgp_id());
-        self.received_capabilities =
-            Some(open.capabilities().iter().map(|x| (*x).clone()).collect());
+        self.received_capabilities = Some(open.capabilities().cloned().collect());
         self.peer_hold_time = Some(open.hold_time());
     }
 
@@ -1058,6 +1057,16 @@ fn update_treatment(errors: &BgpParsingIgnoredErrors) -> UpdateTreatment {
                             treatment = tmp
                         }
                     }
+                    MpReachParsingError::FlowSpecNlriError(_) => {
+                        // The error doesn't carry the AFI, IPv4 is the most common
+                        let tmp = UpdateTreatment::ResetAddressFamily(
+                            AddressFamily::IPv4.into(),
+                            SubsequentAddressFamily::FlowSpecFilter.into(),
+                        );
+                        if treatment < tmp {
+                            treatment = tmp
+                        }
+                    }
                 }
             }
             PathAttributeParsingError::MpUnreachErrorError(err) => {
@@ -1175,6 +1184,16 @@ fn update_treatment(errors: &BgpParsingIgnoredErrors) -> UpdateTreatment {
                             treatment = tmp
                         }
                     }
+                    MpUnreachParsingError::FlowSpecNlriError(_) => {
+                        // The error doesn't carry the AFI, IPv4 is the most common
+                        let tmp = UpdateTreatment::ResetAddressFamily(
+                            AddressFamily::IPv4.into(),
+                            SubsequentAddressFamily::FlowSpecFilter.into(),
+                        );
+                        if treatment < tmp {
+                            treatment = tmp
+                        }
+                    }
                 }
             }
             PathAttributeParsingError::OnlyToCustomerError(_) => {
@@ -1450,7 +1469,7 @@ impl<
             BgpMessage::Open(open) => {
                 this.stats.open_sent += 1;
                 this.sent_capabilities
-                    .replace(open.capabilities().iter().map(|x| (*x).clone()).collect());
+                    .replace(open.capabilities().cloned().collect());
             }
             BgpMessage::Update(_) => {
                 match *this.keepalive_timer.as_mut() {
@@ -1508,3 +1527,43 @@ impl ActiveConnect<SocketAddr, TcpStream, BgpCodec> for TcpActiveConnect {
         TcpStream::connect(peer_addr).await
     }
 }
+
+/// Connect to peers with the source address, TTL security, and the other
+/// transport options of the neighbor, see [`netgauze_tcp_connect`]
+#[async_trait]
+impl ActiveConnect<SocketAddr, TcpStream, BgpCodec> for netgauze_tcp_connect::TcpConnectConfig {
+    async fn connect(&mut self, peer_addr: SocketAddr) -> io::Result<TcpStream> {
+        netgauze_tcp_connect::TcpConnectConfig::connect(self, peer_addr).await
+    }
+}
+
+/// Connect to peers with TCP MD5 or TCP-AO, see [`netgauze_tcp_auth`]
+#[cfg(feature = "tcp-auth")]
+#[derive(Debug, Clone)]
+pub struct TcpAuthActiveConnect {
+    key: netgauze_tcp_auth::TcpAuthKey,
+}
+
+#[cfg(feature = "tcp-auth")]
+impl TcpAuthActiveConnect {
+    pub const fn new(key: netgauze_tcp_auth::TcpAuthKey) -> Self {
+        Self { key }
+    }
+
+    pub const fn key(&self) -> &netgauze_tcp_auth::TcpAuthKey {
+        &self.key
+    }
+}
+
+#[cfg(feature = "tcp-auth")]
+#[async_trait]
+impl ActiveConnect<SocketAddr, TcpStream, BgpCodec> for TcpAuthActiveConnect {
+    async fn connect(&mut self, peer_addr: SocketAddr) -> io::Result<TcpStream> {
+        let socket = match peer_addr {
+            SocketAddr::V4(_) => tokio::net::TcpSocket::new_v4()?,
+            SocketAddr::V6(_) => tokio::net::TcpSocket::new_v6()?,
+        };
+        netgauze_tcp_auth::set_tcp_auth_key(&socket, peer_addr.ip(), &self.key)?;
+        socket.connect(peer_addr).await
+    }
+}
@@ -72,6 +72,10 @@ pub struct BgpListener<A: Display, I: AsyncWrite + AsyncRead> {
     peers: HashMap<IpAddr, PeerHandle<A, I>>,
     // TODO: change the flag to a policy trait
     allow_dynamic_peers: bool,
+    /// TCP MD5 or TCP-AO keys installed on the listening sockets, indexed by
+    /// ip address of the peer
+    #[cfg(feature = "tcp-auth")]
+    tcp_auth_keys: HashMap<IpAddr, netgauze_tcp_auth::TcpAuthKey>,
 }
 
 impl<
@@ -84,12 +88,21 @@ impl<
             sockets,
             peers: HashMap::new(),
             allow_dynamic_peers,
+            #[cfg(feature = "tcp-auth")]
+            tcp_auth_keys: HashMap::new(),
         }
     }
 
     pub fn reg_peer(&mut self, peer_ip: IpAddr, peer_handle: PeerHandle<A, I>) {
         self.peers.insert(peer_ip, peer_handle);
     }
+
+    /// Accept connections from `peer_ip` only when authenticated with `key`.
+    /// Keys must be set before [`BgpListener::run`].
+    #[cfg(feature = "tcp-auth")]
+    pub fn set_tcp_auth_key(&mut self, peer_ip: IpAddr, key: netgauze_tcp_auth::TcpAuthKey) {
+        self.tcp_auth_keys.insert(peer_ip, key);
+    }
 }
 
 impl BgpListener<SocketAddr, TcpStream> {
@@ -160,6 +173,14 @@ impl BgpListener<SocketAddr, TcpStream> {
 
         for socket in &self.sockets {
             let listener = TcpListener::bind(socket).await?;
+            #[cfg(feature = "tcp-auth")]
+            for (peer_ip, key) in &self.tcp_auth_keys {
+                // IPv6 peers can't connect to IPv4 sockets
+                if socket.is_ipv4() && peer_ip.is_ipv6() {
+                    continue;
+                }
+                netgauze_tcp_auth::set_tcp_auth_key(&listener, *peer_ip, key)?;
+            }
             let listener_stream = TcpListenerStream::new(listener);
             listening_sockets.push(listener_stream);
         }
@@ -197,7 +197,6 @@ impl<
                 self.remote_as.replace(asn);
                 self.peer_capabilities = open
                     .capabilities()
-                    .into_iter()
                     .filter(|cap| cap.code() != Ok(BgpCapabilityCode::FourOctetAs))
                     .cloned()
                     .collect();
@@ -4,12 +4,12 @@
 use chrono::{TimeZone, Utc};
 use netgauze_bgp_pkt::BgpMessage;
 use netgauze_bmp_pkt::{
-    iana::RouteMirroringInformation, BmpMessage, BmpMessageValue, BmpPeerType, MirroredBgpMessage,
-    PeerHeader, RouteMirroringMessage, RouteMirroringValue,
+    iana::RouteMirroringInformation, wire::deserializer::BmpParsingContext, BmpMessage,
+    BmpMessageValue, BmpPeerType, MirroredBgpMessage, PeerHeader, RouteMirroringMessage,
+    RouteMirroringValue,
 };
 use netgauze_parse_utils::{ReadablePduWithOneInput, Span, WritablePdu};
 use std::{
-    collections::HashMap,
     io::Cursor,
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
     str::FromStr,
@@ -51,6 +51,7 @@ fn main() {
     );
 
     // Deserialize the message from binary format
-    let (_, bmp_msg_back) = BmpMessage::from_wire(Span::new(&buf), &mut HashMap::new()).unwrap();
+    let (_, bmp_msg_back) =
+        BmpMessage::from_wire(Span::new(&buf), &mut BmpParsingContext::default()).unwrap();
     assert_eq!(bmp_msg, bmp_msg_back);
 }
@@ -0,0 +1,160 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Capture raw BMP messages along with the [`BmpParsingContext`] of the
+//! session, e.g., the ADD-PATH and multiple labels capabilities negotiated by
+//! each peer, so they can be decoded later without the session history.
+//!
+//! Captures use the [`netgauze_parse_utils::capture`] container, with the
+//! context serialized as JSON.
+
+use std::io::{Read, Write};
+
+use bytes::BytesMut;
+use netgauze_parse_utils::capture::{Capture, CaptureError, CaptureKind};
+use tokio_util::codec::Decoder;
+
+use crate::{
+    codec::{BmpCodec, BmpCodecDecoderError},
+    wire::deserializer::BmpParsingContext,
+    BmpMessage,
+};
+
+/// Raw BMP messages and the parsing context in effect before the first one
+#[derive(Debug, Clone)]
+pub struct BmpCapture {
+    context: BmpParsingContext,
+    data: Vec<u8>,
+}
+
+impl BmpCapture {
+    pub const fn new(context: BmpParsingContext, data: Vec<u8>) -> Self {
+        Self { context, data }
+    }
+
+    /// Capture `data` with the current parsing context of `codec`. To capture
+    /// a message that failed to decode, call it with the message bytes after
+    /// the error, the context is left unchanged by failed messages.
+    pub fn from_codec(codec: &BmpCodec, data: Vec<u8>) -> Self {
+        Self::new(codec.parsing_context().clone(), data)
+    }
+
+    pub const fn context(&self) -> &BmpParsingContext {
+        &self.context
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), CaptureError> {
+        let context = serde_json::to_vec(&self.context)
+            .map_err(|err| CaptureError::ContextError(err.to_string()))?;
+        Capture::new(CaptureKind::Bmp, context, self.data.clone()).write(writer)
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, CaptureError> {
+        let capture = Capture::read(reader)?;
+        if capture.kind() != CaptureKind::Bmp {
+            return Err(CaptureError::UnexpectedKind(capture.kind()));
+        }
+        let context = serde_json::from_slice(capture.context())
+            .map_err(|err| CaptureError::ContextError(err.to_string()))?;
+        Ok(Self::new(context, capture.data().to_vec()))
+    }
+
+    /// Decode the captured messages starting from the captured context,
+    /// stopping at the first error. The error is boxed since it's much larger
+    /// than the decoded messages.
+    pub fn decode(&self) -> Result<Vec<BmpMessage>, Box<BmpCodecDecoderError>> {
+        let mut codec = BmpCodec::default();
+        codec.set_parsing_context(self.context.clone());
+        let mut buf = BytesMut::from(self.data.as_slice());
+        let mut messages = vec![];
+        while let Some(msg) = codec.decode(&mut buf).map_err(Box::new)? {
+            messages.push(msg);
+        }
+        if !buf.is_empty() {
+            return Err(Box::new(BmpCodecDecoderError::Incomplete(Some(buf.len()))));
+        }
+        Ok(messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BmpMessageValue, InitiationInformation, InitiationMessage, PeerKey};
+    use netgauze_bgp_pkt::wire::deserializer::BgpParsingContext;
+    use netgauze_iana::address_family::AddressType;
+    use netgauze_parse_utils::WritablePdu;
+    use std::{
+        collections::HashMap,
+        net::{IpAddr, Ipv4Addr},
+    };
+
+    #[test]
+    fn test_bmp_capture() {
+        let msg = BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(vec![
+            InitiationInformation::SystemName("router1".to_string()),
+        ])));
+        let mut data = vec![];
+        msg.write(&mut data).unwrap();
+
+        let peer = PeerKey::new(
+            Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10))),
+            crate::BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            65000,
+            Ipv4Addr::new(192, 0, 2, 10),
+        );
+        let mut context = BmpParsingContext::default();
+        context.peers_mut().insert(
+            peer,
+            BgpParsingContext::new(
+                true,
+                HashMap::new(),
+                HashMap::from([(AddressType::Ipv4Unicast, true)]),
+                false,
+                false,
+                false,
+                false,
+            ),
+        );
+        let capture = BmpCapture::new(context, data.clone());
+
+        let mut buf = vec![];
+        capture.write(&mut buf).unwrap();
+        let read = BmpCapture::read(&mut buf.as_slice()).unwrap();
+        assert_eq!(read.data(), data.as_slice());
+        assert_eq!(
+            read.context().peers()[&peer].add_path(),
+            &HashMap::from([(AddressType::Ipv4Unicast, true)])
+        );
+        assert_eq!(read.decode(), Ok(vec![msg]));
+
+        // Truncated message
+        let truncated = BmpCapture::new(
+            BmpParsingContext::default(),
+            data[..data.len() - 1].to_vec(),
+        );
+        assert!(truncated.decode().is_err());
+    }
+}
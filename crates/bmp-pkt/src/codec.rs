@@ -16,20 +16,24 @@
 //! Codecs to decode and encode BMP Protocol messages from byte streams
 
 use crate::{
-    iana::BmpVersion,
-    wire::{deserializer::BmpMessageParsingError, serializer::BmpMessageWritingError},
-    BmpMessage, BmpMessageValue, PeerKey,
+    iana::{BmpMessageType, BmpVersion},
+    v4::BmpV4MessageValue,
+    wire::{
+        deserializer::{BmpMessageParsingError, BmpParsingContext},
+        serializer::BmpMessageWritingError,
+    },
+    BmpMessage, BmpMessageValue, PeerKey, PeerKeyStrategy,
 };
 use byteorder::{ByteOrder, NetworkEndian};
 use bytes::{Buf, BufMut, BytesMut};
-use netgauze_bgp_pkt::{capabilities::BgpCapability, BgpMessage};
-
-use netgauze_bgp_pkt::{
-    capabilities::{AddPathCapability, MultipleLabel},
-    wire::deserializer::BgpParsingContext,
+use netgauze_bgp_pkt::{capabilities::BgpCapability, open::BgpOpenMessage, BgpMessage};
+use netgauze_iana::address_family::AddressType;
+use netgauze_parse_utils::{
+    raw_frame::{RawFrame, RawFramePolicy, RawFrameSampler},
+    Categorize, ErrorCategory, LocatedParsingError, ReadablePduWithOneInput, Span, WritablePdu,
 };
-use netgauze_parse_utils::{LocatedParsingError, ReadablePduWithOneInput, Span, WritablePdu};
 use nom::Needed;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tokio_util::codec::{Decoder, Encoder};
@@ -37,11 +41,149 @@ use tokio_util::codec::{Decoder, Encoder};
 /// Min length for a valid BMP Message: 1-octet version + 4-octet length
 pub const BMP_MESSAGE_MIN_LENGTH: usize = 5;
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// Length of the BMP common header: 1-octet version + 4-octet length +
+/// 1-octet message type
+pub const BMP_COMMON_HEADER_LENGTH: u32 = 6;
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BmpCodecDecoderError {
     IoError(String),
     Incomplete(Option<usize>),
-    BmpMessageParsingError(BmpMessageParsingError),
+    /// The `frame` is the message that failed to parse, only captured when a
+    /// policy is set with [`BmpCodec::set_raw_frame_policy`] and within its
+    /// rate limit
+    BmpMessageParsingError {
+        error: BmpMessageParsingError,
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Option::is_none")
+        )]
+        frame: Option<RawFrame>,
+    },
+    /// The length of the common header is implausible, only reported when
+    /// [`BmpCodec::set_max_message_length`] is configured
+    ImplausibleLength(BmpLengthDiagnostic),
+    /// The version is newer than the one set with [`BmpCodec::set_max_version`]
+    UnsupportedVersion(BmpVersion),
+    /// The version differs from the one of the first message of the session
+    VersionMismatch {
+        expected: BmpVersion,
+        found: BmpVersion,
+    },
+}
+
+impl Categorize for BmpCodecDecoderError {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            Self::IoError(_) => ErrorCategory::Io,
+            Self::Incomplete(_) => ErrorCategory::Truncated,
+            Self::BmpMessageParsingError { error, .. } => error.category(),
+            Self::ImplausibleLength(_) | Self::VersionMismatch { .. } => {
+                ErrorCategory::SemanticViolation
+            }
+            Self::UnsupportedVersion(_) => ErrorCategory::Unsupported,
+        }
+    }
+
+    fn variant(&self) -> &'static str {
+        match self {
+            Self::IoError(_) => "IoError",
+            Self::Incomplete(_) => "Incomplete",
+            Self::BmpMessageParsingError { .. } => "BmpMessageParsingError",
+            Self::ImplausibleLength(_) => "ImplausibleLength",
+            Self::UnsupportedVersion(_) => "UnsupportedVersion",
+            Self::VersionMismatch { .. } => "VersionMismatch",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn Categorize> {
+        match self {
+            Self::BmpMessageParsingError { error, .. } => Some(error),
+            Self::IoError(_)
+            | Self::Incomplete(_)
+            | Self::ImplausibleLength(_)
+            | Self::UnsupportedVersion(_)
+            | Self::VersionMismatch { .. } => None,
+        }
+    }
+}
+
+/// Heuristic guess of what went wrong with an implausible BMP length
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BmpLengthGuess {
+    /// The length is plausible when read in little-endian, i.e., the exporter
+    /// didn't convert it to network byte order
+    ByteSwapped(u32),
+    /// The length doesn't even cover the common header, the message is cut
+    /// short before its message type
+    TruncatedHeader,
+    /// No known pattern matches the length
+    Unknown,
+}
+
+/// Details of a BMP length that is shorter than the common header or longer
+/// than the configured max message length
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BmpLengthDiagnostic {
+    length: u32,
+    max_length: u32,
+    guess: BmpLengthGuess,
+}
+
+impl BmpLengthDiagnostic {
+    /// Diagnose `length`, returns `None` when it's plausible
+    pub fn check(length: u32, max_length: u32) -> Option<Self> {
+        let plausible = |length| (BMP_COMMON_HEADER_LENGTH..=max_length).contains(&length);
+        if plausible(length) {
+            return None;
+        }
+        let guess = if plausible(length.swap_bytes()) {
+            BmpLengthGuess::ByteSwapped(length.swap_bytes())
+        } else if length < BMP_COMMON_HEADER_LENGTH {
+            BmpLengthGuess::TruncatedHeader
+        } else {
+            BmpLengthGuess::Unknown
+        };
+        Some(Self {
+            length,
+            max_length,
+            guess,
+        })
+    }
+
+    /// Length as read from the common header
+    pub const fn length(&self) -> u32 {
+        self.length
+    }
+
+    pub const fn max_length(&self) -> u32 {
+        self.max_length
+    }
+
+    pub const fn guess(&self) -> BmpLengthGuess {
+        self.guess
+    }
+}
+
+impl BmpCodecDecoderError {
+    /// The parsing error, with or without a captured frame
+    pub const fn parsing_error(&self) -> Option<&BmpMessageParsingError> {
+        match self {
+            Self::BmpMessageParsingError { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+
+    /// The message that failed to parse, if captured
+    pub const fn raw_frame(&self) -> Option<&RawFrame> {
+        match self {
+            Self::BmpMessageParsingError { frame, .. } => frame.as_ref(),
+            _ => None,
+        }
+    }
 }
 
 impl From<std::io::Error> for BmpCodecDecoderError {
@@ -50,113 +192,233 @@ impl From<std::io::Error> for BmpCodecDecoderError {
     }
 }
 
+/// Action taken by the [`BmpCodec`] decoder for a given [`BmpMessageType`]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BmpMessageAction {
+    /// Fully decode the message
+    #[default]
+    Decode,
+
+    /// Don't decode the message, but pass its body as
+    /// [`BmpMessageValue::Raw`]
+    Raw,
+
+    /// Skip the message without decoding it
+    Drop,
+}
+
 /// Encoder and Decoder for [`BmpMessage`]
 #[derive(Debug, Default)]
 pub struct BmpCodec {
     /// Helper to track in the decoder if we are inside a BMP message or not
     in_message: bool,
-    ctx: HashMap<PeerKey, BgpParsingContext>,
+    ctx: BmpParsingContext,
+    /// Message types that are not fully decoded, missing types are decoded
+    actions: HashMap<BmpMessageType, BmpMessageAction>,
+    /// When set, implausible lengths are reported with a diagnostic
+    max_message_length: Option<u32>,
+    /// Highest version accepted by the decoder, all the versions when not set
+    max_version: Option<BmpVersion>,
+    /// Version of the session, learned from its first message
+    session_version: Option<BmpVersion>,
+    /// Captures the messages that failed to parse, disabled by default
+    raw_frames: RawFrameSampler,
 }
 
+/// Add-Path send and receive flags, and the Multiple Labels counts advertised
+/// in an OPEN message
 #[inline]
 fn get_caps(
-    capabilities: Vec<&BgpCapability>,
-) -> (Vec<AddPathCapability>, Vec<Vec<MultipleLabel>>) {
-    let add_path_caps = capabilities
-        .iter()
-        .flat_map(|cap| {
-            if let BgpCapability::AddPath(add_path) = cap {
-                Some(add_path)
-            } else {
-                None
+    open: Option<&BgpOpenMessage>,
+) -> (HashMap<AddressType, (bool, bool)>, HashMap<AddressType, u8>) {
+    let mut add_path = HashMap::new();
+    let mut multiple_labels = HashMap::new();
+    for cap in open.into_iter().flat_map(|open| open.capabilities()) {
+        match cap {
+            BgpCapability::AddPath(value) => {
+                for family in value.address_families() {
+                    add_path.insert(family.address_type(), (family.send(), family.receive()));
+                }
             }
-        })
-        .cloned()
-        .collect::<Vec<AddPathCapability>>();
-    let multiple_labels_caps = capabilities
-        .iter()
-        .flat_map(|cap| {
-            if let BgpCapability::MultipleLabels(value) = cap {
-                Some(value)
-            } else {
-                None
+            BgpCapability::MultipleLabels(labels) => {
+                for label in labels {
+                    multiple_labels.insert(label.address_type(), label.count());
+                }
             }
-        })
-        .cloned()
-        .collect::<Vec<Vec<MultipleLabel>>>();
-    (add_path_caps, multiple_labels_caps)
+            _ => {}
+        }
+    }
+    (add_path, multiple_labels)
 }
 impl BmpCodec {
+    /// Create a codec that applies the given action on each message type, any
+    /// message type not in `actions` is fully decoded.
+    ///
+    /// Note: when Peer Up, Peer Down or Termination messages are not decoded,
+    /// the BGP parsing context (i.e., Add Path and Multi label MPLS) can't be
+    /// tracked for the peers.
+    pub fn with_message_actions(actions: HashMap<BmpMessageType, BmpMessageAction>) -> Self {
+        Self {
+            in_message: false,
+            ctx: BmpParsingContext::default(),
+            actions,
+            max_message_length: None,
+            max_version: None,
+            session_version: None,
+            raw_frames: RawFrameSampler::default(),
+        }
+    }
+
+    /// Get the action applied to the given message type
+    pub fn message_action(&self, msg_type: BmpMessageType) -> BmpMessageAction {
+        self.actions.get(&msg_type).copied().unwrap_or_default()
+    }
+
+    /// Set the action applied to the given message type
+    pub fn set_message_action(&mut self, msg_type: BmpMessageType, action: BmpMessageAction) {
+        self.actions.insert(msg_type, action);
+    }
+
+    /// Max length accepted for a BMP message, if configured
+    pub const fn max_message_length(&self) -> Option<u32> {
+        self.max_message_length
+    }
+
+    /// Reject messages whose length is shorter than the common header or
+    /// longer than `max_message_length` with
+    /// [`BmpCodecDecoderError::ImplausibleLength`], which carries a guess of
+    /// what went wrong. Without it, a corrupted length can make the decoder
+    /// buffer up to 4GB before failing.
+    pub fn set_max_message_length(&mut self, max_message_length: Option<u32>) {
+        self.max_message_length = max_message_length;
+    }
+
+    /// Highest BMP version accepted by the decoder, if configured
+    pub const fn max_version(&self) -> Option<BmpVersion> {
+        self.max_version
+    }
+
+    /// Reject messages of a version newer than `max_version` with
+    /// [`BmpCodecDecoderError::UnsupportedVersion`], e.g., to only accept
+    /// version 3 from routers that would otherwise send version 4
+    pub fn set_max_version(&mut self, max_version: Option<BmpVersion>) {
+        self.max_version = max_version;
+    }
+
+    /// Version used by the session, as negotiated by its first accepted
+    /// message. Messages of other versions are rejected with
+    /// [`BmpCodecDecoderError::VersionMismatch`].
+    pub const fn session_version(&self) -> Option<BmpVersion> {
+        self.session_version
+    }
+
+    /// Policy for capturing the messages that failed to parse
+    pub const fn raw_frame_policy(&self) -> RawFramePolicy {
+        self.raw_frames.policy()
+    }
+
+    /// Attach the messages that failed to parse, truncated and rate limited
+    /// according to `policy`, to the errors as
+    /// [`BmpCodecDecoderError::BmpMessageParsingError`]
+    pub fn set_raw_frame_policy(&mut self, policy: RawFramePolicy) {
+        self.raw_frames = RawFrameSampler::new(policy);
+    }
+
+    /// Number of messages that failed to parse but were not captured because
+    /// of the rate limit of the raw frame policy
+    pub const fn suppressed_raw_frames(&self) -> u64 {
+        self.raw_frames.suppressed()
+    }
+
+    /// Get the strategy used to identify peers when tracking their BGP parsing
+    /// context
+    pub const fn peer_key_strategy(&self) -> PeerKeyStrategy {
+        self.ctx.peer_key_strategy()
+    }
+
+    /// Set the strategy used to identify peers when tracking their BGP parsing
+    /// context. Any context tracked so far is dropped.
+    pub fn set_peer_key_strategy(&mut self, peer_key_strategy: PeerKeyStrategy) {
+        self.ctx.set_peer_key_strategy(peer_key_strategy);
+    }
+
+    /// Parsing context tracked so far for the peers of the session
+    pub const fn parsing_context(&self) -> &BmpParsingContext {
+        &self.ctx
+    }
+
+    /// Replace the parsing context, e.g., to decode a message captured in the
+    /// middle of a session
+    pub fn set_parsing_context(&mut self, ctx: BmpParsingContext) {
+        self.ctx = ctx;
+    }
+
     /// Update the parsing context based on information presented in the payload
     /// of BMP message. It updates BGP parsing flags such as: Add Path and
     /// Multi label MPLS capabilities
     pub fn update_parsing_ctx(&mut self, msg: &BmpMessage) {
         match msg {
-            BmpMessage::V3(value) => match value {
-                BmpMessageValue::PeerDownNotification(peer_down) => {
-                    let peer_key = PeerKey::from_peer_header(peer_down.peer_header());
-                    self.ctx.remove(&peer_key);
+            BmpMessage::V3(BmpMessageValue::PeerDownNotification(peer_down))
+            | BmpMessage::V4(BmpV4MessageValue::PeerDownNotification(peer_down)) => {
+                let peer_key = self.ctx.peer_key(peer_down.peer_header());
+                self.ctx.peers_mut().remove(&peer_key);
+            }
+            BmpMessage::V3(BmpMessageValue::Termination(termination))
+            | BmpMessage::V4(BmpV4MessageValue::Termination(termination)) => {
+                let peer_key = self.ctx.peer_key(termination.peer_header());
+                self.ctx.peers_mut().remove(&peer_key);
+            }
+            BmpMessage::V3(BmpMessageValue::PeerUpNotification(peer_up))
+            | BmpMessage::V4(BmpV4MessageValue::PeerUpNotification(peer_up)) => {
+                let sent = match peer_up.sent_message() {
+                    BgpMessage::Open(open) => Some(open),
+                    _ => None,
+                };
+                let received = match peer_up.received_message() {
+                    BgpMessage::Open(open) => Some(open),
+                    _ => None,
+                };
+                let (local_add_path, local_labels) = get_caps(sent);
+                let (remote_add_path, remote_labels) = get_caps(received);
+                // Path IDs are carried in one direction only when the sender
+                // advertised send and the receiver advertised receive
+                let mut add_path_receive = HashMap::new();
+                let mut add_path_send = HashMap::new();
+                for address_type in local_add_path.keys().chain(remote_add_path.keys()) {
+                    let (local_send, local_receive) = local_add_path
+                        .get(address_type)
+                        .copied()
+                        .unwrap_or_default();
+                    let (remote_send, remote_receive) = remote_add_path
+                        .get(address_type)
+                        .copied()
+                        .unwrap_or_default();
+                    add_path_receive.insert(*address_type, local_receive && remote_send);
+                    add_path_send.insert(*address_type, local_send && remote_receive);
                 }
-                BmpMessageValue::Termination(termination) => {
-                    let peer_key = PeerKey::from_peer_header(termination.peer_header());
-                    self.ctx.remove(&peer_key);
+                let mut multiple_labels = local_labels;
+                multiple_labels.extend(remote_labels);
+
+                let mut peer_keys = vec![self.ctx.peer_key(peer_up.peer_header())];
+                if let Some(open) = received {
+                    peer_keys.push(self.ctx.peer_key_strategy().apply(PeerKey::new(
+                        peer_up.peer_header().address(),
+                        peer_up.peer_header().peer_type(),
+                        peer_up.peer_header().rd(),
+                        peer_up.peer_header().peer_as(),
+                        open.bgp_id(),
+                    )));
+                    peer_keys.dedup();
                 }
-                BmpMessageValue::PeerUpNotification(peer_up) => {
-                    if let BgpMessage::Open(open) = peer_up.sent_message() {
-                        let capabilities = open.capabilities();
-                        let (add_path_caps, multiple_labels_caps) = get_caps(capabilities);
-                        let peer_key = PeerKey::from_peer_header(peer_up.peer_header());
-                        let bgp_ctx = self.ctx.entry(peer_key).or_default();
-                        bgp_ctx.add_path_mut().clear();
-                        bgp_ctx.multiple_labels_mut().clear();
-                        for add_path in add_path_caps {
-                            for add_path_family in add_path.address_families() {
-                                bgp_ctx.add_path_mut().insert(
-                                    add_path_family.address_type(),
-                                    add_path_family.receive(),
-                                );
-                            }
-                        }
-                        for labels in multiple_labels_caps {
-                            for label in labels {
-                                bgp_ctx
-                                    .multiple_labels_mut()
-                                    .insert(label.address_type(), label.count());
-                            }
-                        }
-                    }
-                    if let BgpMessage::Open(open) = peer_up.received_message() {
-                        let capabilities = open.capabilities();
-                        let (add_path_caps, multiple_labels_caps) = get_caps(capabilities);
-                        let peer_key = PeerKey::new(
-                            peer_up.peer_header().address(),
-                            peer_up.peer_header().peer_type(),
-                            peer_up.peer_header().rd(),
-                            peer_up.peer_header().peer_as(),
-                            open.bgp_id(),
-                        );
-                        let bgp_ctx = self.ctx.entry(peer_key).or_default();
-                        bgp_ctx.add_path_mut().clear();
-                        bgp_ctx.multiple_labels_mut().clear();
-                        for add_path in add_path_caps {
-                            for add_path_family in add_path.address_families() {
-                                bgp_ctx.add_path_mut().insert(
-                                    add_path_family.address_type(),
-                                    add_path_family.receive(),
-                                );
-                            }
-                        }
-                        for multiple_labels in multiple_labels_caps {
-                            for label in multiple_labels {
-                                bgp_ctx
-                                    .multiple_labels_mut()
-                                    .insert(label.address_type(), label.count());
-                            }
-                        }
-                    }
+                for peer_key in peer_keys {
+                    let bgp_ctx = self.ctx.peers_mut().entry(peer_key).or_default();
+                    *bgp_ctx.add_path_mut() = add_path_receive.clone();
+                    *bgp_ctx.add_path_send_mut() = add_path_send.clone();
+                    *bgp_ctx.multiple_labels_mut() = multiple_labels.clone();
                 }
-                _ => {}
-            },
+            }
+            _ => {}
         };
     }
 }
@@ -177,56 +439,119 @@ impl Decoder for BmpCodec {
     type Error = BmpCodecDecoderError;
 
     fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        if self.in_message || buf.len() >= BMP_MESSAGE_MIN_LENGTH {
-            let version: u8 = buf[0];
+        // Loop to skip over dropped messages that are already in the buffer
+        while self.in_message || buf.len() >= BMP_MESSAGE_MIN_LENGTH {
             // Fail early if the version is invalid
-            if let Err(e) = BmpVersion::try_from(version) {
-                buf.advance(1);
-                return Err(BmpCodecDecoderError::BmpMessageParsingError(
-                    BmpMessageParsingError::UndefinedBmpVersion(e),
-                ));
-            }
+            let version = match BmpVersion::try_from(buf[0]) {
+                Ok(version) => version,
+                Err(e) => {
+                    buf.advance(1);
+                    return Err(BmpCodecDecoderError::BmpMessageParsingError {
+                        error: BmpMessageParsingError::UndefinedBmpVersion(e),
+                        frame: None,
+                    });
+                }
+            };
             // Read the length, starting form after the version
-            let length = NetworkEndian::read_u32(&buf[1..BMP_MESSAGE_MIN_LENGTH]) as usize;
+            let length = NetworkEndian::read_u32(&buf[1..BMP_MESSAGE_MIN_LENGTH]);
+            if let Some(diagnostic) = self
+                .max_message_length
+                .and_then(|max_length| BmpLengthDiagnostic::check(length, max_length))
+            {
+                // The length can't be trusted to find the next message, skip the header only
+                self.in_message = false;
+                buf.advance(BMP_MESSAGE_MIN_LENGTH);
+                return Err(BmpCodecDecoderError::ImplausibleLength(diagnostic));
+            }
+            let length = length as usize;
             if buf.len() < length {
                 // We still didn't read all the bytes for the message yet
                 self.in_message = true;
-                Ok(None)
+                return Ok(None);
+            }
+            self.in_message = false;
+            // Negotiate the version of the session with its first message
+            if self
+                .max_version
+                .is_some_and(|max_version| version as u8 > max_version as u8)
+            {
+                buf.advance(length.max(BMP_MESSAGE_MIN_LENGTH));
+                return Err(BmpCodecDecoderError::UnsupportedVersion(version));
+            }
+            match self.session_version {
+                Some(expected) if expected != version => {
+                    buf.advance(length.max(BMP_MESSAGE_MIN_LENGTH));
+                    return Err(BmpCodecDecoderError::VersionMismatch {
+                        expected,
+                        found: version,
+                    });
+                }
+                Some(_) => {}
+                None => self.session_version = Some(version),
+            }
+            // Check the action for the message type before decoding it.
+            // Undefined types and malformed lengths are left to the parser to report.
+            let action = if length > BMP_MESSAGE_MIN_LENGTH {
+                BmpMessageType::try_from(buf[BMP_MESSAGE_MIN_LENGTH])
+                    .ok()
+                    .map(|msg_type| (msg_type, self.message_action(msg_type)))
             } else {
-                self.in_message = false;
-                let msg = match BmpMessage::from_wire(Span::new(buf), &mut self.ctx) {
-                    Ok((span, msg)) => {
-                        self.update_parsing_ctx(&msg);
-                        buf.advance(span.location_offset());
-                        msg
-                    }
-                    Err(error) => {
-                        let err = match error {
-                            nom::Err::Incomplete(needed) => {
-                                let needed = match needed {
-                                    Needed::Unknown => None,
-                                    Needed::Size(size) => Some(size.get()),
-                                };
-                                BmpCodecDecoderError::Incomplete(needed)
-                            }
-                            nom::Err::Error(error) | nom::Err::Failure(error) => {
-                                BmpCodecDecoderError::BmpMessageParsingError(error.error().clone())
-                            }
-                        };
-                        // Make sure we advance the buffer far enough, so we don't get stuck on an
-                        // error value.
-                        // Unfortunately, BMP doesn't have synchronization values like in BGP
-                        // to understand we are in a new message.
-                        buf.advance(if length < 5 { 5 } else { length });
-                        return Err(err);
-                    }
-                };
-                Ok(Some(msg))
+                None
+            };
+            match action {
+                Some((_, BmpMessageAction::Drop)) => {
+                    buf.advance(length);
+                    continue;
+                }
+                Some((msg_type, BmpMessageAction::Raw)) => {
+                    let value = buf[BMP_MESSAGE_MIN_LENGTH + 1..length].to_vec();
+                    buf.advance(length);
+                    let msg = match version {
+                        BmpVersion::Version3 => {
+                            BmpMessage::V3(BmpMessageValue::Raw { msg_type, value })
+                        }
+                        BmpVersion::Version4 => {
+                            BmpMessage::V4(BmpV4MessageValue::Raw { msg_type, value })
+                        }
+                    };
+                    return Ok(Some(msg));
+                }
+                Some((_, BmpMessageAction::Decode)) | None => {}
             }
-        } else {
-            // We don't have enough data yet to start processing
-            Ok(None)
+            let msg = match BmpMessage::from_wire(Span::new(buf), &mut self.ctx) {
+                Ok((span, msg)) => {
+                    self.update_parsing_ctx(&msg);
+                    buf.advance(span.location_offset());
+                    msg
+                }
+                Err(error) => {
+                    let err = match error {
+                        nom::Err::Incomplete(needed) => {
+                            let needed = match needed {
+                                Needed::Unknown => None,
+                                Needed::Size(size) => Some(size.get()),
+                            };
+                            BmpCodecDecoderError::Incomplete(needed)
+                        }
+                        nom::Err::Error(error) | nom::Err::Failure(error) => {
+                            BmpCodecDecoderError::BmpMessageParsingError {
+                                error: error.error().clone(),
+                                frame: self.raw_frames.capture(&buf[..length.min(buf.len())]),
+                            }
+                        }
+                    };
+                    // Make sure we advance the buffer far enough, so we don't get stuck on an
+                    // error value.
+                    // Unfortunately, BMP doesn't have synchronization values like in BGP
+                    // to understand we are in a new message.
+                    buf.advance(if length < 5 { 5 } else { length });
+                    return Err(err);
+                }
+            };
+            return Ok(Some(msg));
         }
+        // We don't have enough data yet to start processing
+        Ok(None)
     }
 }
 
@@ -235,12 +560,17 @@ mod tests {
     use super::*;
     use crate::*;
     use chrono::TimeZone;
+    use ipnet::Ipv4Net;
     use netgauze_bgp_pkt::{
         capabilities::{
-            ExtendedNextHopEncoding, ExtendedNextHopEncodingCapability, FourOctetAsCapability,
+            AddPathAddressFamily, AddPathCapability, ExtendedNextHopEncoding,
+            ExtendedNextHopEncodingCapability, FourOctetAsCapability,
             MultiProtocolExtensionsCapability,
         },
+        nlri::{Ipv4Unicast, Ipv4UnicastAddress},
         open::{BgpOpenMessage, BgpOpenMessageParameter},
+        update::BgpUpdateMessage,
+        wire::deserializer::BgpParsingContext,
     };
     use netgauze_iana::address_family::AddressFamily;
     use std::{net::Ipv6Addr, str::FromStr};
@@ -269,6 +599,107 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_codec_message_actions() -> Result<(), BmpMessageWritingError> {
+        let init = BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(vec![
+            InitiationInformation::SystemName("PE2".to_string()),
+        ])));
+        let peer_header = PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))),
+            64512,
+            Ipv4Addr::new(10, 0, 0, 1),
+            None,
+        );
+        let terminate = BmpMessage::V3(BmpMessageValue::Termination(TerminationMessage::new(
+            peer_header,
+            vec![TerminationInformation::String("test".to_string())],
+        )));
+        let mut codec = BmpCodec::with_message_actions(HashMap::from([
+            (BmpMessageType::Initiation, BmpMessageAction::Drop),
+            (BmpMessageType::Termination, BmpMessageAction::Raw),
+        ]));
+        assert_eq!(
+            codec.message_action(BmpMessageType::RouteMonitoring),
+            BmpMessageAction::Decode
+        );
+
+        let mut buf = BytesMut::new();
+        codec.encode(init.clone(), &mut buf)?;
+        codec.encode(terminate.clone(), &mut buf)?;
+        codec.encode(init.clone(), &mut buf)?;
+
+        let mut terminate_buf = BytesMut::new();
+        codec.encode(terminate.clone(), &mut terminate_buf)?;
+        let raw = BmpMessage::V3(BmpMessageValue::Raw {
+            msg_type: BmpMessageType::Termination,
+            value: terminate_buf[BMP_MESSAGE_MIN_LENGTH + 1..].to_vec(),
+        });
+        assert_eq!(raw.len(), terminate.len());
+
+        // Initiation is dropped, and Termination is passed raw
+        assert_eq!(codec.decode(&mut buf), Ok(Some(raw)));
+        // The last Initiation is dropped as well
+        assert_eq!(codec.decode(&mut buf), Ok(None));
+        assert!(buf.is_empty());
+
+        codec.set_message_action(BmpMessageType::Initiation, BmpMessageAction::Decode);
+        codec.encode(init.clone(), &mut buf)?;
+        assert_eq!(codec.decode(&mut buf), Ok(Some(init)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_codec_version_negotiation() -> Result<(), BmpMessageWritingError> {
+        let information = vec![InitiationInformation::SystemName("PE2".to_string())];
+        let init_v3 = BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(
+            information.clone(),
+        )));
+        let init_v4 = BmpMessage::V4(BmpV4MessageValue::Initiation(InitiationMessage::new(
+            information,
+        )));
+
+        let mut codec = BmpCodec::default();
+        let mut buf = BytesMut::new();
+        codec.encode(init_v4.clone(), &mut buf)?;
+        codec.encode(init_v3.clone(), &mut buf)?;
+        codec.encode(init_v4.clone(), &mut buf)?;
+        assert_eq!(codec.session_version(), None);
+        assert_eq!(codec.decode(&mut buf), Ok(Some(init_v4.clone())));
+        assert_eq!(codec.session_version(), Some(BmpVersion::Version4));
+        // Messages of another version are skipped
+        assert_eq!(
+            codec.decode(&mut buf),
+            Err(BmpCodecDecoderError::VersionMismatch {
+                expected: BmpVersion::Version4,
+                found: BmpVersion::Version3,
+            })
+        );
+        assert_eq!(codec.decode(&mut buf), Ok(Some(init_v4.clone())));
+        assert!(buf.is_empty());
+
+        let mut codec = BmpCodec::default();
+        codec.set_max_version(Some(BmpVersion::Version3));
+        codec.encode(init_v4, &mut buf)?;
+        codec.encode(init_v3.clone(), &mut buf)?;
+        assert_eq!(
+            codec.decode(&mut buf),
+            Err(BmpCodecDecoderError::UnsupportedVersion(
+                BmpVersion::Version4
+            ))
+        );
+        assert_eq!(codec.session_version(), None);
+        assert_eq!(codec.decode(&mut buf), Ok(Some(init_v3)));
+        assert_eq!(codec.session_version(), Some(BmpVersion::Version3));
+        Ok(())
+    }
+
     #[test]
     fn test_peer_key_add_remove() -> Result<(), BmpMessageWritingError> {
         let peer_header = PeerHeader::new(
@@ -382,23 +813,284 @@ mod tests {
         let mut codec = BmpCodec::default();
         let peer_key = PeerKey::from_peer_header(&peer_header);
         // Check initially empty
-        assert!(!codec.ctx.contains_key(&peer_key));
+        assert!(!codec.ctx.peers().contains_key(&peer_key));
 
         // Check peer registered correctly
         codec.update_parsing_ctx(&peer_up);
-        assert!(codec.ctx.contains_key(&peer_key));
+        assert!(codec.ctx.peers().contains_key(&peer_key));
 
         // Check peer removed after a Peer Down Message
         codec.update_parsing_ctx(&peer_down);
-        assert!(!codec.ctx.contains_key(&peer_key));
+        assert!(!codec.ctx.peers().contains_key(&peer_key));
 
         // Register again
         codec.update_parsing_ctx(&peer_up);
-        assert!(codec.ctx.contains_key(&peer_key));
+        assert!(codec.ctx.peers().contains_key(&peer_key));
 
         // Check peer removed after a terminate message
         codec.update_parsing_ctx(&terminate);
-        assert!(!codec.ctx.contains_key(&peer_key));
+        assert!(!codec.ctx.peers().contains_key(&peer_key));
         Ok(())
     }
+
+    #[test]
+    fn test_peer_key_strategy() {
+        let peer_type = BmpPeerType::GlobalInstancePeer {
+            ipv6: true,
+            post_policy: false,
+            asn2: false,
+            adj_rib_out: false,
+        };
+        let peer_header = PeerHeader::new(
+            peer_type,
+            None,
+            Some(IpAddr::V6(Ipv6Addr::from_str("fc00::1").unwrap())),
+            64512,
+            Ipv4Addr::new(10, 0, 0, 1),
+            Some(Utc.timestamp_opt(1664821826, 645593000).unwrap()),
+        );
+        // Same peer after a reconnect, reporting a different ASN and BGP ID
+        let reconnected_header = PeerHeader::new(
+            peer_type,
+            None,
+            Some(IpAddr::V6(Ipv6Addr::from_str("fc00::1").unwrap())),
+            64513,
+            Ipv4Addr::new(10, 0, 0, 2),
+            Some(Utc.timestamp_opt(1664821826, 645593000).unwrap()),
+        );
+        let adj_rib_out_header = PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: true,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: true,
+            },
+            None,
+            Some(IpAddr::V6(Ipv6Addr::from_str("fc00::1").unwrap())),
+            64512,
+            Ipv4Addr::new(10, 0, 0, 1),
+            Some(Utc.timestamp_opt(1664821826, 645593000).unwrap()),
+        );
+        let key = PeerKey::from_peer_header(&peer_header);
+        let reconnected_key = PeerKey::from_peer_header(&reconnected_header);
+        let adj_rib_out_key = PeerKey::from_peer_header(&adj_rib_out_header);
+
+        let full = PeerKeyStrategy::Full;
+        assert_eq!(full.apply(key), key);
+        assert_ne!(full.apply(key), full.apply(reconnected_key));
+        assert_ne!(full.apply(key), full.apply(adj_rib_out_key));
+
+        let address_rd = PeerKeyStrategy::AddressAndRd;
+        assert_eq!(address_rd.apply(key), address_rd.apply(reconnected_key));
+        assert_eq!(address_rd.apply(key), address_rd.apply(adj_rib_out_key));
+
+        let address_rd_o_flag = PeerKeyStrategy::AddressRdAndAdjRibOut;
+        assert_eq!(
+            address_rd_o_flag.apply(key),
+            address_rd_o_flag.apply(reconnected_key)
+        );
+        assert_ne!(
+            address_rd_o_flag.apply(key),
+            address_rd_o_flag.apply(adj_rib_out_key)
+        );
+
+        let peer_down = BmpMessage::V3(BmpMessageValue::PeerDownNotification(
+            PeerDownNotificationMessage::build(
+                reconnected_header,
+                PeerDownNotificationReason::LocalSystemClosedFsmEventFollows(2),
+            )
+            .unwrap(),
+        ));
+        let mut codec = BmpCodec::default();
+        codec.set_peer_key_strategy(PeerKeyStrategy::AddressAndRd);
+        assert_eq!(codec.peer_key_strategy(), PeerKeyStrategy::AddressAndRd);
+        let peer_key = codec.ctx.peer_key(&peer_header);
+        codec
+            .ctx
+            .peers_mut()
+            .insert(peer_key, BgpParsingContext::default());
+
+        // Peer down of the reconnected peer removes the context of the old session
+        codec.update_parsing_ctx(&peer_down);
+        assert!(codec.ctx.peers().is_empty());
+    }
+
+    #[test]
+    fn test_add_path_asymmetric() -> Result<(), BmpMessageWritingError> {
+        let peer_header = |adj_rib_out| {
+            PeerHeader::new(
+                BmpPeerType::GlobalInstancePeer {
+                    ipv6: false,
+                    post_policy: false,
+                    asn2: false,
+                    adj_rib_out,
+                },
+                None,
+                Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 3))),
+                64512,
+                Ipv4Addr::new(10, 0, 0, 3),
+                None,
+            )
+        };
+        let open = |bgp_id, send, receive| {
+            BgpMessage::Open(BgpOpenMessage::new(
+                64512,
+                180,
+                bgp_id,
+                vec![BgpOpenMessageParameter::Capabilities(vec![
+                    BgpCapability::AddPath(AddPathCapability::new(vec![
+                        AddPathAddressFamily::new(AddressType::Ipv4Unicast, send, receive),
+                    ])),
+                ])],
+            ))
+        };
+        // The monitored router sends path IDs, but doesn't accept them
+        let peer_up = BmpMessage::V3(BmpMessageValue::PeerUpNotification(
+            PeerUpNotificationMessage::build(
+                peer_header(false),
+                Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))),
+                Some(179),
+                Some(29834),
+                open(Ipv4Addr::new(10, 0, 0, 1), true, false),
+                open(Ipv4Addr::new(10, 0, 0, 3), true, true),
+                vec![],
+            )
+            .unwrap(),
+        ));
+        let network = Ipv4Unicast::from_net(Ipv4Net::from_str("192.0.2.0/24").unwrap()).unwrap();
+        let route_monitoring = |adj_rib_out, path_id| {
+            BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+                RouteMonitoringMessage::build(
+                    peer_header(adj_rib_out),
+                    BgpMessage::Update(BgpUpdateMessage::new(
+                        vec![Ipv4UnicastAddress::new(path_id, network)],
+                        vec![],
+                        vec![],
+                    )),
+                )
+                .unwrap(),
+            ))
+        };
+        let adj_rib_in = route_monitoring(false, None);
+        let adj_rib_out = route_monitoring(true, Some(7));
+
+        let mut codec = BmpCodec::default();
+        codec.set_peer_key_strategy(PeerKeyStrategy::AddressAndRd);
+        codec.update_parsing_ctx(&peer_up);
+        let bgp_ctx = &codec.ctx.peers()[&codec.ctx.peer_key(&peer_header(false))];
+        assert_eq!(
+            bgp_ctx.add_path(),
+            &HashMap::from([(AddressType::Ipv4Unicast, false)])
+        );
+        assert_eq!(
+            bgp_ctx.add_path_send(),
+            &HashMap::from([(AddressType::Ipv4Unicast, true)])
+        );
+
+        let mut buf = BytesMut::new();
+        codec.encode(adj_rib_in.clone(), &mut buf)?;
+        codec.encode(adj_rib_out.clone(), &mut buf)?;
+        assert_eq!(codec.decode(&mut buf), Ok(Some(adj_rib_in)));
+        assert_eq!(codec.decode(&mut buf), Ok(Some(adj_rib_out)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_codec_implausible_length() {
+        let mut codec = BmpCodec::default();
+        codec.set_max_message_length(Some(1024));
+        assert_eq!(codec.max_message_length(), Some(1024));
+
+        // Initiation message with its length in little-endian
+        let mut buf = BytesMut::from(&[0x03, 0x06, 0x00, 0x00, 0x00, 0x04][..]);
+        assert_eq!(
+            codec.decode(&mut buf),
+            Err(BmpCodecDecoderError::ImplausibleLength(
+                BmpLengthDiagnostic {
+                    length: 0x06000000,
+                    max_length: 1024,
+                    guess: BmpLengthGuess::ByteSwapped(6),
+                }
+            ))
+        );
+        // Only the header is skipped
+        assert_eq!(buf.len(), 1);
+
+        let mut buf = BytesMut::from(&[0x03, 0x00, 0x00, 0x00, 0x05][..]);
+        assert_eq!(
+            codec.decode(&mut buf),
+            Err(BmpCodecDecoderError::ImplausibleLength(
+                BmpLengthDiagnostic {
+                    length: 5,
+                    max_length: 1024,
+                    guess: BmpLengthGuess::TruncatedHeader,
+                }
+            ))
+        );
+        assert!(buf.is_empty());
+
+        let diagnostic = BmpLengthDiagnostic::check(0x00ffffff, 1024).unwrap();
+        assert_eq!(diagnostic.guess(), BmpLengthGuess::Unknown);
+        assert_eq!(BmpLengthDiagnostic::check(1024, 1024), None);
+
+        // Without a max length, lengths are checked by the parser
+        let mut buf = BytesMut::from(&[0x03, 0x00, 0x00, 0x00, 0x01][..]);
+        assert_eq!(
+            BmpCodec::default().decode(&mut buf),
+            Err(BmpCodecDecoderError::BmpMessageParsingError {
+                error: BmpMessageParsingError::InvalidBmpLength(1),
+                frame: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_codec_error_category() {
+        // Undefined BMP message type 200
+        let error = BmpCodec::default()
+            .decode(&mut BytesMut::from(&[3, 0, 0, 0, 6, 200][..]))
+            .unwrap_err();
+        assert_eq!(error.category(), ErrorCategory::UnknownCode);
+        let cause = error.cause().unwrap();
+        assert_eq!(cause.variant(), "BmpMessageValueError");
+        let leaf = cause.cause().unwrap();
+        assert_eq!(leaf.variant(), "UndefinedBmpMessageType");
+        assert!(leaf.cause().is_none());
+
+        // Initiation message with a TLV claiming more bytes than available
+        let error = BmpCodec::default()
+            .decode(&mut BytesMut::from(&[3, 0, 0, 0, 10, 4, 0, 2, 0, 10][..]))
+            .unwrap_err();
+        assert_eq!(error.category(), ErrorCategory::Truncated);
+
+        assert_eq!(
+            BmpCodecDecoderError::UnsupportedVersion(BmpVersion::Version4).category(),
+            ErrorCategory::Unsupported
+        );
+    }
+
+    #[test]
+    fn test_codec_raw_frame() {
+        let policy = RawFramePolicy::new(4, 1, std::time::Duration::from_secs(3600));
+        let mut codec = BmpCodec::default();
+        codec.set_raw_frame_policy(policy);
+        assert_eq!(codec.raw_frame_policy(), policy);
+
+        // Two messages of an undefined type, only the first one is captured
+        let mut buf = BytesMut::from(&[0x03, 0x00, 0x00, 0x00, 0x06, 0xff][..]);
+        buf.extend_from_slice(&buf.clone());
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert_eq!(
+            err.raw_frame(),
+            Some(&RawFrame::new(&[0x03, 0x00, 0x00, 0x00, 0x06, 0xff], 4))
+        );
+        assert_eq!(err.raw_frame().unwrap().data(), "AwAAAA==");
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert!(matches!(
+            err,
+            BmpCodecDecoderError::BmpMessageParsingError { frame: None, .. }
+        ));
+        assert_eq!(codec.suppressed_raw_frames(), 1);
+        assert!(buf.is_empty());
+    }
 }
@@ -15,6 +15,7 @@
 
 //! Contains BMP codes that are registered at IANA [BGP Monitoring Protocol (BMP) Parameters](https://www.iana.org/assignments/bmp-parameters/bmp-parameters.xhtml)
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use strum_macros::{Display, FromRepr};
 
@@ -38,17 +39,26 @@ pub const PEER_FLAGS_IS_ADJ_RIB_OUT: u8 = 0b00010000;
 /// See [RFC9069](https://datatracker.ietf.org/doc/html/rfc9069)
 pub const PEER_FLAGS_IS_FILTERED: u8 = 0b10000000;
 
+/// Corresponds to the I flag of the Route Policy and Attribute Trace message.
+/// If set indicates that the traced prefix is an IPv6 prefix.
+/// See [draft-xu-grow-bmp-route-policy-attr-trace](https://datatracker.ietf.org/doc/html/draft-xu-grow-bmp-route-policy-attr-trace)
+pub const ROUTE_POLICY_TRACE_FLAGS_IS_IPV6: u8 = 0b10000000;
+
 /// Currently supported BMP versions
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BmpVersion {
     Version3 = 3,
+    /// TLV based Route Monitoring, see [draft-ietf-grow-bmp-tlv](https://datatracker.ietf.org/doc/html/draft-ietf-grow-bmp-tlv)
+    Version4 = 4,
 }
 
 /// BGP version is not one of [`BmpVersion`], the carried value is the undefined
 /// code.
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UndefinedBmpVersion(pub u8);
 
 impl From<BmpVersion> for u8 {
@@ -70,7 +80,8 @@ impl TryFrom<u8> for BmpVersion {
 
 /// BMP Message types as registered in IANA [BMP Message Types](https://www.iana.org/assignments/bmp-parameters/bmp-parameters.xhtml#message-types)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BmpMessageType {
     RouteMonitoring = 0,
@@ -80,6 +91,9 @@ pub enum BmpMessageType {
     Initiation = 4,
     Termination = 5,
     RouteMirroring = 6,
+    /// Temporary code point used by
+    /// [draft-xu-grow-bmp-route-policy-attr-trace](https://datatracker.ietf.org/doc/html/draft-xu-grow-bmp-route-policy-attr-trace)
+    RoutePolicyAndAttributeTrace = 100,
     Experimental251 = 251,
     Experimental252 = 252,
     Experimental253 = 253,
@@ -88,7 +102,8 @@ pub enum BmpMessageType {
 
 /// BGP Message type is not one of [`BmpMessageType`], the carried value is the
 /// undefined code.
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UndefinedBmpMessageType(pub u8);
 
 impl From<BmpMessageType> for u8 {
@@ -110,7 +125,8 @@ impl TryFrom<u8> for BmpMessageType {
 
 /// BMP Message types as registered in IANA [BMP Message Types](https://www.iana.org/assignments/bmp-parameters/bmp-parameters.xhtml#message-types)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BmpPeerTypeCode {
     GlobalInstancePeer = 0,
@@ -125,7 +141,8 @@ pub enum BmpPeerTypeCode {
 
 /// BGP Message type is not one of [`BmpPeerTypeCode`], the carried value is the
 /// undefined code.
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UndefinedBmpPeerTypeCode(pub u8);
 
 impl From<BmpPeerTypeCode> for u8 {
@@ -147,7 +164,8 @@ impl TryFrom<u8> for BmpPeerTypeCode {
 
 /// BMP `InformationTLV` types as registered in IANA [BMP Initiation and Peer Up Information TLVs](https://www.iana.org/assignments/bmp-parameters/bmp-parameters.xhtml#initiation-peer-up-tlvs)
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum InitiationInformationTlvType {
     String = 0,
@@ -163,7 +181,8 @@ pub enum InitiationInformationTlvType {
 
 /// BMP `InformationTLV` type is not one of [`InitiationInformationTlvType`],
 /// the carried value is the undefined code.
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UndefinedInitiationInformationTlvType(pub u16);
 
 impl From<InitiationInformationTlvType> for u16 {
@@ -185,7 +204,8 @@ impl TryFrom<u16> for InitiationInformationTlvType {
 
 /// BMP Termination `InformationTLV` types as registered in IANA [BMP Termination Message TLVs](https://www.iana.org/assignments/bmp-parameters/bmp-parameters.xhtml#termination-message-tlvs)
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum TerminationInformationTlvType {
     String = 0,
@@ -198,7 +218,8 @@ pub enum TerminationInformationTlvType {
 
 /// BMP `InformationTLV` type is not one of [`TerminationInformationTlvType`],
 /// the carried value is the undefined code.
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UndefinedTerminationInformationTlvType(pub u16);
 
 impl From<TerminationInformationTlvType> for u16 {
@@ -220,7 +241,8 @@ impl TryFrom<u16> for TerminationInformationTlvType {
 
 /// BMP peer termination Reason codes as registered in IANA [BMP Termination Message Reason Codes](https://www.iana.org/assignments/bmp-parameters/bmp-parameters.xhtml#termination-message-reason-codes)
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum PeerTerminationCode {
     AdministrativelyClosed = 0,
@@ -236,7 +258,8 @@ pub enum PeerTerminationCode {
 
 /// BMP termination reason code type is not one of [`PeerTerminationCode`], the
 /// carried value is the undefined code.
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UndefinedPeerTerminationCode(pub u16);
 
 impl From<PeerTerminationCode> for u16 {
@@ -258,7 +281,8 @@ impl TryFrom<u16> for PeerTerminationCode {
 
 /// BMP Peer down Reason codes as registered in IANA [BMP Peer Down Reason Codes](https://www.iana.org/assignments/bmp-parameters/bmp-parameters.xhtml#peer-down-reason-codes)
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum PeerDownReasonCode {
     LocalSystemClosedNotificationPduFollows = 1,
@@ -275,7 +299,8 @@ pub enum PeerDownReasonCode {
 
 /// BMP Peer down reason code type is not one of [`PeerDownReasonCode`], the
 /// carried value is the undefined code.
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UndefinedPeerDownReasonCode(pub u8);
 
 impl From<PeerDownReasonCode> for u8 {
@@ -297,7 +322,8 @@ impl TryFrom<u8> for PeerDownReasonCode {
 
 /// [BMP Route Mirroring TLVs](https://www.iana.org/assignments/bmp-parameters/bmp-parameters.xhtml#route-mirroring-tlvs)
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum RouteMirroringTlvType {
     BgpMessage = 0,
@@ -310,7 +336,8 @@ pub enum RouteMirroringTlvType {
 
 /// BMP type is not one of [`RouteMirroringTlvType`], the carried value is the
 /// undefined code.
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UndefinedRouteMirroringTlvType(pub u16);
 
 impl From<RouteMirroringTlvType> for u16 {
@@ -330,9 +357,51 @@ impl TryFrom<u16> for RouteMirroringTlvType {
     }
 }
 
+/// TLV types of the BMP version 4 Route Monitoring messages, see
+/// [draft-ietf-grow-bmp-tlv](https://datatracker.ietf.org/doc/html/draft-ietf-grow-bmp-tlv).
+/// The codes are not assigned by IANA yet, these are the ones used by the
+/// current implementations.
+#[repr(u16)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub enum BmpV4RouteMonitoringTlvType {
+    VrfTableName = 3,
+    BgpUpdatePdu = 4,
+    Group = 5,
+    /// See [draft-ietf-grow-bmp-tlv](https://datatracker.ietf.org/doc/html/draft-ietf-grow-bmp-tlv)
+    StatelessParsing = 7,
+    /// See [draft-ietf-grow-bmp-path-marking-tlv](https://datatracker.ietf.org/doc/html/draft-ietf-grow-bmp-path-marking-tlv)
+    PathMarking = 9,
+}
+
+/// BMP type is not one of [`BmpV4RouteMonitoringTlvType`], the carried value
+/// is the undefined code.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UndefinedBmpV4RouteMonitoringTlvType(pub u16);
+
+impl From<BmpV4RouteMonitoringTlvType> for u16 {
+    fn from(value: BmpV4RouteMonitoringTlvType) -> Self {
+        value as u16
+    }
+}
+
+impl TryFrom<u16> for BmpV4RouteMonitoringTlvType {
+    type Error = UndefinedBmpV4RouteMonitoringTlvType;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match Self::from_repr(value) {
+            Some(val) => Ok(val),
+            None => Err(UndefinedBmpV4RouteMonitoringTlvType(value)),
+        }
+    }
+}
+
 /// [BMP Route Mirroring Information Codes](https://www.iana.org/assignments/bmp-parameters/bmp-parameters.xhtml#route-mirroring-information-codes)
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum RouteMirroringInformation {
     ErroredPdu = 0,
@@ -345,7 +414,8 @@ pub enum RouteMirroringInformation {
 
 /// Code is not one of [`RouteMirroringInformation`], the carried value is the
 /// undefined code.
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UndefinedRouteMirroringInformation(pub u16);
 
 impl From<RouteMirroringInformation> for u16 {
@@ -367,7 +437,8 @@ impl TryFrom<u16> for RouteMirroringInformation {
 
 /// [BMP Statistics Types](https://www.iana.org/assignments/bmp-parameters/bmp-parameters.xhtml#statistics-types)
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BmpStatisticsType {
     NumberOfPrefixesRejectedByInboundPolicy = 0,
@@ -396,7 +467,8 @@ pub enum BmpStatisticsType {
 
 /// Code is not one of [`BmpStatisticsType`], the carried value is the undefined
 /// code.
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UndefinedBmpStatisticsType(pub u16);
 
 impl From<BmpStatisticsType> for u16 {
@@ -513,6 +585,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bmp_v4_route_monitoring_tlv_type() {
+        let undefined_code = 255;
+        let defined_code = 4;
+        let defined_value = BmpV4RouteMonitoringTlvType::try_from(defined_code);
+        let undefined = BmpV4RouteMonitoringTlvType::try_from(undefined_code);
+        let defined_code_u16: u16 = BmpV4RouteMonitoringTlvType::BgpUpdatePdu.into();
+        assert_eq!(defined_value, Ok(BmpV4RouteMonitoringTlvType::BgpUpdatePdu));
+        assert_eq!(defined_code_u16, defined_code);
+        assert_eq!(
+            undefined,
+            Err(UndefinedBmpV4RouteMonitoringTlvType(undefined_code))
+        );
+    }
+
     #[test]
     fn test_route_mirroring_information() {
         let undefined_code = 255;
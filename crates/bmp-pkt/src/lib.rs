@@ -14,6 +14,7 @@
 // limitations under the License.
 
 use std::{
+    borrow::Cow,
     net::{IpAddr, Ipv4Addr},
     ops::Deref,
 };
@@ -22,21 +23,37 @@ use std::{
 use chrono::TimeZone;
 use chrono::{DateTime, Utc};
 
-use netgauze_bgp_pkt::{iana::BgpMessageType, nlri::RouteDistinguisher, BgpMessage};
-use netgauze_iana::address_family::AddressType;
+use ipnet::IpNet;
+use netgauze_bgp_pkt::{
+    iana::BgpMessageType,
+    nlri::{Ipv4UnicastAddress, RouteDistinguisher},
+    path_attribute::{Aggregator, AsPath, PathAttribute, PathAttributeValue},
+    update::BgpUpdateMessage,
+    BgpMessage,
+};
+use netgauze_iana::address_family::{AddressFamily, AddressType, SubsequentAddressFamily};
 
-use crate::iana::{
-    BmpMessageType, BmpPeerTypeCode, BmpStatisticsType, BmpVersion, InitiationInformationTlvType,
-    PeerDownReasonCode, PeerTerminationCode, RouteMirroringInformation, RouteMirroringTlvType,
-    TerminationInformationTlvType,
+use crate::{
+    iana::{
+        BmpMessageType, BmpPeerTypeCode, BmpStatisticsType, BmpVersion,
+        InitiationInformationTlvType, PeerDownReasonCode, PeerTerminationCode,
+        RouteMirroringInformation, RouteMirroringTlvType, TerminationInformationTlvType,
+    },
+    v4::BmpV4MessageValue,
 };
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "capture")]
+pub mod capture;
 #[cfg(feature = "codec")]
 pub mod codec;
 pub mod iana;
-#[cfg(feature = "serde")]
+#[cfg(any(all(test, feature = "wire"), feature = "test-vectors"))]
+pub mod test_vectors;
+pub mod v4;
+#[cfg(feature = "wire")]
 pub mod wire;
 
 /// ```text
@@ -50,10 +67,12 @@ pub mod wire;
 /// |   Msg. Type   |
 /// +---------------+
 /// ```
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BmpMessage {
     V3(BmpMessageValue),
+    V4(BmpV4MessageValue),
 }
 
 impl BmpMessage {
@@ -62,6 +81,7 @@ impl BmpMessage {
     pub fn get_version(&self) -> BmpVersion {
         match self {
             BmpMessage::V3(_) => BmpVersion::Version3,
+            BmpMessage::V4(_) => BmpVersion::Version4,
         }
     }
 
@@ -70,11 +90,23 @@ impl BmpMessage {
     pub fn get_type(&self) -> BmpMessageType {
         match &self {
             BmpMessage::V3(value) => value.get_type(),
+            BmpMessage::V4(value) => value.get_type(),
+        }
+    }
+
+    /// The message value in the version 3 format, for processing that doesn't
+    /// depend on the version. Version 4 messages are converted with
+    /// [`BmpV4MessageValue::to_v3`].
+    pub fn to_v3_value(&self) -> Cow<'_, BmpMessageValue> {
+        match self {
+            BmpMessage::V3(value) => Cow::Borrowed(value),
+            BmpMessage::V4(value) => Cow::Owned(value.to_v3()),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BmpMessageValue {
     RouteMonitoring(RouteMonitoringMessage),
@@ -84,10 +116,18 @@ pub enum BmpMessageValue {
     Initiation(InitiationMessage),
     Termination(TerminationMessage),
     RouteMirroring(RouteMirroringMessage),
+    RoutePolicyAndAttributeTrace(RoutePolicyAndAttributeTraceMessage),
     Experimental251(Vec<u8>),
     Experimental252(Vec<u8>),
     Experimental253(Vec<u8>),
     Experimental254(Vec<u8>),
+    /// Message body that is passed through without decoding it, i.e., the
+    /// bytes following the BMP common header. Produced by the codec when it's
+    /// configured with `BmpMessageAction::Raw` for the message type.
+    Raw {
+        msg_type: BmpMessageType,
+        value: Vec<u8>,
+    },
 }
 
 impl BmpMessageValue {
@@ -101,10 +141,12 @@ impl BmpMessageValue {
             Self::Initiation(_) => BmpMessageType::Initiation,
             Self::Termination(_) => BmpMessageType::Termination,
             Self::RouteMirroring(_) => BmpMessageType::RouteMirroring,
+            Self::RoutePolicyAndAttributeTrace(_) => BmpMessageType::RoutePolicyAndAttributeTrace,
             Self::Experimental251(_) => BmpMessageType::Experimental251,
             Self::Experimental252(_) => BmpMessageType::Experimental252,
             Self::Experimental253(_) => BmpMessageType::Experimental253,
             Self::Experimental254(_) => BmpMessageType::Experimental254,
+            Self::Raw { msg_type, .. } => *msg_type,
         }
     }
 }
@@ -134,7 +176,8 @@ impl BmpMessageValue {
 ///  |                  Timestamp (microseconds)                     |
 ///  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct PeerHeader {
     peer_type: BmpPeerType,
@@ -202,6 +245,178 @@ impl PeerHeader {
             BmpPeerType::Experimental254 { .. } => true,
         }
     }
+
+    /// The O flag, the carried routes are from the Adj-RIB-Out of the peer
+    /// [RFC8671](https://datatracker.ietf.org/doc/html/rfc8671)
+    pub const fn is_adj_rib_out(&self) -> bool {
+        match self.peer_type {
+            BmpPeerType::GlobalInstancePeer { adj_rib_out, .. }
+            | BmpPeerType::RdInstancePeer { adj_rib_out, .. }
+            | BmpPeerType::LocalInstancePeer { adj_rib_out, .. } => adj_rib_out,
+            _ => false,
+        }
+    }
+}
+
+/// Inconsistencies between the values given to [`PeerHeaderBuilder`] and the
+/// peer type
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PeerHeaderBuilderError {
+    /// [`BmpPeerType::RdInstancePeer`] requires a route distinguisher
+    MissingRouteDistinguisher,
+    /// [`BmpPeerType::GlobalInstancePeer`] has a zero route distinguisher
+    UnexpectedRouteDistinguisher(RouteDistinguisher),
+    /// The peer address of [`BmpPeerType::LocRibInstancePeer`] is zero-filled
+    UnexpectedPeerAddress(IpAddr),
+    /// [`BmpPeerType::LocRibInstancePeer`] carries the time the routes were
+    /// installed in the Loc-RIB
+    MissingTimestamp,
+}
+
+/// Builds a [`PeerHeader`] with the peer type flags derived from the given
+/// values, so they can't contradict each other:
+/// - `ipv6` is set when the peer address is an IPv6 address.
+/// - `asn2` is set when the peer AS fits in two octets, unless overridden with
+///   [`PeerHeaderBuilder::asn2`].
+///
+/// [`PeerHeaderBuilder::build`] validates the presence of the route
+/// distinguisher, peer address, and timestamp required by the peer type.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PeerHeaderBuilder {
+    peer_type: BmpPeerTypeCode,
+    post_policy: bool,
+    adj_rib_out: bool,
+    asn2: Option<bool>,
+    filtered: bool,
+    experimental_flags: u8,
+    rd: Option<RouteDistinguisher>,
+    address: Option<IpAddr>,
+    peer_as: u32,
+    bgp_id: Ipv4Addr,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+impl PeerHeaderBuilder {
+    pub const fn new(peer_type: BmpPeerTypeCode, peer_as: u32, bgp_id: Ipv4Addr) -> Self {
+        Self {
+            peer_type,
+            post_policy: false,
+            adj_rib_out: false,
+            asn2: None,
+            filtered: false,
+            experimental_flags: 0,
+            rd: None,
+            address: None,
+            peer_as,
+            bgp_id,
+            timestamp: None,
+        }
+    }
+
+    pub const fn post_policy(mut self, value: bool) -> Self {
+        self.post_policy = value;
+        self
+    }
+
+    pub const fn adj_rib_out(mut self, value: bool) -> Self {
+        self.adj_rib_out = value;
+        self
+    }
+
+    /// Override the `asn2` flag derived from the peer AS, e.g., for a router
+    /// that reformats all the `AS_PATH` information into four octets
+    pub const fn asn2(mut self, value: bool) -> Self {
+        self.asn2 = Some(value);
+        self
+    }
+
+    /// Only used by [`BmpPeerType::LocRibInstancePeer`]
+    pub const fn filtered(mut self, value: bool) -> Self {
+        self.filtered = value;
+        self
+    }
+
+    /// Only used by the experimental peer types
+    pub const fn experimental_flags(mut self, value: u8) -> Self {
+        self.experimental_flags = value;
+        self
+    }
+
+    pub const fn rd(mut self, value: RouteDistinguisher) -> Self {
+        self.rd = Some(value);
+        self
+    }
+
+    pub const fn address(mut self, value: IpAddr) -> Self {
+        self.address = Some(value);
+        self
+    }
+
+    pub const fn timestamp(mut self, value: DateTime<Utc>) -> Self {
+        self.timestamp = Some(value);
+        self
+    }
+
+    pub fn build(self) -> Result<PeerHeader, PeerHeaderBuilderError> {
+        let ipv6 = matches!(self.address, Some(IpAddr::V6(_)));
+        let asn2 = self.asn2.unwrap_or(u16::try_from(self.peer_as).is_ok());
+        let (post_policy, adj_rib_out) = (self.post_policy, self.adj_rib_out);
+        let flags = self.experimental_flags;
+        let peer_type = match self.peer_type {
+            BmpPeerTypeCode::GlobalInstancePeer => {
+                if let Some(rd) = self.rd {
+                    return Err(PeerHeaderBuilderError::UnexpectedRouteDistinguisher(rd));
+                }
+                BmpPeerType::GlobalInstancePeer {
+                    ipv6,
+                    post_policy,
+                    asn2,
+                    adj_rib_out,
+                }
+            }
+            BmpPeerTypeCode::RdInstancePeer => {
+                if self.rd.is_none() {
+                    return Err(PeerHeaderBuilderError::MissingRouteDistinguisher);
+                }
+                BmpPeerType::RdInstancePeer {
+                    ipv6,
+                    post_policy,
+                    asn2,
+                    adj_rib_out,
+                }
+            }
+            BmpPeerTypeCode::LocalInstancePeer => BmpPeerType::LocalInstancePeer {
+                ipv6,
+                post_policy,
+                asn2,
+                adj_rib_out,
+            },
+            BmpPeerTypeCode::LocRibInstancePeer => {
+                if let Some(address) = self.address {
+                    return Err(PeerHeaderBuilderError::UnexpectedPeerAddress(address));
+                }
+                if self.timestamp.is_none() {
+                    return Err(PeerHeaderBuilderError::MissingTimestamp);
+                }
+                BmpPeerType::LocRibInstancePeer {
+                    filtered: self.filtered,
+                }
+            }
+            BmpPeerTypeCode::Experimental251 => BmpPeerType::Experimental251 { flags },
+            BmpPeerTypeCode::Experimental252 => BmpPeerType::Experimental252 { flags },
+            BmpPeerTypeCode::Experimental253 => BmpPeerType::Experimental253 { flags },
+            BmpPeerTypeCode::Experimental254 => BmpPeerType::Experimental254 { flags },
+        };
+        Ok(PeerHeader::new(
+            peer_type,
+            self.rd,
+            self.address,
+            self.peer_as,
+            self.bgp_id,
+            self.timestamp,
+        ))
+    }
 }
 
 /// Identifies the type of peer, along with the type specific flags
@@ -227,7 +442,8 @@ impl PeerHeader {
 ///    when used with route mirroring messages.
 ///  - filtered: The F flag indicates that the Loc-RIB is filtered. This MUST be
 ///    set when a filter is applied to Loc-RIB routes sent to the BMP collector.
-#[derive(Debug, Hash, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Hash, Clone, Copy, Eq, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BmpPeerType {
     GlobalInstancePeer {
@@ -290,7 +506,8 @@ impl BmpPeerType {
 /// The [`InitiationInformation::SystemDescription`] and
 /// [`InitiationInformation::SystemName`] Information TLVs MUST be sent, any
 /// others are optional. The string TLV MAY be included multiple times.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct InitiationMessage {
     information: Vec<InitiationInformation>,
@@ -319,7 +536,8 @@ impl InitiationMessage {
 /// ~                                                               ~
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum InitiationInformation {
     /// The Information field contains a free-form UTF-8 string whose length is
@@ -357,6 +575,13 @@ pub enum InitiationInformation {
     Experimental65532(Vec<u8>),
     Experimental65533(Vec<u8>),
     Experimental65534(Vec<u8>),
+
+    /// A string TLV whose value is not valid UTF-8, kept as raw bytes when
+    /// parsing with the `StringDecodingPolicy::Raw` policy
+    NonUtf8String {
+        tlv_type: InitiationInformationTlvType,
+        value: Vec<u8>,
+    },
 }
 
 impl InitiationInformation {
@@ -382,13 +607,15 @@ impl InitiationInformation {
             InitiationInformation::Experimental65534(_) => {
                 InitiationInformationTlvType::Experimental65534
             }
+            InitiationInformation::NonUtf8String { tlv_type, .. } => *tlv_type,
         }
     }
 }
 
 /// The termination message provides a way for a monitored router to indicate
 /// why it is terminating a session.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct TerminationMessage {
     peer_header: PeerHeader,
@@ -424,7 +651,8 @@ impl TerminationMessage {
 /// ~                                                               ~
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum TerminationInformation {
     String(String),
@@ -433,6 +661,9 @@ pub enum TerminationInformation {
     Experimental65532(Vec<u8>),
     Experimental65533(Vec<u8>),
     Experimental65534(Vec<u8>),
+    /// A string TLV whose value is not valid UTF-8, kept as raw bytes when
+    /// parsing with the `StringDecodingPolicy::Raw` policy
+    NonUtf8String(Vec<u8>),
 }
 
 impl TerminationInformation {
@@ -445,6 +676,7 @@ impl TerminationInformation {
             Self::Experimental65532(_) => TerminationInformationTlvType::Experimental65532,
             Self::Experimental65533(_) => TerminationInformationTlvType::Experimental65533,
             Self::Experimental65534(_) => TerminationInformationTlvType::Experimental65534,
+            Self::NonUtf8String(_) => TerminationInformationTlvType::String,
         }
     }
 }
@@ -452,7 +684,8 @@ impl TerminationInformation {
 /// Runtime errors when constructing a [`RouteMonitoringMessage`]
 /// Peer Up BGP messages should only carry
 /// [`BgpMessage::Update`], anything else is an error
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum RouteMonitoringMessageError {
     UnexpectedMessageType(BgpMessageType),
@@ -465,7 +698,8 @@ pub enum RouteMonitoringMessageError {
 //
 /// Following the common BMP header and per-peer header is a BGP Update
 /// PDU.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct RouteMonitoringMessage {
     peer_header: PeerHeader,
@@ -497,9 +731,112 @@ impl RouteMonitoringMessage {
     }
 }
 
+/// Errors when building a [`RouteMonitoringMessage`] with
+/// [`RouteMonitoringMessageBuilder`]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RouteMonitoringMessageBuilderError {
+    PeerHeaderError(PeerHeaderBuilderError),
+    /// `AS_PATH` and `AGGREGATOR` are encoded with different AS number sizes
+    InconsistentAsEncoding,
+    /// The AS number size of the path attributes contradicts the `asn2` flag
+    /// of the peer header, `asn4` is the size used by the path attributes
+    AsEncodingMismatch {
+        asn4: bool,
+    },
+}
+
+/// Builds a [`RouteMonitoringMessage`] from the routes and path attributes of
+/// the BGP update, so the message is valid before it's written:
+/// - `asn2` of the peer header is derived from the AS number size used by
+///   `AS_PATH` and `AGGREGATOR`, and the override given with
+///   [`PeerHeaderBuilder::asn2`] must agree with it.
+/// - `ipv6` of the peer header is derived from the peer address, as done by
+///   [`PeerHeaderBuilder`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteMonitoringMessageBuilder {
+    peer_header: PeerHeaderBuilder,
+    withdraw_routes: Vec<Ipv4UnicastAddress>,
+    path_attributes: Vec<PathAttribute>,
+    nlri: Vec<Ipv4UnicastAddress>,
+}
+
+impl RouteMonitoringMessageBuilder {
+    pub const fn new(peer_header: PeerHeaderBuilder) -> Self {
+        Self {
+            peer_header,
+            withdraw_routes: vec![],
+            path_attributes: vec![],
+            nlri: vec![],
+        }
+    }
+
+    pub fn withdraw_routes(mut self, value: Vec<Ipv4UnicastAddress>) -> Self {
+        self.withdraw_routes = value;
+        self
+    }
+
+    pub fn path_attributes(mut self, value: Vec<PathAttribute>) -> Self {
+        self.path_attributes = value;
+        self
+    }
+
+    pub fn path_attribute(mut self, value: PathAttribute) -> Self {
+        self.path_attributes.push(value);
+        self
+    }
+
+    pub fn nlri(mut self, value: Vec<Ipv4UnicastAddress>) -> Self {
+        self.nlri = value;
+        self
+    }
+
+    pub fn build(self) -> Result<RouteMonitoringMessage, RouteMonitoringMessageBuilderError> {
+        let asn4 = self.asn4()?;
+        let mut peer_header = self.peer_header;
+        if peer_header.asn2.is_none() {
+            peer_header.asn2 = asn4.map(|asn4| !asn4);
+        }
+        let peer_header = peer_header
+            .build()
+            .map_err(RouteMonitoringMessageBuilderError::PeerHeaderError)?;
+        if let Some(asn4) = asn4 {
+            if peer_header.is_asn4() != asn4 {
+                return Err(RouteMonitoringMessageBuilderError::AsEncodingMismatch { asn4 });
+            }
+        }
+        let update = BgpUpdateMessage::new(self.withdraw_routes, self.path_attributes, self.nlri);
+        Ok(RouteMonitoringMessage {
+            peer_header,
+            update_message: BgpMessage::Update(update),
+        })
+    }
+
+    /// AS number size used by the path attributes, `None` when none of them
+    /// carries an AS number
+    fn asn4(&self) -> Result<Option<bool>, RouteMonitoringMessageBuilderError> {
+        let mut asn4 = None;
+        for attr in &self.path_attributes {
+            let attr_asn4 = match attr.value() {
+                PathAttributeValue::AsPath(AsPath::As2PathSegments(_)) => false,
+                PathAttributeValue::AsPath(AsPath::As4PathSegments(_)) => true,
+                PathAttributeValue::Aggregator(Aggregator::As2Aggregator(_)) => false,
+                PathAttributeValue::Aggregator(Aggregator::As4Aggregator(_)) => true,
+                _ => continue,
+            };
+            if asn4.is_some_and(|asn4| asn4 != attr_asn4) {
+                return Err(RouteMonitoringMessageBuilderError::InconsistentAsEncoding);
+            }
+            asn4 = Some(attr_asn4);
+        }
+        Ok(asn4)
+    }
+}
+
 /// Route Mirroring messages are used for verbatim duplication of messages as
 /// received.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct RouteMirroringMessage {
     peer_header: PeerHeader,
@@ -523,14 +860,16 @@ impl RouteMirroringMessage {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum MirroredBgpMessage {
     Parsed(BgpMessage),
     Raw(Vec<u8>),
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum RouteMirroringValue {
     /// A BGP PDU.  This PDU may or may not be an Update message.
@@ -561,6 +900,181 @@ impl RouteMirroringValue {
     }
 }
 
+/// Route Policy and Attribute Trace message, used by some vendors to expose
+/// the route policies a prefix went through and the attributes it had before
+/// and after each step. See
+/// [draft-xu-grow-bmp-route-policy-attr-trace](https://datatracker.ietf.org/doc/html/draft-xu-grow-bmp-route-policy-attr-trace)
+///
+/// Following the common BMP header and per-peer header:
+///
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |I|   Flags     |                                               |
+/// +-+-+-+-+-+-+-+-+                                               +
+/// |               Route Distinguisher (8 bytes)                   |
+/// +               +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |               | Prefix Length |                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+                               +
+/// ~                Prefix (4 or 16 bytes)                         ~
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ~                Route Origin (16 bytes)                        ~
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |  Event Count  |     Total Event Length        |               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+               +
+/// ~                Events (variable)                              ~
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+///
+/// The I flag is derived from the prefix address family.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct RoutePolicyAndAttributeTraceMessage {
+    peer_header: PeerHeader,
+    rd: RouteDistinguisher,
+    #[cfg_attr(feature = "fuzz", arbitrary(with = crate::arbitrary_ipnet))]
+    prefix: IpNet,
+    route_origin: String,
+    events: Vec<RoutePolicyAndAttributeTraceEvent>,
+}
+
+impl RoutePolicyAndAttributeTraceMessage {
+    pub const fn new(
+        peer_header: PeerHeader,
+        rd: RouteDistinguisher,
+        prefix: IpNet,
+        route_origin: String,
+        events: Vec<RoutePolicyAndAttributeTraceEvent>,
+    ) -> Self {
+        Self {
+            peer_header,
+            rd,
+            prefix,
+            route_origin,
+            events,
+        }
+    }
+
+    pub const fn peer_header(&self) -> &PeerHeader {
+        &self.peer_header
+    }
+
+    pub const fn rd(&self) -> RouteDistinguisher {
+        self.rd
+    }
+
+    pub const fn prefix(&self) -> IpNet {
+        self.prefix
+    }
+
+    /// Name of the protocol or process the route originated from, encoded on
+    /// the wire as a zero padded 16 bytes string.
+    pub fn route_origin(&self) -> &str {
+        &self.route_origin
+    }
+
+    pub const fn events(&self) -> &Vec<RoutePolicyAndAttributeTraceEvent> {
+        &self.events
+    }
+}
+
+/// A single processing step of the traced route.
+///
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |     Single Event Length       |  Event Index  |               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+               +
+/// |                    Timestamp (seconds)                        |
+/// +               +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |               |   Timestamp (microseconds)                    |
+/// +-+-+-+-+-+-+-+-+               +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                               |   Path Identifier             |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+               +-+-+-+-+-+-+-+-+
+/// |                               |      AFI                      |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |     SAFI      |              TLVs (variable)                  ~
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct RoutePolicyAndAttributeTraceEvent {
+    index: u8,
+    #[cfg_attr(feature = "fuzz", arbitrary(with = arbitrary_ext::arbitrary_option(crate::arbitrary_datetime)))]
+    timestamp: Option<DateTime<Utc>>,
+    path_id: u32,
+    address_type: AddressType,
+    tlvs: Vec<RoutePolicyAndAttributeTraceTlv>,
+}
+
+impl RoutePolicyAndAttributeTraceEvent {
+    pub const fn new(
+        index: u8,
+        timestamp: Option<DateTime<Utc>>,
+        path_id: u32,
+        address_type: AddressType,
+        tlvs: Vec<RoutePolicyAndAttributeTraceTlv>,
+    ) -> Self {
+        Self {
+            index,
+            timestamp,
+            path_id,
+            address_type,
+            tlvs,
+        }
+    }
+
+    pub const fn index(&self) -> u8 {
+        self.index
+    }
+
+    pub const fn timestamp(&self) -> Option<&DateTime<Utc>> {
+        self.timestamp.as_ref()
+    }
+
+    pub const fn path_id(&self) -> u32 {
+        self.path_id
+    }
+
+    pub const fn address_type(&self) -> AddressType {
+        self.address_type
+    }
+
+    pub const fn tlvs(&self) -> &Vec<RoutePolicyAndAttributeTraceTlv> {
+        &self.tlvs
+    }
+}
+
+/// TLV carried in a [`RoutePolicyAndAttributeTraceEvent`], such as the
+/// VRF/table name, the matched policy, or the pre- and post-policy path
+/// attributes. The TLV types are not yet registered with IANA and vary between
+/// the draft revisions, hence the value is kept undecoded.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct RoutePolicyAndAttributeTraceTlv {
+    code: u16,
+    value: Vec<u8>,
+}
+
+impl RoutePolicyAndAttributeTraceTlv {
+    pub const fn new(code: u16, value: Vec<u8>) -> Self {
+        Self { code, value }
+    }
+
+    pub const fn code(&self) -> u16 {
+        self.code
+    }
+
+    pub const fn value(&self) -> &Vec<u8> {
+        &self.value
+    }
+}
+
 /// The Peer Up message is used to indicate that a peering session has
 /// come up (i.e., has transitioned into the Established state).
 ///
@@ -583,7 +1097,8 @@ impl RouteMirroringValue {
 /// ~                                                               ~
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct PeerUpNotificationMessage {
     peer_header: PeerHeader,
@@ -599,7 +1114,8 @@ pub struct PeerUpNotificationMessage {
 /// Runtime errors when constructing a [`PeerUpNotificationMessage`]
 /// Peer Up BGP messages should only carry
 /// [`BgpMessage::Open`], anything else is an error
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum PeerUpNotificationMessageError {
     UnexpectedSentMessageType(BgpMessageType),
@@ -671,7 +1187,8 @@ impl PeerUpNotificationMessage {
 /// Runtime errors when constructing a [`PeerDownNotificationMessage`]
 /// Peer Up BGP messages should only carry
 /// [`BgpMessage::Notification`], anything else is an error
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum PeerDownNotificationMessageError {
     UnexpectedBgpMessageType(BgpMessageType),
@@ -690,7 +1207,8 @@ pub enum PeerDownNotificationMessageError {
 /// ~                                                               ~
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct PeerDownNotificationMessage {
     peer_header: PeerHeader,
@@ -746,7 +1264,8 @@ impl PeerDownNotificationMessage {
 
 /// Reason indicates why the session was closed and
 /// [`PeerDownNotificationMessage`] is sent.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum PeerDownNotificationReason {
     /// The local system closed the session.  Following the
@@ -832,7 +1351,8 @@ impl PeerDownNotificationReason {
 /// followed by
 ///```text
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct StatisticsReportMessage {
     peer_header: PeerHeader,
@@ -868,7 +1388,8 @@ impl StatisticsReportMessage {
 /// ~                                                               ~
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum StatisticsCounter {
     NumberOfPrefixesRejectedByInboundPolicy(CounterU32),
@@ -956,12 +1477,95 @@ impl StatisticsCounter {
             Self::Unknown(code, _) => Err(*code),
         }
     }
+
+    /// Value of the statistic, `None` for experimental and unknown statistics
+    /// that carry opaque data
+    pub const fn value(&self) -> Option<StatisticsValue> {
+        match self {
+            Self::NumberOfPrefixesRejectedByInboundPolicy(value)
+            | Self::NumberOfDuplicatePrefixAdvertisements(value)
+            | Self::NumberOfDuplicateWithdraws(value)
+            | Self::NumberOfUpdatesInvalidatedDueToClusterListLoop(value)
+            | Self::NumberOfUpdatesInvalidatedDueToAsPathLoop(value)
+            | Self::NumberOfUpdatesInvalidatedDueToOriginatorId(value)
+            | Self::NumberOfUpdatesInvalidatedDueToAsConfederationLoop(value)
+            | Self::NumberOfUpdatesSubjectedToTreatAsWithdraw(value)
+            | Self::NumberOfPrefixesSubjectedToTreatAsWithdraw(value)
+            | Self::NumberOfDuplicateUpdateMessagesReceived(value) => {
+                Some(StatisticsValue::Counter(*value))
+            }
+            Self::NumberOfRoutesInAdjRibIn(value)
+            | Self::NumberOfRoutesInLocRib(value)
+            | Self::NumberOfRoutesInPerAfiSafiAdjRibIn(_, value)
+            | Self::NumberOfRoutesInPerAfiSafiLocRib(_, value)
+            | Self::NumberOfRoutesInPrePolicyAdjRibOut(value)
+            | Self::NumberOfRoutesInPostPolicyAdjRibOut(value)
+            | Self::NumberOfRoutesInPerAfiSafiPrePolicyAdjRibOut(_, value)
+            | Self::NumberOfRoutesInPerAfiSafiPostPolicyAdjRibOut(_, value) => {
+                Some(StatisticsValue::Gauge(*value))
+            }
+            Self::Experimental65531(_)
+            | Self::Experimental65532(_)
+            | Self::Experimental65533(_)
+            | Self::Experimental65534(_)
+            | Self::Unknown(_, _) => None,
+        }
+    }
+
+    /// Address family of the per AFI/SAFI statistics
+    pub const fn address_type(&self) -> Option<AddressType> {
+        match self {
+            Self::NumberOfRoutesInPerAfiSafiAdjRibIn(address_type, _)
+            | Self::NumberOfRoutesInPerAfiSafiLocRib(address_type, _)
+            | Self::NumberOfRoutesInPerAfiSafiPrePolicyAdjRibOut(address_type, _)
+            | Self::NumberOfRoutesInPerAfiSafiPostPolicyAdjRibOut(address_type, _) => {
+                Some(*address_type)
+            }
+            _ => None,
+        }
+    }
+
+    /// The per AFI/SAFI statistics as an `(AFI, SAFI, routes count)` tuple
+    pub const fn afi_safi_value(&self) -> Option<(AddressFamily, SubsequentAddressFamily, u64)> {
+        match (self.address_type(), self.value()) {
+            (Some(address_type), Some(value)) => Some((
+                address_type.address_family(),
+                address_type.subsequent_address_family(),
+                value.value(),
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Value of a [`StatisticsCounter`], the statistics defined by
+/// [RFC 7854](https://datatracker.ietf.org/doc/html/rfc7854#section-4.8) are
+/// either 32-bit counters of events or 64-bit gauges of routes
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum StatisticsValue {
+    Counter(CounterU32),
+    Gauge(GaugeU64),
+}
+
+impl StatisticsValue {
+    pub const fn value(&self) -> u64 {
+        match self {
+            Self::Counter(counter) => counter.value() as u64,
+            Self::Gauge(gauge) => gauge.value(),
+        }
+    }
+
+    pub const fn is_gauge(&self) -> bool {
+        matches!(self, Self::Gauge(_))
+    }
 }
 
 /// A non-negative integer that monotonically increases
 /// until it reaches a maximum value, when it wraps around and starts
 /// increasing again from 0.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct CounterU32(u32);
 
@@ -993,7 +1597,8 @@ impl Deref for CounterU32 {
 /// its minimum value. If the information being modeled subsequently
 /// decreases below the maximum value (or increases above the minimum
 /// value), the 64-bit Gauge also decreases (or increases).
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct GaugeU64(u64);
 
@@ -1024,7 +1629,8 @@ fn arbitrary_ipv4(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Ipv4
 
 /// PeerKey is used to identify a BMP peer. This key is unique only
 /// to the BMP session.
-#[derive(Debug, Hash, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Hash, Copy, Clone, Eq, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct PeerKey {
     #[cfg_attr(feature = "fuzz", arbitrary(with = arbitrary_ext::arbitrary_option(crate::arbitrary_ip)))]
@@ -1080,6 +1686,74 @@ impl PeerKey {
     }
 }
 
+/// Selects which [`PeerKey`] fields identify a peer, for stateful processing
+/// such as tracking the BGP parsing context of a peer. Fields that are not
+/// part of the key are replaced with fixed placeholder values.
+///
+/// Some vendors change the ASN or BGP ID of a peer across reconnects, in which
+/// case keying on the address and RD only keeps the peer state stable.
+#[derive(Debug, Default, Hash, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub enum PeerKeyStrategy {
+    /// Peer address, peer type (including its flags), RD, ASN and BGP ID. The
+    /// default, so pre- and post-policy, and Adj-RIB-In and Adj-RIB-Out, of
+    /// the same peer are different peers.
+    #[default]
+    Full,
+
+    /// Peer address and RD only
+    AddressAndRd,
+
+    /// Peer address, RD and the O flag (Adj-RIB-Out) of the peer type, so
+    /// Adj-RIB-In and Adj-RIB-Out of the same peer are kept apart
+    AddressRdAndAdjRibOut,
+}
+
+impl PeerKeyStrategy {
+    /// Placeholder peer type used when the peer type is not part of the key
+    const PLACEHOLDER_PEER_TYPE: BmpPeerType = BmpPeerType::GlobalInstancePeer {
+        ipv6: false,
+        post_policy: false,
+        asn2: false,
+        adj_rib_out: false,
+    };
+
+    /// Reduce a full peer key to the fields selected by the strategy
+    pub const fn apply(&self, key: PeerKey) -> PeerKey {
+        match self {
+            Self::Full => key,
+            Self::AddressAndRd => PeerKey::new(
+                key.peer_address,
+                Self::PLACEHOLDER_PEER_TYPE,
+                key.rd,
+                0,
+                Ipv4Addr::UNSPECIFIED,
+            ),
+            Self::AddressRdAndAdjRibOut => {
+                let adj_rib_out = match key.peer_type {
+                    BmpPeerType::GlobalInstancePeer { adj_rib_out, .. }
+                    | BmpPeerType::RdInstancePeer { adj_rib_out, .. }
+                    | BmpPeerType::LocalInstancePeer { adj_rib_out, .. } => adj_rib_out,
+                    _ => false,
+                };
+                PeerKey::new(
+                    key.peer_address,
+                    BmpPeerType::GlobalInstancePeer {
+                        ipv6: false,
+                        post_policy: false,
+                        asn2: false,
+                        adj_rib_out,
+                    },
+                    key.rd,
+                    0,
+                    Ipv4Addr::UNSPECIFIED,
+                )
+            }
+        }
+    }
+}
+
 // Custom function to generate arbitrary ipv6 addresses
 #[cfg(feature = "fuzz")]
 fn arbitrary_ipv6(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<std::net::Ipv6Addr> {
@@ -1087,6 +1761,16 @@ fn arbitrary_ipv6(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<std:
     Ok(std::net::Ipv6Addr::from(value))
 }
 
+// Custom function to generate arbitrary IPv4 and IPv6 prefixes
+#[cfg(feature = "fuzz")]
+fn arbitrary_ipnet(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<IpNet> {
+    let ipv4 = ipnet::Ipv4Net::new(arbitrary_ipv4(u)?, u.int_in_range(0..=32)?).unwrap();
+    let ipv6 = ipnet::Ipv6Net::new(arbitrary_ipv6(u)?, u.int_in_range(0..=128)?).unwrap();
+    let choices = [IpNet::V4(ipv4), IpNet::V6(ipv6)];
+    let net = u.choose(&choices)?;
+    Ok(*net)
+}
+
 // Custom function to generate arbitrary IPv4 and IPv6 addresses
 #[cfg(feature = "fuzz")]
 fn arbitrary_ip(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<IpAddr> {
@@ -1106,3 +1790,348 @@ fn arbitrary_datetime(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use netgauze_bgp_pkt::path_attribute::{
+        As2PathSegment, As4Aggregator, As4PathSegment, AsPathSegmentType,
+    };
+    use std::net::Ipv6Addr;
+
+    #[test]
+    fn test_peer_header_builder() {
+        let bgp_id = Ipv4Addr::new(192, 0, 2, 1);
+        let timestamp = Utc.timestamp_opt(1680000000, 0).unwrap();
+        let rd = RouteDistinguisher::As2Administrator {
+            asn2: 65000,
+            number: 1,
+        };
+
+        let header = PeerHeaderBuilder::new(BmpPeerTypeCode::GlobalInstancePeer, 65000, bgp_id)
+            .address(IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)))
+            .post_policy(true)
+            .timestamp(timestamp)
+            .build();
+        assert_eq!(
+            header,
+            Ok(PeerHeader::new(
+                BmpPeerType::GlobalInstancePeer {
+                    ipv6: true,
+                    post_policy: true,
+                    asn2: true,
+                    adj_rib_out: false,
+                },
+                None,
+                Some(IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1))),
+                65000,
+                bgp_id,
+                Some(timestamp),
+            ))
+        );
+
+        let header = PeerHeaderBuilder::new(BmpPeerTypeCode::RdInstancePeer, 4200000000, bgp_id)
+            .rd(rd)
+            .address(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)))
+            .build()
+            .unwrap();
+        assert_eq!(
+            header.peer_type(),
+            BmpPeerType::RdInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            }
+        );
+        assert!(header.is_asn4());
+        let header = PeerHeaderBuilder::new(BmpPeerTypeCode::LocalInstancePeer, 65000, bgp_id)
+            .asn2(false)
+            .build()
+            .unwrap();
+        assert!(header.is_asn4());
+
+        let header = PeerHeaderBuilder::new(BmpPeerTypeCode::LocRibInstancePeer, 65000, bgp_id)
+            .filtered(true)
+            .timestamp(timestamp)
+            .build()
+            .unwrap();
+        assert_eq!(
+            header.peer_type(),
+            BmpPeerType::LocRibInstancePeer { filtered: true }
+        );
+
+        assert_eq!(
+            PeerHeaderBuilder::new(BmpPeerTypeCode::GlobalInstancePeer, 65000, bgp_id)
+                .rd(rd)
+                .build(),
+            Err(PeerHeaderBuilderError::UnexpectedRouteDistinguisher(rd))
+        );
+        assert_eq!(
+            PeerHeaderBuilder::new(BmpPeerTypeCode::RdInstancePeer, 65000, bgp_id).build(),
+            Err(PeerHeaderBuilderError::MissingRouteDistinguisher)
+        );
+        assert_eq!(
+            PeerHeaderBuilder::new(BmpPeerTypeCode::LocRibInstancePeer, 65000, bgp_id)
+                .address(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)))
+                .timestamp(timestamp)
+                .build(),
+            Err(PeerHeaderBuilderError::UnexpectedPeerAddress(IpAddr::V4(
+                Ipv4Addr::new(192, 0, 2, 2)
+            )))
+        );
+        assert_eq!(
+            PeerHeaderBuilder::new(BmpPeerTypeCode::LocRibInstancePeer, 65000, bgp_id).build(),
+            Err(PeerHeaderBuilderError::MissingTimestamp)
+        );
+    }
+
+    #[test]
+    fn test_route_monitoring_message_builder() {
+        let bgp_id = Ipv4Addr::new(192, 0, 2, 1);
+        let as2_path = PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::AsPath(AsPath::As2PathSegments(vec![As2PathSegment::new(
+                AsPathSegmentType::AsSequence,
+                vec![65000],
+            )])),
+        )
+        .unwrap();
+        let as4_path = PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![As4PathSegment::new(
+                AsPathSegmentType::AsSequence,
+                vec![65000],
+            )])),
+        )
+        .unwrap();
+        let as4_aggregator = PathAttribute::from(
+            true,
+            true,
+            false,
+            false,
+            PathAttributeValue::Aggregator(Aggregator::As4Aggregator(As4Aggregator::new(
+                65000, bgp_id,
+            ))),
+        )
+        .unwrap();
+        let peer_header = || {
+            PeerHeaderBuilder::new(BmpPeerTypeCode::GlobalInstancePeer, 65000, bgp_id)
+                .address(IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)))
+        };
+
+        // A peer with a two-octet AS reformatted into four octets by the router
+        let msg = RouteMonitoringMessageBuilder::new(peer_header())
+            .path_attribute(as4_path.clone())
+            .path_attribute(as4_aggregator.clone())
+            .build()
+            .unwrap();
+        assert_eq!(
+            msg.peer_header().peer_type(),
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: true,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            }
+        );
+        assert_eq!(
+            msg.update_message(),
+            &BgpMessage::Update(BgpUpdateMessage::new(
+                vec![],
+                vec![as4_path.clone(), as4_aggregator.clone()],
+                vec![]
+            ))
+        );
+        let msg = RouteMonitoringMessageBuilder::new(peer_header())
+            .path_attributes(vec![as2_path.clone()])
+            .build()
+            .unwrap();
+        assert!(!msg.peer_header().is_asn4());
+        let msg = RouteMonitoringMessageBuilder::new(peer_header().asn2(false))
+            .build()
+            .unwrap();
+        assert!(msg.peer_header().is_asn4());
+
+        assert_eq!(
+            RouteMonitoringMessageBuilder::new(peer_header().asn2(false))
+                .path_attribute(as2_path.clone())
+                .build(),
+            Err(RouteMonitoringMessageBuilderError::AsEncodingMismatch { asn4: false })
+        );
+        assert_eq!(
+            RouteMonitoringMessageBuilder::new(peer_header())
+                .path_attributes(vec![as2_path.clone(), as4_aggregator])
+                .build(),
+            Err(RouteMonitoringMessageBuilderError::InconsistentAsEncoding)
+        );
+        // Loc-RIB routes are always encoded with four-octet AS numbers
+        assert_eq!(
+            RouteMonitoringMessageBuilder::new(
+                PeerHeaderBuilder::new(BmpPeerTypeCode::LocRibInstancePeer, 65000, bgp_id)
+                    .timestamp(Utc.timestamp_opt(1680000000, 0).unwrap())
+            )
+            .path_attribute(as2_path)
+            .build(),
+            Err(RouteMonitoringMessageBuilderError::AsEncodingMismatch { asn4: false })
+        );
+        assert_eq!(
+            RouteMonitoringMessageBuilder::new(PeerHeaderBuilder::new(
+                BmpPeerTypeCode::RdInstancePeer,
+                65000,
+                bgp_id
+            ))
+            .build(),
+            Err(RouteMonitoringMessageBuilderError::PeerHeaderError(
+                PeerHeaderBuilderError::MissingRouteDistinguisher
+            ))
+        );
+    }
+
+    #[test]
+    fn test_statistics_counter_values() {
+        let rejected = StatisticsCounter::NumberOfPrefixesRejectedByInboundPolicy(CounterU32(7));
+        assert_eq!(
+            rejected.value(),
+            Some(StatisticsValue::Counter(CounterU32(7)))
+        );
+        assert_eq!(rejected.address_type(), None);
+        assert_eq!(rejected.afi_safi_value(), None);
+
+        let per_afi_safi = StatisticsCounter::NumberOfRoutesInPerAfiSafiAdjRibIn(
+            AddressType::Ipv6Unicast,
+            GaugeU64(100),
+        );
+        assert!(per_afi_safi.value().unwrap().is_gauge());
+        assert_eq!(per_afi_safi.address_type(), Some(AddressType::Ipv6Unicast));
+        assert_eq!(
+            per_afi_safi.afi_safi_value(),
+            Some((AddressFamily::IPv6, SubsequentAddressFamily::Unicast, 100))
+        );
+
+        assert_eq!(StatisticsCounter::Experimental65531(vec![1]).value(), None);
+    }
+
+    /// The JSON representation is relied upon by consumers, e.g., Kafka or
+    /// Elasticsearch pipelines, changing it is a breaking change
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_representation() {
+        use netgauze_bgp_pkt::{
+            nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+            path_attribute::{AsPath, NextHop, Origin, PathAttribute, PathAttributeValue},
+            update::BgpUpdateMessage,
+        };
+        let update = BgpUpdateMessage::new(
+            vec![],
+            vec![
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::Origin(Origin::IGP),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![As4PathSegment::new(
+                        AsPathSegmentType::AsSequence,
+                        vec![65001],
+                    )])),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(192, 0, 2, 1))),
+                )
+                .unwrap(),
+            ],
+            vec![Ipv4UnicastAddress::new_no_path_id(
+                Ipv4Unicast::from_net("198.51.100.0/24".parse().unwrap()).unwrap(),
+            )],
+        );
+        let msg = BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            RouteMonitoringMessage::build(
+                PeerHeader::new(
+                    BmpPeerType::GlobalInstancePeer {
+                        ipv6: false,
+                        post_policy: false,
+                        asn2: false,
+                        adj_rib_out: false,
+                    },
+                    None,
+                    Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))),
+                    65001,
+                    Ipv4Addr::new(192, 0, 2, 1),
+                    Some(Utc.timestamp_opt(1680000000, 0).unwrap()),
+                ),
+                BgpMessage::Update(update),
+            )
+            .unwrap(),
+        ));
+        let attr = |value| {
+            serde_json::json!({
+                "optional": false,
+                "transitive": true,
+                "partial": false,
+                "extended_length": false,
+                "value": value,
+            })
+        };
+        let expected = serde_json::json!({
+            "V3": {
+                "RouteMonitoring": {
+                    "peer_header": {
+                        "peer_type": {
+                            "GlobalInstancePeer": {
+                                "ipv6": false,
+                                "post_policy": false,
+                                "asn2": false,
+                                "adj_rib_out": false,
+                            }
+                        },
+                        "rd": null,
+                        "address": "192.0.2.1",
+                        "peer_as": 65001,
+                        "bgp_id": "192.0.2.1",
+                        "timestamp": "2023-03-28T10:40:00Z",
+                    },
+                    "update_message": {
+                        "Update": {
+                            "withdrawn_routes": [],
+                            "path_attributes": [
+                                attr(serde_json::json!({"Origin": "IGP"})),
+                                attr(serde_json::json!({
+                                    "AsPath": {
+                                        "As4PathSegments": [{
+                                            "segment_type": "AsSequence",
+                                            "as_numbers": [65001],
+                                        }]
+                                    }
+                                })),
+                                attr(serde_json::json!({"NextHop": {"next_hop": "192.0.2.1"}})),
+                            ],
+                            "nlri": [{"path_id": null, "network": "198.51.100.0/24"}],
+                        }
+                    },
+                }
+            }
+        });
+        assert_eq!(serde_json::to_value(&msg).unwrap(), expected);
+        assert_eq!(serde_json::from_value::<BmpMessage>(expected).unwrap(), msg);
+    }
+}
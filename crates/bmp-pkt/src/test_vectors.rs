@@ -0,0 +1,174 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! BMP conformance test vectors, each one is checked to round trip against
+//! this crate's wire encoder and decoder.
+//! Vectors carry a complete BMP message (including the common header) and are
+//! decoded with an empty BGP parsing context map.
+
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
+
+use chrono::{DateTime, TimeZone, Utc};
+pub use netgauze_parse_utils::test_vectors::TestVector;
+
+use crate::{
+    BmpMessage, BmpMessageValue, BmpPeerType, CounterU32, InitiationInformation, InitiationMessage,
+    PeerDownNotificationMessage, PeerDownNotificationReason, PeerHeader, StatisticsCounter,
+    StatisticsReportMessage, TerminationInformation, TerminationMessage,
+};
+
+fn timestamp(secs: i64, nsecs: u32) -> Option<DateTime<Utc>> {
+    Utc.timestamp_opt(secs, nsecs).single()
+}
+
+fn global_instance_peer(ipv6: bool) -> BmpPeerType {
+    BmpPeerType::GlobalInstancePeer {
+        ipv6,
+        post_policy: false,
+        asn2: false,
+        adj_rib_out: false,
+    }
+}
+
+/// Test vectors for [`BmpMessage`]
+pub fn bmp_messages() -> Vec<TestVector<BmpMessage>> {
+    vec![
+        TestVector::new(
+            "initiation",
+            vec![
+                0x03, 0x00, 0x00, 0x00, 0x17, 0x04, 0x00, 0x01, 0x00, 0x06, 0x74, 0x65, 0x73, 0x74,
+                0x31, 0x31, 0x00, 0x02, 0x00, 0x03, 0x50, 0x45, 0x32,
+            ],
+            BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(vec![
+                InitiationInformation::SystemDescription("test11".to_string()),
+                InitiationInformation::SystemName("PE2".to_string()),
+            ]))),
+        ),
+        TestVector::new(
+            "termination",
+            vec![
+                3, 0, 0, 0, 56, 5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 172, 16, 0, 20, 0, 0, 0, 200, 172, 16, 0, 20, 99, 60, 152, 139, 0, 4, 90, 174,
+                0, 0, 0, 4, 116, 101, 115, 116,
+            ],
+            BmpMessage::V3(BmpMessageValue::Termination(TerminationMessage::new(
+                PeerHeader::new(
+                    global_instance_peer(false),
+                    None,
+                    Some(IpAddr::V4(Ipv4Addr::new(172, 16, 0, 20))),
+                    200,
+                    Ipv4Addr::new(172, 16, 0, 20),
+                    timestamp(1664915595, 285358000),
+                ),
+                vec![TerminationInformation::String("test".to_string())],
+            ))),
+        ),
+        TestVector::new(
+            "statistics report",
+            vec![
+                0x03, 0x00, 0x00, 0x00, 0x6c, 0x01, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0xfd, 0xfd, 0x00, 0x00, 0x00, 0x00, 0x8b, 0xea, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0xc8, 0xac, 0x10, 0x00, 0x14, 0x63, 0x3c,
+                0x99, 0x78, 0x00, 0x04, 0x73, 0x3f, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x04,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x04, 0x00, 0x00, 0x00, 0x02, 0x00, 0x05,
+                0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x02, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0b, 0x00, 0x04, 0x00, 0x00,
+                0x00, 0x06, 0xff, 0xfb, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00,
+            ],
+            BmpMessage::V3(BmpMessageValue::StatisticsReport(
+                StatisticsReportMessage::new(
+                    PeerHeader::new(
+                        global_instance_peer(true),
+                        None,
+                        Ipv6Addr::from_str("fdfd:0:0:8bea::2").ok().map(IpAddr::V6),
+                        200,
+                        Ipv4Addr::new(172, 16, 0, 20),
+                        timestamp(1664915832, 291647000),
+                    ),
+                    vec![
+                        StatisticsCounter::NumberOfPrefixesRejectedByInboundPolicy(
+                            CounterU32::new(0),
+                        ),
+                        StatisticsCounter::NumberOfUpdatesInvalidatedDueToAsPathLoop(
+                            CounterU32::new(2),
+                        ),
+                        StatisticsCounter::NumberOfUpdatesInvalidatedDueToOriginatorId(
+                            CounterU32::new(0),
+                        ),
+                        StatisticsCounter::NumberOfUpdatesInvalidatedDueToClusterListLoop(
+                            CounterU32::new(0),
+                        ),
+                        StatisticsCounter::NumberOfDuplicateWithdraws(CounterU32::new(0)),
+                        StatisticsCounter::NumberOfUpdatesSubjectedToTreatAsWithdraw(
+                            CounterU32::new(6),
+                        ),
+                        StatisticsCounter::Experimental65531(vec![0, 0, 0, 0]),
+                    ],
+                ),
+            )),
+        ),
+        TestVector::new(
+            "peer down notification",
+            vec![
+                0x03, 0x00, 0x00, 0x00, 0x33, 0x02, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0xfc, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0xfc, 0x00, 0x0a, 0x00, 0x00, 0x01, 0x63, 0x3b,
+                0x2a, 0x53, 0x00, 0x07, 0x71, 0xe3, 0x02, 0x00, 0x02,
+            ],
+            // The reason is always valid for the given peer header
+            BmpMessage::V3(BmpMessageValue::PeerDownNotification(
+                PeerDownNotificationMessage::build(
+                    PeerHeader::new(
+                        global_instance_peer(true),
+                        None,
+                        Ipv6Addr::from_str("fc00::1").ok().map(IpAddr::V6),
+                        64512,
+                        Ipv4Addr::new(10, 0, 0, 1),
+                        timestamp(1664821843, 487907000),
+                    ),
+                    PeerDownNotificationReason::LocalSystemClosedFsmEventFollows(2),
+                )
+                .unwrap(),
+            )),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wire::deserializer::BmpParsingContext;
+    use netgauze_parse_utils::test_helpers::{test_parsed_completely_with_one_input, test_write};
+
+    #[test]
+    fn test_bmp_messages_vectors() {
+        let vectors = bmp_messages();
+        for vector in vectors {
+            test_parsed_completely_with_one_input(
+                vector.wire(),
+                &mut BmpParsingContext::default(),
+                vector.expected(),
+            );
+            assert!(
+                test_write(vector.expected(), vector.wire()).is_ok(),
+                "Failed writing vector: {}",
+                vector.name()
+            );
+        }
+    }
+}
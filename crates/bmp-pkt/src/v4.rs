@@ -0,0 +1,350 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! BMP version 4 messages, as defined in
+//! [draft-ietf-grow-bmp-tlv](https://datatracker.ietf.org/doc/html/draft-ietf-grow-bmp-tlv).
+//!
+//! Version 4 keeps the messages of version 3, except the Route Monitoring
+//! message that carries the BGP Update PDU in a TLV, alongside other TLVs
+//! describing the PDU, such as the capabilities needed to parse it without
+//! the Peer Up state of the peer, or the status of its paths.
+
+use netgauze_bgp_pkt::{capabilities::BgpCapability, iana::BgpMessageType, BgpMessage};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    iana::{BmpMessageType, BmpV4RouteMonitoringTlvType},
+    BmpMessageValue, InitiationMessage, PeerDownNotificationMessage, PeerHeader,
+    PeerUpNotificationMessage, RouteMirroringMessage, RouteMonitoringMessage,
+    RouteMonitoringMessageError, RoutePolicyAndAttributeTraceMessage, StatisticsReportMessage,
+    TerminationMessage,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub enum BmpV4MessageValue {
+    RouteMonitoring(Box<BmpV4RouteMonitoringMessage>),
+    StatisticsReport(StatisticsReportMessage),
+    PeerDownNotification(PeerDownNotificationMessage),
+    PeerUpNotification(PeerUpNotificationMessage),
+    Initiation(InitiationMessage),
+    Termination(TerminationMessage),
+    RouteMirroring(RouteMirroringMessage),
+    RoutePolicyAndAttributeTrace(RoutePolicyAndAttributeTraceMessage),
+    Experimental251(Vec<u8>),
+    Experimental252(Vec<u8>),
+    Experimental253(Vec<u8>),
+    Experimental254(Vec<u8>),
+    /// Message body that is passed through without decoding it, see
+    /// [`BmpMessageValue::Raw`]
+    Raw {
+        msg_type: BmpMessageType,
+        value: Vec<u8>,
+    },
+}
+
+impl BmpV4MessageValue {
+    /// Get IANA type
+    pub const fn get_type(&self) -> BmpMessageType {
+        match self {
+            Self::RouteMonitoring(_) => BmpMessageType::RouteMonitoring,
+            Self::StatisticsReport(_) => BmpMessageType::StatisticsReport,
+            Self::PeerDownNotification(_) => BmpMessageType::PeerDownNotification,
+            Self::PeerUpNotification(_) => BmpMessageType::PeerUpNotification,
+            Self::Initiation(_) => BmpMessageType::Initiation,
+            Self::Termination(_) => BmpMessageType::Termination,
+            Self::RouteMirroring(_) => BmpMessageType::RouteMirroring,
+            Self::RoutePolicyAndAttributeTrace(_) => BmpMessageType::RoutePolicyAndAttributeTrace,
+            Self::Experimental251(_) => BmpMessageType::Experimental251,
+            Self::Experimental252(_) => BmpMessageType::Experimental252,
+            Self::Experimental253(_) => BmpMessageType::Experimental253,
+            Self::Experimental254(_) => BmpMessageType::Experimental254,
+            Self::Raw { msg_type, .. } => *msg_type,
+        }
+    }
+
+    /// The equivalent version 3 message, the Route Monitoring TLVs other than
+    /// the BGP Update PDU are dropped
+    pub fn to_v3(&self) -> BmpMessageValue {
+        match self {
+            Self::RouteMonitoring(value) => BmpMessageValue::RouteMonitoring(value.to_v3()),
+            Self::StatisticsReport(value) => BmpMessageValue::StatisticsReport(value.clone()),
+            Self::PeerDownNotification(value) => {
+                BmpMessageValue::PeerDownNotification(value.clone())
+            }
+            Self::PeerUpNotification(value) => BmpMessageValue::PeerUpNotification(value.clone()),
+            Self::Initiation(value) => BmpMessageValue::Initiation(value.clone()),
+            Self::Termination(value) => BmpMessageValue::Termination(value.clone()),
+            Self::RouteMirroring(value) => BmpMessageValue::RouteMirroring(value.clone()),
+            Self::RoutePolicyAndAttributeTrace(value) => {
+                BmpMessageValue::RoutePolicyAndAttributeTrace(value.clone())
+            }
+            Self::Experimental251(value) => BmpMessageValue::Experimental251(value.clone()),
+            Self::Experimental252(value) => BmpMessageValue::Experimental252(value.clone()),
+            Self::Experimental253(value) => BmpMessageValue::Experimental253(value.clone()),
+            Self::Experimental254(value) => BmpMessageValue::Experimental254(value.clone()),
+            Self::Raw { msg_type, value } => BmpMessageValue::Raw {
+                msg_type: *msg_type,
+                value: value.clone(),
+            },
+        }
+    }
+}
+
+impl From<BmpMessageValue> for BmpV4MessageValue {
+    /// Version 3 Route Monitoring messages are converted to version 4 ones
+    /// without any TLVs other than the BGP Update PDU
+    fn from(value: BmpMessageValue) -> Self {
+        match value {
+            BmpMessageValue::RouteMonitoring(value) => {
+                Self::RouteMonitoring(Box::new(value.into()))
+            }
+            BmpMessageValue::StatisticsReport(value) => Self::StatisticsReport(value),
+            BmpMessageValue::PeerDownNotification(value) => Self::PeerDownNotification(value),
+            BmpMessageValue::PeerUpNotification(value) => Self::PeerUpNotification(value),
+            BmpMessageValue::Initiation(value) => Self::Initiation(value),
+            BmpMessageValue::Termination(value) => Self::Termination(value),
+            BmpMessageValue::RouteMirroring(value) => Self::RouteMirroring(value),
+            BmpMessageValue::RoutePolicyAndAttributeTrace(value) => {
+                Self::RoutePolicyAndAttributeTrace(value)
+            }
+            BmpMessageValue::Experimental251(value) => Self::Experimental251(value),
+            BmpMessageValue::Experimental252(value) => Self::Experimental252(value),
+            BmpMessageValue::Experimental253(value) => Self::Experimental253(value),
+            BmpMessageValue::Experimental254(value) => Self::Experimental254(value),
+            BmpMessageValue::Raw { msg_type, value } => Self::Raw { msg_type, value },
+        }
+    }
+}
+
+/// Version 4 Route Monitoring message: the per-peer header followed by TLVs,
+/// one of them carrying the BGP Update PDU.
+///
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |          Type (2 octets)      |        Length (2 octets)      |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |          Index (2 octets)     |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                    Value (variable)                           |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+///
+/// The BGP Update PDU TLV is written after all the other TLVs.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct BmpV4RouteMonitoringMessage {
+    peer_header: PeerHeader,
+    tlvs: Vec<BmpV4RouteMonitoringTlv>,
+    update_message: BgpMessage,
+}
+
+impl BmpV4RouteMonitoringMessage {
+    pub fn build(
+        peer_header: PeerHeader,
+        tlvs: Vec<BmpV4RouteMonitoringTlv>,
+        update_message: BgpMessage,
+    ) -> Result<Self, RouteMonitoringMessageError> {
+        if update_message.get_type() != BgpMessageType::Update {
+            return Err(RouteMonitoringMessageError::UnexpectedMessageType(
+                update_message.get_type(),
+            ));
+        }
+        Ok(Self {
+            peer_header,
+            tlvs,
+            update_message,
+        })
+    }
+
+    pub const fn peer_header(&self) -> &PeerHeader {
+        &self.peer_header
+    }
+
+    /// TLVs other than the BGP Update PDU
+    pub fn tlvs(&self) -> &[BmpV4RouteMonitoringTlv] {
+        &self.tlvs
+    }
+
+    pub const fn update_message(&self) -> &BgpMessage {
+        &self.update_message
+    }
+
+    /// Capabilities of the Stateless Parsing TLVs
+    pub fn stateless_parsing(&self) -> impl Iterator<Item = &BgpCapability> {
+        self.tlvs.iter().filter_map(|tlv| match tlv.value() {
+            BmpV4RouteMonitoringTlvValue::StatelessParsing(capability) => Some(capability),
+            _ => None,
+        })
+    }
+
+    /// The equivalent version 3 message, without the TLVs
+    pub fn to_v3(&self) -> RouteMonitoringMessage {
+        RouteMonitoringMessage {
+            peer_header: self.peer_header.clone(),
+            update_message: self.update_message.clone(),
+        }
+    }
+}
+
+impl From<RouteMonitoringMessage> for BmpV4RouteMonitoringMessage {
+    fn from(value: RouteMonitoringMessage) -> Self {
+        Self {
+            peer_header: value.peer_header,
+            tlvs: vec![],
+            update_message: value.update_message,
+        }
+    }
+}
+
+/// A TLV of a [`BmpV4RouteMonitoringMessage`], the index refers to the NLRI
+/// of the BGP Update PDU the TLV applies to, zero when it applies to all of
+/// them
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct BmpV4RouteMonitoringTlv {
+    index: u16,
+    value: BmpV4RouteMonitoringTlvValue,
+}
+
+impl BmpV4RouteMonitoringTlv {
+    pub const fn new(index: u16, value: BmpV4RouteMonitoringTlvValue) -> Self {
+        Self { index, value }
+    }
+
+    pub const fn index(&self) -> u16 {
+        self.index
+    }
+
+    pub const fn value(&self) -> &BmpV4RouteMonitoringTlvValue {
+        &self.value
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub enum BmpV4RouteMonitoringTlvValue {
+    VrfTableName(String),
+    /// Indexes of the NLRI grouped under the index of the TLV
+    Group(Vec<u16>),
+    /// BGP capability that is needed to parse the BGP Update PDU, e.g.,
+    /// ADD-PATH, so the message can be parsed without the Peer Up state
+    StatelessParsing(BgpCapability),
+    PathMarking(PathMarking),
+    Unknown {
+        code: u16,
+        value: Vec<u8>,
+    },
+}
+
+impl BmpV4RouteMonitoringTlvValue {
+    /// Type of the TLV, the undefined code of unknown TLVs is returned as
+    /// error
+    pub const fn get_type(&self) -> Result<BmpV4RouteMonitoringTlvType, u16> {
+        match self {
+            Self::VrfTableName(_) => Ok(BmpV4RouteMonitoringTlvType::VrfTableName),
+            Self::Group(_) => Ok(BmpV4RouteMonitoringTlvType::Group),
+            Self::StatelessParsing(_) => Ok(BmpV4RouteMonitoringTlvType::StatelessParsing),
+            Self::PathMarking(_) => Ok(BmpV4RouteMonitoringTlvType::PathMarking),
+            Self::Unknown { code, .. } => Err(*code),
+        }
+    }
+}
+
+/// Status of a path as marked by the router, see
+/// [draft-ietf-grow-bmp-path-marking-tlv](https://datatracker.ietf.org/doc/html/draft-ietf-grow-bmp-path-marking-tlv)
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub enum PathStatus {
+    Invalid = 0x00000001,
+    Best = 0x00000002,
+    NonSelected = 0x00000004,
+    Primary = 0x00000008,
+    Backup = 0x00000010,
+    NonInstalled = 0x00000020,
+    BestExternal = 0x00000040,
+    AddPath = 0x00000080,
+    FilteredInInboundPolicy = 0x00000100,
+    FilteredInOutboundPolicy = 0x00000200,
+    InvalidRov = 0x00000400,
+    Stale = 0x00000800,
+    Suppressed = 0x00001000,
+}
+
+impl PathStatus {
+    const ALL: [PathStatus; 13] = [
+        Self::Invalid,
+        Self::Best,
+        Self::NonSelected,
+        Self::Primary,
+        Self::Backup,
+        Self::NonInstalled,
+        Self::BestExternal,
+        Self::AddPath,
+        Self::FilteredInInboundPolicy,
+        Self::FilteredInOutboundPolicy,
+        Self::InvalidRov,
+        Self::Stale,
+        Self::Suppressed,
+    ];
+}
+
+/// Path Marking TLV value: the status bits of the path and an optional
+/// reason code
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct PathMarking {
+    path_status: u32,
+    reason_code: Option<u16>,
+}
+
+impl PathMarking {
+    pub const fn new(path_status: u32, reason_code: Option<u16>) -> Self {
+        Self {
+            path_status,
+            reason_code,
+        }
+    }
+
+    /// Raw status bits, including the ones not defined in [`PathStatus`]
+    pub const fn path_status(&self) -> u32 {
+        self.path_status
+    }
+
+    pub const fn reason_code(&self) -> Option<u16> {
+        self.reason_code
+    }
+
+    pub const fn has_status(&self, status: PathStatus) -> bool {
+        self.path_status & status as u32 != 0
+    }
+
+    /// The defined statuses that are set
+    pub fn statuses(&self) -> impl Iterator<Item = PathStatus> + '_ {
+        PathStatus::ALL
+            .into_iter()
+            .filter(|status| self.has_status(*status))
+    }
+}
@@ -21,8 +21,11 @@ use chrono::TimeZone;
 
 use std::{collections::HashMap, net::Ipv6Addr, string::FromUtf8Error};
 
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+
 use netgauze_bgp_pkt::wire::deserializer::{
-    nlri::RouteDistinguisherParsingError, BgpMessageParsingError, BgpParsingContext,
+    nlri::RouteDistinguisherParsingError, AddPathDirection, BgpMessageParsingError,
+    BgpParsingContext,
 };
 use netgauze_iana::address_family::{
     AddressFamily, InvalidAddressType, SubsequentAddressFamily, UndefinedAddressFamily,
@@ -35,32 +38,154 @@ use nom::{
 };
 
 use netgauze_parse_utils::{
-    parse_into_located, parse_into_located_one_input, parse_till_empty_into_located,
-    ErrorKindSerdeDeref, ReadablePdu, ReadablePduWithOneInput, Span,
+    parse_into_located, parse_into_located_one_input, parse_till_empty_into_with_one_input_located,
+    ReadablePdu, ReadablePduWithOneInput, Span, StringDecodingPolicy,
 };
 use netgauze_serde_macros::LocatedError;
 
-use crate::{iana::*, *};
+use crate::{
+    iana::*, v4::BmpV4MessageValue, wire::deserializer::v4::BmpV4MessageValueParsingError, *,
+};
+
+pub mod v4;
+
+/// BMP parsing context, keeps track of the BGP parsing context of each peer
+/// monitored in a BMP session.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BmpParsingContext {
+    peer_key_strategy: PeerKeyStrategy,
+    string_decoding_policy: StringDecodingPolicy,
+    relax_markers: bool,
+    #[cfg_attr(feature = "serde", serde(with = "peers_as_list"))]
+    peers: HashMap<PeerKey, BgpParsingContext>,
+}
+
+/// Serialize the peers as a list of pairs, since formats such as JSON only
+/// allow string keys in maps
+#[cfg(feature = "serde")]
+mod peers_as_list {
+    use std::collections::HashMap;
+
+    use netgauze_bgp_pkt::wire::deserializer::BgpParsingContext;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::PeerKey;
+
+    pub fn serialize<S: Serializer>(
+        peers: &HashMap<PeerKey, BgpParsingContext>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(peers.iter())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<PeerKey, BgpParsingContext>, D::Error> {
+        Ok(
+            Vec::<(PeerKey, BgpParsingContext)>::deserialize(deserializer)?
+                .into_iter()
+                .collect(),
+        )
+    }
+}
+
+impl BmpParsingContext {
+    pub fn new(peer_key_strategy: PeerKeyStrategy) -> Self {
+        Self {
+            peer_key_strategy,
+            string_decoding_policy: StringDecodingPolicy::default(),
+            relax_markers: false,
+            peers: HashMap::new(),
+        }
+    }
+
+    /// How the string TLVs of Initiation, Peer Up, Peer Down and Termination
+    /// messages that are not valid UTF-8 are decoded
+    pub const fn string_decoding_policy(&self) -> StringDecodingPolicy {
+        self.string_decoding_policy
+    }
+
+    pub fn set_string_decoding_policy(&mut self, string_decoding_policy: StringDecodingPolicy) {
+        self.string_decoding_policy = string_decoding_policy;
+    }
+
+    /// Parse embedded BGP messages with an invalid or omitted marker, see
+    /// [`BgpParsingContext::relax_markers`]. The anomalies are recorded in the
+    /// parsing errors of the peer's BGP parsing context.
+    pub const fn relax_markers(&self) -> bool {
+        self.relax_markers
+    }
+
+    pub fn set_relax_markers(&mut self, relax_markers: bool) {
+        self.relax_markers = relax_markers;
+    }
+
+    pub const fn peer_key_strategy(&self) -> PeerKeyStrategy {
+        self.peer_key_strategy
+    }
+
+    /// Change how peers are identified, the contexts of already known peers
+    /// are dropped since they're keyed by the old strategy.
+    pub fn set_peer_key_strategy(&mut self, peer_key_strategy: PeerKeyStrategy) {
+        self.peer_key_strategy = peer_key_strategy;
+        self.peers.clear();
+    }
+
+    /// Key of the peer in this context according to the [`PeerKeyStrategy`]
+    pub const fn peer_key(&self, peer_header: &PeerHeader) -> PeerKey {
+        self.peer_key_strategy
+            .apply(PeerKey::from_peer_header(peer_header))
+    }
+
+    pub const fn peers(&self) -> &HashMap<PeerKey, BgpParsingContext> {
+        &self.peers
+    }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+    pub fn peers_mut(&mut self) -> &mut HashMap<PeerKey, BgpParsingContext> {
+        &mut self.peers
+    }
+
+    /// BGP parsing context of the peer, created if it's not known yet, and
+    /// updated with the settings of the peer header and this context
+    fn peer_context_mut(&mut self, peer_header: &PeerHeader) -> &mut BgpParsingContext {
+        let peer_key = self.peer_key(peer_header);
+        let bgp_ctx = self.peers.entry(peer_key).or_default();
+        bgp_ctx.set_asn4(peer_header.is_asn4());
+        bgp_ctx.set_add_path_direction(if peer_header.is_adj_rib_out() {
+            AddPathDirection::Send
+        } else {
+            AddPathDirection::Receive
+        });
+        bgp_ctx.set_string_decoding_policy(self.string_decoding_policy);
+        bgp_ctx.set_relax_markers(self.relax_markers);
+        bgp_ctx
+    }
+}
+
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BmpMessageParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedBmpVersion(#[from_external] UndefinedBmpVersion),
     InvalidBmpLength(u32),
     BmpMessageValueError(#[from_located(module = "self")] BmpMessageValueParsingError),
+    BmpV4MessageValueError(
+        #[from_located(module = "crate::wire::deserializer::v4")] BmpV4MessageValueParsingError,
+    ),
 }
 
-impl<'a>
-    ReadablePduWithOneInput<
-        'a,
-        &mut HashMap<PeerKey, BgpParsingContext>,
-        LocatedBmpMessageParsingError<'a>,
-    > for BmpMessage
+impl<'a> ReadablePduWithOneInput<'a, &mut BmpParsingContext, LocatedBmpMessageParsingError<'a>>
+    for BmpMessage
 {
     fn from_wire(
         buf: Span<'a>,
-        ctx: &mut HashMap<PeerKey, BgpParsingContext>,
+        ctx: &mut BmpParsingContext,
     ) -> IResult<Span<'a>, Self, LocatedBmpMessageParsingError<'a>> {
         let (buf, version) = nom::combinator::map_res(be_u8, BmpVersion::try_from)(buf)?;
         let input = buf;
@@ -79,6 +204,10 @@ impl<'a>
                 let (buf, value) = parse_into_located_one_input(buf, ctx)?;
                 (buf, BmpMessage::V3(value))
             }
+            BmpVersion::Version4 => {
+                let (buf, value): (_, BmpV4MessageValue) = parse_into_located_one_input(buf, ctx)?;
+                (buf, BmpMessage::V4(value))
+            }
         };
         // Make sure bmp message is fully parsed according to it's length
         if !buf.is_empty() {
@@ -91,10 +220,15 @@ impl<'a>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BmpMessageValueParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedBmpMessageType(#[from_external] UndefinedBmpMessageType),
     RouteMonitoringMessageError(
         #[from_located(module = "self")] RouteMonitoringMessageParsingError,
@@ -107,22 +241,21 @@ pub enum BmpMessageValueParsingError {
         #[from_located(module = "self")] PeerDownNotificationMessageParsingError,
     ),
     RouteMirroringMessageError(#[from_located(module = "self")] RouteMirroringMessageParsingError),
+    RoutePolicyAndAttributeTraceMessageError(
+        #[from_located(module = "self")] RoutePolicyAndAttributeTraceMessageParsingError,
+    ),
     TerminationMessageError(#[from_located(module = "self")] TerminationMessageParsingError),
     StatisticsReportMessageError(
         #[from_located(module = "self")] StatisticsReportMessageParsingError,
     ),
 }
 
-impl<'a>
-    ReadablePduWithOneInput<
-        'a,
-        &mut HashMap<PeerKey, BgpParsingContext>,
-        LocatedBmpMessageValueParsingError<'a>,
-    > for BmpMessageValue
+impl<'a> ReadablePduWithOneInput<'a, &mut BmpParsingContext, LocatedBmpMessageValueParsingError<'a>>
+    for BmpMessageValue
 {
     fn from_wire(
         buf: Span<'a>,
-        ctx: &mut HashMap<PeerKey, BgpParsingContext>,
+        ctx: &mut BmpParsingContext,
     ) -> IResult<Span<'a>, Self, LocatedBmpMessageValueParsingError<'a>> {
         let (buf, msg_type) = nom::combinator::map_res(be_u8, BmpMessageType::try_from)(buf)?;
         let (buf, msg) = match msg_type {
@@ -143,17 +276,21 @@ impl<'a>
                 (buf, BmpMessageValue::PeerUpNotification(value))
             }
             BmpMessageType::Initiation => {
-                let (buf, init) = parse_into_located(buf)?;
+                let (buf, init) = parse_into_located_one_input(buf, ctx.string_decoding_policy())?;
                 (buf, BmpMessageValue::Initiation(init))
             }
             BmpMessageType::Termination => {
-                let (buf, init) = parse_into_located(buf)?;
+                let (buf, init) = parse_into_located_one_input(buf, ctx.string_decoding_policy())?;
                 (buf, BmpMessageValue::Termination(init))
             }
             BmpMessageType::RouteMirroring => {
                 let (buf, init) = parse_into_located_one_input(buf, ctx)?;
                 (buf, BmpMessageValue::RouteMirroring(init))
             }
+            BmpMessageType::RoutePolicyAndAttributeTrace => {
+                let (buf, value) = parse_into_located(buf)?;
+                (buf, BmpMessageValue::RoutePolicyAndAttributeTrace(value))
+            }
             BmpMessageType::Experimental251 => {
                 (buf, BmpMessageValue::Experimental252(buf.to_vec()))
             }
@@ -171,7 +308,24 @@ impl<'a>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+/// Parse a string of `length` bytes, `None` is returned when it's not valid
+/// UTF-8 and the raw bytes are to be kept according to the policy
+#[inline]
+fn parse_string<'a, E>(
+    buf: Span<'a>,
+    length: u16,
+    string_decoding_policy: StringDecodingPolicy,
+) -> IResult<Span<'a>, Option<String>, E>
+where
+    E: nom::error::ParseError<Span<'a>> + FromExternalError<Span<'a>, FromUtf8Error>,
+{
+    nom::combinator::map_res(nom::bytes::complete::take(length), |x: Span<'_>| {
+        string_decoding_policy.decode(x.fragment())
+    })(buf)
+}
+
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum InitiationMessageParsingError {
     InitiationInformationError(#[from_located(module = "self")] InitiationInformationParsingError),
 }
@@ -180,15 +334,32 @@ impl<'a> ReadablePdu<'a, LocatedInitiationMessageParsingError<'a>> for Initiatio
     fn from_wire(
         buf: Span<'a>,
     ) -> IResult<Span<'a>, Self, LocatedInitiationMessageParsingError<'a>> {
-        let (buf, information) = parse_till_empty_into_located(buf)?;
+        <Self as ReadablePduWithOneInput<_, _>>::from_wire(buf, StringDecodingPolicy::Strict)
+    }
+}
+
+impl<'a> ReadablePduWithOneInput<'a, StringDecodingPolicy, LocatedInitiationMessageParsingError<'a>>
+    for InitiationMessage
+{
+    fn from_wire(
+        buf: Span<'a>,
+        string_decoding_policy: StringDecodingPolicy,
+    ) -> IResult<Span<'a>, Self, LocatedInitiationMessageParsingError<'a>> {
+        let (buf, information) =
+            parse_till_empty_into_with_one_input_located(buf, string_decoding_policy)?;
         Ok((buf, InitiationMessage::new(information)))
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum InitiationInformationParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedType(#[from_external] UndefinedInitiationInformationTlvType),
     FromUtf8Error(String),
 }
@@ -207,46 +378,62 @@ impl<'a> FromExternalError<Span<'a>, FromUtf8Error>
 impl<'a> ReadablePdu<'a, LocatedInitiationInformationParsingError<'a>> for InitiationInformation {
     fn from_wire(
         buf: Span<'a>,
+    ) -> IResult<Span<'a>, Self, LocatedInitiationInformationParsingError<'a>> {
+        <Self as ReadablePduWithOneInput<_, _>>::from_wire(buf, StringDecodingPolicy::Strict)
+    }
+}
+
+impl<'a>
+    ReadablePduWithOneInput<'a, StringDecodingPolicy, LocatedInitiationInformationParsingError<'a>>
+    for InitiationInformation
+{
+    fn from_wire(
+        buf: Span<'a>,
+        string_decoding_policy: StringDecodingPolicy,
     ) -> IResult<Span<'a>, Self, LocatedInitiationInformationParsingError<'a>> {
         let (buf, tlv_type) =
             nom::combinator::map_res(be_u16, InitiationInformationTlvType::try_from)(buf)?;
         let (buf, length) = be_u16(buf)?;
         let (reminder, buf) = nom::bytes::complete::take(length)(buf)?;
+        let non_utf8 = || InitiationInformation::NonUtf8String {
+            tlv_type,
+            value: buf.to_vec(),
+        };
         match tlv_type {
             InitiationInformationTlvType::String => {
-                let (_, str) =
-                    nom::combinator::map_res(nom::bytes::complete::take(length), |x: Span<'_>| {
-                        String::from_utf8(x.to_vec())
-                    })(buf)?;
-                Ok((reminder, InitiationInformation::String(str)))
+                let (_, str) = parse_string(buf, length, string_decoding_policy)?;
+                Ok((
+                    reminder,
+                    str.map_or_else(non_utf8, InitiationInformation::String),
+                ))
             }
             InitiationInformationTlvType::SystemDescription => {
-                let (_, str) =
-                    nom::combinator::map_res(nom::bytes::complete::take(length), |x: Span<'_>| {
-                        String::from_utf8(x.to_vec())
-                    })(buf)?;
-                Ok((reminder, InitiationInformation::SystemDescription(str)))
+                let (_, str) = parse_string(buf, length, string_decoding_policy)?;
+                Ok((
+                    reminder,
+                    str.map_or_else(non_utf8, InitiationInformation::SystemDescription),
+                ))
             }
             InitiationInformationTlvType::SystemName => {
-                let (_, str) =
-                    nom::combinator::map_res(nom::bytes::complete::take(length), |x: Span<'_>| {
-                        String::from_utf8(x.to_vec())
-                    })(buf)?;
-                Ok((reminder, InitiationInformation::SystemName(str)))
+                let (_, str) = parse_string(buf, length, string_decoding_policy)?;
+                Ok((
+                    reminder,
+                    str.map_or_else(non_utf8, InitiationInformation::SystemName),
+                ))
             }
             InitiationInformationTlvType::VrfTableName => {
-                let (_, str) =
-                    nom::combinator::map_res(nom::bytes::complete::take(length), |x: Span<'_>| {
-                        String::from_utf8(x.to_vec())
-                    })(buf)?;
-                Ok((reminder, InitiationInformation::VrfTableName(str)))
+                let (_, str) = parse_string(buf, length, string_decoding_policy)?;
+                Ok((
+                    reminder,
+                    str.map_or_else(non_utf8, InitiationInformation::VrfTableName),
+                ))
             }
             InitiationInformationTlvType::AdminLabel => {
-                let (_, str) =
-                    nom::combinator::map_res(nom::bytes::complete::take(length), |x: Span<'_>| {
-                        String::from_utf8(x.to_vec())
-                    })(buf)?;
-                Ok((reminder, InitiationInformation::AdminLabel(str)))
+                let (_, str) = parse_string(buf, length, string_decoding_policy)?;
+                Ok((
+                    reminder,
+                    str.map_or_else(non_utf8, InitiationInformation::AdminLabel),
+                ))
             }
             InitiationInformationTlvType::Experimental65531 => Ok((
                 reminder,
@@ -268,7 +455,8 @@ impl<'a> ReadablePdu<'a, LocatedInitiationInformationParsingError<'a>> for Initi
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RouteMonitoringMessageParsingError {
     RouteMonitoringMessageError(RouteMonitoringMessageError),
     PeerHeaderError(#[from_located(module = "self")] PeerHeaderParsingError),
@@ -280,18 +468,16 @@ pub enum RouteMonitoringMessageParsingError {
 impl<'a>
     ReadablePduWithOneInput<
         'a,
-        &mut HashMap<PeerKey, BgpParsingContext>,
+        &mut BmpParsingContext,
         LocatedRouteMonitoringMessageParsingError<'a>,
     > for RouteMonitoringMessage
 {
     fn from_wire(
         buf: Span<'a>,
-        ctx: &mut HashMap<PeerKey, BgpParsingContext>,
+        ctx: &mut BmpParsingContext,
     ) -> IResult<Span<'a>, Self, LocatedRouteMonitoringMessageParsingError<'a>> {
         let (buf, peer_header): (Span<'_>, PeerHeader) = parse_into_located(buf)?;
-        let peer_key = PeerKey::from_peer_header(&peer_header);
-        let bgp_ctx = ctx.entry(peer_key).or_default();
-        bgp_ctx.set_asn4(peer_header.is_asn4());
+        let bgp_ctx = ctx.peer_context_mut(&peer_header);
         let input = buf;
         let (buf, update_message): (Span<'_>, BgpMessage) =
             parse_into_located_one_input(buf, bgp_ctx)?;
@@ -319,10 +505,15 @@ impl<'a>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BmpPeerTypeParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedBmpPeerTypeCode(#[from_external] UndefinedBmpPeerTypeCode),
 }
 
@@ -365,9 +556,13 @@ impl<'a> ReadablePdu<'a, LocatedBmpPeerTypeParsingError<'a>> for BmpPeerType {
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PeerHeaderParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     BmpPeerTypeError(#[from_located(module = "self")] BmpPeerTypeParsingError),
     RouteDistinguisherError(
@@ -419,9 +614,13 @@ impl<'a> ReadablePdu<'a, LocatedPeerHeaderParsingError<'a>> for PeerHeader {
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PeerUpNotificationMessageParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     PeerUpMessageError(PeerUpNotificationMessageError),
     UnexpectedPeerType(BmpPeerTypeCode),
@@ -456,17 +655,16 @@ const fn check_is_ipv6(peer_type: &BmpPeerType) -> Result<bool, BmpPeerTypeCode>
 impl<'a>
     ReadablePduWithOneInput<
         'a,
-        &mut HashMap<PeerKey, BgpParsingContext>,
+        &mut BmpParsingContext,
         LocatedPeerUpNotificationMessageParsingError<'a>,
     > for PeerUpNotificationMessage
 {
     fn from_wire(
         buf: Span<'a>,
-        ctx: &mut HashMap<PeerKey, BgpParsingContext>,
+        ctx: &mut BmpParsingContext,
     ) -> IResult<Span<'a>, Self, LocatedPeerUpNotificationMessageParsingError<'a>> {
         let input = buf;
         let (buf, peer_header): (Span<'_>, PeerHeader) = parse_into_located(buf)?;
-        let peer_key = PeerKey::from_peer_header(&peer_header);
         let ipv6 = match check_is_ipv6(&peer_header.peer_type) {
             Ok(ipv6) => ipv6,
             Err(code) => {
@@ -499,13 +697,12 @@ impl<'a>
         } else {
             Some(remote_port)
         };
-        let bgp_ctx = ctx.entry(peer_key).or_default();
-        bgp_ctx.set_asn4(peer_header.is_asn4());
+        let bgp_ctx = ctx.peer_context_mut(&peer_header);
         let (buf, sent_message) = parse_into_located_one_input(buf, bgp_ctx)?;
-        let bgp_ctx = ctx.entry(peer_key).or_default();
-        bgp_ctx.set_asn4(peer_header.is_asn4());
+        let bgp_ctx = ctx.peer_context_mut(&peer_header);
         let (buf, received_message) = parse_into_located_one_input(buf, bgp_ctx)?;
-        let (buf, information) = parse_till_empty_into_located(buf)?;
+        let (buf, information) =
+            parse_till_empty_into_with_one_input_located(buf, ctx.string_decoding_policy())?;
         let peer_up_msg = PeerUpNotificationMessage::build(
             peer_header,
             local_address,
@@ -529,7 +726,8 @@ impl<'a>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PeerDownNotificationMessageParsingError {
     PeerDownMessageError(PeerDownNotificationMessageError),
     PeerHeaderError(#[from_located(module = "self")] PeerHeaderParsingError),
@@ -541,19 +739,17 @@ pub enum PeerDownNotificationMessageParsingError {
 impl<'a>
     ReadablePduWithOneInput<
         'a,
-        &mut HashMap<PeerKey, BgpParsingContext>,
+        &mut BmpParsingContext,
         LocatedPeerDownNotificationMessageParsingError<'a>,
     > for PeerDownNotificationMessage
 {
     fn from_wire(
         buf: Span<'a>,
-        ctx: &mut HashMap<PeerKey, BgpParsingContext>,
+        ctx: &mut BmpParsingContext,
     ) -> IResult<Span<'a>, Self, LocatedPeerDownNotificationMessageParsingError<'a>> {
         let input = buf;
         let (buf, peer_header): (Span<'_>, PeerHeader) = parse_into_located(buf)?;
-        let peer_key = PeerKey::from_peer_header(&peer_header);
-        let bgp_ctx = ctx.entry(peer_key).or_default();
-        bgp_ctx.set_asn4(peer_header.is_asn4());
+        let bgp_ctx = ctx.peer_context_mut(&peer_header);
         let (buf, reason) = parse_into_located_one_input(buf, bgp_ctx)?;
         let msg = PeerDownNotificationMessage::build(peer_header, reason);
         match msg {
@@ -570,10 +766,15 @@ impl<'a>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PeerDownNotificationReasonParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedPeerDownReasonCode(#[from_external] UndefinedPeerDownReasonCode),
     BgpMessageError(
         #[from_located(module = "netgauze_bgp_pkt::wire::deserializer")] BgpMessageParsingError,
@@ -623,7 +824,8 @@ impl<'a>
                 Ok((buf, PeerDownNotificationReason::PeerDeConfigured))
             }
             PeerDownReasonCode::LocalSystemClosedTlvDataFollows => {
-                let (buf, information) = parse_into_located(buf)?;
+                let (buf, information) =
+                    parse_into_located_one_input(buf, bgp_ctx.string_decoding_policy())?;
                 Ok((
                     buf,
                     PeerDownNotificationReason::LocalSystemClosedTlvDataFollows(information),
@@ -661,7 +863,8 @@ impl<'a>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RouteMirroringMessageParsingError {
     PeerHeaderError(#[from_located(module = "self")] PeerHeaderParsingError),
     RouteMirroringValueError(#[from_located(module = "self")] RouteMirroringValueParsingError),
@@ -670,18 +873,16 @@ pub enum RouteMirroringMessageParsingError {
 impl<'a>
     ReadablePduWithOneInput<
         'a,
-        &mut HashMap<PeerKey, BgpParsingContext>,
+        &mut BmpParsingContext,
         LocatedRouteMirroringMessageParsingError<'a>,
     > for RouteMirroringMessage
 {
     fn from_wire(
         buf: Span<'a>,
-        ctx: &mut HashMap<PeerKey, BgpParsingContext>,
+        ctx: &mut BmpParsingContext,
     ) -> IResult<Span<'a>, Self, LocatedRouteMirroringMessageParsingError<'a>> {
         let (mut buf, peer_header): (Span<'_>, PeerHeader) = parse_into_located(buf)?;
-        let peer_key = PeerKey::from_peer_header(&peer_header);
-        let bgp_ctx = ctx.entry(peer_key).or_default();
-        bgp_ctx.set_asn4(peer_header.is_asn4());
+        let bgp_ctx = ctx.peer_context_mut(&peer_header);
         let mut mirrored = Vec::new();
         while !buf.is_empty() {
             let (tmp, element) = parse_into_located_one_input(buf, &mut *bgp_ctx)?;
@@ -692,11 +893,17 @@ impl<'a>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RouteMirroringValueParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedRouteMirroringTlvType(#[from_external] UndefinedRouteMirroringTlvType),
+    #[category(UnknownCode)]
     UndefinedRouteMirroringInformation(#[from_external] UndefinedRouteMirroringInformation),
     BgpMessageError(
         #[from_located(module = "netgauze_bgp_pkt::wire::deserializer")] BgpMessageParsingError,
@@ -760,7 +967,167 @@ impl<'a>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RoutePolicyAndAttributeTraceMessageParsingError {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
+    NomError(#[from_nom] ErrorKind),
+    PeerHeaderError(#[from_located(module = "self")] PeerHeaderParsingError),
+    RouteDistinguisherError(
+        #[from_located(module = "netgauze_bgp_pkt::wire::deserializer::nlri")]
+        RouteDistinguisherParsingError,
+    ),
+    InvalidPrefixLength(u8),
+    FromUtf8Error(String),
+    RoutePolicyAndAttributeTraceEventError(
+        #[from_located(module = "self")] RoutePolicyAndAttributeTraceEventParsingError,
+    ),
+}
+
+impl<'a> FromExternalError<Span<'a>, FromUtf8Error>
+    for LocatedRoutePolicyAndAttributeTraceMessageParsingError<'a>
+{
+    fn from_external_error(input: Span<'a>, _kind: ErrorKind, error: FromUtf8Error) -> Self {
+        LocatedRoutePolicyAndAttributeTraceMessageParsingError::new(
+            input,
+            RoutePolicyAndAttributeTraceMessageParsingError::FromUtf8Error(error.to_string()),
+        )
+    }
+}
+
+impl<'a> ReadablePdu<'a, LocatedRoutePolicyAndAttributeTraceMessageParsingError<'a>>
+    for RoutePolicyAndAttributeTraceMessage
+{
+    fn from_wire(
+        buf: Span<'a>,
+    ) -> IResult<Span<'a>, Self, LocatedRoutePolicyAndAttributeTraceMessageParsingError<'a>> {
+        let (buf, peer_header) = parse_into_located(buf)?;
+        let (buf, flags) = be_u8(buf)?;
+        let (buf, rd) = parse_into_located(buf)?;
+        let input = buf;
+        let (buf, prefix_len) = be_u8(buf)?;
+        let (buf, prefix) = if flags & ROUTE_POLICY_TRACE_FLAGS_IS_IPV6 == 0 {
+            let (buf, address) = be_u32(buf)?;
+            let prefix = Ipv4Net::new(Ipv4Addr::from(address), prefix_len).map(IpNet::V4);
+            (buf, prefix)
+        } else {
+            let (buf, address) = be_u128(buf)?;
+            let prefix = Ipv6Net::new(Ipv6Addr::from(address), prefix_len).map(IpNet::V6);
+            (buf, prefix)
+        };
+        let prefix = match prefix {
+            Ok(prefix) => prefix,
+            Err(_) => {
+                return Err(nom::Err::Error(
+                    LocatedRoutePolicyAndAttributeTraceMessageParsingError::new(
+                        input,
+                        RoutePolicyAndAttributeTraceMessageParsingError::InvalidPrefixLength(
+                            prefix_len,
+                        ),
+                    ),
+                ))
+            }
+        };
+        let (buf, route_origin) =
+            nom::combinator::map_res(nom::bytes::complete::take(16usize), |x: Span<'_>| {
+                let len = x.iter().position(|b| *b == 0).unwrap_or(x.len());
+                String::from_utf8(x[..len].to_vec())
+            })(buf)?;
+        let (buf, event_count) = be_u8(buf)?;
+        let (buf, mut events_buf) = nom::multi::length_data(be_u16)(buf)?;
+        let mut events = Vec::with_capacity(event_count as usize);
+        while !events_buf.is_empty() {
+            let (tmp, event) = parse_into_located(events_buf)?;
+            events.push(event);
+            events_buf = tmp;
+        }
+        Ok((
+            buf,
+            RoutePolicyAndAttributeTraceMessage::new(peer_header, rd, prefix, route_origin, events),
+        ))
+    }
+}
+
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RoutePolicyAndAttributeTraceEventParsingError {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
+    NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
+    UndefinedAddressFamily(#[from_external] UndefinedAddressFamily),
+    #[category(UnknownCode)]
+    UndefinedSubsequentAddressFamily(#[from_external] UndefinedSubsequentAddressFamily),
+    InvalidAddressType(InvalidAddressType),
+    InvalidTime(u32, u32),
+}
+
+impl<'a> ReadablePdu<'a, LocatedRoutePolicyAndAttributeTraceEventParsingError<'a>>
+    for RoutePolicyAndAttributeTraceEvent
+{
+    fn from_wire(
+        buf: Span<'a>,
+    ) -> IResult<Span<'a>, Self, LocatedRoutePolicyAndAttributeTraceEventParsingError<'a>> {
+        let (reminder, buf) = nom::multi::length_data(be_u16)(buf)?;
+        let (buf, index) = be_u8(buf)?;
+        let input = buf;
+        let (buf, timestamp_secs) = be_u32(buf)?;
+        let (buf, timestamp_micro) = be_u32(buf)?;
+        let timestamp = if timestamp_secs != 0 || timestamp_micro != 0 {
+            match Utc.timestamp_opt(timestamp_secs.into(), timestamp_micro.saturating_mul(1_000)) {
+                LocalResult::Single(time) => Some(time),
+                _ => {
+                    return Err(nom::Err::Error(
+                        LocatedRoutePolicyAndAttributeTraceEventParsingError::new(
+                            input,
+                            RoutePolicyAndAttributeTraceEventParsingError::InvalidTime(
+                                timestamp_secs,
+                                timestamp_micro,
+                            ),
+                        ),
+                    ))
+                }
+            }
+        } else {
+            None
+        };
+        let (buf, path_id) = be_u32(buf)?;
+        let input = buf;
+        let (buf, afi) = nom::combinator::map_res(be_u16, AddressFamily::try_from)(buf)?;
+        let (mut buf, safi) =
+            nom::combinator::map_res(be_u8, SubsequentAddressFamily::try_from)(buf)?;
+        let address_type = match AddressType::from_afi_safi(afi, safi) {
+            Ok(address_type) => address_type,
+            Err(err) => {
+                return Err(nom::Err::Error(
+                    LocatedRoutePolicyAndAttributeTraceEventParsingError::new(
+                        input,
+                        RoutePolicyAndAttributeTraceEventParsingError::InvalidAddressType(err),
+                    ),
+                ))
+            }
+        };
+        let mut tlvs = Vec::new();
+        while !buf.is_empty() {
+            let (tmp, code) = be_u16(buf)?;
+            let (tmp, value) = nom::multi::length_data(be_u16)(tmp)?;
+            tlvs.push(RoutePolicyAndAttributeTraceTlv::new(code, value.to_vec()));
+            buf = tmp;
+        }
+        Ok((
+            reminder,
+            RoutePolicyAndAttributeTraceEvent::new(index, timestamp, path_id, address_type, tlvs),
+        ))
+    }
+}
+
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TerminationMessageParsingError {
     PeerHeaderError(#[from_located(module = "self")] PeerHeaderParsingError),
     TerminationInformationError(
@@ -771,18 +1138,37 @@ pub enum TerminationMessageParsingError {
 impl<'a> ReadablePdu<'a, LocatedTerminationMessageParsingError<'a>> for TerminationMessage {
     fn from_wire(
         buf: Span<'a>,
+    ) -> IResult<Span<'a>, Self, LocatedTerminationMessageParsingError<'a>> {
+        <Self as ReadablePduWithOneInput<_, _>>::from_wire(buf, StringDecodingPolicy::Strict)
+    }
+}
+
+impl<'a>
+    ReadablePduWithOneInput<'a, StringDecodingPolicy, LocatedTerminationMessageParsingError<'a>>
+    for TerminationMessage
+{
+    fn from_wire(
+        buf: Span<'a>,
+        string_decoding_policy: StringDecodingPolicy,
     ) -> IResult<Span<'a>, Self, LocatedTerminationMessageParsingError<'a>> {
         let (buf, peer_header) = parse_into_located(buf)?;
-        let (buf, information) = parse_till_empty_into_located(buf)?;
+        let (buf, information) =
+            parse_till_empty_into_with_one_input_located(buf, string_decoding_policy)?;
         Ok((buf, TerminationMessage::new(peer_header, information)))
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TerminationInformationParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedTerminationInformationTlvType(#[from_external] UndefinedTerminationInformationTlvType),
+    #[category(UnknownCode)]
     UndefinedPeerTerminationCode(#[from_external] UndefinedPeerTerminationCode),
     FromUtf8Error(String),
 }
@@ -801,6 +1187,18 @@ impl<'a> FromExternalError<Span<'a>, FromUtf8Error>
 impl<'a> ReadablePdu<'a, LocatedTerminationInformationParsingError<'a>> for TerminationInformation {
     fn from_wire(
         buf: Span<'a>,
+    ) -> IResult<Span<'a>, Self, LocatedTerminationInformationParsingError<'a>> {
+        <Self as ReadablePduWithOneInput<_, _>>::from_wire(buf, StringDecodingPolicy::Strict)
+    }
+}
+
+impl<'a>
+    ReadablePduWithOneInput<'a, StringDecodingPolicy, LocatedTerminationInformationParsingError<'a>>
+    for TerminationInformation
+{
+    fn from_wire(
+        buf: Span<'a>,
+        string_decoding_policy: StringDecodingPolicy,
     ) -> IResult<Span<'a>, Self, LocatedTerminationInformationParsingError<'a>> {
         let (buf, code) =
             nom::combinator::map_res(be_u16, TerminationInformationTlvType::try_from)(buf)?;
@@ -808,11 +1206,13 @@ impl<'a> ReadablePdu<'a, LocatedTerminationInformationParsingError<'a>> for Term
         let (reminder, buf) = nom::multi::length_data(be_u16)(buf)?;
         let (buf, value) = match code {
             TerminationInformationTlvType::String => {
-                let (buf, str) =
-                    nom::combinator::map_res(nom::bytes::complete::take(length), |x: Span<'_>| {
-                        String::from_utf8(x.to_vec())
-                    })(buf)?;
-                (buf, TerminationInformation::String(str))
+                let raw = buf;
+                let (buf, str) = parse_string(buf, length, string_decoding_policy)?;
+                let value = str.map_or_else(
+                    || TerminationInformation::NonUtf8String(raw.to_vec()),
+                    TerminationInformation::String,
+                );
+                (buf, value)
             }
             TerminationInformationTlvType::Reason => {
                 let (buf, reason) =
@@ -852,9 +1252,13 @@ impl<'a> ReadablePdu<'a, LocatedTerminationInformationParsingError<'a>> for Term
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum StatisticsReportMessageParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     PeerHeaderError(#[from_located(module = "self")] PeerHeaderParsingError),
     StatisticsCounterError(#[from_located(module = "self")] StatisticsCounterParsingError),
@@ -878,11 +1282,17 @@ impl<'a> ReadablePdu<'a, LocatedStatisticsReportMessageParsingError<'a>>
     }
 }
 
-#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum StatisticsCounterParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
     UndefinedAddressFamily(#[from_external] UndefinedAddressFamily),
+    #[category(UnknownCode)]
     UndefinedSubsequentAddressFamily(#[from_external] UndefinedSubsequentAddressFamily),
     InvalidAddressType(InvalidAddressType),
 }
@@ -0,0 +1,321 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deserializer for the BMP version 4 messages
+
+use std::string::FromUtf8Error;
+
+use netgauze_bgp_pkt::{
+    capabilities::BgpCapability,
+    wire::deserializer::{
+        capabilities::BgpCapabilityParsingError, BgpMessageParsingError, BgpParsingContext,
+    },
+    BgpMessage,
+};
+use netgauze_parse_utils::{
+    parse_into_located, parse_into_located_one_input, ReadablePdu, ReadablePduWithOneInput, Span,
+};
+use netgauze_serde_macros::LocatedError;
+use nom::{
+    error::ErrorKind,
+    number::complete::{be_u16, be_u32, be_u8},
+    IResult,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    iana::{BmpMessageType, BmpV4RouteMonitoringTlvType, UndefinedBmpMessageType},
+    v4::*,
+    wire::deserializer::{BmpMessageValueParsingError, BmpParsingContext, PeerHeaderParsingError},
+    BmpMessageValue, PeerHeader, RouteMonitoringMessageError,
+};
+
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BmpV4MessageValueParsingError {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
+    NomError(#[from_nom] ErrorKind),
+    #[category(UnknownCode)]
+    UndefinedBmpMessageType(#[from_external] UndefinedBmpMessageType),
+    RouteMonitoringMessageError(
+        #[from_located(module = "self")] BmpV4RouteMonitoringMessageParsingError,
+    ),
+    /// Errors of the messages that are the same as in version 3
+    BmpMessageValueError(
+        #[from_located(module = "crate::wire::deserializer")] BmpMessageValueParsingError,
+    ),
+}
+
+impl<'a>
+    ReadablePduWithOneInput<'a, &mut BmpParsingContext, LocatedBmpV4MessageValueParsingError<'a>>
+    for BmpV4MessageValue
+{
+    fn from_wire(
+        buf: Span<'a>,
+        ctx: &mut BmpParsingContext,
+    ) -> IResult<Span<'a>, Self, LocatedBmpV4MessageValueParsingError<'a>> {
+        let (_, msg_type) = nom::combinator::map_res(be_u8, BmpMessageType::try_from)(buf)?;
+        if msg_type == BmpMessageType::RouteMonitoring {
+            let (buf, _) = be_u8(buf)?;
+            let (buf, value) = parse_into_located_one_input(buf, ctx)?;
+            return Ok((buf, BmpV4MessageValue::RouteMonitoring(Box::new(value))));
+        }
+        let (buf, value): (Span<'_>, BmpMessageValue) = parse_into_located_one_input(buf, ctx)?;
+        Ok((buf, value.into()))
+    }
+}
+
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BmpV4RouteMonitoringMessageParsingError {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
+    NomError(#[from_nom] ErrorKind),
+    RouteMonitoringMessageError(RouteMonitoringMessageError),
+    PeerHeaderError(#[from_located(module = "crate::wire::deserializer")] PeerHeaderParsingError),
+    TlvError(#[from_located(module = "self")] BmpV4RouteMonitoringTlvParsingError),
+    BgpMessageError(
+        #[from_located(module = "netgauze_bgp_pkt::wire::deserializer")] BgpMessageParsingError,
+    ),
+    MissingBgpUpdatePdu,
+    DuplicateBgpUpdatePdu,
+}
+
+impl<'a>
+    ReadablePduWithOneInput<
+        'a,
+        &mut BmpParsingContext,
+        LocatedBmpV4RouteMonitoringMessageParsingError<'a>,
+    > for BmpV4RouteMonitoringMessage
+{
+    fn from_wire(
+        buf: Span<'a>,
+        ctx: &mut BmpParsingContext,
+    ) -> IResult<Span<'a>, Self, LocatedBmpV4RouteMonitoringMessageParsingError<'a>> {
+        let (mut buf, peer_header): (Span<'_>, PeerHeader) = parse_into_located(buf)?;
+        let input = buf;
+        let mut tlvs = vec![];
+        let mut pdu = None;
+        while !buf.is_empty() {
+            let (_, code) = be_u16(buf)?;
+            if code == BmpV4RouteMonitoringTlvType::BgpUpdatePdu as u16 {
+                let tlv_input = buf;
+                let (tmp, _) = be_u16(buf)?;
+                let (tmp, length) = be_u16(tmp)?;
+                let (tmp, _index) = be_u16(tmp)?;
+                let (tmp, value) = nom::bytes::complete::take(length)(tmp)?;
+                if pdu.is_some() {
+                    return Err(nom::Err::Error(
+                        LocatedBmpV4RouteMonitoringMessageParsingError::new(
+                            tlv_input,
+                            BmpV4RouteMonitoringMessageParsingError::DuplicateBgpUpdatePdu,
+                        ),
+                    ));
+                }
+                pdu = Some(value);
+                buf = tmp;
+            } else {
+                let (tmp, tlv) = parse_into_located(buf)?;
+                tlvs.push(tlv);
+                buf = tmp;
+            }
+        }
+        let pdu = match pdu {
+            Some(pdu) => pdu,
+            None => {
+                return Err(nom::Err::Error(
+                    LocatedBmpV4RouteMonitoringMessageParsingError::new(
+                        input,
+                        BmpV4RouteMonitoringMessageParsingError::MissingBgpUpdatePdu,
+                    ),
+                ))
+            }
+        };
+
+        // The Stateless Parsing TLVs apply only to this message, so they're
+        // applied to a copy of the peer's context
+        let peer_ctx = ctx.peer_context_mut(&peer_header);
+        let mut stateless_ctx;
+        let bgp_ctx = if tlvs.iter().any(|tlv: &BmpV4RouteMonitoringTlv| {
+            matches!(
+                tlv.value(),
+                BmpV4RouteMonitoringTlvValue::StatelessParsing(_)
+            )
+        }) {
+            stateless_ctx = peer_ctx.clone();
+            for tlv in &tlvs {
+                if let BmpV4RouteMonitoringTlvValue::StatelessParsing(capability) = tlv.value() {
+                    apply_stateless_parsing(&mut stateless_ctx, capability);
+                }
+            }
+            &mut stateless_ctx
+        } else {
+            peer_ctx
+        };
+        let (pdu_buf, update_message): (Span<'_>, BgpMessage) =
+            parse_into_located_one_input(pdu, bgp_ctx)?;
+        if !pdu_buf.is_empty() {
+            return Err(nom::Err::Error(
+                LocatedBmpV4RouteMonitoringMessageParsingError::new(
+                    pdu_buf,
+                    BmpV4RouteMonitoringMessageParsingError::NomError(ErrorKind::NonEmpty),
+                ),
+            ));
+        }
+        match BmpV4RouteMonitoringMessage::build(peer_header, tlvs, update_message) {
+            Ok(msg) => Ok((buf, msg)),
+            Err(err) => Err(nom::Err::Error(
+                LocatedBmpV4RouteMonitoringMessageParsingError::new(
+                    pdu,
+                    BmpV4RouteMonitoringMessageParsingError::RouteMonitoringMessageError(err),
+                ),
+            )),
+        }
+    }
+}
+
+/// Update the BGP parsing context with a capability of a Stateless Parsing TLV
+fn apply_stateless_parsing(bgp_ctx: &mut BgpParsingContext, capability: &BgpCapability) {
+    match capability {
+        BgpCapability::FourOctetAs(_) => bgp_ctx.set_asn4(true),
+        BgpCapability::AddPath(add_path) => {
+            for address_family in add_path.address_families() {
+                bgp_ctx
+                    .add_path_mut()
+                    .insert(address_family.address_type(), address_family.receive());
+                bgp_ctx
+                    .add_path_send_mut()
+                    .insert(address_family.address_type(), address_family.send());
+            }
+        }
+        BgpCapability::MultipleLabels(labels) => {
+            for label in labels {
+                bgp_ctx
+                    .multiple_labels_mut()
+                    .insert(label.address_type(), label.count());
+            }
+        }
+        _ => {}
+    }
+}
+
+#[derive(LocatedError, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BmpV4RouteMonitoringTlvParsingError {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
+    NomError(#[from_nom] ErrorKind),
+    FromUtf8Error(String),
+    InvalidGroupLength(u16),
+    InvalidPathMarkingLength(u16),
+    BgpCapabilityError(
+        #[from_located(module = "netgauze_bgp_pkt::wire::deserializer::capabilities")]
+        BgpCapabilityParsingError,
+    ),
+}
+
+impl<'a> nom::error::FromExternalError<Span<'a>, FromUtf8Error>
+    for LocatedBmpV4RouteMonitoringTlvParsingError<'a>
+{
+    fn from_external_error(input: Span<'a>, _kind: ErrorKind, error: FromUtf8Error) -> Self {
+        LocatedBmpV4RouteMonitoringTlvParsingError::new(
+            input,
+            BmpV4RouteMonitoringTlvParsingError::FromUtf8Error(error.to_string()),
+        )
+    }
+}
+
+impl<'a> ReadablePdu<'a, LocatedBmpV4RouteMonitoringTlvParsingError<'a>>
+    for BmpV4RouteMonitoringTlv
+{
+    fn from_wire(
+        buf: Span<'a>,
+    ) -> IResult<Span<'a>, Self, LocatedBmpV4RouteMonitoringTlvParsingError<'a>> {
+        let (buf, code) = be_u16(buf)?;
+        let (buf, length) = be_u16(buf)?;
+        let (buf, index) = be_u16(buf)?;
+        let input = buf;
+        let (buf, data) = nom::bytes::complete::take(length)(buf)?;
+        let value = match BmpV4RouteMonitoringTlvType::try_from(code) {
+            Ok(BmpV4RouteMonitoringTlvType::VrfTableName) => {
+                let (_, name) =
+                    nom::combinator::map_res(nom::bytes::complete::take(length), |x: Span<'_>| {
+                        String::from_utf8(x.to_vec())
+                    })(data)?;
+                BmpV4RouteMonitoringTlvValue::VrfTableName(name)
+            }
+            Ok(BmpV4RouteMonitoringTlvType::Group) => {
+                if length % 2 != 0 {
+                    return Err(nom::Err::Error(
+                        LocatedBmpV4RouteMonitoringTlvParsingError::new(
+                            input,
+                            BmpV4RouteMonitoringTlvParsingError::InvalidGroupLength(length),
+                        ),
+                    ));
+                }
+                let (_, indexes) = nom::multi::many0(be_u16)(data)?;
+                BmpV4RouteMonitoringTlvValue::Group(indexes)
+            }
+            Ok(BmpV4RouteMonitoringTlvType::StatelessParsing) => {
+                let (rest, capability) = parse_into_located(data)?;
+                if !rest.is_empty() {
+                    return Err(nom::Err::Error(
+                        LocatedBmpV4RouteMonitoringTlvParsingError::new(
+                            rest,
+                            BmpV4RouteMonitoringTlvParsingError::NomError(ErrorKind::NonEmpty),
+                        ),
+                    ));
+                }
+                BmpV4RouteMonitoringTlvValue::StatelessParsing(capability)
+            }
+            Ok(BmpV4RouteMonitoringTlvType::PathMarking) => {
+                if length != 4 && length != 6 {
+                    return Err(nom::Err::Error(
+                        LocatedBmpV4RouteMonitoringTlvParsingError::new(
+                            input,
+                            BmpV4RouteMonitoringTlvParsingError::InvalidPathMarkingLength(length),
+                        ),
+                    ));
+                }
+                let (rest, path_status) = be_u32(data)?;
+                let reason_code = if length == 6 {
+                    Some(be_u16(rest)?.1)
+                } else {
+                    None
+                };
+                BmpV4RouteMonitoringTlvValue::PathMarking(PathMarking::new(
+                    path_status,
+                    reason_code,
+                ))
+            }
+            // The BGP Update PDU is parsed by the Route Monitoring message
+            Ok(BmpV4RouteMonitoringTlvType::BgpUpdatePdu) | Err(_) => {
+                BmpV4RouteMonitoringTlvValue::Unknown {
+                    code,
+                    value: data.to_vec(),
+                }
+            }
+        };
+        Ok((buf, BmpV4RouteMonitoringTlv::new(index, value)))
+    }
+}
@@ -17,17 +17,24 @@
 
 use crate::{iana::*, *};
 use byteorder::{NetworkEndian, WriteBytesExt};
+use ipnet::IpNet;
 use netgauze_bgp_pkt::wire::serializer::{
     nlri::RouteDistinguisherWritingError, BgpMessageWritingError,
 };
-use netgauze_parse_utils::WritablePdu;
+use netgauze_parse_utils::{
+    bounded::{write_u16_length, write_u16_length_prefixed, write_u32_length, write_u8_length},
+    WritablePdu,
+};
 use netgauze_serde_macros::WritingError;
 use std::io::Write;
 
+pub mod v4;
+
 #[derive(WritingError, Eq, PartialEq, Clone, Debug)]
 pub enum BmpMessageWritingError {
     StdIOError(#[from_std_io_error] String),
     BmpMessageValueError(#[from] BmpMessageValueWritingError),
+    BmpV4MessageValueError(#[from] v4::BmpV4MessageValueWritingError),
 }
 
 impl WritablePdu<BmpMessageWritingError> for BmpMessage {
@@ -38,6 +45,7 @@ impl WritablePdu<BmpMessageWritingError> for BmpMessage {
         Self::BASE_LENGTH
             + match self {
                 Self::V3(value) => value.len(),
+                Self::V4(value) => value.len(),
             }
     }
 
@@ -45,7 +53,12 @@ impl WritablePdu<BmpMessageWritingError> for BmpMessage {
         match self {
             Self::V3(value) => {
                 writer.write_u8(BmpVersion::Version3.into())?;
-                writer.write_u32::<NetworkEndian>(self.len() as u32)?;
+                write_u32_length(writer, self.len())?;
+                value.write(writer)?;
+            }
+            Self::V4(value) => {
+                writer.write_u8(BmpVersion::Version4.into())?;
+                write_u32_length(writer, self.len())?;
                 value.write(writer)?;
             }
         }
@@ -58,6 +71,9 @@ pub enum BmpMessageValueWritingError {
     StdIOError(#[from_std_io_error] String),
     RouteMonitoringMessageError(#[from] RouteMonitoringMessageWritingError),
     RouteMirroringMessageError(#[from] RouteMirroringMessageWritingError),
+    RoutePolicyAndAttributeTraceMessageError(
+        #[from] RoutePolicyAndAttributeTraceMessageWritingError,
+    ),
     InitiationMessageError(#[from] InitiationMessageWritingError),
     PeerUpNotificationMessageError(#[from] PeerUpNotificationMessageWritingError),
     PeerDownNotificationMessageError(#[from] PeerDownNotificationMessageWritingError),
@@ -78,10 +94,12 @@ impl WritablePdu<BmpMessageValueWritingError> for BmpMessageValue {
             Self::Initiation(value) => value.len(),
             Self::Termination(value) => value.len(),
             Self::RouteMirroring(value) => value.len(),
+            Self::RoutePolicyAndAttributeTrace(value) => value.len(),
             Self::Experimental251(value) => value.len(),
             Self::Experimental252(value) => value.len(),
             Self::Experimental253(value) => value.len(),
             Self::Experimental254(value) => value.len(),
+            Self::Raw { value, .. } => value.len(),
         };
         Self::BASE_LENGTH + len
     }
@@ -96,10 +114,12 @@ impl WritablePdu<BmpMessageValueWritingError> for BmpMessageValue {
             Self::Initiation(value) => value.write(writer)?,
             Self::Termination(value) => value.write(writer)?,
             Self::RouteMirroring(value) => value.write(writer)?,
+            Self::RoutePolicyAndAttributeTrace(value) => value.write(writer)?,
             Self::Experimental251(value) => writer.write_all(value)?,
             Self::Experimental252(value) => writer.write_all(value)?,
             Self::Experimental253(value) => writer.write_all(value)?,
             Self::Experimental254(value) => writer.write_all(value)?,
+            Self::Raw { value, .. } => writer.write_all(value)?,
         }
         Ok(())
     }
@@ -130,6 +150,122 @@ impl WritablePdu<RouteMirroringMessageWritingError> for RouteMirroringMessage {
     }
 }
 
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum RoutePolicyAndAttributeTraceMessageWritingError {
+    StdIOError(#[from_std_io_error] String),
+    PeerHeaderError(#[from] PeerHeaderWritingError),
+    RouteDistinguisherError(#[from] RouteDistinguisherWritingError),
+    /// Route origin is longer than the fixed 16 bytes on the wire
+    RouteOriginTooLong(usize),
+    RoutePolicyAndAttributeTraceEventError(#[from] RoutePolicyAndAttributeTraceEventWritingError),
+}
+
+impl WritablePdu<RoutePolicyAndAttributeTraceMessageWritingError>
+    for RoutePolicyAndAttributeTraceMessage
+{
+    ///  1-octet flags
+    ///  8-octets route distinguisher
+    ///  1-octet prefix length
+    /// 16-octets route origin
+    ///  1-octet event count
+    ///  2-octets total event length
+    const BASE_LENGTH: usize = 29;
+
+    fn len(&self) -> usize {
+        let prefix_len = match self.prefix() {
+            IpNet::V4(_) => 4,
+            IpNet::V6(_) => 16,
+        };
+        Self::BASE_LENGTH
+            + self.peer_header().len()
+            + prefix_len
+            + self.events().iter().map(|x| x.len()).sum::<usize>()
+    }
+
+    fn write<T: Write>(
+        &self,
+        writer: &mut T,
+    ) -> Result<(), RoutePolicyAndAttributeTraceMessageWritingError> {
+        let route_origin = self.route_origin().as_bytes();
+        if route_origin.len() > 16 {
+            return Err(
+                RoutePolicyAndAttributeTraceMessageWritingError::RouteOriginTooLong(
+                    route_origin.len(),
+                ),
+            );
+        }
+        self.peer_header().write(writer)?;
+        match self.prefix() {
+            IpNet::V4(_) => writer.write_u8(0)?,
+            IpNet::V6(_) => writer.write_u8(ROUTE_POLICY_TRACE_FLAGS_IS_IPV6)?,
+        }
+        self.rd().write(writer)?;
+        writer.write_u8(self.prefix().prefix_len())?;
+        match self.prefix() {
+            IpNet::V4(net) => writer.write_all(&net.addr().octets())?,
+            IpNet::V6(net) => writer.write_all(&net.addr().octets())?,
+        }
+        writer.write_all(route_origin)?;
+        writer.write_all(&vec![0; 16 - route_origin.len()])?;
+        write_u8_length(writer, self.events().len())?;
+        write_u16_length(writer, self.events().iter().map(|x| x.len()).sum())?;
+        for event in self.events() {
+            event.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum RoutePolicyAndAttributeTraceEventWritingError {
+    StdIOError(#[from_std_io_error] String),
+}
+
+impl WritablePdu<RoutePolicyAndAttributeTraceEventWritingError>
+    for RoutePolicyAndAttributeTraceEvent
+{
+    /// 2-octets single event length
+    /// 1-octet event index
+    /// 4-octets timestamp (seconds)
+    /// 4-octets timestamp (microseconds)
+    /// 4-octets path identifier
+    /// 2-octets AFI
+    /// 1-octet SAFI
+    const BASE_LENGTH: usize = 18;
+
+    fn len(&self) -> usize {
+        Self::BASE_LENGTH
+            + self
+                .tlvs()
+                .iter()
+                .map(|tlv| 4 + tlv.value().len())
+                .sum::<usize>()
+    }
+
+    fn write<T: Write>(
+        &self,
+        writer: &mut T,
+    ) -> Result<(), RoutePolicyAndAttributeTraceEventWritingError> {
+        write_u16_length(writer, self.len() - 2)?;
+        writer.write_u8(self.index())?;
+        match self.timestamp() {
+            None => writer.write_u64::<NetworkEndian>(0)?,
+            Some(time) => {
+                writer.write_u32::<NetworkEndian>(time.timestamp() as u32)?;
+                writer.write_u32::<NetworkEndian>(time.timestamp_subsec_micros())?;
+            }
+        }
+        writer.write_u32::<NetworkEndian>(self.path_id())?;
+        writer.write_u16::<NetworkEndian>(self.address_type().address_family().into())?;
+        writer.write_u8(self.address_type().subsequent_address_family().into())?;
+        for tlv in self.tlvs() {
+            writer.write_u16::<NetworkEndian>(tlv.code())?;
+            write_u16_length_prefixed(writer, tlv.value())?;
+        }
+        Ok(())
+    }
+}
+
 #[inline]
 const fn compute_peer_flags_value(
     ipv6: bool,
@@ -287,7 +423,7 @@ impl WritablePdu<RouteMirroringValueWritingError> for RouteMirroringValue {
 
     fn write<T: Write>(&self, writer: &mut T) -> Result<(), RouteMirroringValueWritingError> {
         writer.write_u16::<NetworkEndian>(self.get_type().into())?;
-        writer.write_u16::<NetworkEndian>((self.len() - Self::BASE_LENGTH) as u16)?;
+        write_u16_length(writer, self.len() - Self::BASE_LENGTH)?;
         match self {
             Self::BgpMessage(msg) => match msg {
                 MirroredBgpMessage::Parsed(msg) => msg.write(writer)?,
@@ -365,6 +501,7 @@ impl WritablePdu<InitiationInformationWritingError> for InitiationInformation {
                 Self::Experimental65532(value) => value.len(),
                 Self::Experimental65533(value) => value.len(),
                 Self::Experimental65534(value) => value.len(),
+                Self::NonUtf8String { value, .. } => value.len(),
             }
     }
 
@@ -372,45 +509,34 @@ impl WritablePdu<InitiationInformationWritingError> for InitiationInformation {
         writer.write_u16::<NetworkEndian>(self.get_type().into())?;
         match self {
             Self::String(value) => {
-                let bytes = value.as_bytes();
-                writer.write_u16::<NetworkEndian>(bytes.len() as u16)?;
-                writer.write_all(bytes)?;
+                write_u16_length_prefixed(writer, value.as_bytes())?;
             }
             Self::SystemDescription(value) => {
-                let bytes = value.as_bytes();
-                writer.write_u16::<NetworkEndian>(bytes.len() as u16)?;
-                writer.write_all(bytes)?;
+                write_u16_length_prefixed(writer, value.as_bytes())?;
             }
             Self::SystemName(value) => {
-                let bytes = value.as_bytes();
-                writer.write_u16::<NetworkEndian>(bytes.len() as u16)?;
-                writer.write_all(bytes)?;
+                write_u16_length_prefixed(writer, value.as_bytes())?;
             }
             Self::VrfTableName(value) => {
-                let bytes = value.as_bytes();
-                writer.write_u16::<NetworkEndian>(bytes.len() as u16)?;
-                writer.write_all(bytes)?;
+                write_u16_length_prefixed(writer, value.as_bytes())?;
             }
             Self::AdminLabel(value) => {
-                let bytes = value.as_bytes();
-                writer.write_u16::<NetworkEndian>(bytes.len() as u16)?;
-                writer.write_all(bytes)?;
+                write_u16_length_prefixed(writer, value.as_bytes())?;
             }
             Self::Experimental65531(value) => {
-                writer.write_u16::<NetworkEndian>(value.len() as u16)?;
-                writer.write_all(value)?;
+                write_u16_length_prefixed(writer, value)?;
             }
             Self::Experimental65532(value) => {
-                writer.write_u16::<NetworkEndian>(value.len() as u16)?;
-                writer.write_all(value)?;
+                write_u16_length_prefixed(writer, value)?;
             }
             Self::Experimental65533(value) => {
-                writer.write_u16::<NetworkEndian>(value.len() as u16)?;
-                writer.write_all(value)?;
+                write_u16_length_prefixed(writer, value)?;
             }
             Self::Experimental65534(value) => {
-                writer.write_u16::<NetworkEndian>(value.len() as u16)?;
-                writer.write_all(value)?;
+                write_u16_length_prefixed(writer, value)?;
+            }
+            Self::NonUtf8String { value, .. } => {
+                write_u16_length_prefixed(writer, value)?;
             }
         }
         Ok(())
@@ -581,12 +707,13 @@ impl WritablePdu<TerminationInformationWritingError> for TerminationInformation
                 Self::Experimental65532(value) => value.len(),
                 Self::Experimental65533(value) => value.len(),
                 Self::Experimental65534(value) => value.len(),
+                Self::NonUtf8String(value) => value.len(),
             }
     }
 
     fn write<T: Write>(&self, writer: &mut T) -> Result<(), TerminationInformationWritingError> {
         writer.write_u16::<NetworkEndian>(self.get_type().into())?;
-        writer.write_u16::<NetworkEndian>((self.len() - Self::BASE_LENGTH) as u16)?;
+        write_u16_length(writer, self.len() - Self::BASE_LENGTH)?;
         match self {
             Self::String(str) => writer.write_all(str.as_bytes())?,
             Self::Reason(reason) => writer.write_u16::<NetworkEndian>((*reason).into())?,
@@ -594,6 +721,7 @@ impl WritablePdu<TerminationInformationWritingError> for TerminationInformation
             Self::Experimental65532(value) => writer.write_all(value)?,
             Self::Experimental65533(value) => writer.write_all(value)?,
             Self::Experimental65534(value) => writer.write_all(value)?,
+            Self::NonUtf8String(value) => writer.write_all(value)?,
         }
         Ok(())
     }
@@ -618,7 +746,7 @@ impl WritablePdu<StatisticsReportMessageWritingError> for StatisticsReportMessag
 
     fn write<T: Write>(&self, writer: &mut T) -> Result<(), StatisticsReportMessageWritingError> {
         self.peer_header.write(writer)?;
-        writer.write_u32::<NetworkEndian>(self.counters.len() as u32)?;
+        write_u32_length(writer, self.counters.len())?;
         for counter in &self.counters {
             counter.write(writer)?;
         }
@@ -669,7 +797,7 @@ impl WritablePdu<StatisticsCounterMessageWritingError> for StatisticsCounter {
             Ok(code) => writer.write_u16::<NetworkEndian>(code.into())?,
             Err(code) => writer.write_u16::<NetworkEndian>(code)?,
         }
-        writer.write_u16::<NetworkEndian>((self.len() - Self::BASE_LENGTH) as u16)?;
+        write_u16_length(writer, self.len() - Self::BASE_LENGTH)?;
         match self {
             Self::NumberOfPrefixesRejectedByInboundPolicy(value) => {
                 writer.write_u32::<NetworkEndian>(value.0)?
@@ -0,0 +1,189 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Serializer for the BMP version 4 messages
+
+use byteorder::{NetworkEndian, WriteBytesExt};
+use netgauze_bgp_pkt::wire::serializer::{
+    capabilities::BGPCapabilityWritingError, BgpMessageWritingError,
+};
+use netgauze_parse_utils::{bounded::write_u16_length, WritablePdu};
+use netgauze_serde_macros::WritingError;
+use std::io::Write;
+
+use crate::{
+    iana::BmpV4RouteMonitoringTlvType,
+    v4::*,
+    wire::serializer::{
+        InitiationMessageWritingError, PeerDownNotificationMessageWritingError,
+        PeerHeaderWritingError, PeerUpNotificationMessageWritingError,
+        RouteMirroringMessageWritingError, RoutePolicyAndAttributeTraceMessageWritingError,
+        StatisticsReportMessageWritingError, TerminationMessageWritingError,
+    },
+};
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum BmpV4MessageValueWritingError {
+    StdIOError(#[from_std_io_error] String),
+    RouteMonitoringMessageError(#[from] BmpV4RouteMonitoringMessageWritingError),
+    RouteMirroringMessageError(#[from] RouteMirroringMessageWritingError),
+    RoutePolicyAndAttributeTraceMessageError(
+        #[from] RoutePolicyAndAttributeTraceMessageWritingError,
+    ),
+    InitiationMessageError(#[from] InitiationMessageWritingError),
+    PeerUpNotificationMessageError(#[from] PeerUpNotificationMessageWritingError),
+    PeerDownNotificationMessageError(#[from] PeerDownNotificationMessageWritingError),
+    TerminationMessageError(#[from] TerminationMessageWritingError),
+    StatisticsReportMessageError(#[from] StatisticsReportMessageWritingError),
+}
+
+impl WritablePdu<BmpV4MessageValueWritingError> for BmpV4MessageValue {
+    /// 1-octet msg type,
+    const BASE_LENGTH: usize = 1;
+
+    fn len(&self) -> usize {
+        let len = match self {
+            Self::RouteMonitoring(value) => value.len(),
+            Self::StatisticsReport(value) => value.len(),
+            Self::PeerDownNotification(value) => value.len(),
+            Self::PeerUpNotification(value) => value.len(),
+            Self::Initiation(value) => value.len(),
+            Self::Termination(value) => value.len(),
+            Self::RouteMirroring(value) => value.len(),
+            Self::RoutePolicyAndAttributeTrace(value) => value.len(),
+            Self::Experimental251(value) => value.len(),
+            Self::Experimental252(value) => value.len(),
+            Self::Experimental253(value) => value.len(),
+            Self::Experimental254(value) => value.len(),
+            Self::Raw { value, .. } => value.len(),
+        };
+        Self::BASE_LENGTH + len
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), BmpV4MessageValueWritingError> {
+        writer.write_u8(self.get_type().into())?;
+        match self {
+            Self::RouteMonitoring(value) => value.write(writer)?,
+            Self::StatisticsReport(value) => value.write(writer)?,
+            Self::PeerDownNotification(value) => value.write(writer)?,
+            Self::PeerUpNotification(value) => value.write(writer)?,
+            Self::Initiation(value) => value.write(writer)?,
+            Self::Termination(value) => value.write(writer)?,
+            Self::RouteMirroring(value) => value.write(writer)?,
+            Self::RoutePolicyAndAttributeTrace(value) => value.write(writer)?,
+            Self::Experimental251(value) => writer.write_all(value)?,
+            Self::Experimental252(value) => writer.write_all(value)?,
+            Self::Experimental253(value) => writer.write_all(value)?,
+            Self::Experimental254(value) => writer.write_all(value)?,
+            Self::Raw { value, .. } => writer.write_all(value)?,
+        }
+        Ok(())
+    }
+}
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum BmpV4RouteMonitoringMessageWritingError {
+    StdIOError(#[from_std_io_error] String),
+    PeerHeaderError(#[from] PeerHeaderWritingError),
+    TlvError(#[from] BmpV4RouteMonitoringTlvWritingError),
+    BgpMessageError(#[from] BgpMessageWritingError),
+}
+
+impl WritablePdu<BmpV4RouteMonitoringMessageWritingError> for BmpV4RouteMonitoringMessage {
+    /// 2-octets type, 2-octets length and 2-octets index of the BGP Update
+    /// PDU TLV
+    const BASE_LENGTH: usize = 6;
+
+    fn len(&self) -> usize {
+        Self::BASE_LENGTH
+            + self.peer_header().len()
+            + self.tlvs().iter().map(|x| x.len()).sum::<usize>()
+            + self.update_message().len()
+    }
+
+    fn write<T: Write>(
+        &self,
+        writer: &mut T,
+    ) -> Result<(), BmpV4RouteMonitoringMessageWritingError> {
+        self.peer_header().write(writer)?;
+        for tlv in self.tlvs() {
+            tlv.write(writer)?;
+        }
+        writer.write_u16::<NetworkEndian>(BmpV4RouteMonitoringTlvType::BgpUpdatePdu.into())?;
+        write_u16_length(writer, self.update_message().len())?;
+        writer.write_u16::<NetworkEndian>(0)?;
+        self.update_message().write(writer)?;
+        Ok(())
+    }
+}
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum BmpV4RouteMonitoringTlvWritingError {
+    StdIOError(#[from_std_io_error] String),
+    BgpCapabilityError(#[from] BGPCapabilityWritingError),
+}
+
+impl WritablePdu<BmpV4RouteMonitoringTlvWritingError> for BmpV4RouteMonitoringTlv {
+    /// 2-octets type, 2-octets length and 2-octets index
+    const BASE_LENGTH: usize = 6;
+
+    fn len(&self) -> usize {
+        let len = match self.value() {
+            BmpV4RouteMonitoringTlvValue::VrfTableName(name) => name.len(),
+            BmpV4RouteMonitoringTlvValue::Group(indexes) => 2 * indexes.len(),
+            BmpV4RouteMonitoringTlvValue::StatelessParsing(capability) => capability.len(),
+            BmpV4RouteMonitoringTlvValue::PathMarking(marking) => {
+                if marking.reason_code().is_some() {
+                    6
+                } else {
+                    4
+                }
+            }
+            BmpV4RouteMonitoringTlvValue::Unknown { value, .. } => value.len(),
+        };
+        Self::BASE_LENGTH + len
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), BmpV4RouteMonitoringTlvWritingError> {
+        let code = match self.value().get_type() {
+            Ok(tlv_type) => tlv_type.into(),
+            Err(code) => code,
+        };
+        writer.write_u16::<NetworkEndian>(code)?;
+        write_u16_length(writer, self.len() - Self::BASE_LENGTH)?;
+        writer.write_u16::<NetworkEndian>(self.index())?;
+        match self.value() {
+            BmpV4RouteMonitoringTlvValue::VrfTableName(name) => {
+                writer.write_all(name.as_bytes())?
+            }
+            BmpV4RouteMonitoringTlvValue::Group(indexes) => {
+                for index in indexes {
+                    writer.write_u16::<NetworkEndian>(*index)?;
+                }
+            }
+            BmpV4RouteMonitoringTlvValue::StatelessParsing(capability) => {
+                capability.write(writer)?
+            }
+            BmpV4RouteMonitoringTlvValue::PathMarking(marking) => {
+                writer.write_u32::<NetworkEndian>(marking.path_status())?;
+                if let Some(reason_code) = marking.reason_code() {
+                    writer.write_u16::<NetworkEndian>(reason_code)?;
+                }
+            }
+            BmpV4RouteMonitoringTlvValue::Unknown { value, .. } => writer.write_all(value)?,
+        }
+        Ok(())
+    }
+}
@@ -33,7 +33,8 @@ use netgauze_bgp_pkt::{
     },
     update::BgpUpdateMessage,
     wire::deserializer::{
-        nlri::RouteDistinguisherParsingError, BgpMessageParsingError, BgpParsingContext,
+        nlri::RouteDistinguisherParsingError, BgpMarkerAnomaly, BgpMessageParsingError,
+        BgpParsingContext,
     },
 };
 use netgauze_iana::address_family::AddressFamily;
@@ -42,10 +43,10 @@ use netgauze_parse_utils::{
         test_parse_error, test_parse_error_with_one_input, test_parsed_completely,
         test_parsed_completely_with_one_input, test_write,
     },
-    Span,
+    Span, StringDecodingPolicy,
 };
 use nom::error::ErrorKind;
-use std::{collections::HashMap, net::Ipv6Addr, str::FromStr};
+use std::{net::Ipv6Addr, str::FromStr};
 
 use crate::{
     iana::*,
@@ -55,6 +56,9 @@ use crate::{
 
 #[cfg(feature = "codec")]
 mod pcap_tests;
+mod v4_tests;
+#[cfg(feature = "serde")]
+mod yaml_tests;
 
 #[test]
 fn test_peer_type() -> Result<(), PeerHeaderWritingError> {
@@ -381,6 +385,39 @@ fn test_initiation_information() -> Result<(), InitiationInformationWritingError
     Ok(())
 }
 
+#[test]
+fn test_initiation_information_string_decoding() -> Result<(), InitiationInformationWritingError> {
+    let non_utf8_wire = [0x00, 0x02, 0x00, 0x03, 0x41, 0xff, 0x42];
+
+    let good_lossy = InitiationInformation::SystemName("A\u{fffd}B".to_string());
+    let good_raw = InitiationInformation::NonUtf8String {
+        tlv_type: InitiationInformationTlvType::SystemName,
+        value: vec![0x41, 0xff, 0x42],
+    };
+
+    let bad_strict = LocatedInitiationInformationParsingError::new(
+        unsafe { Span::new_from_raw_offset(4, &non_utf8_wire[4..]) },
+        InitiationInformationParsingError::FromUtf8Error(
+            "invalid utf-8 sequence of 1 bytes from index 1".to_string(),
+        ),
+    );
+
+    test_parse_error::<InitiationInformation, LocatedInitiationInformationParsingError<'_>>(
+        &non_utf8_wire,
+        &bad_strict,
+    );
+    test_parse_error_with_one_input::<
+        InitiationInformation,
+        StringDecodingPolicy,
+        LocatedInitiationInformationParsingError<'_>,
+    >(&non_utf8_wire, StringDecodingPolicy::Strict, &bad_strict);
+    test_parsed_completely_with_one_input(&non_utf8_wire, StringDecodingPolicy::Lossy, &good_lossy);
+    test_parsed_completely_with_one_input(&non_utf8_wire, StringDecodingPolicy::Raw, &good_raw);
+
+    test_write(&good_raw, &non_utf8_wire)?;
+    Ok(())
+}
+
 #[test]
 fn test_initiation_message() -> Result<(), InitiationMessageWritingError> {
     let good_wire = [
@@ -438,12 +475,16 @@ fn test_bmp_value_initiation_message() -> Result<(), BmpMessageValueWritingError
             ),
         ),
     );
-    test_parsed_completely_with_one_input(&good_wire, &mut HashMap::new(), &good);
+    test_parsed_completely_with_one_input(&good_wire, &mut BmpParsingContext::default(), &good);
     test_parse_error_with_one_input::<
         BmpMessageValue,
-        &mut HashMap<PeerKey, BgpParsingContext>,
+        &mut BmpParsingContext,
         LocatedBmpMessageValueParsingError<'_>,
-    >(&bad_information_wire, &mut HashMap::new(), &bad_information);
+    >(
+        &bad_information_wire,
+        &mut BmpParsingContext::default(),
+        &bad_information,
+    );
     test_write(&good, &good_wire)?;
     Ok(())
 }
@@ -546,22 +587,30 @@ fn test_route_monitoring_message() -> Result<(), RouteMonitoringMessageWritingEr
         ),
     );
 
-    test_parsed_completely_with_one_input(&good_wire, &mut HashMap::new(), &good);
+    test_parsed_completely_with_one_input(&good_wire, &mut BmpParsingContext::default(), &good);
     test_parse_error_with_one_input::<
         RouteMonitoringMessage,
-        &mut HashMap<PeerKey, BgpParsingContext>,
+        &mut BmpParsingContext,
         LocatedRouteMonitoringMessageParsingError<'_>,
-    >(&bad_peer_header_wire, &mut HashMap::new(), &bad_peer_header);
+    >(
+        &bad_peer_header_wire,
+        &mut BmpParsingContext::default(),
+        &bad_peer_header,
+    );
     test_parse_error_with_one_input::<
         RouteMonitoringMessage,
-        &mut HashMap<PeerKey, BgpParsingContext>,
+        &mut BmpParsingContext,
         LocatedRouteMonitoringMessageParsingError<'_>,
-    >(&bad_bgp_wire, &mut HashMap::new(), &bad_bgp);
+    >(&bad_bgp_wire, &mut BmpParsingContext::default(), &bad_bgp);
     test_parse_error_with_one_input::<
         RouteMonitoringMessage,
-        &mut HashMap<PeerKey, BgpParsingContext>,
+        &mut BmpParsingContext,
         LocatedRouteMonitoringMessageParsingError<'_>,
-    >(&bad_bgp_type_wire, &mut HashMap::new(), &bad_bgp_type);
+    >(
+        &bad_bgp_type_wire,
+        &mut BmpParsingContext::default(),
+        &bad_bgp_type,
+    );
 
     test_write(&good, &good_wire)?;
 
@@ -647,12 +696,12 @@ fn test_bmp_value_route_monitoring() -> Result<(), BmpMessageValueWritingError>
             )),
         ),
     );
-    test_parsed_completely_with_one_input(&good_wire, &mut HashMap::new(), &good);
+    test_parsed_completely_with_one_input(&good_wire, &mut BmpParsingContext::default(), &good);
     test_parse_error_with_one_input::<
         BmpMessageValue,
-        &mut HashMap<PeerKey, BgpParsingContext>,
+        &mut BmpParsingContext,
         LocatedBmpMessageValueParsingError<'_>,
-    >(&bad_wire, &mut HashMap::new(), &bad);
+    >(&bad_wire, &mut BmpParsingContext::default(), &bad);
     test_write(&good, &good_wire)?;
 
     Ok(())
@@ -783,12 +832,12 @@ fn test_bmp_value_peer_up_notification() -> Result<(), BmpMessageValueWritingErr
             ),
         ),
     );
-    test_parsed_completely_with_one_input(&good_wire, &mut HashMap::new(), &good);
+    test_parsed_completely_with_one_input(&good_wire, &mut BmpParsingContext::default(), &good);
     test_parse_error_with_one_input::<
         BmpMessageValue,
-        &mut HashMap<PeerKey, BgpParsingContext>,
+        &mut BmpParsingContext,
         LocatedBmpMessageValueParsingError<'_>,
-    >(&bad_wire, &mut HashMap::new(), &bad);
+    >(&bad_wire, &mut BmpParsingContext::default(), &bad);
 
     test_write(&good, &good_wire)?;
     Ok(())
@@ -853,7 +902,7 @@ fn test_bmp_peer_up_loc_rib_notification() -> Result<(), BmpMessageWritingError>
         .unwrap(),
     ));
 
-    test_parsed_completely_with_one_input(&good_wire, &mut HashMap::new(), &good);
+    test_parsed_completely_with_one_input(&good_wire, &mut BmpParsingContext::default(), &good);
     test_write(&good, &good_wire)?;
     Ok(())
 }
@@ -1084,23 +1133,35 @@ fn test_peer_down_notification() -> Result<(), PeerDownNotificationMessageWritin
             ),
         ),
     );
-    test_parsed_completely_with_one_input(&good_wire, &mut HashMap::new(), &good);
+    test_parsed_completely_with_one_input(&good_wire, &mut BmpParsingContext::default(), &good);
 
     test_parse_error_with_one_input::<
         PeerDownNotificationMessage,
-        &mut HashMap<PeerKey, BgpParsingContext>,
+        &mut BmpParsingContext,
         LocatedPeerDownNotificationMessageParsingError<'_>,
-    >(&bad_information_wire, &mut HashMap::new(), &bad_information);
+    >(
+        &bad_information_wire,
+        &mut BmpParsingContext::default(),
+        &bad_information,
+    );
     test_parse_error_with_one_input::<
         PeerDownNotificationMessage,
-        &mut HashMap<PeerKey, BgpParsingContext>,
+        &mut BmpParsingContext,
         LocatedPeerDownNotificationMessageParsingError<'_>,
-    >(&bad_peer_header_wire, &mut HashMap::new(), &bad_peer_header);
+    >(
+        &bad_peer_header_wire,
+        &mut BmpParsingContext::default(),
+        &bad_peer_header,
+    );
     test_parse_error_with_one_input::<
         PeerDownNotificationMessage,
-        &mut HashMap<PeerKey, BgpParsingContext>,
+        &mut BmpParsingContext,
         LocatedPeerDownNotificationMessageParsingError<'_>,
-    >(&bad_peer_reason_wire, &mut HashMap::new(), &bad_peer_reason);
+    >(
+        &bad_peer_reason_wire,
+        &mut BmpParsingContext::default(),
+        &bad_peer_reason,
+    );
 
     test_write(&good, &good_wire)?;
     Ok(())
@@ -1141,13 +1202,13 @@ fn test_bmp_peer_down_notification() -> Result<(), BmpMessageWritingError> {
         BmpMessageValueParsingError::NomError(ErrorKind::Eof),
     );
 
-    test_parsed_completely_with_one_input(&good_wire, &mut HashMap::new(), &good);
+    test_parsed_completely_with_one_input(&good_wire, &mut BmpParsingContext::default(), &good);
 
     test_parse_error_with_one_input::<
         BmpMessageValue,
-        &mut HashMap<PeerKey, BgpParsingContext>,
+        &mut BmpParsingContext,
         LocatedBmpMessageValueParsingError<'_>,
-    >(&bad_eof_wire, &mut HashMap::new(), &bad_eof);
+    >(&bad_eof_wire, &mut BmpParsingContext::default(), &bad_eof);
 
     test_write(&good, &good_wire)?;
 
@@ -1242,7 +1303,153 @@ fn test_bmp_router_mirroring() -> Result<(), BmpMessageWritingError> {
             BgpMessage::KeepAlive,
         ))],
     )));
-    test_parsed_completely_with_one_input(&good_wire, &mut HashMap::new(), &good);
+    test_parsed_completely_with_one_input(&good_wire, &mut BmpParsingContext::default(), &good);
+
+    test_write(&good, &good_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_bmp_router_mirroring_information() -> Result<(), BmpMessageWritingError> {
+    let good_wire = [
+        0x03, 0x00, 0x00, 0x00, 0x53, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xac, 0x10,
+        0x00, 0x14, 0x00, 0x00, 0x00, 0xc8, 0xac, 0x10, 0x00, 0x14, 0x63, 0x3c, 0x98, 0x8b, 0x00,
+        0x04, 0x5a, 0xae, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x13, 0xff, 0xff,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00,
+        0x13, 0x04, 0x00, 0x01, 0x00, 0x02, 0x00, 0x01,
+    ];
+    let raw_wire = [0x00, 0x00, 0x00, 0x03, 0x01, 0x02, 0x03];
+
+    let good = BmpMessage::V3(BmpMessageValue::RouteMirroring(RouteMirroringMessage::new(
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(172, 16, 0, 20))),
+            200,
+            Ipv4Addr::new(172, 16, 0, 20),
+            Some(Utc.timestamp_opt(1664915595, 285358000).unwrap()),
+        ),
+        vec![
+            RouteMirroringValue::Information(RouteMirroringInformation::ErroredPdu),
+            RouteMirroringValue::BgpMessage(MirroredBgpMessage::Parsed(BgpMessage::KeepAlive)),
+            RouteMirroringValue::Information(RouteMirroringInformation::MessagesLost),
+        ],
+    )));
+    // An errored PDU that can't be parsed is mirrored as is
+    let raw = RouteMirroringValue::BgpMessage(MirroredBgpMessage::Raw(vec![0x01, 0x02, 0x03]));
+
+    test_parsed_completely_with_one_input(&good_wire, &mut BmpParsingContext::default(), &good);
+    test_write(&good, &good_wire)?;
+    test_write(&raw, &raw_wire).unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_bmp_router_mirroring_relaxed_marker() {
+    let good_wire = [
+        0x03, 0x00, 0x00, 0x00, 0x47, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xac, 0x10,
+        0x00, 0x14, 0x00, 0x00, 0x00, 0xc8, 0xac, 0x10, 0x00, 0x14, 0x63, 0x3c, 0x98, 0x8b, 0x00,
+        0x04, 0x5a, 0xae, 0x00, 0x00, 0x00, 0x13, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x13, 0x04,
+    ];
+    let peer_header = PeerHeader::new(
+        BmpPeerType::GlobalInstancePeer {
+            ipv6: false,
+            post_policy: false,
+            asn2: false,
+            adj_rib_out: false,
+        },
+        None,
+        Some(IpAddr::V4(Ipv4Addr::new(172, 16, 0, 20))),
+        200,
+        Ipv4Addr::new(172, 16, 0, 20),
+        Some(Utc.timestamp_opt(1664915595, 285358000).unwrap()),
+    );
+
+    let good = BmpMessage::V3(BmpMessageValue::RouteMirroring(RouteMirroringMessage::new(
+        peer_header.clone(),
+        vec![RouteMirroringValue::BgpMessage(MirroredBgpMessage::Parsed(
+            BgpMessage::KeepAlive,
+        ))],
+    )));
+
+    let mut ctx = BmpParsingContext::default();
+    ctx.set_relax_markers(true);
+    test_parsed_completely_with_one_input(&good_wire, &mut ctx, &good);
+    let peer_key = ctx.peer_key(&peer_header);
+    assert_eq!(
+        ctx.peers()[&peer_key].parsing_errors().marker_anomalies(),
+        &vec![BgpMarkerAnomaly::Mangled(0)]
+    );
+}
+
+#[test]
+fn test_bmp_route_policy_and_attribute_trace() -> Result<(), BmpMessageWritingError> {
+    let good_wire = [
+        0x03, 0x00, 0x00, 0x00, 0x6b, 0x64, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xac, 0x10,
+        0x00, 0x14, 0x00, 0x00, 0x00, 0xc8, 0xac, 0x10, 0x00, 0x14, 0x63, 0x3c, 0x98, 0x8b, 0x00,
+        0x04, 0x5a, 0xae, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0xc6, 0x33,
+        0x64, 0x00, 0x62, 0x67, 0x70, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x1a, 0x00, 0x18, 0x01, 0x63, 0x3c, 0x98, 0x8b, 0x00, 0x04,
+        0x5a, 0xae, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01, 0x00, 0x01, 0x00, 0x04, 0x74, 0x65,
+        0x73, 0x74,
+    ];
+    let bad_prefix_len_wire = [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xac, 0x10, 0x00, 0x14, 0x00, 0x00, 0x00, 0xc8,
+        0xac, 0x10, 0x00, 0x14, 0x63, 0x3c, 0x98, 0x8b, 0x00, 0x04, 0x5a, 0xae, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0xc6, 0x33, 0x64, 0x00,
+    ];
+
+    let timestamp = Utc.timestamp_opt(1664915595, 285358000).unwrap();
+    let good = BmpMessage::V3(BmpMessageValue::RoutePolicyAndAttributeTrace(
+        RoutePolicyAndAttributeTraceMessage::new(
+            PeerHeader::new(
+                BmpPeerType::GlobalInstancePeer {
+                    ipv6: false,
+                    post_policy: false,
+                    asn2: false,
+                    adj_rib_out: false,
+                },
+                None,
+                Some(IpAddr::V4(Ipv4Addr::new(172, 16, 0, 20))),
+                200,
+                Ipv4Addr::new(172, 16, 0, 20),
+                Some(timestamp),
+            ),
+            RouteDistinguisher::As2Administrator { asn2: 0, number: 0 },
+            IpNet::from_str("198.51.100.0/24").unwrap(),
+            "bgp".to_string(),
+            vec![RoutePolicyAndAttributeTraceEvent::new(
+                1,
+                Some(timestamp),
+                0,
+                AddressType::Ipv4Unicast,
+                vec![RoutePolicyAndAttributeTraceTlv::new(
+                    1,
+                    vec![116, 101, 115, 116],
+                )],
+            )],
+        ),
+    ));
+    let bad_prefix_len = LocatedRoutePolicyAndAttributeTraceMessageParsingError::new(
+        unsafe { Span::new_from_raw_offset(51, &bad_prefix_len_wire[51..]) },
+        RoutePolicyAndAttributeTraceMessageParsingError::InvalidPrefixLength(33),
+    );
+
+    test_parsed_completely_with_one_input(&good_wire, &mut BmpParsingContext::default(), &good);
+    test_parse_error::<
+        RoutePolicyAndAttributeTraceMessage,
+        LocatedRoutePolicyAndAttributeTraceMessageParsingError<'_>,
+    >(&bad_prefix_len_wire, &bad_prefix_len);
 
     test_write(&good, &good_wire)?;
     Ok(())
@@ -1337,7 +1544,40 @@ fn test_bmp_termination() -> Result<(), BmpMessageWritingError> {
         ),
         vec![TerminationInformation::String("test".to_string())],
     )));
-    test_parsed_completely_with_one_input(&good_wire, &mut HashMap::new(), &good);
+    test_parsed_completely_with_one_input(&good_wire, &mut BmpParsingContext::default(), &good);
+
+    test_write(&good, &good_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_bmp_termination_reason() -> Result<(), BmpMessageWritingError> {
+    let good_wire = [
+        3, 0, 0, 0, 61, 5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 172,
+        16, 0, 20, 0, 0, 0, 200, 172, 16, 0, 20, 99, 60, 152, 139, 0, 4, 90, 174, 0, 1, 0, 2, 0, 0,
+        0, 0, 0, 3, 98, 121, 101,
+    ];
+
+    let good = BmpMessage::V3(BmpMessageValue::Termination(TerminationMessage::new(
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(172, 16, 0, 20))),
+            200,
+            Ipv4Addr::new(172, 16, 0, 20),
+            Some(Utc.timestamp_opt(1664915595, 285358000).unwrap()),
+        ),
+        vec![
+            TerminationInformation::Reason(PeerTerminationCode::AdministrativelyClosed),
+            TerminationInformation::String("bye".to_string()),
+        ],
+    )));
+    test_parsed_completely_with_one_input(&good_wire, &mut BmpParsingContext::default(), &good);
 
     test_write(&good, &good_wire)?;
     Ok(())
@@ -1383,7 +1623,7 @@ fn test_bmp_statistics_report() -> Result<(), BmpMessageWritingError> {
             ],
         ),
     ));
-    test_parsed_completely_with_one_input(&good_wire, &mut HashMap::new(), &good);
+    test_parsed_completely_with_one_input(&good_wire, &mut BmpParsingContext::default(), &good);
     test_write(&good, &good_wire)?;
 
     Ok(())
@@ -1398,6 +1638,58 @@ fn test_statistics_counter() -> Result<(), StatisticsCounterMessageWritingError>
     Ok(())
 }
 
+#[test]
+fn test_statistics_counter_types() -> Result<(), StatisticsCounterMessageWritingError> {
+    // Counter types not covered by the statistics report tests
+    let cases: Vec<(Vec<u8>, StatisticsCounter)> = vec![
+        (
+            vec![0x00, 0x0c, 0x00, 0x04, 0x00, 0x00, 0x00, 0x05],
+            StatisticsCounter::NumberOfPrefixesSubjectedToTreatAsWithdraw(CounterU32(5)),
+        ),
+        (
+            vec![0x00, 0x0d, 0x00, 0x04, 0x00, 0x00, 0x01, 0x00],
+            StatisticsCounter::NumberOfDuplicateUpdateMessagesReceived(CounterU32(256)),
+        ),
+        (
+            vec![
+                0x00, 0x0e, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+            ],
+            StatisticsCounter::NumberOfRoutesInPrePolicyAdjRibOut(GaugeU64(65536)),
+        ),
+        (
+            vec![
+                0x00, 0x10, 0x00, 0x0b, 0x00, 0x02, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x07,
+            ],
+            StatisticsCounter::NumberOfRoutesInPerAfiSafiPrePolicyAdjRibOut(
+                AddressType::Ipv6Unicast,
+                GaugeU64(7),
+            ),
+        ),
+        (
+            vec![0xff, 0xfc, 0x00, 0x02, 0xab, 0xcd],
+            StatisticsCounter::Experimental65532(vec![0xab, 0xcd]),
+        ),
+        (
+            vec![0xff, 0xfd, 0x00, 0x00],
+            StatisticsCounter::Experimental65533(vec![]),
+        ),
+        (
+            vec![0xff, 0xfe, 0x00, 0x01, 0x01],
+            StatisticsCounter::Experimental65534(vec![0x01]),
+        ),
+        (
+            vec![0x01, 0x00, 0x00, 0x03, 0x01, 0x02, 0x03],
+            StatisticsCounter::Unknown(256, vec![0x01, 0x02, 0x03]),
+        ),
+    ];
+    for (good_wire, good) in cases {
+        test_parsed_completely(&good_wire, &good);
+        test_write(&good, &good_wire)?;
+    }
+    Ok(())
+}
+
 #[test]
 fn test_bmp_stats() -> Result<(), BmpMessageWritingError> {
     let good_wire = [
@@ -1525,7 +1817,7 @@ fn test_bmp_stats() -> Result<(), BmpMessageWritingError> {
         ),
     ));
 
-    test_parsed_completely_with_one_input(&good_wire, &mut HashMap::new(), &good);
+    test_parsed_completely_with_one_input(&good_wire, &mut BmpParsingContext::default(), &good);
     test_write(&good, &good_wire)?;
     Ok(())
 }
@@ -1661,7 +1953,7 @@ fn test_bmp_route_monitoring_unaligned_prefix() -> Result<(), BmpMessageWritingE
         .unwrap(),
     ));
 
-    test_parsed_completely_with_one_input(&good_wire, &mut HashMap::new(), &good);
+    test_parsed_completely_with_one_input(&good_wire, &mut BmpParsingContext::default(), &good);
     test_write(&good, &good_wire)?;
     Ok(())
 }
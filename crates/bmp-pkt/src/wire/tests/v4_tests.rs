@@ -0,0 +1,189 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(not(feature = "fuzz"))]
+use chrono::TimeZone;
+use ipnet::Ipv4Net;
+use netgauze_bgp_pkt::{
+    capabilities::{AddPathAddressFamily, AddPathCapability, BgpCapability},
+    nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+    update::BgpUpdateMessage,
+};
+use netgauze_iana::address_family::AddressType;
+use netgauze_parse_utils::{
+    test_helpers::{
+        test_parse_error_with_one_input, test_parsed_completely_with_one_input, test_write,
+    },
+    ReadablePduWithOneInput, Span,
+};
+use std::str::FromStr;
+
+use crate::{
+    v4::*,
+    wire::{
+        deserializer::{v4::*, *},
+        serializer::*,
+    },
+    *,
+};
+
+fn peer_header() -> PeerHeader {
+    PeerHeader::new(
+        BmpPeerType::GlobalInstancePeer {
+            ipv6: false,
+            post_policy: false,
+            asn2: false,
+            adj_rib_out: false,
+        },
+        None,
+        Some(IpAddr::V4(Ipv4Addr::new(172, 16, 0, 20))),
+        200,
+        Ipv4Addr::new(172, 16, 0, 20),
+        Some(Utc.timestamp_opt(1664656357, 746092000).unwrap()),
+    )
+}
+
+fn add_path_update() -> BgpMessage {
+    BgpMessage::Update(BgpUpdateMessage::new(
+        vec![],
+        vec![],
+        vec![Ipv4UnicastAddress::new(
+            Some(1),
+            Ipv4Unicast::from_net(Ipv4Net::from_str("172.16.1.0/24").unwrap()).unwrap(),
+        )],
+    ))
+}
+
+const PEER_HEADER_WIRE: [u8; 42] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xac, 0x10, 0x00, 0x14, 0x00, 0x00, 0x00, 0xc8, 0xac, 0x10,
+    0x00, 0x14, 0x63, 0x38, 0xa3, 0xe5, 0x00, 0x0b, 0x62, 0x6c,
+];
+
+const ADD_PATH_ROUTE_MONITORING_WIRE: [u8; 109] = [
+    0x04, 0x00, 0x00, 0x00, 0x6d, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xac, 0x10, 0x00, 0x14,
+    0x00, 0x00, 0x00, 0xc8, 0xac, 0x10, 0x00, 0x14, 0x63, 0x38, 0xa3, 0xe5, 0x00, 0x0b, 0x62, 0x6c,
+    0x00, 0x07, 0x00, 0x06, 0x00, 0x00, 0x45, 0x04, 0x00, 0x01, 0x01, 0x03, 0x00, 0x09, 0x00, 0x06,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x00, 0x04, 0x00, 0x1f, 0x00, 0x00, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x1f,
+    0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x18, 0xac, 0x10, 0x01,
+];
+
+/// Route Monitoring message of [`add_path_update`] with a Stateless Parsing
+/// TLV of the Add-Path capability
+fn add_path_route_monitoring(send: bool, receive: bool) -> BmpMessage {
+    BmpMessage::V4(BmpV4MessageValue::RouteMonitoring(Box::new(
+        BmpV4RouteMonitoringMessage::build(
+            peer_header(),
+            vec![
+                BmpV4RouteMonitoringTlv::new(
+                    0,
+                    BmpV4RouteMonitoringTlvValue::StatelessParsing(BgpCapability::AddPath(
+                        AddPathCapability::new(vec![AddPathAddressFamily::new(
+                            AddressType::Ipv4Unicast,
+                            send,
+                            receive,
+                        )]),
+                    )),
+                ),
+                BmpV4RouteMonitoringTlv::new(
+                    0,
+                    BmpV4RouteMonitoringTlvValue::PathMarking(PathMarking::new(
+                        PathStatus::Best as u32,
+                        Some(1),
+                    )),
+                ),
+            ],
+            add_path_update(),
+        )
+        .unwrap(),
+    )))
+}
+
+#[test]
+fn test_bmp_v4_route_monitoring() -> Result<(), BmpMessageWritingError> {
+    let good_wire = ADD_PATH_ROUTE_MONITORING_WIRE;
+    let good = add_path_route_monitoring(true, true);
+
+    let mut ctx = BmpParsingContext::default();
+    test_parsed_completely_with_one_input(&good_wire, &mut ctx, &good);
+    // The Stateless Parsing TLVs don't leak into the context of the peer
+    assert!(ctx
+        .peers()
+        .values()
+        .all(|bgp_ctx| bgp_ctx.add_path().is_empty()));
+    test_write(&good, &good_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_bmp_v4_route_monitoring_add_path_directions() {
+    let mut receive_wire = ADD_PATH_ROUTE_MONITORING_WIRE;
+    receive_wire[59] = 0x01;
+    let mut send_wire = ADD_PATH_ROUTE_MONITORING_WIRE;
+    send_wire[59] = 0x02;
+
+    // The path identifiers are parsed only when Add-Path is received for the
+    // Adj-RIB-In of the peer
+    test_parsed_completely_with_one_input(
+        &receive_wire,
+        &mut BmpParsingContext::default(),
+        &add_path_route_monitoring(false, true),
+    );
+    assert!(
+        BmpMessage::from_wire(Span::new(&send_wire), &mut BmpParsingContext::default()).is_err()
+    );
+}
+
+#[test]
+fn test_bmp_v4_route_monitoring_errors() {
+    let mut missing_pdu_wire = PEER_HEADER_WIRE.to_vec();
+    missing_pdu_wire.extend([0x00, 0x09, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02]);
+    let mut bad_path_marking_wire = PEER_HEADER_WIRE.to_vec();
+    bad_path_marking_wire.extend([
+        0x00, 0x09, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00,
+    ]);
+
+    let missing_pdu = LocatedBmpV4RouteMonitoringMessageParsingError::new(
+        unsafe { Span::new_from_raw_offset(42, &missing_pdu_wire[42..]) },
+        BmpV4RouteMonitoringMessageParsingError::MissingBgpUpdatePdu,
+    );
+    let bad_path_marking = LocatedBmpV4RouteMonitoringMessageParsingError::new(
+        unsafe { Span::new_from_raw_offset(48, &bad_path_marking_wire[48..]) },
+        BmpV4RouteMonitoringMessageParsingError::TlvError(
+            BmpV4RouteMonitoringTlvParsingError::InvalidPathMarkingLength(5),
+        ),
+    );
+
+    test_parse_error_with_one_input::<
+        BmpV4RouteMonitoringMessage,
+        &mut BmpParsingContext,
+        LocatedBmpV4RouteMonitoringMessageParsingError<'_>,
+    >(
+        &missing_pdu_wire,
+        &mut BmpParsingContext::default(),
+        &missing_pdu,
+    );
+    test_parse_error_with_one_input::<
+        BmpV4RouteMonitoringMessage,
+        &mut BmpParsingContext,
+        LocatedBmpV4RouteMonitoringMessageParsingError<'_>,
+    >(
+        &bad_path_marking_wire,
+        &mut BmpParsingContext::default(),
+        &bad_path_marking,
+    );
+}
@@ -0,0 +1,143 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! BMP collector writing the route events of the monitored routers as JSON
+//! Lines files, one directory per hour.
+//!
+//! ```text
+//! cargo run --example bmp-collector --features file -- [listen address] [output directory] [router IPs...]
+//! ```
+//!
+//! When router IPs are given, the events of the other routers are dropped.
+
+use std::{
+    collections::HashSet,
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    time::Duration,
+};
+
+use tokio::sync::mpsc;
+use tower::{buffer::Buffer, service_fn, ServiceBuilder};
+
+use netgauze_bmp_pkt::BmpMessage;
+use netgauze_bmp_service::{
+    handle::BmpServerHandle,
+    server::{BmpRequest, BmpServer, BmpServerResponse},
+    sink::{file::FileSink, BatchingSink, RouteEvent, VrfNames, DEFAULT_BATCH_SIZE},
+};
+
+fn init_tracing() {
+    let subscriber = tracing_subscriber::FmtSubscriber::builder()
+        .with_max_level(tracing::Level::INFO)
+        .finish();
+    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
+}
+
+/// Turn the received messages into route events and write them to `sink`
+/// until all the senders are dropped. The failed writes are retried by the
+/// periodic flushes.
+async fn write_events(
+    mut rx: mpsc::Receiver<(SocketAddr, BmpMessage)>,
+    mut sink: BatchingSink<FileSink>,
+    routers: HashSet<IpAddr>,
+) -> Result<FileSink, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let mut vrf_names = VrfNames::new();
+    let mut flush = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        tokio::select! {
+            msg = rx.recv() => {
+                let Some((router, msg)) = msg else {
+                    break;
+                };
+                if !routers.is_empty() && !routers.contains(&router.ip()) {
+                    continue;
+                }
+                // Initiation messages carry the VRF names used to label later events
+                vrf_names.observe_bmp_message(router, &msg);
+                let mut events = RouteEvent::from_bmp_message(router, &msg);
+                vrf_names.label(&mut events);
+                if let Err(err) = sink.extend(events).await {
+                    tracing::warn!("failed writing route events: {err}");
+                }
+            }
+            _ = flush.tick() => {
+                if let Err(err) = sink.flush().await {
+                    tracing::warn!("failed flushing route events: {err}");
+                }
+            }
+        }
+    }
+    Ok(sink.into_inner().await?)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    init_tracing();
+    let mut args = std::env::args().skip(1);
+    let local_socket: SocketAddr = args
+        .next()
+        .unwrap_or_else(|| "0.0.0.0:33000".to_string())
+        .parse()?;
+    let root = PathBuf::from(args.next().unwrap_or_else(|| "bmp-routes".to_string()));
+    let routers = args
+        .map(|arg| arg.parse::<IpAddr>())
+        .collect::<Result<HashSet<_>, _>>()?;
+
+    let mut file_sink = FileSink::new(root, "routes".to_string());
+    file_sink.bootstrap().await?;
+    let sink = BatchingSink::new(file_sink, DEFAULT_BATCH_SIZE);
+
+    let (tx, rx) = mpsc::channel(1024);
+    let writer = tokio::spawn(write_events(rx, sink, routers));
+
+    let collect_svc = service_fn(move |request: BmpRequest| {
+        let tx = tx.clone();
+        async move {
+            match request {
+                Ok(tagged) => {
+                    if let Some(msg) = tagged.value() {
+                        let router = tagged.tag().remote_socket();
+                        if tx.send((router, msg.clone())).await.is_err() {
+                            return Ok(Some(BmpServerResponse::CloseConnection));
+                        }
+                    }
+                }
+                Err(err) => tracing::warn!("{err}"),
+            }
+            Ok::<Option<BmpServerResponse>, Infallible>(None)
+        }
+    });
+    let buffer_svc = Buffer::new(ServiceBuilder::new().service(collect_svc), 100);
+
+    let handle = BmpServerHandle::default();
+    let server_handle = handle.clone();
+    let server = tokio::spawn(async move {
+        BmpServer::new(local_socket, server_handle)
+            .serve(buffer_svc)
+            .await
+    });
+    tracing::info!("collecting BMP on {local_socket}");
+
+    tokio::signal::ctrl_c().await?;
+    tracing::info!("shutting down");
+    handle.shutdown();
+    server.await??;
+    // The service, hence the last sender, is dropped with the server
+    let mut file_sink = writer.await??;
+    file_sink.close().await?;
+    Ok(())
+}
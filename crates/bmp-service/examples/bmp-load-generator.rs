@@ -0,0 +1,49 @@
+use std::{net::SocketAddr, time::Instant};
+
+use netgauze_bmp_service::loadgen::{read_mrt_rib, synthetic_rib, BmpLoadGenerator};
+
+// jemalloc takes precedence when both allocators are enabled, e.g., with
+// `--all-features`
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+#[cfg(all(feature = "mimalloc", not(feature = "jemalloc")))]
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+/// Usage: bmp-load-generator <collector> [messages/sec] [routers] [peers]
+/// [prefixes | MRT RIB file]
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let args: Vec<String> = std::env::args().collect();
+    let collector: SocketAddr = args
+        .get(1)
+        .map(|x| x.as_str())
+        .unwrap_or("127.0.0.1:1790")
+        .parse()?;
+    let messages_per_second = args.get(2).map(|x| x.parse()).transpose()?.unwrap_or(1000);
+    let routers = args.get(3).map(|x| x.parse()).transpose()?.unwrap_or(1);
+    let peers = args.get(4).map(|x| x.parse()).transpose()?.unwrap_or(1);
+    let rib = match args.get(5) {
+        None => synthetic_rib(1000),
+        Some(arg) => match arg.parse() {
+            Ok(prefixes) => synthetic_rib(prefixes),
+            Err(_) => read_mrt_rib(&std::fs::read(arg)?)?,
+        },
+    };
+
+    let generator = BmpLoadGenerator::new(rib)
+        .with_messages_per_second(messages_per_second)
+        .with_routers(routers)
+        .with_peers(peers);
+    println!(
+        "Replaying {} routes from {routers} routers with {peers} peers each to {collector} at {messages_per_second} messages/sec",
+        generator.rib().len()
+    );
+    let start = Instant::now();
+    let stats = generator.run(collector).await?;
+    println!("{}", serde_json::to_string(&stats)?);
+    println!("Replayed in {:.3}s", start.elapsed().as_secs_f64());
+    Ok(())
+}
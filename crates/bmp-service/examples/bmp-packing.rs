@@ -0,0 +1,39 @@
+use bytes::BytesMut;
+use tokio_util::codec::Decoder;
+
+use netgauze_bmp_pkt::codec::BmpCodec;
+use netgauze_bmp_service::packing::PackingAnalyzer;
+
+/// Usage: bmp-packing <BMP archive files>...
+///
+/// The files are read as a single BMP stream in the given order, e.g., the
+/// recording files of a connection as listed by
+/// `netgauze_bmp_service::recorder::recorded_files`.
+fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let mut codec = BmpCodec::default();
+    let mut analyzer = PackingAnalyzer::new();
+    let mut buf = BytesMut::new();
+    for path in std::env::args().skip(1) {
+        buf.extend_from_slice(&std::fs::read(path)?);
+        while let Some(msg) = codec.decode(&mut buf).map_err(|err| format!("{err:?}"))? {
+            analyzer.apply(&msg).map_err(|err| format!("{err:?}"))?;
+        }
+    }
+    let report = analyzer.report();
+    println!("{}", serde_json::to_string(&report)?);
+    println!(
+        "{} route monitoring messages, {:.1} prefixes per update on average, {:.1}% of the updates reuse a path attribute set",
+        report.route_monitoring_messages(),
+        report.prefixes_per_update().mean().unwrap_or_default(),
+        report.attribute_reuse_ratio() * 100.0,
+    );
+    println!(
+        "Packing saves {} of {} bytes ({:.1}%) with {} instead of {} messages",
+        report.savings(),
+        report.bytes(),
+        report.savings_ratio() * 100.0,
+        report.packed_messages(),
+        report.route_monitoring_messages(),
+    );
+    Ok(())
+}
@@ -21,6 +21,16 @@ use tower::buffer::Buffer;
 
 use netgauze_bmp_service::handle::BmpServerHandle;
 
+// jemalloc takes precedence when both allocators are enabled, e.g., with
+// `--all-features`
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+#[cfg(all(feature = "mimalloc", not(feature = "jemalloc")))]
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
 fn init_tracing() {
     // Very simple setup at the moment to validate the instrumentation in the code
     // is working in the future that should be configured automatically based on
@@ -0,0 +1,530 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detection of the changes of the capabilities negotiated by the monitored
+//! peers when their sessions are re-established.
+//!
+//! A peer that comes back with different capabilities, e.g., ADD-PATH turned
+//! on for a family, silently changes how its updates are encoded and what the
+//! collector receives. [`CapabilityDriftDetector`] remembers the capabilities
+//! negotiated in the last Peer Up message of each peer, and reports a
+//! [`CapabilityDrift`] when the next Peer Up of the same peer differs.
+//! [`CapabilityDriftLayer`] wraps a BMP service to check all the requests
+//! received by a [`crate::server::BmpServer`].
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use netgauze_bgp_pkt::{capabilities::BgpCapability, open::BgpOpenMessage, BgpMessage};
+use netgauze_bmp_pkt::{
+    BmpMessage, BmpMessageValue, PeerKey, PeerKeyStrategy, PeerUpNotificationMessage,
+};
+use netgauze_iana::address_family::AddressType;
+
+use crate::{server::BmpRequest, AddrInfo};
+
+/// Capabilities in effect on a BGP session, i.e., advertised by both ends
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NegotiatedCapabilities {
+    families: BTreeSet<AddressType>,
+    add_path_receive: BTreeSet<AddressType>,
+    add_path_send: BTreeSet<AddressType>,
+    four_octet_as: bool,
+    extended_message: bool,
+}
+
+impl NegotiatedCapabilities {
+    /// Capabilities of the OPEN messages sent and received by the monitored
+    /// router. Without any multiprotocol capability, IPv4 unicast is the only
+    /// family.
+    pub fn from_open_messages(sent: &BgpOpenMessage, received: &BgpOpenMessage) -> Self {
+        let local = Advertised::from_open(sent);
+        let remote = Advertised::from_open(received);
+        let families = if local.families.is_empty() && remote.families.is_empty() {
+            BTreeSet::from([AddressType::Ipv4Unicast])
+        } else {
+            local
+                .families
+                .intersection(&remote.families)
+                .copied()
+                .collect()
+        };
+        // Path IDs are carried in one direction only when the sender
+        // advertised send and the receiver advertised receive
+        let add_path_receive = local
+            .add_path_receive
+            .intersection(&remote.add_path_send)
+            .copied()
+            .collect();
+        let add_path_send = local
+            .add_path_send
+            .intersection(&remote.add_path_receive)
+            .copied()
+            .collect();
+        Self {
+            families,
+            add_path_receive,
+            add_path_send,
+            four_octet_as: local.four_octet_as && remote.four_octet_as,
+            extended_message: local.extended_message && remote.extended_message,
+        }
+    }
+
+    /// Capabilities of a Peer Up message, `None` when it doesn't carry the
+    /// OPEN messages
+    pub fn from_peer_up(peer_up: &PeerUpNotificationMessage) -> Option<Self> {
+        match (peer_up.sent_message(), peer_up.received_message()) {
+            (BgpMessage::Open(sent), BgpMessage::Open(received)) => {
+                Some(Self::from_open_messages(sent, received))
+            }
+            _ => None,
+        }
+    }
+
+    /// Address families negotiated with the multiprotocol extensions
+    pub const fn families(&self) -> &BTreeSet<AddressType> {
+        &self.families
+    }
+
+    /// Families whose routes are received with path IDs
+    pub const fn add_path_receive(&self) -> &BTreeSet<AddressType> {
+        &self.add_path_receive
+    }
+
+    /// Families whose routes are sent with path IDs
+    pub const fn add_path_send(&self) -> &BTreeSet<AddressType> {
+        &self.add_path_send
+    }
+
+    pub const fn four_octet_as(&self) -> bool {
+        self.four_octet_as
+    }
+
+    pub const fn extended_message(&self) -> bool {
+        self.extended_message
+    }
+}
+
+/// Capabilities advertised in a single OPEN message
+#[derive(Debug, Default)]
+struct Advertised {
+    families: BTreeSet<AddressType>,
+    add_path_receive: BTreeSet<AddressType>,
+    add_path_send: BTreeSet<AddressType>,
+    four_octet_as: bool,
+    extended_message: bool,
+}
+
+impl Advertised {
+    fn from_open(open: &BgpOpenMessage) -> Self {
+        let mut advertised = Self::default();
+        for capability in open.capabilities() {
+            match capability {
+                BgpCapability::MultiProtocolExtensions(value) => {
+                    advertised.families.insert(value.address_type());
+                }
+                BgpCapability::AddPath(value) => {
+                    for family in value.address_families() {
+                        if family.receive() {
+                            advertised.add_path_receive.insert(family.address_type());
+                        }
+                        if family.send() {
+                            advertised.add_path_send.insert(family.address_type());
+                        }
+                    }
+                }
+                BgpCapability::FourOctetAs(_) => advertised.four_octet_as = true,
+                BgpCapability::ExtendedMessage => advertised.extended_message = true,
+                _ => {}
+            }
+        }
+        advertised
+    }
+}
+
+/// Difference between the capabilities of two consecutive sessions of a peer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityDrift {
+    router: AddrInfo,
+    peer: PeerKey,
+    previous: NegotiatedCapabilities,
+    current: NegotiatedCapabilities,
+}
+
+impl CapabilityDrift {
+    pub const fn new(
+        router: AddrInfo,
+        peer: PeerKey,
+        previous: NegotiatedCapabilities,
+        current: NegotiatedCapabilities,
+    ) -> Self {
+        Self {
+            router,
+            peer,
+            previous,
+            current,
+        }
+    }
+
+    /// The BMP session that reported the re-established peer
+    pub const fn router(&self) -> AddrInfo {
+        self.router
+    }
+
+    /// Key of the peer, according to the [`PeerKeyStrategy`] of the detector
+    pub const fn peer(&self) -> PeerKey {
+        self.peer
+    }
+
+    pub const fn previous(&self) -> &NegotiatedCapabilities {
+        &self.previous
+    }
+
+    pub const fn current(&self) -> &NegotiatedCapabilities {
+        &self.current
+    }
+
+    pub fn families_added(&self) -> Vec<AddressType> {
+        added(&self.previous.families, &self.current.families)
+    }
+
+    pub fn families_removed(&self) -> Vec<AddressType> {
+        added(&self.current.families, &self.previous.families)
+    }
+
+    /// Families now received with path IDs
+    pub fn add_path_enabled(&self) -> Vec<AddressType> {
+        added(
+            &self.previous.add_path_receive,
+            &self.current.add_path_receive,
+        )
+    }
+
+    /// Families no longer received with path IDs
+    pub fn add_path_disabled(&self) -> Vec<AddressType> {
+        added(
+            &self.current.add_path_receive,
+            &self.previous.add_path_receive,
+        )
+    }
+}
+
+/// Elements of `current` that are not in `previous`
+fn added(previous: &BTreeSet<AddressType>, current: &BTreeSet<AddressType>) -> Vec<AddressType> {
+    current.difference(previous).copied().collect()
+}
+
+/// Remembers the capabilities negotiated by the monitored peers of each
+/// router and reports the peers that come back with different ones.
+///
+/// Routers are identified by their IP address, so the capabilities are kept
+/// across BMP sessions, e.g., when the router or the collector restarts.
+/// Peer Up messages without the OPEN messages are ignored.
+#[derive(Debug, Default)]
+pub struct CapabilityDriftDetector {
+    peer_key_strategy: PeerKeyStrategy,
+    peers: HashMap<(IpAddr, PeerKey), NegotiatedCapabilities>,
+}
+
+impl CapabilityDriftDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How the monitored peers of each router are told apart, see
+    /// [`PeerKeyStrategy::Full`] for the default
+    pub fn with_peer_key_strategy(mut self, peer_key_strategy: PeerKeyStrategy) -> Self {
+        self.peer_key_strategy = peer_key_strategy;
+        self
+    }
+
+    pub const fn peer_key_strategy(&self) -> PeerKeyStrategy {
+        self.peer_key_strategy
+    }
+
+    /// Capabilities negotiated in the last session of a peer of `router`
+    pub fn capabilities(&self, router: IpAddr, peer: PeerKey) -> Option<&NegotiatedCapabilities> {
+        self.peers.get(&(router, peer))
+    }
+
+    /// Process a request received by the BMP server
+    pub fn check_request(&mut self, request: &BmpRequest) -> Option<CapabilityDrift> {
+        match request {
+            Ok(tagged) => tagged
+                .value()
+                .as_ref()
+                .and_then(|msg| self.check(tagged.tag(), msg)),
+            Err(_) => None,
+        }
+    }
+
+    /// Process the next message received from `router`
+    pub fn check(&mut self, router: AddrInfo, msg: &BmpMessage) -> Option<CapabilityDrift> {
+        let value = msg.to_v3_value();
+        let peer_up = match value.as_ref() {
+            BmpMessageValue::PeerUpNotification(peer_up) => peer_up,
+            _ => return None,
+        };
+        let current = NegotiatedCapabilities::from_peer_up(peer_up)?;
+        let peer = self
+            .peer_key_strategy
+            .apply(PeerKey::from_peer_header(peer_up.peer_header()));
+        match self
+            .peers
+            .insert((router.remote_socket().ip(), peer), current.clone())
+        {
+            Some(previous) if previous != current => {
+                Some(CapabilityDrift::new(router, peer, previous, current))
+            }
+            _ => None,
+        }
+    }
+
+    /// Forget the peers of a router, e.g., when it's decommissioned
+    pub fn remove_router(&mut self, router: IpAddr) -> usize {
+        let before = self.peers.len();
+        self.peers.retain(|(addr, _), _| *addr != router);
+        before - self.peers.len()
+    }
+}
+
+/// [`Layer`] comparing each Peer Up message with the previous one of the same
+/// peer with a shared [`CapabilityDriftDetector`]. A drift is logged as a
+/// warning with the families and ADD-PATH settings that changed, and sent to
+/// the events channel.
+#[derive(Debug, Clone)]
+pub struct CapabilityDriftLayer {
+    detector: Arc<Mutex<CapabilityDriftDetector>>,
+    events: mpsc::UnboundedSender<CapabilityDrift>,
+}
+
+impl CapabilityDriftLayer {
+    pub fn new(
+        detector: CapabilityDriftDetector,
+        events: mpsc::UnboundedSender<CapabilityDrift>,
+    ) -> Self {
+        Self {
+            detector: Arc::new(Mutex::new(detector)),
+            events,
+        }
+    }
+
+    /// The shared detector, e.g., to query the negotiated capabilities
+    pub fn detector(&self) -> Arc<Mutex<CapabilityDriftDetector>> {
+        self.detector.clone()
+    }
+}
+
+impl<S> Layer<S> for CapabilityDriftLayer {
+    type Service = CapabilityDriftService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CapabilityDriftService {
+            inner,
+            detector: self.detector.clone(),
+            events: self.events.clone(),
+        }
+    }
+}
+
+/// Service created by [`CapabilityDriftLayer`]
+#[derive(Debug, Clone)]
+pub struct CapabilityDriftService<S> {
+    inner: S,
+    detector: Arc<Mutex<CapabilityDriftDetector>>,
+    events: mpsc::UnboundedSender<CapabilityDrift>,
+}
+
+impl<S> Service<BmpRequest> for CapabilityDriftService<S>
+where
+    S: Service<BmpRequest>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: BmpRequest) -> Self::Future {
+        let drift = self
+            .detector
+            .lock()
+            .expect("capability drift detector lock is poisoned")
+            .check_request(&request);
+        if let Some(drift) = drift {
+            tracing::warn!(
+                router = %drift.router().remote_socket(),
+                peer = ?drift.peer(),
+                families_added = ?drift.families_added(),
+                families_removed = ?drift.families_removed(),
+                add_path_enabled = ?drift.add_path_enabled(),
+                add_path_disabled = ?drift.add_path_disabled(),
+                "BMP peer re-established with different capabilities"
+            );
+            // The receiver is optional, drifts are still logged when it's
+            // dropped
+            let _ = self.events.send(drift);
+        }
+        self.inner.call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use netgauze_bgp_pkt::{
+        capabilities::{
+            AddPathAddressFamily, AddPathCapability, MultiProtocolExtensionsCapability,
+        },
+        open::BgpOpenMessageParameter,
+    };
+    use netgauze_bmp_pkt::{
+        BmpPeerType, PeerDownNotificationMessage, PeerDownNotificationReason, PeerHeader,
+    };
+    use std::net::Ipv4Addr;
+
+    fn router() -> AddrInfo {
+        AddrInfo::new(
+            "192.0.2.100:1790".parse().unwrap(),
+            "192.0.2.1:40000".parse().unwrap(),
+        )
+    }
+
+    fn peer_header() -> PeerHeader {
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 1))),
+            64500,
+            Ipv4Addr::new(203, 0, 113, 1),
+            Some(Utc.timestamp_opt(1680000000, 0).unwrap()),
+        )
+    }
+
+    fn open(families: &[AddressType], add_path: &[AddressType]) -> BgpMessage {
+        let mut capabilities = families
+            .iter()
+            .map(|family| {
+                BgpCapability::MultiProtocolExtensions(MultiProtocolExtensionsCapability::new(
+                    *family,
+                ))
+            })
+            .collect::<Vec<_>>();
+        capabilities.push(BgpCapability::AddPath(AddPathCapability::new(
+            add_path
+                .iter()
+                .map(|family| AddPathAddressFamily::new(*family, true, true))
+                .collect(),
+        )));
+        BgpMessage::Open(BgpOpenMessage::new(
+            64500,
+            180,
+            Ipv4Addr::new(203, 0, 113, 1),
+            vec![BgpOpenMessageParameter::Capabilities(capabilities)],
+        ))
+    }
+
+    fn peer_up(families: &[AddressType], add_path: &[AddressType]) -> BmpMessage {
+        BmpMessage::V3(BmpMessageValue::PeerUpNotification(
+            PeerUpNotificationMessage::build(
+                peer_header(),
+                None,
+                None,
+                None,
+                open(families, add_path),
+                open(families, add_path),
+                vec![],
+            )
+            .unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_negotiated_capabilities() {
+        let sent = open(&[AddressType::Ipv4Unicast, AddressType::Ipv6Unicast], &[]);
+        let received = open(&[AddressType::Ipv4Unicast], &[AddressType::Ipv4Unicast]);
+        let (BgpMessage::Open(sent), BgpMessage::Open(received)) = (sent, received) else {
+            unreachable!()
+        };
+        let capabilities = NegotiatedCapabilities::from_open_messages(&sent, &received);
+        assert_eq!(
+            capabilities.families(),
+            &BTreeSet::from([AddressType::Ipv4Unicast])
+        );
+        // Only the remote end advertised ADD-PATH
+        assert!(capabilities.add_path_receive().is_empty());
+        assert!(!capabilities.four_octet_as());
+    }
+
+    #[test]
+    fn test_capability_drift() {
+        let mut detector = CapabilityDriftDetector::new();
+        let peer = PeerKey::from_peer_header(&peer_header());
+        let peer_down = BmpMessage::V3(BmpMessageValue::PeerDownNotification(
+            PeerDownNotificationMessage::build(
+                peer_header(),
+                PeerDownNotificationReason::RemoteSystemClosedNoData,
+            )
+            .unwrap(),
+        ));
+
+        let first = peer_up(&[AddressType::Ipv4Unicast], &[]);
+        assert_eq!(detector.check(router(), &first), None);
+        assert_eq!(detector.check(router(), &peer_down), None);
+        // Same capabilities
+        assert_eq!(detector.check(router(), &first), None);
+        assert_eq!(detector.check(router(), &peer_down), None);
+
+        let second = peer_up(
+            &[AddressType::Ipv4Unicast, AddressType::Ipv6Unicast],
+            &[AddressType::Ipv4Unicast],
+        );
+        let drift = detector.check(router(), &second).unwrap();
+        assert_eq!(drift.peer(), peer);
+        assert_eq!(drift.families_added(), vec![AddressType::Ipv6Unicast]);
+        assert_eq!(drift.families_removed(), vec![]);
+        assert_eq!(drift.add_path_enabled(), vec![AddressType::Ipv4Unicast]);
+        assert_eq!(drift.add_path_disabled(), vec![]);
+
+        // The capabilities are kept across BMP sessions of the router
+        assert_eq!(
+            detector.check_request(&Ok(crate::TaggedData::new(router(), None))),
+            None
+        );
+        let drift = detector.check(router(), &first).unwrap();
+        assert_eq!(drift.families_removed(), vec![AddressType::Ipv6Unicast]);
+        assert_eq!(drift.add_path_disabled(), vec![AddressType::Ipv4Unicast]);
+
+        assert_eq!(detector.remove_router(router().remote_socket().ip()), 1);
+        assert_eq!(detector.check(router(), &second), None);
+    }
+}
@@ -0,0 +1,379 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conformance analyzer of the BMP sessions of a router and of the BGP
+//! messages they carry.
+//!
+//! Besides the malformed messages rejected by the parser, the analyzer checks
+//! the message sequence of the session, e.g., the session starts with an
+//! Initiation message, and reports the deprecated and unknown code points.
+//! A captured session, e.g., a [`netgauze_bmp_pkt::capture::BmpCapture`], is
+//! analyzed at once with [`analyze`].
+
+use std::collections::HashSet;
+
+use bytes::BytesMut;
+use tokio_util::codec::Decoder;
+
+use netgauze_bgp_pkt::{
+    path_attribute::{AsPath, AsPathSegmentType, PathAttributeValue},
+    BgpMessage,
+};
+use netgauze_bmp_pkt::{
+    codec::{BmpCodec, BmpCodecDecoderError},
+    iana::BmpMessageType,
+    wire::deserializer::BmpParsingContext,
+    BmpMessage, BmpMessageValue, PeerHeader, PeerKey, PeerKeyStrategy,
+};
+use netgauze_parse_utils::conformance::{ConformanceReport, FindingKind};
+
+use crate::unknown_codes::{UnknownCode, UnknownCodesCounter};
+
+/// Path attributes deprecated by
+/// [RFC 6938](https://datatracker.ietf.org/doc/html/rfc6938): DPA, ADVERTISER
+/// and RCID_PATH / CLUSTER_ID
+const DEPRECATED_PATH_ATTRIBUTES: [u8; 3] = [11, 12, 13];
+
+/// Checks the messages of a single BMP session, in the order they're received
+#[derive(Debug)]
+pub struct BmpConformanceAnalyzer {
+    report: ConformanceReport,
+    peer_key_strategy: PeerKeyStrategy,
+    /// Peers with a Peer Up message and no Peer Down message since
+    peers_up: HashSet<PeerKey>,
+    terminated: bool,
+    unknown_codes: UnknownCodesCounter,
+}
+
+impl BmpConformanceAnalyzer {
+    /// `source` identifies the router in the report
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            report: ConformanceReport::new(source),
+            peer_key_strategy: PeerKeyStrategy::default(),
+            peers_up: HashSet::new(),
+            terminated: false,
+            unknown_codes: UnknownCodesCounter::new(),
+        }
+    }
+
+    /// How the monitored peers are told apart when checking that they're up
+    pub const fn with_peer_key_strategy(mut self, peer_key_strategy: PeerKeyStrategy) -> Self {
+        self.peer_key_strategy = peer_key_strategy;
+        self
+    }
+
+    /// Check a decoded message
+    pub fn observe(&mut self, msg: &BmpMessage) {
+        let first = self.report.messages() == 0;
+        self.report.next_message();
+        let value = msg.to_v3_value();
+        if first && value.get_type() != BmpMessageType::Initiation {
+            self.report.record(
+                FindingKind::Violation,
+                "bmp.first-message",
+                Some("RFC 7854 section 4.3"),
+                format!(
+                    "session starts with {} instead of Initiation",
+                    value.get_type()
+                ),
+            );
+        }
+        if self.terminated {
+            self.report.record(
+                FindingKind::Violation,
+                "bmp.after-termination",
+                Some("RFC 7854 section 4.5"),
+                format!("{} received after Termination", value.get_type()),
+            );
+        }
+        match value.as_ref() {
+            BmpMessageValue::PeerUpNotification(peer_up) => {
+                let peer_key = self.peer_key(peer_up.peer_header());
+                if !self.peers_up.insert(peer_key) {
+                    self.report.record(
+                        FindingKind::Violation,
+                        "bmp.duplicate-peer-up",
+                        Some("RFC 7854 section 4.10"),
+                        format!("Peer Up for peer {:?} already up", peer_up.peer_header()),
+                    );
+                }
+            }
+            BmpMessageValue::PeerDownNotification(peer_down) => {
+                let peer_key = self.peer_key(peer_down.peer_header());
+                if !self.peers_up.remove(&peer_key) {
+                    self.peer_not_up(value.get_type(), peer_down.peer_header());
+                }
+            }
+            BmpMessageValue::RouteMonitoring(route_monitoring) => {
+                self.check_peer_up(value.get_type(), route_monitoring.peer_header());
+                if let BgpMessage::Update(update) = route_monitoring.update_message() {
+                    for attr in update.path_attributes() {
+                        if let PathAttributeValue::AsPath(as_path) = attr.value() {
+                            self.check_as_path(as_path);
+                        }
+                    }
+                }
+            }
+            BmpMessageValue::StatisticsReport(stats) => {
+                self.check_peer_up(value.get_type(), stats.peer_header());
+            }
+            BmpMessageValue::Termination(_) => {
+                self.terminated = true;
+            }
+            _ => {}
+        }
+        // Unknown codes are counted by `unknown_codes`, the count is added
+        // to the finding when creating the report
+        for code in self.unknown_codes.observe(msg) {
+            record_unknown_code(&mut self.report, code, 0);
+        }
+    }
+
+    /// Record a message rejected by the decoder
+    pub fn observe_error(&mut self, error: &BmpCodecDecoderError) {
+        self.report.next_message();
+        let (code, description) = match error {
+            BmpCodecDecoderError::Incomplete(_) => {
+                ("bmp.truncated", "message is truncated".to_string())
+            }
+            error => ("bmp.malformed", format!("{error:?}")),
+        };
+        self.report.record(
+            FindingKind::Violation,
+            code,
+            Some("RFC 7854 section 4"),
+            description,
+        );
+    }
+
+    /// Report of the messages observed so far
+    pub fn report(&self) -> ConformanceReport {
+        let mut report = self.report.clone();
+        for (code, count) in self.unknown_codes.counts() {
+            record_unknown_code(&mut report, *code, *count);
+        }
+        report
+    }
+
+    fn peer_key(&self, header: &PeerHeader) -> PeerKey {
+        self.peer_key_strategy
+            .apply(PeerKey::from_peer_header(header))
+    }
+
+    fn check_peer_up(&mut self, msg_type: BmpMessageType, header: &PeerHeader) {
+        if !self.peers_up.contains(&self.peer_key(header)) {
+            self.peer_not_up(msg_type, header);
+        }
+    }
+
+    fn peer_not_up(&mut self, msg_type: BmpMessageType, header: &PeerHeader) {
+        self.report.record(
+            FindingKind::Violation,
+            "bmp.peer-not-up",
+            Some("RFC 7854 section 4.10"),
+            format!("{msg_type} for peer {header:?} without a Peer Up"),
+        );
+    }
+
+    fn check_as_path(&mut self, as_path: &AsPath) {
+        let has_set = match as_path {
+            AsPath::As2PathSegments(segments) => segments
+                .iter()
+                .any(|segment| segment.segment_type() == AsPathSegmentType::AsSet),
+            AsPath::As4PathSegments(segments) => segments
+                .iter()
+                .any(|segment| segment.segment_type() == AsPathSegmentType::AsSet),
+        };
+        if has_set {
+            self.report.record(
+                FindingKind::Deprecated,
+                "bgp.as-set",
+                Some("RFC 6472"),
+                "AS_PATH with an AS_SET segment",
+            );
+        }
+    }
+}
+
+fn record_unknown_code(report: &mut ConformanceReport, code: UnknownCode, count: u64) {
+    match code {
+        UnknownCode::PathAttribute(code) if DEPRECATED_PATH_ATTRIBUTES.contains(&code) => report
+            .record_count(
+                FindingKind::Deprecated,
+                format!("bgp.path-attribute.{code}"),
+                Some("RFC 6938"),
+                format!("deprecated path attribute type {code}"),
+                count,
+            ),
+        UnknownCode::PathAttribute(code) => report.record_count(
+            FindingKind::UnknownCode,
+            format!("bgp.path-attribute.{code}"),
+            None,
+            format!("unknown path attribute type {code}"),
+            count,
+        ),
+        UnknownCode::Capability(code) => report.record_count(
+            FindingKind::UnknownCode,
+            format!("bgp.capability.{code}"),
+            None,
+            format!("unknown BGP capability {code}"),
+            count,
+        ),
+    }
+}
+
+/// Analyze the raw messages of a BMP session, decoded starting from
+/// `context`. Decoding continues after malformed messages, and trailing
+/// bytes that don't make a full message are reported as truncated.
+pub fn analyze(
+    source: impl Into<String>,
+    context: BmpParsingContext,
+    data: &[u8],
+) -> ConformanceReport {
+    let mut analyzer =
+        BmpConformanceAnalyzer::new(source).with_peer_key_strategy(context.peer_key_strategy());
+    let mut codec = BmpCodec::default();
+    codec.set_parsing_context(context);
+    let mut buf = BytesMut::from(data);
+    loop {
+        let remaining = buf.len();
+        match codec.decode(&mut buf) {
+            Ok(Some(msg)) => analyzer.observe(&msg),
+            Ok(None) => break,
+            Err(error) => {
+                analyzer.observe_error(&error);
+                // The codec skips malformed messages, make sure it's not stuck
+                if buf.len() == remaining {
+                    break;
+                }
+            }
+        }
+    }
+    if !buf.is_empty() {
+        analyzer.observe_error(&BmpCodecDecoderError::Incomplete(Some(buf.len())));
+    }
+    analyzer.report()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netgauze_bgp_pkt::{
+        path_attribute::{As4PathSegment, PathAttribute, UnknownAttribute},
+        update::BgpUpdateMessage,
+    };
+    use netgauze_bmp_pkt::{
+        BmpPeerType, InitiationInformation, InitiationMessage, RouteMonitoringMessage,
+        TerminationMessage,
+    };
+    use netgauze_parse_utils::WritablePdu;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn peer_header() -> PeerHeader {
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10))),
+            65000,
+            Ipv4Addr::new(192, 0, 2, 10),
+            None,
+        )
+    }
+
+    fn route_monitoring() -> BmpMessage {
+        let attrs = vec![
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![As4PathSegment::new(
+                    AsPathSegmentType::AsSet,
+                    vec![65001, 65002],
+                )])),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                true,
+                true,
+                false,
+                false,
+                PathAttributeValue::UnknownAttribute(UnknownAttribute::new(11, vec![1])),
+            )
+            .unwrap(),
+        ];
+        BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            RouteMonitoringMessage::build(
+                peer_header(),
+                BgpMessage::Update(BgpUpdateMessage::new(vec![], attrs, vec![])),
+            )
+            .unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_analyze() {
+        let initiation = BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(vec![
+            InitiationInformation::SystemName("router1".to_string()),
+        ])));
+        let termination = BmpMessage::V3(BmpMessageValue::Termination(TerminationMessage::new(
+            peer_header(),
+            vec![],
+        )));
+        let mut data = vec![];
+        for msg in [&initiation, &route_monitoring(), &termination, &initiation] {
+            msg.write(&mut data).unwrap();
+        }
+        // Undefined message type 200
+        data.extend_from_slice(&[3, 0, 0, 0, 6, 200]);
+
+        let report = analyze("192.0.2.1", BmpParsingContext::default(), &data);
+        assert_eq!(report.source(), "192.0.2.1");
+        assert_eq!(report.messages(), 5);
+        let kinds = report
+            .findings()
+            .iter()
+            .map(|finding| (finding.code(), finding.kind(), finding.first_seen()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                ("bmp.peer-not-up", FindingKind::Violation, 1),
+                ("bgp.as-set", FindingKind::Deprecated, 1),
+                ("bgp.path-attribute.11", FindingKind::Deprecated, 1),
+                ("bmp.after-termination", FindingKind::Violation, 3),
+                ("bmp.malformed", FindingKind::Violation, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_first_message() {
+        let mut analyzer = BmpConformanceAnalyzer::new("router");
+        analyzer.observe(&route_monitoring());
+        analyzer.observe(&route_monitoring());
+        let report = analyzer.report();
+        assert_eq!(report.finding("bmp.first-message").unwrap().count(), 1);
+        assert_eq!(report.finding("bmp.peer-not-up").unwrap().count(), 2);
+        assert_eq!(report.finding("bgp.as-set").unwrap().count(), 2);
+        assert_eq!(report.finding("bgp.path-attribute.11").unwrap().count(), 2);
+    }
+}
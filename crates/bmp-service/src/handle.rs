@@ -69,6 +69,7 @@ struct BmpServerHandleInner {
     graceful_shutdown: NotifyOnce,
     graceful_shutdown_duration: Option<Duration>,
     connection_end: NotifyOnce,
+    closed: NotifyOnce,
 }
 
 impl BmpServerHandleInner {
@@ -80,6 +81,7 @@ impl BmpServerHandleInner {
             graceful_shutdown: NotifyOnce::default(),
             graceful_shutdown_duration,
             connection_end: NotifyOnce::default(),
+            closed: NotifyOnce::default(),
         }
     }
 }
@@ -112,6 +114,32 @@ impl BmpServerHandle {
         self.inner.graceful_shutdown.notify_waiters();
     }
 
+    /// Stop accepting new connections and wait for the server and all its
+    /// connections to end.
+    ///
+    /// The connections still open after the graceful shutdown duration are
+    /// closed by the server, which first passes synthetic Peer Down and
+    /// Termination messages to the service, see
+    /// [`crate::server::BmpServer::serve`]. Once resolved, the service won't
+    /// receive any further request, hence its buffered messages can be
+    /// flushed to the sinks.
+    pub async fn drain(&self) {
+        self.graceful_shutdown();
+        self.closed().await;
+        if self.connection_count() > 0 {
+            self.inner.connection_end.notified().await;
+        }
+    }
+
+    pub(crate) fn notify_closed(&self) {
+        self.inner.closed.notify_waiters();
+    }
+
+    /// Wait for the server to stop listening
+    pub async fn closed(&self) {
+        self.inner.closed.notified().await;
+    }
+
     pub(crate) async fn wait_shutdown(&self) {
         self.inner.shutdown.notified().await;
     }
@@ -0,0 +1,200 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Minimal HTTP/1.1 server answering `GET` requests with JSON snapshots.
+//!
+//! It's meant for operators poking at a collector with `curl`, not as a
+//! general purpose web server: every connection answers a single request and
+//! is then closed.
+
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use serde::Serialize;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// Requests larger than that are rejected
+const MAX_REQUEST_LEN: usize = 8 * 1024;
+
+/// Time allowed to the client to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+type Endpoint = Arc<dyn Fn() -> serde_json::Value + Send + Sync>;
+
+/// Serves a JSON document per path, computed on each request. `GET /` lists
+/// the available paths.
+#[derive(Clone, Default)]
+pub struct IntrospectionServer {
+    endpoints: BTreeMap<String, Endpoint>,
+}
+
+impl std::fmt::Debug for IntrospectionServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IntrospectionServer")
+            .field("endpoints", &self.endpoints.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl IntrospectionServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve the value returned by `snapshot` under `path`, e.g., `/bmp`
+    pub fn with_endpoint<T, F>(mut self, path: impl Into<String>, snapshot: F) -> Self
+    where
+        T: Serialize,
+        F: Fn() -> T + Send + Sync + 'static,
+    {
+        self.endpoints.insert(
+            path.into(),
+            Arc::new(move || {
+                serde_json::to_value(snapshot())
+                    .unwrap_or_else(|err| serde_json::json!({ "error": err.to_string() }))
+            }),
+        );
+        self
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.endpoints.keys().map(String::as_str)
+    }
+
+    /// Accept connections until the listener fails
+    pub async fn serve(self, listener: TcpListener) -> std::io::Result<()> {
+        let server = Arc::new(self);
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let server = server.clone();
+            tokio::spawn(async move {
+                if let Err(err) = server.handle(stream).await {
+                    tracing::debug!(%peer, "introspection request failed: {err}");
+                }
+            });
+        }
+    }
+
+    async fn handle(&self, mut stream: TcpStream) -> std::io::Result<()> {
+        let request = match tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream)).await {
+            Ok(request) => request?,
+            Err(_) => return Ok(()),
+        };
+        let (status, body) = match request {
+            Some(request) => self.respond(&request),
+            None => (
+                "400 Bad Request",
+                serde_json::json!({"error": "bad request"}),
+            ),
+        };
+        let body = body.to_string();
+        let response = format!(
+            "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await
+    }
+
+    /// Status and body answering the request line
+    fn respond(&self, request_line: &str) -> (&'static str, serde_json::Value) {
+        let mut parts = request_line.split(' ');
+        let (method, target) = match (parts.next(), parts.next()) {
+            (Some(method), Some(target)) => (method, target),
+            _ => {
+                return (
+                    "400 Bad Request",
+                    serde_json::json!({"error": "bad request"}),
+                )
+            }
+        };
+        if method != "GET" {
+            return (
+                "405 Method Not Allowed",
+                serde_json::json!({"error": "method not allowed"}),
+            );
+        }
+        // Query strings are ignored
+        let path = target.split('?').next().unwrap_or(target);
+        if path == "/" {
+            return (
+                "200 OK",
+                serde_json::json!(self.paths().collect::<Vec<_>>()),
+            );
+        }
+        match self.endpoints.get(path) {
+            Some(endpoint) => ("200 OK", endpoint()),
+            None => ("404 Not Found", serde_json::json!({"error": "not found"})),
+        }
+    }
+}
+
+/// Read the request head and return its request line, `None` when the
+/// request is malformed or too large
+async fn read_request(stream: &mut TcpStream) -> std::io::Result<Option<String>> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|window| window == b"\r\n\r\n") {
+        if buf.len() > MAX_REQUEST_LEN {
+            return Ok(None);
+        }
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk[..read]);
+    }
+    let head = match std::str::from_utf8(&buf) {
+        Ok(head) => head,
+        Err(_) => return Ok(None),
+    };
+    Ok(head.lines().next().map(str::to_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn get(addr: std::net::SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_introspection_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = IntrospectionServer::new().with_endpoint("/bmp", || vec![1, 2, 3]);
+        tokio::spawn(server.serve(listener));
+
+        let response = get(addr, "GET /bmp HTTP/1.1\r\nHost: localhost\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\n[1,2,3]"));
+
+        let response = get(addr, "GET /?pretty HTTP/1.1\r\n\r\n").await;
+        assert!(response.ends_with("[\"/bmp\"]"));
+
+        let response = get(addr, "GET /flow HTTP/1.1\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        let response = get(addr, "POST /bmp HTTP/1.1\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+}
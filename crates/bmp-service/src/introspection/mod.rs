@@ -0,0 +1,551 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Read-only view of the state kept by a long-running collector, to debug it
+//! while it's running.
+//!
+//! [`BmpIntrospector`] follows the BMP sessions and keeps, for each router,
+//! the monitored peers that are up along with the capabilities negotiated in
+//! their BGP session and the RIB sizes reported by the router.
+//! [`IntrospectionLayer`] wraps a BMP service to feed a shared introspector
+//! with all the requests received by a [`crate::server::BmpServer`].
+//! Snapshots are serializable, so they can be logged or exposed as is.
+//!
+//! With the `introspection-http` feature, [`http::IntrospectionServer`]
+//! serves snapshots as JSON over HTTP.
+
+#[cfg(feature = "introspection-http")]
+pub mod http;
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use netgauze_bgp_pkt::{capabilities::BgpCapability, BgpMessage};
+use netgauze_bmp_pkt::{
+    BmpMessage, BmpMessageValue, InitiationInformation, PeerHeader, PeerKey, PeerKeyStrategy,
+    StatisticsCounter,
+};
+
+use crate::{server::BmpRequest, view::MultiRouterView, AddrInfo};
+
+/// State of a monitored peer that is up
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerSnapshot {
+    pub peer: PeerKey,
+    /// Time of the Peer Up message, as set by the router when available
+    pub up_since: DateTime<Utc>,
+    pub local_address: Option<IpAddr>,
+    pub local_port: Option<u16>,
+    pub remote_port: Option<u16>,
+    /// The smallest of the hold times of both BGP OPEN messages
+    pub hold_time: u16,
+    /// Capabilities advertised by both sides of the BGP session, as
+    /// advertised by the peer. Multiprotocol capabilities are negotiated per
+    /// AFI/SAFI.
+    pub negotiated_capabilities: Vec<BgpCapability>,
+    pub route_monitoring_messages: u64,
+    /// Last `NumberOfRoutesInAdjRibIn` statistic reported by the router
+    pub adj_rib_in_routes: Option<u64>,
+    /// Last `NumberOfRoutesInLocRib` statistic reported by the router
+    pub loc_rib_routes: Option<u64>,
+    /// Routes held by the collector for the peer, see
+    /// [`IntrospectionSnapshot::with_view`]
+    pub collected_routes: Option<usize>,
+}
+
+/// State of a BMP session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouterSnapshot {
+    pub router: AddrInfo,
+    /// Time the first message of the session was received
+    pub connected_since: DateTime<Utc>,
+    pub sys_name: Option<String>,
+    pub sys_descr: Option<String>,
+    pub messages: u64,
+    pub peers: Vec<PeerSnapshot>,
+}
+
+/// Serializable state of all the BMP sessions, routers and peers are sorted
+/// by address
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntrospectionSnapshot {
+    pub routers: Vec<RouterSnapshot>,
+}
+
+impl IntrospectionSnapshot {
+    /// Number of peers that are up across all the routers
+    pub fn peers(&self) -> usize {
+        self.routers.iter().map(|router| router.peers.len()).sum()
+    }
+
+    /// Fill [`PeerSnapshot::collected_routes`] with the routes `view` holds
+    /// for each peer. `view` must use the same [`PeerKeyStrategy`] as the
+    /// introspector.
+    pub fn with_view(mut self, view: &MultiRouterView) -> Self {
+        let routes = view.routes_per_peer();
+        for router in &mut self.routers {
+            let addr = router.router.remote_socket().ip();
+            for peer in &mut router.peers {
+                peer.collected_routes = Some(routes.get(&(addr, peer.peer)).copied().unwrap_or(0));
+            }
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RouterState {
+    connected_since: DateTime<Utc>,
+    sys_name: Option<String>,
+    sys_descr: Option<String>,
+    messages: u64,
+    peers: HashMap<PeerKey, PeerSnapshot>,
+}
+
+impl RouterState {
+    fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            connected_since: now,
+            sys_name: None,
+            sys_descr: None,
+            messages: 0,
+            peers: HashMap::new(),
+        }
+    }
+}
+
+/// Capabilities of `received` that are also advertised in `sent`
+fn negotiated_capabilities(sent: &BgpMessage, received: &BgpMessage) -> Vec<BgpCapability> {
+    let (sent, received) = match (sent, received) {
+        (BgpMessage::Open(sent), BgpMessage::Open(received)) => (sent, received),
+        _ => return vec![],
+    };
+    let sent = sent.capabilities();
+    received
+        .capabilities()
+        .filter(|cap| {
+            sent.clone().any(|sent_cap| match cap {
+                BgpCapability::MultiProtocolExtensions(_) => sent_cap == *cap,
+                _ => sent_cap.code() == cap.code(),
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+/// Tracks the BMP sessions to answer what the collector currently knows about
+/// the routers and their peers. The state of a session is dropped when its
+/// connection ends.
+#[derive(Debug)]
+pub struct BmpIntrospector {
+    peer_key_strategy: PeerKeyStrategy,
+    routers: HashMap<AddrInfo, RouterState>,
+}
+
+impl Default for BmpIntrospector {
+    fn default() -> Self {
+        Self::new(PeerKeyStrategy::AddressAndRd)
+    }
+}
+
+impl BmpIntrospector {
+    /// Create an introspector identifying the monitored peers with the given
+    /// strategy. [`PeerKeyStrategy::AddressAndRd`] is used by default, so the
+    /// Route Monitoring messages of post-policy or Adj-RIB-Out are counted
+    /// for the same peer.
+    pub fn new(peer_key_strategy: PeerKeyStrategy) -> Self {
+        Self {
+            peer_key_strategy,
+            routers: HashMap::new(),
+        }
+    }
+
+    pub const fn peer_key_strategy(&self) -> PeerKeyStrategy {
+        self.peer_key_strategy
+    }
+
+    /// Number of routers with an ongoing session
+    pub fn routers(&self) -> usize {
+        self.routers.len()
+    }
+
+    /// Update the state with a request received by the BMP server, the end of
+    /// the connection or decoding errors close the session of the router.
+    pub fn observe_request(&mut self, request: &BmpRequest) {
+        match request {
+            Ok(tagged) => match tagged.value() {
+                Some(msg) => self.observe(tagged.tag(), msg),
+                None => self.remove_router(&tagged.tag()),
+            },
+            Err(tagged) => self.remove_router(&tagged.tag()),
+        }
+    }
+
+    /// Update the state with the next message received from `router`
+    pub fn observe(&mut self, router: AddrInfo, msg: &BmpMessage) {
+        let value = msg.to_v3_value();
+        let now = Utc::now();
+        let peer_key_strategy = self.peer_key_strategy;
+        let peer_key =
+            |header: &PeerHeader| peer_key_strategy.apply(PeerKey::from_peer_header(header));
+        let state = self
+            .routers
+            .entry(router)
+            .or_insert_with(|| RouterState::new(now));
+        state.messages += 1;
+        match value.as_ref() {
+            BmpMessageValue::Initiation(init) => {
+                for info in init.information() {
+                    match info {
+                        InitiationInformation::SystemName(name) => {
+                            state.sys_name = Some(name.clone())
+                        }
+                        InitiationInformation::SystemDescription(descr) => {
+                            state.sys_descr = Some(descr.clone())
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            BmpMessageValue::PeerUpNotification(peer_up) => {
+                let header = peer_up.peer_header();
+                let hold_time = match (peer_up.sent_message(), peer_up.received_message()) {
+                    (BgpMessage::Open(sent), BgpMessage::Open(received)) => {
+                        sent.hold_time().min(received.hold_time())
+                    }
+                    _ => 0,
+                };
+                let peer = peer_key(header);
+                state.peers.insert(
+                    peer,
+                    PeerSnapshot {
+                        peer,
+                        up_since: header.timestamp().copied().unwrap_or(now),
+                        local_address: peer_up.local_address(),
+                        local_port: peer_up.local_port(),
+                        remote_port: peer_up.remote_port(),
+                        hold_time,
+                        negotiated_capabilities: negotiated_capabilities(
+                            peer_up.sent_message(),
+                            peer_up.received_message(),
+                        ),
+                        route_monitoring_messages: 0,
+                        adj_rib_in_routes: None,
+                        loc_rib_routes: None,
+                        collected_routes: None,
+                    },
+                );
+            }
+            BmpMessageValue::PeerDownNotification(peer_down) => {
+                state.peers.remove(&peer_key(peer_down.peer_header()));
+            }
+            BmpMessageValue::RouteMonitoring(route_monitoring) => {
+                if let Some(peer) = state
+                    .peers
+                    .get_mut(&peer_key(route_monitoring.peer_header()))
+                {
+                    peer.route_monitoring_messages += 1;
+                }
+            }
+            BmpMessageValue::StatisticsReport(stats) => {
+                if let Some(peer) = state.peers.get_mut(&peer_key(stats.peer_header())) {
+                    for counter in stats.counters() {
+                        match counter {
+                            StatisticsCounter::NumberOfRoutesInAdjRibIn(gauge) => {
+                                peer.adj_rib_in_routes = Some(gauge.value())
+                            }
+                            StatisticsCounter::NumberOfRoutesInLocRib(gauge) => {
+                                peer.loc_rib_routes = Some(gauge.value())
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            BmpMessageValue::Termination(_) => {
+                state.peers.clear();
+            }
+            _ => {}
+        }
+    }
+
+    /// Forget the session of `router`
+    pub fn remove_router(&mut self, router: &AddrInfo) {
+        self.routers.remove(router);
+    }
+
+    /// Current state of all the sessions
+    pub fn snapshot(&self) -> IntrospectionSnapshot {
+        let mut routers = self
+            .routers
+            .iter()
+            .map(|(router, state)| {
+                let mut peers = state.peers.values().cloned().collect::<Vec<_>>();
+                peers.sort_by_key(|peer| {
+                    (
+                        peer.peer.peer_address(),
+                        peer.peer.asn(),
+                        peer.peer.bgp_id(),
+                    )
+                });
+                RouterSnapshot {
+                    router: *router,
+                    connected_since: state.connected_since,
+                    sys_name: state.sys_name.clone(),
+                    sys_descr: state.sys_descr.clone(),
+                    messages: state.messages,
+                    peers,
+                }
+            })
+            .collect::<Vec<_>>();
+        routers.sort_by_key(|router| router.router);
+        IntrospectionSnapshot { routers }
+    }
+}
+
+/// [`Layer`] recording every request in a shared [`BmpIntrospector`] before
+/// handing it to the inner BMP service. The introspector is shared by all the
+/// services of the layer, so [`IntrospectionLayer::snapshot`] covers all the
+/// routers connected to the server.
+#[derive(Debug, Clone)]
+pub struct IntrospectionLayer {
+    introspector: Arc<Mutex<BmpIntrospector>>,
+}
+
+impl IntrospectionLayer {
+    pub fn new(introspector: BmpIntrospector) -> Self {
+        Self {
+            introspector: Arc::new(Mutex::new(introspector)),
+        }
+    }
+
+    /// The shared introspector, to take snapshots while the server is running
+    pub fn introspector(&self) -> Arc<Mutex<BmpIntrospector>> {
+        self.introspector.clone()
+    }
+
+    /// Current state of all the sessions seen by the layer
+    pub fn snapshot(&self) -> IntrospectionSnapshot {
+        self.introspector
+            .lock()
+            .expect("introspector lock is poisoned")
+            .snapshot()
+    }
+}
+
+impl<S> Layer<S> for IntrospectionLayer {
+    type Service = IntrospectionService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        IntrospectionService {
+            inner,
+            introspector: self.introspector.clone(),
+        }
+    }
+}
+
+/// Service created by [`IntrospectionLayer`]
+#[derive(Debug, Clone)]
+pub struct IntrospectionService<S> {
+    inner: S,
+    introspector: Arc<Mutex<BmpIntrospector>>,
+}
+
+impl<S> Service<BmpRequest> for IntrospectionService<S>
+where
+    S: Service<BmpRequest>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: BmpRequest) -> Self::Future {
+        self.introspector
+            .lock()
+            .expect("introspector lock is poisoned")
+            .observe_request(&request);
+        self.inner.call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use netgauze_bgp_pkt::{
+        capabilities::{FourOctetAsCapability, MultiProtocolExtensionsCapability},
+        open::{BgpOpenMessage, BgpOpenMessageParameter},
+        update::BgpUpdateMessage,
+    };
+    use netgauze_bmp_pkt::{
+        BmpPeerType, GaugeU64, InitiationMessage, PeerDownNotificationMessage,
+        PeerDownNotificationReason, PeerUpNotificationMessage, RouteMonitoringMessage,
+        StatisticsReportMessage,
+    };
+    use netgauze_iana::address_family::AddressType;
+    use std::net::Ipv4Addr;
+
+    fn router() -> AddrInfo {
+        AddrInfo::new(
+            "192.0.2.100:1790".parse().unwrap(),
+            "192.0.2.1:40000".parse().unwrap(),
+        )
+    }
+
+    fn peer_header(post_policy: bool) -> PeerHeader {
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10))),
+            65000,
+            Ipv4Addr::new(192, 0, 2, 10),
+            Some(Utc.timestamp_opt(1680000000, 0).unwrap()),
+        )
+    }
+
+    fn open(hold_time: u16, capabilities: Vec<BgpCapability>) -> BgpMessage {
+        BgpMessage::Open(BgpOpenMessage::new(
+            65000,
+            hold_time,
+            Ipv4Addr::LOCALHOST,
+            vec![BgpOpenMessageParameter::Capabilities(capabilities)],
+        ))
+    }
+
+    fn peer_up() -> BmpMessage {
+        let ipv4 = BgpCapability::MultiProtocolExtensions(MultiProtocolExtensionsCapability::new(
+            AddressType::Ipv4Unicast,
+        ));
+        let ipv6 = BgpCapability::MultiProtocolExtensions(MultiProtocolExtensionsCapability::new(
+            AddressType::Ipv6Unicast,
+        ));
+        let asn4 = BgpCapability::FourOctetAs(FourOctetAsCapability::new(65000));
+        BmpMessage::V3(BmpMessageValue::PeerUpNotification(
+            PeerUpNotificationMessage::build(
+                peer_header(false),
+                Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))),
+                Some(179),
+                Some(40000),
+                open(90, vec![ipv4.clone(), asn4.clone()]),
+                open(180, vec![ipv4, ipv6, asn4, BgpCapability::RouteRefresh]),
+                vec![],
+            )
+            .unwrap(),
+        ))
+    }
+
+    fn route_monitoring(post_policy: bool) -> BmpMessage {
+        BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            RouteMonitoringMessage::build(
+                peer_header(post_policy),
+                BgpMessage::Update(BgpUpdateMessage::new(vec![], vec![], vec![])),
+            )
+            .unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_introspector() {
+        let mut introspector = BmpIntrospector::default();
+        let initiation = BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(vec![
+            InitiationInformation::SystemName("router1".to_string()),
+        ])));
+        let stats = BmpMessage::V3(BmpMessageValue::StatisticsReport(
+            StatisticsReportMessage::new(
+                peer_header(false),
+                vec![StatisticsCounter::NumberOfRoutesInAdjRibIn(GaugeU64::new(
+                    42,
+                ))],
+            ),
+        ));
+        for msg in [
+            initiation,
+            peer_up(),
+            route_monitoring(false),
+            route_monitoring(true),
+            stats,
+        ] {
+            introspector.observe(router(), &msg);
+        }
+
+        let snapshot = introspector.snapshot();
+        assert_eq!(snapshot.routers.len(), 1);
+        assert_eq!(snapshot.peers(), 1);
+        let router_snapshot = &snapshot.routers[0];
+        assert_eq!(router_snapshot.sys_name, Some("router1".to_string()));
+        assert_eq!(router_snapshot.messages, 5);
+        let peer = &router_snapshot.peers[0];
+        assert_eq!(peer.up_since, Utc.timestamp_opt(1680000000, 0).unwrap());
+        assert_eq!(peer.hold_time, 90);
+        assert_eq!(
+            peer.negotiated_capabilities,
+            vec![
+                BgpCapability::MultiProtocolExtensions(MultiProtocolExtensionsCapability::new(
+                    AddressType::Ipv4Unicast,
+                )),
+                BgpCapability::FourOctetAs(FourOctetAsCapability::new(65000)),
+            ]
+        );
+        // Post-policy messages are counted for the same peer
+        assert_eq!(peer.route_monitoring_messages, 2);
+        assert_eq!(peer.adj_rib_in_routes, Some(42));
+        assert_eq!(peer.loc_rib_routes, None);
+        assert_eq!(peer.collected_routes, None);
+        assert_eq!(
+            snapshot
+                .clone()
+                .with_view(
+                    &MultiRouterView::new().with_peer_key_strategy(PeerKeyStrategy::AddressAndRd)
+                )
+                .routers[0]
+                .peers[0]
+                .collected_routes,
+            Some(0)
+        );
+        assert!(serde_json::to_string(&snapshot).is_ok());
+
+        let peer_down = BmpMessage::V3(BmpMessageValue::PeerDownNotification(
+            PeerDownNotificationMessage::build(
+                peer_header(false),
+                PeerDownNotificationReason::RemoteSystemClosedNoData,
+            )
+            .unwrap(),
+        ));
+        introspector.observe(router(), &peer_down);
+        assert_eq!(introspector.snapshot().peers(), 0);
+
+        // End of the connection
+        introspector.observe_request(&Ok(crate::TaggedData::new(router(), None)));
+        assert_eq!(introspector.routers(), 0);
+    }
+}
@@ -0,0 +1,665 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reconciliation of the observed BMP state against a declared inventory.
+//!
+//! Operators declare in an [`Inventory`] the routers expected to connect and,
+//! for each of them, the peers expected to be up with their AS number and
+//! address families. [`InventoryReconciler`] follows the BMP sessions and
+//! compares what the routers report with the inventory, each difference is a
+//! [`Discrepancy`]. Reconciling reports the discrepancies that appeared since
+//! the last reconciliation, and the ones that disappeared, as
+//! [`InventoryEvent`], so a discrepancy is reported once until it's resolved.
+//!
+//! [`InventoryLayer`] wraps a BMP service to reconcile whenever a router or a
+//! peer comes and goes, and [`InventoryLayer::run`] reconciles periodically,
+//! e.g., to report the routers that never connect.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs::File,
+    io::BufReader,
+    net::IpAddr,
+    path::Path,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use netgauze_bgp_pkt::nlri::RouteDistinguisher;
+use netgauze_bmp_pkt::{BmpMessage, BmpMessageValue, PeerKey};
+use netgauze_iana::address_family::AddressType;
+
+use crate::{capability_drift::NegotiatedCapabilities, server::BmpRequest, AddrInfo};
+
+/// A BGP peer expected to be up on a router
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpectedPeer {
+    pub address: IpAddr,
+    /// Route distinguisher of the VRF of the peer, `None` for global peers
+    #[serde(default)]
+    pub rd: Option<RouteDistinguisher>,
+    /// Expected AS number of the peer, any when not set
+    #[serde(default)]
+    pub asn: Option<u32>,
+    /// Address families expected to be negotiated with the peer
+    #[serde(default)]
+    pub families: BTreeSet<AddressType>,
+}
+
+/// A router expected to connect, identified by its IP address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpectedRouter {
+    pub address: IpAddr,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub peers: Vec<ExpectedPeer>,
+}
+
+/// Routers and peers expected to be monitored
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Inventory {
+    pub routers: Vec<ExpectedRouter>,
+}
+
+impl Inventory {
+    /// Read an inventory from a JSON file
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+}
+
+/// Difference between the inventory and the observed state. Peers are
+/// identified by their address and route distinguisher.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Discrepancy {
+    /// The router has no BMP session
+    MissingRouter { router: IpAddr },
+
+    /// A router that is not in the inventory has a BMP session
+    UnexpectedRouter { router: IpAddr },
+
+    /// The peer is not up on the router
+    MissingPeer {
+        router: IpAddr,
+        peer: IpAddr,
+        rd: Option<RouteDistinguisher>,
+    },
+
+    /// A peer that is not in the inventory is up on the router
+    UnexpectedPeer {
+        router: IpAddr,
+        peer: IpAddr,
+        rd: Option<RouteDistinguisher>,
+    },
+
+    /// The peer is up with another AS number
+    AsnMismatch {
+        router: IpAddr,
+        peer: IpAddr,
+        rd: Option<RouteDistinguisher>,
+        expected: u32,
+        found: u32,
+    },
+
+    /// The peer is up, but some of the expected address families are not
+    /// negotiated
+    MissingFamilies {
+        router: IpAddr,
+        peer: IpAddr,
+        rd: Option<RouteDistinguisher>,
+        families: Vec<AddressType>,
+    },
+}
+
+/// Change of the discrepancies between two reconciliations
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InventoryEvent {
+    Raised(Discrepancy),
+    Cleared(Discrepancy),
+}
+
+/// A peer that is up, as reported in its Peer Up message
+#[derive(Debug, Clone)]
+struct ObservedPeer {
+    asn: u32,
+    /// `None` when the Peer Up message doesn't carry the OPEN messages
+    families: Option<BTreeSet<AddressType>>,
+}
+
+/// Follows the BMP sessions and reconciles them against an [`Inventory`].
+///
+/// Discrepancies are only raised once they last for the grace period, so the
+/// routers and peers have time to come up when the collector starts and
+/// sessions flapping shortly are not reported.
+#[derive(Debug)]
+pub struct InventoryReconciler {
+    inventory: Inventory,
+    grace_period: Duration,
+    sessions: HashMap<AddrInfo, HashMap<PeerKey, ObservedPeer>>,
+    /// Discrepancies waiting for the grace period, with the time they were
+    /// first seen
+    pending: BTreeMap<Discrepancy, Instant>,
+    raised: BTreeSet<Discrepancy>,
+}
+
+impl InventoryReconciler {
+    pub fn new(inventory: Inventory) -> Self {
+        Self {
+            inventory,
+            grace_period: Duration::ZERO,
+            sessions: HashMap::new(),
+            pending: BTreeMap::new(),
+            raised: BTreeSet::new(),
+        }
+    }
+
+    /// How long a discrepancy must last before it's raised, none by default
+    pub const fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    pub const fn inventory(&self) -> &Inventory {
+        &self.inventory
+    }
+
+    pub const fn grace_period(&self) -> Duration {
+        self.grace_period
+    }
+
+    /// Discrepancies raised and not cleared yet
+    pub const fn raised(&self) -> &BTreeSet<Discrepancy> {
+        &self.raised
+    }
+
+    /// Replace the inventory, e.g., after it's edited, the changes are
+    /// reported by the next reconciliation
+    pub fn set_inventory(&mut self, inventory: Inventory) {
+        self.inventory = inventory;
+    }
+
+    /// Process a request received by the BMP server, returns if the observed
+    /// routers or peers changed
+    pub fn observe_request(&mut self, request: &BmpRequest) -> bool {
+        match request {
+            Ok(tagged) => match tagged.value() {
+                Some(msg) => self.observe(tagged.tag(), msg),
+                // The connection is closed
+                None => self.sessions.remove(&tagged.tag()).is_some(),
+            },
+            Err(_) => false,
+        }
+    }
+
+    /// Process the next message received from `router`, returns if the
+    /// observed routers or peers changed
+    pub fn observe(&mut self, router: AddrInfo, msg: &BmpMessage) -> bool {
+        let value = msg.to_v3_value();
+        if let BmpMessageValue::Termination(_) = value.as_ref() {
+            return self.sessions.remove(&router).is_some();
+        }
+        let mut changed = !self.sessions.contains_key(&router);
+        let peers = self.sessions.entry(router).or_default();
+        match value.as_ref() {
+            BmpMessageValue::PeerUpNotification(peer_up) => {
+                let observed = ObservedPeer {
+                    asn: peer_up.peer_header().peer_as(),
+                    families: NegotiatedCapabilities::from_peer_up(peer_up)
+                        .map(|capabilities| capabilities.families().clone()),
+                };
+                peers.insert(PeerKey::from_peer_header(peer_up.peer_header()), observed);
+                changed = true;
+            }
+            BmpMessageValue::PeerDownNotification(peer_down) => {
+                changed |= peers
+                    .remove(&PeerKey::from_peer_header(peer_down.peer_header()))
+                    .is_some();
+            }
+            _ => {}
+        }
+        changed
+    }
+
+    /// Differences between the inventory and the current state
+    pub fn discrepancies(&self) -> BTreeSet<Discrepancy> {
+        // Peers that are up per router IP, the pre- and post-policy, or
+        // Adj-RIB-In and Adj-RIB-Out, views of a peer are merged
+        let mut observed: HashMap<IpAddr, BTreeMap<(IpAddr, Option<RouteDistinguisher>), _>> =
+            HashMap::new();
+        for (session, peers) in &self.sessions {
+            let router = observed.entry(session.remote_socket().ip()).or_default();
+            for (key, peer) in peers {
+                // Loc-RIB peers have no address
+                if let Some(address) = key.peer_address() {
+                    router.insert((address, key.rd()), peer);
+                }
+            }
+        }
+
+        let mut discrepancies = BTreeSet::new();
+        for expected_router in &self.inventory.routers {
+            let router = expected_router.address;
+            let Some(peers) = observed.remove(&router) else {
+                discrepancies.insert(Discrepancy::MissingRouter { router });
+                continue;
+            };
+            for expected in &expected_router.peers {
+                let (peer, rd) = (expected.address, expected.rd);
+                let Some(observed) = peers.get(&(peer, rd)) else {
+                    discrepancies.insert(Discrepancy::MissingPeer { router, peer, rd });
+                    continue;
+                };
+                match expected.asn {
+                    Some(asn) if asn != observed.asn => {
+                        discrepancies.insert(Discrepancy::AsnMismatch {
+                            router,
+                            peer,
+                            rd,
+                            expected: asn,
+                            found: observed.asn,
+                        });
+                    }
+                    _ => {}
+                }
+                if let Some(families) = &observed.families {
+                    let missing = expected
+                        .families
+                        .difference(families)
+                        .copied()
+                        .collect::<Vec<_>>();
+                    if !missing.is_empty() {
+                        discrepancies.insert(Discrepancy::MissingFamilies {
+                            router,
+                            peer,
+                            rd,
+                            families: missing,
+                        });
+                    }
+                }
+            }
+            for (peer, rd) in peers.keys() {
+                if !expected_router
+                    .peers
+                    .iter()
+                    .any(|expected| expected.address == *peer && expected.rd == *rd)
+                {
+                    discrepancies.insert(Discrepancy::UnexpectedPeer {
+                        router,
+                        peer: *peer,
+                        rd: *rd,
+                    });
+                }
+            }
+        }
+        // The routers left are not in the inventory
+        for router in observed.into_keys() {
+            discrepancies.insert(Discrepancy::UnexpectedRouter { router });
+        }
+        discrepancies
+    }
+
+    /// Compare the current state with the inventory, and report the changes
+    /// since the last reconciliation
+    pub fn reconcile(&mut self) -> Vec<InventoryEvent> {
+        self.reconcile_at(Instant::now())
+    }
+
+    pub fn reconcile_at(&mut self, now: Instant) -> Vec<InventoryEvent> {
+        let current = self.discrepancies();
+        let mut events = vec![];
+        let cleared = self
+            .raised
+            .difference(&current)
+            .cloned()
+            .collect::<Vec<_>>();
+        for discrepancy in cleared {
+            self.raised.remove(&discrepancy);
+            events.push(InventoryEvent::Cleared(discrepancy));
+        }
+        self.pending
+            .retain(|discrepancy, _| current.contains(discrepancy));
+        for discrepancy in current {
+            if self.raised.contains(&discrepancy) {
+                continue;
+            }
+            let first_seen = *self.pending.entry(discrepancy.clone()).or_insert(now);
+            if now.saturating_duration_since(first_seen) >= self.grace_period {
+                self.pending.remove(&discrepancy);
+                self.raised.insert(discrepancy.clone());
+                events.push(InventoryEvent::Raised(discrepancy));
+            }
+        }
+        events
+    }
+}
+
+/// Log the events and send them to `tx`
+fn emit(events: Vec<InventoryEvent>, tx: &mpsc::UnboundedSender<InventoryEvent>) {
+    for event in events {
+        match &event {
+            InventoryEvent::Raised(discrepancy) => {
+                tracing::warn!(?discrepancy, "BMP state differs from the inventory")
+            }
+            InventoryEvent::Cleared(discrepancy) => {
+                tracing::info!(?discrepancy, "BMP state matches the inventory again")
+            }
+        }
+        // The receiver is optional, events are still logged when it's dropped
+        let _ = tx.send(event);
+    }
+}
+
+/// [`Layer`] feeding a shared [`InventoryReconciler`] with the requests of the
+/// inner BMP service, and reconciling when a router or a peer comes or goes.
+/// Events are logged and sent to the given channel, requests are passed
+/// unchanged to the inner service.
+#[derive(Debug, Clone)]
+pub struct InventoryLayer {
+    reconciler: Arc<Mutex<InventoryReconciler>>,
+    events: mpsc::UnboundedSender<InventoryEvent>,
+}
+
+impl InventoryLayer {
+    pub fn new(
+        reconciler: InventoryReconciler,
+        events: mpsc::UnboundedSender<InventoryEvent>,
+    ) -> Self {
+        Self {
+            reconciler: Arc::new(Mutex::new(reconciler)),
+            events,
+        }
+    }
+
+    /// The shared reconciler, e.g., to update the inventory
+    pub fn reconciler(&self) -> Arc<Mutex<InventoryReconciler>> {
+        self.reconciler.clone()
+    }
+
+    /// Reconcile now
+    pub fn reconcile(&self) {
+        let events = self
+            .reconciler
+            .lock()
+            .expect("inventory reconciler lock is poisoned")
+            .reconcile();
+        emit(events, &self.events);
+    }
+
+    /// Reconcile every `period`, needed to raise the discrepancies once their
+    /// grace period is over and the routers that never connect
+    pub async fn run(self, period: Duration) {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            self.reconcile();
+        }
+    }
+}
+
+impl<S> Layer<S> for InventoryLayer {
+    type Service = InventoryService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        InventoryService {
+            inner,
+            reconciler: self.reconciler.clone(),
+            events: self.events.clone(),
+        }
+    }
+}
+
+/// Service created by [`InventoryLayer`]
+#[derive(Debug, Clone)]
+pub struct InventoryService<S> {
+    inner: S,
+    reconciler: Arc<Mutex<InventoryReconciler>>,
+    events: mpsc::UnboundedSender<InventoryEvent>,
+}
+
+impl<S> Service<BmpRequest> for InventoryService<S>
+where
+    S: Service<BmpRequest>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: BmpRequest) -> Self::Future {
+        let events = {
+            let mut reconciler = self
+                .reconciler
+                .lock()
+                .expect("inventory reconciler lock is poisoned");
+            if reconciler.observe_request(&request) {
+                reconciler.reconcile()
+            } else {
+                vec![]
+            }
+        };
+        emit(events, &self.events);
+        self.inner.call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use netgauze_bgp_pkt::{
+        capabilities::{BgpCapability, MultiProtocolExtensionsCapability},
+        open::{BgpOpenMessage, BgpOpenMessageParameter},
+        BgpMessage,
+    };
+    use netgauze_bmp_pkt::{
+        BmpPeerType, PeerDownNotificationMessage, PeerDownNotificationReason, PeerHeader,
+        PeerUpNotificationMessage,
+    };
+    use std::net::Ipv4Addr;
+
+    const ROUTER: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+    const PEER: IpAddr = IpAddr::V4(Ipv4Addr::new(203, 0, 113, 1));
+    const OTHER_PEER: IpAddr = IpAddr::V4(Ipv4Addr::new(203, 0, 113, 2));
+
+    fn router() -> AddrInfo {
+        AddrInfo::new(
+            "192.0.2.100:1790".parse().unwrap(),
+            "192.0.2.1:40000".parse().unwrap(),
+        )
+    }
+
+    fn peer_header(address: IpAddr, asn: u32) -> PeerHeader {
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(address),
+            asn,
+            Ipv4Addr::new(203, 0, 113, 1),
+            Some(Utc.timestamp_opt(1680000000, 0).unwrap()),
+        )
+    }
+
+    fn peer_up(address: IpAddr, asn: u32, families: &[AddressType]) -> BmpMessage {
+        let open = BgpMessage::Open(BgpOpenMessage::new(
+            64500,
+            180,
+            Ipv4Addr::new(203, 0, 113, 1),
+            vec![BgpOpenMessageParameter::Capabilities(
+                families
+                    .iter()
+                    .map(|family| {
+                        BgpCapability::MultiProtocolExtensions(
+                            MultiProtocolExtensionsCapability::new(*family),
+                        )
+                    })
+                    .collect(),
+            )],
+        ));
+        BmpMessage::V3(BmpMessageValue::PeerUpNotification(
+            PeerUpNotificationMessage::build(
+                peer_header(address, asn),
+                None,
+                None,
+                None,
+                open.clone(),
+                open,
+                vec![],
+            )
+            .unwrap(),
+        ))
+    }
+
+    fn peer_down(address: IpAddr, asn: u32) -> BmpMessage {
+        BmpMessage::V3(BmpMessageValue::PeerDownNotification(
+            PeerDownNotificationMessage::build(
+                peer_header(address, asn),
+                PeerDownNotificationReason::RemoteSystemClosedNoData,
+            )
+            .unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_inventory_reconciler() {
+        let inventory: Inventory = serde_json::from_value(serde_json::json!({
+            "routers": [{
+                "address": "192.0.2.1",
+                "peers": [{
+                    "address": "203.0.113.1",
+                    "asn": 64500,
+                    "families": ["Ipv4Unicast", "Ipv6Unicast"],
+                }],
+            }],
+        }))
+        .unwrap();
+        let mut reconciler = InventoryReconciler::new(inventory);
+        let missing_router = Discrepancy::MissingRouter { router: ROUTER };
+        let missing_peer = Discrepancy::MissingPeer {
+            router: ROUTER,
+            peer: PEER,
+            rd: None,
+        };
+        assert_eq!(
+            reconciler.reconcile(),
+            vec![InventoryEvent::Raised(missing_router.clone())]
+        );
+        // Discrepancies are reported once
+        assert_eq!(reconciler.reconcile(), vec![]);
+
+        let up = peer_up(PEER, 64501, &[AddressType::Ipv4Unicast]);
+        assert!(reconciler.observe(router(), &up));
+        assert_eq!(
+            reconciler.reconcile(),
+            vec![
+                InventoryEvent::Cleared(missing_router),
+                InventoryEvent::Raised(Discrepancy::AsnMismatch {
+                    router: ROUTER,
+                    peer: PEER,
+                    rd: None,
+                    expected: 64500,
+                    found: 64501,
+                }),
+                InventoryEvent::Raised(Discrepancy::MissingFamilies {
+                    router: ROUTER,
+                    peer: PEER,
+                    rd: None,
+                    families: vec![AddressType::Ipv6Unicast],
+                }),
+            ]
+        );
+
+        // Unexpected peer, and the expected one goes down
+        reconciler.observe(router(), &peer_up(OTHER_PEER, 64502, &[]));
+        reconciler.observe(router(), &peer_down(PEER, 64501));
+        let events = reconciler.reconcile();
+        assert_eq!(events.len(), 4);
+        assert!(events.contains(&InventoryEvent::Raised(missing_peer.clone())));
+        assert!(
+            events.contains(&InventoryEvent::Raised(Discrepancy::UnexpectedPeer {
+                router: ROUTER,
+                peer: OTHER_PEER,
+                rd: None,
+            }))
+        );
+
+        // The session ends
+        assert!(reconciler.observe_request(&Ok(crate::TaggedData::new(router(), None))));
+        assert_eq!(
+            reconciler.raised(),
+            &BTreeSet::from([
+                missing_peer.clone(),
+                Discrepancy::UnexpectedPeer {
+                    router: ROUTER,
+                    peer: OTHER_PEER,
+                    rd: None,
+                }
+            ])
+        );
+        let events = reconciler.reconcile();
+        assert!(
+            events.contains(&InventoryEvent::Raised(Discrepancy::MissingRouter {
+                router: ROUTER
+            }))
+        );
+        assert!(events.contains(&InventoryEvent::Cleared(missing_peer)));
+    }
+
+    #[test]
+    fn test_grace_period() {
+        let mut reconciler = InventoryReconciler::new(Inventory::default())
+            .with_grace_period(Duration::from_secs(60));
+        let start = Instant::now();
+        reconciler.observe(router(), &peer_up(PEER, 64500, &[]));
+        let unexpected = Discrepancy::UnexpectedRouter { router: ROUTER };
+        assert_eq!(reconciler.reconcile_at(start), vec![]);
+        assert_eq!(
+            reconciler.reconcile_at(start + Duration::from_secs(60)),
+            vec![InventoryEvent::Raised(unexpected.clone())]
+        );
+        assert!(reconciler.observe_request(&Ok(crate::TaggedData::new(router(), None))));
+        assert_eq!(
+            reconciler.reconcile_at(start + Duration::from_secs(61)),
+            vec![InventoryEvent::Cleared(unexpected)]
+        );
+
+        // Flapping shorter than the grace period is not reported
+        reconciler.observe(router(), &peer_up(PEER, 64500, &[]));
+        assert_eq!(
+            reconciler.reconcile_at(start + Duration::from_secs(62)),
+            vec![]
+        );
+        reconciler.observe_request(&Ok(crate::TaggedData::new(router(), None)));
+        assert_eq!(
+            reconciler.reconcile_at(start + Duration::from_secs(200)),
+            vec![]
+        );
+    }
+}
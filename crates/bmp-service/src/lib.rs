@@ -21,9 +21,33 @@ use std::{
     net::SocketAddr,
 };
 
+pub mod capability_drift;
+pub mod conformance;
 pub mod handle;
+pub mod introspection;
+pub mod inventory;
+pub mod listener;
+pub mod loadgen;
+pub mod origin;
+pub mod pacing;
+pub mod packing;
+pub mod prefix_limit;
+pub mod recorder;
+pub mod reflection;
+pub mod rib_stats;
+pub mod rib_store;
+#[cfg(all(feature = "sctp", target_os = "linux"))]
+pub mod sctp;
+pub mod sequence;
 pub mod server;
+pub mod sink;
+pub mod stats;
+pub mod stats_scheduler;
 pub mod transport;
+pub mod unknown_codes;
+pub mod view;
+#[cfg(feature = "webhook")]
+pub mod webhook;
 
 /// Capture the address of both sides of a socket
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Serialize, Deserialize)]
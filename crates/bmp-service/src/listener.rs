@@ -0,0 +1,412 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sockets the [`crate::server::BmpServer`] can accept BMP connections on.
+//!
+//! Besides plain TCP, Unix domain sockets are supported for deployments where
+//! BMP is proxied locally (e.g. by a sidecar container), as well as listening
+//! sockets inherited from systemd socket activation (`LISTEN_FDS`). With the
+//! `sctp` feature, on Linux, BMP can be received over SCTP as well.
+
+use std::{
+    fmt::{Display, Formatter},
+    io,
+    net::{IpAddr, Ipv6Addr, SocketAddr},
+};
+#[cfg(unix)]
+use std::{
+    ops::Range,
+    os::unix::io::{FromRawFd, RawFd},
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use tokio::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::AddrInfo;
+
+/// First file descriptor passed by systemd socket activation, see
+/// [sd_listen_fds(3)](https://www.freedesktop.org/software/systemd/man/sd_listen_fds.html)
+#[cfg(unix)]
+pub const SD_LISTEN_FDS_START: RawFd = 3;
+
+/// Where the BMP server listens for incoming connections
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BmpListenerConfig {
+    /// Bind a TCP listener to the given socket address
+    Tcp(SocketAddr),
+
+    /// Bind a Unix domain socket listener to the given path. Unix domain
+    /// sockets don't carry IP addresses, hence connections are tagged with
+    /// [`unix_addr_info`].
+    #[cfg(unix)]
+    Unix(PathBuf),
+
+    /// Use a listening socket (either TCP or Unix domain) inherited from
+    /// systemd socket activation. The value is the index of the socket among
+    /// the ones passed in `LISTEN_FDS`, i.e., `0` refers to file
+    /// descriptor [`SD_LISTEN_FDS_START`].
+    #[cfg(unix)]
+    Systemd(usize),
+
+    /// Bind an SCTP listener to all the local addresses of the
+    /// [`crate::sctp::SctpConfig`]. Connections are tagged with the primary
+    /// address of each side.
+    #[cfg(all(feature = "sctp", target_os = "linux"))]
+    Sctp(crate::sctp::SctpConfig),
+}
+
+impl Display for BmpListenerConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "tcp://{addr}"),
+            #[cfg(unix)]
+            Self::Unix(path) => write!(f, "unix://{}", path.display()),
+            #[cfg(unix)]
+            Self::Systemd(index) => write!(f, "systemd://{index}"),
+            #[cfg(all(feature = "sctp", target_os = "linux"))]
+            Self::Sctp(config) => write!(f, "sctp://{config}"),
+        }
+    }
+}
+
+impl BmpListenerConfig {
+    /// IP addresses to listen on, empty for Unix domain sockets and for
+    /// sockets passed by systemd, whose address isn't known before binding
+    pub fn local_addrs(&self) -> Vec<SocketAddr> {
+        match self {
+            Self::Tcp(addr) => vec![*addr],
+            #[cfg(unix)]
+            Self::Unix(_) | Self::Systemd(_) => vec![],
+            #[cfg(all(feature = "sctp", target_os = "linux"))]
+            Self::Sctp(config) => config.local_addrs().to_vec(),
+        }
+    }
+}
+
+impl From<SocketAddr> for BmpListenerConfig {
+    fn from(value: SocketAddr) -> Self {
+        Self::Tcp(value)
+    }
+}
+
+/// Socket address used as the local socket of connections accepted on a Unix
+/// domain socket
+pub const UNIX_LOCAL_SOCKET: SocketAddr = SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0);
+
+/// Prefix of the remote addresses of the Unix domain socket connections, the
+/// discard-only prefix `100::/64` of
+/// [RFC6666](https://datatracker.ietf.org/doc/html/rfc6666)
+#[cfg(unix)]
+const UNIX_REMOTE_PREFIX: u128 = 0x0100 << 112;
+
+/// Unix domain sockets don't have IP addresses to distinguish between
+/// connected peers. Hence, connections are tagged with the unspecified IPv6
+/// address as local socket, and a per-connection sequence number in the
+/// interface identifier of a `100::/64` address as remote socket, so the
+/// connections of a long-running server never share an [`AddrInfo`].
+#[cfg(unix)]
+pub fn unix_addr_info(sequence: u64) -> AddrInfo {
+    AddrInfo::new(
+        UNIX_LOCAL_SOCKET,
+        SocketAddr::new(
+            IpAddr::V6(Ipv6Addr::from(UNIX_REMOTE_PREFIX | u128::from(sequence))),
+            0,
+        ),
+    )
+}
+
+/// Return the range of file descriptors passed by systemd socket activation,
+/// given the values of the `LISTEN_PID` and `LISTEN_FDS` environment variables
+/// and the current process id.
+#[cfg(unix)]
+pub fn systemd_listen_fds(
+    listen_pid: Option<&str>,
+    listen_fds: Option<&str>,
+    pid: u32,
+) -> io::Result<Range<RawFd>> {
+    let listen_pid = listen_pid
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "LISTEN_PID is not set"))?;
+    let listen_pid: u32 = listen_pid
+        .parse()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid LISTEN_PID value"))?;
+    if listen_pid != pid {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "LISTEN_PID doesn't match the current process",
+        ));
+    }
+    let listen_fds = listen_fds
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "LISTEN_FDS is not set"))?;
+    let listen_fds: RawFd = listen_fds
+        .parse()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid LISTEN_FDS value"))?;
+    Ok(SD_LISTEN_FDS_START..SD_LISTEN_FDS_START + listen_fds)
+}
+
+/// A bound listener for one of the [`BmpListenerConfig`] types
+#[derive(Debug)]
+pub(crate) enum BmpListener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, AtomicU64),
+    /// One-to-one SCTP sockets are driven as TCP sockets, but don't support
+    /// the TCP socket options
+    #[cfg(all(feature = "sctp", target_os = "linux"))]
+    Sctp(TcpListener),
+}
+
+/// An accepted BMP connection
+#[derive(Debug)]
+pub(crate) enum BmpConnection {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl BmpListener {
+    pub(crate) async fn bind(config: &BmpListenerConfig) -> io::Result<Self> {
+        match config {
+            BmpListenerConfig::Tcp(addr) => Ok(Self::Tcp(TcpListener::bind(addr).await?)),
+            #[cfg(unix)]
+            BmpListenerConfig::Unix(path) => {
+                Ok(Self::Unix(UnixListener::bind(path)?, AtomicU64::new(0)))
+            }
+            #[cfg(unix)]
+            BmpListenerConfig::Systemd(index) => {
+                let fds = systemd_listen_fds(
+                    std::env::var("LISTEN_PID").ok().as_deref(),
+                    std::env::var("LISTEN_FDS").ok().as_deref(),
+                    std::process::id(),
+                )?;
+                let fd = fds.start + *index as RawFd;
+                if !fds.contains(&fd) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("no socket with index {index} is passed in LISTEN_FDS"),
+                    ));
+                }
+                Self::from_raw_fd(fd)
+            }
+            #[cfg(all(feature = "sctp", target_os = "linux"))]
+            BmpListenerConfig::Sctp(config) => Ok(Self::Sctp(config.listen()?)),
+        }
+    }
+
+    /// Take ownership of an already bound listening socket
+    #[cfg(unix)]
+    fn from_raw_fd(fd: RawFd) -> io::Result<Self> {
+        // SAFETY: the file descriptor is passed to the process by systemd
+        // and is owned by the listener from now on.
+        let tcp = unsafe { std::net::TcpListener::from_raw_fd(fd) };
+        if tcp.local_addr().is_ok() {
+            tcp.set_nonblocking(true)?;
+            #[cfg(all(feature = "sctp", target_os = "linux"))]
+            if crate::sctp::is_sctp_socket(fd) {
+                return Ok(Self::Sctp(TcpListener::from_std(tcp)?));
+            }
+            return Ok(Self::Tcp(TcpListener::from_std(tcp)?));
+        }
+        // Not an IP socket, try again as a Unix domain socket
+        let fd = std::os::unix::io::IntoRawFd::into_raw_fd(tcp);
+        // SAFETY: ownership of the file descriptor is released by the TCP
+        // listener above
+        let unix = unsafe { std::os::unix::net::UnixListener::from_raw_fd(fd) };
+        unix.local_addr()?;
+        unix.set_nonblocking(true)?;
+        Ok(Self::Unix(UnixListener::from_std(unix)?, AtomicU64::new(0)))
+    }
+
+    /// Authenticate the connections of the given routers with TCP MD5 or
+    /// TCP-AO, only TCP listeners are supported
+    #[cfg(feature = "tcp-auth")]
+    pub(crate) fn set_tcp_auth_keys(
+        &self,
+        keys: &std::collections::HashMap<IpAddr, netgauze_tcp_auth::TcpAuthKey>,
+    ) -> io::Result<()> {
+        match self {
+            Self::Tcp(listener) => {
+                let ipv4 = listener.local_addr()?.is_ipv4();
+                for (router, key) in keys {
+                    // IPv6 routers can't connect to IPv4 sockets
+                    if ipv4 && router.is_ipv6() {
+                        continue;
+                    }
+                    netgauze_tcp_auth::set_tcp_auth_key(listener, *router, key)?;
+                }
+                Ok(())
+            }
+            #[cfg(unix)]
+            Self::Unix(_, _) if keys.is_empty() => Ok(()),
+            #[cfg(unix)]
+            Self::Unix(_, _) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "TCP authentication keys are not supported on Unix domain sockets",
+            )),
+            #[cfg(all(feature = "sctp", target_os = "linux"))]
+            Self::Sctp(_) if keys.is_empty() => Ok(()),
+            #[cfg(all(feature = "sctp", target_os = "linux"))]
+            Self::Sctp(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "TCP authentication keys are not supported on SCTP sockets",
+            )),
+        }
+    }
+
+    pub(crate) async fn accept(&self) -> io::Result<(BmpConnection, AddrInfo)> {
+        match self {
+            Self::Tcp(listener) => accept_stream(listener).await,
+            #[cfg(unix)]
+            Self::Unix(listener, sequence) => {
+                let (stream, _) = listener.accept().await?;
+                let sequence = sequence.fetch_add(1, Ordering::SeqCst);
+                Ok((BmpConnection::Unix(stream), unix_addr_info(sequence)))
+            }
+            #[cfg(all(feature = "sctp", target_os = "linux"))]
+            Self::Sctp(listener) => accept_stream(listener).await,
+        }
+    }
+}
+
+async fn accept_stream(listener: &TcpListener) -> io::Result<(BmpConnection, AddrInfo)> {
+    let (stream, remote_addr) = listener.accept().await?;
+    let local_addr = stream.local_addr()?;
+    Ok((
+        BmpConnection::Tcp(stream),
+        AddrInfo::new(local_addr, remote_addr),
+    ))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            BmpListenerConfig::Tcp(SocketAddr::from(([127, 0, 0, 1], 1790))).to_string(),
+            "tcp://127.0.0.1:1790"
+        );
+        assert_eq!(
+            BmpListenerConfig::Unix(PathBuf::from("/run/bmp.sock")).to_string(),
+            "unix:///run/bmp.sock"
+        );
+        assert_eq!(BmpListenerConfig::Systemd(1).to_string(), "systemd://1");
+    }
+
+    #[test]
+    fn test_local_addrs() {
+        let addr = SocketAddr::from(([127, 0, 0, 1], 1790));
+        assert_eq!(BmpListenerConfig::Tcp(addr).local_addrs(), vec![addr]);
+        assert_eq!(
+            BmpListenerConfig::Unix(PathBuf::from("/run/bmp.sock")).local_addrs(),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_unix_addr_info() {
+        assert_eq!(
+            unix_addr_info(0).remote_socket(),
+            "[100::]:0".parse().unwrap()
+        );
+        let last = unix_addr_info(u64::MAX);
+        assert_eq!(last.local_socket(), UNIX_LOCAL_SOCKET);
+        assert_eq!(
+            last.remote_socket(),
+            "[100::ffff:ffff:ffff:ffff]:0".parse().unwrap()
+        );
+        assert_ne!(unix_addr_info(1 << 16), unix_addr_info(0));
+    }
+
+    #[test]
+    fn test_systemd_listen_fds() {
+        assert_eq!(systemd_listen_fds(Some("10"), Some("2"), 10).unwrap(), 3..5);
+        assert_eq!(
+            systemd_listen_fds(None, Some("2"), 10).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(
+            systemd_listen_fds(Some("11"), Some("2"), 10)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(
+            systemd_listen_fds(Some("10"), None, 10).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(
+            systemd_listen_fds(Some("10"), Some("x"), 10)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn test_from_raw_fd() {
+        use std::os::unix::io::IntoRawFd;
+
+        let tcp = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = tcp.local_addr().unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let _guard = runtime.enter();
+        match BmpListener::from_raw_fd(tcp.into_raw_fd()).unwrap() {
+            BmpListener::Tcp(listener) => assert_eq!(listener.local_addr().unwrap(), addr),
+            other => panic!("Expected TCP listener, got {other:?}"),
+        }
+
+        let path =
+            std::env::temp_dir().join(format!("netgauze-bmp-fd-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let unix = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        match BmpListener::from_raw_fd(unix.into_raw_fd()).unwrap() {
+            BmpListener::Unix(_, _) => {}
+            other => panic!("Expected Unix listener, got {other:?}"),
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(all(feature = "sctp", feature = "tcp-auth", target_os = "linux"))]
+    #[tokio::test]
+    async fn test_sctp_tcp_auth_keys() {
+        let config =
+            BmpListenerConfig::Sctp(crate::sctp::SctpConfig::new("127.0.0.1:0".parse().unwrap()));
+        let listener = match BmpListener::bind(&config).await {
+            Ok(listener) => listener,
+            // The kernel is built or running without SCTP
+            Err(err) if err.raw_os_error() == Some(libc::EPROTONOSUPPORT) => return,
+            Err(err) => panic!("unexpected error: {err}"),
+        };
+        assert!(matches!(listener, BmpListener::Sctp(_)));
+        assert!(listener
+            .set_tcp_auth_keys(&std::collections::HashMap::new())
+            .is_ok());
+        let keys = std::collections::HashMap::from([(
+            IpAddr::from([127, 0, 0, 1]),
+            netgauze_tcp_auth::TcpAuthKey::md5("secret"),
+        )]);
+        assert_eq!(
+            listener.set_tcp_auth_keys(&keys).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+}
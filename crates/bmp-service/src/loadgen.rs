@@ -0,0 +1,721 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Synthetic BMP feeds for capacity planning of collectors.
+//!
+//! [`BmpLoadGenerator`] simulates a number of routers, each with a number of
+//! peers. Every router opens a BMP session to the collector, sends an
+//! Initiation message and a Peer Up for each of its peers, then replays a full
+//! table as Route Monitoring messages for every peer followed by an
+//! End-of-RIB marker. The messages of all the routers are paced together at a
+//! given rate of messages per second.
+//!
+//! The table is a RIB snapshot, either synthetic with [`synthetic_rib`], or
+//! read from an MRT `TABLE_DUMP_V2` dump with [`read_mrt_rib`].
+
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+
+use bytes::{BufMut, BytesMut};
+use chrono::{SubsecRound, Utc};
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use nom::{
+    bytes::complete::take,
+    multi::length_data,
+    number::complete::{be_u16, be_u32, be_u8},
+    IResult,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{io::AsyncWriteExt, time::MissedTickBehavior};
+
+use netgauze_bgp_pkt::{
+    capabilities::{BgpCapability, FourOctetAsCapability, MultiProtocolExtensionsCapability},
+    iana::PathAttributeType,
+    nlri::{Ipv4Unicast, Ipv4UnicastAddress, Ipv6Unicast, Ipv6UnicastAddress},
+    open::{BgpOpenMessage, BgpOpenMessageParameter},
+    path_attribute::{
+        As4PathSegment, AsPath, AsPathSegmentType, MpReach, MpUnreach, NextHop, Origin,
+        PathAttribute, PathAttributeValue,
+    },
+    update::BgpUpdateMessage,
+    wire::deserializer::BgpParsingContext,
+    BgpMessage,
+};
+use netgauze_bmp_pkt::{
+    wire::serializer::BmpMessageWritingError, BmpMessage, BmpMessageValue, BmpPeerType,
+    InitiationInformation, InitiationMessage, PeerHeader, PeerUpNotificationMessage,
+    RouteMonitoringMessage,
+};
+use netgauze_iana::address_family::AddressType;
+use netgauze_parse_utils::{ReadablePduWithOneInput, Span, WritablePdu};
+use netgauze_tcp_connect::TcpConnectConfig;
+
+const MRT_TYPE_TABLE_DUMP_V2: u16 = 13;
+const MRT_SUBTYPE_RIB_IPV4_UNICAST: u16 = 2;
+const MRT_SUBTYPE_RIB_IPV6_UNICAST: u16 = 4;
+
+const EXTENDED_LENGTH_PATH_ATTRIBUTE_MASK: u8 = 0x10;
+
+/// AS number of the simulated routers
+pub const LOAD_GENERATOR_LOCAL_ASN: u32 = 65000;
+
+/// Synthetic RIBs are made of consecutive /24 prefixes starting at `1.0.0.0`
+/// and stopping before the multicast range
+pub const SYNTHETIC_RIB_MAX_PREFIXES: u32 = 223 << 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadGeneratorError {
+    IoError(String),
+    /// MRT dump couldn't be read, with the offset of the faulty record
+    InvalidMrt(usize, String),
+    InvalidPrefix(IpNet),
+    /// The next hop is missing or not of the same address family as the
+    /// prefix
+    InvalidNextHop(IpNet, Option<IpAddr>),
+    WritingError(BmpMessageWritingError),
+}
+
+impl std::fmt::Display for LoadGeneratorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::error::Error for LoadGeneratorError {}
+
+impl From<std::io::Error> for LoadGeneratorError {
+    fn from(value: std::io::Error) -> Self {
+        Self::IoError(value.to_string())
+    }
+}
+
+impl From<BmpMessageWritingError> for LoadGeneratorError {
+    fn from(value: BmpMessageWritingError) -> Self {
+        Self::WritingError(value)
+    }
+}
+
+/// A route of a RIB snapshot, kept as the BGP Update announcing it
+#[derive(Debug, Clone, PartialEq)]
+pub struct RibEntry {
+    prefix: IpNet,
+    update: BgpUpdateMessage,
+}
+
+impl RibEntry {
+    /// The `NEXT_HOP` or `MP_REACH_NLRI` attribute is built from `next_hop`,
+    /// so it shouldn't be included in `attributes`.
+    pub fn new(
+        prefix: IpNet,
+        next_hop: IpAddr,
+        mut attributes: Vec<PathAttribute>,
+    ) -> Result<Self, LoadGeneratorError> {
+        let nlri = match (prefix, next_hop) {
+            (IpNet::V4(net), IpAddr::V4(next_hop)) => {
+                let network = Ipv4Unicast::from_net(net)
+                    .map_err(|_| LoadGeneratorError::InvalidPrefix(prefix))?;
+                attributes.push(
+                    PathAttribute::from(
+                        false,
+                        true,
+                        false,
+                        false,
+                        PathAttributeValue::NextHop(NextHop::new(next_hop)),
+                    )
+                    .expect("NEXT_HOP is well-known transitive"),
+                );
+                vec![Ipv4UnicastAddress::new_no_path_id(network)]
+            }
+            (IpNet::V6(net), IpAddr::V6(next_hop)) => {
+                let network = Ipv6Unicast::from_net(net)
+                    .map_err(|_| LoadGeneratorError::InvalidPrefix(prefix))?;
+                attributes.push(
+                    PathAttribute::from(
+                        true,
+                        false,
+                        false,
+                        false,
+                        PathAttributeValue::MpReach(MpReach::Ipv6Unicast {
+                            next_hop_global: next_hop,
+                            next_hop_local: None,
+                            nlri: vec![Ipv6UnicastAddress::new(None, network)],
+                        }),
+                    )
+                    .expect("MP_REACH_NLRI is optional non-transitive"),
+                );
+                vec![]
+            }
+            _ => return Err(LoadGeneratorError::InvalidNextHop(prefix, Some(next_hop))),
+        };
+        let mut update = BgpUpdateMessage::new(vec![], attributes, nlri);
+        update.sort_path_attributes();
+        Ok(Self { prefix, update })
+    }
+
+    pub const fn prefix(&self) -> IpNet {
+        self.prefix
+    }
+
+    pub const fn update(&self) -> &BgpUpdateMessage {
+        &self.update
+    }
+}
+
+/// Build a RIB of `prefixes` consecutive /24 prefixes, capped at
+/// [`SYNTHETIC_RIB_MAX_PREFIXES`]. Each prefix is originated by one of 1000
+/// private AS numbers behind [`LOAD_GENERATOR_LOCAL_ASN`].
+pub fn synthetic_rib(prefixes: u32) -> Vec<RibEntry> {
+    (0..prefixes.min(SYNTHETIC_RIB_MAX_PREFIXES))
+        .map(|index| {
+            let prefix = Ipv4Net::new(Ipv4Addr::from(0x0100_0000 + (index << 8)), 24)
+                .expect("valid prefix length");
+            let attributes = vec![
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::Origin(Origin::IGP),
+                )
+                .expect("ORIGIN is well-known transitive"),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![As4PathSegment::new(
+                        AsPathSegmentType::AsSequence,
+                        vec![LOAD_GENERATOR_LOCAL_ASN, 4200000000 + index % 1000],
+                    )])),
+                )
+                .expect("AS_PATH is well-known transitive"),
+            ];
+            RibEntry::new(
+                IpNet::V4(prefix),
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                attributes,
+            )
+            .expect("synthetic prefixes are unicast")
+        })
+        .collect()
+}
+
+/// Read the IPv4 and IPv6 unicast RIBs of an MRT `TABLE_DUMP_V2` dump
+/// ([RFC6396](https://datatracker.ietf.org/doc/html/rfc6396)).
+///
+/// Only the first RIB entry of each prefix is kept, since the same table is
+/// replayed for all the simulated peers. Other MRT records are skipped.
+pub fn read_mrt_rib(buf: &[u8]) -> Result<Vec<RibEntry>, LoadGeneratorError> {
+    let mut rib = vec![];
+    let mut rest = buf;
+    while !rest.is_empty() {
+        let offset = buf.len() - rest.len();
+        let invalid = |err: String| LoadGeneratorError::InvalidMrt(offset, err);
+        let (next, (mrt_type, subtype, record)) =
+            parse_mrt_record(rest).map_err(|err| invalid(err.to_string()))?;
+        rest = next;
+        if mrt_type != MRT_TYPE_TABLE_DUMP_V2 {
+            continue;
+        }
+        let ipv6 = match subtype {
+            MRT_SUBTYPE_RIB_IPV4_UNICAST => false,
+            MRT_SUBTYPE_RIB_IPV6_UNICAST => true,
+            _ => continue,
+        };
+        let (_, (prefix_len, prefix, attributes)) =
+            parse_rib_record(record).map_err(|err| invalid(err.to_string()))?;
+        let attributes = match attributes {
+            Some(attributes) => attributes,
+            None => continue,
+        };
+        let prefix = if ipv6 {
+            let mut octets = [0u8; 16];
+            octets
+                .get_mut(..prefix.len())
+                .ok_or_else(|| invalid(format!("invalid prefix length {prefix_len}")))?
+                .copy_from_slice(prefix);
+            Ipv6Net::new(Ipv6Addr::from(octets), prefix_len).map(IpNet::V6)
+        } else {
+            let mut octets = [0u8; 4];
+            octets
+                .get_mut(..prefix.len())
+                .ok_or_else(|| invalid(format!("invalid prefix length {prefix_len}")))?
+                .copy_from_slice(prefix);
+            Ipv4Net::new(Ipv4Addr::from(octets), prefix_len).map(IpNet::V4)
+        }
+        .map_err(|_| invalid(format!("invalid prefix length {prefix_len}")))?;
+        let (next_hop, attributes) = parse_mrt_attributes(attributes).map_err(invalid)?;
+        let next_hop = next_hop.ok_or(LoadGeneratorError::InvalidNextHop(prefix, None))?;
+        rib.push(RibEntry::new(prefix, next_hop, attributes)?);
+    }
+    Ok(rib)
+}
+
+/// Returns the type, subtype and message of an MRT record
+fn parse_mrt_record(buf: &[u8]) -> IResult<&[u8], (u16, u16, &[u8])> {
+    let (buf, _timestamp) = be_u32(buf)?;
+    let (buf, mrt_type) = be_u16(buf)?;
+    let (buf, subtype) = be_u16(buf)?;
+    let (buf, message) = length_data(be_u32)(buf)?;
+    Ok((buf, (mrt_type, subtype, message)))
+}
+
+/// Returns the prefix length, prefix and the path attributes of the first
+/// entry of an AFI/SAFI-specific RIB record
+#[allow(clippy::type_complexity)]
+fn parse_rib_record(buf: &[u8]) -> IResult<&[u8], (u8, &[u8], Option<&[u8]>)> {
+    let (buf, _sequence) = be_u32(buf)?;
+    let (buf, prefix_len) = be_u8(buf)?;
+    let (buf, prefix) = take(usize::from(prefix_len).div_ceil(8))(buf)?;
+    let (buf, entry_count) = be_u16(buf)?;
+    if entry_count == 0 {
+        return Ok((buf, (prefix_len, prefix, None)));
+    }
+    let (buf, _peer_index) = be_u16(buf)?;
+    let (buf, _originated_time) = be_u32(buf)?;
+    let (buf, attributes) = length_data(be_u16)(buf)?;
+    Ok((buf, (prefix_len, prefix, Some(attributes))))
+}
+
+/// Returns the flags, type code and value of a path attribute
+fn parse_attribute_header(buf: &[u8]) -> IResult<&[u8], (u8, &[u8])> {
+    let (buf, flags) = be_u8(buf)?;
+    let (buf, code) = be_u8(buf)?;
+    let (buf, value) = if flags & EXTENDED_LENGTH_PATH_ATTRIBUTE_MASK != 0 {
+        length_data(be_u16)(buf)?
+    } else {
+        length_data(be_u8)(buf)?
+    };
+    Ok((buf, (code, value)))
+}
+
+/// Split the next hop from the other path attributes of a RIB entry
+fn parse_mrt_attributes(mut buf: &[u8]) -> Result<(Option<IpAddr>, Vec<PathAttribute>), String> {
+    // AS numbers in TABLE_DUMP_V2 are always encoded in four octets
+    let mut ctx = BgpParsingContext::default();
+    ctx.set_asn4(true);
+    let mut next_hop = None;
+    let mut attributes = vec![];
+    while !buf.is_empty() {
+        let (rest, (code, value)) = parse_attribute_header(buf).map_err(|err| err.to_string())?;
+        if code == PathAttributeType::NextHop as u8 {
+            next_hop = <[u8; 4]>::try_from(value)
+                .ok()
+                .map(|octets| IpAddr::V4(Ipv4Addr::from(octets)));
+        } else if code == PathAttributeType::MpReachNlri as u8 {
+            next_hop = mp_reach_next_hop(value);
+        } else {
+            let attribute = &buf[..buf.len() - rest.len()];
+            let (_, attribute) = <PathAttribute as ReadablePduWithOneInput<
+                '_,
+                &mut BgpParsingContext,
+                _,
+            >>::from_wire(Span::new(attribute), &mut ctx)
+            .map_err(|err| format!("{err:?}"))?;
+            attributes.push(attribute);
+        }
+        buf = rest;
+    }
+    Ok((next_hop, attributes))
+}
+
+/// MRT abbreviates `MP_REACH_NLRI` to the next hop length and address
+/// (RFC6396 section 4.3.4), some implementations still dump the full
+/// attribute
+fn mp_reach_next_hop(value: &[u8]) -> Option<IpAddr> {
+    let next_hop = match value.first() {
+        Some(len) if value.len() == 1 + usize::from(*len) => &value[1..],
+        _ => {
+            let len = usize::from(*value.get(3)?);
+            value.get(4..4 + len)?
+        }
+    };
+    match next_hop.len() {
+        4 => Some(IpAddr::V4(Ipv4Addr::from(
+            <[u8; 4]>::try_from(next_hop).ok()?,
+        ))),
+        16 | 32 => Some(IpAddr::V6(Ipv6Addr::from(
+            <[u8; 16]>::try_from(&next_hop[..16]).ok()?,
+        ))),
+        _ => None,
+    }
+}
+
+/// Totals of what was sent by a [`BmpLoadGenerator`]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadGeneratorStats {
+    pub messages: u64,
+    pub routes: u64,
+    pub bytes: u64,
+}
+
+/// Replay a RIB snapshot from a number of simulated routers and peers, see
+/// the [module documentation](self)
+#[derive(Debug, Clone)]
+pub struct BmpLoadGenerator {
+    rib: Vec<RibEntry>,
+    has_ipv6: bool,
+    messages_per_second: u32,
+    routers: u32,
+    peers: u32,
+    connect_config: TcpConnectConfig,
+}
+
+impl BmpLoadGenerator {
+    pub fn new(rib: Vec<RibEntry>) -> Self {
+        let has_ipv6 = rib.iter().any(|entry| matches!(entry.prefix, IpNet::V6(_)));
+        Self {
+            rib,
+            has_ipv6,
+            messages_per_second: 1000,
+            routers: 1,
+            peers: 1,
+            connect_config: TcpConnectConfig::default(),
+        }
+    }
+
+    /// Rate of messages across all the routers, at least one
+    pub fn with_messages_per_second(mut self, messages_per_second: u32) -> Self {
+        self.messages_per_second = messages_per_second.max(1);
+        self
+    }
+
+    pub fn with_routers(mut self, routers: u32) -> Self {
+        self.routers = routers;
+        self
+    }
+
+    /// Number of peers of each router
+    pub fn with_peers(mut self, peers: u32) -> Self {
+        self.peers = peers;
+        self
+    }
+
+    /// Source address, TTL, and the other options of the routers' connections
+    /// to the collector
+    pub fn with_connect_config(mut self, connect_config: TcpConnectConfig) -> Self {
+        self.connect_config = connect_config;
+        self
+    }
+
+    pub fn rib(&self) -> &[RibEntry] {
+        &self.rib
+    }
+
+    pub const fn messages_per_second(&self) -> u32 {
+        self.messages_per_second
+    }
+
+    pub const fn routers(&self) -> u32 {
+        self.routers
+    }
+
+    pub const fn peers(&self) -> u32 {
+        self.peers
+    }
+
+    pub const fn connect_config(&self) -> &TcpConnectConfig {
+        &self.connect_config
+    }
+
+    /// Total number of messages sent by a router
+    pub fn messages_per_router(&self) -> u64 {
+        let end_of_rib = if self.has_ipv6 { 2 } else { 1 };
+        1 + u64::from(self.peers) * (1 + self.rib.len() as u64 + end_of_rib)
+    }
+
+    /// Local address of a router, allocated from `172.16.0.0/12`
+    fn router_address(&self, router: u32) -> Ipv4Addr {
+        Ipv4Addr::from(0xac10_0000 + router + 1)
+    }
+
+    /// Peers addresses are allocated sequentially from `10.0.0.0/8` across all
+    /// the routers
+    fn peer_header(&self, router: u32, peer: u32) -> PeerHeader {
+        let address = Ipv4Addr::from(0x0a00_0000 + router * self.peers + peer + 1);
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(address)),
+            4200000000 + peer,
+            address,
+            Some(Utc::now().trunc_subsecs(6)),
+        )
+    }
+
+    fn open(asn: u32, bgp_id: Ipv4Addr) -> BgpMessage {
+        BgpMessage::Open(BgpOpenMessage::new(
+            u16::try_from(asn).unwrap_or(23456),
+            180,
+            bgp_id,
+            vec![BgpOpenMessageParameter::Capabilities(vec![
+                BgpCapability::MultiProtocolExtensions(MultiProtocolExtensionsCapability::new(
+                    AddressType::Ipv4Unicast,
+                )),
+                BgpCapability::MultiProtocolExtensions(MultiProtocolExtensionsCapability::new(
+                    AddressType::Ipv6Unicast,
+                )),
+                BgpCapability::FourOctetAs(FourOctetAsCapability::new(asn)),
+            ])],
+        ))
+    }
+
+    fn initiation(&self, router: u32) -> BmpMessage {
+        BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(vec![
+            InitiationInformation::SystemDescription("NetGauze BMP load generator".to_string()),
+            InitiationInformation::SystemName(format!("router-{router}")),
+        ])))
+    }
+
+    fn peer_up(&self, router: u32, peer: u32) -> BmpMessage {
+        let peer_header = self.peer_header(router, peer);
+        let local_address = self.router_address(router);
+        let sent = Self::open(LOAD_GENERATOR_LOCAL_ASN, local_address);
+        let received = Self::open(peer_header.peer_as(), peer_header.bgp_id());
+        BmpMessage::V3(BmpMessageValue::PeerUpNotification(
+            PeerUpNotificationMessage::build(
+                peer_header,
+                Some(IpAddr::V4(local_address)),
+                Some(179),
+                Some(u16::try_from(30000 + peer % 30000).unwrap_or(30000)),
+                sent,
+                received,
+                vec![],
+            )
+            .expect("peer up carries open messages"),
+        ))
+    }
+
+    fn route_monitoring(peer_header: PeerHeader, update: BgpUpdateMessage) -> BmpMessage {
+        BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            RouteMonitoringMessage::build(peer_header, BgpMessage::Update(update))
+                .expect("route monitoring carries update messages"),
+        ))
+    }
+
+    fn end_of_rib(&self, router: u32, peer: u32) -> Vec<BmpMessage> {
+        let mut end_of_rib = vec![Self::route_monitoring(
+            self.peer_header(router, peer),
+            BgpUpdateMessage::new(vec![], vec![], vec![]),
+        )];
+        if self.has_ipv6 {
+            let attribute = PathAttribute::from(
+                true,
+                false,
+                false,
+                false,
+                PathAttributeValue::MpUnreach(MpUnreach::Ipv6Unicast { nlri: vec![] }),
+            )
+            .expect("MP_UNREACH_NLRI is optional non-transitive");
+            end_of_rib.push(Self::route_monitoring(
+                self.peer_header(router, peer),
+                BgpUpdateMessage::new(vec![], vec![attribute], vec![]),
+            ));
+        }
+        end_of_rib
+    }
+
+    /// Messages of a router's BMP session: the Initiation, a Peer Up for each
+    /// peer, then the table of each peer followed by End-of-RIB markers.
+    /// Messages are built lazily to not hold copies of the table in memory.
+    pub fn router_messages(&self, router: u32) -> impl Iterator<Item = BmpMessage> + '_ {
+        let peer_ups = (0..self.peers).map(move |peer| self.peer_up(router, peer));
+        let tables = (0..self.peers).flat_map(move |peer| {
+            self.rib
+                .iter()
+                .map(move |entry| {
+                    Self::route_monitoring(self.peer_header(router, peer), entry.update.clone())
+                })
+                .chain(self.end_of_rib(router, peer))
+        });
+        std::iter::once(self.initiation(router))
+            .chain(peer_ups)
+            .chain(tables)
+    }
+
+    /// Connect each simulated router to the collector and replay the table,
+    /// interleaving the routers' messages at the configured rate. Returns when
+    /// all the routers are done and their connections closed.
+    pub async fn run(
+        &self,
+        collector: SocketAddr,
+    ) -> Result<LoadGeneratorStats, LoadGeneratorError> {
+        let mut routers = Vec::with_capacity(self.routers as usize);
+        for router in 0..self.routers {
+            let stream = self.connect_config.connect(collector).await?;
+            routers.push((stream, self.router_messages(router)));
+        }
+        let mut interval = tokio::time::interval(Duration::from_secs_f64(
+            1.0 / f64::from(self.messages_per_second),
+        ));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Burst);
+        let mut stats = LoadGeneratorStats::default();
+        let mut buf = BytesMut::new();
+        let mut index = 0;
+        while !routers.is_empty() {
+            index %= routers.len();
+            let (stream, messages) = &mut routers[index];
+            let msg = match messages.next() {
+                Some(msg) => msg,
+                None => {
+                    let (mut stream, _) = routers.swap_remove(index);
+                    stream.shutdown().await?;
+                    continue;
+                }
+            };
+            interval.tick().await;
+            buf.clear();
+            msg.write(&mut (&mut buf).writer())?;
+            stream.write_all(&buf).await?;
+            stats.messages += 1;
+            stats.bytes += buf.len() as u64;
+            if matches!(&msg, BmpMessage::V3(BmpMessageValue::RouteMonitoring(_))) {
+                stats.routes += 1;
+            }
+            index += 1;
+        }
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use netgauze_bmp_pkt::codec::BmpCodec;
+    use std::str::FromStr;
+    use tokio::net::TcpListener;
+    use tokio_util::codec::{Decoder, FramedRead};
+
+    fn well_known(value: PathAttributeValue) -> PathAttribute {
+        PathAttribute::from(false, true, false, false, value).unwrap()
+    }
+
+    #[test]
+    fn test_read_mrt_rib() {
+        let buf = [
+            // PEER_INDEX_TABLE, skipped
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x0d, 0x00, 0x01, 0x00, 0x00, 0x00, 0x04, 0xc0, 0x00,
+            0x02, 0x01, // RIB_IPV4_UNICAST 198.51.100.0/24
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x0d, 0x00, 0x02, 0x00, 0x00, 0x00, 0x2a, 0x00, 0x00,
+            0x00, 0x00, 0x18, 0xc6, 0x33, 0x64, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x18, 0x40, 0x01, 0x01, 0x00, 0x40, 0x02, 0x0a, 0x02, 0x02, 0x00, 0x00, 0xfd,
+            0xe8, 0x00, 0x00, 0xfd, 0xe9, 0x40, 0x03, 0x04, 0xc0, 0x00, 0x02, 0x01,
+            // RIB_IPV6_UNICAST 2001:db8::/32 with abbreviated MP_REACH_NLRI
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x0d, 0x00, 0x04, 0x00, 0x00, 0x00, 0x2b, 0x00, 0x00,
+            0x00, 0x01, 0x20, 0x20, 0x01, 0x0d, 0xb8, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x18, 0x40, 0x01, 0x01, 0x00, 0x80, 0x0e, 0x11, 0x10, 0x20, 0x01, 0x0d,
+            0xb8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+        ];
+        let origin = well_known(PathAttributeValue::Origin(Origin::IGP));
+        let as_path = well_known(PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![
+            As4PathSegment::new(AsPathSegmentType::AsSequence, vec![65000, 65001]),
+        ])));
+        let expected = vec![
+            RibEntry::new(
+                IpNet::from_str("198.51.100.0/24").unwrap(),
+                IpAddr::from_str("192.0.2.1").unwrap(),
+                vec![origin.clone(), as_path],
+            )
+            .unwrap(),
+            RibEntry::new(
+                IpNet::from_str("2001:db8::/32").unwrap(),
+                IpAddr::from_str("2001:db8::1").unwrap(),
+                vec![origin],
+            )
+            .unwrap(),
+        ];
+        assert_eq!(read_mrt_rib(&buf), Ok(expected));
+        assert!(matches!(
+            read_mrt_rib(&buf[..20]),
+            Err(LoadGeneratorError::InvalidMrt(16, _))
+        ));
+    }
+
+    #[test]
+    fn test_router_messages() {
+        let generator = BmpLoadGenerator::new(synthetic_rib(10))
+            .with_routers(2)
+            .with_peers(3);
+        let messages = generator.router_messages(1).collect::<Vec<_>>();
+        assert_eq!(messages.len() as u64, generator.messages_per_router());
+        assert_eq!(messages.len(), 1 + 3 * (1 + 10 + 1));
+        let types = messages
+            .iter()
+            .map(|msg| msg.get_type())
+            .collect::<Vec<_>>();
+        assert_eq!(types[0], netgauze_bmp_pkt::iana::BmpMessageType::Initiation);
+        assert!(types[1..4]
+            .iter()
+            .all(|x| *x == netgauze_bmp_pkt::iana::BmpMessageType::PeerUpNotification));
+        assert!(types[4..]
+            .iter()
+            .all(|x| *x == netgauze_bmp_pkt::iana::BmpMessageType::RouteMonitoring));
+
+        // The messages decode back to the same values
+        let mut buf = BytesMut::new();
+        for msg in &messages {
+            msg.write(&mut (&mut buf).writer()).unwrap();
+        }
+        let mut codec = BmpCodec::default();
+        let mut decoded = vec![];
+        while let Some(msg) = codec.decode(&mut buf).unwrap() {
+            decoded.push(msg);
+        }
+        assert_eq!(decoded, messages);
+    }
+
+    #[tokio::test]
+    async fn test_load_generator_run() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let collector = listener.local_addr().unwrap();
+        let received = tokio::spawn(async move {
+            let mut received = vec![];
+            for _ in 0..2 {
+                let (stream, remote) = listener.accept().await.unwrap();
+                assert_eq!(remote.ip(), IpAddr::V4(Ipv4Addr::new(127, 0, 0, 3)));
+                received.push(tokio::spawn(async move {
+                    FramedRead::new(stream, BmpCodec::default())
+                        .map(|msg| msg.unwrap())
+                        .count()
+                        .await
+                }));
+            }
+            let mut counts = vec![];
+            for handle in received {
+                counts.push(handle.await.unwrap());
+            }
+            counts
+        });
+        let generator = BmpLoadGenerator::new(synthetic_rib(5))
+            .with_messages_per_second(1000)
+            .with_routers(2)
+            .with_connect_config(
+                TcpConnectConfig::default()
+                    .with_source_address(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 3))),
+            );
+        let stats = generator.run(collector).await.unwrap();
+        assert_eq!(stats.messages, 2 * 8);
+        assert_eq!(stats.routes, 2 * 6);
+        assert_eq!(received.await.unwrap(), vec![8, 8]);
+    }
+}
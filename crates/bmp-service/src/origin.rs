@@ -0,0 +1,474 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Track which ASNs originated each prefix over time.
+//!
+//! [`OriginHistory`] records, per prefix and per time window, the origin ASNs
+//! seen in the announcements along with when they were first and last seen.
+//! It answers questions such as "who originated this prefix last Tuesday"
+//! and flags origins never seen before for a prefix, which is the basic
+//! input of origin hijack heuristics.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeZone, Utc};
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+
+use netgauze_bgp_pkt::{
+    iana::AS_TRANS,
+    path_attribute::{As4Path, AsPath, PathAttributeValue},
+    BgpMessage,
+};
+use netgauze_bmp_pkt::{BmpMessage, BmpMessageValue};
+
+/// Default length of a time window
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Announcements of a prefix by a single origin ASN within a time window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OriginObservation {
+    first_seen: DateTime<Utc>,
+    last_seen: DateTime<Utc>,
+    count: u64,
+}
+
+impl OriginObservation {
+    const fn new(time: DateTime<Utc>) -> Self {
+        Self {
+            first_seen: time,
+            last_seen: time,
+            count: 1,
+        }
+    }
+
+    pub const fn first_seen(&self) -> DateTime<Utc> {
+        self.first_seen
+    }
+
+    pub const fn last_seen(&self) -> DateTime<Utc> {
+        self.last_seen
+    }
+
+    /// Number of announcements
+    pub const fn count(&self) -> u64 {
+        self.count
+    }
+
+    fn merge(&mut self, other: &OriginObservation) {
+        self.first_seen = self.first_seen.min(other.first_seen);
+        self.last_seen = self.last_seen.max(other.last_seen);
+        self.count += other.count;
+    }
+}
+
+type Windows = BTreeMap<DateTime<Utc>, HashMap<u32, OriginObservation>>;
+
+/// Origin ASNs of each prefix, bucketed in fixed time windows.
+///
+/// Windows older than the optional retention are dropped by
+/// [`OriginHistory::expire`], which should be called periodically.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OriginHistory {
+    window: Duration,
+    retention: Option<Duration>,
+    prefixes: HashMap<IpNet, Windows>,
+}
+
+impl Default for OriginHistory {
+    fn default() -> Self {
+        Self {
+            window: DEFAULT_WINDOW,
+            retention: None,
+            prefixes: HashMap::new(),
+        }
+    }
+}
+
+impl OriginHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Length of each time window, rounded down to whole seconds and at least
+    /// one second.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = Duration::from_secs(window.as_secs().max(1));
+        self
+    }
+
+    /// How long windows are kept, `None` keeps them forever
+    pub const fn with_retention(mut self, retention: Option<Duration>) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    pub const fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub const fn retention(&self) -> Option<Duration> {
+        self.retention
+    }
+
+    /// Start of the window `time` falls in
+    pub fn window_start(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let secs = self.window.as_secs() as i64;
+        let start = time.timestamp().div_euclid(secs) * secs;
+        Utc.timestamp_opt(start, 0).unwrap()
+    }
+
+    /// Record that `origin_as` announced `prefix` at `time`, returns `true`
+    /// when the ASN was never seen originating the prefix before, within the
+    /// retained history.
+    pub fn observe(&mut self, prefix: IpNet, origin_as: u32, time: DateTime<Utc>) -> bool {
+        let window_start = self.window_start(time);
+        let windows = self.prefixes.entry(prefix).or_default();
+        let is_new = !windows
+            .values()
+            .any(|origins| origins.contains_key(&origin_as));
+        let observation = OriginObservation::new(time);
+        windows
+            .entry(window_start)
+            .or_default()
+            .entry(origin_as)
+            .and_modify(|existing| existing.merge(&observation))
+            .or_insert(observation);
+        is_new
+    }
+
+    /// Record the announcements of a BMP Route Monitoring message, other
+    /// messages are ignored. Returns the announcements whose origin was never
+    /// seen before for the prefix.
+    ///
+    /// The time of an announcement is the peer header timestamp, messages
+    /// without one are ignored. The origin is taken from the AS path, see
+    /// [`AsPath::origin_as`], the `AS4_PATH` is used when the AS path has the
+    /// `AS_TRANS` origin, and the peer AS when the AS path is empty. Routes
+    /// without an IP prefix or an unambiguous origin are ignored.
+    pub fn observe_bmp_message(&mut self, msg: &BmpMessage) -> Vec<(IpNet, u32)> {
+        let route_monitoring = match msg {
+            BmpMessage::V3(BmpMessageValue::RouteMonitoring(route_monitoring)) => route_monitoring,
+            _ => return vec![],
+        };
+        let update = match route_monitoring.update_message() {
+            BgpMessage::Update(update) => update,
+            _ => return vec![],
+        };
+        let peer_header = route_monitoring.peer_header();
+        let time = match peer_header.timestamp() {
+            Some(time) => *time,
+            None => return vec![],
+        };
+        let mut as_path = None;
+        let mut as4_path = None;
+        for attr in update.path_attributes() {
+            match attr.value() {
+                PathAttributeValue::AsPath(value) => as_path = Some(value),
+                PathAttributeValue::As4Path(value) => as4_path = Some(value),
+                _ => {}
+            }
+        }
+        let origin_as = match route_origin(as_path, as4_path, peer_header.peer_as()) {
+            Some(origin_as) => origin_as,
+            None => return vec![],
+        };
+        let mut new_origins = vec![];
+        for route in update.announced() {
+            if let Some(prefix) = route.prefix().ip_net() {
+                if self.observe(prefix, origin_as, time) {
+                    new_origins.push((prefix, origin_as));
+                }
+            }
+        }
+        new_origins
+    }
+
+    /// Origins of `prefix` over all the retained history
+    pub fn origins(&self, prefix: &IpNet) -> HashMap<u32, OriginObservation> {
+        self.merged(prefix, ..)
+    }
+
+    /// Origins of `prefix` seen in the windows overlapping `[from, to)`
+    pub fn origins_between(
+        &self,
+        prefix: &IpNet,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> HashMap<u32, OriginObservation> {
+        if to <= from {
+            return HashMap::new();
+        }
+        self.merged(prefix, self.window_start(from)..to)
+    }
+
+    /// Origins of `prefix` seen in the window `time` falls in
+    pub fn origins_at(
+        &self,
+        prefix: &IpNet,
+        time: DateTime<Utc>,
+    ) -> HashMap<u32, OriginObservation> {
+        let window_start = self.window_start(time);
+        self.prefixes
+            .get(prefix)
+            .and_then(|windows| windows.get(&window_start))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn merged<R: std::ops::RangeBounds<DateTime<Utc>>>(
+        &self,
+        prefix: &IpNet,
+        range: R,
+    ) -> HashMap<u32, OriginObservation> {
+        let mut merged: HashMap<u32, OriginObservation> = HashMap::new();
+        let windows = match self.prefixes.get(prefix) {
+            Some(windows) => windows,
+            None => return merged,
+        };
+        for origins in windows.range(range).map(|(_, origins)| origins) {
+            for (origin_as, observation) in origins {
+                merged
+                    .entry(*origin_as)
+                    .and_modify(|existing| existing.merge(observation))
+                    .or_insert(*observation);
+            }
+        }
+        merged
+    }
+
+    /// Drop the windows that ended before the retention, returns the number of
+    /// dropped windows.
+    pub fn expire(&mut self, now: DateTime<Utc>) -> usize {
+        let retention = match self
+            .retention
+            .and_then(|retention| chrono::Duration::from_std(retention).ok())
+        {
+            Some(retention) => retention,
+            None => return 0,
+        };
+        let window = chrono::Duration::from_std(self.window).unwrap_or(chrono::Duration::zero());
+        let mut dropped = 0;
+        self.prefixes.retain(|_, windows| {
+            let before = windows.len();
+            windows.retain(|start, _| *start + window > now - retention);
+            dropped += before - windows.len();
+            !windows.is_empty()
+        });
+        dropped
+    }
+
+    pub fn prefixes(&self) -> impl Iterator<Item = &IpNet> {
+        self.prefixes.keys()
+    }
+
+    /// Number of tracked prefixes
+    pub fn len(&self) -> usize {
+        self.prefixes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty()
+    }
+}
+
+fn route_origin(as_path: Option<&AsPath>, as4_path: Option<&As4Path>, peer_as: u32) -> Option<u32> {
+    let is_empty = match as_path? {
+        AsPath::As2PathSegments(segments) => segments.is_empty(),
+        AsPath::As4PathSegments(segments) => segments.is_empty(),
+    };
+    if is_empty {
+        return Some(peer_as);
+    }
+    match as_path?.origin_as() {
+        Some(origin_as) if origin_as == AS_TRANS as u32 => {
+            as4_path.and_then(As4Path::origin_as).or(Some(origin_as))
+        }
+        origin_as => origin_as,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ipnet::Ipv4Net;
+    use netgauze_bgp_pkt::{
+        nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+        path_attribute::{As2PathSegment, As4PathSegment, AsPathSegmentType, PathAttribute},
+        update::BgpUpdateMessage,
+    };
+    use netgauze_bmp_pkt::{BmpPeerType, PeerHeader, RouteMonitoringMessage};
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        str::FromStr,
+    };
+
+    fn time(hour: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 26, hour, min, 0).unwrap()
+    }
+
+    fn route_monitoring(
+        as_path: AsPath,
+        as4_path: Option<As4Path>,
+        time: DateTime<Utc>,
+    ) -> BmpMessage {
+        let mut path_attributes = vec![PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::AsPath(as_path),
+        )
+        .unwrap()];
+        if let Some(as4_path) = as4_path {
+            path_attributes.push(
+                PathAttribute::from(
+                    true,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::As4Path(as4_path),
+                )
+                .unwrap(),
+            );
+        }
+        let update = BgpUpdateMessage::new(
+            vec![],
+            path_attributes,
+            vec![Ipv4UnicastAddress::new_no_path_id(
+                Ipv4Unicast::from_net(Ipv4Net::from_str("198.51.100.0/24").unwrap()).unwrap(),
+            )],
+        );
+        BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            RouteMonitoringMessage::build(
+                PeerHeader::new(
+                    BmpPeerType::GlobalInstancePeer {
+                        ipv6: false,
+                        post_policy: false,
+                        asn2: false,
+                        adj_rib_out: false,
+                    },
+                    None,
+                    Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2))),
+                    65001,
+                    Ipv4Addr::new(192, 0, 2, 2),
+                    Some(time),
+                ),
+                BgpMessage::Update(update),
+            )
+            .unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_origin_history() {
+        let prefix = IpNet::from_str("198.51.100.0/24").unwrap();
+        let mut history = OriginHistory::new();
+        assert!(history.observe(prefix, 64500, time(10, 5)));
+        assert!(!history.observe(prefix, 64500, time(10, 50)));
+        assert!(!history.observe(prefix, 64500, time(12, 0)));
+        assert!(history.observe(prefix, 64666, time(12, 30)));
+
+        let origins = history.origins(&prefix);
+        assert_eq!(origins.len(), 2);
+        assert_eq!(origins[&64500].first_seen(), time(10, 5));
+        assert_eq!(origins[&64500].last_seen(), time(12, 0));
+        assert_eq!(origins[&64500].count(), 3);
+
+        let at_ten = history.origins_at(&prefix, time(10, 0));
+        assert_eq!(at_ten.keys().collect::<Vec<_>>(), vec![&64500]);
+        assert_eq!(at_ten[&64500].last_seen(), time(10, 50));
+        assert!(history.origins_at(&prefix, time(11, 0)).is_empty());
+
+        // The window of `from` is included, `to` is excluded
+        let between = history.origins_between(&prefix, time(10, 30), time(12, 0));
+        assert_eq!(between.len(), 1);
+        assert_eq!(between[&64500].count(), 2);
+        let between = history.origins_between(&prefix, time(11, 0), time(13, 0));
+        assert_eq!(between.len(), 2);
+        assert!(history
+            .origins_between(&prefix, time(13, 0), time(11, 0))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_expire() {
+        let prefix = IpNet::from_str("198.51.100.0/24").unwrap();
+        let mut history = OriginHistory::new().with_retention(Some(Duration::from_secs(3600)));
+        history.observe(prefix, 64500, time(10, 5));
+        history.observe(prefix, 64500, time(11, 5));
+        assert_eq!(history.expire(time(11, 30)), 0);
+        assert_eq!(history.expire(time(12, 0)), 1);
+        assert_eq!(history.origins(&prefix)[&64500].count(), 1);
+        assert_eq!(history.expire(time(13, 0)), 1);
+        assert!(history.is_empty());
+
+        // Expired origins are reported as new again
+        assert!(history.observe(prefix, 64500, time(13, 5)));
+        assert_eq!(OriginHistory::new().expire(time(13, 0)), 0);
+    }
+
+    #[test]
+    fn test_observe_bmp_message() {
+        let prefix = IpNet::from_str("198.51.100.0/24").unwrap();
+        let mut history = OriginHistory::new();
+        let msg = route_monitoring(
+            AsPath::As4PathSegments(vec![As4PathSegment::new(
+                AsPathSegmentType::AsSequence,
+                vec![65001, 64500],
+            )]),
+            None,
+            time(10, 0),
+        );
+        assert_eq!(history.observe_bmp_message(&msg), vec![(prefix, 64500)]);
+        assert!(history.observe_bmp_message(&msg).is_empty());
+
+        // AS_TRANS origin resolved with the AS4_PATH
+        let msg = route_monitoring(
+            AsPath::As2PathSegments(vec![As2PathSegment::new(
+                AsPathSegmentType::AsSequence,
+                vec![65001, AS_TRANS],
+            )]),
+            Some(As4Path::new(vec![As4PathSegment::new(
+                AsPathSegmentType::AsSequence,
+                vec![4200000000],
+            )])),
+            time(10, 0),
+        );
+        assert_eq!(
+            history.observe_bmp_message(&msg),
+            vec![(prefix, 4200000000)]
+        );
+
+        // Empty AS path is originated by the peer
+        let msg = route_monitoring(AsPath::As4PathSegments(vec![]), None, time(10, 0));
+        assert_eq!(history.observe_bmp_message(&msg), vec![(prefix, 65001)]);
+
+        // Ambiguous origin
+        let msg = route_monitoring(
+            AsPath::As4PathSegments(vec![As4PathSegment::new(
+                AsPathSegmentType::AsSet,
+                vec![64501, 64502],
+            )]),
+            None,
+            time(10, 0),
+        );
+        assert!(history.observe_bmp_message(&msg).is_empty());
+        assert_eq!(history.origins(&prefix).len(), 3);
+    }
+}
@@ -0,0 +1,473 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pacing of the BMP or BGP messages re-exported to a downstream consumer.
+//!
+//! When proxying BMP sessions or re-advertising routes, a full table reload
+//! of a router is re-exported as fast as it's received, which overwhelms slow
+//! downstream collectors. [`PacedSender`] queues the messages and sends them
+//! to a [`Sink`], e.g., a [`tokio_util::codec::FramedWrite`] with a
+//! [`netgauze_bmp_pkt::codec::BmpCodec`], within the messages per second and
+//! bytes per second limits of a [`PacingConfig`].
+//!
+//! The limits are enforced with a leaky bucket, allowing bursts up to the
+//! configured burst duration. Messages that keep the session alive or end it
+//! (see [`Paced::is_priority`]) are sent ahead of the queued messages without
+//! waiting for the bucket, though they still count against the limits.
+
+use std::{collections::VecDeque, time::Duration};
+
+use futures::{Sink, SinkExt};
+use serde::{Deserialize, Serialize};
+use tokio::{sync::mpsc, time::Instant};
+
+use netgauze_bgp_pkt::BgpMessage;
+use netgauze_bmp_pkt::{v4::BmpV4MessageValue, BmpMessage, BmpMessageValue};
+use netgauze_parse_utils::WritablePdu;
+
+/// Default number of messages queued by [`PacedSender::run`] before it stops
+/// receiving new ones
+pub const DEFAULT_MAX_QUEUED: usize = 1024;
+
+/// Messages that can be paced by a [`PacedSender`]
+pub trait Paced {
+    /// Messages sent ahead of the queued ones, without waiting for the rate
+    /// limits
+    fn is_priority(&self) -> bool;
+
+    /// Length of the message on the wire, counted against the bytes per
+    /// second limit
+    fn wire_len(&self) -> usize;
+}
+
+/// Initiation, Termination, and Peer Down messages have priority, so the
+/// downstream collector learns about the state of the sessions without
+/// waiting behind a table reload
+impl Paced for BmpMessage {
+    fn is_priority(&self) -> bool {
+        matches!(
+            self,
+            BmpMessage::V3(
+                BmpMessageValue::Initiation(_)
+                    | BmpMessageValue::Termination(_)
+                    | BmpMessageValue::PeerDownNotification(_)
+            ) | BmpMessage::V4(
+                BmpV4MessageValue::Initiation(_)
+                    | BmpV4MessageValue::Termination(_)
+                    | BmpV4MessageValue::PeerDownNotification(_)
+            )
+        )
+    }
+
+    fn wire_len(&self) -> usize {
+        self.len()
+    }
+}
+
+/// KeepAlive and Notification messages have priority, so the hold timer of
+/// the downstream peer doesn't expire during a table reload
+impl Paced for BgpMessage {
+    fn is_priority(&self) -> bool {
+        matches!(self, BgpMessage::KeepAlive | BgpMessage::Notification(_))
+    }
+
+    fn wire_len(&self) -> usize {
+        self.len()
+    }
+}
+
+/// Rate limits of a [`PacedSender`], no limit is enforced by default
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PacingConfig {
+    messages_per_second: Option<f64>,
+    bytes_per_second: Option<f64>,
+    burst: Duration,
+}
+
+impl Default for PacingConfig {
+    fn default() -> Self {
+        Self {
+            messages_per_second: None,
+            bytes_per_second: None,
+            burst: Duration::ZERO,
+        }
+    }
+}
+
+impl PacingConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_messages_per_second(mut self, messages_per_second: f64) -> Self {
+        self.messages_per_second = Some(messages_per_second).filter(|rate| *rate > 0.0);
+        self
+    }
+
+    pub fn with_bytes_per_second(mut self, bytes_per_second: f64) -> Self {
+        self.bytes_per_second = Some(bytes_per_second).filter(|rate| *rate > 0.0);
+        self
+    }
+
+    /// Time ahead of the rate that messages are sent without waiting, e.g.,
+    /// with 100 messages per second and a burst of one second, up to 100
+    /// messages are sent at once after being idle
+    pub const fn with_burst(mut self, burst: Duration) -> Self {
+        self.burst = burst;
+        self
+    }
+
+    pub const fn messages_per_second(&self) -> Option<f64> {
+        self.messages_per_second
+    }
+
+    pub const fn bytes_per_second(&self) -> Option<f64> {
+        self.bytes_per_second
+    }
+
+    pub const fn burst(&self) -> Duration {
+        self.burst
+    }
+}
+
+/// Leaky bucket with the theoretical arrival time of the next message per
+/// limit, as in the Generic Cell Rate Algorithm
+#[derive(Debug, Clone)]
+struct LeakyBucket {
+    config: PacingConfig,
+    next_message: Option<Instant>,
+    next_byte: Option<Instant>,
+}
+
+impl LeakyBucket {
+    const fn new(config: PacingConfig) -> Self {
+        Self {
+            config,
+            next_message: None,
+            next_byte: None,
+        }
+    }
+
+    /// Earliest time a message can be sent
+    fn ready_at(&self, now: Instant) -> Instant {
+        let earliest = |next: Option<Instant>| {
+            next.and_then(|next| next.checked_sub(self.config.burst))
+                .unwrap_or(now)
+        };
+        earliest(self.next_message)
+            .max(earliest(self.next_byte))
+            .max(now)
+    }
+
+    /// Count a message of `len` bytes sent at `now` against the limits
+    fn consume(&mut self, len: usize, now: Instant) {
+        let advance = |next: Option<Instant>, cost: f64| {
+            next.unwrap_or(now).max(now) + Duration::from_secs_f64(cost)
+        };
+        if let Some(rate) = self.config.messages_per_second {
+            self.next_message = Some(advance(self.next_message, 1.0 / rate));
+        }
+        if let Some(rate) = self.config.bytes_per_second {
+            self.next_byte = Some(advance(self.next_byte, len as f64 / rate));
+        }
+    }
+}
+
+/// Totals of what was sent by a [`PacedSender`]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PacingStats {
+    pub messages: u64,
+    pub bytes: u64,
+    /// Messages sent ahead of the queue
+    pub priority_messages: u64,
+    /// Total time spent waiting for the rate limits
+    pub delayed: Duration,
+}
+
+/// Sends messages to a downstream [`Sink`] within the rate limits of a
+/// [`PacingConfig`], see the [module documentation](self)
+#[derive(Debug)]
+pub struct PacedSender<S, M> {
+    sink: S,
+    bucket: LeakyBucket,
+    max_queued: usize,
+    priority: VecDeque<M>,
+    queue: VecDeque<M>,
+    stats: PacingStats,
+}
+
+impl<S, M> PacedSender<S, M>
+where
+    S: Sink<M> + Unpin,
+    M: Paced,
+{
+    pub fn new(sink: S, config: PacingConfig) -> Self {
+        Self {
+            sink,
+            bucket: LeakyBucket::new(config),
+            max_queued: DEFAULT_MAX_QUEUED,
+            priority: VecDeque::new(),
+            queue: VecDeque::new(),
+            stats: PacingStats::default(),
+        }
+    }
+
+    /// Messages queued by [`Self::run`] before it stops receiving new ones,
+    /// to push back on the producer. Defaults to [`DEFAULT_MAX_QUEUED`].
+    pub fn with_max_queued(mut self, max_queued: usize) -> Self {
+        self.max_queued = max_queued.max(1);
+        self
+    }
+
+    pub const fn config(&self) -> &PacingConfig {
+        &self.bucket.config
+    }
+
+    pub const fn stats(&self) -> PacingStats {
+        self.stats
+    }
+
+    pub const fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    /// Number of messages waiting to be sent
+    pub fn queued(&self) -> usize {
+        self.priority.len() + self.queue.len()
+    }
+
+    /// Queue a message, priority messages are queued ahead of the others
+    pub fn push(&mut self, msg: M) {
+        if msg.is_priority() {
+            self.priority.push_back(msg);
+        } else {
+            self.queue.push_back(msg);
+        }
+    }
+
+    /// Send the next queued message, waiting for the rate limits unless it's
+    /// a priority message. Returns `false` when the queue is empty.
+    pub async fn send_next(&mut self) -> Result<bool, S::Error> {
+        let msg = match self.priority.pop_front() {
+            Some(msg) => {
+                self.stats.priority_messages += 1;
+                msg
+            }
+            None => {
+                if self.queue.is_empty() {
+                    return Ok(false);
+                }
+                let now = Instant::now();
+                let ready_at = self.bucket.ready_at(now);
+                if ready_at > now {
+                    tokio::time::sleep_until(ready_at).await;
+                    self.stats.delayed += ready_at - now;
+                }
+                match self.queue.pop_front() {
+                    Some(msg) => msg,
+                    None => return Ok(false),
+                }
+            }
+        };
+        let len = msg.wire_len();
+        self.bucket.consume(len, Instant::now());
+        self.sink.send(msg).await?;
+        self.stats.messages += 1;
+        self.stats.bytes += len as u64;
+        Ok(true)
+    }
+
+    /// Send the messages received from `rx` until it's closed and the queue
+    /// is drained, then close the sink. Messages keep being received while
+    /// waiting for the rate limits, so priority messages overtake the queue.
+    pub async fn run(mut self, mut rx: mpsc::Receiver<M>) -> Result<PacingStats, S::Error> {
+        let mut receiving = true;
+        loop {
+            while receiving && self.queued() < self.max_queued {
+                match rx.try_recv() {
+                    Ok(msg) => self.push(msg),
+                    Err(mpsc::error::TryRecvError::Empty) => break,
+                    Err(mpsc::error::TryRecvError::Disconnected) => receiving = false,
+                }
+            }
+            if self.queued() == 0 {
+                if !receiving {
+                    break;
+                }
+                match rx.recv().await {
+                    Some(msg) => self.push(msg),
+                    None => receiving = false,
+                }
+                continue;
+            }
+            if !receiving || self.queued() >= self.max_queued || !self.priority.is_empty() {
+                self.send_next().await?;
+                continue;
+            }
+            tokio::select! {
+                biased;
+                msg = rx.recv() => match msg {
+                    Some(msg) => self.push(msg),
+                    None => receiving = false,
+                },
+                _ = tokio::time::sleep_until(self.bucket.ready_at(Instant::now())) => {
+                    self.send_next().await?;
+                },
+            }
+        }
+        self.sink.close().await?;
+        Ok(self.stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use netgauze_bmp_pkt::{InitiationMessage, TerminationMessage};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct TestMessage {
+        id: u32,
+        priority: bool,
+        len: usize,
+    }
+
+    impl Paced for TestMessage {
+        fn is_priority(&self) -> bool {
+            self.priority
+        }
+
+        fn wire_len(&self) -> usize {
+            self.len
+        }
+    }
+
+    fn msg(id: u32, len: usize) -> TestMessage {
+        TestMessage {
+            id,
+            priority: false,
+            len,
+        }
+    }
+
+    #[test]
+    fn test_priority() {
+        let initiation =
+            BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(vec![])));
+        assert!(initiation.is_priority());
+        assert_eq!(initiation.wire_len(), 6);
+        assert!(BgpMessage::KeepAlive.is_priority());
+        assert_eq!(BgpMessage::KeepAlive.wire_len(), 19);
+        let termination = BmpMessage::V3(BmpMessageValue::Termination(TerminationMessage::new(
+            netgauze_bmp_pkt::PeerHeader::new(
+                netgauze_bmp_pkt::BmpPeerType::GlobalInstancePeer {
+                    ipv6: false,
+                    post_policy: false,
+                    asn2: false,
+                    adj_rib_out: false,
+                },
+                None,
+                None,
+                0,
+                std::net::Ipv4Addr::UNSPECIFIED,
+                None,
+            ),
+            vec![],
+        )));
+        assert!(termination.is_priority());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_messages_per_second() {
+        let mut sender = PacedSender::new(
+            Vec::new(),
+            PacingConfig::new().with_messages_per_second(10.0),
+        );
+        let start = Instant::now();
+        for id in 0..5 {
+            sender.push(msg(id, 100));
+        }
+        while sender.send_next().await.unwrap() {}
+        // The first message is sent right away, then one every 100ms
+        assert_eq!(start.elapsed(), Duration::from_millis(400));
+        assert_eq!(sender.stats().messages, 5);
+        assert_eq!(sender.stats().bytes, 500);
+        assert_eq!(sender.stats().delayed, Duration::from_millis(400));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_bytes_per_second_with_burst() {
+        let mut sender = PacedSender::new(
+            Vec::new(),
+            PacingConfig::new()
+                .with_bytes_per_second(1000.0)
+                .with_burst(Duration::from_millis(500)),
+        );
+        let start = Instant::now();
+        for id in 0..10 {
+            sender.push(msg(id, 100));
+        }
+        while sender.send_next().await.unwrap() {}
+        // 1000 bytes take a second, the first message and the next 500ms
+        // worth of messages are sent in a burst
+        assert_eq!(start.elapsed(), Duration::from_millis(400));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_priority_overtakes_queue() {
+        let (tx, rx) = mpsc::channel(16);
+        let (sink, sent) = futures::channel::mpsc::unbounded();
+        let sender = PacedSender::new(sink, PacingConfig::new().with_messages_per_second(1.0));
+        let task = tokio::spawn(sender.run(rx));
+        for id in 0..3 {
+            tx.send(msg(id, 10)).await.unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        tx.send(TestMessage {
+            id: 100,
+            priority: true,
+            len: 10,
+        })
+        .await
+        .unwrap();
+        drop(tx);
+
+        let stats = task.await.unwrap().unwrap();
+        assert_eq!(stats.priority_messages, 1);
+        let sent: Vec<u32> = sent.map(|msg| msg.id).collect().await;
+        assert_eq!(sent, vec![0, 1, 100, 2]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_run() {
+        let (tx, rx) = mpsc::channel(16);
+        let sender = PacedSender::new(
+            Vec::new(),
+            PacingConfig::new().with_messages_per_second(2.0),
+        )
+        .with_max_queued(2);
+        let start = Instant::now();
+        let task = tokio::spawn(sender.run(rx));
+        for id in 0..4 {
+            tx.send(msg(id, 10)).await.unwrap();
+        }
+        drop(tx);
+        let stats = task.await.unwrap().unwrap();
+        assert_eq!(stats.messages, 4);
+        assert_eq!(stats.bytes, 40);
+        assert_eq!(start.elapsed(), Duration::from_millis(1500));
+    }
+}
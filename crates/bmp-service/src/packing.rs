@@ -0,0 +1,587 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Packing statistics of the Route Monitoring messages of a BMP archive.
+//!
+//! Routers differ in how many prefixes they pack in the BGP UPDATEs sent in
+//! Route Monitoring messages. [`PackingAnalyzer`] goes over the messages of
+//! an archive, e.g., a recording made by [`crate::recorder`], and reports the
+//! distribution of updates per message and prefixes per update, how often
+//! the path attribute sets are reused, and how many bytes would be saved if
+//! the updates sharing the same path attributes were packed together. The
+//! report guides operators configuring the BMP batching of their routers.
+//!
+//! Savings are estimated conservatively: only consecutive updates of the same
+//! peer are packed, since reordering the updates of a peer could change the
+//! resulting routes, and a packed update never exceeds the maximum BGP
+//! message size.
+
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap, HashSet},
+    hash::{Hash, Hasher},
+};
+
+use serde::{Deserialize, Serialize};
+
+use netgauze_bgp_pkt::{
+    path_attribute::PathAttributeValue,
+    update::BgpUpdateMessage,
+    wire::{
+        deserializer::BGP_MAX_MESSAGE_LENGTH, serializer::path_attribute::PathAttributeWritingError,
+    },
+    BgpMessage,
+};
+use netgauze_bmp_pkt::{
+    BmpMessage, BmpMessageValue, MirroredBgpMessage, PeerHeader, PeerKey, PeerKeyStrategy,
+    RouteMirroringValue,
+};
+use netgauze_parse_utils::WritablePdu;
+
+/// Distribution of the values of a sample, kept as a histogram of exact
+/// values
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Distribution {
+    histogram: BTreeMap<usize, u64>,
+}
+
+impl Distribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, value: usize) {
+        *self.histogram.entry(value).or_default() += 1;
+    }
+
+    /// Number of occurrences of each value
+    pub const fn histogram(&self) -> &BTreeMap<usize, u64> {
+        &self.histogram
+    }
+
+    /// Number of recorded values
+    pub fn count(&self) -> u64 {
+        self.histogram.values().sum()
+    }
+
+    /// Sum of the recorded values
+    pub fn sum(&self) -> u64 {
+        self.histogram
+            .iter()
+            .map(|(value, count)| *value as u64 * count)
+            .sum()
+    }
+
+    pub fn min(&self) -> Option<usize> {
+        self.histogram.keys().next().copied()
+    }
+
+    pub fn max(&self) -> Option<usize> {
+        self.histogram.keys().next_back().copied()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        match self.count() {
+            0 => None,
+            count => Some(self.sum() as f64 / count as f64),
+        }
+    }
+
+    /// Smallest recorded value that is greater than or equal to the given
+    /// ratio of the values, e.g., `0.5` for the median
+    pub fn quantile(&self, ratio: f64) -> Option<usize> {
+        let rank = (ratio.clamp(0.0, 1.0) * self.count() as f64).ceil() as u64;
+        let mut seen = 0;
+        for (value, count) in &self.histogram {
+            seen += count;
+            if seen >= rank.max(1) {
+                return Some(*value);
+            }
+        }
+        None
+    }
+}
+
+/// Packing statistics of the Route Monitoring messages seen by a
+/// [`PackingAnalyzer`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackingReport {
+    route_monitoring_messages: u64,
+    updates_per_message: Distribution,
+    prefixes_per_update: Distribution,
+    updates_per_packed_message: Distribution,
+    attribute_sets: u64,
+    reused_attribute_sets: u64,
+    bytes: u64,
+    packed_bytes: u64,
+}
+
+impl PackingReport {
+    pub const fn route_monitoring_messages(&self) -> u64 {
+        self.route_monitoring_messages
+    }
+
+    /// BGP UPDATEs carried by each BMP message. A Route Monitoring message
+    /// always carries a single update, while a Route Mirroring message may
+    /// carry several.
+    pub const fn updates_per_message(&self) -> &Distribution {
+        &self.updates_per_message
+    }
+
+    /// Announced and withdrawn prefixes of each update in Route Monitoring
+    /// messages, End-of-RIB markers carry none
+    pub const fn prefixes_per_update(&self) -> &Distribution {
+        &self.prefixes_per_update
+    }
+
+    /// Updates merged in each message once packed
+    pub const fn updates_per_packed_message(&self) -> &Distribution {
+        &self.updates_per_packed_message
+    }
+
+    /// Number of distinct path attribute sets, counted per peer
+    pub const fn attribute_sets(&self) -> u64 {
+        self.attribute_sets
+    }
+
+    /// Number of updates carrying a path attribute set already seen from
+    /// the same peer
+    pub const fn reused_attribute_sets(&self) -> u64 {
+        self.reused_attribute_sets
+    }
+
+    /// Ratio of the updates reusing a path attribute set, zero when no
+    /// updates were seen
+    pub fn attribute_reuse_ratio(&self) -> f64 {
+        match self.route_monitoring_messages {
+            0 => 0.0,
+            messages => self.reused_attribute_sets as f64 / messages as f64,
+        }
+    }
+
+    /// Size of the Route Monitoring messages as seen
+    pub const fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Size of the Route Monitoring messages once packed
+    pub const fn packed_bytes(&self) -> u64 {
+        self.packed_bytes
+    }
+
+    pub fn packed_messages(&self) -> u64 {
+        self.updates_per_packed_message.count()
+    }
+
+    /// Bytes saved by packing
+    pub const fn savings(&self) -> u64 {
+        self.bytes - self.packed_bytes
+    }
+
+    /// Ratio of the bytes saved by packing, zero when no updates were seen
+    pub fn savings_ratio(&self) -> f64 {
+        match self.bytes {
+            0 => 0.0,
+            bytes => self.savings() as f64 / bytes as f64,
+        }
+    }
+}
+
+/// Updates of a peer packed so far in the same message
+#[derive(Debug, Clone, Copy)]
+struct PackedMessage {
+    attributes: u64,
+    size: usize,
+    updates: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+struct PeerPacking {
+    attribute_sets: HashSet<u64>,
+    packed: Option<PackedMessage>,
+}
+
+/// Collect the [`PackingReport`] of a stream of BMP messages, see the
+/// [module documentation](self)
+///
+/// Path attribute sets are identified by a hash of their wire encoding,
+/// along with the address families and next hops of the routes, since routes
+/// that differ in these can't share an update either.
+#[derive(Debug, Clone)]
+pub struct PackingAnalyzer {
+    peer_key_strategy: PeerKeyStrategy,
+    max_message_size: usize,
+    peers: HashMap<PeerKey, PeerPacking>,
+    report: PackingReport,
+    buf: Vec<u8>,
+}
+
+impl Default for PackingAnalyzer {
+    fn default() -> Self {
+        Self {
+            peer_key_strategy: PeerKeyStrategy::default(),
+            max_message_size: BGP_MAX_MESSAGE_LENGTH as usize,
+            peers: HashMap::new(),
+            report: PackingReport::default(),
+            buf: vec![],
+        }
+    }
+}
+
+impl PackingAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How the monitored peers are told apart
+    pub fn with_peer_key_strategy(mut self, peer_key_strategy: PeerKeyStrategy) -> Self {
+        self.peer_key_strategy = peer_key_strategy;
+        self
+    }
+
+    /// Maximum size of a packed BGP message, 4096 octets by default. Sessions
+    /// that negotiated [RFC8654](https://datatracker.ietf.org/doc/html/rfc8654)
+    /// extended messages allow up to 65535 octets.
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    pub const fn peer_key_strategy(&self) -> PeerKeyStrategy {
+        self.peer_key_strategy
+    }
+
+    pub const fn max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
+    /// Account for a BMP message of the archive, messages other than Route
+    /// Monitoring and Route Mirroring only delimit the sessions of the peers
+    pub fn apply(&mut self, msg: &BmpMessage) -> Result<(), PathAttributeWritingError> {
+        let value = msg.to_v3_value();
+        match value.as_ref() {
+            BmpMessageValue::RouteMonitoring(route_monitoring) => {
+                if let BgpMessage::Update(update) = route_monitoring.update_message() {
+                    self.update(route_monitoring.peer_header(), update, msg.len())?;
+                }
+            }
+            BmpMessageValue::RouteMirroring(route_mirroring) => {
+                let updates = route_mirroring
+                    .mirrored()
+                    .iter()
+                    .filter(|value| {
+                        matches!(
+                            value,
+                            RouteMirroringValue::BgpMessage(MirroredBgpMessage::Parsed(
+                                BgpMessage::Update(_)
+                            ))
+                        )
+                    })
+                    .count();
+                if updates > 0 {
+                    self.report.updates_per_message.record(updates);
+                }
+            }
+            BmpMessageValue::PeerDownNotification(peer_down) => {
+                let peer = self.peer_key(peer_down.peer_header());
+                if let Some(peer) = self.peers.remove(&peer) {
+                    self.close(peer.packed);
+                }
+            }
+            BmpMessageValue::Termination(_) => {
+                for (_, peer) in std::mem::take(&mut self.peers) {
+                    self.close(peer.packed);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn peer_key(&self, header: &PeerHeader) -> PeerKey {
+        self.peer_key_strategy
+            .apply(PeerKey::from_peer_header(header))
+    }
+
+    fn update(
+        &mut self,
+        peer_header: &PeerHeader,
+        update: &BgpUpdateMessage,
+        message_len: usize,
+    ) -> Result<(), PathAttributeWritingError> {
+        let attributes = self.attributes_key(update)?;
+        let peer_key = self.peer_key(peer_header);
+        let (prefixes, prefixes_len) = update
+            .announced()
+            .chain(update.withdrawn())
+            .fold((0, 0), |(count, len), route| {
+                (count + 1, len + route.prefix().wire_len())
+            });
+        let report = &mut self.report;
+        report.route_monitoring_messages += 1;
+        report.updates_per_message.record(1);
+        report.prefixes_per_update.record(prefixes);
+        report.bytes += message_len as u64;
+
+        let peer = self.peers.entry(peer_key).or_default();
+        if peer.attribute_sets.insert(attributes) {
+            report.attribute_sets += 1;
+        } else {
+            report.reused_attribute_sets += 1;
+        }
+        match peer.packed.take() {
+            // End-of-RIB markers are never packed with other updates
+            Some(mut packed)
+                if prefixes > 0
+                    && packed.attributes == attributes
+                    && packed.size + prefixes_len <= self.max_message_size =>
+            {
+                report.packed_bytes += prefixes_len as u64;
+                packed.size += prefixes_len;
+                packed.updates += 1;
+                peer.packed = Some(packed);
+            }
+            previous => {
+                report.packed_bytes += message_len as u64;
+                if let Some(previous) = previous {
+                    report.updates_per_packed_message.record(previous.updates);
+                }
+                if prefixes > 0 {
+                    peer.packed = Some(PackedMessage {
+                        attributes,
+                        size: BgpMessage::BASE_LENGTH + update.len(),
+                        updates: 1,
+                    });
+                } else {
+                    report.updates_per_packed_message.record(1);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn attributes_key(
+        &mut self,
+        update: &BgpUpdateMessage,
+    ) -> Result<u64, PathAttributeWritingError> {
+        let mut hasher = DefaultHasher::new();
+        for attribute in update.path_attributes() {
+            if matches!(
+                attribute.value(),
+                PathAttributeValue::MpReach(_) | PathAttributeValue::MpUnreach(_)
+            ) {
+                continue;
+            }
+            self.buf.clear();
+            attribute.write(&mut self.buf)?;
+            self.buf.hash(&mut hasher);
+        }
+        let mut previous = None;
+        for route in update.announced() {
+            let family = (route.address_type(), route.next_hop());
+            if previous != Some(family) {
+                family.hash(&mut hasher);
+                previous = Some(family);
+            }
+        }
+        // Withdrawals are hashed apart from the announcements
+        let mut previous = None;
+        hasher.write_u8(0);
+        for route in update.withdrawn() {
+            if previous != Some(route.address_type()) {
+                route.address_type().hash(&mut hasher);
+                previous = Some(route.address_type());
+            }
+        }
+        Ok(hasher.finish())
+    }
+
+    fn close(&mut self, packed: Option<PackedMessage>) {
+        if let Some(packed) = packed {
+            self.report
+                .updates_per_packed_message
+                .record(packed.updates);
+        }
+    }
+
+    /// Statistics of the messages seen so far, the updates still being packed
+    /// count as packed messages
+    pub fn report(&self) -> PackingReport {
+        let mut report = self.report.clone();
+        for packed in self.peers.values().filter_map(|peer| peer.packed) {
+            report.updates_per_packed_message.record(packed.updates);
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netgauze_bgp_pkt::{
+        nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+        path_attribute::{NextHop, Origin, PathAttribute},
+    };
+    use netgauze_bmp_pkt::{
+        BmpPeerType, PeerDownNotificationMessage, PeerDownNotificationReason,
+        RouteMonitoringMessage,
+    };
+    use std::net::Ipv4Addr;
+
+    fn peer_header(peer: u8) -> PeerHeader {
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(Ipv4Addr::new(192, 0, 2, peer).into()),
+            65000 + u32::from(peer),
+            Ipv4Addr::new(192, 0, 2, peer),
+            None,
+        )
+    }
+
+    fn nlri(prefixes: &[&str]) -> Vec<Ipv4UnicastAddress> {
+        prefixes
+            .iter()
+            .map(|prefix| {
+                Ipv4UnicastAddress::new_no_path_id(
+                    Ipv4Unicast::from_net(prefix.parse().unwrap()).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    fn update(peer: u8, next_hop: u8, prefixes: &[&str]) -> BmpMessage {
+        let attrs = if prefixes.is_empty() {
+            vec![]
+        } else {
+            vec![
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::Origin(Origin::IGP),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(203, 0, 113, next_hop))),
+                )
+                .unwrap(),
+            ]
+        };
+        let update = BgpUpdateMessage::new(vec![], attrs, nlri(prefixes));
+        BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            RouteMonitoringMessage::build(peer_header(peer), BgpMessage::Update(update)).unwrap(),
+        ))
+    }
+
+    fn peer_down(peer: u8) -> BmpMessage {
+        BmpMessage::V3(BmpMessageValue::PeerDownNotification(
+            PeerDownNotificationMessage::build(
+                peer_header(peer),
+                PeerDownNotificationReason::RemoteSystemClosedNoData,
+            )
+            .unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_distribution() {
+        let mut distribution = Distribution::new();
+        assert_eq!(distribution.mean(), None);
+        assert_eq!(distribution.quantile(0.5), None);
+        for value in [1, 1, 2, 10] {
+            distribution.record(value);
+        }
+        assert_eq!(distribution.count(), 4);
+        assert_eq!(distribution.sum(), 14);
+        assert_eq!(distribution.min(), Some(1));
+        assert_eq!(distribution.max(), Some(10));
+        assert_eq!(distribution.mean(), Some(3.5));
+        assert_eq!(distribution.quantile(0.0), Some(1));
+        assert_eq!(distribution.quantile(0.5), Some(1));
+        assert_eq!(distribution.quantile(0.75), Some(2));
+        assert_eq!(distribution.quantile(1.0), Some(10));
+        assert_eq!(distribution.histogram().get(&1), Some(&2));
+    }
+
+    #[test]
+    fn test_packing_analyzer() {
+        let messages = vec![
+            update(1, 1, &["198.51.100.0/24"]),
+            // Interleaved updates of another peer don't prevent packing
+            update(2, 1, &["198.51.100.0/24"]),
+            update(1, 1, &["198.51.101.0/24", "198.51.102.0/24"]),
+            update(1, 2, &["198.51.103.0/24"]),
+            // Reused, but not consecutive, so not packed
+            update(1, 1, &["198.51.104.0/24"]),
+            // End-of-RIB
+            update(1, 0, &[]),
+        ];
+        let mut analyzer = PackingAnalyzer::new();
+        for msg in &messages {
+            analyzer.apply(msg).unwrap();
+        }
+        let report = analyzer.report();
+        let bytes = messages.iter().map(|msg| msg.len() as u64).sum::<u64>();
+        assert_eq!(report.route_monitoring_messages(), 6);
+        assert_eq!(report.updates_per_message().count(), 6);
+        assert_eq!(
+            report.prefixes_per_update().histogram(),
+            &BTreeMap::from([(0, 1), (1, 4), (2, 1)])
+        );
+        assert_eq!(report.attribute_sets(), 4);
+        assert_eq!(report.reused_attribute_sets(), 2);
+        assert_eq!(report.attribute_reuse_ratio(), 2.0 / 6.0);
+        assert_eq!(
+            report.updates_per_packed_message().histogram(),
+            &BTreeMap::from([(1, 4), (2, 1)])
+        );
+        assert_eq!(report.packed_messages(), 5);
+        assert_eq!(report.bytes(), bytes);
+        // The packed update only adds its two prefixes of 4 octets each
+        assert_eq!(report.savings(), messages[2].len() as u64 - 8);
+        assert_eq!(report.packed_bytes(), bytes - report.savings());
+
+        // Nothing fits in a message at the size of the first update
+        let mut analyzer = PackingAnalyzer::new().with_max_message_size(messages[0].len() - 48);
+        for msg in &messages {
+            analyzer.apply(msg).unwrap();
+        }
+        assert_eq!(analyzer.report().savings(), 0);
+        assert_eq!(analyzer.report().packed_messages(), 6);
+    }
+
+    #[test]
+    fn test_packing_analyzer_peer_down() {
+        let mut analyzer = PackingAnalyzer::new();
+        analyzer.apply(&update(1, 1, &["198.51.100.0/24"])).unwrap();
+        analyzer.apply(&peer_down(1)).unwrap();
+        analyzer.apply(&update(1, 1, &["198.51.101.0/24"])).unwrap();
+        let report = analyzer.report();
+        assert_eq!(report.packed_messages(), 2);
+        assert_eq!(report.savings(), 0);
+        // The new session starts afresh
+        assert_eq!(report.reused_attribute_sets(), 0);
+    }
+}
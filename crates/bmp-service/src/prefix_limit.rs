@@ -0,0 +1,557 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Monitoring of the number of prefixes announced by each monitored peer,
+//! similar to the maximum-prefix limit of BGP routers but without tearing
+//! down any session.
+//!
+//! [`PrefixLimitMonitor`] counts the prefixes of each peer from the Route
+//! Monitoring messages and reports a [`PrefixLimitEvent`] each time the count
+//! crosses a [`PrefixThreshold`]. A threshold is raised when the count reaches
+//! its raise value and cleared only once the count drops to its clear value,
+//! so a count oscillating around the limit doesn't flood the alerts.
+//! [`PrefixLimitLayer`] wraps a BMP service to monitor all the requests
+//! received by a [`crate::server::BmpServer`].
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use netgauze_bgp_pkt::BgpMessage;
+use netgauze_bmp_pkt::{BmpMessage, BmpMessageValue, PeerKey, PeerKeyStrategy};
+
+use crate::{server::BmpRequest, view::ViewPrefix, AddrInfo};
+
+/// Prefix count limit with hysteresis
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PrefixThreshold {
+    raise: usize,
+    clear: usize,
+}
+
+impl PrefixThreshold {
+    /// Threshold raised when the prefix count reaches `raise` and cleared
+    /// when it drops to `clear`. Returns `None` if `clear` is not lower than
+    /// `raise`.
+    pub const fn new(raise: usize, clear: usize) -> Option<Self> {
+        if clear >= raise {
+            return None;
+        }
+        Some(Self { raise, clear })
+    }
+
+    /// Threshold at `percent` of a maximum-prefix `limit`, cleared 5% below,
+    /// e.g., the warning-only threshold of a router configuration
+    pub const fn percent_of(limit: usize, percent: usize) -> Option<Self> {
+        let raise = limit * percent / 100;
+        let hysteresis = match limit * 5 / 100 {
+            0 => 1,
+            value => value,
+        };
+        Self::new(raise, raise.saturating_sub(hysteresis))
+    }
+
+    pub const fn raise(&self) -> usize {
+        self.raise
+    }
+
+    pub const fn clear(&self) -> usize {
+        self.clear
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PrefixLimitEventKind {
+    /// The prefix count reached the raise value of the threshold
+    Raise,
+    /// The prefix count dropped to the clear value of a raised threshold, or
+    /// the peer went down
+    Clear,
+}
+
+/// A [`PrefixThreshold`] of a monitored peer is crossed
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrefixLimitEvent {
+    router: AddrInfo,
+    peer: PeerKey,
+    kind: PrefixLimitEventKind,
+    threshold: PrefixThreshold,
+    count: usize,
+}
+
+impl PrefixLimitEvent {
+    pub const fn new(
+        router: AddrInfo,
+        peer: PeerKey,
+        kind: PrefixLimitEventKind,
+        threshold: PrefixThreshold,
+        count: usize,
+    ) -> Self {
+        Self {
+            router,
+            peer,
+            kind,
+            threshold,
+            count,
+        }
+    }
+
+    pub const fn router(&self) -> AddrInfo {
+        self.router
+    }
+
+    /// Key of the monitored peer, according to the [`PeerKeyStrategy`] of
+    /// the monitor
+    pub const fn peer(&self) -> PeerKey {
+        self.peer
+    }
+
+    pub const fn kind(&self) -> PrefixLimitEventKind {
+        self.kind
+    }
+
+    pub const fn threshold(&self) -> PrefixThreshold {
+        self.threshold
+    }
+
+    /// Prefix count of the peer when the threshold is crossed
+    pub const fn count(&self) -> usize {
+        self.count
+    }
+}
+
+#[derive(Debug, Default)]
+struct PeerPrefixes {
+    prefixes: HashSet<(ViewPrefix, Option<u32>)>,
+    /// Raised thresholds
+    raised: HashSet<PrefixThreshold>,
+}
+
+/// Counts the prefixes announced by the monitored peers of each router and
+/// reports the crossed thresholds. With ADD-PATH, each path of a prefix is
+/// counted. Routes without an IP prefix are not counted.
+///
+/// Thresholds are checked once per BMP message. The counts of a peer are
+/// dropped, and its raised thresholds cleared, when the peer goes down or the
+/// BMP session of its router ends.
+#[derive(Debug, Default)]
+pub struct PrefixLimitMonitor {
+    peer_key_strategy: PeerKeyStrategy,
+    thresholds: Vec<PrefixThreshold>,
+    peer_thresholds: HashMap<IpAddr, Vec<PrefixThreshold>>,
+    peers: HashMap<(AddrInfo, PeerKey), PeerPrefixes>,
+}
+
+impl PrefixLimitMonitor {
+    /// Monitor all the peers with the same thresholds
+    pub fn new(thresholds: Vec<PrefixThreshold>) -> Self {
+        Self {
+            thresholds,
+            ..Default::default()
+        }
+    }
+
+    /// How the monitored peers of each router are told apart, see
+    /// [`PeerKeyStrategy::Full`] for the default
+    pub fn with_peer_key_strategy(mut self, peer_key_strategy: PeerKeyStrategy) -> Self {
+        self.peer_key_strategy = peer_key_strategy;
+        self
+    }
+
+    /// Thresholds of the peers with the given address, replacing the
+    /// thresholds common to all the peers
+    pub fn with_peer_thresholds(
+        mut self,
+        peer_address: IpAddr,
+        thresholds: Vec<PrefixThreshold>,
+    ) -> Self {
+        self.peer_thresholds.insert(peer_address, thresholds);
+        self
+    }
+
+    pub const fn peer_key_strategy(&self) -> PeerKeyStrategy {
+        self.peer_key_strategy
+    }
+
+    /// Thresholds of a peer
+    pub fn thresholds(&self, peer: &PeerKey) -> &[PrefixThreshold] {
+        peer.peer_address()
+            .and_then(|address| self.peer_thresholds.get(&address))
+            .unwrap_or(&self.thresholds)
+    }
+
+    /// Current prefix count of a peer of `router`
+    pub fn count(&self, router: AddrInfo, peer: PeerKey) -> usize {
+        self.peers
+            .get(&(router, peer))
+            .map_or(0, |peer| peer.prefixes.len())
+    }
+
+    /// Process a request received by the BMP server, the end of the
+    /// connection or decoding errors end the session of the router.
+    pub fn check_request(&mut self, request: &BmpRequest) -> Vec<PrefixLimitEvent> {
+        match request {
+            Ok(tagged) => match tagged.value() {
+                Some(msg) => self.check(tagged.tag(), msg),
+                None => self.remove_router(tagged.tag()),
+            },
+            Err(tagged) => self.remove_router(tagged.tag()),
+        }
+    }
+
+    /// Process the next message received from `router`
+    pub fn check(&mut self, router: AddrInfo, msg: &BmpMessage) -> Vec<PrefixLimitEvent> {
+        let value = msg.to_v3_value();
+        match value.as_ref() {
+            BmpMessageValue::RouteMonitoring(route_monitoring) => {
+                let update = match route_monitoring.update_message() {
+                    BgpMessage::Update(update) => update,
+                    _ => return vec![],
+                };
+                let peer = self
+                    .peer_key_strategy
+                    .apply(PeerKey::from_peer_header(route_monitoring.peer_header()));
+                let state = self.peers.entry((router, peer)).or_default();
+                for route in update.withdrawn() {
+                    if let Some(prefix) = route.prefix().ip_net() {
+                        let prefix = ViewPrefix::new(route.prefix().rd(), prefix);
+                        state.prefixes.remove(&(prefix, route.path_id()));
+                    }
+                }
+                for route in update.announced() {
+                    if let Some(prefix) = route.prefix().ip_net() {
+                        let prefix = ViewPrefix::new(route.prefix().rd(), prefix);
+                        state.prefixes.insert((prefix, route.path_id()));
+                    }
+                }
+                self.check_thresholds(router, peer)
+            }
+            BmpMessageValue::PeerDownNotification(peer_down) => {
+                let peer = self
+                    .peer_key_strategy
+                    .apply(PeerKey::from_peer_header(peer_down.peer_header()));
+                self.peers
+                    .remove(&(router, peer))
+                    .map(|state| Self::clear_all(router, peer, state))
+                    .unwrap_or_default()
+            }
+            BmpMessageValue::Termination(_) => self.remove_router(router),
+            _ => vec![],
+        }
+    }
+
+    fn check_thresholds(&mut self, router: AddrInfo, peer: PeerKey) -> Vec<PrefixLimitEvent> {
+        let thresholds = peer
+            .peer_address()
+            .and_then(|address| self.peer_thresholds.get(&address))
+            .unwrap_or(&self.thresholds);
+        let state = match self.peers.get_mut(&(router, peer)) {
+            Some(state) => state,
+            None => return vec![],
+        };
+        let count = state.prefixes.len();
+        let mut events = vec![];
+        for threshold in thresholds {
+            let kind = if state.raised.contains(threshold) {
+                if count > threshold.clear {
+                    continue;
+                }
+                state.raised.remove(threshold);
+                PrefixLimitEventKind::Clear
+            } else {
+                if count < threshold.raise {
+                    continue;
+                }
+                state.raised.insert(*threshold);
+                PrefixLimitEventKind::Raise
+            };
+            events.push(PrefixLimitEvent::new(router, peer, kind, *threshold, count));
+        }
+        events
+    }
+
+    fn clear_all(router: AddrInfo, peer: PeerKey, state: PeerPrefixes) -> Vec<PrefixLimitEvent> {
+        let mut raised = state.raised.into_iter().collect::<Vec<_>>();
+        raised.sort_by_key(|threshold| threshold.raise);
+        raised
+            .into_iter()
+            .map(|threshold| {
+                PrefixLimitEvent::new(router, peer, PrefixLimitEventKind::Clear, threshold, 0)
+            })
+            .collect()
+    }
+
+    fn remove_router(&mut self, router: AddrInfo) -> Vec<PrefixLimitEvent> {
+        let peers = self
+            .peers
+            .keys()
+            .filter(|(addr, _)| *addr == router)
+            .copied()
+            .collect::<Vec<_>>();
+        peers
+            .into_iter()
+            .filter_map(|key| self.peers.remove(&key).map(|state| (key, state)))
+            .flat_map(|((router, peer), state)| Self::clear_all(router, peer, state))
+            .collect()
+    }
+}
+
+/// [`Layer`] counting the prefixes of each peer with a shared
+/// [`PrefixLimitMonitor`]. Raised thresholds are logged as warnings and
+/// cleared ones as info, and both are sent to the events channel, which may be
+/// dropped when the logs are enough.
+#[derive(Debug, Clone)]
+pub struct PrefixLimitLayer {
+    monitor: Arc<Mutex<PrefixLimitMonitor>>,
+    events: mpsc::UnboundedSender<PrefixLimitEvent>,
+}
+
+impl PrefixLimitLayer {
+    pub fn new(
+        monitor: PrefixLimitMonitor,
+        events: mpsc::UnboundedSender<PrefixLimitEvent>,
+    ) -> Self {
+        Self {
+            monitor: Arc::new(Mutex::new(monitor)),
+            events,
+        }
+    }
+
+    /// The shared monitor, e.g., to query the prefix counts
+    pub fn monitor(&self) -> Arc<Mutex<PrefixLimitMonitor>> {
+        self.monitor.clone()
+    }
+}
+
+impl<S> Layer<S> for PrefixLimitLayer {
+    type Service = PrefixLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        PrefixLimitService {
+            inner,
+            monitor: self.monitor.clone(),
+            events: self.events.clone(),
+        }
+    }
+}
+
+/// Service created by [`PrefixLimitLayer`]
+#[derive(Debug, Clone)]
+pub struct PrefixLimitService<S> {
+    inner: S,
+    monitor: Arc<Mutex<PrefixLimitMonitor>>,
+    events: mpsc::UnboundedSender<PrefixLimitEvent>,
+}
+
+impl<S> Service<BmpRequest> for PrefixLimitService<S>
+where
+    S: Service<BmpRequest>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: BmpRequest) -> Self::Future {
+        let events = self
+            .monitor
+            .lock()
+            .expect("prefix limit monitor lock is poisoned")
+            .check_request(&request);
+        for event in events {
+            match event.kind() {
+                PrefixLimitEventKind::Raise => tracing::warn!("BMP prefix limit raised: {event:?}"),
+                PrefixLimitEventKind::Clear => {
+                    tracing::info!("BMP prefix limit cleared: {event:?}")
+                }
+            }
+            // The receiver is optional, events are still logged when it's
+            // dropped
+            let _ = self.events.send(event);
+        }
+        self.inner.call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use ipnet::Ipv4Net;
+    use netgauze_bgp_pkt::{
+        nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+        path_attribute::{NextHop, Origin, PathAttribute, PathAttributeValue},
+        update::BgpUpdateMessage,
+    };
+    use netgauze_bmp_pkt::{
+        BmpPeerType, PeerDownNotificationMessage, PeerDownNotificationReason, PeerHeader,
+        RouteMonitoringMessage,
+    };
+    use std::{net::Ipv4Addr, str::FromStr};
+
+    fn router() -> AddrInfo {
+        AddrInfo::new(
+            "192.0.2.100:1790".parse().unwrap(),
+            "192.0.2.1:40000".parse().unwrap(),
+        )
+    }
+
+    fn peer_header(peer: u8) -> PeerHeader {
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, peer))),
+            64500 + peer as u32,
+            Ipv4Addr::new(203, 0, 113, peer),
+            Some(Utc.timestamp_opt(1680000000, 0).unwrap()),
+        )
+    }
+
+    fn nlri(prefixes: std::ops::Range<u8>) -> Vec<Ipv4UnicastAddress> {
+        prefixes
+            .map(|i| {
+                Ipv4UnicastAddress::new_no_path_id(
+                    Ipv4Unicast::from_net(Ipv4Net::from_str(&format!("198.51.{i}.0/24")).unwrap())
+                        .unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    fn update(
+        peer: u8,
+        announced: std::ops::Range<u8>,
+        withdrawn: std::ops::Range<u8>,
+    ) -> BmpMessage {
+        let attrs = vec![
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::Origin(Origin::IGP),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(203, 0, 113, peer))),
+            )
+            .unwrap(),
+        ];
+        let update = BgpUpdateMessage::new(nlri(withdrawn), attrs, nlri(announced));
+        BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            RouteMonitoringMessage::build(peer_header(peer), BgpMessage::Update(update)).unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_prefix_threshold() {
+        assert_eq!(PrefixThreshold::new(10, 10), None);
+        assert_eq!(
+            PrefixThreshold::percent_of(1000, 80),
+            PrefixThreshold::new(800, 750)
+        );
+        assert_eq!(
+            PrefixThreshold::percent_of(1000, 100),
+            PrefixThreshold::new(1000, 950)
+        );
+        assert_eq!(
+            PrefixThreshold::percent_of(10, 100),
+            PrefixThreshold::new(10, 9)
+        );
+    }
+
+    #[test]
+    fn test_prefix_limit_monitor() {
+        let warning = PrefixThreshold::new(4, 2).unwrap();
+        let limit = PrefixThreshold::new(6, 5).unwrap();
+        let mut monitor = PrefixLimitMonitor::new(vec![warning, limit])
+            .with_peer_thresholds(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 2)), vec![]);
+        let peer = |peer| PeerKey::from_peer_header(&peer_header(peer));
+        let event = |kind, threshold, count| {
+            PrefixLimitEvent::new(router(), peer(1), kind, threshold, count)
+        };
+
+        assert_eq!(monitor.check(router(), &update(1, 0..3, 0..0)), vec![]);
+        // Announcing the same prefixes again doesn't change the count
+        assert_eq!(monitor.check(router(), &update(1, 0..3, 0..0)), vec![]);
+        assert_eq!(monitor.count(router(), peer(1)), 3);
+        assert_eq!(
+            monitor.check(router(), &update(1, 3..6, 0..0)),
+            vec![
+                event(PrefixLimitEventKind::Raise, warning, 6),
+                event(PrefixLimitEventKind::Raise, limit, 6)
+            ]
+        );
+        // Hysteresis, the warning stays raised above its clear value
+        assert_eq!(
+            monitor.check(router(), &update(1, 0..0, 0..3)),
+            vec![event(PrefixLimitEventKind::Clear, limit, 3)]
+        );
+        assert_eq!(monitor.check(router(), &update(1, 0..1, 0..0)), vec![]);
+        assert_eq!(
+            monitor.check(router(), &update(1, 0..0, 0..4)),
+            vec![event(PrefixLimitEventKind::Clear, warning, 2)]
+        );
+
+        // Peer without thresholds
+        assert_eq!(monitor.check(router(), &update(2, 0..10, 0..0)), vec![]);
+        assert_eq!(monitor.count(router(), peer(2)), 10);
+
+        // Peer down clears the raised thresholds
+        assert_eq!(
+            monitor.check(router(), &update(1, 0..2, 0..0)),
+            vec![event(PrefixLimitEventKind::Raise, warning, 4)]
+        );
+        let peer_down = BmpMessage::V3(BmpMessageValue::PeerDownNotification(
+            PeerDownNotificationMessage::build(
+                peer_header(1),
+                PeerDownNotificationReason::RemoteSystemClosedNoData,
+            )
+            .unwrap(),
+        ));
+        assert_eq!(
+            monitor.check(router(), &peer_down),
+            vec![event(PrefixLimitEventKind::Clear, warning, 0)]
+        );
+        assert_eq!(monitor.count(router(), peer(1)), 0);
+
+        // End of the BMP session
+        assert_eq!(
+            monitor.check_request(&Ok(crate::TaggedData::new(router(), None))),
+            vec![]
+        );
+        assert_eq!(monitor.count(router(), peer(2)), 0);
+    }
+}
@@ -0,0 +1,508 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lossless recording of the raw byte streams received from BMP routers.
+//!
+//! When enabled with [`crate::server::BmpServer::with_recorder`], the bytes
+//! of each connection are written to disk exactly as received, before they
+//! are decoded, so decoding bugs can be replayed later against the original
+//! stream.
+//!
+//! Recordings are laid out as `root/{router ip}/{connection}.{seq}.bmp`,
+//! where `connection` is the connection start time in unix milliseconds and
+//! the remote port. Files are rotated when reaching the configured size, and
+//! concatenating the files of a connection, in the order returned by
+//! [`recorded_files`], reproduces its byte stream.
+//!
+//! The files are written by a writer task per connection, fed through a
+//! bounded queue, so the disk I/O never stalls reading from the router.
+
+use std::{
+    fs, io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use chrono::Utc;
+use pin_project::pin_project;
+use tokio::{
+    fs::File,
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf},
+    sync::mpsc,
+    task::JoinHandle,
+};
+
+use crate::AddrInfo;
+
+/// Default size of a recording file before it's rotated
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Default number of recording files kept per connection
+pub const DEFAULT_MAX_FILES: usize = 16;
+
+/// Default number of reads queued to the writer task of a connection
+pub const DEFAULT_QUEUE_SIZE: usize = 1024;
+
+const RECORDING_EXTENSION: &str = "bmp";
+
+/// Where and how much of the raw BMP streams is recorded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecorderConfig {
+    root: PathBuf,
+    max_file_size: u64,
+    max_files: Option<usize>,
+    queue_size: usize,
+}
+
+impl RecorderConfig {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            max_files: Some(DEFAULT_MAX_FILES),
+            queue_size: DEFAULT_QUEUE_SIZE,
+        }
+    }
+
+    /// Size at which the recording file is rotated, at least one byte
+    pub fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = max_file_size.max(1);
+        self
+    }
+
+    /// Number of files kept per connection, the oldest files of the
+    /// connection are deleted on rotation while the files of the other
+    /// connections of the router are left alone. `None` keeps all the files.
+    pub const fn with_max_files(mut self, max_files: Option<usize>) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    /// Number of reads queued to the writer task, at least one. When the disk
+    /// can't keep up and the queue is full, the recording of the connection is
+    /// stopped rather than leaving a gap in it.
+    pub fn with_queue_size(mut self, queue_size: usize) -> Self {
+        self.queue_size = queue_size.max(1);
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub const fn max_file_size(&self) -> u64 {
+        self.max_file_size
+    }
+
+    pub const fn max_files(&self) -> Option<usize> {
+        self.max_files
+    }
+
+    pub const fn queue_size(&self) -> usize {
+        self.queue_size
+    }
+
+    /// Directory of the recordings of the router connecting from `remote`
+    pub fn router_dir(&self, remote: SocketAddr) -> PathBuf {
+        self.root.join(remote.ip().to_string().replace(':', "_"))
+    }
+}
+
+fn is_recording(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == RECORDING_EXTENSION)
+}
+
+/// Recording files in `router_dir` in the order they were written
+pub fn recorded_files(router_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(router_dir)? {
+        let path = entry?.path();
+        if is_recording(&path) {
+            files.push(path);
+        }
+    }
+    // File names start with fixed width fields, hence sorting them by name
+    // sorts them by connection start time then rotation sequence
+    files.sort();
+    Ok(files)
+}
+
+/// Records the byte stream of a single connection
+#[derive(Debug)]
+pub struct StreamRecorder {
+    config: RecorderConfig,
+    dir: PathBuf,
+    connection: String,
+    seq: u32,
+    written: u64,
+    file: Option<File>,
+}
+
+impl StreamRecorder {
+    /// Recorder for the connection described by `addr_info`, nothing is
+    /// created on disk until the first bytes are recorded.
+    pub fn new(config: RecorderConfig, addr_info: AddrInfo) -> Self {
+        let remote = addr_info.remote_socket();
+        let dir = config.router_dir(remote);
+        let connection = format!("{:013}-{:05}", Utc::now().timestamp_millis(), remote.port());
+        Self {
+            config,
+            dir,
+            connection,
+            seq: 0,
+            written: 0,
+            file: None,
+        }
+    }
+
+    pub const fn config(&self) -> &RecorderConfig {
+        &self.config
+    }
+
+    /// Path of the file currently being written, if any
+    pub fn current_file(&self) -> Option<PathBuf> {
+        self.file.as_ref().map(|_| self.file_path(self.seq))
+    }
+
+    /// Append `bytes` to the recording, rotating the file as needed
+    pub async fn record(&mut self, mut bytes: &[u8]) -> io::Result<()> {
+        while !bytes.is_empty() {
+            if self.file.is_none() || self.written >= self.config.max_file_size {
+                self.rotate().await?;
+            }
+            let available = self.config.max_file_size - self.written;
+            let len = bytes.len().min(available.try_into().unwrap_or(usize::MAX));
+            let file = self.file.as_mut().expect("recording file is opened above");
+            file.write_all(&bytes[..len]).await?;
+            self.written += len as u64;
+            bytes = &bytes[len..];
+        }
+        Ok(())
+    }
+
+    /// Flush and close the current file
+    pub async fn close(&mut self) -> io::Result<()> {
+        match self.file.take() {
+            Some(mut file) => file.flush().await,
+            None => Ok(()),
+        }
+    }
+
+    /// Record the bytes received from `receiver` until it's closed or
+    /// recording fails
+    async fn run(mut self, mut receiver: mpsc::Receiver<Bytes>) {
+        while let Some(bytes) = receiver.recv().await {
+            if let Err(err) = self.record(&bytes).await {
+                tracing::warn!(
+                    "stopped recording the BMP stream to {:?}: {err}",
+                    self.current_file()
+                );
+                return;
+            }
+        }
+        if let Err(err) = self.close().await {
+            tracing::warn!(
+                "failed to close the BMP recording {:?}: {err}",
+                self.current_file()
+            );
+        }
+    }
+
+    fn file_path(&self, seq: u32) -> PathBuf {
+        self.dir.join(format!(
+            "{}.{seq:06}.{RECORDING_EXTENSION}",
+            self.connection
+        ))
+    }
+
+    async fn rotate(&mut self) -> io::Result<()> {
+        if let Some(mut file) = self.file.take() {
+            file.flush().await?;
+            self.seq += 1;
+        } else {
+            tokio::fs::create_dir_all(&self.dir).await?;
+        }
+        let current = self.file_path(self.seq);
+        self.file = Some(File::create(&current).await?);
+        self.written = 0;
+        if let Some(max_files) = self.config.max_files {
+            let mut files = self.connection_files().await?;
+            files.retain(|path| *path != current);
+            let excess = files.len().saturating_sub(max_files.max(1) - 1);
+            for path in &files[..excess] {
+                tokio::fs::remove_file(path).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Recording files of this connection in the order they were written
+    async fn connection_files(&self) -> io::Result<Vec<PathBuf>> {
+        let prefix = format!("{}.", self.connection);
+        let mut files = vec![];
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let is_connection = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&prefix));
+            if is_connection && is_recording(&path) {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+/// Wraps a connection stream to record the bytes read from it.
+///
+/// The bytes are handed to the writer task of the [`StreamRecorder`] through
+/// a bounded queue. Recording is best effort: when the queue is full or the
+/// writer fails, it's stopped with a `tracing` warning and the connection
+/// carries on.
+#[pin_project]
+#[derive(Debug)]
+pub struct RecordingStream<T> {
+    #[pin]
+    inner: T,
+    sender: Option<mpsc::Sender<Bytes>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl<T> RecordingStream<T> {
+    /// When `recorder` is `None`, the stream is passed through as is.
+    /// Otherwise, spawns the writer task, hence must be called within a
+    /// tokio runtime.
+    pub fn new(inner: T, recorder: Option<StreamRecorder>) -> Self {
+        let (sender, writer) = match recorder {
+            None => (None, None),
+            Some(recorder) => {
+                let (sender, receiver) = mpsc::channel(recorder.config().queue_size());
+                (Some(sender), Some(tokio::spawn(recorder.run(receiver))))
+            }
+        };
+        Self {
+            inner,
+            sender,
+            writer,
+        }
+    }
+
+    /// If the bytes read are still being recorded
+    pub const fn is_recording(&self) -> bool {
+        self.sender.is_some()
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Stop recording and wait for the writer task to write the bytes queued
+    /// so far
+    pub async fn close(self) -> T {
+        drop(self.sender);
+        if let Some(writer) = self.writer {
+            if let Err(err) = writer.await {
+                tracing::warn!("BMP recording writer task failed: {err}");
+            }
+        }
+        self.inner
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for RecordingStream<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.project();
+        let before = buf.filled().len();
+        let poll = this.inner.poll_read(cx, buf);
+        let read = &buf.filled()[before..];
+        if let (Poll::Ready(Ok(())), Some(sender)) = (&poll, this.sender.as_ref()) {
+            if !read.is_empty() {
+                match sender.try_send(Bytes::copy_from_slice(read)) {
+                    Ok(()) => {}
+                    Err(mpsc::error::TrySendError::Full(_)) => {
+                        tracing::warn!(
+                            "stopped recording the BMP stream, the writer is lagging behind"
+                        );
+                        *this.sender = None;
+                    }
+                    // The writer task failed and reported it already
+                    Err(mpsc::error::TrySendError::Closed(_)) => *this.sender = None,
+                }
+            }
+        }
+        poll
+    }
+}
+impl<T: AsyncWrite> AsyncWrite for RecordingStream<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+    use netgauze_bmp_pkt::{
+        codec::BmpCodec, BmpMessage, BmpMessageValue, InitiationInformation, InitiationMessage,
+    };
+    use tokio::io::AsyncReadExt;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    #[tokio::test]
+    async fn test_recording_stream() {
+        let root = std::env::temp_dir().join(format!("netgauze-bmp-record-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let config = RecorderConfig::new(root.clone())
+            .with_max_file_size(10)
+            .with_max_files(None);
+        let addr_info = AddrInfo::new(
+            "192.0.2.100:1790".parse().unwrap(),
+            "192.0.2.1:40000".parse().unwrap(),
+        );
+
+        let msg = BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(vec![
+            InitiationInformation::SystemName("router".to_string()),
+        ])));
+        let mut wire = BytesMut::new();
+        BmpCodec::default().encode(msg.clone(), &mut wire).unwrap();
+        let wire = wire.freeze();
+
+        let recorder = StreamRecorder::new(config.clone(), addr_info);
+        let mut stream = RecordingStream::new(wire.as_ref(), Some(recorder));
+        let mut received = vec![];
+        stream.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received, wire.as_ref());
+        assert!(stream.is_recording());
+        stream.close().await;
+
+        let files = recorded_files(&config.router_dir(addr_info.remote_socket())).unwrap();
+        assert_eq!(files.len(), wire.len().div_ceil(10));
+        let mut recorded = BytesMut::new();
+        for file in &files {
+            let content = fs::read(file).unwrap();
+            assert!(content.len() <= 10);
+            recorded.extend_from_slice(&content);
+        }
+        assert_eq!(recorded.as_ref(), wire.as_ref());
+        assert_eq!(
+            BmpCodec::default().decode(&mut recorded).unwrap(),
+            Some(msg)
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_max_files() {
+        let root = std::env::temp_dir().join(format!("netgauze-bmp-rotate-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let config = RecorderConfig::new(root.clone())
+            .with_max_file_size(4)
+            .with_max_files(Some(2));
+        let addr_info = AddrInfo::new(
+            "192.0.2.100:1790".parse().unwrap(),
+            "[2001:db8::1]:40000".parse().unwrap(),
+        );
+        let mut recorder = StreamRecorder::new(config.clone(), addr_info);
+        recorder
+            .record(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10])
+            .await
+            .unwrap();
+        // Another connection of the same router, its files are pruned
+        // separately
+        let other_addr_info = AddrInfo::new(
+            "192.0.2.100:1790".parse().unwrap(),
+            "[2001:db8::1]:40001".parse().unwrap(),
+        );
+        let mut other = StreamRecorder::new(config.clone(), other_addr_info);
+        other.record(&[11, 12, 13, 14, 15]).await.unwrap();
+        recorder.record(&[16, 17, 18]).await.unwrap();
+        let current = recorder.current_file();
+        recorder.close().await.unwrap();
+        other.close().await.unwrap();
+
+        let dir = config.router_dir(addr_info.remote_socket());
+        assert_eq!(dir, root.join("2001_db8__1"));
+        let files = recorded_files(&dir).unwrap();
+        assert_eq!(files.len(), 4);
+        let content = files
+            .iter()
+            .map(|file| fs::read(file).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            content,
+            vec![
+                vec![9, 10, 16, 17],
+                vec![18],
+                vec![11, 12, 13, 14],
+                vec![15]
+            ]
+        );
+        assert_eq!(current.as_ref(), files.get(1));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_recording_queue_full() {
+        let root = std::env::temp_dir().join(format!("netgauze-bmp-queue-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let config = RecorderConfig::new(root.clone()).with_queue_size(1);
+        let addr_info = AddrInfo::new(
+            "192.0.2.100:1790".parse().unwrap(),
+            "192.0.2.2:40000".parse().unwrap(),
+        );
+        let recorder = StreamRecorder::new(config, addr_info);
+        let wire = [1u8, 2, 3, 4, 5, 6];
+        let mut stream = RecordingStream::new(&wire[..], Some(recorder));
+        let mut buf = [0u8; 2];
+        // The writer task doesn't get to run in between the reads, hence the
+        // second read finds the queue full
+        stream.read_exact(&mut buf).await.unwrap();
+        assert!(stream.is_recording());
+        stream.read_exact(&mut buf).await.unwrap();
+        assert!(!stream.is_recording());
+        // Reading goes on without recording
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, [5, 6]);
+        stream.close().await;
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,421 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detection of route reflection misconfigurations in the monitored networks.
+//!
+//! [`ReflectionChecker`] validates the `ORIGINATOR_ID` and `CLUSTER_LIST`
+//! attributes of the routes received by the monitored routers with a
+//! [`ReflectionValidator`], and reports a [`ReflectionAnomaly`] for each
+//! violation. The BGP identifier of a router is learned from the OPEN message
+//! it sent in the Peer Up message of each peer, its cluster IDs default to the
+//! BGP identifier unless configured with
+//! [`ReflectionChecker::with_cluster_ids`]. [`ReflectionCheckLayer`] wraps a
+//! BMP service to run the checks on all the requests received by a
+//! [`crate::server::BmpServer`].
+//!
+//! Adj-RIB-Out routes are sent by the router rather than received, hence
+//! they're not checked.
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr},
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use netgauze_bgp_pkt::{
+    path_attribute::ClusterId,
+    reflection::{ReflectionValidator, ReflectionViolation},
+    BgpMessage,
+};
+use netgauze_bmp_pkt::{BmpMessage, BmpMessageValue, PeerKey, PeerKeyStrategy};
+
+use crate::{server::BmpRequest, AddrInfo};
+
+/// A [`ReflectionViolation`] found in a route received by a monitored router
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReflectionAnomaly {
+    router: AddrInfo,
+    peer: PeerKey,
+    violation: ReflectionViolation,
+}
+
+impl ReflectionAnomaly {
+    pub const fn new(router: AddrInfo, peer: PeerKey, violation: ReflectionViolation) -> Self {
+        Self {
+            router,
+            peer,
+            violation,
+        }
+    }
+
+    pub const fn router(&self) -> AddrInfo {
+        self.router
+    }
+
+    /// Key of the peer the route is received from, according to the
+    /// [`PeerKeyStrategy`] of the checker
+    pub const fn peer(&self) -> PeerKey {
+        self.peer
+    }
+
+    pub const fn violation(&self) -> &ReflectionViolation {
+        &self.violation
+    }
+}
+
+/// Validates the reflection attributes of the routes received by the
+/// monitored routers. The state of a session is dropped when its connection
+/// ends.
+#[derive(Debug)]
+pub struct ReflectionChecker {
+    peer_key_strategy: PeerKeyStrategy,
+    cluster_ids: HashMap<IpAddr, Vec<ClusterId>>,
+    /// BGP identifier of the monitored router on the session with each peer
+    router_ids: HashMap<(AddrInfo, PeerKey), Ipv4Addr>,
+}
+
+impl Default for ReflectionChecker {
+    fn default() -> Self {
+        Self {
+            peer_key_strategy: PeerKeyStrategy::AddressAndRd,
+            cluster_ids: HashMap::new(),
+            router_ids: HashMap::new(),
+        }
+    }
+}
+
+impl ReflectionChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How the monitored peers are identified,
+    /// [`PeerKeyStrategy::AddressAndRd`] by default, since the peer flags
+    /// differ between the Peer Up and post-policy Route Monitoring messages of
+    /// the same peer
+    pub fn with_peer_key_strategy(mut self, peer_key_strategy: PeerKeyStrategy) -> Self {
+        self.peer_key_strategy = peer_key_strategy;
+        self
+    }
+
+    /// Cluster IDs configured on the router with the given BMP source address
+    pub fn with_cluster_ids(mut self, router: IpAddr, cluster_ids: Vec<ClusterId>) -> Self {
+        self.cluster_ids.insert(router, cluster_ids);
+        self
+    }
+
+    pub const fn peer_key_strategy(&self) -> PeerKeyStrategy {
+        self.peer_key_strategy
+    }
+
+    /// Check a request received by the BMP server, the end of the connection
+    /// or decoding errors close the session of the router.
+    pub fn check_request(&mut self, request: &BmpRequest) -> Vec<ReflectionAnomaly> {
+        match request {
+            Ok(tagged) => match tagged.value() {
+                Some(msg) => self.check(tagged.tag(), msg),
+                None => {
+                    self.remove_session(tagged.tag());
+                    vec![]
+                }
+            },
+            Err(tagged) => {
+                self.remove_session(tagged.tag());
+                vec![]
+            }
+        }
+    }
+
+    fn remove_session(&mut self, router: AddrInfo) {
+        self.router_ids.retain(|(session, _), _| *session != router);
+    }
+
+    /// Check the next message received from `router`
+    pub fn check(&mut self, router: AddrInfo, msg: &BmpMessage) -> Vec<ReflectionAnomaly> {
+        let value = msg.to_v3_value();
+        match value.as_ref() {
+            BmpMessageValue::PeerUpNotification(peer_up) => {
+                let peer = self
+                    .peer_key_strategy
+                    .apply(PeerKey::from_peer_header(peer_up.peer_header()));
+                if let BgpMessage::Open(open) = peer_up.sent_message() {
+                    self.router_ids.insert((router, peer), open.bgp_id());
+                }
+                vec![]
+            }
+            BmpMessageValue::PeerDownNotification(peer_down) => {
+                let peer = self
+                    .peer_key_strategy
+                    .apply(PeerKey::from_peer_header(peer_down.peer_header()));
+                self.router_ids.remove(&(router, peer));
+                vec![]
+            }
+            BmpMessageValue::RouteMonitoring(route_monitoring) => {
+                let header = route_monitoring.peer_header();
+                let update = match route_monitoring.update_message() {
+                    BgpMessage::Update(update) if !header.is_adj_rib_out() => update,
+                    _ => return vec![],
+                };
+                let peer = self
+                    .peer_key_strategy
+                    .apply(PeerKey::from_peer_header(header));
+                let mut validator = ReflectionValidator::new();
+                if let Some(router_id) = self.router_ids.get(&(router, peer)) {
+                    validator = validator.with_router_id(*router_id);
+                }
+                for cluster_id in self
+                    .cluster_ids
+                    .get(&router.remote_socket().ip())
+                    .into_iter()
+                    .flatten()
+                {
+                    validator = validator.with_cluster_id(cluster_id.clone());
+                }
+                validator
+                    .validate_update(update)
+                    .into_iter()
+                    .map(|violation| ReflectionAnomaly::new(router, peer, violation))
+                    .collect()
+            }
+            _ => vec![],
+        }
+    }
+}
+
+/// [`Layer`] validating the reflected routes of the Route Monitoring messages
+/// with a shared [`ReflectionChecker`], which learns the BGP identifiers from
+/// the Peer Up messages of the same requests. Each anomaly is logged as a
+/// warning and sent to the anomalies channel.
+#[derive(Debug, Clone)]
+pub struct ReflectionCheckLayer {
+    checker: Arc<Mutex<ReflectionChecker>>,
+    anomalies: mpsc::UnboundedSender<ReflectionAnomaly>,
+}
+
+impl ReflectionCheckLayer {
+    pub fn new(
+        checker: ReflectionChecker,
+        anomalies: mpsc::UnboundedSender<ReflectionAnomaly>,
+    ) -> Self {
+        Self {
+            checker: Arc::new(Mutex::new(checker)),
+            anomalies,
+        }
+    }
+}
+
+impl<S> Layer<S> for ReflectionCheckLayer {
+    type Service = ReflectionCheckService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ReflectionCheckService {
+            inner,
+            checker: self.checker.clone(),
+            anomalies: self.anomalies.clone(),
+        }
+    }
+}
+
+/// Service created by [`ReflectionCheckLayer`]
+#[derive(Debug, Clone)]
+pub struct ReflectionCheckService<S> {
+    inner: S,
+    checker: Arc<Mutex<ReflectionChecker>>,
+    anomalies: mpsc::UnboundedSender<ReflectionAnomaly>,
+}
+
+impl<S> Service<BmpRequest> for ReflectionCheckService<S>
+where
+    S: Service<BmpRequest>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: BmpRequest) -> Self::Future {
+        let anomalies = self
+            .checker
+            .lock()
+            .expect("reflection checker lock is poisoned")
+            .check_request(&request);
+        for anomaly in anomalies {
+            tracing::warn!("route reflection anomaly: {anomaly:?}");
+            // The receiver is optional, anomalies are still logged when it's
+            // dropped
+            let _ = self.anomalies.send(anomaly);
+        }
+        self.inner.call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netgauze_bgp_pkt::{
+        open::BgpOpenMessage,
+        path_attribute::{ClusterList, Originator, PathAttribute, PathAttributeValue},
+        update::BgpUpdateMessage,
+    };
+    use netgauze_bmp_pkt::{
+        BmpPeerType, PeerHeader, PeerUpNotificationMessage, RouteMonitoringMessage,
+    };
+
+    use crate::TaggedData;
+
+    const ROUTER_ID: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+
+    fn router() -> AddrInfo {
+        AddrInfo::new(
+            "192.0.2.100:1790".parse().unwrap(),
+            "192.0.2.1:40000".parse().unwrap(),
+        )
+    }
+
+    fn peer_header(adj_rib_out: bool) -> PeerHeader {
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10))),
+            65000,
+            Ipv4Addr::new(192, 0, 2, 10),
+            None,
+        )
+    }
+
+    fn peer_up() -> BmpMessage {
+        let sent = BgpMessage::Open(BgpOpenMessage::new(65000, 180, ROUTER_ID, vec![]));
+        let received = BgpMessage::Open(BgpOpenMessage::new(
+            65000,
+            180,
+            Ipv4Addr::new(192, 0, 2, 10),
+            vec![],
+        ));
+        BmpMessage::V3(BmpMessageValue::PeerUpNotification(
+            PeerUpNotificationMessage::build(
+                peer_header(false),
+                Some(IpAddr::V4(ROUTER_ID)),
+                Some(179),
+                Some(40000),
+                sent,
+                received,
+                vec![],
+            )
+            .unwrap(),
+        ))
+    }
+
+    fn route_monitoring(adj_rib_out: bool, originator: Ipv4Addr) -> BmpMessage {
+        let attrs = vec![
+            PathAttribute::from(
+                true,
+                false,
+                false,
+                false,
+                PathAttributeValue::Originator(Originator::new(originator)),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                true,
+                false,
+                false,
+                false,
+                PathAttributeValue::ClusterList(ClusterList::new(vec![ClusterId::new(
+                    Ipv4Addr::new(198, 51, 100, 1),
+                )])),
+            )
+            .unwrap(),
+        ];
+        BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            RouteMonitoringMessage::build(
+                peer_header(adj_rib_out),
+                BgpMessage::Update(BgpUpdateMessage::new(vec![], attrs, vec![])),
+            )
+            .unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_reflection_checker() {
+        let mut checker = ReflectionChecker::new();
+        let peer = PeerKey::from_peer_header(&peer_header(false));
+        let peer = checker.peer_key_strategy().apply(peer);
+
+        // The router ID isn't known before the Peer Up message
+        assert_eq!(
+            checker.check(router(), &route_monitoring(false, ROUTER_ID)),
+            vec![]
+        );
+        assert_eq!(checker.check(router(), &peer_up()), vec![]);
+        assert_eq!(
+            checker.check(router(), &route_monitoring(false, ROUTER_ID)),
+            vec![ReflectionAnomaly::new(
+                router(),
+                peer,
+                ReflectionViolation::OriginatorIsReceiver(ROUTER_ID)
+            )]
+        );
+        // Routes sent by the router aren't checked
+        assert_eq!(
+            checker.check(router(), &route_monitoring(true, ROUTER_ID)),
+            vec![]
+        );
+        assert_eq!(
+            checker.check(
+                router(),
+                &route_monitoring(false, Ipv4Addr::new(192, 0, 2, 2))
+            ),
+            vec![]
+        );
+
+        // The configured cluster IDs of the router
+        let mut checker = ReflectionChecker::new().with_cluster_ids(
+            router().remote_socket().ip(),
+            vec![ClusterId::new(Ipv4Addr::new(198, 51, 100, 1))],
+        );
+        assert_eq!(
+            checker
+                .check(
+                    router(),
+                    &route_monitoring(false, Ipv4Addr::new(192, 0, 2, 2))
+                )
+                .iter()
+                .map(|anomaly| anomaly.violation().clone())
+                .collect::<Vec<_>>(),
+            vec![ReflectionViolation::ClusterLoop(ClusterId::new(
+                Ipv4Addr::new(198, 51, 100, 1)
+            ))]
+        );
+
+        // The session state is dropped with the connection
+        checker.check(router(), &peer_up());
+        checker.check_request(&Ok(TaggedData::new(router(), None)));
+        assert!(checker.router_ids.is_empty());
+    }
+}
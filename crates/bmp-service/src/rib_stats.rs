@@ -0,0 +1,257 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Route counts and memory accounting of the RIBs kept by the collector.
+//!
+//! [`RibStats`] is returned by [`crate::view::MultiRouterView::stats`] and
+//! [`crate::rib_store::RibStore::stats`]. Routes are counted per AFI/SAFI,
+//! i.e., per table, and per monitored peer, along with an estimate of the
+//! memory they take, so operators can tell which peers and tables dominate
+//! the memory of the collector.
+//!
+//! Memory figures are approximate: they add up the size of the stored values
+//! and of the slots of the collections holding them. The heap allocations
+//! inside path attributes, e.g., AS paths or communities, are estimated by
+//! their wire length.
+
+use std::{collections::HashMap, mem::size_of, net::IpAddr};
+
+use netgauze_bgp_pkt::{intern::InternerStats, path_attribute::PathAttribute};
+use netgauze_bmp_pkt::PeerKey;
+use netgauze_iana::address_family::AddressType;
+use netgauze_parse_utils::WritablePdu;
+
+use crate::view::VantagePoint;
+
+/// Number of routes and the memory they take
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RouteStats {
+    routes: usize,
+    memory: usize,
+}
+
+impl RouteStats {
+    pub const fn new(routes: usize, memory: usize) -> Self {
+        Self { routes, memory }
+    }
+
+    pub const fn routes(&self) -> usize {
+        self.routes
+    }
+
+    /// Approximate memory of the routes, in bytes
+    pub const fn memory(&self) -> usize {
+        self.memory
+    }
+
+    fn count(&mut self, routes: usize, memory: usize) {
+        self.routes += routes;
+        self.memory += memory;
+    }
+}
+
+impl std::ops::Add for RouteStats {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self.count(other.routes, other.memory);
+        self
+    }
+}
+
+impl std::iter::Sum for RouteStats {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |sum, stats| sum + stats)
+    }
+}
+
+/// Snapshot of the routes held in a RIB
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RibStats {
+    prefixes: HashMap<AddressType, usize>,
+    tables: HashMap<AddressType, RouteStats>,
+    peers: HashMap<(IpAddr, PeerKey), HashMap<AddressType, RouteStats>>,
+    memory: usize,
+    interner: Option<InternerStats>,
+}
+
+impl RibStats {
+    /// Number of prefixes with at least one route, per AFI/SAFI
+    pub const fn prefixes(&self) -> &HashMap<AddressType, usize> {
+        &self.prefixes
+    }
+
+    /// Routes of each AFI/SAFI across all the peers
+    pub const fn tables(&self) -> &HashMap<AddressType, RouteStats> {
+        &self.tables
+    }
+
+    pub fn table(&self, address_type: AddressType) -> RouteStats {
+        self.tables.get(&address_type).copied().unwrap_or_default()
+    }
+
+    /// Routes of each monitored peer of each router, per AFI/SAFI
+    pub const fn peers(&self) -> &HashMap<(IpAddr, PeerKey), HashMap<AddressType, RouteStats>> {
+        &self.peers
+    }
+
+    /// Routes of a monitored peer of `router` across all AFI/SAFIs
+    pub fn peer(&self, router: IpAddr, peer: PeerKey) -> RouteStats {
+        self.peers
+            .get(&(router, peer))
+            .map(|tables| tables.values().copied().sum())
+            .unwrap_or_default()
+    }
+
+    /// The `n` peers taking the most memory, largest first
+    pub fn top_peers(&self, n: usize) -> Vec<((IpAddr, PeerKey), RouteStats)> {
+        let mut peers = self
+            .peers
+            .iter()
+            .map(|(key, tables)| (*key, tables.values().copied().sum::<RouteStats>()))
+            .collect::<Vec<_>>();
+        peers.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.memory()));
+        peers.truncate(n);
+        peers
+    }
+
+    pub fn routes(&self) -> usize {
+        self.tables.values().map(RouteStats::routes).sum()
+    }
+
+    /// Approximate memory of the whole RIB, in bytes. It includes the indexes
+    /// that aren't accounted to any peer, and counts the shared values once,
+    /// so it differs from the sum of the memory of the peers.
+    pub const fn memory(&self) -> usize {
+        self.memory
+    }
+
+    /// Sharing of the path attribute sets, for RIBs that intern them
+    pub const fn interner(&self) -> Option<InternerStats> {
+        self.interner
+    }
+
+    pub(crate) fn with_interner(mut self, interner: InternerStats) -> Self {
+        self.interner = Some(interner);
+        self
+    }
+
+    pub(crate) fn add_prefix(&mut self, address_type: AddressType, memory: usize) {
+        *self.prefixes.entry(address_type).or_default() += 1;
+        self.memory += memory;
+    }
+
+    /// Account a route of `vantage_point` taking `memory` bytes to its table
+    /// and peer, the total memory is accounted with [`RibStats::add_memory`]
+    pub(crate) fn add_route(
+        &mut self,
+        address_type: AddressType,
+        vantage_point: &VantagePoint,
+        memory: usize,
+    ) {
+        self.tables
+            .entry(address_type)
+            .or_default()
+            .count(1, memory);
+        self.peers
+            .entry((vantage_point.router(), vantage_point.peer()))
+            .or_default()
+            .entry(address_type)
+            .or_default()
+            .count(1, memory);
+    }
+
+    pub(crate) fn add_memory(&mut self, memory: usize) {
+        self.memory += memory;
+    }
+}
+
+/// Approximate memory of a set of path attributes, `capacity` is the number
+/// of attributes allocated for
+pub(crate) fn attributes_memory(attributes: &[PathAttribute], capacity: usize) -> usize {
+    capacity * size_of::<PathAttribute>()
+        + attributes
+            .iter()
+            .map(|attribute| attribute.len())
+            .sum::<usize>()
+}
+
+/// Approximate memory of the slots of a hash table holding `capacity` values
+/// of type `T`, including its control byte per slot
+pub(crate) const fn hash_slots_memory<T>(capacity: usize) -> usize {
+    capacity * (size_of::<T>() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_rib_stats() {
+        let router = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let peer = |last| {
+            PeerKey::new(
+                Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, last))),
+                netgauze_bmp_pkt::BmpPeerType::GlobalInstancePeer {
+                    ipv6: false,
+                    post_policy: false,
+                    asn2: false,
+                    adj_rib_out: false,
+                },
+                None,
+                64500,
+                Ipv4Addr::new(203, 0, 113, last),
+            )
+        };
+        let mut stats = RibStats::default();
+        stats.add_prefix(AddressType::Ipv4Unicast, 10);
+        stats.add_route(
+            AddressType::Ipv4Unicast,
+            &VantagePoint::new(router, peer(1), None),
+            100,
+        );
+        stats.add_route(
+            AddressType::Ipv6Unicast,
+            &VantagePoint::new(router, peer(1), None),
+            200,
+        );
+        stats.add_route(
+            AddressType::Ipv4Unicast,
+            &VantagePoint::new(router, peer(2), None),
+            50,
+        );
+
+        stats.add_memory(300);
+
+        assert_eq!(stats.routes(), 3);
+        assert_eq!(stats.memory(), 310);
+        assert_eq!(stats.prefixes()[&AddressType::Ipv4Unicast], 1);
+        assert_eq!(
+            stats.table(AddressType::Ipv4Unicast),
+            RouteStats::new(2, 150)
+        );
+        assert_eq!(
+            stats.table(AddressType::Ipv4Multicast),
+            RouteStats::default()
+        );
+        assert_eq!(stats.peer(router, peer(1)), RouteStats::new(2, 300));
+        assert_eq!(
+            stats.top_peers(1),
+            vec![((router, peer(1)), RouteStats::new(2, 300))]
+        );
+        assert_eq!(stats.interner(), None);
+    }
+}
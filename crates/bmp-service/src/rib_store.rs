@@ -0,0 +1,618 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage of the routes received from the monitored peers of all routers.
+//!
+//! [`RibStore`] abstracts where routes are kept, so very large multi-router
+//! RIBs can be backed by an on-disk key-value store instead of RAM.
+//! [`MemoryRibStore`] is the default in-memory implementation, other backends
+//! are expected to be added behind cargo features. Routes are keyed by
+//! [`ViewPrefix`] and [`VantagePoint`], which, like [`StoredRoute`], are
+//! serializable to be used as keys and values by such backends.
+//!
+//! With ADD-PATH, the path id is part of the [`VantagePoint`], so a peer has
+//! a route per path id for the same prefix. Announcing a path id again
+//! replaces its route only, as does withdrawing it, the other paths of the
+//! peer are kept. Collapsing them would hide the multiple paths ADD-PATH is
+//! used to expose.
+//!
+//! [`RibStore::stats`] counts the routes per AFI/SAFI and per peer, see
+//! [`crate::rib_stats`].
+
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    net::IpAddr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use netgauze_bgp_pkt::{
+    path_attribute::{PathAttribute, PathAttributeValue},
+    BgpMessage,
+};
+use netgauze_bmp_pkt::{BmpMessage, BmpMessageValue, PeerKey, PeerKeyStrategy};
+
+use crate::{
+    rib_stats::{attributes_memory, hash_slots_memory, RibStats},
+    view::{VantagePoint, ViewPrefix},
+};
+
+/// A route as received from a vantage point
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredRoute {
+    next_hop: Option<IpAddr>,
+    attributes: Vec<PathAttribute>,
+}
+
+impl StoredRoute {
+    pub const fn new(next_hop: Option<IpAddr>, attributes: Vec<PathAttribute>) -> Self {
+        Self {
+            next_hop,
+            attributes,
+        }
+    }
+
+    pub const fn next_hop(&self) -> Option<IpAddr> {
+        self.next_hop
+    }
+
+    pub fn attributes(&self) -> &[PathAttribute] {
+        &self.attributes
+    }
+}
+
+/// Storage backend of the routes, a vantage point, i.e., a path id of a peer,
+/// has at most one route per prefix
+pub trait RibStore {
+    type Error;
+
+    fn get(
+        &self,
+        prefix: &ViewPrefix,
+        vantage_point: &VantagePoint,
+    ) -> Result<Option<StoredRoute>, Self::Error>;
+
+    /// Set the route of `vantage_point` for `prefix`, returns the route it
+    /// replaced
+    fn put(
+        &mut self,
+        prefix: ViewPrefix,
+        vantage_point: VantagePoint,
+        route: StoredRoute,
+    ) -> Result<Option<StoredRoute>, Self::Error>;
+
+    /// Remove the route of `vantage_point` for `prefix`, returns the removed
+    /// route
+    fn delete(
+        &mut self,
+        prefix: &ViewPrefix,
+        vantage_point: &VantagePoint,
+    ) -> Result<Option<StoredRoute>, Self::Error>;
+
+    /// Routes of `prefix` from all the vantage points
+    fn routes_of_prefix(
+        &self,
+        prefix: &ViewPrefix,
+    ) -> Result<Vec<(VantagePoint, StoredRoute)>, Self::Error>;
+
+    /// Routes received from a peer of `router`, across all its path ids
+    fn routes_of_peer(
+        &self,
+        router: IpAddr,
+        peer: PeerKey,
+    ) -> Result<Vec<(ViewPrefix, VantagePoint, StoredRoute)>, Self::Error>;
+
+    /// Routes of `prefix` received from a peer of `router`, one per path id,
+    /// sorted by path id
+    fn paths_of_peer(
+        &self,
+        prefix: &ViewPrefix,
+        router: IpAddr,
+        peer: PeerKey,
+    ) -> Result<Vec<(Option<u32>, StoredRoute)>, Self::Error> {
+        let mut paths = self
+            .routes_of_prefix(prefix)?
+            .into_iter()
+            .filter(|(vantage_point, _)| {
+                vantage_point.router() == router && vantage_point.peer() == peer
+            })
+            .map(|(vantage_point, route)| (vantage_point.path_id(), route))
+            .collect::<Vec<_>>();
+        paths.sort_by_key(|(path_id, _)| *path_id);
+        Ok(paths)
+    }
+
+    /// Remove the routes of `prefix` received from a peer of `router`, across
+    /// all its path ids, returns the number of removed routes
+    fn delete_paths_of_peer(
+        &mut self,
+        prefix: &ViewPrefix,
+        router: IpAddr,
+        peer: PeerKey,
+    ) -> Result<usize, Self::Error> {
+        let paths = self.paths_of_peer(prefix, router, peer)?;
+        for (path_id, _) in &paths {
+            self.delete(prefix, &VantagePoint::new(router, peer, *path_id))?;
+        }
+        Ok(paths.len())
+    }
+
+    /// Remove the routes received from a peer of `router`, returns the number
+    /// of removed routes
+    fn remove_peer(&mut self, router: IpAddr, peer: PeerKey) -> Result<usize, Self::Error>;
+
+    /// Remove the routes received from `router`, returns the number of
+    /// removed routes
+    fn remove_router(&mut self, router: IpAddr) -> Result<usize, Self::Error>;
+
+    /// Number of stored routes
+    fn len(&self) -> Result<usize, Self::Error>;
+
+    fn is_empty(&self) -> Result<bool, Self::Error> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Count the routes per AFI/SAFI and per peer. Backends that don't keep
+    /// the routes in memory report the memory of their in-memory caches, if
+    /// any.
+    fn stats(&self) -> Result<RibStats, Self::Error>;
+}
+
+/// [`RibStore`] keeping the routes in memory, indexed by prefix and by peer
+#[derive(Debug, Clone, Default)]
+pub struct MemoryRibStore {
+    prefixes: HashMap<ViewPrefix, HashMap<VantagePoint, StoredRoute>>,
+    peers: HashMap<(IpAddr, PeerKey), HashSet<(ViewPrefix, VantagePoint)>>,
+}
+
+impl MemoryRibStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl RibStore for MemoryRibStore {
+    type Error = Infallible;
+
+    fn get(
+        &self,
+        prefix: &ViewPrefix,
+        vantage_point: &VantagePoint,
+    ) -> Result<Option<StoredRoute>, Self::Error> {
+        Ok(self
+            .prefixes
+            .get(prefix)
+            .and_then(|routes| routes.get(vantage_point))
+            .cloned())
+    }
+
+    fn put(
+        &mut self,
+        prefix: ViewPrefix,
+        vantage_point: VantagePoint,
+        route: StoredRoute,
+    ) -> Result<Option<StoredRoute>, Self::Error> {
+        self.peers
+            .entry((vantage_point.router(), vantage_point.peer()))
+            .or_default()
+            .insert((prefix, vantage_point));
+        Ok(self
+            .prefixes
+            .entry(prefix)
+            .or_default()
+            .insert(vantage_point, route))
+    }
+
+    fn delete(
+        &mut self,
+        prefix: &ViewPrefix,
+        vantage_point: &VantagePoint,
+    ) -> Result<Option<StoredRoute>, Self::Error> {
+        let routes = match self.prefixes.get_mut(prefix) {
+            Some(routes) => routes,
+            None => return Ok(None),
+        };
+        let removed = routes.remove(vantage_point);
+        if routes.is_empty() {
+            self.prefixes.remove(prefix);
+        }
+        let peer = (vantage_point.router(), vantage_point.peer());
+        if let Some(keys) = self.peers.get_mut(&peer) {
+            keys.remove(&(*prefix, *vantage_point));
+            if keys.is_empty() {
+                self.peers.remove(&peer);
+            }
+        }
+        Ok(removed)
+    }
+
+    fn routes_of_prefix(
+        &self,
+        prefix: &ViewPrefix,
+    ) -> Result<Vec<(VantagePoint, StoredRoute)>, Self::Error> {
+        Ok(self
+            .prefixes
+            .get(prefix)
+            .map(|routes| {
+                routes
+                    .iter()
+                    .map(|(vantage_point, route)| (*vantage_point, route.clone()))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    fn routes_of_peer(
+        &self,
+        router: IpAddr,
+        peer: PeerKey,
+    ) -> Result<Vec<(ViewPrefix, VantagePoint, StoredRoute)>, Self::Error> {
+        let keys = match self.peers.get(&(router, peer)) {
+            Some(keys) => keys,
+            None => return Ok(vec![]),
+        };
+        Ok(keys
+            .iter()
+            .filter_map(|(prefix, vantage_point)| {
+                self.prefixes
+                    .get(prefix)
+                    .and_then(|routes| routes.get(vantage_point))
+                    .map(|route| (*prefix, *vantage_point, route.clone()))
+            })
+            .collect())
+    }
+
+    fn remove_peer(&mut self, router: IpAddr, peer: PeerKey) -> Result<usize, Self::Error> {
+        let keys = self.peers.remove(&(router, peer)).unwrap_or_default();
+        for (prefix, vantage_point) in &keys {
+            if let Some(routes) = self.prefixes.get_mut(prefix) {
+                routes.remove(vantage_point);
+                if routes.is_empty() {
+                    self.prefixes.remove(prefix);
+                }
+            }
+        }
+        Ok(keys.len())
+    }
+
+    fn remove_router(&mut self, router: IpAddr) -> Result<usize, Self::Error> {
+        let peers = self
+            .peers
+            .keys()
+            .filter(|(peer_router, _)| *peer_router == router)
+            .copied()
+            .collect::<Vec<_>>();
+        let mut removed = 0;
+        for (router, peer) in peers {
+            removed += self.remove_peer(router, peer)?;
+        }
+        Ok(removed)
+    }
+
+    fn len(&self) -> Result<usize, Self::Error> {
+        Ok(self.prefixes.values().map(HashMap::len).sum())
+    }
+
+    /// The memory of a route includes its entry in the peer index, the
+    /// attributes aren't shared among routes
+    fn stats(&self) -> Result<RibStats, Self::Error> {
+        let mut stats = RibStats::default();
+        stats.add_memory(hash_slots_memory::<(
+            ViewPrefix,
+            HashMap<VantagePoint, StoredRoute>,
+        )>(self.prefixes.capacity()));
+        stats.add_memory(hash_slots_memory::<(
+            (IpAddr, PeerKey),
+            HashSet<(ViewPrefix, VantagePoint)>,
+        )>(self.peers.capacity()));
+        for keys in self.peers.values() {
+            stats.add_memory(hash_slots_memory::<(ViewPrefix, VantagePoint)>(
+                keys.capacity(),
+            ));
+        }
+        for (prefix, routes) in &self.prefixes {
+            let address_type = prefix.address_type();
+            stats.add_prefix(
+                address_type,
+                hash_slots_memory::<(VantagePoint, StoredRoute)>(routes.capacity()),
+            );
+            for (vantage_point, route) in routes {
+                let attributes = attributes_memory(&route.attributes, route.attributes.capacity());
+                stats.add_memory(attributes);
+                stats.add_route(
+                    address_type,
+                    vantage_point,
+                    attributes
+                        + hash_slots_memory::<(VantagePoint, StoredRoute)>(1)
+                        + hash_slots_memory::<(ViewPrefix, VantagePoint)>(1),
+                );
+            }
+        }
+        Ok(stats)
+    }
+}
+
+/// Update `store` with a BMP message received from `router`, following the
+/// same rules as [`crate::view::MultiRouterView::apply`]
+pub fn apply<S: RibStore>(
+    store: &mut S,
+    peer_key_strategy: PeerKeyStrategy,
+    router: IpAddr,
+    msg: &BmpMessage,
+) -> Result<(), S::Error> {
+    let value = msg.to_v3_value();
+    match value.as_ref() {
+        BmpMessageValue::RouteMonitoring(route_monitoring) => {
+            let update = match route_monitoring.update_message() {
+                BgpMessage::Update(update) => update,
+                _ => return Ok(()),
+            };
+            let peer =
+                peer_key_strategy.apply(PeerKey::from_peer_header(route_monitoring.peer_header()));
+            for route in update.withdrawn() {
+                if let Some(prefix) = route.prefix().ip_net() {
+                    store.delete(
+                        &ViewPrefix::new(route.prefix().rd(), prefix),
+                        &VantagePoint::new(router, peer, route.path_id()),
+                    )?;
+                }
+            }
+            let attributes = update
+                .path_attributes()
+                .iter()
+                .filter(|attr| {
+                    !matches!(
+                        attr.value(),
+                        PathAttributeValue::MpReach(_) | PathAttributeValue::MpUnreach(_)
+                    )
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            for route in update.announced() {
+                if let Some(prefix) = route.prefix().ip_net() {
+                    store.put(
+                        ViewPrefix::new(route.prefix().rd(), prefix),
+                        VantagePoint::new(router, peer, route.path_id()),
+                        StoredRoute::new(route.next_hop(), attributes.clone()),
+                    )?;
+                }
+            }
+        }
+        BmpMessageValue::PeerDownNotification(peer_down) => {
+            let peer = peer_key_strategy.apply(PeerKey::from_peer_header(peer_down.peer_header()));
+            store.remove_peer(router, peer)?;
+        }
+        BmpMessageValue::Termination(_) => {
+            store.remove_router(router)?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ipnet::IpNet;
+    use netgauze_bgp_pkt::{
+        nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+        path_attribute::{NextHop, Origin},
+        update::BgpUpdateMessage,
+    };
+    use netgauze_bmp_pkt::{BmpPeerType, PeerHeader, RouteMonitoringMessage};
+    use netgauze_iana::address_family::AddressType;
+    use std::{net::Ipv4Addr, str::FromStr};
+
+    const PREFIX: &str = "198.51.100.0/24";
+
+    fn peer_header(peer: u8) -> PeerHeader {
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, peer))),
+            64500 + peer as u32,
+            Ipv4Addr::new(203, 0, 113, peer),
+            None,
+        )
+    }
+
+    fn update(peer: u8, withdraw: bool) -> BmpMessage {
+        update_path(peer, None, Ipv4Addr::new(203, 0, 113, peer), withdraw)
+    }
+
+    fn update_path(
+        peer: u8,
+        path_id: Option<u32>,
+        next_hop: Ipv4Addr,
+        withdraw: bool,
+    ) -> BmpMessage {
+        let nlri = vec![Ipv4UnicastAddress::new(
+            path_id,
+            Ipv4Unicast::from_net(ipnet::Ipv4Net::from_str(PREFIX).unwrap()).unwrap(),
+        )];
+        let update = if withdraw {
+            BgpUpdateMessage::new(nlri, vec![], vec![])
+        } else {
+            let attrs = vec![
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::Origin(Origin::IGP),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::NextHop(NextHop::new(next_hop)),
+                )
+                .unwrap(),
+            ];
+            BgpUpdateMessage::new(vec![], attrs, nlri)
+        };
+        BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            RouteMonitoringMessage::build(peer_header(peer), BgpMessage::Update(update)).unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_memory_rib_store() {
+        let router = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let strategy = PeerKeyStrategy::default();
+        let prefix = ViewPrefix::from(IpNet::from_str(PREFIX).unwrap());
+        let peer1 = strategy.apply(PeerKey::from_peer_header(&peer_header(1)));
+        let mut store = MemoryRibStore::new();
+
+        apply(&mut store, strategy, router, &update(1, false)).unwrap();
+        apply(&mut store, strategy, router, &update(2, false)).unwrap();
+        assert_eq!(store.len(), Ok(2));
+        assert_eq!(store.routes_of_prefix(&prefix).unwrap().len(), 2);
+        let route = store
+            .get(&prefix, &VantagePoint::new(router, peer1, None))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            route.next_hop(),
+            Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 1)))
+        );
+        assert_eq!(route.attributes().len(), 2);
+        assert_eq!(
+            store.routes_of_peer(router, peer1).unwrap(),
+            vec![(prefix, VantagePoint::new(router, peer1, None), route)]
+        );
+
+        // Announcing again replaces the route of the vantage point
+        apply(&mut store, strategy, router, &update(1, false)).unwrap();
+        assert_eq!(store.len(), Ok(2));
+
+        apply(&mut store, strategy, router, &update(1, true)).unwrap();
+        assert_eq!(store.len(), Ok(1));
+        assert_eq!(store.routes_of_peer(router, peer1), Ok(vec![]));
+
+        assert_eq!(store.remove_router(router), Ok(1));
+        assert_eq!(store.is_empty(), Ok(true));
+        assert!(store.peers.is_empty());
+    }
+
+    #[test]
+    fn test_memory_rib_store_stats() {
+        let router = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let strategy = PeerKeyStrategy::default();
+        let peer = |peer| strategy.apply(PeerKey::from_peer_header(&peer_header(peer)));
+        let mut store = MemoryRibStore::new();
+        assert_eq!(store.stats().unwrap().routes(), 0);
+
+        apply(&mut store, strategy, router, &update(1, false)).unwrap();
+        apply(&mut store, strategy, router, &update(2, false)).unwrap();
+        let stats = store.stats().unwrap();
+        assert_eq!(stats.routes(), 2);
+        assert_eq!(stats.prefixes()[&AddressType::Ipv4Unicast], 1);
+        assert_eq!(stats.table(AddressType::Ipv4Unicast).routes(), 2);
+        assert_eq!(stats.table(AddressType::Ipv6Unicast).routes(), 0);
+        assert_eq!(stats.peer(router, peer(1)).routes(), 1);
+        assert!(stats.peer(router, peer(1)).memory() > 0);
+        assert!(stats.memory() >= stats.table(AddressType::Ipv4Unicast).memory());
+        assert_eq!(stats.interner(), None);
+    }
+
+    #[test]
+    fn test_add_path() {
+        let router = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let strategy = PeerKeyStrategy::default();
+        let prefix = ViewPrefix::from(IpNet::from_str(PREFIX).unwrap());
+        let peer = strategy.apply(PeerKey::from_peer_header(&peer_header(1)));
+        let next_hop = |last| Ipv4Addr::new(198, 51, 100, last);
+        let mut store = MemoryRibStore::new();
+
+        // Two paths of the same peer are kept apart
+        apply(
+            &mut store,
+            strategy,
+            router,
+            &update_path(1, Some(1), next_hop(1), false),
+        )
+        .unwrap();
+        apply(
+            &mut store,
+            strategy,
+            router,
+            &update_path(1, Some(2), next_hop(2), false),
+        )
+        .unwrap();
+        let next_hops = |store: &MemoryRibStore| {
+            store
+                .paths_of_peer(&prefix, router, peer)
+                .unwrap()
+                .into_iter()
+                .map(|(path_id, route)| (path_id, route.next_hop()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            next_hops(&store),
+            vec![
+                (Some(1), Some(IpAddr::V4(next_hop(1)))),
+                (Some(2), Some(IpAddr::V4(next_hop(2)))),
+            ]
+        );
+
+        // Implicit replace of the same path id only
+        apply(
+            &mut store,
+            strategy,
+            router,
+            &update_path(1, Some(1), next_hop(3), false),
+        )
+        .unwrap();
+        assert_eq!(
+            next_hops(&store),
+            vec![
+                (Some(1), Some(IpAddr::V4(next_hop(3)))),
+                (Some(2), Some(IpAddr::V4(next_hop(2)))),
+            ]
+        );
+
+        // Withdraw of a single path id
+        apply(
+            &mut store,
+            strategy,
+            router,
+            &update_path(1, Some(2), next_hop(2), true),
+        )
+        .unwrap();
+        assert_eq!(
+            next_hops(&store),
+            vec![(Some(1), Some(IpAddr::V4(next_hop(3))))]
+        );
+
+        apply(
+            &mut store,
+            strategy,
+            router,
+            &update_path(1, Some(4), next_hop(4), false),
+        )
+        .unwrap();
+        assert_eq!(store.delete_paths_of_peer(&prefix, router, peer), Ok(2));
+        assert_eq!(store.is_empty(), Ok(true));
+    }
+}
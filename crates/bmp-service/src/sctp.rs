@@ -0,0 +1,310 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! BMP over SCTP, as supported by some router implementations.
+//!
+//! One-to-one style SCTP sockets (`SOCK_STREAM`) behave as stream sockets,
+//! BMP messages are carried on a single stream in order. Hence, the sockets
+//! are driven with tokio's TCP types and decoded with the same
+//! [`netgauze_bmp_pkt::codec::BmpCodec`]. Multi-homing is configured by
+//! binding to, or connecting to, more than one address of the same port.
+//!
+//! Only available on Linux, with the `sctp` kernel module loaded.
+
+use std::{
+    fmt::{Display, Formatter},
+    io,
+    net::SocketAddr,
+    os::fd::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd, RawFd},
+};
+
+use libc::{c_int, c_void, socklen_t};
+use tokio::net::{TcpListener, TcpStream};
+
+/// `SOL_SCTP`, socket option level of SCTP
+const SOL_SCTP: c_int = libc::IPPROTO_SCTP;
+/// `SCTP_SOCKOPT_BINDX_ADD` from `linux/sctp.h`
+const SCTP_SOCKOPT_BINDX_ADD: c_int = 100;
+/// `SCTP_SOCKOPT_CONNECTX` from `linux/sctp.h`
+const SCTP_SOCKOPT_CONNECTX: c_int = 110;
+
+/// Backlog of the listening SCTP sockets, same as tokio's TCP listeners
+const LISTEN_BACKLOG: c_int = 1024;
+
+/// Local addresses of SCTP associations. All the addresses share the same
+/// port, the first one is the primary address and the others are added for
+/// multi-homing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SctpConfig {
+    local_addrs: Vec<SocketAddr>,
+}
+
+impl SctpConfig {
+    pub fn new(local_addr: SocketAddr) -> Self {
+        Self {
+            local_addrs: vec![local_addr],
+        }
+    }
+
+    /// Add a local address for multi-homing
+    pub fn with_local_addr(mut self, local_addr: SocketAddr) -> Self {
+        self.local_addrs.push(local_addr);
+        self
+    }
+
+    pub fn local_addrs(&self) -> &[SocketAddr] {
+        &self.local_addrs
+    }
+
+    /// Bind a listening socket to all the local addresses
+    pub fn listen(&self) -> io::Result<TcpListener> {
+        if self.local_addrs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no local address to listen on",
+            ));
+        }
+        let socket = sctp_socket(&self.local_addrs)?;
+        set_option(socket.as_raw_fd(), libc::SOL_SOCKET, libc::SO_REUSEADDR, &1)?;
+        bindx(socket.as_raw_fd(), &self.local_addrs)?;
+        // SAFETY: the file descriptor is a valid bound socket
+        if unsafe { libc::listen(socket.as_raw_fd(), LISTEN_BACKLOG) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: ownership of the file descriptor is moved to the listener
+        let listener = unsafe { std::net::TcpListener::from_raw_fd(socket.into_raw_fd()) };
+        TcpListener::from_std(listener)
+    }
+
+    /// Establish an association with a peer reachable on any of `peer_addrs`,
+    /// from the local addresses if any are configured
+    pub async fn connect(&self, peer_addrs: &[SocketAddr]) -> io::Result<TcpStream> {
+        if peer_addrs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no peer address to connect to",
+            ));
+        }
+        let addrs = self
+            .local_addrs
+            .iter()
+            .chain(peer_addrs)
+            .copied()
+            .collect::<Vec<_>>();
+        let socket = sctp_socket(&addrs)?;
+        if !self.local_addrs.is_empty() {
+            bindx(socket.as_raw_fd(), &self.local_addrs)?;
+        }
+        let packed = pack_addrs(peer_addrs)?;
+        // SAFETY: packed holds the socket addresses and the length is its size
+        let ret = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                SOL_SCTP,
+                SCTP_SOCKOPT_CONNECTX,
+                packed.as_ptr() as *const c_void,
+                packed.len() as socklen_t,
+            )
+        };
+        if ret < 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(libc::EINPROGRESS) {
+                return Err(err);
+            }
+        }
+        // SAFETY: ownership of the file descriptor is moved to the stream
+        let stream = unsafe { std::net::TcpStream::from_raw_fd(socket.into_raw_fd()) };
+        let stream = TcpStream::from_std(stream)?;
+        stream.writable().await?;
+        match stream.take_error()? {
+            Some(err) => Err(err),
+            None => Ok(stream),
+        }
+    }
+}
+
+impl Display for SctpConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let addrs = self
+            .local_addrs
+            .iter()
+            .map(|addr| addr.to_string())
+            .collect::<Vec<_>>();
+        write!(f, "{}", addrs.join(","))
+    }
+}
+
+/// Non-blocking one-to-one SCTP socket, IPv6 when any of `addrs` is IPv6
+fn sctp_socket(addrs: &[SocketAddr]) -> io::Result<OwnedFd> {
+    let domain = if addrs.iter().any(SocketAddr::is_ipv6) {
+        libc::AF_INET6
+    } else {
+        libc::AF_INET
+    };
+    // SAFETY: plain socket creation, the result is checked below
+    let fd = unsafe {
+        libc::socket(
+            domain,
+            libc::SOCK_STREAM | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+            libc::IPPROTO_SCTP,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: fd is a newly created socket owned by nobody else
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+fn set_option<T>(fd: RawFd, level: c_int, option: c_int, value: &T) -> io::Result<()> {
+    // SAFETY: `value` is a valid `T` of the given length
+    let ret = unsafe {
+        libc::setsockopt(
+            fd,
+            level,
+            option,
+            value as *const T as *const c_void,
+            std::mem::size_of::<T>() as socklen_t,
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Check if the socket is an SCTP socket, e.g., one passed by systemd
+pub(crate) fn is_sctp_socket(fd: RawFd) -> bool {
+    let mut protocol: c_int = 0;
+    let mut len = std::mem::size_of::<c_int>() as socklen_t;
+    // SAFETY: `protocol` is a valid `c_int` of the given length
+    let ret = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_PROTOCOL,
+            &mut protocol as *mut c_int as *mut c_void,
+            &mut len,
+        )
+    };
+    ret == 0 && protocol == libc::IPPROTO_SCTP
+}
+
+/// Bind the socket to all of `addrs`, see `sctp_bindx(3)`
+fn bindx(fd: RawFd, addrs: &[SocketAddr]) -> io::Result<()> {
+    let packed = pack_addrs(addrs)?;
+    // SAFETY: packed holds the socket addresses and the length is its size
+    let ret = unsafe {
+        libc::setsockopt(
+            fd,
+            SOL_SCTP,
+            SCTP_SOCKOPT_BINDX_ADD,
+            packed.as_ptr() as *const c_void,
+            packed.len() as socklen_t,
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Pack the socket addresses back to back, as expected by the SCTP socket
+/// options taking more than one address. All the addresses must share the
+/// same port.
+fn pack_addrs(addrs: &[SocketAddr]) -> io::Result<Vec<u8>> {
+    if let Some(first) = addrs.first() {
+        if addrs.iter().any(|addr| addr.port() != first.port()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "SCTP multi-homed addresses must share the same port",
+            ));
+        }
+    }
+    let mut packed = vec![];
+    for addr in addrs {
+        match addr {
+            SocketAddr::V4(addr) => {
+                // SAFETY: all zeros is a valid sockaddr_in
+                let mut sin: libc::sockaddr_in = unsafe { std::mem::zeroed() };
+                sin.sin_family = libc::AF_INET as libc::sa_family_t;
+                sin.sin_port = addr.port().to_be();
+                sin.sin_addr.s_addr = u32::from_ne_bytes(addr.ip().octets());
+                packed.extend_from_slice(as_bytes(&sin));
+            }
+            SocketAddr::V6(addr) => {
+                // SAFETY: all zeros is a valid sockaddr_in6
+                let mut sin6: libc::sockaddr_in6 = unsafe { std::mem::zeroed() };
+                sin6.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+                sin6.sin6_port = addr.port().to_be();
+                sin6.sin6_flowinfo = addr.flowinfo();
+                sin6.sin6_addr.s6_addr = addr.ip().octets();
+                sin6.sin6_scope_id = addr.scope_id();
+                packed.extend_from_slice(as_bytes(&sin6));
+            }
+        }
+    }
+    Ok(packed)
+}
+
+fn as_bytes<T>(value: &T) -> &[u8] {
+    // SAFETY: socket addresses are plain old data without padding
+    unsafe { std::slice::from_raw_parts(value as *const T as *const u8, std::mem::size_of::<T>()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_pack_addrs() {
+        let v4: SocketAddr = "192.0.2.1:1790".parse().unwrap();
+        let v6: SocketAddr = "[2001:db8::1]:1790".parse().unwrap();
+        let packed = pack_addrs(&[v4, v6]).unwrap();
+        assert_eq!(
+            packed.len(),
+            std::mem::size_of::<libc::sockaddr_in>() + std::mem::size_of::<libc::sockaddr_in6>()
+        );
+        // Port and address of the IPv4 address in network byte order
+        assert_eq!(&packed[2..8], &[0x06, 0xfe, 192, 0, 2, 1]);
+
+        let other_port: SocketAddr = "192.0.2.2:1791".parse().unwrap();
+        assert_eq!(
+            pack_addrs(&[v4, other_port]).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[tokio::test]
+    async fn test_listen_connect() {
+        let config = SctpConfig::new("127.0.0.1:0".parse().unwrap());
+        assert_eq!(config.to_string(), "127.0.0.1:0");
+        let listener = match config.listen() {
+            Ok(listener) => listener,
+            // The kernel is built or running without SCTP
+            Err(err) if err.raw_os_error() == Some(libc::EPROTONOSUPPORT) => return,
+            Err(err) => panic!("unexpected error: {err}"),
+        };
+        assert!(is_sctp_socket(listener.as_raw_fd()));
+        let addr = listener.local_addr().unwrap();
+        let mut client = SctpConfig::default().connect(&[addr]).await.unwrap();
+        let (mut server, _) = listener.accept().await.unwrap();
+        client.write_all(&[0x03, 0x00]).await.unwrap();
+        let mut buf = [0u8; 2];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, [0x03, 0x00]);
+    }
+}
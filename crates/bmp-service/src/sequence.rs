@@ -0,0 +1,479 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checks that the BMP messages of each router arrive in the order defined by
+//! [RFC7854](https://datatracker.ietf.org/doc/html/rfc7854), useful to debug
+//! router implementations.
+//!
+//! [`BmpSequenceChecker`] tracks the state of each BMP session and reports
+//! [`BmpSequenceAnomaly`]s, [`SequenceCheckLayer`] wraps a BMP service to run
+//! the checks on all the requests received by a
+//! [`crate::server::BmpServer`].
+//!
+//! The checks rely on the Peer Up and Peer Down messages being decoded, when
+//! they're passed raw or dropped by the codec, the per-peer checks are not
+//! reliable.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use netgauze_bmp_pkt::{
+    iana::BmpMessageType, BmpMessage, BmpMessageValue, PeerHeader, PeerKey, PeerKeyStrategy,
+};
+
+use crate::{server::BmpRequest, AddrInfo};
+
+/// Protocol order violation detected in a BMP session
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BmpSequenceAnomalyKind {
+    /// The first message of the session is not an Initiation message
+    MissingInitiation,
+
+    /// Route Monitoring or Statistics Report message for a peer that is not
+    /// up
+    MessageBeforePeerUp,
+
+    /// Peer Up message for a peer that is already up
+    DuplicatePeerUp,
+
+    /// Peer Down message for a peer that is not up
+    PeerDownWithoutPeerUp,
+
+    /// Any message following the Termination message of the session
+    MessageAfterTermination,
+}
+
+/// A [`BmpSequenceAnomalyKind`] along with the message that triggered it
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BmpSequenceAnomaly {
+    router: AddrInfo,
+    kind: BmpSequenceAnomalyKind,
+    message_type: BmpMessageType,
+    peer: Option<PeerKey>,
+}
+
+impl BmpSequenceAnomaly {
+    pub const fn new(
+        router: AddrInfo,
+        kind: BmpSequenceAnomalyKind,
+        message_type: BmpMessageType,
+        peer: Option<PeerKey>,
+    ) -> Self {
+        Self {
+            router,
+            kind,
+            message_type,
+            peer,
+        }
+    }
+
+    pub const fn router(&self) -> AddrInfo {
+        self.router
+    }
+
+    pub const fn kind(&self) -> BmpSequenceAnomalyKind {
+        self.kind
+    }
+
+    pub const fn message_type(&self) -> BmpMessageType {
+        self.message_type
+    }
+
+    /// Key of the monitored peer for per-peer messages, according to the
+    /// [`PeerKeyStrategy`] of the checker
+    pub const fn peer(&self) -> Option<PeerKey> {
+        self.peer
+    }
+}
+
+#[derive(Debug, Default)]
+struct SessionState {
+    started: bool,
+    terminated: bool,
+    peers: HashSet<PeerKey>,
+}
+
+/// Tracks the BMP session of each router and reports the messages that
+/// violate the protocol order. The state of a session is dropped when its
+/// connection ends.
+#[derive(Debug)]
+pub struct BmpSequenceChecker {
+    peer_key_strategy: PeerKeyStrategy,
+    sessions: HashMap<AddrInfo, SessionState>,
+}
+
+impl Default for BmpSequenceChecker {
+    fn default() -> Self {
+        Self::new(PeerKeyStrategy::AddressAndRd)
+    }
+}
+
+impl BmpSequenceChecker {
+    /// Create a checker identifying the monitored peers with the given
+    /// strategy. [`PeerKeyStrategy::AddressAndRd`] is used by default, since
+    /// the peer flags differ between the Peer Up and post-policy Route
+    /// Monitoring messages of the same peer.
+    pub fn new(peer_key_strategy: PeerKeyStrategy) -> Self {
+        Self {
+            peer_key_strategy,
+            sessions: HashMap::new(),
+        }
+    }
+
+    pub const fn peer_key_strategy(&self) -> PeerKeyStrategy {
+        self.peer_key_strategy
+    }
+
+    /// Number of routers with an ongoing session
+    pub fn sessions(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Check a request received by the BMP server, the end of the connection
+    /// or decoding errors close the session of the router.
+    pub fn check_request(&mut self, request: &BmpRequest) -> Vec<BmpSequenceAnomaly> {
+        match request {
+            Ok(tagged) => match tagged.value() {
+                Some(msg) => self.check(tagged.tag(), msg),
+                None => {
+                    self.sessions.remove(&tagged.tag());
+                    vec![]
+                }
+            },
+            Err(tagged) => {
+                self.sessions.remove(&tagged.tag());
+                vec![]
+            }
+        }
+    }
+
+    /// Check the next message received from `router`
+    pub fn check(&mut self, router: AddrInfo, msg: &BmpMessage) -> Vec<BmpSequenceAnomaly> {
+        let value = msg.to_v3_value();
+        let message_type = value.get_type();
+        let peer_key_strategy = self.peer_key_strategy;
+        let peer_key =
+            |header: &PeerHeader| peer_key_strategy.apply(PeerKey::from_peer_header(header));
+        let session = self.sessions.entry(router).or_default();
+        let anomaly = |kind, peer| BmpSequenceAnomaly::new(router, kind, message_type, peer);
+
+        let mut anomalies = vec![];
+        if session.terminated {
+            anomalies.push(anomaly(
+                BmpSequenceAnomalyKind::MessageAfterTermination,
+                None,
+            ));
+            return anomalies;
+        }
+        if !session.started && message_type != BmpMessageType::Initiation {
+            anomalies.push(anomaly(BmpSequenceAnomalyKind::MissingInitiation, None));
+        }
+        session.started = true;
+
+        match value.as_ref() {
+            BmpMessageValue::PeerUpNotification(peer_up) => {
+                let peer = peer_key(peer_up.peer_header());
+                if !session.peers.insert(peer) {
+                    anomalies.push(anomaly(BmpSequenceAnomalyKind::DuplicatePeerUp, Some(peer)));
+                }
+            }
+            BmpMessageValue::PeerDownNotification(peer_down) => {
+                let peer = peer_key(peer_down.peer_header());
+                if !session.peers.remove(&peer) {
+                    anomalies.push(anomaly(
+                        BmpSequenceAnomalyKind::PeerDownWithoutPeerUp,
+                        Some(peer),
+                    ));
+                }
+            }
+            BmpMessageValue::RouteMonitoring(route_monitoring) => {
+                let peer = peer_key(route_monitoring.peer_header());
+                if !session.peers.contains(&peer) {
+                    anomalies.push(anomaly(
+                        BmpSequenceAnomalyKind::MessageBeforePeerUp,
+                        Some(peer),
+                    ));
+                }
+            }
+            BmpMessageValue::StatisticsReport(stats) => {
+                let peer = peer_key(stats.peer_header());
+                if !session.peers.contains(&peer) {
+                    anomalies.push(anomaly(
+                        BmpSequenceAnomalyKind::MessageBeforePeerUp,
+                        Some(peer),
+                    ));
+                }
+            }
+            BmpMessageValue::Termination(_) => session.terminated = true,
+            _ => {}
+        }
+        anomalies
+    }
+}
+
+/// [`Layer`] tracking the session state of each router with a shared
+/// [`BmpSequenceChecker`], so messages out of the RFC7854 order, e.g., a
+/// Route Monitoring before the Peer Up of its peer, are logged as warnings
+/// and sent to the anomalies channel.
+#[derive(Debug, Clone)]
+pub struct SequenceCheckLayer {
+    checker: Arc<Mutex<BmpSequenceChecker>>,
+    anomalies: mpsc::UnboundedSender<BmpSequenceAnomaly>,
+}
+
+impl SequenceCheckLayer {
+    pub fn new(
+        checker: BmpSequenceChecker,
+        anomalies: mpsc::UnboundedSender<BmpSequenceAnomaly>,
+    ) -> Self {
+        Self {
+            checker: Arc::new(Mutex::new(checker)),
+            anomalies,
+        }
+    }
+}
+
+impl<S> Layer<S> for SequenceCheckLayer {
+    type Service = SequenceCheckService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SequenceCheckService {
+            inner,
+            checker: self.checker.clone(),
+            anomalies: self.anomalies.clone(),
+        }
+    }
+}
+
+/// Service created by [`SequenceCheckLayer`]
+#[derive(Debug, Clone)]
+pub struct SequenceCheckService<S> {
+    inner: S,
+    checker: Arc<Mutex<BmpSequenceChecker>>,
+    anomalies: mpsc::UnboundedSender<BmpSequenceAnomaly>,
+}
+
+impl<S> Service<BmpRequest> for SequenceCheckService<S>
+where
+    S: Service<BmpRequest>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: BmpRequest) -> Self::Future {
+        let anomalies = self
+            .checker
+            .lock()
+            .expect("sequence checker lock is poisoned")
+            .check_request(&request);
+        for anomaly in anomalies {
+            tracing::warn!("BMP sequence anomaly: {anomaly:?}");
+            // The receiver is optional, anomalies are still logged when it's
+            // dropped
+            let _ = self.anomalies.send(anomaly);
+        }
+        self.inner.call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netgauze_bgp_pkt::{open::BgpOpenMessage, update::BgpUpdateMessage, BgpMessage};
+    use netgauze_bmp_pkt::{
+        BmpPeerType, InitiationMessage, PeerDownNotificationMessage, PeerDownNotificationReason,
+        PeerUpNotificationMessage, RouteMonitoringMessage, TerminationMessage,
+    };
+    use std::{
+        convert::Infallible,
+        net::{IpAddr, Ipv4Addr},
+    };
+    use tower::{service_fn, ServiceExt};
+
+    use crate::TaggedData;
+
+    fn router() -> AddrInfo {
+        AddrInfo::new(
+            "192.0.2.100:1790".parse().unwrap(),
+            "192.0.2.1:40000".parse().unwrap(),
+        )
+    }
+
+    fn peer_header(post_policy: bool) -> PeerHeader {
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10))),
+            65000,
+            Ipv4Addr::new(192, 0, 2, 10),
+            None,
+        )
+    }
+
+    fn initiation() -> BmpMessage {
+        BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(vec![])))
+    }
+
+    fn peer_up() -> BmpMessage {
+        let open = BgpMessage::Open(BgpOpenMessage::new(65000, 180, Ipv4Addr::LOCALHOST, vec![]));
+        BmpMessage::V3(BmpMessageValue::PeerUpNotification(
+            PeerUpNotificationMessage::build(
+                peer_header(false),
+                Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))),
+                Some(179),
+                Some(40000),
+                open.clone(),
+                open,
+                vec![],
+            )
+            .unwrap(),
+        ))
+    }
+
+    fn peer_down() -> BmpMessage {
+        BmpMessage::V3(BmpMessageValue::PeerDownNotification(
+            PeerDownNotificationMessage::build(
+                peer_header(false),
+                PeerDownNotificationReason::RemoteSystemClosedNoData,
+            )
+            .unwrap(),
+        ))
+    }
+
+    fn route_monitoring(post_policy: bool) -> BmpMessage {
+        BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            RouteMonitoringMessage::build(
+                peer_header(post_policy),
+                BgpMessage::Update(BgpUpdateMessage::new(vec![], vec![], vec![])),
+            )
+            .unwrap(),
+        ))
+    }
+
+    fn termination() -> BmpMessage {
+        BmpMessage::V3(BmpMessageValue::Termination(TerminationMessage::new(
+            peer_header(false),
+            vec![],
+        )))
+    }
+
+    fn kinds(anomalies: Vec<BmpSequenceAnomaly>) -> Vec<BmpSequenceAnomalyKind> {
+        anomalies.iter().map(|anomaly| anomaly.kind()).collect()
+    }
+
+    #[test]
+    fn test_valid_sequence() {
+        let mut checker = BmpSequenceChecker::default();
+        for msg in [
+            initiation(),
+            peer_up(),
+            route_monitoring(false),
+            route_monitoring(true),
+            peer_down(),
+            peer_up(),
+            termination(),
+        ] {
+            assert_eq!(checker.check(router(), &msg), vec![]);
+        }
+    }
+
+    #[test]
+    fn test_anomalies() {
+        let mut checker = BmpSequenceChecker::default();
+        let peer = Some(
+            PeerKeyStrategy::AddressAndRd.apply(PeerKey::from_peer_header(&peer_header(false))),
+        );
+        assert_eq!(
+            checker.check(router(), &route_monitoring(false)),
+            vec![
+                BmpSequenceAnomaly::new(
+                    router(),
+                    BmpSequenceAnomalyKind::MissingInitiation,
+                    BmpMessageType::RouteMonitoring,
+                    None,
+                ),
+                BmpSequenceAnomaly::new(
+                    router(),
+                    BmpSequenceAnomalyKind::MessageBeforePeerUp,
+                    BmpMessageType::RouteMonitoring,
+                    peer,
+                ),
+            ]
+        );
+        assert_eq!(kinds(checker.check(router(), &peer_up())), vec![]);
+        assert_eq!(
+            kinds(checker.check(router(), &peer_up())),
+            vec![BmpSequenceAnomalyKind::DuplicatePeerUp]
+        );
+        assert_eq!(kinds(checker.check(router(), &peer_down())), vec![]);
+        assert_eq!(
+            kinds(checker.check(router(), &peer_down())),
+            vec![BmpSequenceAnomalyKind::PeerDownWithoutPeerUp]
+        );
+        assert_eq!(kinds(checker.check(router(), &termination())), vec![]);
+        assert_eq!(
+            kinds(checker.check(router(), &peer_up())),
+            vec![BmpSequenceAnomalyKind::MessageAfterTermination]
+        );
+
+        // The session is reset when the connection ends
+        assert_eq!(
+            checker.check_request(&Ok(TaggedData::new(router(), None))),
+            vec![]
+        );
+        assert_eq!(checker.sessions(), 0);
+        assert_eq!(kinds(checker.check(router(), &initiation())), vec![]);
+    }
+
+    #[tokio::test]
+    async fn test_sequence_check_layer() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let layer = SequenceCheckLayer::new(BmpSequenceChecker::default(), tx);
+        let svc = layer.layer(service_fn(|_req: BmpRequest| async move {
+            Ok::<Option<crate::server::BmpServerResponse>, Infallible>(None)
+        }));
+        svc.clone()
+            .oneshot(Ok(TaggedData::new(router(), Some(initiation()))))
+            .await
+            .unwrap();
+        assert!(rx.try_recv().is_err());
+        // Services cloned for each connection share the checker
+        svc.oneshot(Ok(TaggedData::new(router(), Some(peer_down()))))
+            .await
+            .unwrap();
+        assert_eq!(
+            rx.try_recv().unwrap().kind(),
+            BmpSequenceAnomalyKind::PeerDownWithoutPeerUp
+        );
+    }
+}
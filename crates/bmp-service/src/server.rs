@@ -13,16 +13,33 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{fmt::Debug, io, net::SocketAddr};
-use tokio::net::{TcpListener, TcpStream};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    io,
+    net::{Ipv4Addr, SocketAddr},
+};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_stream::StreamExt;
 use tokio_util::codec::Framed;
 use tower::ServiceExt;
 use tower_service::Service;
 
-use netgauze_bmp_pkt::{codec::BmpCodec, BmpMessage};
+use netgauze_bmp_pkt::{
+    codec::{BmpCodec, BmpMessageAction},
+    iana::{BmpMessageType, PeerTerminationCode},
+    BmpMessage, BmpMessageValue, BmpPeerType, PeerDownNotificationMessage,
+    PeerDownNotificationReason, PeerHeader, PeerKey, PeerKeyStrategy, TerminationInformation,
+    TerminationMessage,
+};
+use netgauze_parse_utils::raw_frame::RawFramePolicy;
 
-use crate::{handle::BmpServerHandle, AddrInfo, BmpCodecDecoderError, TaggedData};
+use crate::{
+    handle::{BmpServerHandle, BmpServerHandleWatcher},
+    listener::{BmpConnection, BmpListener, BmpListenerConfig},
+    recorder::{RecorderConfig, RecordingStream, StreamRecorder},
+    AddrInfo, BmpCodecDecoderError, TaggedData,
+};
 
 /// Tagged BMP Protocol request
 pub type BmpRequest =
@@ -39,20 +56,149 @@ pub enum BmpServerResponse {
 /// Listen and serve BMP Protocol
 #[derive(Debug)]
 pub struct BmpServer {
-    local_addr: SocketAddr,
+    listener: BmpListenerConfig,
     handle: BmpServerHandle,
+    message_actions: HashMap<BmpMessageType, BmpMessageAction>,
+    peer_key_strategy: PeerKeyStrategy,
+    max_message_length: Option<u32>,
+    raw_frame_policy: RawFramePolicy,
+    recorder: Option<RecorderConfig>,
+    #[cfg(feature = "tcp-auth")]
+    tcp_auth_keys: HashMap<std::net::IpAddr, netgauze_tcp_auth::TcpAuthKey>,
 }
 
 impl BmpServer {
-    pub const fn new(local_addr: SocketAddr, handle: BmpServerHandle) -> Self {
-        Self { local_addr, handle }
+    /// Create a BMP server listening on a TCP socket
+    pub fn new(local_addr: SocketAddr, handle: BmpServerHandle) -> Self {
+        Self::with_listener(BmpListenerConfig::Tcp(local_addr), handle)
     }
 
-    pub const fn local_addr(&self) -> SocketAddr {
-        self.local_addr
+    /// Create a BMP server listening on any of the [`BmpListenerConfig`]
+    /// socket types
+    pub fn with_listener(listener: BmpListenerConfig, handle: BmpServerHandle) -> Self {
+        Self {
+            listener,
+            handle,
+            message_actions: HashMap::new(),
+            peer_key_strategy: PeerKeyStrategy::default(),
+            max_message_length: None,
+            raw_frame_policy: RawFramePolicy::default(),
+            recorder: None,
+            #[cfg(feature = "tcp-auth")]
+            tcp_auth_keys: HashMap::new(),
+        }
+    }
+
+    /// Configure which BMP message types are decoded, passed raw or dropped
+    /// by the codec of each connection. Message types not in `actions` are
+    /// fully decoded.
+    pub fn with_message_actions(
+        mut self,
+        actions: HashMap<BmpMessageType, BmpMessageAction>,
+    ) -> Self {
+        self.message_actions = actions;
+        self
+    }
+
+    /// Configure how the codec of each connection identifies the monitored
+    /// peers when tracking their BGP parsing context
+    pub const fn with_peer_key_strategy(mut self, peer_key_strategy: PeerKeyStrategy) -> Self {
+        self.peer_key_strategy = peer_key_strategy;
+        self
+    }
+
+    /// Report messages longer than `max_message_length`, or shorter than the
+    /// BMP common header, with a diagnostic guessing the cause, see
+    /// [`BmpCodec::set_max_message_length`]
+    pub const fn with_max_message_length(mut self, max_message_length: u32) -> Self {
+        self.max_message_length = Some(max_message_length);
+        self
+    }
+
+    /// Attach the messages that fail to parse to the decoding errors, within
+    /// the limits of `policy` for each connection, see
+    /// [`BmpCodec::set_raw_frame_policy`]
+    pub const fn with_raw_frame_policy(mut self, policy: RawFramePolicy) -> Self {
+        self.raw_frame_policy = policy;
+        self
+    }
+
+    /// Record the raw byte stream of each connection to disk before decoding
+    /// it, see [`crate::recorder`]
+    pub fn with_recorder(mut self, recorder: RecorderConfig) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Accept connections from `router` only when authenticated with TCP MD5
+    /// or TCP-AO `key`. Serving fails for Unix domain socket listeners with
+    /// keys.
+    #[cfg(feature = "tcp-auth")]
+    pub fn with_tcp_auth_key(
+        mut self,
+        router: std::net::IpAddr,
+        key: netgauze_tcp_auth::TcpAuthKey,
+    ) -> Self {
+        self.tcp_auth_keys.insert(router, key);
+        self
+    }
+
+    pub const fn listener(&self) -> &BmpListenerConfig {
+        &self.listener
+    }
+
+    /// IP addresses the server listens on, see
+    /// [`BmpListenerConfig::local_addrs`]
+    pub fn local_addrs(&self) -> Vec<SocketAddr> {
+        self.listener.local_addrs()
     }
 
-    #[tracing::instrument(skip(self,service), fields(local_addr=format!("{}", self.local_addr)))]
+    /// Address of the TCP listener, or the primary address of the SCTP one.
+    /// Listeners without an IP address report
+    /// [`crate::listener::UNIX_LOCAL_SOCKET`].
+    #[deprecated(note = "use `BmpServer::local_addrs`, not every listener has an IP address")]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addrs()
+            .first()
+            .copied()
+            .unwrap_or(crate::listener::UNIX_LOCAL_SOCKET)
+    }
+
+    pub const fn message_actions(&self) -> &HashMap<BmpMessageType, BmpMessageAction> {
+        &self.message_actions
+    }
+
+    pub const fn peer_key_strategy(&self) -> PeerKeyStrategy {
+        self.peer_key_strategy
+    }
+
+    pub const fn max_message_length(&self) -> Option<u32> {
+        self.max_message_length
+    }
+
+    pub const fn raw_frame_policy(&self) -> RawFramePolicy {
+        self.raw_frame_policy
+    }
+
+    pub const fn recorder(&self) -> Option<&RecorderConfig> {
+        self.recorder.as_ref()
+    }
+
+    #[cfg(feature = "tcp-auth")]
+    pub const fn tcp_auth_keys(&self) -> &HashMap<std::net::IpAddr, netgauze_tcp_auth::TcpAuthKey> {
+        &self.tcp_auth_keys
+    }
+
+    /// Accept connections and pass the messages received to `service` until
+    /// shut down by the [`BmpServerHandle`].
+    ///
+    /// When the server closes a connection on shutdown, instead of the
+    /// router, the service receives a synthetic Peer Down message for each
+    /// peer that is still up, a Termination message, and the end of the
+    /// stream. Hence, the service sees the same sequence of messages as when
+    /// the router terminates the session, and can clean up the state of the
+    /// router.
+    #[tracing::instrument(skip(self,service), fields(listener=format!("{}", self.listener)))]
     pub async fn serve<S, E>(self, service: S) -> io::Result<()>
     where
         S: Service<BmpRequest, Response = Option<BmpServerResponse>, Error = E>
@@ -63,42 +209,70 @@ impl BmpServer {
         S::Error: Send,
         E: Debug,
     {
-        let local_addr = self.local_addr;
+        let handle = self.handle.clone();
+        let result = self.run(service).await;
+        handle.notify_closed();
+        result
+    }
+
+    async fn run<S, E>(self, service: S) -> io::Result<()>
+    where
+        S: Service<BmpRequest, Response = Option<BmpServerResponse>, Error = E>
+            + 'static
+            + Send
+            + Clone,
+        S::Future: Send + 'static,
+        S::Error: Send,
+        E: Debug,
+    {
         tracing::info!("binding on socket");
-        let listener = TcpListener::bind(local_addr).await?;
+        let listener = BmpListener::bind(&self.listener).await?;
+        #[cfg(feature = "tcp-auth")]
+        listener.set_tcp_auth_keys(&self.tcp_auth_keys)?;
         let handle = self.handle;
+        let message_actions = self.message_actions;
+        let peer_key_strategy = self.peer_key_strategy;
+        let max_message_length = self.max_message_length;
+        let raw_frame_policy = self.raw_frame_policy;
+        let recorder = self.recorder;
         handle.notify_listening();
         tracing::info!("started listening");
         let accept_loop_future = async {
             loop {
-                let (tcp_stream, remote_addr) = tokio::select! {
+                let (connection, addr_info) = tokio::select! {
                     biased;
                     result = listener.accept() => {
-                        let (tcp_stream, remote_addr) = result?;
-                        tracing::info!("accepted new connection: {:?}", remote_addr);
-                        (tcp_stream, remote_addr)
+                        let (connection, addr_info) = result?;
+                        tracing::info!("accepted new connection: {:?}", addr_info);
+                        (connection, addr_info)
                     },
                     _ = handle.wait_graceful_shutdown() => {
                         tracing::info!("graceful_shutdown");
                         return Ok::<(), io::Error>(())
                     },
                 };
-                let addr_info = AddrInfo::new(local_addr, remote_addr);
-                let framed = Framed::new(tcp_stream, BmpCodec::default());
+                let mut codec = BmpCodec::with_message_actions(message_actions.clone());
+                codec.set_peer_key_strategy(peer_key_strategy);
+                codec.set_max_message_length(max_message_length);
+                codec.set_raw_frame_policy(raw_frame_policy);
+                let stream_recorder = recorder
+                    .clone()
+                    .map(|config| StreamRecorder::new(config, addr_info));
                 let svc = service.clone();
                 let watcher = handle.watcher();
                 tokio::spawn(async move {
                     tracing::trace_span!("client_worker");
                     tracing::info!("worker_started");
-                    tokio::select! {
-                        biased;
-                        _ = watcher.wait_shutdown() => {
-                             tracing::info!("worker_shutdown: {:?}", addr_info);
-                        },
-                        ret = Self::handle_connection(svc.clone(), addr_info, framed) =>{
-                            tracing::info!("worker closed {:?} and service ret: {:?}", addr_info, ret);
-                        },
-                    }
+                    let ret = Self::handle_bmp_connection(
+                        svc.clone(),
+                        addr_info,
+                        connection,
+                        codec,
+                        stream_recorder,
+                        &watcher,
+                    )
+                    .await;
+                    tracing::info!("worker closed {:?} and service ret: {:?}", addr_info, ret);
                     tracing::info!("worker_ended");
                 });
             }
@@ -124,19 +298,50 @@ impl BmpServer {
         Ok(())
     }
 
+    async fn handle_bmp_connection<S, E>(
+        service: S,
+        addr_info: AddrInfo,
+        connection: BmpConnection,
+        codec: BmpCodec,
+        recorder: Option<StreamRecorder>,
+        watcher: &BmpServerHandleWatcher,
+    ) -> Result<(), E>
+    where
+        S: Service<BmpRequest, Response = Option<BmpServerResponse>, Error = E>
+            + 'static
+            + Send
+            + Clone,
+        S::Future: Send + 'static,
+        S::Error: Send,
+    {
+        match connection {
+            BmpConnection::Tcp(stream) => {
+                let framed = Framed::new(RecordingStream::new(stream, recorder), codec);
+                Self::handle_connection(service, addr_info, framed, watcher).await
+            }
+            #[cfg(unix)]
+            BmpConnection::Unix(stream) => {
+                let framed = Framed::new(RecordingStream::new(stream, recorder), codec);
+                Self::handle_connection(service, addr_info, framed, watcher).await
+            }
+        }
+    }
+
     #[tracing::instrument(
-        skip(service, addr_info, framed),
+        skip(service, addr_info, framed, watcher),
         fields(
             local_socket=format!("{}", addr_info.local_socket()),
             remote_socket=format!("{}", addr_info.remote_socket())
         )
     )]
-    async fn handle_connection<S, E>(
+    async fn handle_connection<S, E, T>(
         mut service: S,
         addr_info: AddrInfo,
-        mut framed: Framed<TcpStream, BmpCodec>,
+        mut framed: Framed<T, BmpCodec>,
+        watcher: &BmpServerHandleWatcher,
     ) -> Result<(), E>
     where
+        T: AsyncRead + AsyncWrite + Unpin,
         S: Service<BmpRequest, Response = Option<BmpServerResponse>, Error = E>
             + 'static
             + Send
@@ -144,11 +349,26 @@ impl BmpServer {
         S::Future: Send + 'static,
         S::Error: Send,
     {
+        let mut session = SessionPeers::new(framed.codec().peer_key_strategy());
         loop {
-            let result = StreamExt::try_next(&mut framed).await;
+            let result = tokio::select! {
+                biased;
+                _ = watcher.wait_shutdown() => {
+                    tracing::info!("worker_shutdown: {:?}", addr_info);
+                    for msg in session.shutdown_messages() {
+                        service.ready().await?;
+                        service.call(Ok(TaggedData::new(addr_info, msg))).await?;
+                    }
+                    return Ok(());
+                },
+                result = StreamExt::try_next(&mut framed) => result,
+            };
             match result {
                 Ok(msg) => {
                     let is_last = msg.is_none();
+                    if let Some(msg) = &msg {
+                        session.observe(msg);
+                    }
                     let tagged = Ok(TaggedData::new(addr_info, msg));
                     service.ready().await?;
                     let svc_response = service.call(tagged).await?;
@@ -167,6 +387,96 @@ impl BmpServer {
     }
 }
 
+/// Peers that are up in a BMP session, to emit their Peer Down messages when
+/// the server closes the session
+#[derive(Debug)]
+struct SessionPeers {
+    peer_key_strategy: PeerKeyStrategy,
+    peers: HashMap<PeerKey, PeerHeader>,
+    terminated: bool,
+}
+
+impl SessionPeers {
+    fn new(peer_key_strategy: PeerKeyStrategy) -> Self {
+        Self {
+            peer_key_strategy,
+            peers: HashMap::new(),
+            terminated: false,
+        }
+    }
+
+    fn peer_key(&self, header: &PeerHeader) -> PeerKey {
+        self.peer_key_strategy
+            .apply(PeerKey::from_peer_header(header))
+    }
+
+    fn observe(&mut self, msg: &BmpMessage) {
+        let value = msg.to_v3_value();
+        match value.as_ref() {
+            BmpMessageValue::PeerUpNotification(peer_up) => {
+                let key = self.peer_key(peer_up.peer_header());
+                self.peers.insert(key, peer_up.peer_header().clone());
+            }
+            BmpMessageValue::PeerDownNotification(peer_down) => {
+                let key = self.peer_key(peer_down.peer_header());
+                self.peers.remove(&key);
+            }
+            BmpMessageValue::Termination(_) => {
+                self.peers.clear();
+                self.terminated = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// Peer Down messages of the peers that are up, the Termination message,
+    /// unless already received from the router, and the end of the stream
+    fn shutdown_messages(&mut self) -> Vec<Option<BmpMessage>> {
+        let mut messages: Vec<Option<BmpMessage>> = self
+            .peers
+            .drain()
+            .filter_map(|(_, header)| {
+                PeerDownNotificationMessage::build(
+                    header,
+                    PeerDownNotificationReason::PeerDeConfigured,
+                )
+                .ok()
+            })
+            .map(|peer_down| {
+                Some(BmpMessage::V3(BmpMessageValue::PeerDownNotification(
+                    peer_down,
+                )))
+            })
+            .collect();
+        if !self.terminated {
+            self.terminated = true;
+            messages.push(Some(BmpMessage::V3(BmpMessageValue::Termination(
+                TerminationMessage::new(
+                    PeerHeader::new(
+                        BmpPeerType::GlobalInstancePeer {
+                            ipv6: false,
+                            post_policy: false,
+                            asn2: false,
+                            adj_rib_out: false,
+                        },
+                        None,
+                        None,
+                        0,
+                        Ipv4Addr::UNSPECIFIED,
+                        None,
+                    ),
+                    vec![
+                        TerminationInformation::Reason(PeerTerminationCode::AdministrativelyClosed),
+                        TerminationInformation::String("collector shutdown".to_string()),
+                    ],
+                ),
+            ))));
+        }
+        messages.push(None);
+        messages
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{
@@ -177,10 +487,11 @@ mod tests {
 
     use futures_util::SinkExt;
     use rand::Rng;
-    use tokio::task::JoinHandle;
+    use tokio::{net::TcpStream, sync::mpsc, task::JoinHandle};
     use tower::{service_fn, ServiceBuilder};
 
-    use netgauze_bmp_pkt::{BmpMessageValue, InitiationMessage};
+    use netgauze_bgp_pkt::{open::BgpOpenMessage, BgpMessage};
+    use netgauze_bmp_pkt::{InitiationMessage, PeerUpNotificationMessage};
 
     use super::*;
 
@@ -210,6 +521,194 @@ mod tests {
         assert!(server.is_finished());
     }
 
+    #[tokio::test]
+    async fn test_drain() {
+        let handle = BmpServerHandle::new(Some(Duration::from_millis(50)));
+        let server_handle = handle.clone();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let addr = get_free_socket();
+        let server = tokio::spawn(async move {
+            let svc = service_fn(move |req: BmpRequest| {
+                let tx = tx.clone();
+                async move {
+                    tx.send(req).unwrap();
+                    Ok::<Option<BmpServerResponse>, Infallible>(None)
+                }
+            });
+            BmpServer::new(addr, server_handle).serve(svc).await
+        });
+        handle.listening().await;
+
+        let mut client = connect(addr).await;
+        let peer_header = PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10))),
+            65000,
+            Ipv4Addr::new(192, 0, 2, 10),
+            None,
+        );
+        let open = BgpMessage::Open(BgpOpenMessage::new(65000, 180, Ipv4Addr::LOCALHOST, vec![]));
+        let initiation =
+            BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(vec![])));
+        let peer_up = BmpMessage::V3(BmpMessageValue::PeerUpNotification(
+            PeerUpNotificationMessage::build(
+                peer_header.clone(),
+                Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))),
+                Some(179),
+                Some(40000),
+                open.clone(),
+                open,
+                vec![],
+            )
+            .unwrap(),
+        ));
+        client.send(initiation.clone()).await.unwrap();
+        client.send(peer_up.clone()).await.unwrap();
+        // Wait for the messages to be received before draining
+        for _ in 0..2 {
+            rx.recv().await.unwrap().unwrap();
+        }
+
+        tokio::time::timeout(Duration::from_secs(1), handle.drain())
+            .await
+            .unwrap();
+        assert!(server.await.unwrap().is_ok());
+        assert_eq!(handle.connection_count(), 0);
+        // No new connections are accepted
+        assert!(TcpStream::connect(addr).await.is_err());
+
+        let mut received = vec![];
+        while let Ok(req) = rx.try_recv() {
+            received.push(req.unwrap().value().clone());
+        }
+        assert_eq!(
+            received,
+            vec![
+                Some(BmpMessage::V3(BmpMessageValue::PeerDownNotification(
+                    PeerDownNotificationMessage::build(
+                        peer_header,
+                        PeerDownNotificationReason::PeerDeConfigured,
+                    )
+                    .unwrap(),
+                ))),
+                Some(BmpMessage::V3(BmpMessageValue::Termination(
+                    TerminationMessage::new(
+                        PeerHeader::new(
+                            BmpPeerType::GlobalInstancePeer {
+                                ipv6: false,
+                                post_policy: false,
+                                asn2: false,
+                                adj_rib_out: false,
+                            },
+                            None,
+                            None,
+                            0,
+                            Ipv4Addr::UNSPECIFIED,
+                            None,
+                        ),
+                        vec![
+                            TerminationInformation::Reason(
+                                PeerTerminationCode::AdministrativelyClosed
+                            ),
+                            TerminationInformation::String("collector shutdown".to_string()),
+                        ],
+                    )
+                ))),
+                None,
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_socket() {
+        let path = std::env::temp_dir().join(format!(
+            "netgauze-bmp-{}-{}.sock",
+            std::process::id(),
+            rand::thread_rng().gen::<u32>()
+        ));
+        let handle = BmpServerHandle::default();
+        let server_handle = handle.clone();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let listener = BmpListenerConfig::Unix(path.clone());
+        let server = tokio::spawn(async move {
+            let svc = service_fn(move |req: BmpRequest| {
+                let tx = tx.clone();
+                async move {
+                    tx.send(req).unwrap();
+                    Ok::<Option<BmpServerResponse>, Infallible>(None)
+                }
+            });
+            BmpServer::with_listener(listener, server_handle)
+                .serve(svc)
+                .await
+        });
+        handle.listening().await;
+
+        let stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+        let mut client = Framed::new(stream, BmpCodec::default());
+        let msg = BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(vec![])));
+        client.send(msg.clone()).await.unwrap();
+        let received = rx.recv().await.unwrap().unwrap();
+        assert_eq!(received.tag(), crate::listener::unix_addr_info(0));
+        assert_eq!(received.value(), &Some(msg));
+
+        handle.shutdown();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(server.is_finished());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(all(feature = "tcp-auth", target_os = "linux"))]
+    #[tokio::test]
+    async fn test_tcp_auth() {
+        use netgauze_tcp_auth::{set_tcp_auth_key, TcpAuthKey};
+
+        let handle = BmpServerHandle::default();
+        let server_handle = handle.clone();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let addr = get_free_socket();
+        let key = TcpAuthKey::md5("secret");
+        let server_key = key.clone();
+        let server = tokio::spawn(async move {
+            let svc = service_fn(move |req: BmpRequest| {
+                let tx = tx.clone();
+                async move {
+                    tx.send(req).unwrap();
+                    Ok::<Option<BmpServerResponse>, Infallible>(None)
+                }
+            });
+            BmpServer::new(addr, server_handle)
+                .with_tcp_auth_key(addr.ip(), server_key)
+                .serve(svc)
+                .await
+        });
+        handle.listening().await;
+
+        // Routers without the key can't connect
+        let unauthenticated =
+            tokio::time::timeout(Duration::from_millis(500), TcpStream::connect(addr)).await;
+        assert!(!matches!(unauthenticated, Ok(Ok(_))));
+
+        let socket = tokio::net::TcpSocket::new_v4().unwrap();
+        set_tcp_auth_key(&socket, addr.ip(), &key).unwrap();
+        let mut client = Framed::new(socket.connect(addr).await.unwrap(), BmpCodec::default());
+        let msg = BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(vec![])));
+        client.send(msg.clone()).await.unwrap();
+        let received = rx.recv().await.unwrap().unwrap();
+        assert_eq!(received.value(), &Some(msg));
+
+        handle.shutdown();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(server.is_finished());
+    }
+
     fn get_free_socket() -> SocketAddr {
         let mut rng = rand::thread_rng();
         let port: u16 = rng.gen_range(25000..50000);
@@ -0,0 +1,260 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! [`crate::sink::RouteEventSink`] writing to ClickHouse over its
+//! [HTTP interface](https://clickhouse.com/docs/en/interfaces/http)
+
+use netgauze_bgp_pkt::nlri::RouteDistinguisher;
+use netgauze_bmp_pkt::BmpPeerType;
+use netgauze_sink::clickhouse::ClickHouseRow;
+
+use crate::sink::{RouteAction, RouteEvent, RouteEventSinkError};
+
+/// Writes route events to a ClickHouse table, deduplicated by their
+/// [`RouteEvent::idempotency_key`] in an `event_id` column, see
+/// [`netgauze_sink::clickhouse::ClickHouseSink`]
+pub type ClickHouseSink = netgauze_sink::clickhouse::ClickHouseSink<RouteEvent>;
+
+impl ClickHouseRow for RouteEvent {
+    const COLUMNS: &'static str = "router String, \
+         timestamp Nullable(DateTime64(6, 'UTC')), \
+         peer_address Nullable(String), \
+         peer_as UInt32, \
+         peer_bgp_id IPv4, \
+         post_policy Bool, \
+         adj_rib_out Bool, \
+         rd Nullable(String), \
+         vrf Nullable(String), \
+         action Enum8('announce' = 1, 'withdraw' = 2), \
+         address_type Nullable(String), \
+         prefix String, \
+         path_id Nullable(UInt32), \
+         next_hop Nullable(String), \
+         origin Nullable(String), \
+         as_path Array(UInt32), \
+         communities Array(String), \
+         large_communities Array(String), \
+         local_pref Nullable(UInt32), \
+         med Nullable(UInt32)";
+
+    const ORDER_BY: &'static str = "router, peer_address, prefix";
+
+    const KEY_COLUMN: &'static str = "event_id";
+
+    fn row(&self) -> Result<serde_json::Value, RouteEventSinkError> {
+        let (post_policy, adj_rib_out) = peer_type_flags(self.peer_type());
+        Ok(serde_json::json!({
+            "router": self.router().to_string(),
+            "timestamp": self
+                .timestamp()
+                .map(|ts| ts.format("%Y-%m-%d %H:%M:%S%.6f").to_string()),
+            "peer_address": self.peer_address().map(|addr| addr.to_string()),
+            "peer_as": self.peer_as(),
+            "peer_bgp_id": self.peer_bgp_id().to_string(),
+            "post_policy": post_policy,
+            "adj_rib_out": adj_rib_out,
+            "rd": self.rd().map(rd_string),
+            "vrf": self.vrf(),
+            "action": match self.action() {
+                RouteAction::Announce => "announce",
+                RouteAction::Withdraw => "withdraw",
+            },
+            "address_type": self.address_type().map(|address_type| format!("{address_type:?}")),
+            "prefix": self.prefix(),
+            "path_id": self.path_id(),
+            "next_hop": self.next_hop().map(|next_hop| next_hop.to_string()),
+            "origin": self.origin().map(|origin| origin.to_string()),
+            "as_path": self.as_path(),
+            "communities": self.communities(),
+            "large_communities": self.large_communities(),
+            "local_pref": self.local_pref(),
+            "med": self.med(),
+        }))
+    }
+}
+
+/// Post-policy and Adj-RIB-Out flags of the peer type, Loc-RIB is reported as
+/// post-policy
+const fn peer_type_flags(peer_type: BmpPeerType) -> (bool, bool) {
+    match peer_type {
+        BmpPeerType::GlobalInstancePeer {
+            post_policy,
+            adj_rib_out,
+            ..
+        }
+        | BmpPeerType::RdInstancePeer {
+            post_policy,
+            adj_rib_out,
+            ..
+        }
+        | BmpPeerType::LocalInstancePeer {
+            post_policy,
+            adj_rib_out,
+            ..
+        } => (post_policy, adj_rib_out),
+        BmpPeerType::LocRibInstancePeer { .. } => (true, false),
+        _ => (false, false),
+    }
+}
+
+/// Route distinguisher in the `ADMINISTRATOR:NUMBER` notation
+fn rd_string(rd: RouteDistinguisher) -> String {
+    match rd {
+        RouteDistinguisher::As2Administrator { asn2, number } => format!("{asn2}:{number}"),
+        RouteDistinguisher::Ipv4Administrator { ip, number } => format!("{ip}:{number}"),
+        RouteDistinguisher::As4Administrator { asn4, number } => format!("{asn4}:{number}"),
+        RouteDistinguisher::LeafAdRoutes => "leaf-ad-routes".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use ipnet::Ipv4Net;
+    use netgauze_bgp_pkt::{
+        nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+        update::BgpUpdateMessage,
+        BgpMessage,
+    };
+    use netgauze_bmp_pkt::{BmpMessage, BmpMessageValue, PeerHeader, RouteMonitoringMessage};
+    use std::{
+        net::{IpAddr, Ipv4Addr, SocketAddr},
+        str::FromStr,
+    };
+
+    #[test]
+    fn test_insert_body() {
+        let sink = ClickHouseSink::new(
+            "http://localhost:8123/".to_string(),
+            "bmp.routes".to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            sink.insert_query(),
+            "INSERT INTO bmp.routes FORMAT JSONEachRow"
+        );
+        let msg = BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            RouteMonitoringMessage::build(
+                PeerHeader::new(
+                    BmpPeerType::RdInstancePeer {
+                        ipv6: false,
+                        post_policy: true,
+                        asn2: false,
+                        adj_rib_out: false,
+                    },
+                    Some(RouteDistinguisher::As2Administrator {
+                        asn2: 65001,
+                        number: 10,
+                    }),
+                    Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2))),
+                    65001,
+                    Ipv4Addr::new(192, 0, 2, 2),
+                    Some(Utc.timestamp_opt(1680000000, 0).unwrap()),
+                ),
+                BgpMessage::Update(BgpUpdateMessage::new(
+                    vec![Ipv4UnicastAddress::new_no_path_id(
+                        Ipv4Unicast::from_net(Ipv4Net::from_str("198.51.100.0/24").unwrap())
+                            .unwrap(),
+                    )],
+                    vec![],
+                    vec![],
+                )),
+            )
+            .unwrap(),
+        ));
+        let events = RouteEvent::from_bmp_message(SocketAddr::from(([192, 0, 2, 1], 40000)), &msg);
+        let body = sink.insert_body(&events).unwrap();
+        let lines = body.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(lines[0]).unwrap(),
+            serde_json::json!({
+                "router": "192.0.2.1:40000",
+                "timestamp": "2023-03-28 10:40:00.000000",
+                "peer_address": "192.0.2.2",
+                "peer_as": 65001,
+                "peer_bgp_id": "192.0.2.2",
+                "post_policy": true,
+                "adj_rib_out": false,
+                "rd": "65001:10",
+                "vrf": null,
+                "action": "withdraw",
+                "address_type": "Ipv4Unicast",
+                "prefix": "198.51.100.0/24",
+                "path_id": null,
+                "next_hop": null,
+                "origin": null,
+                "as_path": [],
+                "communities": [],
+                "large_communities": [],
+                "local_pref": null,
+                "med": null,
+            })
+        );
+    }
+    #[test]
+    fn test_deduplication() {
+        let sink = ClickHouseSink::new(
+            "http://localhost:8123/".to_string(),
+            "bmp.routes".to_string(),
+        )
+        .unwrap();
+        assert!(!sink.deduplication());
+        assert!(sink.create_table_query().ends_with(
+            "med Nullable(UInt32)) ENGINE = MergeTree ORDER BY (router, peer_address, prefix)"
+        ));
+
+        let sink = sink.with_deduplication();
+        assert!(sink.deduplication());
+        assert!(sink.create_table_query().ends_with(
+            "med Nullable(UInt32), event_id FixedString(32)) \
+             ENGINE = ReplacingMergeTree ORDER BY (router, peer_address, prefix, event_id)"
+        ));
+        let msg = BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            RouteMonitoringMessage::build(
+                PeerHeader::new(
+                    BmpPeerType::GlobalInstancePeer {
+                        ipv6: false,
+                        post_policy: false,
+                        asn2: false,
+                        adj_rib_out: false,
+                    },
+                    None,
+                    Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2))),
+                    65001,
+                    Ipv4Addr::new(192, 0, 2, 2),
+                    None,
+                ),
+                BgpMessage::Update(BgpUpdateMessage::new(
+                    vec![Ipv4UnicastAddress::new_no_path_id(
+                        Ipv4Unicast::from_net(Ipv4Net::from_str("198.51.100.0/24").unwrap())
+                            .unwrap(),
+                    )],
+                    vec![],
+                    vec![],
+                )),
+            )
+            .unwrap(),
+        ));
+        let events = RouteEvent::from_bmp_message(SocketAddr::from(([192, 0, 2, 1], 40000)), &msg);
+        let body = sink.insert_body(&events).unwrap();
+        let row = serde_json::from_str::<serde_json::Value>(body.trim_end()).unwrap();
+        assert_eq!(
+            row["event_id"],
+            serde_json::json!(events[0].idempotency_key().unwrap())
+        );
+    }
+}
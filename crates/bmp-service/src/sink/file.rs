@@ -0,0 +1,96 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! [`crate::sink::RouteEventSink`] writing JSON Lines files into time-bucketed
+//! directories
+
+pub use netgauze_sink::file::{COMPLETION_MARKER, DEFAULT_BUCKET_INTERVAL, DEFAULT_BUCKET_LAYOUT};
+
+use crate::sink::RouteEvent;
+
+/// Writes route events as JSON Lines into one directory per time bucket, e.g.,
+/// `root/2024/03/28/13/routes-1711630800.jsonl` for hourly buckets, see
+/// [`netgauze_sink::file::FileSink`]
+pub type FileSink = netgauze_sink::file::FileSink<RouteEvent>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use ipnet::Ipv4Net;
+    use netgauze_bgp_pkt::{
+        nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+        update::BgpUpdateMessage,
+        BgpMessage,
+    };
+    use netgauze_bmp_pkt::{
+        BmpMessage, BmpMessageValue, BmpPeerType, PeerHeader, RouteMonitoringMessage,
+    };
+    use std::{net::Ipv4Addr, str::FromStr};
+
+    fn test_events() -> Vec<RouteEvent> {
+        let update = BgpUpdateMessage::new(
+            vec![Ipv4UnicastAddress::new_no_path_id(
+                Ipv4Unicast::from_net(Ipv4Net::from_str("198.51.100.0/24").unwrap()).unwrap(),
+            )],
+            vec![],
+            vec![],
+        );
+        let msg = BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            RouteMonitoringMessage::build(
+                PeerHeader::new(
+                    BmpPeerType::GlobalInstancePeer {
+                        ipv6: false,
+                        post_policy: false,
+                        asn2: false,
+                        adj_rib_out: false,
+                    },
+                    None,
+                    None,
+                    65000,
+                    Ipv4Addr::new(192, 0, 2, 1),
+                    None,
+                ),
+                BgpMessage::Update(update),
+            )
+            .unwrap(),
+        ));
+        RouteEvent::from_bmp_message("192.0.2.1:1790".parse().unwrap(), &msg)
+    }
+
+    #[tokio::test]
+    async fn test_file_sink() {
+        let root = std::env::temp_dir().join(format!("netgauze-bmp-file-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let mut sink = FileSink::new(root.clone(), "routes".to_string());
+        sink.bootstrap().await.unwrap();
+
+        let now = Utc.with_ymd_and_hms(2024, 3, 28, 13, 5, 0).unwrap();
+        sink.write_at(&test_events(), now).await.unwrap();
+        sink.write_at(&test_events(), now).await.unwrap();
+        sink.close().await.unwrap();
+
+        let dir = root.join("2024/03/28/13");
+        assert!(dir.join(COMPLETION_MARKER).exists());
+        let content = std::fs::read_to_string(dir.join("routes-1711630800.jsonl")).unwrap();
+        let events = content
+            .lines()
+            .map(|line| RouteEvent::from_json(serde_json::from_str(line).unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(events, [test_events(), test_events()].concat());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
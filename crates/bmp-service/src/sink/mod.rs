@@ -0,0 +1,752 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage sinks for route events.
+//!
+//! Each BGP update carried in a BMP Route Monitoring message is flattened into
+//! [`RouteEvent`]s, one per announced or withdrawn prefix, along with the
+//! monitored peer information and the path attributes of the route. Events
+//! are written to a [`RouteEventSink`], usually through a [`BatchingSink`] to
+//! insert them in batches and retry failed writes.
+//!
+//! Events of VPN tables are labeled with the name of their VRF by
+//! [`VrfNames`], instead of only the opaque route distinguisher.
+//!
+//! Serialized events carry a [`SchemaVersion`], so consumers can be migrated
+//! to a new layout after upgrading the service, see [`RouteEvent::to_json`].
+//!
+//! Events are identified by a hash of their content, see
+//! [`RouteEvent::idempotency_key`], so sinks can drop the events written again
+//! when a batch is retried or replayed after a crash.
+//!
+//! Aggregations over event time, e.g., per-minute update counts, are computed
+//! with [`window::WindowedAggregator`] despite the out of order delivery of
+//! the events of different routers.
+//!
+//! Sinks are enabled with crate features:
+//! - `file`: [`file::FileSink`] writes JSON Lines files into time-bucketed
+//!   directories.
+//! - `clickhouse`: [`clickhouse::ClickHouseSink`] writes to ClickHouse over its
+//!   HTTP interface.
+
+#[cfg(feature = "clickhouse")]
+pub mod clickhouse;
+#[cfg(feature = "file")]
+pub mod file;
+pub mod window;
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use netgauze_bgp_pkt::{
+    nlri::RouteDistinguisher,
+    path_attribute::{AsPath, Origin, PathAttributeValue},
+    update::{Route, RoutePrefix},
+    BgpMessage,
+};
+use netgauze_bmp_pkt::{
+    BmpMessage, BmpMessageValue, BmpPeerType, InitiationInformation, PeerHeader,
+};
+use netgauze_iana::address_family::AddressType;
+use netgauze_sink::{
+    content_key, take_schema_version, to_versioned_json, RecordSink, SinkError, SinkRecord,
+};
+
+pub use netgauze_sink::{
+    batching::{DEFAULT_BATCH_SIZE, DEFAULT_MAX_PENDING},
+    SchemaVersion, SCHEMA_VERSION_FIELD,
+};
+
+/// Errors of the route event sinks, see [`SinkError`]
+pub type RouteEventSinkError = SinkError;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RouteAction {
+    Announce,
+    Withdraw,
+}
+
+/// A single prefix announced or withdrawn by a monitored peer.
+///
+/// Path attributes are only set for announcements. Prefixes of address
+/// families other than IPv4/IPv6 unicast and multicast are kept as the JSON
+/// serialization of their NLRI.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouteEvent {
+    router: SocketAddr,
+    timestamp: Option<DateTime<Utc>>,
+    peer_address: Option<IpAddr>,
+    peer_as: u32,
+    peer_bgp_id: Ipv4Addr,
+    peer_type: BmpPeerType,
+    rd: Option<RouteDistinguisher>,
+    vrf: Option<String>,
+    action: RouteAction,
+    address_type: Option<AddressType>,
+    prefix: String,
+    path_id: Option<u32>,
+    next_hop: Option<IpAddr>,
+    origin: Option<Origin>,
+    as_path: Vec<u32>,
+    communities: Vec<String>,
+    large_communities: Vec<String>,
+    local_pref: Option<u32>,
+    med: Option<u32>,
+}
+
+/// Path attributes of the announced routes in a single update
+#[derive(Debug, Clone, Default)]
+struct Attributes {
+    next_hop: Option<IpAddr>,
+    origin: Option<Origin>,
+    as_path: Vec<u32>,
+    communities: Vec<String>,
+    large_communities: Vec<String>,
+    local_pref: Option<u32>,
+    med: Option<u32>,
+}
+
+impl RouteEvent {
+    /// Flatten a BMP message received from `router` into route events, only
+    /// Route Monitoring messages produce events.
+    pub fn from_bmp_message(router: SocketAddr, msg: &BmpMessage) -> Vec<RouteEvent> {
+        let route_monitoring = match msg {
+            BmpMessage::V3(BmpMessageValue::RouteMonitoring(route_monitoring)) => route_monitoring,
+            _ => return vec![],
+        };
+        let update = match route_monitoring.update_message() {
+            BgpMessage::Update(update) => update,
+            _ => return vec![],
+        };
+        let peer_header = route_monitoring.peer_header();
+        let event = |action, route: Route<'_>, attrs: &Attributes| {
+            RouteEvent::new(
+                router,
+                peer_header,
+                action,
+                Some(route.address_type()),
+                prefix_string(route.prefix()),
+                route.path_id(),
+                Attributes {
+                    next_hop: route.next_hop(),
+                    ..attrs.clone()
+                },
+            )
+        };
+
+        let mut attrs = Attributes::default();
+        for attr in update.path_attributes() {
+            match attr.value() {
+                PathAttributeValue::Origin(origin) => attrs.origin = Some(*origin),
+                PathAttributeValue::AsPath(as_path) => attrs.as_path = as_path_asns(as_path),
+                PathAttributeValue::MultiExitDiscriminator(med) => attrs.med = Some(med.metric()),
+                PathAttributeValue::LocalPreference(local_pref) => {
+                    attrs.local_pref = Some(local_pref.metric())
+                }
+                PathAttributeValue::Communities(communities) => {
+                    attrs.communities = communities
+                        .communities()
+                        .iter()
+                        .map(|c| format!("{}:{}", c.collection_asn(), c.collection_value()))
+                        .collect()
+                }
+                PathAttributeValue::LargeCommunities(communities) => {
+                    attrs.large_communities = communities
+                        .communities()
+                        .iter()
+                        .map(|c| {
+                            format!(
+                                "{}:{}:{}",
+                                c.global_admin(),
+                                c.local_data1(),
+                                c.local_data2()
+                            )
+                        })
+                        .collect()
+                }
+                _ => {}
+            }
+        }
+
+        let no_attrs = Attributes::default();
+        update
+            .withdrawn()
+            .map(|route| event(RouteAction::Withdraw, route, &no_attrs))
+            .chain(
+                update
+                    .announced()
+                    .map(|route| event(RouteAction::Announce, route, &attrs)),
+            )
+            .collect()
+    }
+
+    fn new(
+        router: SocketAddr,
+        peer_header: &PeerHeader,
+        action: RouteAction,
+        address_type: Option<AddressType>,
+        prefix: String,
+        path_id: Option<u32>,
+        attrs: Attributes,
+    ) -> Self {
+        Self {
+            router,
+            timestamp: peer_header.timestamp().copied(),
+            peer_address: peer_header.address(),
+            peer_as: peer_header.peer_as(),
+            peer_bgp_id: peer_header.bgp_id(),
+            peer_type: peer_header.peer_type(),
+            rd: peer_header.rd(),
+            vrf: None,
+            action,
+            address_type,
+            prefix,
+            path_id,
+            next_hop: attrs.next_hop,
+            origin: attrs.origin,
+            as_path: attrs.as_path,
+            communities: attrs.communities,
+            large_communities: attrs.large_communities,
+            local_pref: attrs.local_pref,
+            med: attrs.med,
+        }
+    }
+
+    /// Address of the BMP speaker that reported the event
+    pub const fn router(&self) -> SocketAddr {
+        self.router
+    }
+
+    pub const fn timestamp(&self) -> Option<DateTime<Utc>> {
+        self.timestamp
+    }
+
+    pub const fn peer_address(&self) -> Option<IpAddr> {
+        self.peer_address
+    }
+
+    pub const fn peer_as(&self) -> u32 {
+        self.peer_as
+    }
+
+    pub const fn peer_bgp_id(&self) -> Ipv4Addr {
+        self.peer_bgp_id
+    }
+
+    pub const fn peer_type(&self) -> BmpPeerType {
+        self.peer_type
+    }
+
+    pub const fn rd(&self) -> Option<RouteDistinguisher> {
+        self.rd
+    }
+
+    /// Name of the VRF of the route distinguisher, when labeled by
+    /// [`VrfNames`]
+    pub fn vrf(&self) -> Option<&str> {
+        self.vrf.as_deref()
+    }
+
+    pub const fn action(&self) -> RouteAction {
+        self.action
+    }
+
+    pub const fn address_type(&self) -> Option<AddressType> {
+        self.address_type
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub const fn path_id(&self) -> Option<u32> {
+        self.path_id
+    }
+
+    pub const fn next_hop(&self) -> Option<IpAddr> {
+        self.next_hop
+    }
+
+    pub const fn origin(&self) -> Option<Origin> {
+        self.origin
+    }
+
+    /// AS numbers of the AS_PATH, AS_SETs are flattened into the path
+    pub const fn as_path(&self) -> &Vec<u32> {
+        &self.as_path
+    }
+
+    /// Communities in the `ASN:VALUE` notation
+    pub const fn communities(&self) -> &Vec<String> {
+        &self.communities
+    }
+
+    /// Large communities in the `GLOBAL:LOCAL1:LOCAL2` notation
+    pub const fn large_communities(&self) -> &Vec<String> {
+        &self.large_communities
+    }
+
+    pub const fn local_pref(&self) -> Option<u32> {
+        self.local_pref
+    }
+
+    pub const fn med(&self) -> Option<u32> {
+        self.med
+    }
+
+    /// Serialize the event in the layout of `version`. Older versions are
+    /// kept to feed consumers that are not migrated yet, the `vrf` field is
+    /// only part of [`SchemaVersion::V2`].
+    pub fn to_json(
+        &self,
+        version: SchemaVersion,
+    ) -> Result<serde_json::Value, RouteEventSinkError> {
+        let mut value = to_versioned_json(self, version)?;
+        if version == SchemaVersion::V1 {
+            if let Some(object) = value.as_object_mut() {
+                object.remove("vrf");
+            }
+        }
+        Ok(value)
+    }
+
+    /// Parse an event serialized with any of the supported schema versions,
+    /// events without a version are read as [`SchemaVersion::V1`]
+    pub fn from_json(mut value: serde_json::Value) -> Result<Self, RouteEventSinkError> {
+        take_schema_version(&mut value)?;
+        serde_json::from_value(value)
+            .map_err(|err| RouteEventSinkError::SerializationError(err.to_string()))
+    }
+
+    /// Deterministic 128-bit hash of the content of the event, as 32 lowercase
+    /// hex digits, to deduplicate the events delivered more than once to a
+    /// sink, e.g., as the Kafka message key or a ClickHouse column.
+    ///
+    /// The key only depends on the fields of [`SchemaVersion::V1`], so it's
+    /// the same for an event read back with [`Self::from_json`] from any
+    /// schema version, and doesn't change when the VRF names are relabeled.
+    /// The router is identified by its IP address only, so the events
+    /// replayed after the router reconnected from another source port keep
+    /// their key. The same prefix announced twice with the same attributes and
+    /// BMP timestamp yields the same key.
+    pub fn idempotency_key(&self) -> Result<String, RouteEventSinkError> {
+        let mut content = self.to_json(SchemaVersion::V1)?;
+        content["router"] = self.router.ip().to_string().into();
+        Ok(content_key(&content))
+    }
+}
+
+impl SinkRecord for RouteEvent {
+    type Error = RouteEventSinkError;
+
+    fn to_json(&self, version: SchemaVersion) -> Result<serde_json::Value, RouteEventSinkError> {
+        RouteEvent::to_json(self, version)
+    }
+
+    fn idempotency_key(&self) -> Result<String, RouteEventSinkError> {
+        RouteEvent::idempotency_key(self)
+    }
+}
+
+fn as_path_asns(as_path: &AsPath) -> Vec<u32> {
+    match as_path {
+        AsPath::As2PathSegments(segments) => segments
+            .iter()
+            .flat_map(|segment| segment.as_numbers().iter().map(|asn| *asn as u32))
+            .collect(),
+        AsPath::As4PathSegments(segments) => segments
+            .iter()
+            .flat_map(|segment| segment.as_numbers().iter().copied())
+            .collect(),
+    }
+}
+
+/// IP prefix of the route, or its JSON representation for the NLRI that don't
+/// carry one
+fn prefix_string(prefix: RoutePrefix<'_>) -> String {
+    match prefix.ip_net() {
+        Some(ip_net) => ip_net.to_string(),
+        None => serde_json::to_string(&prefix).unwrap_or_default(),
+    }
+}
+
+/// Names of the VRFs by route distinguisher, used to label the
+/// [`RouteEvent`]s of Loc-RIB and RD instance peers.
+///
+/// Names are learned per router from the VRF/Table Name TLV of the Peer Up
+/// messages ([RFC9069](https://datatracker.ietf.org/doc/html/rfc9069)) and
+/// forgotten when the router terminates the session. Configured names are used
+/// for the route distinguishers a router didn't name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VrfNames {
+    configured: HashMap<RouteDistinguisher, String>,
+    learned: HashMap<(IpAddr, RouteDistinguisher), String>,
+}
+
+impl VrfNames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Configure the name of the VRF of `rd` for all routers
+    pub fn with_vrf(mut self, rd: RouteDistinguisher, name: String) -> Self {
+        self.configured.insert(rd, name);
+        self
+    }
+
+    /// Learn the VRF names from Peer Up messages received from `router`
+    pub fn observe_bmp_message(&mut self, router: SocketAddr, msg: &BmpMessage) {
+        let value = msg.to_v3_value();
+        match value.as_ref() {
+            BmpMessageValue::PeerUpNotification(peer_up) => {
+                let rd = match peer_up.peer_header().rd() {
+                    Some(rd) => rd,
+                    None => return,
+                };
+                let name = peer_up.information().iter().find_map(|info| match info {
+                    InitiationInformation::VrfTableName(name) => Some(name),
+                    _ => None,
+                });
+                if let Some(name) = name {
+                    self.learned.insert((router.ip(), rd), name.clone());
+                }
+            }
+            BmpMessageValue::Termination(_) => {
+                self.learned
+                    .retain(|(learned_router, _), _| *learned_router != router.ip());
+            }
+            _ => {}
+        }
+    }
+
+    /// Name of the VRF of `rd` on `router`
+    pub fn vrf_name(&self, router: IpAddr, rd: RouteDistinguisher) -> Option<&str> {
+        self.learned
+            .get(&(router, rd))
+            .or_else(|| self.configured.get(&rd))
+            .map(String::as_str)
+    }
+
+    /// Set the VRF name of the events with a known route distinguisher
+    pub fn label(&self, events: &mut [RouteEvent]) {
+        for event in events {
+            if let Some(rd) = event.rd {
+                event.vrf = self.vrf_name(event.router.ip(), rd).map(str::to_string);
+            }
+        }
+    }
+}
+
+/// Storage backend for [`RouteEvent`]s, implemented by all the
+/// [`RecordSink`]s of route events
+pub trait RouteEventSink: RecordSink<RouteEvent> {}
+
+impl<S: RecordSink<RouteEvent>> RouteEventSink for S {}
+
+/// Buffers route events and writes them to the inner [`RouteEventSink`] in
+/// batches, see [`netgauze_sink::batching::BatchingSink`]
+pub type BatchingSink<S> = netgauze_sink::batching::BatchingSink<S, RouteEvent>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use ipnet::{Ipv4Net, Ipv6Net};
+    use netgauze_bgp_pkt::open::BgpOpenMessage;
+    use netgauze_bgp_pkt::{
+        community::Community,
+        nlri::{Ipv4Unicast, Ipv4UnicastAddress, Ipv6Unicast, Ipv6UnicastAddress},
+        path_attribute::{
+            As4PathSegment, AsPathSegmentType, Communities, LocalPreference, MpUnreach, NextHop,
+            PathAttribute,
+        },
+        update::BgpUpdateMessage,
+    };
+    use netgauze_bmp_pkt::{PeerUpNotificationMessage, RouteMonitoringMessage, TerminationMessage};
+    use std::str::FromStr;
+
+    fn route_monitoring(update: BgpUpdateMessage) -> BmpMessage {
+        BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            RouteMonitoringMessage::build(
+                PeerHeader::new(
+                    BmpPeerType::GlobalInstancePeer {
+                        ipv6: false,
+                        post_policy: false,
+                        asn2: false,
+                        adj_rib_out: false,
+                    },
+                    None,
+                    Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2))),
+                    65001,
+                    Ipv4Addr::new(192, 0, 2, 2),
+                    Some(Utc.timestamp_opt(1680000000, 0).unwrap()),
+                ),
+                BgpMessage::Update(update),
+            )
+            .unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_route_events() {
+        let update = BgpUpdateMessage::new(
+            vec![Ipv4UnicastAddress::new_no_path_id(
+                Ipv4Unicast::from_net(Ipv4Net::from_str("198.51.100.0/24").unwrap()).unwrap(),
+            )],
+            vec![
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::Origin(Origin::IGP),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![As4PathSegment::new(
+                        AsPathSegmentType::AsSequence,
+                        vec![65001, 3356],
+                    )])),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(192, 0, 2, 2))),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::LocalPreference(LocalPreference::new(200)),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    true,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::Communities(Communities::new(vec![Community::new(
+                        (65001 << 16) | 100,
+                    )])),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    true,
+                    false,
+                    false,
+                    false,
+                    PathAttributeValue::MpUnreach(MpUnreach::Ipv6Unicast {
+                        nlri: vec![Ipv6UnicastAddress::new(
+                            Some(7),
+                            Ipv6Unicast::from_net(Ipv6Net::from_str("2001:db8::/32").unwrap())
+                                .unwrap(),
+                        )],
+                    }),
+                )
+                .unwrap(),
+            ],
+            vec![Ipv4UnicastAddress::new_no_path_id(
+                Ipv4Unicast::from_net(Ipv4Net::from_str("203.0.113.0/24").unwrap()).unwrap(),
+            )],
+        );
+        let router = SocketAddr::from(([192, 0, 2, 1], 40000));
+        let events = RouteEvent::from_bmp_message(router, &route_monitoring(update));
+        assert_eq!(events.len(), 3);
+
+        assert_eq!(events[0].action(), RouteAction::Withdraw);
+        assert_eq!(events[0].prefix(), "198.51.100.0/24");
+        assert_eq!(events[0].origin(), None);
+
+        assert_eq!(events[1].action(), RouteAction::Withdraw);
+        assert_eq!(events[1].address_type(), Some(AddressType::Ipv6Unicast));
+        assert_eq!(events[1].prefix(), "2001:db8::/32");
+        assert_eq!(events[1].path_id(), Some(7));
+
+        let announce = &events[2];
+        assert_eq!(announce.action(), RouteAction::Announce);
+        assert_eq!(announce.router(), router);
+        assert_eq!(announce.peer_as(), 65001);
+        assert_eq!(announce.prefix(), "203.0.113.0/24");
+        assert_eq!(
+            announce.next_hop(),
+            Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)))
+        );
+        assert_eq!(announce.origin(), Some(Origin::IGP));
+        assert_eq!(announce.as_path(), &vec![65001, 3356]);
+        assert_eq!(announce.communities(), &vec!["65001:100".to_string()]);
+        assert_eq!(announce.local_pref(), Some(200));
+        assert_eq!(announce.med(), None);
+    }
+
+    #[test]
+    fn test_schema_versions() {
+        let update = BgpUpdateMessage::new(
+            vec![Ipv4UnicastAddress::new_no_path_id(
+                Ipv4Unicast::from_net(Ipv4Net::from_str("198.51.100.0/24").unwrap()).unwrap(),
+            )],
+            vec![],
+            vec![],
+        );
+        let router = SocketAddr::from(([192, 0, 2, 1], 40000));
+        let event = RouteEvent::from_bmp_message(router, &route_monitoring(update)).remove(0);
+        let v1 = event.to_json(SchemaVersion::V1).unwrap();
+        let v2 = event.to_json(SchemaVersion::V2).unwrap();
+        assert_eq!(v1.get(SCHEMA_VERSION_FIELD), None);
+        assert_eq!(v2[SCHEMA_VERSION_FIELD], serde_json::json!(2));
+        assert_eq!(RouteEvent::from_json(v1), Ok(event.clone()));
+        assert_eq!(RouteEvent::from_json(v2.clone()), Ok(event));
+
+        let mut v3 = v2;
+        v3[SCHEMA_VERSION_FIELD] = serde_json::json!("3");
+        assert_eq!(
+            RouteEvent::from_json(v3),
+            Err(RouteEventSinkError::UnsupportedSchemaVersion(
+                serde_json::json!("3")
+            ))
+        );
+    }
+
+    #[test]
+    fn test_idempotency_key() {
+        let update = BgpUpdateMessage::new(
+            vec![
+                Ipv4UnicastAddress::new_no_path_id(
+                    Ipv4Unicast::from_net(Ipv4Net::from_str("198.51.100.0/24").unwrap()).unwrap(),
+                ),
+                Ipv4UnicastAddress::new_no_path_id(
+                    Ipv4Unicast::from_net(Ipv4Net::from_str("203.0.113.0/24").unwrap()).unwrap(),
+                ),
+            ],
+            vec![],
+            vec![],
+        );
+        let router = SocketAddr::from(([192, 0, 2, 1], 40000));
+        let mut events = RouteEvent::from_bmp_message(router, &route_monitoring(update));
+        let key = events[0].idempotency_key().unwrap();
+        assert_eq!(key.len(), 32);
+        assert_ne!(key, events[1].idempotency_key().unwrap());
+
+        let v1 = RouteEvent::from_json(events[0].to_json(SchemaVersion::V1).unwrap()).unwrap();
+        let v2 = RouteEvent::from_json(events[0].to_json(SchemaVersion::V2).unwrap()).unwrap();
+        assert_eq!(v1.idempotency_key().unwrap(), key);
+        assert_eq!(v2.idempotency_key().unwrap(), key);
+
+        events[0].vrf = Some("blue".to_string());
+        assert_eq!(events[0].idempotency_key().unwrap(), key);
+        events[0].router = SocketAddr::from(([192, 0, 2, 1], 40001));
+        assert_eq!(events[0].idempotency_key().unwrap(), key);
+        events[0].router = SocketAddr::from(([192, 0, 2, 2], 40000));
+        assert_ne!(events[0].idempotency_key().unwrap(), key);
+        events[0].router = router;
+        events[0].peer_as = 65002;
+        assert_ne!(events[0].idempotency_key().unwrap(), key);
+    }
+
+    #[test]
+    fn test_vrf_names() {
+        let router = SocketAddr::from(([192, 0, 2, 1], 40000));
+        let rd = |number| RouteDistinguisher::As2Administrator {
+            asn2: 65001,
+            number,
+        };
+        let peer_header = |number| {
+            PeerHeader::new(
+                BmpPeerType::LocRibInstancePeer { filtered: false },
+                Some(rd(number)),
+                None,
+                65001,
+                Ipv4Addr::new(192, 0, 2, 1),
+                None,
+            )
+        };
+        let withdraw = |number| {
+            BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+                RouteMonitoringMessage::build(
+                    peer_header(number),
+                    BgpMessage::Update(BgpUpdateMessage::new(
+                        vec![Ipv4UnicastAddress::new_no_path_id(
+                            Ipv4Unicast::from_net(Ipv4Net::from_str("198.51.100.0/24").unwrap())
+                                .unwrap(),
+                        )],
+                        vec![],
+                        vec![],
+                    )),
+                )
+                .unwrap(),
+            ))
+        };
+        let open = BgpMessage::Open(BgpOpenMessage::new(65001, 180, Ipv4Addr::LOCALHOST, vec![]));
+        let peer_up = BmpMessage::V3(BmpMessageValue::PeerUpNotification(
+            PeerUpNotificationMessage::build(
+                peer_header(10),
+                None,
+                None,
+                None,
+                open.clone(),
+                open,
+                vec![InitiationInformation::VrfTableName("blue".to_string())],
+            )
+            .unwrap(),
+        ));
+
+        let mut vrf_names = VrfNames::new().with_vrf(rd(20), "red".to_string());
+        vrf_names.observe_bmp_message(router, &peer_up);
+        let mut events = [
+            RouteEvent::from_bmp_message(router, &withdraw(10)),
+            RouteEvent::from_bmp_message(router, &withdraw(20)),
+            RouteEvent::from_bmp_message(router, &withdraw(30)),
+        ]
+        .concat();
+        vrf_names.label(&mut events);
+        assert_eq!(
+            events.iter().map(|event| event.vrf()).collect::<Vec<_>>(),
+            vec![Some("blue"), Some("red"), None]
+        );
+
+        // The previous schema version doesn't have the VRF name
+        assert_eq!(
+            events[0].to_json(SchemaVersion::V2).unwrap()["vrf"],
+            serde_json::json!("blue")
+        );
+        assert_eq!(
+            events[0].to_json(SchemaVersion::V1).unwrap().get("vrf"),
+            None
+        );
+
+        vrf_names.observe_bmp_message(
+            router,
+            &BmpMessage::V3(BmpMessageValue::Termination(TerminationMessage::new(
+                peer_header(10),
+                vec![],
+            ))),
+        );
+        assert_eq!(vrf_names.vrf_name(router.ip(), rd(10)), None);
+        assert_eq!(vrf_names.vrf_name(router.ip(), rd(20)), Some("red"));
+    }
+}
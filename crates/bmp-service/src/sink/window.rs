@@ -0,0 +1,437 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Event-time windowing of [`RouteEvent`]s.
+//!
+//! Events of different routers are delivered out of order, hence aggregations
+//! such as the number of updates per minute can't be computed on arrival
+//! time. [`Watermark`] tracks the progress of the event time of each router,
+//! using the timestamps of the BMP peer headers, and [`WindowedAggregator`]
+//! only emits a [`ClosedWindow`] once the watermark passed its end.
+//!
+//! Each window is emitted exactly once: events that arrive after their window
+//! was emitted are rejected and counted as late in the [`WindowStats`]
+//! instead of updating an aggregate that was already sent downstream. With
+//! [`WindowedAggregator::with_deduplication`], events delivered more than once
+//! are only aggregated once, see [`RouteEvent::idempotency_key`].
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    hash::Hash,
+    net::SocketAddr,
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::sink::RouteEvent;
+
+/// Event time up to which all the events are expected to have been received.
+///
+/// The event time of each router is the latest timestamp of its events, the
+/// watermark is the earliest of them minus the allowed lateness, so a router
+/// lagging behind holds back the watermark. Routers that terminated their
+/// session should be removed with [`Self::remove_router`] to not hold it
+/// back forever.
+#[derive(Debug, Clone)]
+pub struct Watermark {
+    allowed_lateness: TimeDelta,
+    routers: HashMap<SocketAddr, DateTime<Utc>>,
+}
+
+impl Watermark {
+    pub fn new(allowed_lateness: Duration) -> Self {
+        Self {
+            allowed_lateness: TimeDelta::from_std(allowed_lateness).unwrap_or(TimeDelta::MAX),
+            routers: HashMap::new(),
+        }
+    }
+
+    pub fn allowed_lateness(&self) -> Duration {
+        self.allowed_lateness.to_std().unwrap_or_default()
+    }
+
+    /// Advance the event time of `router`, earlier timestamps are ignored
+    pub fn observe(&mut self, router: SocketAddr, timestamp: DateTime<Utc>) {
+        let event_time = self.routers.entry(router).or_insert(timestamp);
+        if timestamp > *event_time {
+            *event_time = timestamp;
+        }
+    }
+
+    pub fn remove_router(&mut self, router: SocketAddr) {
+        self.routers.remove(&router);
+    }
+
+    /// Latest timestamp seen from `router`
+    pub fn router_event_time(&self, router: SocketAddr) -> Option<DateTime<Utc>> {
+        self.routers.get(&router).copied()
+    }
+
+    /// Current watermark, `None` until an event is observed
+    pub fn current(&self) -> Option<DateTime<Utc>> {
+        let event_time = self.routers.values().min()?;
+        Some(
+            event_time
+                .checked_sub_signed(self.allowed_lateness)
+                .unwrap_or(DateTime::<Utc>::MIN_UTC),
+        )
+    }
+}
+
+/// Counters of the events rejected by a [`WindowedAggregator`]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowStats {
+    /// Events aggregated in a window
+    pub accepted: u64,
+
+    /// Events of windows that were already emitted
+    pub late: u64,
+
+    /// Events without a timestamp in their peer header
+    pub untimed: u64,
+
+    /// Events already aggregated, only counted with deduplication enabled
+    pub duplicates: u64,
+}
+
+/// Aggregates of a window that the watermark passed
+#[derive(Debug, Clone)]
+pub struct ClosedWindow<K, V> {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    aggregates: HashMap<K, V>,
+}
+
+impl<K, V> ClosedWindow<K, V> {
+    /// Start of the window, inclusive
+    pub const fn start(&self) -> DateTime<Utc> {
+        self.start
+    }
+
+    /// End of the window, exclusive
+    pub const fn end(&self) -> DateTime<Utc> {
+        self.end
+    }
+
+    pub const fn aggregates(&self) -> &HashMap<K, V> {
+        &self.aggregates
+    }
+
+    pub fn into_aggregates(self) -> HashMap<K, V> {
+        self.aggregates
+    }
+}
+
+#[derive(Debug, Clone)]
+struct OpenWindow<K, V> {
+    aggregates: HashMap<K, V>,
+    idempotency_keys: HashSet<String>,
+}
+
+impl<K, V> Default for OpenWindow<K, V> {
+    fn default() -> Self {
+        Self {
+            aggregates: HashMap::new(),
+            idempotency_keys: HashSet::new(),
+        }
+    }
+}
+
+/// Aggregates [`RouteEvent`]s by key in tumbling windows of event time, see
+/// the [module documentation](self).
+///
+/// Windows are aligned on the UNIX epoch, e.g., windows of one minute start on
+/// the minute.
+#[derive(Debug, Clone)]
+pub struct WindowedAggregator<K, V> {
+    size: TimeDelta,
+    watermark: Watermark,
+    deduplication: bool,
+    windows: BTreeMap<DateTime<Utc>, OpenWindow<K, V>>,
+    /// End of the last emitted window, events before it are late
+    emitted_until: Option<DateTime<Utc>>,
+    stats: WindowStats,
+}
+
+impl<K: Eq + Hash, V: Default> WindowedAggregator<K, V> {
+    /// Create an aggregator with windows of `size` that waits for events up to
+    /// `allowed_lateness` behind the event time of the slowest router.
+    ///
+    /// Panics when `size` is shorter than a millisecond.
+    pub fn new(size: Duration, allowed_lateness: Duration) -> Self {
+        let size = TimeDelta::from_std(size).unwrap_or(TimeDelta::MAX);
+        assert!(
+            size.num_milliseconds() > 0,
+            "window size must be at least a millisecond"
+        );
+        Self {
+            size,
+            watermark: Watermark::new(allowed_lateness),
+            deduplication: false,
+            windows: BTreeMap::new(),
+            emitted_until: None,
+            stats: WindowStats::default(),
+        }
+    }
+
+    /// Aggregate events with the same [`RouteEvent::idempotency_key`] only
+    /// once, e.g., when a router replays its routes after a reconnection
+    pub fn with_deduplication(mut self) -> Self {
+        self.deduplication = true;
+        self
+    }
+
+    pub fn size(&self) -> Duration {
+        self.size.to_std().unwrap_or_default()
+    }
+
+    pub const fn watermark(&self) -> &Watermark {
+        &self.watermark
+    }
+
+    pub const fn stats(&self) -> WindowStats {
+        self.stats
+    }
+
+    /// Number of windows waiting for the watermark
+    pub fn open_windows(&self) -> usize {
+        self.windows.len()
+    }
+
+    /// Start of the window `timestamp` belongs to
+    pub fn window_start(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        let size = self.size.num_milliseconds();
+        let start = timestamp.timestamp_millis().div_euclid(size) * size;
+        DateTime::<Utc>::from_timestamp_millis(start).unwrap_or(DateTime::<Utc>::MIN_UTC)
+    }
+
+    /// Apply `update` to the aggregate of `key` in the window of the event,
+    /// and advance the watermark with its timestamp. Returns `false` when the
+    /// event is rejected, see [`WindowStats`].
+    pub fn observe(&mut self, event: &RouteEvent, key: K, update: impl FnOnce(&mut V)) -> bool {
+        let Some(timestamp) = event.timestamp() else {
+            self.stats.untimed += 1;
+            return false;
+        };
+        let start = self.window_start(timestamp);
+        if self
+            .emitted_until
+            .is_some_and(|emitted_until| start < emitted_until)
+        {
+            self.stats.late += 1;
+            return false;
+        }
+        let window = self.windows.entry(start).or_default();
+        if self.deduplication {
+            // Events that can't be serialized are aggregated anyway
+            if let Ok(idempotency_key) = event.idempotency_key() {
+                if !window.idempotency_keys.insert(idempotency_key) {
+                    self.stats.duplicates += 1;
+                    return false;
+                }
+            }
+        }
+        update(window.aggregates.entry(key).or_default());
+        self.stats.accepted += 1;
+        self.watermark.observe(event.router(), timestamp);
+        true
+    }
+
+    /// Forget the event time of `router`, usually when its BMP session is
+    /// terminated, so it doesn't hold back the watermark
+    pub fn remove_router(&mut self, router: SocketAddr) {
+        self.watermark.remove_router(router);
+    }
+
+    /// Emit the windows that ended before the current watermark, in order
+    pub fn close_windows(&mut self) -> Vec<ClosedWindow<K, V>> {
+        match self.watermark.current() {
+            Some(watermark) => self.close_until(Some(watermark)),
+            None => vec![],
+        }
+    }
+
+    /// Emit all the open windows regardless of the watermark, e.g., on
+    /// shutdown. Events of these windows received afterward are late.
+    pub fn flush(&mut self) -> Vec<ClosedWindow<K, V>> {
+        self.close_until(None)
+    }
+
+    /// Emit the windows that ended before `watermark`, or all of them
+    fn close_until(&mut self, watermark: Option<DateTime<Utc>>) -> Vec<ClosedWindow<K, V>> {
+        let mut closed = vec![];
+        while let Some(entry) = self.windows.first_entry() {
+            let start = *entry.key();
+            let end = start
+                .checked_add_signed(self.size)
+                .unwrap_or(DateTime::<Utc>::MAX_UTC);
+            if watermark.is_some_and(|watermark| end > watermark) {
+                break;
+            }
+            let window = entry.remove();
+            self.emitted_until = Some(end);
+            closed.push(ClosedWindow {
+                start,
+                end,
+                aggregates: window.aggregates,
+            });
+        }
+        closed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use ipnet::Ipv4Net;
+    use netgauze_bgp_pkt::{
+        nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+        update::BgpUpdateMessage,
+        BgpMessage,
+    };
+    use netgauze_bmp_pkt::{
+        BmpMessage, BmpMessageValue, BmpPeerType, PeerHeader, RouteMonitoringMessage,
+    };
+    use std::{net::Ipv4Addr, str::FromStr};
+
+    fn withdraw(router: &str, timestamp: Option<DateTime<Utc>>) -> RouteEvent {
+        let update = BgpUpdateMessage::new(
+            vec![Ipv4UnicastAddress::new_no_path_id(
+                Ipv4Unicast::from_net(Ipv4Net::from_str("198.51.100.0/24").unwrap()).unwrap(),
+            )],
+            vec![],
+            vec![],
+        );
+        let msg = BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            RouteMonitoringMessage::build(
+                PeerHeader::new(
+                    BmpPeerType::GlobalInstancePeer {
+                        ipv6: false,
+                        post_policy: false,
+                        asn2: false,
+                        adj_rib_out: false,
+                    },
+                    None,
+                    None,
+                    65000,
+                    Ipv4Addr::new(192, 0, 2, 1),
+                    timestamp,
+                ),
+                BgpMessage::Update(update),
+            )
+            .unwrap(),
+        ));
+        RouteEvent::from_bmp_message(router.parse().unwrap(), &msg).remove(0)
+    }
+
+    fn at(minute: u32, second: u32) -> Option<DateTime<Utc>> {
+        Some(
+            Utc.with_ymd_and_hms(2024, 3, 28, 13, minute, second)
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_watermark() {
+        let first: SocketAddr = "192.0.2.1:1790".parse().unwrap();
+        let second: SocketAddr = "192.0.2.2:1790".parse().unwrap();
+        let mut watermark = Watermark::new(Duration::from_secs(10));
+        assert_eq!(watermark.current(), None);
+
+        watermark.observe(first, at(5, 0).unwrap());
+        assert_eq!(watermark.current(), at(4, 50));
+        watermark.observe(second, at(2, 0).unwrap());
+        assert_eq!(watermark.current(), at(1, 50));
+        // Out of order events don't move the watermark back
+        watermark.observe(second, at(1, 0).unwrap());
+        assert_eq!(watermark.router_event_time(second), at(2, 0));
+        watermark.remove_router(second);
+        assert_eq!(watermark.current(), at(4, 50));
+    }
+
+    #[test]
+    fn test_windowed_counts() {
+        let mut counts = WindowedAggregator::<SocketAddr, u64>::new(
+            Duration::from_secs(60),
+            Duration::from_secs(10),
+        );
+        let count = |counts: &mut WindowedAggregator<SocketAddr, u64>, event: RouteEvent| {
+            counts.observe(&event, event.router(), |count| *count += 1)
+        };
+        let first = "192.0.2.1:1790";
+        let second = "192.0.2.2:1790";
+
+        assert!(count(&mut counts, withdraw(first, at(0, 10))));
+        assert!(count(&mut counts, withdraw(first, at(1, 5))));
+        assert!(count(&mut counts, withdraw(first, at(2, 30))));
+        // The second router lags behind and holds back the watermark
+        assert!(count(&mut counts, withdraw(second, at(0, 20))));
+        assert!(counts.close_windows().is_empty());
+        assert!(count(&mut counts, withdraw(second, at(0, 50))));
+        assert!(count(&mut counts, withdraw(second, at(1, 30))));
+
+        let closed = counts.close_windows();
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].start(), at(0, 0).unwrap());
+        assert_eq!(closed[0].end(), at(1, 0).unwrap());
+        assert_eq!(
+            closed[0].aggregates(),
+            &HashMap::from([(first.parse().unwrap(), 1), (second.parse().unwrap(), 2)])
+        );
+
+        // The first window was emitted, its events are late now
+        assert!(!count(&mut counts, withdraw(second, at(0, 55))));
+        assert!(!count(&mut counts, withdraw(second, None)));
+        assert_eq!(
+            counts.stats(),
+            WindowStats {
+                accepted: 6,
+                late: 1,
+                untimed: 1,
+                duplicates: 0,
+            }
+        );
+
+        let flushed = counts.flush();
+        assert_eq!(
+            flushed
+                .iter()
+                .map(|window| window.start())
+                .collect::<Vec<_>>(),
+            vec![at(1, 0).unwrap(), at(2, 0).unwrap()]
+        );
+        assert_eq!(counts.open_windows(), 0);
+    }
+
+    #[test]
+    fn test_windowed_deduplication() {
+        let mut counts =
+            WindowedAggregator::<(), u64>::new(Duration::from_secs(60), Duration::ZERO)
+                .with_deduplication();
+        let event = withdraw("192.0.2.1:1790", at(0, 10));
+        assert!(counts.observe(&event, (), |count| *count += 1));
+        assert!(!counts.observe(&event, (), |count| *count += 1));
+        assert!(
+            counts.observe(&withdraw("192.0.2.1:1790", at(0, 20)), (), |count| {
+                *count += 1
+            })
+        );
+        assert_eq!(counts.stats().duplicates, 1);
+        assert_eq!(counts.flush()[0].aggregates(), &HashMap::from([((), 2)]));
+    }
+}
@@ -0,0 +1,419 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deltas and rates between consecutive BMP Statistics Reports.
+//!
+//! Statistics Reports carry absolute values, [`StatisticsTracker`] keeps the
+//! last value of each statistic per monitored peer and computes what changed
+//! since the previous report of the same peer, e.g., to plot the number of
+//! prefixes rejected by inbound policy per minute.
+
+use std::{collections::HashMap, net::IpAddr, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use netgauze_bmp_pkt::{
+    iana::BmpStatisticsType, BmpMessage, BmpMessageValue, PeerHeader, PeerKey, PeerKeyStrategy,
+    StatisticsCounter, StatisticsReportMessage,
+};
+use netgauze_iana::address_family::AddressType;
+
+/// Identifies a statistic within the reports of a peer, per AFI/SAFI
+/// statistics are told apart by their address type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StatisticsKey {
+    stat_type: BmpStatisticsType,
+    address_type: Option<AddressType>,
+}
+
+impl StatisticsKey {
+    pub const fn new(stat_type: BmpStatisticsType, address_type: Option<AddressType>) -> Self {
+        Self {
+            stat_type,
+            address_type,
+        }
+    }
+
+    pub const fn stat_type(&self) -> BmpStatisticsType {
+        self.stat_type
+    }
+
+    pub const fn address_type(&self) -> Option<AddressType> {
+        self.address_type
+    }
+}
+
+/// How the values of a statistic evolve, see
+/// [RFC7854](https://datatracker.ietf.org/doc/html/rfc7854#section-4.8)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StatisticsKind {
+    /// Monotonically increasing 32-bit counter
+    Counter,
+    /// Instantaneous 64-bit value
+    Gauge,
+}
+
+/// Change of a statistic between two consecutive reports of a peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatisticsDelta {
+    key: StatisticsKey,
+    kind: StatisticsKind,
+    previous: u64,
+    value: u64,
+    delta: i64,
+    interval: Duration,
+    reset: bool,
+}
+
+impl StatisticsDelta {
+    pub const fn key(&self) -> StatisticsKey {
+        self.key
+    }
+
+    pub const fn kind(&self) -> StatisticsKind {
+        self.kind
+    }
+
+    /// Value in the previous report
+    pub const fn previous(&self) -> u64 {
+        self.previous
+    }
+
+    /// Value in the current report
+    pub const fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// Increase of a counter, or the change of a gauge which can be negative.
+    /// After a counter reset, it's the value of the counter since the reset.
+    pub const fn delta(&self) -> i64 {
+        self.delta
+    }
+
+    /// Time between the two reports
+    pub const fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// The counter went backwards, hence was reset since the previous report.
+    /// A 32-bit wrap around can't be told apart from a reset and is reported
+    /// as such.
+    pub const fn reset(&self) -> bool {
+        self.reset
+    }
+
+    /// Delta per second, `None` when both reports have the same timestamp
+    pub fn rate_per_second(&self) -> Option<f64> {
+        if self.interval.is_zero() {
+            None
+        } else {
+            Some(self.delta as f64 / self.interval.as_secs_f64())
+        }
+    }
+
+    /// Delta per minute, `None` when both reports have the same timestamp
+    pub fn rate_per_minute(&self) -> Option<f64> {
+        self.rate_per_second().map(|rate| rate * 60.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Sample {
+    value: u64,
+    time: DateTime<Utc>,
+}
+
+/// Last reported statistics of each monitored peer of each router.
+///
+/// The report time is the timestamp of the per-peer header, or the receive
+/// time when the router doesn't set it. The state of a peer is dropped on
+/// Peer Down, so the counters of a new session start afresh.
+#[derive(Debug, Clone, Default)]
+pub struct StatisticsTracker {
+    peer_key_strategy: PeerKeyStrategy,
+    peers: HashMap<(IpAddr, PeerKey), HashMap<StatisticsKey, Sample>>,
+}
+
+impl StatisticsTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How the monitored peers of each router are told apart
+    pub fn with_peer_key_strategy(mut self, peer_key_strategy: PeerKeyStrategy) -> Self {
+        self.peer_key_strategy = peer_key_strategy;
+        self
+    }
+
+    pub const fn peer_key_strategy(&self) -> PeerKeyStrategy {
+        self.peer_key_strategy
+    }
+
+    /// Update the tracker with a BMP message received from `router` at
+    /// `received`, returns the deltas of the statistics when it's a
+    /// Statistics Report.
+    ///
+    /// Peer Down messages drop the state of the peer, and Termination
+    /// messages the state of all the peers of the router. When the BMP
+    /// session is closed without a Termination message,
+    /// [`StatisticsTracker::remove_router`] should be called.
+    pub fn apply(
+        &mut self,
+        router: IpAddr,
+        msg: &BmpMessage,
+        received: DateTime<Utc>,
+    ) -> Vec<StatisticsDelta> {
+        let value = msg.to_v3_value();
+        match value.as_ref() {
+            BmpMessageValue::StatisticsReport(report) => self.update(router, report, received),
+            BmpMessageValue::PeerDownNotification(peer_down) => {
+                let peer = self.peer_key(peer_down.peer_header());
+                self.remove_peer(router, peer);
+                vec![]
+            }
+            BmpMessageValue::Termination(_) => {
+                self.remove_router(router);
+                vec![]
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Record a Statistics Report and return the deltas of the statistics
+    /// already present in the previous report of the peer. Experimental and
+    /// unknown statistics are ignored.
+    pub fn update(
+        &mut self,
+        router: IpAddr,
+        report: &StatisticsReportMessage,
+        received: DateTime<Utc>,
+    ) -> Vec<StatisticsDelta> {
+        let time = report
+            .peer_header()
+            .timestamp()
+            .copied()
+            .unwrap_or(received);
+        let peer = self.peer_key(report.peer_header());
+        let samples = self.peers.entry((router, peer)).or_default();
+        let mut deltas = vec![];
+        for counter in report.counters() {
+            let (key, kind, value) = match sample(counter) {
+                Some(sample) => sample,
+                None => continue,
+            };
+            let previous = match samples.insert(key, Sample { value, time }) {
+                Some(previous) => previous,
+                None => continue,
+            };
+            let (delta, reset) = match kind {
+                StatisticsKind::Counter if value < previous.value => (value as i64, true),
+                StatisticsKind::Counter => ((value - previous.value) as i64, false),
+                StatisticsKind::Gauge => {
+                    let delta = (value as i128 - previous.value as i128)
+                        .clamp(i64::MIN as i128, i64::MAX as i128);
+                    (delta as i64, false)
+                }
+            };
+            deltas.push(StatisticsDelta {
+                key,
+                kind,
+                previous: previous.value,
+                value,
+                delta,
+                interval: (time - previous.time).to_std().unwrap_or_default(),
+                reset,
+            });
+        }
+        deltas
+    }
+
+    fn peer_key(&self, header: &PeerHeader) -> PeerKey {
+        self.peer_key_strategy
+            .apply(PeerKey::from_peer_header(header))
+    }
+
+    /// Drop the state of a peer of `router`, returns if it was tracked
+    pub fn remove_peer(&mut self, router: IpAddr, peer: PeerKey) -> bool {
+        self.peers.remove(&(router, peer)).is_some()
+    }
+
+    /// Drop the state of all the peers of `router`, returns the number of
+    /// removed peers
+    pub fn remove_router(&mut self, router: IpAddr) -> usize {
+        let before = self.peers.len();
+        self.peers
+            .retain(|(peer_router, _), _| *peer_router != router);
+        before - self.peers.len()
+    }
+
+    /// Number of tracked peers
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+}
+
+fn sample(counter: &StatisticsCounter) -> Option<(StatisticsKey, StatisticsKind, u64)> {
+    let stat_type = counter.get_type().ok()?;
+    let (address_type, kind, value) = match counter {
+        StatisticsCounter::NumberOfPrefixesRejectedByInboundPolicy(value)
+        | StatisticsCounter::NumberOfDuplicatePrefixAdvertisements(value)
+        | StatisticsCounter::NumberOfDuplicateWithdraws(value)
+        | StatisticsCounter::NumberOfUpdatesInvalidatedDueToClusterListLoop(value)
+        | StatisticsCounter::NumberOfUpdatesInvalidatedDueToAsPathLoop(value)
+        | StatisticsCounter::NumberOfUpdatesInvalidatedDueToOriginatorId(value)
+        | StatisticsCounter::NumberOfUpdatesInvalidatedDueToAsConfederationLoop(value)
+        | StatisticsCounter::NumberOfUpdatesSubjectedToTreatAsWithdraw(value)
+        | StatisticsCounter::NumberOfPrefixesSubjectedToTreatAsWithdraw(value)
+        | StatisticsCounter::NumberOfDuplicateUpdateMessagesReceived(value) => {
+            (None, StatisticsKind::Counter, u64::from(value.value()))
+        }
+        StatisticsCounter::NumberOfRoutesInAdjRibIn(value)
+        | StatisticsCounter::NumberOfRoutesInLocRib(value)
+        | StatisticsCounter::NumberOfRoutesInPrePolicyAdjRibOut(value)
+        | StatisticsCounter::NumberOfRoutesInPostPolicyAdjRibOut(value) => {
+            (None, StatisticsKind::Gauge, value.value())
+        }
+        StatisticsCounter::NumberOfRoutesInPerAfiSafiAdjRibIn(address_type, value)
+        | StatisticsCounter::NumberOfRoutesInPerAfiSafiLocRib(address_type, value)
+        | StatisticsCounter::NumberOfRoutesInPerAfiSafiPrePolicyAdjRibOut(address_type, value)
+        | StatisticsCounter::NumberOfRoutesInPerAfiSafiPostPolicyAdjRibOut(address_type, value) => {
+            (Some(*address_type), StatisticsKind::Gauge, value.value())
+        }
+        StatisticsCounter::Experimental65531(_)
+        | StatisticsCounter::Experimental65532(_)
+        | StatisticsCounter::Experimental65533(_)
+        | StatisticsCounter::Experimental65534(_)
+        | StatisticsCounter::Unknown(_, _) => return None,
+    };
+    Some((StatisticsKey::new(stat_type, address_type), kind, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use netgauze_bmp_pkt::{
+        BmpPeerType, CounterU32, GaugeU64, PeerDownNotificationMessage, PeerDownNotificationReason,
+    };
+    use std::net::Ipv4Addr;
+
+    fn peer_header(time: Option<DateTime<Utc>>) -> PeerHeader {
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 1))),
+            64501,
+            Ipv4Addr::new(203, 0, 113, 1),
+            time,
+        )
+    }
+
+    fn report(time: Option<DateTime<Utc>>, rejected: u32, adj_rib_in: u64) -> BmpMessage {
+        BmpMessage::V3(BmpMessageValue::StatisticsReport(
+            StatisticsReportMessage::new(
+                peer_header(time),
+                vec![
+                    StatisticsCounter::NumberOfPrefixesRejectedByInboundPolicy(CounterU32::new(
+                        rejected,
+                    )),
+                    StatisticsCounter::NumberOfRoutesInPerAfiSafiAdjRibIn(
+                        AddressType::Ipv4Unicast,
+                        GaugeU64::new(adj_rib_in),
+                    ),
+                    StatisticsCounter::Experimental65531(vec![1, 2, 3]),
+                ],
+            ),
+        ))
+    }
+
+    #[test]
+    fn test_statistics_tracker() {
+        let router = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let t0 = Utc.timestamp_opt(1680000000, 0).unwrap();
+        let t1 = Utc.timestamp_opt(1680000120, 0).unwrap();
+        let t2 = Utc.timestamp_opt(1680000180, 0).unwrap();
+        let rejected = StatisticsKey::new(
+            BmpStatisticsType::NumberOfPrefixesRejectedByInboundPolicy,
+            None,
+        );
+        let adj_rib_in = StatisticsKey::new(
+            BmpStatisticsType::NumberOfRoutesInPerAfiSafiAdjRibIn,
+            Some(AddressType::Ipv4Unicast),
+        );
+        let mut tracker = StatisticsTracker::new();
+
+        // Nothing to compare the first report with
+        assert_eq!(
+            tracker.apply(router, &report(Some(t0), 10, 100), t0),
+            vec![]
+        );
+
+        let deltas = tracker.apply(router, &report(Some(t1), 40, 90), t1);
+        assert_eq!(
+            deltas,
+            vec![
+                StatisticsDelta {
+                    key: rejected,
+                    kind: StatisticsKind::Counter,
+                    previous: 10,
+                    value: 40,
+                    delta: 30,
+                    interval: Duration::from_secs(120),
+                    reset: false,
+                },
+                StatisticsDelta {
+                    key: adj_rib_in,
+                    kind: StatisticsKind::Gauge,
+                    previous: 100,
+                    value: 90,
+                    delta: -10,
+                    interval: Duration::from_secs(120),
+                    reset: false,
+                },
+            ]
+        );
+        assert_eq!(deltas[0].rate_per_minute(), Some(15.0));
+
+        // Counter reset, and the receive time is used without a timestamp
+        let deltas = tracker.apply(router, &report(None, 3, 90), t2);
+        assert_eq!(deltas[0].delta(), 3);
+        assert!(deltas[0].reset());
+        assert_eq!(deltas[0].interval(), Duration::from_secs(60));
+        assert_eq!(deltas[1].rate_per_second(), Some(0.0));
+
+        // Peer down drops the state of the peer
+        let peer_down = BmpMessage::V3(BmpMessageValue::PeerDownNotification(
+            PeerDownNotificationMessage::build(
+                peer_header(Some(t2)),
+                PeerDownNotificationReason::RemoteSystemClosedNoData,
+            )
+            .unwrap(),
+        ));
+        assert_eq!(tracker.apply(router, &peer_down, t2), vec![]);
+        assert!(tracker.is_empty());
+        assert_eq!(tracker.apply(router, &report(Some(t2), 5, 0), t2), vec![]);
+        assert_eq!(tracker.len(), 1);
+    }
+}
@@ -0,0 +1,305 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Periodic BMP Statistics Reports on the exporter side.
+//!
+//! Router simulators and test harnesses register the monitored peers with a
+//! [`StatisticsReportScheduler`], each with a function returning the current
+//! values of its statistics. The scheduler emits a Statistics Report for each
+//! peer every interval. A random jitter is added to every interval, so the
+//! reports of many peers are spread out instead of reaching the collector all
+//! at once.
+//!
+//! ```no_run
+//! use futures_util::SinkExt;
+//! use netgauze_bmp_pkt::{
+//!     codec::BmpCodec, BmpPeerType, CounterU32, PeerHeader, StatisticsCounter,
+//! };
+//! use netgauze_bmp_service::stats_scheduler::StatisticsReportScheduler;
+//! use std::{net::Ipv4Addr, time::Duration};
+//! use tokio::net::TcpStream;
+//! use tokio_util::codec::Framed;
+//!
+//! # async fn run() {
+//! let stream = TcpStream::connect("127.0.0.1:1790").await.unwrap();
+//! let mut client = Framed::new(stream, BmpCodec::default());
+//! let mut scheduler = StatisticsReportScheduler::new(Duration::from_secs(60))
+//!     .with_jitter(Duration::from_secs(5));
+//! let peer_type = BmpPeerType::GlobalInstancePeer {
+//!     ipv6: false,
+//!     post_policy: false,
+//!     asn2: false,
+//!     adj_rib_out: false,
+//! };
+//! let peer_header = PeerHeader::new(
+//!     peer_type,
+//!     None,
+//!     Some(Ipv4Addr::new(192, 0, 2, 1).into()),
+//!     65001,
+//!     Ipv4Addr::new(192, 0, 2, 1),
+//!     None,
+//! );
+//! scheduler.register(peer_header, || {
+//!     vec![StatisticsCounter::NumberOfPrefixesRejectedByInboundPolicy(
+//!         CounterU32::new(0),
+//!     )]
+//! });
+//! while let Some(msg) = scheduler.next_report().await {
+//!     client.send(msg).await.unwrap();
+//! }
+//! # }
+//! ```
+
+use std::{collections::HashMap, time::Duration};
+
+use chrono::{SubsecRound, Utc};
+use rand::Rng;
+use tokio::time::Instant;
+
+use netgauze_bmp_pkt::{
+    BmpMessage, BmpMessageValue, PeerHeader, PeerKey, StatisticsCounter, StatisticsReportMessage,
+};
+
+/// Returns the current values of the statistics of a peer
+pub type StatisticsCountersFn = Box<dyn FnMut() -> Vec<StatisticsCounter> + Send>;
+
+struct ScheduledPeer {
+    peer_header: PeerHeader,
+    counters: StatisticsCountersFn,
+    next_report: Instant,
+}
+
+/// Emit a Statistics Report for each of the registered peers every interval,
+/// see the [module documentation](self)
+pub struct StatisticsReportScheduler {
+    interval: Duration,
+    jitter: Duration,
+    peers: HashMap<PeerKey, ScheduledPeer>,
+}
+
+impl std::fmt::Debug for StatisticsReportScheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StatisticsReportScheduler")
+            .field("interval", &self.interval)
+            .field("jitter", &self.jitter)
+            .field("peers", &self.peers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl StatisticsReportScheduler {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            jitter: Duration::ZERO,
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Each interval is randomly shortened or extended by up to `jitter`,
+    /// which is capped at the interval
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter.min(self.interval);
+        self
+    }
+
+    pub const fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub const fn jitter(&self) -> Duration {
+        self.jitter
+    }
+
+    /// Number of registered peers
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    /// Register a peer, the first report is emitted after one interval.
+    /// Registering the same peer again replaces its counters function.
+    ///
+    /// The peer header of the reports is the given `peer_header` with the
+    /// timestamp of the report.
+    pub fn register<F>(&mut self, peer_header: PeerHeader, counters: F)
+    where
+        F: FnMut() -> Vec<StatisticsCounter> + Send + 'static,
+    {
+        let next_report = Instant::now() + self.next_delay();
+        self.peers.insert(
+            PeerKey::from_peer_header(&peer_header),
+            ScheduledPeer {
+                peer_header,
+                counters: Box::new(counters),
+                next_report,
+            },
+        );
+    }
+
+    /// Stop the reports of a peer, e.g., when it goes down. Returns `false`
+    /// if the peer wasn't registered.
+    pub fn unregister(&mut self, peer_header: &PeerHeader) -> bool {
+        self.peers
+            .remove(&PeerKey::from_peer_header(peer_header))
+            .is_some()
+    }
+
+    fn next_delay(&self) -> Duration {
+        if self.jitter.is_zero() {
+            return self.interval;
+        }
+        let jitter = rand::thread_rng().gen_range(Duration::ZERO..=self.jitter * 2);
+        (self.interval + jitter).saturating_sub(self.jitter)
+    }
+
+    /// Wait until the next report is due and return it, or `None` if no peers
+    /// are registered.
+    ///
+    /// The future is cancel safe, so it can be used in `tokio::select!` along
+    /// with the other events of a simulator, e.g., to register new peers.
+    pub async fn next_report(&mut self) -> Option<BmpMessage> {
+        let (key, deadline) = self
+            .peers
+            .iter()
+            .min_by_key(|(_, peer)| peer.next_report)
+            .map(|(key, peer)| (*key, peer.next_report))?;
+        tokio::time::sleep_until(deadline).await;
+        let delay = self.next_delay();
+        let peer = self.peers.get_mut(&key)?;
+        // Schedule from the deadline to not drift, unless the reports are
+        // too late to catch up, e.g., the writer was blocked
+        let now = Instant::now();
+        peer.next_report = if deadline + delay > now {
+            deadline + delay
+        } else {
+            now + delay
+        };
+        let header = &peer.peer_header;
+        let peer_header = PeerHeader::new(
+            header.peer_type(),
+            header.rd(),
+            header.address(),
+            header.peer_as(),
+            header.bgp_id(),
+            Some(Utc::now().trunc_subsecs(6)),
+        );
+        Some(BmpMessage::V3(BmpMessageValue::StatisticsReport(
+            StatisticsReportMessage::new(peer_header, (peer.counters)()),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netgauze_bmp_pkt::{BmpPeerType, CounterU32, GaugeU64};
+    use std::net::Ipv4Addr;
+
+    fn peer_header(peer: u8) -> PeerHeader {
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(Ipv4Addr::new(192, 0, 2, peer).into()),
+            65000 + u32::from(peer),
+            Ipv4Addr::new(192, 0, 2, peer),
+            None,
+        )
+    }
+
+    fn report_peer(msg: &BmpMessage) -> (u32, Vec<StatisticsCounter>) {
+        match msg {
+            BmpMessage::V3(BmpMessageValue::StatisticsReport(report)) => {
+                assert!(report.peer_header().timestamp().is_some());
+                (report.peer_header().peer_as(), report.counters().clone())
+            }
+            _ => panic!("expected a statistics report, got {msg:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_statistics_report_scheduler() {
+        let mut scheduler = StatisticsReportScheduler::new(Duration::from_millis(20));
+        assert_eq!(scheduler.next_report().await, None);
+
+        let mut routes = 0;
+        scheduler.register(peer_header(1), move || {
+            routes += 10;
+            vec![StatisticsCounter::NumberOfRoutesInAdjRibIn(GaugeU64::new(
+                routes,
+            ))]
+        });
+        scheduler.register(peer_header(2), || {
+            vec![StatisticsCounter::NumberOfDuplicateWithdraws(
+                CounterU32::new(1),
+            )]
+        });
+        assert_eq!(scheduler.len(), 2);
+
+        let start = Instant::now();
+        let mut reports = vec![];
+        for _ in 0..4 {
+            reports.push(report_peer(&scheduler.next_report().await.unwrap()));
+        }
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        // Peers registered at the same time are reported in the same round
+        let mut first_round = vec![reports[0].0, reports[1].0];
+        first_round.sort();
+        assert_eq!(first_round, vec![65001, 65002]);
+        let peer1 = reports
+            .iter()
+            .filter(|(asn, _)| *asn == 65001)
+            .map(|(_, counters)| counters.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            peer1,
+            vec![
+                vec![StatisticsCounter::NumberOfRoutesInAdjRibIn(GaugeU64::new(
+                    10
+                ))],
+                vec![StatisticsCounter::NumberOfRoutesInAdjRibIn(GaugeU64::new(
+                    20
+                ))],
+            ]
+        );
+
+        assert!(scheduler.unregister(&peer_header(1)));
+        assert!(!scheduler.unregister(&peer_header(1)));
+        let (asn, _) = report_peer(&scheduler.next_report().await.unwrap());
+        assert_eq!(asn, 65002);
+    }
+
+    #[tokio::test]
+    async fn test_statistics_report_scheduler_jitter() {
+        let interval = Duration::from_millis(20);
+        let jitter = Duration::from_millis(10);
+        let scheduler = StatisticsReportScheduler::new(interval).with_jitter(jitter);
+        for _ in 0..100 {
+            let delay = scheduler.next_delay();
+            assert!(delay >= interval - jitter && delay <= interval + jitter);
+        }
+        // Jitter is capped at the interval
+        let scheduler = StatisticsReportScheduler::new(interval).with_jitter(interval * 2);
+        assert_eq!(scheduler.jitter(), interval);
+    }
+}
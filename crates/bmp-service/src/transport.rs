@@ -57,6 +57,7 @@ use crate::TaggedData;
 use futures_core::{Stream, TryStream};
 use pin_project::pin_project;
 use std::{
+    collections::VecDeque,
     fmt::Debug,
     pin::Pin,
     task::{Context, Poll},
@@ -142,6 +143,107 @@ where
     }
 }
 
+/// Default max number of consecutive items taken from a single source by
+/// [`MultiplexedStream`]
+pub const DEFAULT_MAX_BURST: usize = 32;
+
+/// Merge the streams of multiple sources, e.g., the decoded streams of the
+/// connected routers, into a single stream of `(source id, item)`.
+///
+/// Items are yielded in the order they are received from the sources, up to
+/// a bounded fairness: once a source yielded `max_burst` consecutive items,
+/// the other sources with items ready are served before it again, so a busy
+/// router can't starve the others. A source is dropped once its stream ends,
+/// and the multiplexed stream ends when there are no sources left.
+#[derive(Debug)]
+pub struct MultiplexedStream<Id, S> {
+    /// Sources in the order they're polled, the current one is at the front
+    sources: VecDeque<(Id, S)>,
+    max_burst: usize,
+    /// Number of consecutive items yielded by the current source
+    burst: usize,
+}
+
+impl<Id, S> Default for MultiplexedStream<Id, S> {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_BURST)
+    }
+}
+
+impl<Id, S> MultiplexedStream<Id, S> {
+    /// `max_burst` is clamped to at least one
+    pub fn new(max_burst: usize) -> Self {
+        Self {
+            sources: VecDeque::new(),
+            max_burst: max_burst.max(1),
+            burst: 0,
+        }
+    }
+
+    /// Add the stream of a new source, it's polled after the existing ones
+    pub fn push(&mut self, id: Id, stream: S) {
+        self.sources.push_back((id, stream));
+    }
+
+    pub const fn max_burst(&self) -> usize {
+        self.max_burst
+    }
+
+    /// Number of sources that didn't end yet
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Ids of the sources that didn't end yet
+    pub fn source_ids(&self) -> impl Iterator<Item = &Id> {
+        self.sources.iter().map(|(id, _)| id)
+    }
+
+    /// Make the next source the current one
+    fn rotate(&mut self) {
+        self.sources.rotate_left(1);
+        self.burst = 0;
+    }
+}
+
+impl<Id: Clone + Unpin, S: Stream + Unpin> Stream for MultiplexedStream<Id, S> {
+    type Item = (Id, S::Item);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        // Each source is polled at most once, pending ones register the waker
+        let mut remaining = this.sources.len();
+        while remaining > 0 {
+            remaining -= 1;
+            let (id, stream) = this.sources.front_mut().expect("sources is not empty");
+            match Pin::new(stream).poll_next(cx) {
+                Poll::Ready(Some(item)) => {
+                    let id = id.clone();
+                    this.burst += 1;
+                    if this.burst >= this.max_burst {
+                        this.rotate();
+                    }
+                    return Poll::Ready(Some((id, item)));
+                }
+                Poll::Ready(None) => {
+                    this.sources.pop_front();
+                    this.burst = 0;
+                }
+                Poll::Pending => this.rotate(),
+            }
+        }
+        if this.sources.is_empty() {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -203,9 +305,10 @@ mod tests {
             stream.next().await,
             Some(Err(TaggedData::new(
                 tag,
-                BmpCodecDecoderError::BmpMessageParsingError(
-                    BmpMessageParsingError::InvalidBmpLength(1)
-                )
+                BmpCodecDecoderError::BmpMessageParsingError {
+                    error: BmpMessageParsingError::InvalidBmpLength(1),
+                    frame: None,
+                }
             )))
         );
         assert_eq!(stream.next().await, None);
@@ -213,9 +316,10 @@ mod tests {
             stream.next().await,
             Some(Err(TaggedData::new(
                 tag,
-                BmpCodecDecoderError::BmpMessageParsingError(
-                    BmpMessageParsingError::UndefinedBmpVersion(UndefinedBmpVersion(0xff))
-                )
+                BmpCodecDecoderError::BmpMessageParsingError {
+                    error: BmpMessageParsingError::UndefinedBmpVersion(UndefinedBmpVersion(0xff)),
+                    frame: None,
+                }
             )))
         );
         assert_eq!(stream.next().await, None);
@@ -224,4 +328,33 @@ mod tests {
             Some(Ok(TaggedData::new(tag, good.clone())))
         );
     }
+
+    #[tokio::test]
+    async fn multiplexed() {
+        let mut stream = MultiplexedStream::new(2);
+        stream.push("r1", futures::stream::iter(vec![1, 2, 3, 4, 5]).boxed());
+        stream.push("r2", futures::stream::iter(vec![10, 20]).boxed());
+        stream.push("r3", futures::stream::pending().boxed());
+        assert_eq!(stream.len(), 3);
+
+        let mut received = vec![];
+        for _ in 0..7 {
+            received.push(stream.next().await.unwrap());
+        }
+        assert_eq!(
+            received,
+            vec![
+                ("r1", 1),
+                ("r1", 2),
+                ("r2", 10),
+                ("r2", 20),
+                ("r1", 3),
+                ("r1", 4),
+                ("r1", 5),
+            ]
+        );
+        // Ended sources are dropped, only the pending one is left
+        assert_eq!(futures::poll!(stream.next()), Poll::Pending);
+        assert_eq!(stream.source_ids().collect::<Vec<_>>(), vec![&"r3"]);
+    }
 }
@@ -0,0 +1,272 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Counters of the codes received from the routers that are not decoded by
+//! NetGauze, so operators learn when routers start sending something new.
+//!
+//! Unknown path attributes and BGP capabilities are kept as opaque values by
+//! the parser, [`UnknownCodesCounter`] counts them per code and
+//! [`UnknownCodesLayer`] wraps a BMP service to count them in all the
+//! requests received by a [`crate::server::BmpServer`]. A `tracing` warning
+//! is logged the first time a code is seen.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use serde::{Deserialize, Serialize};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use netgauze_bgp_pkt::{
+    capabilities::BgpCapability, path_attribute::PathAttributeValue, BgpMessage,
+};
+use netgauze_bmp_pkt::{BmpMessage, BmpMessageValue};
+
+use crate::server::BmpRequest;
+
+/// A code received on the wire that is not decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum UnknownCode {
+    /// Path attribute type code of a Route Monitoring message
+    PathAttribute(u8),
+    /// Capability code of the BGP OPEN messages of a Peer Up message
+    Capability(u8),
+}
+
+/// Number of times each [`UnknownCode`] is received
+#[derive(Debug, Clone, Default)]
+pub struct UnknownCodesCounter {
+    counts: HashMap<UnknownCode, u64>,
+}
+
+impl UnknownCodesCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count the unknown codes of a message, returns the codes seen for the
+    /// first time
+    pub fn observe(&mut self, msg: &BmpMessage) -> Vec<UnknownCode> {
+        let value = msg.to_v3_value();
+        let mut codes = vec![];
+        match value.as_ref() {
+            BmpMessageValue::RouteMonitoring(route_monitoring) => {
+                if let BgpMessage::Update(update) = route_monitoring.update_message() {
+                    for attr in update.path_attributes() {
+                        if let PathAttributeValue::UnknownAttribute(unknown) = attr.value() {
+                            codes.push(UnknownCode::PathAttribute(unknown.code()));
+                        }
+                    }
+                }
+            }
+            BmpMessageValue::PeerUpNotification(peer_up) => {
+                for open in [peer_up.sent_message(), peer_up.received_message()] {
+                    if let BgpMessage::Open(open) = open {
+                        for capability in open.capabilities() {
+                            if let BgpCapability::Unrecognized(unknown) = capability {
+                                codes.push(UnknownCode::Capability(*unknown.code()));
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        let mut new = vec![];
+        for code in codes {
+            let count = self.counts.entry(code).or_insert(0);
+            if *count == 0 {
+                new.push(code);
+            }
+            *count += 1;
+        }
+        new
+    }
+
+    /// Number of times `code` is received so far
+    pub fn count(&self, code: UnknownCode) -> u64 {
+        self.counts.get(&code).copied().unwrap_or(0)
+    }
+
+    pub const fn counts(&self) -> &HashMap<UnknownCode, u64> {
+        &self.counts
+    }
+
+    /// Forget all the counts
+    pub fn reset(&mut self) {
+        self.counts.clear();
+    }
+}
+
+/// [`Layer`] counting the unknown codes of the requests of the inner BMP
+/// service in a shared [`UnknownCodesCounter`], requests are passed unchanged
+/// to the inner service.
+#[derive(Debug, Clone, Default)]
+pub struct UnknownCodesLayer {
+    counter: Arc<Mutex<UnknownCodesCounter>>,
+}
+
+impl UnknownCodesLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The shared counter, to export the counts while the server is running
+    pub fn counter(&self) -> Arc<Mutex<UnknownCodesCounter>> {
+        self.counter.clone()
+    }
+}
+
+impl<S> Layer<S> for UnknownCodesLayer {
+    type Service = UnknownCodesService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        UnknownCodesService {
+            inner,
+            counter: self.counter.clone(),
+        }
+    }
+}
+
+/// Service created by [`UnknownCodesLayer`]
+#[derive(Debug, Clone)]
+pub struct UnknownCodesService<S> {
+    inner: S,
+    counter: Arc<Mutex<UnknownCodesCounter>>,
+}
+
+impl<S> Service<BmpRequest> for UnknownCodesService<S>
+where
+    S: Service<BmpRequest>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: BmpRequest) -> Self::Future {
+        if let Ok(tagged) = &request {
+            if let Some(msg) = tagged.value() {
+                let new = self
+                    .counter
+                    .lock()
+                    .expect("unknown codes counter lock is poisoned")
+                    .observe(msg);
+                for code in new {
+                    tracing::warn!(
+                        router = %tagged.tag().remote_socket(),
+                        "received unknown {code:?} for the first time"
+                    );
+                }
+            }
+        }
+        self.inner.call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netgauze_bgp_pkt::{
+        capabilities::UnrecognizedCapability,
+        open::{BgpOpenMessage, BgpOpenMessageParameter},
+        path_attribute::{PathAttribute, UnknownAttribute},
+        update::BgpUpdateMessage,
+    };
+    use netgauze_bmp_pkt::{
+        BmpPeerType, PeerHeader, PeerUpNotificationMessage, RouteMonitoringMessage,
+    };
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn peer_header() -> PeerHeader {
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10))),
+            65000,
+            Ipv4Addr::new(192, 0, 2, 10),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_unknown_codes_counter() {
+        let open = |code| {
+            BgpMessage::Open(BgpOpenMessage::new(
+                65000,
+                180,
+                Ipv4Addr::LOCALHOST,
+                vec![BgpOpenMessageParameter::Capabilities(vec![
+                    BgpCapability::RouteRefresh,
+                    BgpCapability::Unrecognized(UnrecognizedCapability::new(code, vec![])),
+                ])],
+            ))
+        };
+        let peer_up = BmpMessage::V3(BmpMessageValue::PeerUpNotification(
+            PeerUpNotificationMessage::build(
+                peer_header(),
+                None,
+                None,
+                None,
+                open(200),
+                open(201),
+                vec![],
+            )
+            .unwrap(),
+        ));
+        let attr = PathAttribute::from(
+            true,
+            true,
+            false,
+            false,
+            PathAttributeValue::UnknownAttribute(UnknownAttribute::new(250, vec![1, 2])),
+        )
+        .unwrap();
+        let route_monitoring = BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            RouteMonitoringMessage::build(
+                peer_header(),
+                BgpMessage::Update(BgpUpdateMessage::new(vec![], vec![attr], vec![])),
+            )
+            .unwrap(),
+        ));
+
+        let mut counter = UnknownCodesCounter::new();
+        assert_eq!(
+            counter.observe(&peer_up),
+            vec![UnknownCode::Capability(200), UnknownCode::Capability(201)]
+        );
+        assert_eq!(
+            counter.observe(&route_monitoring),
+            vec![UnknownCode::PathAttribute(250)]
+        );
+        // Codes already seen are only counted
+        assert_eq!(counter.observe(&route_monitoring), vec![]);
+        assert_eq!(counter.count(UnknownCode::PathAttribute(250)), 2);
+        assert_eq!(counter.count(UnknownCode::Capability(200)), 1);
+        assert_eq!(counter.count(UnknownCode::PathAttribute(251)), 0);
+        assert_eq!(counter.counts().len(), 3);
+    }
+}
@@ -0,0 +1,739 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Merged view of the routes monitored by multiple BMP routers.
+//!
+//! [`MultiRouterView`] keeps, for each prefix, the distinct paths received
+//! from all the monitored peers of all the routers, each path tagged with the
+//! [`VantagePoint`]s it's visible from. This is the core data structure of
+//! looking-glass style applications: which paths exist for a prefix, and which
+//! routers see each of them.
+//!
+//! Path attribute sets are shared among the paths through a
+//! [`PathAttributesInterner`], so a path seen by many routers is stored once.
+//!
+//! Peers using ADD-PATH advertise many paths for the same prefix, told apart
+//! by their path id. Each path id is a distinct [`VantagePoint`], so the paths
+//! of such a peer are all kept, and announcing or withdrawing a path id only
+//! affects that path.
+//!
+//! [`MultiRouterView::stats`] counts the routes per AFI/SAFI and per peer,
+//! along with the memory they take, see [`crate::rib_stats`].
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    net::IpAddr,
+    sync::Arc,
+};
+
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+
+use netgauze_bgp_pkt::{
+    intern::{InternerStats, PathAttributes, PathAttributesInterner},
+    nlri::RouteDistinguisher,
+    path_attribute::{PathAttribute, PathAttributeValue},
+    wire::serializer::path_attribute::PathAttributeWritingError,
+    BgpMessage,
+};
+use netgauze_bmp_pkt::{BmpMessage, BmpMessageValue, PeerHeader, PeerKey, PeerKeyStrategy};
+use netgauze_iana::address_family::AddressType;
+
+use crate::rib_stats::{attributes_memory, hash_slots_memory, RibStats};
+
+/// Key of a prefix in the view, VPN prefixes are kept apart by their route
+/// distinguisher
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ViewPrefix {
+    rd: Option<RouteDistinguisher>,
+    prefix: IpNet,
+}
+
+impl ViewPrefix {
+    pub const fn new(rd: Option<RouteDistinguisher>, prefix: IpNet) -> Self {
+        Self { rd, prefix }
+    }
+
+    pub const fn rd(&self) -> Option<RouteDistinguisher> {
+        self.rd
+    }
+
+    pub const fn prefix(&self) -> IpNet {
+        self.prefix
+    }
+
+    /// AFI/SAFI of the prefix. The SAFI the routes are received with isn't
+    /// kept, so prefixes with a route distinguisher are counted as MPLS VPN
+    /// routes and the others as unicast routes.
+    pub const fn address_type(&self) -> AddressType {
+        match (self.prefix, self.rd) {
+            (IpNet::V4(_), None) => AddressType::Ipv4Unicast,
+            (IpNet::V4(_), Some(_)) => AddressType::Ipv4MplsLabeledVpn,
+            (IpNet::V6(_), None) => AddressType::Ipv6Unicast,
+            (IpNet::V6(_), Some(_)) => AddressType::Ipv6MplsLabeledVpn,
+        }
+    }
+}
+
+impl From<IpNet> for ViewPrefix {
+    fn from(prefix: IpNet) -> Self {
+        Self::new(None, prefix)
+    }
+}
+
+/// Where a path is received: a monitored peer of a router, along with the
+/// path id when the peer uses ADD-PATH
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct VantagePoint {
+    router: IpAddr,
+    peer: PeerKey,
+    path_id: Option<u32>,
+}
+
+impl VantagePoint {
+    pub const fn new(router: IpAddr, peer: PeerKey, path_id: Option<u32>) -> Self {
+        Self {
+            router,
+            peer,
+            path_id,
+        }
+    }
+
+    pub const fn router(&self) -> IpAddr {
+        self.router
+    }
+
+    pub const fn peer(&self) -> PeerKey {
+        self.peer
+    }
+
+    pub const fn path_id(&self) -> Option<u32> {
+        self.path_id
+    }
+}
+
+/// A distinct path of a prefix along with the vantage points it's visible
+/// from.
+///
+/// Paths are identified by their next hop and path attributes, excluding the
+/// `MP_REACH_NLRI` and `MP_UNREACH_NLRI` attributes that carry the NLRI of
+/// the whole update.
+#[derive(Debug, Clone)]
+pub struct ViewPath {
+    next_hop: Option<IpAddr>,
+    attributes: PathAttributes,
+    vantage_points: HashSet<VantagePoint>,
+}
+
+impl ViewPath {
+    pub const fn next_hop(&self) -> Option<IpAddr> {
+        self.next_hop
+    }
+
+    pub fn attributes(&self) -> &[PathAttribute] {
+        &self.attributes
+    }
+
+    pub const fn vantage_points(&self) -> &HashSet<VantagePoint> {
+        &self.vantage_points
+    }
+
+    /// Routers that have this path from at least one of their peers
+    pub fn routers(&self) -> BTreeSet<IpAddr> {
+        self.vantage_points.iter().map(|vp| vp.router()).collect()
+    }
+
+    pub fn is_visible_from(&self, router: IpAddr) -> bool {
+        self.vantage_points.iter().any(|vp| vp.router() == router)
+    }
+}
+
+/// All the paths of a single prefix
+#[derive(Debug, Clone, Default)]
+pub struct PrefixView {
+    paths: Vec<ViewPath>,
+}
+
+impl PrefixView {
+    pub fn paths(&self) -> &[ViewPath] {
+        &self.paths
+    }
+
+    /// Routers that have at least one path for the prefix
+    pub fn routers(&self) -> BTreeSet<IpAddr> {
+        self.paths.iter().flat_map(ViewPath::routers).collect()
+    }
+
+    /// Paths visible from `router`
+    pub fn paths_from(&self, router: IpAddr) -> impl Iterator<Item = &ViewPath> {
+        self.paths
+            .iter()
+            .filter(move |path| path.is_visible_from(router))
+    }
+
+    /// Remove `vantage_point` from the paths, returns if it had a path
+    fn withdraw(&mut self, vantage_point: &VantagePoint) -> bool {
+        let mut withdrawn = false;
+        for path in &mut self.paths {
+            withdrawn |= path.vantage_points.remove(vantage_point);
+        }
+        self.paths.retain(|path| !path.vantage_points.is_empty());
+        withdrawn
+    }
+
+    fn retain(&mut self, f: impl Fn(&VantagePoint) -> bool) -> usize {
+        let mut removed = 0;
+        for path in &mut self.paths {
+            let before = path.vantage_points.len();
+            path.vantage_points.retain(&f);
+            removed += before - path.vantage_points.len();
+        }
+        self.paths.retain(|path| !path.vantage_points.is_empty());
+        removed
+    }
+}
+
+/// Paths of every prefix across all the routers, fed with BMP messages.
+///
+/// A vantage point has at most a single path per prefix, announcing a new one
+/// replaces the previous path of the vantage point, as in a BGP Adj-RIB-In.
+/// With ADD-PATH, the replaced path is the one of the same path id.
+#[derive(Debug, Default)]
+pub struct MultiRouterView {
+    peer_key_strategy: PeerKeyStrategy,
+    interner: PathAttributesInterner,
+    prefixes: HashMap<ViewPrefix, PrefixView>,
+}
+
+impl MultiRouterView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How the monitored peers of each router are told apart, see
+    /// [`PeerKeyStrategy::Full`] for the default
+    pub fn with_peer_key_strategy(mut self, peer_key_strategy: PeerKeyStrategy) -> Self {
+        self.peer_key_strategy = peer_key_strategy;
+        self
+    }
+
+    pub const fn peer_key_strategy(&self) -> PeerKeyStrategy {
+        self.peer_key_strategy
+    }
+
+    /// Update the view with a BMP message received from `router`.
+    ///
+    /// Route Monitoring messages announce and withdraw paths, Peer Down
+    /// messages remove all the paths of the peer, and Termination messages
+    /// all the paths of the router. Routes without an IP prefix are ignored.
+    /// When the BMP session is closed without a Termination message,
+    /// [`MultiRouterView::remove_router`] should be called.
+    pub fn apply(
+        &mut self,
+        router: IpAddr,
+        msg: &BmpMessage,
+    ) -> Result<(), PathAttributeWritingError> {
+        let value = msg.to_v3_value();
+        match value.as_ref() {
+            BmpMessageValue::RouteMonitoring(route_monitoring) => {
+                let update = match route_monitoring.update_message() {
+                    BgpMessage::Update(update) => update,
+                    _ => return Ok(()),
+                };
+                let peer = self.peer_key(route_monitoring.peer_header());
+                for route in update.withdrawn() {
+                    if let Some(prefix) = route.prefix().ip_net() {
+                        let prefix = ViewPrefix::new(route.prefix().rd(), prefix);
+                        self.withdraw(&prefix, &VantagePoint::new(router, peer, route.path_id()));
+                    }
+                }
+                let attributes = update
+                    .path_attributes()
+                    .iter()
+                    .filter(|attr| {
+                        !matches!(
+                            attr.value(),
+                            PathAttributeValue::MpReach(_) | PathAttributeValue::MpUnreach(_)
+                        )
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+                for route in update.announced() {
+                    if let Some(prefix) = route.prefix().ip_net() {
+                        self.announce(
+                            ViewPrefix::new(route.prefix().rd(), prefix),
+                            VantagePoint::new(router, peer, route.path_id()),
+                            route.next_hop(),
+                            &attributes,
+                        )?;
+                    }
+                }
+            }
+            BmpMessageValue::PeerDownNotification(peer_down) => {
+                let peer = self.peer_key(peer_down.peer_header());
+                self.remove_peer(router, peer);
+            }
+            BmpMessageValue::Termination(_) => {
+                self.remove_router(router);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn peer_key(&self, header: &PeerHeader) -> PeerKey {
+        self.peer_key_strategy
+            .apply(PeerKey::from_peer_header(header))
+    }
+
+    /// Set the path of `vantage_point` for `prefix`
+    pub fn announce(
+        &mut self,
+        prefix: ViewPrefix,
+        vantage_point: VantagePoint,
+        next_hop: Option<IpAddr>,
+        attributes: &[PathAttribute],
+    ) -> Result<(), PathAttributeWritingError> {
+        let attributes = self.interner.intern(attributes)?;
+        let view = self.prefixes.entry(prefix).or_default();
+        view.withdraw(&vantage_point);
+        match view
+            .paths
+            .iter_mut()
+            .find(|path| path.next_hop == next_hop && Arc::ptr_eq(&path.attributes, &attributes))
+        {
+            Some(path) => {
+                path.vantage_points.insert(vantage_point);
+            }
+            None => view.paths.push(ViewPath {
+                next_hop,
+                attributes,
+                vantage_points: HashSet::from([vantage_point]),
+            }),
+        }
+        Ok(())
+    }
+
+    /// Remove the path of `vantage_point` for `prefix`, returns if there was
+    /// one
+    pub fn withdraw(&mut self, prefix: &ViewPrefix, vantage_point: &VantagePoint) -> bool {
+        let view = match self.prefixes.get_mut(prefix) {
+            Some(view) => view,
+            None => return false,
+        };
+        let withdrawn = view.withdraw(vantage_point);
+        if view.paths.is_empty() {
+            self.prefixes.remove(prefix);
+        }
+        withdrawn
+    }
+
+    /// Paths of `prefix` received from a peer of `router`, one per path id,
+    /// sorted by path id
+    pub fn paths_of_peer(
+        &self,
+        prefix: &ViewPrefix,
+        router: IpAddr,
+        peer: PeerKey,
+    ) -> Vec<(Option<u32>, &ViewPath)> {
+        let mut paths = self
+            .prefixes
+            .get(prefix)
+            .into_iter()
+            .flat_map(|view| view.paths.iter())
+            .flat_map(|path| {
+                path.vantage_points
+                    .iter()
+                    .filter(|vp| vp.router() == router && vp.peer() == peer)
+                    .map(move |vp| (vp.path_id(), path))
+            })
+            .collect::<Vec<_>>();
+        paths.sort_by_key(|(path_id, _)| *path_id);
+        paths
+    }
+
+    /// Remove the paths of `prefix` received from a peer of `router`, across
+    /// all its path ids, returns the number of removed paths
+    pub fn withdraw_paths_of_peer(
+        &mut self,
+        prefix: &ViewPrefix,
+        router: IpAddr,
+        peer: PeerKey,
+    ) -> usize {
+        let view = match self.prefixes.get_mut(prefix) {
+            Some(view) => view,
+            None => return 0,
+        };
+        let removed = view.retain(|vp| vp.router() != router || vp.peer() != peer);
+        if view.paths.is_empty() {
+            self.prefixes.remove(prefix);
+        }
+        removed
+    }
+
+    /// Remove all the paths received from a peer of `router`, returns the
+    /// number of removed paths
+    pub fn remove_peer(&mut self, router: IpAddr, peer: PeerKey) -> usize {
+        self.remove(|vp| vp.router() == router && vp.peer() == peer)
+    }
+
+    /// Remove all the paths received from `router`, returns the number of
+    /// removed paths
+    pub fn remove_router(&mut self, router: IpAddr) -> usize {
+        self.remove(|vp| vp.router() == router)
+    }
+
+    fn remove(&mut self, f: impl Fn(&VantagePoint) -> bool) -> usize {
+        let mut removed = 0;
+        self.prefixes.retain(|_, view| {
+            removed += view.retain(|vp| !f(vp));
+            !view.paths.is_empty()
+        });
+        self.interner.purge();
+        removed
+    }
+
+    pub fn prefix(&self, prefix: &ViewPrefix) -> Option<&PrefixView> {
+        self.prefixes.get(prefix)
+    }
+
+    pub fn prefixes(&self) -> impl Iterator<Item = (&ViewPrefix, &PrefixView)> {
+        self.prefixes.iter()
+    }
+
+    /// Number of prefixes with at least one path
+    pub fn len(&self) -> usize {
+        self.prefixes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty()
+    }
+
+    /// Statistics of the path attribute sets sharing
+    pub fn interner_stats(&self) -> InternerStats {
+        self.interner.stats()
+    }
+
+    /// Number of routes, i.e., paths of prefixes, held for each monitored
+    /// peer of each router
+    pub fn routes_per_peer(&self) -> HashMap<(IpAddr, PeerKey), usize> {
+        let mut routes = HashMap::new();
+        for vantage_point in self
+            .prefixes
+            .values()
+            .flat_map(|view| view.paths.iter())
+            .flat_map(|path| path.vantage_points.iter())
+        {
+            *routes
+                .entry((vantage_point.router(), vantage_point.peer()))
+                .or_insert(0) += 1;
+        }
+        routes
+    }
+
+    /// Count the routes per AFI/SAFI and per peer, and estimate the memory
+    /// they take.
+    ///
+    /// The memory of a path, including its share of the interned attribute
+    /// set, is split evenly among its vantage points. Each interned set is
+    /// counted once in [`RibStats::memory`], however many paths share it.
+    pub fn stats(&self) -> RibStats {
+        let mut stats = RibStats::default().with_interner(self.interner.stats());
+        let mut attribute_sets = HashSet::new();
+        stats.add_memory(hash_slots_memory::<(ViewPrefix, PrefixView)>(
+            self.prefixes.capacity(),
+        ));
+        for (prefix, view) in &self.prefixes {
+            let address_type = prefix.address_type();
+            stats.add_prefix(
+                address_type,
+                view.paths.capacity() * std::mem::size_of::<ViewPath>(),
+            );
+            for path in &view.paths {
+                let attributes = attributes_memory(&path.attributes, path.attributes.len());
+                let vantage_points =
+                    hash_slots_memory::<VantagePoint>(path.vantage_points.capacity());
+                if attribute_sets.insert(Arc::as_ptr(&path.attributes) as *const () as usize) {
+                    stats.add_memory(attributes);
+                }
+                stats.add_memory(vantage_points);
+                // Each path holds a strong reference to its set, the interner
+                // only weak ones
+                let path_memory = attributes / Arc::strong_count(&path.attributes) + vantage_points;
+                let route_memory = path_memory / path.vantage_points.len().max(1);
+                for vantage_point in &path.vantage_points {
+                    stats.add_route(address_type, vantage_point, route_memory);
+                }
+            }
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use ipnet::Ipv4Net;
+    use netgauze_bgp_pkt::{
+        nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+        path_attribute::{As4PathSegment, AsPath, AsPathSegmentType, NextHop, Origin},
+        update::BgpUpdateMessage,
+    };
+    use netgauze_bmp_pkt::{
+        BmpPeerType, PeerDownNotificationMessage, PeerDownNotificationReason,
+        RouteMonitoringMessage, TerminationMessage,
+    };
+    use netgauze_iana::address_family::AddressType;
+    use std::{net::Ipv4Addr, str::FromStr};
+
+    const PREFIX: &str = "198.51.100.0/24";
+
+    fn router(last: u8) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(192, 0, 2, last))
+    }
+
+    fn peer_header(peer: u8) -> PeerHeader {
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, peer))),
+            64500 + peer as u32,
+            Ipv4Addr::new(203, 0, 113, peer),
+            Some(Utc.timestamp_opt(1680000000, 0).unwrap()),
+        )
+    }
+
+    fn nlri(path_id: Option<u32>) -> Vec<Ipv4UnicastAddress> {
+        vec![Ipv4UnicastAddress::new(
+            path_id,
+            Ipv4Unicast::from_net(Ipv4Net::from_str(PREFIX).unwrap()).unwrap(),
+        )]
+    }
+
+    fn update(peer: u8, as_path: Vec<u32>, withdraw: bool) -> BmpMessage {
+        update_path(peer, None, as_path, withdraw)
+    }
+
+    fn update_path(
+        peer: u8,
+        path_id: Option<u32>,
+        as_path: Vec<u32>,
+        withdraw: bool,
+    ) -> BmpMessage {
+        let update = if withdraw {
+            BgpUpdateMessage::new(nlri(path_id), vec![], vec![])
+        } else {
+            let attrs = vec![
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::Origin(Origin::IGP),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![As4PathSegment::new(
+                        AsPathSegmentType::AsSequence,
+                        as_path,
+                    )])),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(203, 0, 113, peer))),
+                )
+                .unwrap(),
+            ];
+            BgpUpdateMessage::new(vec![], attrs, nlri(path_id))
+        };
+        BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            RouteMonitoringMessage::build(peer_header(peer), BgpMessage::Update(update)).unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_multi_router_view() {
+        let prefix = ViewPrefix::from(IpNet::from_str(PREFIX).unwrap());
+        let mut view = MultiRouterView::new();
+
+        // Same path received by two routers from the same peer
+        view.apply(router(1), &update(1, vec![64501, 64510], false))
+            .unwrap();
+        view.apply(router(2), &update(1, vec![64501, 64510], false))
+            .unwrap();
+        let paths = view.prefix(&prefix).unwrap().paths();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].routers(), BTreeSet::from([router(1), router(2)]));
+        assert_eq!(
+            paths[0].next_hop(),
+            Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 1)))
+        );
+        assert_eq!(view.interner_stats().hits(), 1);
+
+        // A different path from another peer of the second router
+        view.apply(router(2), &update(2, vec![64502, 64510], false))
+            .unwrap();
+        let prefix_view = view.prefix(&prefix).unwrap();
+        assert_eq!(prefix_view.paths().len(), 2);
+        assert_eq!(prefix_view.paths_from(router(1)).count(), 1);
+        assert_eq!(prefix_view.paths_from(router(2)).count(), 2);
+        let peer = |peer| PeerKey::from_peer_header(&peer_header(peer));
+        assert_eq!(
+            view.routes_per_peer(),
+            HashMap::from([
+                ((router(1), peer(1)), 1),
+                ((router(2), peer(1)), 1),
+                ((router(2), peer(2)), 1),
+            ])
+        );
+
+        // Implicit withdraw, the first router switches to another path
+        view.apply(router(1), &update(1, vec![64501, 64520, 64510], false))
+            .unwrap();
+        let prefix_view = view.prefix(&prefix).unwrap();
+        assert_eq!(prefix_view.paths().len(), 3);
+        assert_eq!(prefix_view.paths_from(router(1)).count(), 1);
+        assert!(prefix_view
+            .paths()
+            .iter()
+            .all(|path| path.vantage_points().len() == 1));
+
+        // Withdraw
+        view.apply(router(1), &update(1, vec![], true)).unwrap();
+        assert_eq!(
+            view.prefix(&prefix).unwrap().routers(),
+            BTreeSet::from([router(2)])
+        );
+
+        // Peer down then termination
+        let peer_down = BmpMessage::V3(BmpMessageValue::PeerDownNotification(
+            PeerDownNotificationMessage::build(
+                peer_header(2),
+                PeerDownNotificationReason::RemoteSystemClosedNoData,
+            )
+            .unwrap(),
+        ));
+        view.apply(router(2), &peer_down).unwrap();
+        assert_eq!(view.prefix(&prefix).unwrap().paths().len(), 1);
+        let termination = BmpMessage::V3(BmpMessageValue::Termination(TerminationMessage::new(
+            peer_header(1),
+            vec![],
+        )));
+        view.apply(router(2), &termination).unwrap();
+        assert!(view.is_empty());
+        assert_eq!(view.interner_stats().entries(), 0);
+    }
+
+    #[test]
+    fn test_stats() {
+        let peer = |peer| PeerKey::from_peer_header(&peer_header(peer));
+        let mut view = MultiRouterView::new();
+        view.apply(router(1), &update(1, vec![64501, 64510], false))
+            .unwrap();
+        view.apply(router(2), &update(1, vec![64501, 64510], false))
+            .unwrap();
+        view.apply(router(2), &update(2, vec![64502, 64510], false))
+            .unwrap();
+        view.announce(
+            ViewPrefix::from(IpNet::from_str("2001:db8::/32").unwrap()),
+            VantagePoint::new(router(1), peer(1), None),
+            None,
+            &[],
+        )
+        .unwrap();
+
+        let stats = view.stats();
+        assert_eq!(stats.routes(), 4);
+        assert_eq!(stats.prefixes()[&AddressType::Ipv4Unicast], 1);
+        assert_eq!(stats.prefixes()[&AddressType::Ipv6Unicast], 1);
+        assert_eq!(stats.table(AddressType::Ipv4Unicast).routes(), 3);
+        assert_eq!(stats.table(AddressType::Ipv6Unicast).routes(), 1);
+        assert_eq!(stats.peer(router(1), peer(1)).routes(), 2);
+        assert_eq!(stats.peer(router(2), peer(2)).routes(), 1);
+        assert_eq!(stats.interner(), Some(view.interner_stats()));
+
+        // The path shared by two routers is split between them, while the
+        // path of the other peer is accounted to it alone
+        let shared = stats.table(AddressType::Ipv4Unicast).memory()
+            - stats.peer(router(2), peer(2)).memory();
+        assert!(shared > 0);
+        assert!(stats.peer(router(2), peer(2)).memory() > shared / 2);
+        assert!(stats.memory() > 0);
+        let top_peers = stats.top_peers(3);
+        assert_eq!(top_peers.len(), 3);
+        assert!(top_peers[0].1.memory() >= top_peers[2].1.memory());
+    }
+
+    #[test]
+    fn test_add_path() {
+        let prefix = ViewPrefix::from(IpNet::from_str(PREFIX).unwrap());
+        let peer = PeerKey::from_peer_header(&peer_header(1));
+        let mut view = MultiRouterView::new();
+        let as_paths = |view: &MultiRouterView| {
+            view.paths_of_peer(&prefix, router(1), peer)
+                .into_iter()
+                .map(|(path_id, path)| (path_id, path.attributes().to_vec()))
+                .collect::<Vec<_>>()
+        };
+
+        // Both paths of the peer are kept, the same attributes are shared
+        view.apply(router(1), &update_path(1, Some(1), vec![64501], false))
+            .unwrap();
+        view.apply(router(1), &update_path(1, Some(2), vec![64501], false))
+            .unwrap();
+        let paths = view.prefix(&prefix).unwrap().paths();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].vantage_points().len(), 2);
+        assert_eq!(view.routes_per_peer()[&(router(1), peer)], 2);
+
+        // Implicit replace of the same path id only
+        view.apply(
+            router(1),
+            &update_path(1, Some(2), vec![64501, 64502], false),
+        )
+        .unwrap();
+        let paths = as_paths(&view);
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].0, Some(1));
+        assert_eq!(paths[1].0, Some(2));
+        assert_ne!(paths[0].1, paths[1].1);
+        assert_eq!(view.prefix(&prefix).unwrap().paths().len(), 2);
+
+        // Withdraw of a single path id
+        view.apply(router(1), &update_path(1, Some(1), vec![], true))
+            .unwrap();
+        let paths = as_paths(&view);
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].0, Some(2));
+
+        assert_eq!(view.withdraw_paths_of_peer(&prefix, router(1), peer), 1);
+        assert!(view.is_empty());
+    }
+}
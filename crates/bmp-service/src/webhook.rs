@@ -0,0 +1,628 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Webhook notifications of the BMP peer lifecycle and anomalies, e.g., to
+//! page someone when a monitored peer goes down. Route updates are not
+//! notified, use the [`crate::sink`] module for them.
+//!
+//! [`PeerEvent`]s are derived from the requests received by a
+//! [`crate::server::BmpServer`] and from the
+//! [`crate::sequence::BmpSequenceAnomaly`]s. [`WebhookSink`] sends each event
+//! in an HTTP POST request, with a JSON body rendered from a
+//! [`WebhookTemplate`]. [`WebhookLayer`] wraps a BMP service to notify the
+//! events of all its requests without waiting for the webhook.
+
+use std::{
+    collections::HashSet,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use netgauze_bmp_pkt::{BmpMessage, BmpMessageValue, PeerHeader, TerminationInformation};
+
+use crate::{sequence::BmpSequenceAnomaly, server::BmpRequest};
+
+/// Kind of the [`PeerEvent`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum PeerEventKind {
+    PeerUp,
+    PeerDown,
+    /// The router terminated the BMP session
+    Termination,
+    /// The BMP connection of the router is closed
+    ConnectionClosed,
+    /// The stream of the router couldn't be decoded
+    DecodeError,
+    /// See [`crate::sequence::BmpSequenceAnomalyKind`]
+    SequenceAnomaly,
+}
+
+/// Lifecycle or anomaly event of a router or one of its monitored peers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerEvent {
+    kind: PeerEventKind,
+    router: SocketAddr,
+    timestamp: DateTime<Utc>,
+    peer_address: Option<IpAddr>,
+    peer_as: Option<u32>,
+    peer_bgp_id: Option<Ipv4Addr>,
+    /// Human readable details, e.g., the Peer Down reason
+    detail: Option<String>,
+}
+
+impl PeerEvent {
+    pub const fn new(kind: PeerEventKind, router: SocketAddr, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            kind,
+            router,
+            timestamp,
+            peer_address: None,
+            peer_as: None,
+            peer_bgp_id: None,
+            detail: None,
+        }
+    }
+
+    fn with_peer(mut self, peer_header: &PeerHeader) -> Self {
+        if let Some(timestamp) = peer_header.timestamp() {
+            self.timestamp = *timestamp;
+        }
+        self.peer_address = peer_header.address();
+        self.peer_as = Some(peer_header.peer_as());
+        self.peer_bgp_id = Some(peer_header.bgp_id());
+        self
+    }
+
+    fn with_detail(mut self, detail: String) -> Self {
+        self.detail = Some(detail);
+        self
+    }
+
+    /// Event of a request received by [`crate::server::BmpServer`], if any
+    pub fn from_request(request: &BmpRequest) -> Option<Self> {
+        match request {
+            Ok(tagged) => {
+                let router = tagged.tag().remote_socket();
+                match tagged.value() {
+                    None => Some(Self::new(
+                        PeerEventKind::ConnectionClosed,
+                        router,
+                        Utc::now(),
+                    )),
+                    Some(msg) => Self::from_message(router, msg),
+                }
+            }
+            Err(tagged) => Some(
+                Self::new(
+                    PeerEventKind::DecodeError,
+                    tagged.tag().remote_socket(),
+                    Utc::now(),
+                )
+                .with_detail(format!("{:?}", tagged.value())),
+            ),
+        }
+    }
+
+    /// Event of a BMP message received from `router`, if any
+    pub fn from_message(router: SocketAddr, msg: &BmpMessage) -> Option<Self> {
+        let value = msg.to_v3_value();
+        match value.as_ref() {
+            BmpMessageValue::PeerUpNotification(peer_up) => Some(
+                Self::new(PeerEventKind::PeerUp, router, Utc::now())
+                    .with_peer(peer_up.peer_header()),
+            ),
+            BmpMessageValue::PeerDownNotification(peer_down) => Some(
+                Self::new(PeerEventKind::PeerDown, router, Utc::now())
+                    .with_peer(peer_down.peer_header())
+                    .with_detail(peer_down.reason().get_type().to_string()),
+            ),
+            BmpMessageValue::Termination(termination) => {
+                let detail = termination
+                    .information()
+                    .iter()
+                    .filter_map(|info| match info {
+                        TerminationInformation::String(value) => Some(value.clone()),
+                        TerminationInformation::Reason(reason) => Some(reason.to_string()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                Some(Self::new(PeerEventKind::Termination, router, Utc::now()).with_detail(detail))
+            }
+            _ => None,
+        }
+    }
+
+    pub const fn kind(&self) -> PeerEventKind {
+        self.kind
+    }
+
+    /// Address of the BMP connection of the router
+    pub const fn router(&self) -> SocketAddr {
+        self.router
+    }
+
+    /// Timestamp of the peer header when present, otherwise when the event
+    /// is created
+    pub const fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+
+    pub const fn peer_address(&self) -> Option<IpAddr> {
+        self.peer_address
+    }
+
+    pub const fn peer_as(&self) -> Option<u32> {
+        self.peer_as
+    }
+
+    pub const fn peer_bgp_id(&self) -> Option<Ipv4Addr> {
+        self.peer_bgp_id
+    }
+
+    pub fn detail(&self) -> Option<&str> {
+        self.detail.as_deref()
+    }
+
+    /// Value of a [`WebhookTemplate`] placeholder, `None` for unknown names
+    fn field(&self, name: &str) -> Option<String> {
+        let value = match name {
+            "kind" => format!("{:?}", self.kind),
+            "router" => self.router.to_string(),
+            "timestamp" => self.timestamp.to_rfc3339(),
+            "peer_address" => self.peer_address.map(|x| x.to_string()).unwrap_or_default(),
+            "peer_as" => self.peer_as.map(|x| x.to_string()).unwrap_or_default(),
+            "peer_bgp_id" => self.peer_bgp_id.map(|x| x.to_string()).unwrap_or_default(),
+            "detail" => self.detail.clone().unwrap_or_default(),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+impl From<&BmpSequenceAnomaly> for PeerEvent {
+    fn from(anomaly: &BmpSequenceAnomaly) -> Self {
+        let mut event = Self::new(
+            PeerEventKind::SequenceAnomaly,
+            anomaly.router().remote_socket(),
+            Utc::now(),
+        )
+        .with_detail(format!(
+            "{:?} on {:?}",
+            anomaly.kind(),
+            anomaly.message_type()
+        ));
+        if let Some(peer) = anomaly.peer() {
+            event.peer_address = peer.peer_address();
+            event.peer_as = Some(peer.asn());
+            event.peer_bgp_id = Some(peer.bgp_id());
+        }
+        event
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookError {
+    /// Placeholder that is not a field of [`PeerEvent`]
+    UnknownPlaceholder(String),
+    /// Placeholder missing its closing `}}`
+    UnterminatedPlaceholder(usize),
+    HttpError(String),
+    /// The webhook replied with a non-success status code
+    StatusError {
+        status: u16,
+        body: String,
+    },
+}
+
+impl std::fmt::Display for WebhookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::error::Error for WebhookError {}
+
+impl From<reqwest::Error> for WebhookError {
+    fn from(value: reqwest::Error) -> Self {
+        Self::HttpError(value.to_string())
+    }
+}
+
+/// Body of the webhook requests, with `{{name}}` placeholders replaced by the
+/// fields of the [`PeerEvent`]: `kind`, `router`, `timestamp`,
+/// `peer_address`, `peer_as`, `peer_bgp_id`, and `detail`. The values are
+/// JSON escaped without the surrounding quotes, so the placeholders go inside
+/// JSON strings, and absent values are empty. `{{event}}` is replaced by the
+/// whole event as a JSON object.
+///
+/// For instance, `{"text": "BMP peer {{peer_address}} of {{router}} is
+/// {{kind}}: {{detail}}"}` for a chat incoming webhook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookTemplate {
+    template: String,
+}
+
+impl Default for WebhookTemplate {
+    fn default() -> Self {
+        Self {
+            template: "{{event}}".to_string(),
+        }
+    }
+}
+
+impl WebhookTemplate {
+    /// Fails on placeholders that are not fields of [`PeerEvent`]
+    pub fn new(template: String) -> Result<Self, WebhookError> {
+        let sample = PeerEvent::new(
+            PeerEventKind::PeerUp,
+            SocketAddr::from(([0, 0, 0, 0], 0)),
+            DateTime::<Utc>::UNIX_EPOCH,
+        );
+        let template = Self { template };
+        template.try_render(&sample)?;
+        Ok(template)
+    }
+
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    pub fn render(&self, event: &PeerEvent) -> String {
+        // Placeholders are validated by the constructor
+        self.try_render(event).unwrap_or_default()
+    }
+
+    fn try_render(&self, event: &PeerEvent) -> Result<String, WebhookError> {
+        let mut body = String::with_capacity(self.template.len());
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find("{{") {
+            body.push_str(&rest[..start]);
+            let offset = self.template.len() - rest.len() + start;
+            let end = rest[start..]
+                .find("}}")
+                .ok_or(WebhookError::UnterminatedPlaceholder(offset))?;
+            let name = rest[start + 2..start + end].trim();
+            if name == "event" {
+                let json = serde_json::to_string(event)
+                    .map_err(|err| WebhookError::HttpError(err.to_string()))?;
+                body.push_str(&json);
+            } else {
+                let value = event
+                    .field(name)
+                    .ok_or_else(|| WebhookError::UnknownPlaceholder(name.to_string()))?;
+                let escaped = serde_json::Value::String(value).to_string();
+                body.push_str(&escaped[1..escaped.len() - 1]);
+            }
+            rest = &rest[start + end + 2..];
+        }
+        body.push_str(rest);
+        Ok(body)
+    }
+}
+
+/// Sends [`PeerEvent`]s to a webhook in HTTP POST requests
+#[derive(Debug, Clone)]
+pub struct WebhookSink {
+    client: reqwest::Client,
+    url: String,
+    template: WebhookTemplate,
+    kinds: Option<HashSet<PeerEventKind>>,
+    headers: Vec<(String, String)>,
+}
+
+impl WebhookSink {
+    /// All the event kinds are sent with the [`WebhookTemplate::default`]
+    /// body
+    pub fn new(url: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            template: WebhookTemplate::default(),
+            kinds: None,
+            headers: vec![],
+        }
+    }
+
+    pub fn with_template(mut self, template: WebhookTemplate) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Send only the events of the given kinds
+    pub fn with_kinds(mut self, kinds: impl IntoIterator<Item = PeerEventKind>) -> Self {
+        self.kinds = Some(kinds.into_iter().collect());
+        self
+    }
+
+    /// Additional request header, e.g., for authorization
+    pub fn with_header(mut self, name: String, value: String) -> Self {
+        self.headers.push((name, value));
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub const fn template(&self) -> &WebhookTemplate {
+        &self.template
+    }
+
+    /// Whether the events of `kind` are sent
+    pub fn accepts(&self, kind: PeerEventKind) -> bool {
+        self.kinds
+            .as_ref()
+            .is_none_or(|kinds| kinds.contains(&kind))
+    }
+
+    /// Send the event, unless its kind is filtered out
+    pub async fn send(&self, event: &PeerEvent) -> Result<(), WebhookError> {
+        if !self.accepts(event.kind()) {
+            return Ok(());
+        }
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .body(self.template.render(event));
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(WebhookError::StatusError {
+                status: status.as_u16(),
+                body: response.text().await.unwrap_or_default(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// [`Layer`] sending the [`PeerEvent`]s of the requests of the inner BMP
+/// service to a [`WebhookSink`]. The requests are sent in background tasks,
+/// failures are logged, and requests are passed unchanged to the inner
+/// service.
+#[derive(Debug, Clone)]
+pub struct WebhookLayer {
+    sink: Arc<WebhookSink>,
+}
+
+impl WebhookLayer {
+    pub fn new(sink: WebhookSink) -> Self {
+        Self {
+            sink: Arc::new(sink),
+        }
+    }
+
+    pub fn sink(&self) -> Arc<WebhookSink> {
+        self.sink.clone()
+    }
+}
+
+impl<S> Layer<S> for WebhookLayer {
+    type Service = WebhookService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        WebhookService {
+            inner,
+            sink: self.sink.clone(),
+        }
+    }
+}
+
+/// Service created by [`WebhookLayer`]
+#[derive(Debug, Clone)]
+pub struct WebhookService<S> {
+    inner: S,
+    sink: Arc<WebhookSink>,
+}
+
+impl<S> Service<BmpRequest> for WebhookService<S>
+where
+    S: Service<BmpRequest>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: BmpRequest) -> Self::Future {
+        if let Some(event) = PeerEvent::from_request(&request) {
+            if self.sink.accepts(event.kind()) {
+                let sink = self.sink.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = sink.send(&event).await {
+                        tracing::warn!("failed to send {:?} event to webhook: {err}", event.kind());
+                    }
+                });
+            }
+        }
+        self.inner.call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use netgauze_bmp_pkt::{
+        iana::PeerTerminationCode, BmpPeerType, PeerDownNotificationMessage,
+        PeerDownNotificationReason, TerminationMessage,
+    };
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use crate::{AddrInfo, TaggedData};
+
+    fn router() -> AddrInfo {
+        AddrInfo::new(
+            "192.0.2.100:1790".parse().unwrap(),
+            "192.0.2.1:40000".parse().unwrap(),
+        )
+    }
+
+    fn peer_header() -> PeerHeader {
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10))),
+            65000,
+            Ipv4Addr::new(192, 0, 2, 10),
+            Some(Utc.timestamp_opt(1664821826, 0).unwrap()),
+        )
+    }
+
+    fn peer_down() -> BmpMessage {
+        BmpMessage::V3(BmpMessageValue::PeerDownNotification(
+            PeerDownNotificationMessage::build(
+                peer_header(),
+                PeerDownNotificationReason::RemoteSystemClosedNoData,
+            )
+            .unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_peer_event_from_request() {
+        let event = PeerEvent::from_request(&Ok(TaggedData::new(router(), Some(peer_down()))));
+        assert_eq!(
+            event,
+            Some(PeerEvent {
+                kind: PeerEventKind::PeerDown,
+                router: router().remote_socket(),
+                timestamp: Utc.timestamp_opt(1664821826, 0).unwrap(),
+                peer_address: Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10))),
+                peer_as: Some(65000),
+                peer_bgp_id: Some(Ipv4Addr::new(192, 0, 2, 10)),
+                detail: Some("RemoteSystemClosedNoData".to_string()),
+            })
+        );
+
+        let termination = BmpMessage::V3(BmpMessageValue::Termination(TerminationMessage::new(
+            peer_header(),
+            vec![
+                TerminationInformation::String("maintenance".to_string()),
+                TerminationInformation::Reason(PeerTerminationCode::AdministrativelyClosed),
+            ],
+        )));
+        let event =
+            PeerEvent::from_request(&Ok(TaggedData::new(router(), Some(termination)))).unwrap();
+        assert_eq!(event.kind(), PeerEventKind::Termination);
+        assert_eq!(event.detail(), Some("maintenance, AdministrativelyClosed"));
+
+        let closed = PeerEvent::from_request(&Ok(TaggedData::new(router(), None))).unwrap();
+        assert_eq!(closed.kind(), PeerEventKind::ConnectionClosed);
+        assert_eq!(closed.peer_address(), None);
+    }
+
+    #[test]
+    fn test_webhook_template() {
+        let event = PeerEvent::from_message(router().remote_socket(), &peer_down()).unwrap();
+        let template = WebhookTemplate::new(
+            r#"{"text": "BMP peer {{peer_address}} (AS{{ peer_as }}) of {{router}} is {{kind}}: {{detail}}"}"#
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            template.render(&event),
+            r#"{"text": "BMP peer 192.0.2.10 (AS65000) of 192.0.2.1:40000 is PeerDown: RemoteSystemClosedNoData"}"#
+        );
+        let quoted = PeerEvent::new(
+            PeerEventKind::DecodeError,
+            router().remote_socket(),
+            Utc::now(),
+        )
+        .with_detail("\"bad\"\n".to_string());
+        assert_eq!(
+            WebhookTemplate::new(r#"{"text": "{{detail}}"}"#.to_string())
+                .unwrap()
+                .render(&quoted),
+            r#"{"text": "\"bad\"\n"}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<PeerEvent>(&WebhookTemplate::default().render(&event)).unwrap(),
+            event
+        );
+        assert_eq!(
+            WebhookTemplate::new("{{peer}}".to_string()),
+            Err(WebhookError::UnknownPlaceholder("peer".to_string()))
+        );
+        assert_eq!(
+            WebhookTemplate::new("ok {{kind".to_string()),
+            Err(WebhookError::UnterminatedPlaceholder(3))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_webhook_sink() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![];
+            let mut buf = [0u8; 1024];
+            // The body is the last part of the request, read till it's complete
+            while !String::from_utf8_lossy(&request).ends_with('}') {
+                let len = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..len]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let sink = WebhookSink::new(url)
+            .with_kinds([PeerEventKind::PeerDown])
+            .with_header("Authorization".to_string(), "Bearer token".to_string())
+            .with_template(
+                WebhookTemplate::new(r#"{"peer": "{{peer_address}}"}"#.to_string()).unwrap(),
+            );
+        let event = PeerEvent::from_message(router().remote_socket(), &peer_down()).unwrap();
+        let closed = PeerEvent::new(
+            PeerEventKind::ConnectionClosed,
+            router().remote_socket(),
+            Utc::now(),
+        );
+        // Filtered out, nothing is sent
+        assert_eq!(sink.send(&closed).await, Ok(()));
+        assert_eq!(sink.send(&event).await, Ok(()));
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /hook HTTP/1.1\r\n"));
+        assert!(request.contains("authorization: Bearer token\r\n"));
+        assert!(request.ends_with("\r\n\r\n{\"peer\": \"192.0.2.10\"}"));
+    }
+}
@@ -0,0 +1,329 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Integration test against a real BMP sender.
+//!
+//! Two FRR routers are started with docker on a private network: `peer`
+//! announces a couple of prefixes to `monitored`, which exports its
+//! Adj-RIB-In over BMP to a [`BmpServer`] running in the test. The test
+//! asserts on the decoded PeerUp and RouteMonitoring messages.
+//!
+//! The test requires a running docker daemon and is ignored by default, run it
+//! with:
+//!
+//! ```text
+//! cargo test -p netgauze-bmp-service --test frr -- --ignored
+//! ```
+//!
+//! The FRR image can be overridden with the `NETGAUZE_FRR_IMAGE` environment
+//! variable.
+
+use std::{
+    collections::HashSet,
+    convert::Infallible,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
+
+use ipnet::IpNet;
+use rand::Rng;
+use tokio::sync::mpsc;
+use tower::service_fn;
+
+use netgauze_bgp_pkt::BgpMessage;
+use netgauze_bmp_pkt::{BmpMessage, BmpMessageValue};
+use netgauze_bmp_service::{
+    handle::BmpServerHandle,
+    server::{BmpRequest, BmpServer, BmpServerResponse},
+};
+
+const DEFAULT_FRR_IMAGE: &str = "quay.io/frrouting/frr:9.1.0";
+const COLLECTOR_HOST: &str = "netgauze-collector";
+const MONITORED_AS: u32 = 65001;
+const PEER_AS: u32 = 65002;
+const PEER_ROUTER_ID: Ipv4Addr = Ipv4Addr::new(10, 255, 0, 2);
+const ANNOUNCED_V4: &str = "198.51.100.0/24";
+const ANNOUNCED_V6: &str = "2001:db8:100::/48";
+const TEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Runs a docker command, panicking with its stderr if it fails.
+fn docker(args: &[&str]) -> String {
+    let output = Command::new("docker")
+        .args(args)
+        .output()
+        .expect("failed to run docker");
+    assert!(
+        output.status.success(),
+        "docker {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Docker network that is removed when dropped
+struct Network {
+    name: String,
+}
+
+impl Network {
+    fn create(name: String, subnet: &str) -> Self {
+        docker(&["network", "create", "--subnet", subnet, &name]);
+        Self { name }
+    }
+}
+
+impl Drop for Network {
+    fn drop(&mut self) {
+        let _ = Command::new("docker")
+            .args(["network", "rm", &self.name])
+            .output();
+    }
+}
+
+/// FRR container that is removed when dropped
+struct FrrContainer {
+    name: String,
+    config_dir: PathBuf,
+}
+
+impl FrrContainer {
+    fn start(name: String, network: &Network, ip: Ipv4Addr, frr_conf: &str) -> Self {
+        let config_dir = std::env::temp_dir().join(&name);
+        std::fs::create_dir_all(&config_dir).unwrap();
+        write_config(&config_dir, frr_conf);
+        let image =
+            std::env::var("NETGAUZE_FRR_IMAGE").unwrap_or_else(|_| DEFAULT_FRR_IMAGE.to_string());
+        let ip = ip.to_string();
+        let volume = format!("{}:/etc/frr", config_dir.display());
+        let add_host = format!("{COLLECTOR_HOST}:host-gateway");
+        docker(&[
+            "run",
+            "--detach",
+            "--rm",
+            "--name",
+            &name,
+            "--network",
+            &network.name,
+            "--ip",
+            &ip,
+            "--add-host",
+            &add_host,
+            "--volume",
+            &volume,
+            &image,
+        ]);
+        Self { name, config_dir }
+    }
+
+    fn logs(&self) -> String {
+        Command::new("docker")
+            .args(["logs", &self.name])
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stderr).to_string())
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for FrrContainer {
+    fn drop(&mut self) {
+        let _ = Command::new("docker")
+            .args(["rm", "--force", &self.name])
+            .output();
+        let _ = std::fs::remove_dir_all(&self.config_dir);
+    }
+}
+
+fn write_config(dir: &Path, frr_conf: &str) {
+    // bgpd must be started with the BMP module loaded
+    let daemons = "\
+zebra=yes
+bgpd=yes
+vtysh_enable=yes
+zebra_options=\"  -A 127.0.0.1 -s 90000000\"
+bgpd_options=\"   -A 127.0.0.1 -M bmp\"
+";
+    std::fs::write(dir.join("daemons"), daemons).unwrap();
+    std::fs::write(dir.join("vtysh.conf"), "service integrated-vtysh-config\n").unwrap();
+    std::fs::write(dir.join("frr.conf"), frr_conf).unwrap();
+}
+
+fn monitored_config(peer_ip: Ipv4Addr, collector_port: u16) -> String {
+    format!(
+        "\
+frr defaults traditional
+hostname monitored
+!
+router bgp {MONITORED_AS}
+ bgp router-id 10.255.0.1
+ no bgp ebgp-requires-policy
+ neighbor {peer_ip} remote-as {PEER_AS}
+ !
+ address-family ipv6 unicast
+  neighbor {peer_ip} activate
+ exit-address-family
+ !
+ bmp targets netgauze
+  bmp monitor ipv4 unicast pre-policy
+  bmp monitor ipv6 unicast pre-policy
+  bmp connect {COLLECTOR_HOST} port {collector_port} min-retry 100 max-retry 1000
+ exit
+!
+"
+    )
+}
+
+fn peer_config(monitored_ip: Ipv4Addr) -> String {
+    format!(
+        "\
+frr defaults traditional
+hostname peer
+!
+router bgp {PEER_AS}
+ bgp router-id {PEER_ROUTER_ID}
+ no bgp ebgp-requires-policy
+ no bgp network import-check
+ neighbor {monitored_ip} remote-as {MONITORED_AS}
+ !
+ address-family ipv4 unicast
+  network {ANNOUNCED_V4}
+ exit-address-family
+ !
+ address-family ipv6 unicast
+  neighbor {monitored_ip} activate
+  network {ANNOUNCED_V6}
+ exit-address-family
+!
+"
+    )
+}
+
+/// Collects what the test expects to see from the BMP session
+#[derive(Debug, Default)]
+struct Observed {
+    peer_up: bool,
+    prefixes: HashSet<IpNet>,
+}
+
+impl Observed {
+    fn update(&mut self, msg: &BmpMessage) {
+        let value = msg.to_v3_value();
+        match value.as_ref() {
+            BmpMessageValue::PeerUpNotification(peer_up) => {
+                let header = peer_up.peer_header();
+                if header.peer_as() == PEER_AS {
+                    assert_eq!(header.bgp_id(), PEER_ROUTER_ID);
+                    assert!(matches!(peer_up.sent_message(), BgpMessage::Open(_)));
+                    assert!(matches!(peer_up.received_message(), BgpMessage::Open(_)));
+                    self.peer_up = true;
+                }
+            }
+            BmpMessageValue::RouteMonitoring(route_monitoring) => {
+                if let BgpMessage::Update(update) = route_monitoring.update_message() {
+                    self.prefixes.extend(
+                        update
+                            .announced()
+                            .filter_map(|route| route.prefix().ip_net()),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn is_complete(&self, expected: &HashSet<IpNet>) -> bool {
+        self.peer_up && expected.is_subset(&self.prefixes)
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires a running docker daemon"]
+async fn test_frr_bmp_session() {
+    let mut rng = rand::thread_rng();
+    let collector_port: u16 = rng.gen_range(25000..50000);
+    let subnet_octet: u8 = rng.gen_range(1..255);
+    let suffix = format!("{}-{}", std::process::id(), rng.gen::<u16>());
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let handle = BmpServerHandle::default();
+    let server_handle = handle.clone();
+    let server = tokio::spawn(async move {
+        let svc = service_fn(move |req: BmpRequest| {
+            let tx = tx.clone();
+            async move {
+                let _ = tx.send(req);
+                Ok::<Option<BmpServerResponse>, Infallible>(None)
+            }
+        });
+        BmpServer::new(
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), collector_port),
+            server_handle,
+        )
+        .serve(svc)
+        .await
+    });
+    handle.listening().await;
+
+    let monitored_ip = Ipv4Addr::new(172, 30, subnet_octet, 2);
+    let peer_ip = Ipv4Addr::new(172, 30, subnet_octet, 3);
+    let network = Network::create(
+        format!("netgauze-bmp-{suffix}"),
+        &format!("172.30.{subnet_octet}.0/24"),
+    );
+    let monitored = FrrContainer::start(
+        format!("netgauze-bmp-monitored-{suffix}"),
+        &network,
+        monitored_ip,
+        &monitored_config(peer_ip, collector_port),
+    );
+    let _peer = FrrContainer::start(
+        format!("netgauze-bmp-peer-{suffix}"),
+        &network,
+        peer_ip,
+        &peer_config(monitored_ip),
+    );
+
+    let expected: HashSet<IpNet> = [ANNOUNCED_V4.parse().unwrap(), ANNOUNCED_V6.parse().unwrap()]
+        .into_iter()
+        .collect();
+    let mut observed = Observed::default();
+    let result = tokio::time::timeout(TEST_TIMEOUT, async {
+        while let Some(req) = rx.recv().await {
+            match req {
+                Ok(tagged) => {
+                    if let Some(msg) = tagged.value() {
+                        observed.update(msg);
+                    }
+                }
+                Err(err) => panic!("failed to decode BMP message from FRR: {err:?}"),
+            }
+            if observed.is_complete(&expected) {
+                return;
+            }
+        }
+    })
+    .await;
+    assert!(
+        result.is_ok(),
+        "timed out waiting for BMP messages, observed: {observed:?}\nmonitored router logs:\n{}",
+        monitored.logs()
+    );
+
+    handle.shutdown();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(server.is_finished());
+}
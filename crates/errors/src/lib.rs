@@ -0,0 +1,230 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Map the detailed parsing and writing errors of the NetGauze crates into a
+//! small set of [`ErrorCategory`].
+//!
+//! Errors are nested enums, one per PDU, wrapping the error of the inner PDU
+//! down to a leaf variant describing the actual problem. Each of them
+//! implements [`Categorize`], derived along with `LocatedError` and
+//! `WritingError` of `netgauze-serde-macros` or implemented by hand for the
+//! codec errors, with an exhaustive match on its variants:
+//!
+//! - `NomError(ErrorKind::Eof)`, `NomError(ErrorKind::Complete)`, and
+//!   `Incomplete` are [`ErrorCategory::Truncated`].
+//! - Variants carrying an error of an inner PDU take its category.
+//! - Undefined types and codes are [`ErrorCategory::UnknownCode`], and known
+//!   but unsupported values, e.g., versions, are [`ErrorCategory::Unsupported`].
+//! - `IoError` and `StdIOError` are [`ErrorCategory::Io`].
+//! - Anything else is a [`ErrorCategory::SemanticViolation`].
+//!
+//! ```
+//! use netgauze_bgp_pkt::{
+//!     iana::UndefinedBgpMessageType, wire::deserializer::BgpMessageParsingError,
+//! };
+//! use netgauze_errors::{CategorizedError, ErrorCategory};
+//!
+//! let error = BgpMessageParsingError::UndefinedBgpMessageType(UndefinedBgpMessageType(9));
+//! let categorized = CategorizedError::new(error);
+//! assert_eq!(categorized.category(), ErrorCategory::UnknownCode);
+//! assert_eq!(categorized.path().leaf(), Some("UndefinedBgpMessageType"));
+//! ```
+
+use std::fmt::{Display, Formatter};
+
+use nom::error::ErrorKind;
+
+pub use netgauze_parse_utils::{Categorize, ErrorCategory};
+
+/// Names of the error variants from the outermost error to the leaf one
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ErrorPath {
+    variants: Vec<&'static str>,
+    nom_error_kind: Option<ErrorKind>,
+}
+
+impl ErrorPath {
+    /// Follow the [`Categorize::cause`] of `error` down to the leaf variant
+    pub fn of<E: Categorize>(error: &E) -> Self {
+        let mut variants = vec![error.variant()];
+        let mut leaf: &dyn Categorize = error;
+        while let Some(cause) = leaf.cause() {
+            variants.push(cause.variant());
+            leaf = cause;
+        }
+        Self {
+            variants,
+            nom_error_kind: leaf.nom_error_kind(),
+        }
+    }
+
+    pub fn variants(&self) -> &[&'static str] {
+        &self.variants
+    }
+
+    /// The innermost error variant
+    pub fn leaf(&self) -> Option<&'static str> {
+        self.variants.last().copied()
+    }
+
+    /// The nom [`ErrorKind`] when the leaf is raised by nom
+    pub const fn nom_error_kind(&self) -> Option<ErrorKind> {
+        self.nom_error_kind
+    }
+}
+
+impl Display for ErrorPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.variants.join("::"))?;
+        if let Some(kind) = self.nom_error_kind {
+            write!(f, "({kind:?})")?;
+        }
+        Ok(())
+    }
+}
+
+/// A detailed error along with its category
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategorizedError<E> {
+    category: ErrorCategory,
+    path: ErrorPath,
+    error: E,
+}
+
+impl<E: Categorize> CategorizedError<E> {
+    pub fn new(error: E) -> Self {
+        Self {
+            category: error.category(),
+            path: ErrorPath::of(&error),
+            error,
+        }
+    }
+}
+
+impl<E> CategorizedError<E> {
+    pub const fn category(&self) -> ErrorCategory {
+        self.category
+    }
+
+    pub const fn path(&self) -> &ErrorPath {
+        &self.path
+    }
+
+    pub const fn error(&self) -> &E {
+        &self.error
+    }
+
+    pub fn into_inner(self) -> E {
+        self.error
+    }
+}
+
+impl<E: std::fmt::Debug> Display for CategorizedError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} error {}: {:?}", self.category, self.path, self.error)
+    }
+}
+
+impl<E: std::fmt::Debug> std::error::Error for CategorizedError<E> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+    use netgauze_bgp_pkt::wire::{
+        deserializer::BgpMessageParsingError, serializer::BgpMessageWritingError,
+    };
+    use netgauze_bmp_pkt::{
+        codec::{BmpCodec, BmpCodecDecoderError},
+        wire::deserializer::BmpMessageParsingError,
+    };
+    use netgauze_flow_pkt::{
+        codec::{FlowInfoCodec, FlowInfoCodecDecoderError},
+        wire::deserializer::ipfix::IpfixPacketParsingError,
+    };
+    use tokio_util::codec::Decoder;
+
+    fn decode_bmp(wire: &[u8]) -> BmpCodecDecoderError {
+        BmpCodec::default()
+            .decode(&mut BytesMut::from(wire))
+            .unwrap_err()
+    }
+
+    #[test]
+    fn test_categories() {
+        let truncated = BgpMessageParsingError::NomError(ErrorKind::Eof);
+        assert_eq!(truncated.category(), ErrorCategory::Truncated);
+        let malformed = BgpMessageParsingError::NomError(ErrorKind::Verify);
+        assert_eq!(malformed.category(), ErrorCategory::SemanticViolation);
+        assert_eq!(
+            FlowInfoCodecDecoderError::UnsupportedVersion(7).category(),
+            ErrorCategory::Unsupported
+        );
+        assert_eq!(
+            FlowInfoCodecDecoderError::Incomplete(None).category(),
+            ErrorCategory::Truncated
+        );
+        assert_eq!(
+            BmpCodecDecoderError::IoError("reset".to_string()).category(),
+            ErrorCategory::Io
+        );
+        assert_eq!(
+            BmpMessageParsingError::InvalidBmpLength(1).category(),
+            ErrorCategory::SemanticViolation
+        );
+        assert_eq!(
+            IpfixPacketParsingError::UnsupportedVersion(9).category(),
+            ErrorCategory::Unsupported
+        );
+        assert_eq!(
+            BgpMessageWritingError::StdIOError("reset".to_string()).category(),
+            ErrorCategory::Io
+        );
+    }
+
+    #[test]
+    fn test_decoded_errors() {
+        // Undefined BMP message type 200
+        let error = CategorizedError::new(decode_bmp(&[3, 0, 0, 0, 6, 200]));
+        assert_eq!(error.category(), ErrorCategory::UnknownCode);
+        assert_eq!(
+            error.path().variants(),
+            &[
+                "BmpMessageParsingError",
+                "BmpMessageValueError",
+                "UndefinedBmpMessageType"
+            ]
+        );
+
+        // Initiation message with a TLV claiming more bytes than available
+        let error = CategorizedError::new(decode_bmp(&[3, 0, 0, 0, 10, 4, 0, 2, 0, 10]));
+        assert_eq!(error.category(), ErrorCategory::Truncated);
+        assert_eq!(error.path().leaf(), Some("NomError"));
+        assert_eq!(error.path().nom_error_kind(), Some(ErrorKind::Eof));
+
+        // Unsupported flow version
+        let error = FlowInfoCodec::default()
+            .decode(&mut BytesMut::from(
+                &[0, 7, 0, 16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0][..],
+            ))
+            .unwrap_err();
+        let error = CategorizedError::new(error);
+        assert_eq!(error.category(), ErrorCategory::Unsupported);
+        assert_eq!(
+            error.to_string(),
+            "Unsupported error UnsupportedVersion: UnsupportedVersion(7)"
+        );
+    }
+}
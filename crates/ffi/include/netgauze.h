@@ -0,0 +1,75 @@
+/*
+ * Copyright (C) 2024-present The NetGauze Authors.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+ * implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * Decode BGP, BMP, NetFlow V9, and IPFIX messages into JSON.
+ *
+ * The decode functions return a NUL-terminated JSON object of the form
+ * {"messages": [...], "errors": [...]} that must be released with
+ * netgauze_string_free(). NULL is returned only when the arguments are
+ * invalid or decoding panicked.
+ */
+
+#ifndef NETGAUZE_H
+#define NETGAUZE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Stateful decoder of the BMP messages received on a single BMP session */
+typedef struct NetGauzeBmpDecoder NetGauzeBmpDecoder;
+
+/* Stateful decoder of the NetFlow V9 and IPFIX packets of a single exporter */
+typedef struct NetGauzeFlowDecoder NetGauzeFlowDecoder;
+
+/*
+ * Decode the BGP messages in buf, decoding stops at the first malformed
+ * message. asn4 is whether four-octet AS numbers were negotiated.
+ */
+char *netgauze_bgp_decode(const uint8_t *buf, size_t len, bool asn4);
+
+NetGauzeBmpDecoder *netgauze_bmp_decoder_new(void);
+void netgauze_bmp_decoder_free(NetGauzeBmpDecoder *decoder);
+
+/*
+ * Decode the BMP messages completed by the bytes in buf, the bytes of an
+ * incomplete message are kept by the decoder until the next call.
+ */
+char *netgauze_bmp_decode(NetGauzeBmpDecoder *decoder, const uint8_t *buf, size_t len);
+
+NetGauzeFlowDecoder *netgauze_flow_decoder_new(void);
+void netgauze_flow_decoder_free(NetGauzeFlowDecoder *decoder);
+
+/*
+ * Decode the NetFlow V9 and IPFIX packets in buf, usually a single UDP
+ * datagram, using the templates previously decoded by the same decoder.
+ */
+char *netgauze_flow_decode(NetGauzeFlowDecoder *decoder, const uint8_t *buf, size_t len);
+
+/* Release a string returned by one of the decode functions */
+void netgauze_string_free(char *value);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* NETGAUZE_H */
@@ -0,0 +1,389 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! C ABI to decode BGP, BMP, NetFlow V9, and IPFIX messages into JSON, so
+//! collectors written in C or C++ can embed the NetGauze parsers. The
+//! functions are declared in `include/netgauze.h`.
+//!
+//! Every decode function returns a NUL-terminated JSON object of the form
+//! `{"messages": [...], "errors": [...]}` that is owned by the caller and
+//! must be released with [`netgauze_string_free`]. The messages and errors
+//! are serialized with the same `serde` representation used by the packet
+//! crates. `NULL` is returned only when the arguments are invalid or decoding
+//! panicked.
+//!
+//! BMP and flow decoding is stateful: BMP needs the capabilities exchanged by
+//! the monitored peers and flow data sets need the templates previously sent
+//! by the exporter. Hence, a decoder handle is created per BMP session or flow
+//! exporter and fed the received bytes in order. Bytes of incomplete messages
+//! are kept by the handle until the rest of the message is received.
+//!
+//! The same decoders are exposed to Python with the `python` feature, see
+//! `pyproject.toml` to build the `netgauze` Python module with maturin, and to
+//! JavaScript with the `wasm` feature when building for
+//! `wasm32-unknown-unknown`.
+
+use std::{
+    ffi::{c_char, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use bytes::BytesMut;
+use netgauze_bgp_pkt::{
+    codec::BgpCodecDecoderError, wire::deserializer::BgpParsingContext, BgpMessage,
+};
+use netgauze_bmp_pkt::{
+    codec::{BmpCodec, BmpCodecDecoderError},
+    BmpMessage,
+};
+use netgauze_flow_pkt::{
+    codec::{FlowInfoCodec, FlowInfoCodecDecoderError},
+    FlowInfo,
+};
+use netgauze_parse_utils::{LocatedParsingError, ReadablePduWithOneInput, Span};
+use nom::Needed;
+use serde::Serialize;
+use tokio_util::codec::Decoder;
+
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+/// Result of decoding a buffer, serialized to JSON for the caller
+#[derive(Debug, Serialize)]
+struct Decoded<T, E> {
+    messages: Vec<T>,
+    errors: Vec<E>,
+}
+
+impl<T: Serialize, E: Serialize> Decoded<T, E> {
+    const fn new() -> Self {
+        Self {
+            messages: vec![],
+            errors: vec![],
+        }
+    }
+
+    fn into_c_string(self) -> *mut c_char {
+        serde_json::to_string(&self)
+            .ok()
+            .and_then(|json| CString::new(json).ok())
+            .map_or(ptr::null_mut(), CString::into_raw)
+    }
+}
+
+/// Decode all the complete messages in the buffer, errors don't stop the
+/// decoding since the codecs skip over the malformed messages.
+fn decode_all<D: Decoder>(codec: &mut D, buf: &mut BytesMut) -> Decoded<D::Item, D::Error>
+where
+    D::Item: Serialize,
+    D::Error: Serialize,
+{
+    let mut decoded = Decoded::new();
+    loop {
+        match codec.decode(buf) {
+            Ok(Some(msg)) => decoded.messages.push(msg),
+            Ok(None) => break,
+            Err(err) => decoded.errors.push(err),
+        }
+    }
+    decoded
+}
+
+/// # Safety
+/// `buf` must be valid for reads of `len` bytes, it may be `NULL` when `len`
+/// is zero.
+unsafe fn input<'a>(buf: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        Some(&[])
+    } else if buf.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts(buf, len))
+    }
+}
+
+/// Run a decode function, a panic must not unwind into the C caller
+fn guard(decode: impl FnOnce() -> *mut c_char) -> *mut c_char {
+    catch_unwind(AssertUnwindSafe(decode)).unwrap_or(ptr::null_mut())
+}
+
+/// Decode the BGP messages in `input`, stopping at the first malformed one
+fn decode_bgp(input: &[u8], asn4: bool) -> Decoded<BgpMessage, BgpCodecDecoderError> {
+    let mut ctx = BgpParsingContext::default();
+    ctx.set_asn4(asn4);
+    let mut decoded = Decoded::new();
+    let mut span = Span::new(input);
+    while !span.is_empty() {
+        match BgpMessage::from_wire(span, &mut ctx) {
+            Ok((remaining, msg)) => {
+                decoded.messages.push(msg);
+                span = remaining;
+            }
+            Err(err) => {
+                let err = match err {
+                    nom::Err::Incomplete(needed) => {
+                        BgpCodecDecoderError::Incomplete(match needed {
+                            Needed::Unknown => None,
+                            Needed::Size(size) => Some(size.get()),
+                        })
+                    }
+                    nom::Err::Error(err) | nom::Err::Failure(err) => {
+                        BgpCodecDecoderError::BgpMessageParsingError(err.error().clone())
+                    }
+                };
+                decoded.errors.push(err);
+                break;
+            }
+        }
+    }
+    decoded
+}
+
+/// Decode the BGP messages in `buf`, decoding stops at the first malformed
+/// message since the rest of the buffer can't be trusted to be aligned with
+/// the beginning of a message. `asn4` is whether four-octet AS numbers were
+/// negotiated for the session.
+///
+/// # Safety
+/// `buf` must be valid for reads of `len` bytes, it may be `NULL` when `len`
+/// is zero. The returned string must be released with
+/// [`netgauze_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn netgauze_bgp_decode(
+    buf: *const u8,
+    len: usize,
+    asn4: bool,
+) -> *mut c_char {
+    let Some(input) = input(buf, len) else {
+        return ptr::null_mut();
+    };
+    guard(|| decode_bgp(input, asn4).into_c_string())
+}
+
+/// Stateful decoder of the BMP messages received on a single BMP session
+#[derive(Debug, Default)]
+pub struct NetGauzeBmpDecoder {
+    codec: BmpCodec,
+    buf: BytesMut,
+}
+
+impl NetGauzeBmpDecoder {
+    fn decode(&mut self, input: &[u8]) -> Decoded<BmpMessage, BmpCodecDecoderError> {
+        self.buf.extend_from_slice(input);
+        decode_all(&mut self.codec, &mut self.buf)
+    }
+}
+
+/// Create a BMP decoder, to be released with [`netgauze_bmp_decoder_free`]
+#[no_mangle]
+pub extern "C" fn netgauze_bmp_decoder_new() -> *mut NetGauzeBmpDecoder {
+    Box::into_raw(Box::default())
+}
+
+/// Release a BMP decoder created with [`netgauze_bmp_decoder_new`]
+///
+/// # Safety
+/// `decoder` must be `NULL` or returned by [`netgauze_bmp_decoder_new`] and
+/// not released before.
+#[no_mangle]
+pub unsafe extern "C" fn netgauze_bmp_decoder_free(decoder: *mut NetGauzeBmpDecoder) {
+    if !decoder.is_null() {
+        drop(Box::from_raw(decoder));
+    }
+}
+
+/// Decode the BMP messages completed by the bytes in `buf`
+///
+/// # Safety
+/// `decoder` must be returned by [`netgauze_bmp_decoder_new`] and not used
+/// concurrently. `buf` must be valid for reads of `len` bytes, it may be
+/// `NULL` when `len` is zero. The returned string must be released with
+/// [`netgauze_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn netgauze_bmp_decode(
+    decoder: *mut NetGauzeBmpDecoder,
+    buf: *const u8,
+    len: usize,
+) -> *mut c_char {
+    let (Some(decoder), Some(input)) = (decoder.as_mut(), input(buf, len)) else {
+        return ptr::null_mut();
+    };
+    guard(|| decoder.decode(input).into_c_string())
+}
+
+/// Stateful decoder of the NetFlow V9 and IPFIX packets received from a
+/// single exporter
+#[derive(Debug, Default)]
+pub struct NetGauzeFlowDecoder {
+    codec: FlowInfoCodec,
+    buf: BytesMut,
+}
+
+impl NetGauzeFlowDecoder {
+    fn decode(&mut self, input: &[u8]) -> Decoded<FlowInfo, FlowInfoCodecDecoderError> {
+        self.buf.extend_from_slice(input);
+        decode_all(&mut self.codec, &mut self.buf)
+    }
+}
+
+/// Create a flow decoder, to be released with [`netgauze_flow_decoder_free`]
+#[no_mangle]
+pub extern "C" fn netgauze_flow_decoder_new() -> *mut NetGauzeFlowDecoder {
+    Box::into_raw(Box::default())
+}
+
+/// Release a flow decoder created with [`netgauze_flow_decoder_new`]
+///
+/// # Safety
+/// `decoder` must be `NULL` or returned by [`netgauze_flow_decoder_new`] and
+/// not released before.
+#[no_mangle]
+pub unsafe extern "C" fn netgauze_flow_decoder_free(decoder: *mut NetGauzeFlowDecoder) {
+    if !decoder.is_null() {
+        drop(Box::from_raw(decoder));
+    }
+}
+
+/// Decode the NetFlow V9 and IPFIX packets in `buf`, usually a single UDP
+/// datagram. Data sets are decoded using the templates previously decoded by
+/// the same decoder.
+///
+/// # Safety
+/// `decoder` must be returned by [`netgauze_flow_decoder_new`] and not used
+/// concurrently. `buf` must be valid for reads of `len` bytes, it may be
+/// `NULL` when `len` is zero. The returned string must be released with
+/// [`netgauze_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn netgauze_flow_decode(
+    decoder: *mut NetGauzeFlowDecoder,
+    buf: *const u8,
+    len: usize,
+) -> *mut c_char {
+    let (Some(decoder), Some(input)) = (decoder.as_mut(), input(buf, len)) else {
+        return ptr::null_mut();
+    };
+    guard(|| decoder.decode(input).into_c_string())
+}
+
+/// Release a string returned by the decode functions
+///
+/// # Safety
+/// `value` must be `NULL` or returned by one of the decode functions and not
+/// released before.
+#[no_mangle]
+pub unsafe extern "C" fn netgauze_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netgauze_bgp_pkt::test_vectors::bgp_messages;
+    use netgauze_bmp_pkt::test_vectors::bmp_messages;
+    use netgauze_flow_pkt::test_vectors::ipfix_packets;
+    use serde_json::{json, Value};
+    use std::ffi::CStr;
+
+    /// Take the ownership of the returned string and parse it
+    fn json(value: *mut c_char) -> Value {
+        assert!(!value.is_null());
+        let parsed = serde_json::from_str(unsafe { CStr::from_ptr(value) }.to_str().unwrap());
+        unsafe { netgauze_string_free(value) };
+        parsed.unwrap()
+    }
+
+    #[test]
+    fn test_bgp_decode() {
+        let vectors = bgp_messages();
+        let wire: Vec<u8> = vectors.iter().flat_map(|v| v.wire().clone()).collect();
+        let expected: Vec<&BgpMessage> = vectors.iter().map(|v| v.expected()).collect();
+
+        let decoded = json(unsafe { netgauze_bgp_decode(wire.as_ptr(), wire.len(), true) });
+        assert_eq!(
+            decoded,
+            json!({"messages": expected, "errors": Vec::<Value>::new()})
+        );
+
+        // Decoding stops at the truncated message
+        let truncated = unsafe { netgauze_bgp_decode(wire.as_ptr(), wire.len() - 1, true) };
+        let truncated = json(truncated);
+        assert_eq!(
+            truncated["messages"].as_array().unwrap().len(),
+            expected.len() - 1
+        );
+        assert_eq!(truncated["errors"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_bmp_decode() {
+        let decoder = netgauze_bmp_decoder_new();
+        for vector in bmp_messages() {
+            // Split each message over two calls
+            let (first, second) = vector.wire().split_at(vector.wire().len() / 2);
+            let decoded =
+                json(unsafe { netgauze_bmp_decode(decoder, first.as_ptr(), first.len()) });
+            assert_eq!(decoded, json!({"messages": [], "errors": []}));
+            let decoded =
+                json(unsafe { netgauze_bmp_decode(decoder, second.as_ptr(), second.len()) });
+            assert_eq!(
+                decoded,
+                json!({"messages": [vector.expected()], "errors": []}),
+                "{}",
+                vector.name()
+            );
+        }
+
+        // Undefined BMP version
+        let wire = [0xff, 0x00, 0x00, 0x00, 0x06, 0x04];
+        let decoded = json(unsafe { netgauze_bmp_decode(decoder, wire.as_ptr(), wire.len()) });
+        assert!(!decoded["errors"].as_array().unwrap().is_empty());
+        unsafe { netgauze_bmp_decoder_free(decoder) };
+    }
+
+    #[test]
+    fn test_flow_decode() {
+        let decoder = netgauze_flow_decoder_new();
+        for vector in ipfix_packets() {
+            let wire = vector.wire();
+            let decoded = json(unsafe { netgauze_flow_decode(decoder, wire.as_ptr(), wire.len()) });
+            assert_eq!(
+                decoded,
+                json!({"messages": [FlowInfo::IPFIX(vector.expected().clone())], "errors": []}),
+                "{}",
+                vector.name()
+            );
+        }
+        unsafe { netgauze_flow_decoder_free(decoder) };
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        unsafe {
+            assert!(netgauze_bgp_decode(ptr::null(), 1, true).is_null());
+            assert!(netgauze_bmp_decode(ptr::null_mut(), ptr::null(), 0).is_null());
+            assert!(netgauze_flow_decode(ptr::null_mut(), ptr::null(), 0).is_null());
+            netgauze_bmp_decoder_free(ptr::null_mut());
+            netgauze_flow_decoder_free(ptr::null_mut());
+            netgauze_string_free(ptr::null_mut());
+        }
+        let empty = json(unsafe { netgauze_bgp_decode(ptr::null(), 0, true) });
+        assert_eq!(empty, json!({"messages": [], "errors": []}));
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Python bindings of the decoders, exposed as the `netgauze` Python module.
+//! The decoded messages are returned as a `dict` with the same structure as
+//! the JSON returned by the C functions.
+//!
+//! ```python
+//! import netgauze
+//!
+//! decoder = netgauze.BmpDecoder()
+//! for msg in decoder.decode(data)["messages"]:
+//!     print(msg)
+//! ```
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+use serde::Serialize;
+
+use crate::{decode_bgp, Decoded, NetGauzeBmpDecoder, NetGauzeFlowDecoder};
+
+/// Convert to Python objects by the way of JSON, so the structure is the same
+/// for all the bindings
+fn to_python<T: Serialize, E: Serialize>(
+    py: Python<'_>,
+    decoded: &Decoded<T, E>,
+) -> PyResult<PyObject> {
+    let json =
+        serde_json::to_string(decoded).map_err(|err| PyValueError::new_err(err.to_string()))?;
+    Ok(py.import("json")?.call_method1("loads", (json,))?.unbind())
+}
+
+/// Decode the BGP messages in `data`, decoding stops at the first malformed
+/// message
+#[pyfunction]
+#[pyo3(name = "decode_bgp", signature = (data, asn4 = true))]
+fn py_decode_bgp(py: Python<'_>, data: &[u8], asn4: bool) -> PyResult<PyObject> {
+    to_python(py, &decode_bgp(data, asn4))
+}
+
+/// Python `BmpDecoder` class wrapping [`NetGauzeBmpDecoder`]
+#[pyclass(name = "BmpDecoder")]
+#[derive(Debug, Default)]
+struct PyBmpDecoder(NetGauzeBmpDecoder);
+
+#[pymethods]
+impl PyBmpDecoder {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode the BMP messages completed by the bytes in `data`
+    fn decode(&mut self, py: Python<'_>, data: &[u8]) -> PyResult<PyObject> {
+        to_python(py, &self.0.decode(data))
+    }
+}
+
+/// Python `FlowDecoder` class wrapping [`NetGauzeFlowDecoder`], it can only be
+/// used from the thread that created it since the templates aren't shared
+/// between threads
+#[pyclass(name = "FlowDecoder", unsendable)]
+#[derive(Debug, Default)]
+struct PyFlowDecoder(NetGauzeFlowDecoder);
+
+#[pymethods]
+impl PyFlowDecoder {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode the NetFlow V9 and IPFIX packets in `data`
+    fn decode(&mut self, py: Python<'_>, data: &[u8]) -> PyResult<PyObject> {
+        to_python(py, &self.0.decode(data))
+    }
+}
+
+#[pymodule]
+fn netgauze(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(py_decode_bgp, m)?)?;
+    m.add_class::<PyBmpDecoder>()?;
+    m.add_class::<PyFlowDecoder>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netgauze_bmp_pkt::test_vectors::bmp_messages;
+    use pyo3::types::{PyDict, PyList};
+
+    #[test]
+    fn test_python_bmp_decoder() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::new(py, "netgauze").unwrap();
+            netgauze(&module).unwrap();
+            let decoder = module.getattr("BmpDecoder").unwrap().call0().unwrap();
+            for vector in bmp_messages() {
+                let decoded = decoder
+                    .call_method1("decode", (vector.wire().as_slice(),))
+                    .unwrap();
+                let decoded = decoded.downcast::<PyDict>().unwrap();
+                let messages = decoded.get_item("messages").unwrap().unwrap();
+                assert_eq!(messages.downcast::<PyList>().unwrap().len(), 1);
+                let errors = decoded.get_item("errors").unwrap().unwrap();
+                assert!(errors.downcast::<PyList>().unwrap().is_empty());
+            }
+        });
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! WebAssembly bindings of the decoders, to decode messages in the browser,
+//! e.g., pasted from a packet capture. Each function accepts either the raw
+//! bytes or a hex string, and returns the same JSON as the C functions.
+//!
+//! ```shell
+//! wasm-pack build crates/ffi --target web -- --features wasm
+//! ```
+
+use netgauze_parse_utils::parse_hex;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{decode_bgp, Decoded, NetGauzeBmpDecoder, NetGauzeFlowDecoder};
+
+fn to_json<T: Serialize, E: Serialize>(decoded: &Decoded<T, E>) -> Result<String, JsError> {
+    Ok(serde_json::to_string(decoded)?)
+}
+
+/// Decode the BGP messages in `input`, decoding stops at the first malformed
+/// message
+#[wasm_bindgen(js_name = decodeBgp)]
+pub fn wasm_decode_bgp(input: &[u8], asn4: bool) -> Result<String, JsError> {
+    to_json(&decode_bgp(input, asn4))
+}
+
+/// Decode the BGP messages in the hex string `input`
+#[wasm_bindgen(js_name = decodeBgpHex)]
+pub fn wasm_decode_bgp_hex(input: &str, asn4: bool) -> Result<String, JsError> {
+    to_json(&decode_bgp(&parse_hex(input)?, asn4))
+}
+
+/// JavaScript `BmpDecoder` class wrapping [`NetGauzeBmpDecoder`]
+#[wasm_bindgen(js_name = BmpDecoder)]
+#[derive(Debug, Default)]
+pub struct WasmBmpDecoder(NetGauzeBmpDecoder);
+
+#[wasm_bindgen(js_class = BmpDecoder)]
+impl WasmBmpDecoder {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode the BMP messages completed by the bytes in `input`
+    pub fn decode(&mut self, input: &[u8]) -> Result<String, JsError> {
+        to_json(&self.0.decode(input))
+    }
+
+    /// Decode the BMP messages completed by the bytes in the hex string `input`
+    #[wasm_bindgen(js_name = decodeHex)]
+    pub fn decode_hex(&mut self, input: &str) -> Result<String, JsError> {
+        to_json(&self.0.decode(&parse_hex(input)?))
+    }
+}
+
+/// JavaScript `FlowDecoder` class wrapping [`NetGauzeFlowDecoder`]
+#[wasm_bindgen(js_name = FlowDecoder)]
+#[derive(Debug, Default)]
+pub struct WasmFlowDecoder(NetGauzeFlowDecoder);
+
+#[wasm_bindgen(js_class = FlowDecoder)]
+impl WasmFlowDecoder {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode the NetFlow V9 and IPFIX packets in `input`
+    pub fn decode(&mut self, input: &[u8]) -> Result<String, JsError> {
+        to_json(&self.0.decode(input))
+    }
+
+    /// Decode the NetFlow V9 and IPFIX packets in the hex string `input`
+    #[wasm_bindgen(js_name = decodeHex)]
+    pub fn decode_hex(&mut self, input: &str) -> Result<String, JsError> {
+        to_json(&self.0.decode(&parse_hex(input)?))
+    }
+}
+
+/// Run with `wasm-pack test --node crates/ffi --features wasm`, `Instant::now`
+/// panics on `wasm32-unknown-unknown`, so the code paths reading the clock are
+/// covered here rather than only compiled
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+    use netgauze_flow_pkt::{
+        codec::{FlowInfoCodec, TemplateBufferPolicy},
+        test_vectors::ipfix_packets,
+        udp::UdpSessionManager,
+        FlowInfo,
+    };
+    use netgauze_parse_utils::raw_frame::{RawFramePolicy, RawFrameSampler};
+    use std::time::Duration;
+    use tokio_util::codec::Decoder;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn test_flow_decoder() {
+        let mut decoder = WasmFlowDecoder::new();
+        for vector in ipfix_packets() {
+            let decoded: serde_json::Value =
+                serde_json::from_str(&decoder.decode(vector.wire()).unwrap()).unwrap();
+            assert_eq!(
+                decoded["messages"],
+                serde_json::json!([FlowInfo::IPFIX(vector.expected().clone())])
+            );
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_template_buffering() {
+        let vectors = ipfix_packets();
+        let mut codec = FlowInfoCodec::with_template_buffer_policy(TemplateBufferPolicy::new(
+            64 * 1024,
+            Duration::from_secs(60),
+        ));
+        let mut buf = BytesMut::from(vectors[1].wire().as_slice());
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert_eq!(codec.pending_packets(), 1);
+        codec.set_template_buffer_policy(TemplateBufferPolicy::default());
+        assert_eq!(codec.pending_packets(), 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_udp_sessions() {
+        let vectors = ipfix_packets();
+        let mut manager = UdpSessionManager::new();
+        let exporter = "192.0.2.1:2055".parse().unwrap();
+        assert_eq!(
+            manager.decode(exporter, vectors[0].wire()).unwrap(),
+            FlowInfo::IPFIX(vectors[0].expected().clone())
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_raw_frame_sampler() {
+        let mut sampler =
+            RawFrameSampler::new(RawFramePolicy::new(16, 1, Duration::from_secs(3600)));
+        assert!(sampler.capture(&[0xff; 32]).is_some());
+        assert!(sampler.capture(&[0xff; 32]).is_none());
+        assert_eq!(sampler.suppressed(), 1);
+    }
+}
@@ -5,6 +5,16 @@ use criterion::{criterion_group, criterion_main, Criterion};
 use netgauze_flow_pkt::{ipfix::IpfixPacket, FieldSpecifier};
 use netgauze_parse_utils::{ReadablePduWithOneInput, Span, WritablePduWithOneInput};
 
+// jemalloc takes precedence when both allocators are enabled, e.g., with
+// `--all-features`
+#[cfg(feature = "bench-jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+#[cfg(all(feature = "bench-mimalloc", not(feature = "bench-jemalloc")))]
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
 const IPFIX_PKT_TEMPLATE_RAW: &[u8] = &[
     0x00, 0x0a, // Version
     0x00, 0x74, // Length
@@ -0,0 +1,124 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Capture raw IPFIX and NetFlow v9 packets along with the templates learned
+//! from the exporter, so data sets can be decoded later without the packets
+//! that carried their templates.
+//!
+//! Captures use the [`netgauze_parse_utils::capture`] container, with the
+//! [`TemplatesState`] serialized as JSON.
+
+use std::io::{Read, Write};
+
+use bytes::BytesMut;
+use netgauze_parse_utils::capture::{Capture, CaptureError, CaptureKind};
+use tokio_util::codec::Decoder;
+
+use crate::{
+    codec::{FlowInfoCodec, FlowInfoCodecDecoderError, TemplatesState},
+    FlowInfo,
+};
+
+/// Raw flow packets and the templates known before the first one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlowCapture {
+    templates: TemplatesState,
+    data: Vec<u8>,
+}
+
+impl FlowCapture {
+    pub const fn new(templates: TemplatesState, data: Vec<u8>) -> Self {
+        Self { templates, data }
+    }
+
+    /// Capture `data` with the templates currently known by `codec`
+    pub fn from_codec(codec: &FlowInfoCodec, data: Vec<u8>) -> Self {
+        Self::new(codec.templates_state(), data)
+    }
+
+    pub const fn templates(&self) -> &TemplatesState {
+        &self.templates
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), CaptureError> {
+        let context = serde_json::to_vec(&self.templates)
+            .map_err(|err| CaptureError::ContextError(err.to_string()))?;
+        Capture::new(CaptureKind::Flow, context, self.data.clone()).write(writer)
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, CaptureError> {
+        let capture = Capture::read(reader)?;
+        if capture.kind() != CaptureKind::Flow {
+            return Err(CaptureError::UnexpectedKind(capture.kind()));
+        }
+        let templates = serde_json::from_slice(capture.context())
+            .map_err(|err| CaptureError::ContextError(err.to_string()))?;
+        Ok(Self::new(templates, capture.data().to_vec()))
+    }
+
+    /// Decode the captured packets starting from the captured templates,
+    /// stopping at the first error
+    pub fn decode(&self) -> Result<Vec<FlowInfo>, FlowInfoCodecDecoderError> {
+        let mut codec = FlowInfoCodec::default();
+        codec.set_templates_state(self.templates.clone());
+        let mut buf = BytesMut::from(self.data.as_slice());
+        let mut packets = vec![];
+        while let Some(info) = codec.decode(&mut buf)? {
+            packets.push(info);
+        }
+        if !buf.is_empty() {
+            return Err(FlowInfoCodecDecoderError::Incomplete(Some(buf.len())));
+        }
+        Ok(packets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_vectors::ipfix_packets;
+
+    #[test]
+    fn test_flow_capture() {
+        let vectors = ipfix_packets();
+        let (template, data) = (&vectors[0], &vectors[1]);
+        let mut codec = FlowInfoCodec::default();
+        codec
+            .decode(&mut BytesMut::from(template.wire().as_slice()))
+            .unwrap();
+
+        let capture = FlowCapture::from_codec(&codec, data.wire().to_vec());
+        assert_eq!(capture.templates().ipfix.len(), 1);
+        let mut buf = vec![];
+        capture.write(&mut buf).unwrap();
+        let read = FlowCapture::read(&mut buf.as_slice()).unwrap();
+        assert_eq!(read, capture);
+        assert_eq!(
+            read.decode(),
+            Ok(vec![FlowInfo::IPFIX(data.expected().clone())])
+        );
+
+        // Without the templates, the data set can't be decoded
+        assert!(
+            FlowCapture::new(TemplatesState::default(), data.wire().to_vec())
+                .decode()
+                .is_err()
+        );
+    }
+}
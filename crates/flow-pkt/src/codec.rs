@@ -17,9 +17,19 @@
 //! It works with [`FlowInfo`] which is enum that combine both IPFIX and Netflow
 //! V9 into one object to make it easier to handle.
 
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, VecDeque},
+    rc::Rc,
+    time::Duration,
+};
+// `std::time::Instant::now` panics on `wasm32-unknown-unknown`
+use web_time::Instant;
+
 use byteorder::{ByteOrder, NetworkEndian};
-use bytes::{Buf, BufMut, BytesMut};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use nom::Needed;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use tokio_util::codec::{Decoder, Encoder};
 use tracing::instrument;
@@ -28,7 +38,7 @@ use crate::{
     ipfix, netflow,
     wire::{
         deserializer::{
-            ipfix::{IpfixPacketParsingError, IPFIX_HEADER_LENGTH},
+            ipfix::{IpfixPacketParsingError, SetParsingError, IPFIX_HEADER_LENGTH},
             netflow::NetFlowV9PacketParsingError,
         },
         serializer::{
@@ -38,16 +48,91 @@ use crate::{
     FlowInfo,
 };
 use netgauze_parse_utils::{
-    LocatedParsingError, ReadablePduWithOneInput, Span, WritablePduWithOneInput,
+    raw_frame::{RawFrame, RawFramePolicy, RawFrameSampler},
+    Categorize, ErrorCategory, LocatedParsingError, ReadablePduWithOneInput, Span,
+    WritablePduWithOneInput,
 };
 
-#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FlowInfoCodecDecoderError {
     IoError(String),
     Incomplete(Option<usize>),
     UnsupportedVersion(u16),
-    IpfixParsingError(IpfixPacketParsingError),
-    NetFlowV9ParingError(NetFlowV9PacketParsingError),
+    /// The `frame` is the packet that failed to parse, only captured when a
+    /// policy is set with [`FlowInfoCodec::set_raw_frame_policy`] and within
+    /// its rate limit
+    IpfixParsingError {
+        error: IpfixPacketParsingError,
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Option::is_none")
+        )]
+        frame: Option<RawFrame>,
+    },
+    /// Same as [`FlowInfoCodecDecoderError::IpfixParsingError`], the `frame`
+    /// holds the bytes that failed to parse
+    NetFlowV9ParingError {
+        error: NetFlowV9PacketParsingError,
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Option::is_none")
+        )]
+        frame: Option<RawFrame>,
+    },
+}
+
+impl FlowInfoCodecDecoderError {
+    /// The packet that failed to parse, if captured
+    pub const fn raw_frame(&self) -> Option<&RawFrame> {
+        match self {
+            Self::IpfixParsingError { frame, .. } | Self::NetFlowV9ParingError { frame, .. } => {
+                frame.as_ref()
+            }
+            _ => None,
+        }
+    }
+
+    /// Attach the `packet` that failed to parse to the parsing errors, when
+    /// captured by `sampler`
+    fn with_frame(mut self, sampler: &mut RawFrameSampler, packet: &[u8]) -> Self {
+        if let Self::IpfixParsingError { frame, .. } | Self::NetFlowV9ParingError { frame, .. } =
+            &mut self
+        {
+            *frame = sampler.capture(packet);
+        }
+        self
+    }
+}
+
+impl Categorize for FlowInfoCodecDecoderError {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            Self::IoError(_) => ErrorCategory::Io,
+            Self::Incomplete(_) => ErrorCategory::Truncated,
+            Self::UnsupportedVersion(_) => ErrorCategory::Unsupported,
+            Self::IpfixParsingError { error, .. } => error.category(),
+            Self::NetFlowV9ParingError { error, .. } => error.category(),
+        }
+    }
+
+    fn variant(&self) -> &'static str {
+        match self {
+            Self::IoError(_) => "IoError",
+            Self::Incomplete(_) => "Incomplete",
+            Self::UnsupportedVersion(_) => "UnsupportedVersion",
+            Self::IpfixParsingError { .. } => "IpfixParsingError",
+            Self::NetFlowV9ParingError { .. } => "NetFlowV9ParingError",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn Categorize> {
+        match self {
+            Self::IpfixParsingError { error, .. } => Some(error),
+            Self::NetFlowV9ParingError { error, .. } => Some(error),
+            Self::IoError(_) | Self::Incomplete(_) | Self::UnsupportedVersion(_) => None,
+        }
+    }
 }
 
 impl From<std::io::Error> for FlowInfoCodecDecoderError {
@@ -56,24 +141,361 @@ impl From<std::io::Error> for FlowInfoCodecDecoderError {
     }
 }
 
+/// How long and how much IPFIX packets referencing a template that is not
+/// received yet are buffered by [`FlowInfoCodec`]. Exporters may send data
+/// sets before the templates describing them, e.g., right after the collector
+/// restarts, so instead of failing, such packets are buffered and decoded
+/// again once new templates are received.
+///
+/// Packets are dropped, with a `tracing` warning event, when they wait longer
+/// than `max_wait` or to keep the buffered bytes under `max_buffered_bytes`.
+/// The default policy disables buffering, i.e., packets referencing unknown
+/// templates are reported as decoding errors.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TemplateBufferPolicy {
+    max_buffered_bytes: usize,
+    max_wait: Duration,
+}
+
+impl TemplateBufferPolicy {
+    pub const fn new(max_buffered_bytes: usize, max_wait: Duration) -> Self {
+        Self {
+            max_buffered_bytes,
+            max_wait,
+        }
+    }
+
+    pub const fn max_buffered_bytes(&self) -> usize {
+        self.max_buffered_bytes
+    }
+
+    pub const fn max_wait(&self) -> Duration {
+        self.max_wait
+    }
+
+    pub const fn is_enabled(&self) -> bool {
+        self.max_buffered_bytes > 0
+    }
+}
+
+/// Summary of a template learned by [`FlowInfoCodec`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TemplateSummary {
+    /// Flow protocol version, 9 for NetFlow v9 and 10 for IPFIX
+    pub version: u16,
+    /// Observation domain of IPFIX templates, `None` for NetFlow v9
+    pub observation_domain_id: Option<u32>,
+    pub template_id: u16,
+    /// Number of scope fields, non-zero for option templates
+    pub scope_fields: usize,
+    pub fields: usize,
+}
+
+/// Copy of the templates learned by a [`FlowInfoCodec`], keyed by template ID,
+/// e.g., to decode packets captured in the middle of a session
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TemplatesState {
+    /// IPFIX templates per observation domain
+    pub ipfix: BTreeMap<u32, BTreeMap<u16, ipfix::DecodingTemplate>>,
+    pub netflow_v9: BTreeMap<u16, netflow::DecodingTemplate>,
+}
+
+/// IPFIX packet waiting for a template
+#[derive(Debug)]
+struct PendingPacket {
+    received: Instant,
+    template_id: u16,
+    packet: Bytes,
+}
+
+impl PendingPacket {
+    fn observation_domain_id(&self) -> u32 {
+        NetworkEndian::read_u32(&self.packet[12..16])
+    }
+
+    /// Emit a structured event for dropping the packet
+    fn report_drop(&self, reason: &str) {
+        tracing::warn!(
+            template_id = self.template_id,
+            observation_domain_id = self.observation_domain_id(),
+            bytes = self.packet.len(),
+            waited_ms = self.received.elapsed().as_millis() as u64,
+            reason,
+            "dropped IPFIX packet waiting for a template"
+        );
+    }
+}
+
 /// [`FlowInfo`] is either IPFIX or Netflow V9 packet.
 /// This struct keep track of the decode process, and keep a cache of the
 /// templates sent by client.
+///
+/// Templates are learned from the Template and Options Template Sets, then
+/// used to decode the Data Sets that follow. As in
+/// [RFC 7011](https://datatracker.ietf.org/doc/html/rfc7011#section-8), IPFIX
+/// templates are scoped to the observation domain of the packet that carried
+/// them. One codec is expected per exporter transport session.
 #[derive(Debug, Default)]
 pub struct FlowInfoCodec {
     /// Helper to track in the decoder if we are inside a message or not
     in_message: bool,
     netflow_v9_templates_map: netflow::TemplatesMap,
-    ipfix_templates_map: ipfix::TemplatesMap,
+    /// IPFIX templates per observation domain
+    ipfix_templates_maps: HashMap<u32, ipfix::TemplatesMap>,
+    template_buffer_policy: TemplateBufferPolicy,
+    /// IPFIX packets waiting for a template, oldest first
+    pending: VecDeque<PendingPacket>,
+    pending_bytes: usize,
+    /// Buffered packets decoded after their template arrived
+    ready: VecDeque<FlowInfo>,
+    dropped_packets: u64,
+    /// Captures the packets that failed to parse, disabled by default
+    raw_frames: RawFrameSampler,
+}
+
+impl FlowInfoCodec {
+    pub fn with_template_buffer_policy(template_buffer_policy: TemplateBufferPolicy) -> Self {
+        Self {
+            template_buffer_policy,
+            ..Default::default()
+        }
+    }
+
+    pub const fn template_buffer_policy(&self) -> TemplateBufferPolicy {
+        self.template_buffer_policy
+    }
+
+    pub fn set_template_buffer_policy(&mut self, template_buffer_policy: TemplateBufferPolicy) {
+        self.template_buffer_policy = template_buffer_policy;
+        self.expire_pending(Instant::now());
+    }
+
+    /// Policy for capturing the packets that failed to parse
+    pub const fn raw_frame_policy(&self) -> RawFramePolicy {
+        self.raw_frames.policy()
+    }
+
+    /// Attach the packets that failed to parse, truncated and rate limited
+    /// according to `policy`, to the errors as
+    /// [`FlowInfoCodecDecoderError::IpfixParsingError`] and
+    /// [`FlowInfoCodecDecoderError::NetFlowV9ParingError`]
+    pub fn set_raw_frame_policy(&mut self, policy: RawFramePolicy) {
+        self.raw_frames = RawFrameSampler::new(policy);
+    }
+
+    /// Number of packets that failed to parse but were not captured because
+    /// of the rate limit of the raw frame policy
+    pub const fn suppressed_raw_frames(&self) -> u64 {
+        self.raw_frames.suppressed()
+    }
+
+    /// Number of IPFIX packets waiting for a template
+    pub fn pending_packets(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Total size of the IPFIX packets waiting for a template
+    pub const fn pending_bytes(&self) -> usize {
+        self.pending_bytes
+    }
+
+    /// Number of buffered IPFIX packets dropped so far without receiving their
+    /// template
+    pub const fn dropped_packets(&self) -> u64 {
+        self.dropped_packets
+    }
+
+    /// Number of IPFIX and NetFlow v9 templates learned so far
+    pub fn templates_count(&self) -> usize {
+        self.ipfix_templates_maps
+            .values()
+            .map(|templates| templates.borrow().len())
+            .sum::<usize>()
+            + self.netflow_v9_templates_map.borrow().len()
+    }
+
+    /// IPFIX templates learned so far for the observation domain
+    pub fn ipfix_templates_map(&self, observation_domain_id: u32) -> Option<ipfix::TemplatesMap> {
+        self.ipfix_templates_maps
+            .get(&observation_domain_id)
+            .cloned()
+    }
+
+    /// IPFIX templates of the observation domain, created when the domain is
+    /// not known yet
+    fn ipfix_templates_map_mut(&mut self, observation_domain_id: u32) -> ipfix::TemplatesMap {
+        self.ipfix_templates_maps
+            .entry(observation_domain_id)
+            .or_default()
+            .clone()
+    }
+
+    /// IPFIX and NetFlow v9 templates learned so far, sorted by version,
+    /// observation domain and template ID
+    pub fn templates(&self) -> Vec<TemplateSummary> {
+        let netflow_v9 = self.netflow_v9_templates_map.borrow();
+        let mut templates = netflow_v9
+            .iter()
+            .map(|(id, template)| TemplateSummary {
+                version: 9,
+                observation_domain_id: None,
+                template_id: *id,
+                scope_fields: template.0.len(),
+                fields: template.1.len(),
+            })
+            .collect::<Vec<_>>();
+        for (domain, ipfix) in &self.ipfix_templates_maps {
+            templates.extend(ipfix.borrow().iter().map(|(id, template)| TemplateSummary {
+                version: 10,
+                observation_domain_id: Some(*domain),
+                template_id: *id,
+                scope_fields: template.0.len(),
+                fields: template.1.len(),
+            }));
+        }
+        templates.sort_by_key(|template| {
+            (
+                template.version,
+                template.observation_domain_id,
+                template.template_id,
+            )
+        });
+        templates
+    }
+
+    /// Copy of the IPFIX and NetFlow v9 templates learned so far
+    pub fn templates_state(&self) -> TemplatesState {
+        TemplatesState {
+            ipfix: self
+                .ipfix_templates_maps
+                .iter()
+                .map(|(domain, templates)| {
+                    let templates = templates
+                        .borrow()
+                        .iter()
+                        .map(|(id, template)| (*id, template.as_ref().clone()))
+                        .collect();
+                    (*domain, templates)
+                })
+                .collect(),
+            netflow_v9: self
+                .netflow_v9_templates_map
+                .borrow()
+                .iter()
+                .map(|(id, template)| (*id, template.as_ref().clone()))
+                .collect(),
+        }
+    }
+
+    /// Replace the templates learned so far
+    pub fn set_templates_state(&mut self, state: TemplatesState) {
+        self.ipfix_templates_maps = state
+            .ipfix
+            .into_iter()
+            .map(|(domain, templates)| {
+                let templates = templates
+                    .into_iter()
+                    .map(|(id, template)| (id, Rc::new(template)))
+                    .collect();
+                (domain, Rc::new(RefCell::new(templates)))
+            })
+            .collect();
+        *self.netflow_v9_templates_map.borrow_mut() = state
+            .netflow_v9
+            .into_iter()
+            .map(|(id, template)| (id, Rc::new(template)))
+            .collect();
+    }
+
+    fn drop_oldest(&mut self, reason: &str) {
+        if let Some(pending) = self.pending.pop_front() {
+            pending.report_drop(reason);
+            self.pending_bytes -= pending.packet.len();
+            self.dropped_packets += 1;
+        }
+    }
+
+    /// Drop the packets that waited longer than allowed by the policy
+    fn expire_pending(&mut self, now: Instant) {
+        while let Some(pending) = self.pending.front() {
+            if now.duration_since(pending.received) < self.template_buffer_policy.max_wait {
+                break;
+            }
+            self.drop_oldest("expired");
+        }
+        while self.pending_bytes > self.template_buffer_policy.max_buffered_bytes {
+            self.drop_oldest("buffer_full");
+        }
+    }
+
+    fn buffer_packet(&mut self, template_id: u16, packet: Bytes) {
+        let pending = PendingPacket {
+            received: Instant::now(),
+            template_id,
+            packet,
+        };
+        if pending.packet.len() > self.template_buffer_policy.max_buffered_bytes {
+            pending.report_drop("buffer_full");
+            self.dropped_packets += 1;
+            return;
+        }
+        tracing::debug!(
+            template_id,
+            observation_domain_id = pending.observation_domain_id(),
+            bytes = pending.packet.len(),
+            "buffering IPFIX packet until its template is received"
+        );
+        self.pending_bytes += pending.packet.len();
+        self.pending.push_back(pending);
+        self.expire_pending(Instant::now());
+    }
+
+    /// Decode again the buffered packets now that new templates might be known
+    fn retry_pending(&mut self) {
+        for pending in std::mem::take(&mut self.pending) {
+            let templates_map = self.ipfix_templates_map_mut(pending.observation_domain_id());
+            match ipfix::IpfixPacket::from_wire(Span::new(&pending.packet), templates_map) {
+                Ok((_, pkt)) => {
+                    self.pending_bytes -= pending.packet.len();
+                    self.ready.push_back(FlowInfo::IPFIX(pkt));
+                }
+                Err(nom::Err::Error(err) | nom::Err::Failure(err))
+                    if missing_template(err.error()).is_some() =>
+                {
+                    self.pending.push_back(pending);
+                }
+                Err(_) => {
+                    self.pending_bytes -= pending.packet.len();
+                    self.dropped_packets += 1;
+                    pending.report_drop("invalid");
+                }
+            }
+        }
+    }
+}
+
+/// Id of the template if the packet failed to parse only because the template
+/// is not known yet
+const fn missing_template(error: &IpfixPacketParsingError) -> Option<u16> {
+    match error {
+        IpfixPacketParsingError::SetParsingError(SetParsingError::NoTemplateDefinedFor(id)) => {
+            Some(*id)
+        }
+        _ => None,
+    }
 }
 
 impl Encoder<ipfix::IpfixPacket> for FlowInfoCodec {
     type Error = IpfixPacketWritingError;
 
     fn encode(&mut self, pkt: ipfix::IpfixPacket, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        dst.reserve(4 + pkt.len(Some(self.ipfix_templates_map.clone())));
+        let templates_map = self.ipfix_templates_map_mut(pkt.observation_domain_id());
+        dst.reserve(4 + pkt.len(Some(templates_map.clone())));
         let mut writer = dst.writer();
-        pkt.write(&mut writer, Some(self.ipfix_templates_map.clone()))?;
+        pkt.write(&mut writer, Some(templates_map))?;
         Ok(())
     }
 }
@@ -105,12 +527,13 @@ impl Encoder<FlowInfo> for FlowInfoCodec {
     }
 }
 
+/// On error, the bytes of the failed packet are returned along with the error
 #[instrument(skip_all)]
 fn parse_ipfix(
     buf: &mut BytesMut,
     length: usize,
     templates_map: ipfix::TemplatesMap,
-) -> Result<Option<FlowInfo>, FlowInfoCodecDecoderError> {
+) -> Result<Option<FlowInfo>, (FlowInfoCodecDecoderError, Bytes)> {
     let msg = match ipfix::IpfixPacket::from_wire(Span::new(buf), templates_map) {
         Ok((span, msg)) => {
             buf.advance(span.location_offset());
@@ -126,23 +549,27 @@ fn parse_ipfix(
                     FlowInfoCodecDecoderError::Incomplete(needed)
                 }
                 nom::Err::Error(error) | nom::Err::Failure(error) => {
-                    FlowInfoCodecDecoderError::IpfixParsingError(error.error().clone())
+                    FlowInfoCodecDecoderError::IpfixParsingError {
+                        error: error.error().clone(),
+                        frame: None,
+                    }
                 }
             };
             // Make sure we advance the buffer far enough, so we don't get stuck on
             // an error value.
-            buf.advance(if length < 5 { 5 } else { length });
-            return Err(err);
+            let packet = buf.split_to(if length < 5 { 5 } else { length });
+            return Err((err, packet.freeze()));
         }
     };
     Ok(Some(FlowInfo::IPFIX(msg)))
 }
 
+/// On error, the discarded bytes are returned along with the error
 #[instrument(skip_all)]
 fn parse_netflow_v9(
     buf: &mut BytesMut,
     templates_map: netflow::TemplatesMap,
-) -> Result<Option<FlowInfo>, FlowInfoCodecDecoderError> {
+) -> Result<Option<FlowInfo>, (FlowInfoCodecDecoderError, Bytes)> {
     let msg = match netflow::NetFlowV9Packet::from_wire(Span::new(buf), templates_map) {
         Ok((span, msg)) => {
             buf.advance(span.location_offset());
@@ -158,15 +585,18 @@ fn parse_netflow_v9(
                     FlowInfoCodecDecoderError::Incomplete(needed)
                 }
                 nom::Err::Error(error) | nom::Err::Failure(error) => {
-                    FlowInfoCodecDecoderError::NetFlowV9ParingError(error.error().clone())
+                    FlowInfoCodecDecoderError::NetFlowV9ParingError {
+                        error: error.error().clone(),
+                        frame: None,
+                    }
                 }
             };
             // Netflow v9 doesn't have a length component to tell us how many bytes
             // should skip for the next packet. Sadly, our best bet is to clear the
             // buffer and start over at the risk of discarding other good packets in
             // the buffer.
-            buf.clear();
-            return Err(err);
+            let packet = buf.split();
+            return Err((err, packet.freeze()));
         }
     };
     Ok(Some(FlowInfo::NetFlowV9(msg)))
@@ -178,6 +608,12 @@ impl Decoder for FlowInfoCodec {
 
     #[instrument(skip_all)]
     fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if let Some(info) = self.ready.pop_front() {
+            return Ok(Some(info));
+        }
+        if !self.pending.is_empty() {
+            self.expire_pending(Instant::now());
+        }
         // We're using IPFIX_HEADER_LENGTH as criteria to start parsing since it's
         // smaller than NetFlow v9 header size.
         let header_length = IPFIX_HEADER_LENGTH as usize;
@@ -193,9 +629,31 @@ impl Decoder for FlowInfoCodec {
             } else {
                 self.in_message = false;
                 if version == ipfix::IPFIX_VERSION {
-                    parse_ipfix(buf, length, self.ipfix_templates_map.clone())
+                    let observation_domain_id = NetworkEndian::read_u32(&buf[12..16]);
+                    let templates_map = self.ipfix_templates_map_mut(observation_domain_id);
+                    match parse_ipfix(buf, length, templates_map) {
+                        Ok(info) => {
+                            if !self.pending.is_empty() {
+                                self.retry_pending();
+                            }
+                            Ok(info)
+                        }
+                        Err((
+                            FlowInfoCodecDecoderError::IpfixParsingError { error, .. },
+                            packet,
+                        )) if self.template_buffer_policy.is_enabled()
+                            && missing_template(&error).is_some() =>
+                        {
+                            if let Some(template_id) = missing_template(&error) {
+                                self.buffer_packet(template_id, packet);
+                            }
+                            self.decode(buf)
+                        }
+                        Err((err, packet)) => Err(err.with_frame(&mut self.raw_frames, &packet)),
+                    }
                 } else if version == netflow::NETFLOW_V9_VERSION {
                     parse_netflow_v9(buf, self.netflow_v9_templates_map.clone())
+                        .map_err(|(err, packet)| err.with_frame(&mut self.raw_frames, &packet))
                 } else {
                     let err = FlowInfoCodecDecoderError::UnsupportedVersion(version);
                     buf.clear();
@@ -208,3 +666,151 @@ impl Decoder for FlowInfoCodec {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_vectors::ipfix_packets;
+
+    fn decode_vector(
+        codec: &mut FlowInfoCodec,
+        wire: &[u8],
+    ) -> Result<Option<FlowInfo>, FlowInfoCodecDecoderError> {
+        let mut buf = BytesMut::from(wire);
+        codec.decode(&mut buf)
+    }
+
+    #[test]
+    fn test_missing_template_without_buffering() {
+        let vectors = ipfix_packets();
+        let mut codec = FlowInfoCodec::default();
+        assert_eq!(
+            decode_vector(&mut codec, vectors[1].wire()),
+            Err(FlowInfoCodecDecoderError::IpfixParsingError {
+                error: IpfixPacketParsingError::SetParsingError(
+                    SetParsingError::NoTemplateDefinedFor(307)
+                ),
+                frame: None,
+            })
+        );
+        assert_eq!(codec.pending_packets(), 0);
+    }
+
+    #[test]
+    fn test_missing_template_raw_frame() {
+        let vectors = ipfix_packets();
+        let wire = vectors[1].wire();
+        let mut codec = FlowInfoCodec::default();
+        codec.set_raw_frame_policy(RawFramePolicy::new(16, 1, Duration::from_secs(3600)));
+        assert_eq!(
+            decode_vector(&mut codec, wire),
+            Err(FlowInfoCodecDecoderError::IpfixParsingError {
+                error: IpfixPacketParsingError::SetParsingError(
+                    SetParsingError::NoTemplateDefinedFor(307)
+                ),
+                frame: Some(RawFrame::new(wire, 16)),
+            })
+        );
+        // Rate limited
+        let err = decode_vector(&mut codec, wire).unwrap_err();
+        assert_eq!(err.raw_frame(), None);
+        assert_eq!(codec.suppressed_raw_frames(), 1);
+    }
+
+    #[test]
+    fn test_buffer_until_template() {
+        let vectors = ipfix_packets();
+        let mut codec = FlowInfoCodec::with_template_buffer_policy(TemplateBufferPolicy::new(
+            64 * 1024,
+            Duration::from_secs(60),
+        ));
+        assert_eq!(decode_vector(&mut codec, vectors[1].wire()), Ok(None));
+        assert_eq!(codec.pending_packets(), 1);
+        assert_eq!(codec.pending_bytes(), vectors[1].wire().len());
+
+        assert_eq!(
+            decode_vector(&mut codec, vectors[0].wire()),
+            Ok(Some(FlowInfo::IPFIX(vectors[0].expected().clone())))
+        );
+        assert_eq!(codec.pending_packets(), 0);
+        assert_eq!(codec.pending_bytes(), 0);
+        assert_eq!(
+            decode_vector(&mut codec, &[]),
+            Ok(Some(FlowInfo::IPFIX(vectors[1].expected().clone())))
+        );
+        assert_eq!(decode_vector(&mut codec, &[]), Ok(None));
+        assert_eq!(codec.dropped_packets(), 0);
+    }
+
+    #[test]
+    fn test_buffered_packet_expired() {
+        let vectors = ipfix_packets();
+        let mut codec = FlowInfoCodec::with_template_buffer_policy(TemplateBufferPolicy::new(
+            64 * 1024,
+            Duration::ZERO,
+        ));
+        assert_eq!(decode_vector(&mut codec, vectors[1].wire()), Ok(None));
+        assert_eq!(codec.pending_packets(), 0);
+        assert_eq!(codec.dropped_packets(), 1);
+        assert_eq!(
+            decode_vector(&mut codec, vectors[0].wire()),
+            Ok(Some(FlowInfo::IPFIX(vectors[0].expected().clone())))
+        );
+        assert_eq!(decode_vector(&mut codec, &[]), Ok(None));
+    }
+
+    #[test]
+    fn test_buffer_full() {
+        let vectors = ipfix_packets();
+        let wire = vectors[1].wire();
+        let mut codec = FlowInfoCodec::with_template_buffer_policy(TemplateBufferPolicy::new(
+            wire.len() + 1,
+            Duration::from_secs(60),
+        ));
+        assert_eq!(decode_vector(&mut codec, wire), Ok(None));
+        assert_eq!(codec.pending_packets(), 1);
+        // Oldest packet is dropped to make room for the new one
+        assert_eq!(decode_vector(&mut codec, wire), Ok(None));
+        assert_eq!(codec.pending_packets(), 1);
+        assert_eq!(codec.dropped_packets(), 1);
+
+        codec.set_template_buffer_policy(TemplateBufferPolicy::new(
+            wire.len() - 1,
+            Duration::from_secs(60),
+        ));
+        assert_eq!(codec.pending_packets(), 0);
+        assert_eq!(codec.dropped_packets(), 2);
+    }
+
+    #[test]
+    fn test_templates_per_observation_domain() {
+        let vectors = ipfix_packets();
+        let mut codec = FlowInfoCodec::default();
+        assert_eq!(
+            decode_vector(&mut codec, vectors[0].wire()),
+            Ok(Some(FlowInfo::IPFIX(vectors[0].expected().clone())))
+        );
+        assert_eq!(codec.templates_count(), 1);
+        assert_eq!(codec.templates()[0].observation_domain_id, Some(0));
+        assert!(codec.ipfix_templates_map(0).is_some());
+
+        // The data set in observation domain 1 doesn't match the template of
+        // domain 0
+        let mut data = vectors[1].wire().to_vec();
+        data[15] = 1;
+        assert_eq!(
+            decode_vector(&mut codec, &data),
+            Err(FlowInfoCodecDecoderError::IpfixParsingError {
+                error: IpfixPacketParsingError::SetParsingError(
+                    SetParsingError::NoTemplateDefinedFor(307)
+                ),
+                frame: None,
+            })
+        );
+        assert_eq!(
+            decode_vector(&mut codec, vectors[1].wire()),
+            Ok(Some(FlowInfo::IPFIX(vectors[1].expected().clone())))
+        );
+        assert_eq!(codec.templates_state().ipfix[&0].len(), 1);
+    }
+}
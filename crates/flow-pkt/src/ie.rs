@@ -1,16 +1,7 @@
 #[allow(non_camel_case_types)]
 #[repr(u8)]
-#[derive(
-    strum_macros::Display,
-    strum_macros::FromRepr,
-    Copy,
-    Clone,
-    PartialEq,
-    Eq,
-    Debug,
-    serde::Serialize,
-    serde::Deserialize,
-)]
+#[derive(strum_macros::Display, strum_macros::FromRepr, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InformationElementDataType {
     /// [RFC5102](https://datatracker.ietf.org/doc/html/rfc5102)
     /// [RFC7011](https://datatracker.ietf.org/doc/html/rfc7011)
@@ -155,3 +146,150 @@ pub trait InformationElementTemplate {
 }
 
 include!(concat!(env!("OUT_DIR"), "/ie_generated.rs"));
+
+/// Helpers to render fields to JSON values according to their abstract data
+/// type
+#[cfg(feature = "serde")]
+pub(crate) mod json {
+    use super::{Field, MacAddress, IE};
+    use netgauze_parse_utils::{ReadablePduWithTwoInputs, Span};
+    use nom::{
+        bytes::complete::take,
+        number::complete::{be_u16, be_u32, be_u8},
+        IResult,
+    };
+    use std::collections::HashSet;
+
+    /// Octets are rendered as a lower case hex string
+    pub(crate) fn octets(value: &[u8]) -> serde_json::Value {
+        serde_json::Value::from(value.iter().map(|b| format!("{b:02x}")).collect::<String>())
+    }
+
+    /// MAC addresses are rendered as colon separated lower case hex
+    pub(crate) fn mac_address(value: &MacAddress) -> serde_json::Value {
+        serde_json::Value::from(
+            value
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<Vec<_>>()
+                .join(":"),
+        )
+    }
+
+    /// Elements of a basicList
+    /// ([RFC 6313 section 4.5.1](https://datatracker.ietf.org/doc/html/rfc6313#section-4.5.1))
+    /// rendered as an array of their values. Lists of unknown Information
+    /// Elements, or that can't be decoded, are kept as hex encoded octets.
+    pub(crate) fn basic_list(value: &[u8]) -> serde_json::Value {
+        match parse_basic_list(Span::new(value)) {
+            Ok((_, elements)) => serde_json::Value::Array(elements),
+            Err(_) => octets(value),
+        }
+    }
+
+    fn parse_basic_list(buf: Span<'_>) -> IResult<Span<'_>, Vec<serde_json::Value>> {
+        let (buf, _semantic) = be_u8(buf)?;
+        let (buf, code) = be_u16(buf)?;
+        let (buf, length) = be_u16(buf)?;
+        let (mut buf, pen) = if code & 0x8000u16 != 0 {
+            be_u32(buf)?
+        } else {
+            (buf, 0)
+        };
+        let ie = match IE::try_from((pen, code)) {
+            // Elements of zero length would never consume the list
+            Ok(ie) if length != 0 && !matches!(ie, IE::Unknown { .. }) => ie,
+            _ => return Err(list_error(buf)),
+        };
+        let mut elements = vec![];
+        while !buf.is_empty() {
+            let (t, element) = Field::from_wire(buf, &ie, length).map_err(|_| list_error(buf))?;
+            buf = t;
+            elements.push(element.to_json_value());
+        }
+        Ok((buf, elements))
+    }
+
+    /// subTemplateList
+    /// ([RFC 6313 section 4.5.2](https://datatracker.ietf.org/doc/html/rfc6313#section-4.5.2))
+    /// rendered as an array with one object of the `template_id` and the hex
+    /// encoded `records`. The template isn't known when rendering a single
+    /// field, hence the data records aren't decoded.
+    pub(crate) fn sub_template_list(value: &[u8]) -> serde_json::Value {
+        match parse_sub_template_list(Span::new(value)) {
+            Ok((records, template_id)) => {
+                serde_json::Value::Array(vec![sub_template_records(template_id, &records)])
+            }
+            Err(_) => octets(value),
+        }
+    }
+
+    fn parse_sub_template_list(buf: Span<'_>) -> IResult<Span<'_>, u16> {
+        let (buf, _semantic) = be_u8(buf)?;
+        be_u16(buf)
+    }
+
+    /// subTemplateMultiList
+    /// ([RFC 6313 section 4.5.3](https://datatracker.ietf.org/doc/html/rfc6313#section-4.5.3))
+    /// rendered as an array of objects of the `template_id` and hex encoded
+    /// `records` of each of its sub-template lists, see [`sub_template_list`].
+    pub(crate) fn sub_template_multi_list(value: &[u8]) -> serde_json::Value {
+        match parse_sub_template_multi_list(Span::new(value)) {
+            Ok((_, lists)) => serde_json::Value::Array(lists),
+            Err(_) => octets(value),
+        }
+    }
+
+    fn parse_sub_template_multi_list(buf: Span<'_>) -> IResult<Span<'_>, Vec<serde_json::Value>> {
+        let (mut buf, _semantic) = be_u8(buf)?;
+        let mut lists = vec![];
+        while !buf.is_empty() {
+            let (t, template_id) = be_u16(buf)?;
+            let (t, length) = be_u16(t)?;
+            // The length includes the template ID and length octets
+            let records_length = length.checked_sub(4).ok_or_else(|| list_error(buf))?;
+            let (t, records) = take(records_length)(t)?;
+            lists.push(sub_template_records(template_id, &records));
+            buf = t;
+        }
+        Ok((buf, lists))
+    }
+
+    fn sub_template_records(template_id: u16, records: &[u8]) -> serde_json::Value {
+        serde_json::json!({
+            "template_id": template_id,
+            "records": octets(records),
+        })
+    }
+
+    fn list_error(buf: Span<'_>) -> nom::Err<nom::error::Error<Span<'_>>> {
+        nom::Err::Error(nom::error::Error::new(buf, nom::error::ErrorKind::Verify))
+    }
+
+    /// JSON object of the fields keyed by their name. Fields repeated in the
+    /// same record are collected in an array in the order they appear.
+    pub(crate) fn fields_object<'a>(
+        fields: impl Iterator<Item = (&'a str, serde_json::Value)>,
+    ) -> serde_json::Value {
+        let mut object = serde_json::Map::new();
+        // Field values can be arrays themselves, e.g., lists, hence track the
+        // repeated fields rather than looking at the value already inserted
+        let mut repeated = HashSet::new();
+        for (name, value) in fields {
+            match object.get_mut(name) {
+                None => {
+                    object.insert(name.to_string(), value);
+                }
+                Some(serde_json::Value::Array(values)) if repeated.contains(name) => {
+                    values.push(value)
+                }
+                Some(existing) => {
+                    let first = existing.take();
+                    *existing = serde_json::Value::Array(vec![first, value]);
+                    repeated.insert(name);
+                }
+            }
+        }
+        serde_json::Value::Object(object)
+    }
+}
@@ -14,6 +14,7 @@
 // limitations under the License.
 
 use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
@@ -57,7 +58,8 @@ pub type TemplatesMap = Rc<RefCell<HashMap<u16, Rc<DecodingTemplate>>>>;
 /// |                    Observation Domain ID                      |
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct IpfixPacket {
     version: u16,
     export_time: DateTime<Utc>,
@@ -127,7 +129,8 @@ impl IpfixPacket {
 /// |          Set ID               |          Length               |
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Set {
     Template(Vec<TemplateRecord>),
     OptionsTemplate(Vec<OptionsTemplateRecord>),
@@ -174,7 +177,8 @@ impl Set {
 /// |      Template ID (> 255)      |         Field Count           |
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TemplateRecord {
     id: u16,
     field_specifiers: Vec<FieldSpecifier>,
@@ -260,7 +264,8 @@ impl TemplateRecord {
 /// |     Option M Field Length     |      Padding (optional)       |
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct OptionsTemplateRecord {
     id: u16,
     scope_field_specifiers: Vec<FieldSpecifier>,
@@ -293,7 +298,8 @@ impl OptionsTemplateRecord {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DataRecord {
     scope_fields: Vec<Field>,
     fields: Vec<Field>,
@@ -314,4 +320,117 @@ impl DataRecord {
     pub const fn fields(&self) -> &Vec<Field> {
         &self.fields
     }
+
+    /// Render the record as a JSON object with `scope_fields` and `fields`
+    /// objects, each keyed by the Information Element names and with values
+    /// rendered according to their abstract data type, see
+    /// [`Field::to_json_value`]. Fields repeated in the same record are
+    /// collected in an array.
+    #[cfg(feature = "serde")]
+    pub fn to_json_value(&self) -> serde_json::Value {
+        serde_json::json!({
+            "scope_fields": crate::ie::json::fields_object(
+                self.scope_fields.iter().map(|field| (field.name(), field.to_json_value()))
+            ),
+            "fields": crate::ie::json::fields_object(
+                self.fields.iter().map(|field| (field.name(), field.to_json_value()))
+            ),
+        })
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::ie;
+    use chrono::TimeZone;
+    use std::net::Ipv6Addr;
+
+    #[test]
+    fn test_data_record_to_json_value() {
+        let record = DataRecord::new(
+            vec![Field::interfaceName(ie::interfaceName("eth0".to_string()))],
+            vec![
+                Field::octetDeltaCount(ie::octetDeltaCount(1500)),
+                Field::sourceIPv6Address(ie::sourceIPv6Address(Ipv6Addr::new(
+                    0x2001, 0xdb8, 0, 0, 0, 0, 0, 1,
+                ))),
+                Field::sourceMacAddress(ie::sourceMacAddress([0x00, 0x1b, 0x21, 0xaa, 0xbb, 0x0c])),
+                Field::flowStartSeconds(ie::flowStartSeconds(
+                    Utc.timestamp_opt(1680000000, 0).unwrap(),
+                )),
+                Field::flowStartMilliseconds(ie::flowStartMilliseconds(
+                    Utc.timestamp_millis_opt(1680000000123).unwrap(),
+                )),
+                Field::mplsLabelStackSection(ie::mplsLabelStackSection(vec![0x01, 0xf4, 0x01])),
+                Field::mplsLabelStackSection(ie::mplsLabelStackSection(vec![0x01, 0xf4, 0x11])),
+                Field::Unknown(vec![0xde, 0xad]),
+            ],
+        );
+        assert_eq!(
+            record.to_json_value(),
+            serde_json::json!({
+                "scope_fields": {
+                    "interfaceName": "eth0",
+                },
+                "fields": {
+                    "octetDeltaCount": 1500,
+                    "sourceIPv6Address": "2001:db8::1",
+                    "sourceMacAddress": "00:1b:21:aa:bb:0c",
+                    "flowStartSeconds": "2023-03-28T10:40:00Z",
+                    "flowStartMilliseconds": "2023-03-28T10:40:00.123Z",
+                    "mplsLabelStackSection": ["01f401", "01f411"],
+                    "Unknown": "dead",
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_data_record_lists_to_json_value() {
+        let record = DataRecord::new(
+            vec![],
+            vec![
+                Field::basicList(ie::basicList(vec![
+                    0x03, // allOf semantic
+                    0x00, 0x08, // sourceIPv4Address
+                    0x00, 0x04, // element length
+                    192, 0, 2, 1, 192, 0, 2, 2,
+                ])),
+                Field::basicList(ie::basicList(vec![
+                    0x03, // allOf semantic
+                    0x00, 0x52, // interfaceName
+                    0xff, 0xff, // variable length elements
+                    0x04, b'e', b't', b'h', b'0', 0x04, b'e', b't', b'h', b'1',
+                ])),
+                Field::subTemplateList(ie::subTemplateList(vec![
+                    0xff, // undefined semantic
+                    0x01, 0x00, // template ID
+                    0xca, 0xfe,
+                ])),
+                Field::subTemplateMultiList(ie::subTemplateMultiList(vec![
+                    0x04, // ordered semantic
+                    0x01, 0x00, 0x00, 0x06, 0xca, 0xfe, // template 256
+                    0x01, 0x01, 0x00, 0x04, // template 257 without records
+                ])),
+                Field::bgpSourceCommunityList(ie::bgpSourceCommunityList(vec![0x03, 0x00])),
+            ],
+        );
+        assert_eq!(
+            record.to_json_value(),
+            serde_json::json!({
+                "scope_fields": {},
+                "fields": {
+                    "basicList": [["192.0.2.1", "192.0.2.2"], ["eth0", "eth1"]],
+                    "subTemplateList": [{"template_id": 256, "records": "cafe"}],
+                    "subTemplateMultiList": [
+                        {"template_id": 256, "records": "cafe"},
+                        {"template_id": 257, "records": ""},
+                    ],
+                    // Truncated lists are kept as octets
+                    "bgpSourceCommunityList": "0300",
+                },
+            })
+        );
+    }
 }
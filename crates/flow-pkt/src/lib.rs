@@ -13,26 +13,35 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(feature = "capture")]
+pub mod capture;
 #[cfg(feature = "codec")]
 pub mod codec;
 pub mod ie;
 pub mod ipfix;
 pub mod netflow;
-#[cfg(feature = "serde")]
+#[cfg(any(all(test, feature = "wire"), feature = "test-vectors"))]
+pub mod test_vectors;
+#[cfg(feature = "wire")]
+pub mod udp;
+#[cfg(feature = "wire")]
 pub mod wire;
 
 use crate::ie::*;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::ops::Deref;
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FlowInfo {
     NetFlowV9(netflow::NetFlowV9Packet),
     IPFIX(ipfix::IpfixPacket),
 }
 
 /// Errors when crafting a new Set
-#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FieldSpecifierError {
     /// Specified field length was out of the range defined by the registry
     InvalidLength(u16, IE),
@@ -49,7 +58,8 @@ pub enum FieldSpecifierError {
 /// |                      Enterprise Number                        |
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct FieldSpecifier {
     element_id: IE,
     length: u16,
@@ -74,12 +84,14 @@ impl FieldSpecifier {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum DataSetIdError {
     InvalidId(u16),
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DataSetId(u16);
 
 /// Values 256 and above are used for Data Sets
@@ -21,6 +21,7 @@ use crate::{
     DataSetId, FieldSpecifier,
 };
 use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::{cell::RefCell, collections::HashMap, ops::Range, rc::Rc};
 
@@ -62,7 +63,8 @@ pub type TemplatesMap = Rc<RefCell<HashMap<u16, Rc<DecodingTemplate>>>>;
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// |                        Source ID                              |
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NetFlowV9Packet {
     version: u16,
     sys_up_time: u32,
@@ -115,7 +117,8 @@ impl NetFlowV9Packet {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Set {
     Template(Vec<TemplateRecord>),
     OptionsTemplate(Vec<OptionsTemplateRecord>),
@@ -135,7 +138,8 @@ impl Set {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TemplateRecord {
     id: u16,
     field_specifiers: Vec<FieldSpecifier>,
@@ -164,7 +168,8 @@ impl TemplateRecord {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct OptionsTemplateRecord {
     id: u16,
     scope_field_specifiers: Vec<ScopeFieldSpecifier>,
@@ -197,7 +202,8 @@ impl OptionsTemplateRecord {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DataRecord {
     scope_fields: Vec<ScopeField>,
     fields: Vec<Field>,
@@ -218,9 +224,26 @@ impl DataRecord {
     pub const fn fields(&self) -> &Vec<Field> {
         &self.fields
     }
+
+    /// Render the record as a JSON object with `scope_fields` and `fields`
+    /// objects, each keyed by the field names and with values rendered
+    /// according to their abstract data type, see [`Field::to_json_value`].
+    /// Fields repeated in the same record are collected in an array.
+    #[cfg(feature = "serde")]
+    pub fn to_json_value(&self) -> serde_json::Value {
+        serde_json::json!({
+            "scope_fields": crate::ie::json::fields_object(
+                self.scope_fields.iter().map(|field| (field.name(), field.to_json_value()))
+            ),
+            "fields": crate::ie::json::fields_object(
+                self.fields.iter().map(|field| (field.name(), field.to_json_value()))
+            ),
+        })
+    }
 }
 
-#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ScopeField {
     Unknown { pen: u32, id: u16, value: Vec<u8> },
     System(System),
@@ -230,22 +253,55 @@ pub enum ScopeField {
     Template(Template),
 }
 
-#[derive(Eq, Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+impl ScopeField {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Unknown { .. } => "Unknown",
+            Self::System(_) => "System",
+            Self::Interface(_) => "Interface",
+            Self::LineCard(_) => "LineCard",
+            Self::Cache(_) => "Cache",
+            Self::Template(_) => "Template",
+        }
+    }
+
+    /// Render the value to JSON, numbers for the identifiers and hex encoded
+    /// octets otherwise
+    #[cfg(feature = "serde")]
+    pub fn to_json_value(&self) -> serde_json::Value {
+        match self {
+            Self::Unknown { value, .. } => crate::ie::json::octets(value),
+            Self::System(value) => serde_json::Value::from(value.0),
+            Self::Interface(value) => serde_json::Value::from(value.0),
+            Self::LineCard(value) => serde_json::Value::from(value.0),
+            Self::Cache(value) => crate::ie::json::octets(&value.0),
+            Self::Template(value) => crate::ie::json::octets(&value.0),
+        }
+    }
+}
+
+#[derive(Eq, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct System(pub u32);
 
-#[derive(Eq, Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Eq, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Interface(pub u32);
 
-#[derive(Eq, Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Eq, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LineCard(pub u32);
 
-#[derive(Eq, Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Eq, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cache(pub Vec<u8>);
 
-#[derive(Eq, Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Eq, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Template(pub Vec<u8>);
 
-#[derive(Copy, Eq, Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ScopeIE {
     Unknown { pen: u32, id: u16 },
     System,
@@ -317,7 +373,8 @@ impl InformationElementTemplate for ScopeIE {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ScopeFieldSpecifier {
     element_id: ScopeIE,
     length: u16,
@@ -0,0 +1,191 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! IPFIX conformance test vectors, each one is checked to round trip against
+//! this crate's wire encoder and decoder. IPFIX data sets can only be decoded with the templates previously
+//! sent by the exporter, hence the vectors must be decoded in order using the
+//! same [`crate::ipfix::TemplatesMap`].
+
+use std::net::Ipv4Addr;
+
+use chrono::{DateTime, TimeZone, Utc};
+pub use netgauze_parse_utils::test_vectors::TestVector;
+
+use crate::{
+    ie,
+    ipfix::{DataRecord, IpfixPacket, Set, TemplateRecord},
+    DataSetId, FieldSpecifier,
+};
+
+// Vectors are constructed from well-known valid values, hence unwrapping is
+// safe in this module.
+
+fn timestamp(secs: i64, millis: u32) -> DateTime<Utc> {
+    Utc.timestamp_opt(secs, millis * 1_000_000).unwrap()
+}
+
+/// Template used by the data set of the vectors
+fn template_fields() -> Vec<FieldSpecifier> {
+    [
+        (ie::IE::sourceIPv4Address, 4),
+        (ie::IE::destinationIPv4Address, 4),
+        (ie::IE::ipClassOfService, 1),
+        (ie::IE::protocolIdentifier, 1),
+        (ie::IE::sourceTransportPort, 2),
+        (ie::IE::destinationTransportPort, 2),
+        (ie::IE::icmpTypeCodeIPv4, 2),
+        (ie::IE::ingressInterface, 4),
+        (ie::IE::bgpSourceAsNumber, 4),
+        (ie::IE::bgpDestinationAsNumber, 4),
+        (ie::IE::bgpNextHopIPv4Address, 4),
+        (ie::IE::egressInterface, 4),
+        (ie::IE::octetDeltaCount, 4),
+        (ie::IE::packetDeltaCount, 4),
+        (ie::IE::flowStartSysUpTime, 4),
+        (ie::IE::flowEndSysUpTime, 4),
+        (ie::IE::ipNextHopIPv4Address, 4),
+        (ie::IE::sourceIPv4PrefixLength, 1),
+        (ie::IE::destinationIPv4PrefixLength, 1),
+        (ie::IE::tcpControlBits, 1),
+        (ie::IE::ipVersion, 1),
+        (ie::IE::flowStartMilliseconds, 8),
+        (ie::IE::flowEndMilliseconds, 8),
+    ]
+    .into_iter()
+    .map(|(ie, length)| FieldSpecifier::new(ie, length).unwrap())
+    .collect()
+}
+
+/// Test vectors for [`IpfixPacket`], to be decoded in order
+pub fn ipfix_packets() -> Vec<TestVector<IpfixPacket>> {
+    vec![
+        TestVector::new(
+            "template set",
+            vec![
+                0x00, 0x0a, 0x00, 0x74, 0x58, 0x3d, 0xe0, 0x59, 0x00, 0x00, 0x0e, 0xe4, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x02, 0x00, 0x64, 0x01, 0x33, 0x00, 0x17, 0x00, 0x08, 0x00, 0x04,
+                0x00, 0x0c, 0x00, 0x04, 0x00, 0x05, 0x00, 0x01, 0x00, 0x04, 0x00, 0x01, 0x00, 0x07,
+                0x00, 0x02, 0x00, 0x0b, 0x00, 0x02, 0x00, 0x20, 0x00, 0x02, 0x00, 0x0a, 0x00, 0x04,
+                0x00, 0x10, 0x00, 0x04, 0x00, 0x11, 0x00, 0x04, 0x00, 0x12, 0x00, 0x04, 0x00, 0x0e,
+                0x00, 0x04, 0x00, 0x01, 0x00, 0x04, 0x00, 0x02, 0x00, 0x04, 0x00, 0x16, 0x00, 0x04,
+                0x00, 0x15, 0x00, 0x04, 0x00, 0x0f, 0x00, 0x04, 0x00, 0x09, 0x00, 0x01, 0x00, 0x0d,
+                0x00, 0x01, 0x00, 0x06, 0x00, 0x01, 0x00, 0x3c, 0x00, 0x01, 0x00, 0x98, 0x00, 0x08,
+                0x00, 0x99, 0x00, 0x08,
+            ],
+            IpfixPacket::new(
+                timestamp(1480450137, 0),
+                3812,
+                0,
+                vec![Set::Template(vec![TemplateRecord::new(
+                    307,
+                    template_fields(),
+                )])],
+            ),
+        ),
+        TestVector::new(
+            "data set",
+            vec![
+                0x00, 0x0a, 0x00, 0x60, 0x58, 0x3d, 0xe0, 0x59, 0x00, 0x00, 0x0e, 0xe4, 0x00, 0x00,
+                0x00, 0x00, 0x01, 0x33, 0x00, 0x50, 0x46, 0x01, 0x73, 0x01, 0x32, 0x00, 0x47, 0x01,
+                0x00, 0x3d, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x3b, 0x00, 0x00,
+                0x00, 0x02, 0x00, 0x00, 0x00, 0x03, 0xcc, 0x2a, 0x6e, 0x65, 0x00, 0x00, 0x03, 0x56,
+                0x00, 0x00, 0x05, 0x20, 0x00, 0x00, 0x00, 0x09, 0xb3, 0xf9, 0x06, 0xee, 0xb3, 0xfb,
+                0xaf, 0x3c, 0xcc, 0x2a, 0x6e, 0xbd, 0x18, 0x18, 0x00, 0x04, 0x00, 0x00, 0x01, 0x58,
+                0xb1, 0xb1, 0x38, 0xff, 0x00, 0x00, 0x01, 0x58, 0xb1, 0xb3, 0xe1, 0x4d,
+            ],
+            IpfixPacket::new(
+                timestamp(1480450137, 0),
+                3812,
+                0,
+                vec![Set::Data {
+                    id: DataSetId::new(307).unwrap(),
+                    records: vec![DataRecord::new(
+                        vec![],
+                        vec![
+                            ie::Field::sourceIPv4Address(ie::sourceIPv4Address(Ipv4Addr::new(
+                                70, 1, 115, 1,
+                            ))),
+                            ie::Field::destinationIPv4Address(ie::destinationIPv4Address(
+                                Ipv4Addr::new(50, 0, 71, 1),
+                            )),
+                            ie::Field::ipClassOfService(ie::ipClassOfService(0)),
+                            ie::Field::protocolIdentifier(ie::protocolIdentifier(61)),
+                            ie::Field::sourceTransportPort(ie::sourceTransportPort(0)),
+                            ie::Field::destinationTransportPort(ie::destinationTransportPort(0)),
+                            ie::Field::icmpTypeCodeIPv4(ie::icmpTypeCodeIPv4(0)),
+                            ie::Field::ingressInterface(ie::ingressInterface(827)),
+                            ie::Field::bgpSourceAsNumber(ie::bgpSourceAsNumber(2)),
+                            ie::Field::bgpDestinationAsNumber(ie::bgpDestinationAsNumber(3)),
+                            ie::Field::bgpNextHopIPv4Address(ie::bgpNextHopIPv4Address(
+                                Ipv4Addr::new(204, 42, 110, 101),
+                            )),
+                            ie::Field::egressInterface(ie::egressInterface(854)),
+                            ie::Field::octetDeltaCount(ie::octetDeltaCount(1312)),
+                            ie::Field::packetDeltaCount(ie::packetDeltaCount(9)),
+                            ie::Field::flowStartSysUpTime(ie::flowStartSysUpTime(0xb3f906ee)),
+                            ie::Field::flowEndSysUpTime(ie::flowEndSysUpTime(0xb3fbaf3c)),
+                            ie::Field::ipNextHopIPv4Address(ie::ipNextHopIPv4Address(
+                                Ipv4Addr::new(204, 42, 110, 189),
+                            )),
+                            ie::Field::sourceIPv4PrefixLength(ie::sourceIPv4PrefixLength(24)),
+                            ie::Field::destinationIPv4PrefixLength(
+                                ie::destinationIPv4PrefixLength(24),
+                            ),
+                            ie::Field::tcpControlBits(ie::tcpControlBits(0)),
+                            ie::Field::ipVersion(ie::ipVersion(4)),
+                            ie::Field::flowStartMilliseconds(ie::flowStartMilliseconds(timestamp(
+                                1480449931, 519,
+                            ))),
+                            ie::Field::flowEndMilliseconds(ie::flowEndMilliseconds(timestamp(
+                                1480450105, 677,
+                            ))),
+                        ],
+                    )],
+                }],
+            ),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipfix::TemplatesMap;
+    use netgauze_parse_utils::test_helpers::{
+        test_parsed_completely_with_one_input, test_write_with_one_input,
+    };
+
+    #[test]
+    fn test_ipfix_packets_vectors() {
+        let templates_map = TemplatesMap::default();
+        for vector in ipfix_packets() {
+            test_parsed_completely_with_one_input(
+                vector.wire(),
+                templates_map.clone(),
+                vector.expected(),
+            );
+            assert!(
+                test_write_with_one_input(
+                    vector.expected(),
+                    Some(templates_map.clone()),
+                    vector.wire()
+                )
+                .is_ok(),
+                "Failed writing vector: {}",
+                vector.name()
+            );
+        }
+    }
+}
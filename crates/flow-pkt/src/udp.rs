@@ -0,0 +1,660 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding of IPFIX and NetFlow v9 packets received over UDP.
+//!
+//! A UDP transport session has no start nor end, the exporter re-sends its
+//! templates periodically, and packets can be lost or reordered on the way.
+//! [`UdpSessionManager`] keeps a session per exporter address and observation
+//! domain (the source ID for NetFlow v9), and applies the template rules of
+//! [RFC 7011 section 8.4](https://datatracker.ietf.org/doc/html/rfc7011#section-8.4):
+//! - Each template has a lifetime, templates that are not refreshed within
+//!   their lifetime expire.
+//! - Receiving a template again refreshes its lifetime, a different
+//!   definition for the same template ID replaces the previous one.
+//! - Template withdrawals are not used over UDP, they are ignored and the
+//!   withdrawn templates are kept until they expire.
+//!
+//! The sequence numbers of the received packets are tracked to report the
+//! lost and reordered packets in [`SessionStats`]. IPFIX sequence numbers
+//! count data records, while NetFlow v9 ones count packets.
+//!
+//! Lifetimes rely on [`Instant`] of `web_time`, which is
+//! `std::time::Instant` on all the targets but `wasm32-unknown-unknown`, where
+//! it's backed by `performance.now()`.
+
+use std::{collections::HashMap, net::SocketAddr, rc::Rc, time::Duration};
+use web_time::Instant;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use netgauze_parse_utils::{
+    Categorize, ErrorCategory, LocatedParsingError, ReadablePduWithOneInput, Span,
+};
+
+use crate::{
+    ipfix, netflow,
+    wire::deserializer::{
+        ipfix::{IpfixPacketParsingError, IPFIX_HEADER_LENGTH},
+        netflow::NetFlowV9PacketParsingError,
+    },
+    FlowInfo,
+};
+
+/// Default lifetime of the templates, three times the default template
+/// refresh timeout of the exporters
+pub const DEFAULT_TEMPLATE_LIFETIME: Duration = Duration::from_secs(1800);
+
+/// Sequence numbers lower than the expected one by more than this value are
+/// considered as an exporter restart rather than a reordered packet
+pub const REORDER_WINDOW: u32 = 65536;
+
+const NETFLOW_V9_HEADER_LENGTH: usize = 20;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum UdpDecodingError {
+    /// The datagram is shorter than the packet header or the packet length
+    Truncated(usize),
+    UnsupportedVersion(u16),
+    IpfixParsingError(IpfixPacketParsingError),
+    NetFlowV9ParsingError(NetFlowV9PacketParsingError),
+}
+
+impl std::fmt::Display for UdpDecodingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::error::Error for UdpDecodingError {}
+
+impl Categorize for UdpDecodingError {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            Self::Truncated(_) => ErrorCategory::Truncated,
+            Self::UnsupportedVersion(_) => ErrorCategory::Unsupported,
+            Self::IpfixParsingError(error) => error.category(),
+            Self::NetFlowV9ParsingError(error) => error.category(),
+        }
+    }
+
+    fn variant(&self) -> &'static str {
+        match self {
+            Self::Truncated(_) => "Truncated",
+            Self::UnsupportedVersion(_) => "UnsupportedVersion",
+            Self::IpfixParsingError(_) => "IpfixParsingError",
+            Self::NetFlowV9ParsingError(_) => "NetFlowV9ParsingError",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn Categorize> {
+        match self {
+            Self::Truncated(_) | Self::UnsupportedVersion(_) => None,
+            Self::IpfixParsingError(error) => Some(error),
+            Self::NetFlowV9ParsingError(error) => Some(error),
+        }
+    }
+}
+
+/// Identifies a UDP session, the observation domain is the source ID for
+/// NetFlow v9
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UdpSessionKey {
+    exporter: SocketAddr,
+    observation_domain_id: u32,
+}
+
+impl UdpSessionKey {
+    pub const fn new(exporter: SocketAddr, observation_domain_id: u32) -> Self {
+        Self {
+            exporter,
+            observation_domain_id,
+        }
+    }
+
+    pub const fn exporter(&self) -> SocketAddr {
+        self.exporter
+    }
+
+    pub const fn observation_domain_id(&self) -> u32 {
+        self.observation_domain_id
+    }
+}
+
+/// Counters of a UDP session
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SessionStats {
+    packets: u64,
+    next_sequence_number: Option<u32>,
+    gaps: u64,
+    missing: u64,
+    reordered: u64,
+    sequence_resets: u64,
+    expired_templates: u64,
+    redefined_templates: u64,
+    ignored_withdrawals: u64,
+}
+
+impl SessionStats {
+    /// Number of decoded packets
+    pub const fn packets(&self) -> u64 {
+        self.packets
+    }
+
+    /// Sequence number expected in the next packet
+    pub const fn next_sequence_number(&self) -> Option<u32> {
+        self.next_sequence_number
+    }
+
+    /// Number of times packets were received ahead of the expected sequence
+    /// number
+    pub const fn gaps(&self) -> u64 {
+        self.gaps
+    }
+
+    /// Number of data records (IPFIX) or packets (NetFlow v9) skipped by the
+    /// gaps. Reordered packets are counted here first, then as reordered when
+    /// they arrive.
+    pub const fn missing(&self) -> u64 {
+        self.missing
+    }
+
+    /// Number of packets received behind the expected sequence number
+    pub const fn reordered(&self) -> u64 {
+        self.reordered
+    }
+
+    /// Number of times the sequence number jumped back further than
+    /// [`REORDER_WINDOW`], e.g., when the exporter restarts
+    pub const fn sequence_resets(&self) -> u64 {
+        self.sequence_resets
+    }
+
+    /// Number of templates dropped since they weren't refreshed within their
+    /// lifetime
+    pub const fn expired_templates(&self) -> u64 {
+        self.expired_templates
+    }
+
+    /// Number of templates received with a different definition than the
+    /// known one for the same template ID
+    pub const fn redefined_templates(&self) -> u64 {
+        self.redefined_templates
+    }
+
+    /// Number of template withdrawals ignored
+    pub const fn ignored_withdrawals(&self) -> u64 {
+        self.ignored_withdrawals
+    }
+
+    fn observe_sequence_number(&mut self, sequence_number: u32, increment: u32) {
+        self.packets += 1;
+        if let Some(next) = self.next_sequence_number {
+            let ahead = sequence_number.wrapping_sub(next);
+            let behind = next.wrapping_sub(sequence_number);
+            if behind > 0 && behind <= REORDER_WINDOW {
+                // Late packet, the expected sequence number stays the same
+                self.reordered += 1;
+                return;
+            }
+            if ahead > 0 && ahead <= u32::MAX / 2 {
+                self.gaps += 1;
+                self.missing += u64::from(ahead);
+            } else if ahead > 0 {
+                self.sequence_resets += 1;
+            }
+        }
+        self.next_sequence_number = Some(sequence_number.wrapping_add(increment));
+    }
+}
+
+#[derive(Debug)]
+struct UdpSession {
+    version: u16,
+    ipfix_templates: ipfix::TemplatesMap,
+    netflow_v9_templates: netflow::TemplatesMap,
+    /// Last time each template was received
+    refreshed: HashMap<u16, Instant>,
+    stats: SessionStats,
+}
+
+impl UdpSession {
+    fn new(version: u16) -> Self {
+        Self {
+            version,
+            ipfix_templates: ipfix::TemplatesMap::default(),
+            netflow_v9_templates: netflow::TemplatesMap::default(),
+            refreshed: HashMap::new(),
+            stats: SessionStats::default(),
+        }
+    }
+
+    fn expire_templates(&mut self, now: Instant, lifetime: Duration) -> usize {
+        let expired = self
+            .refreshed
+            .iter()
+            .filter(|(_, refreshed)| now.saturating_duration_since(**refreshed) >= lifetime)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in &expired {
+            self.refreshed.remove(id);
+            self.ipfix_templates.borrow_mut().remove(id);
+            self.netflow_v9_templates.borrow_mut().remove(id);
+        }
+        self.stats.expired_templates += expired.len() as u64;
+        expired.len()
+    }
+
+    /// Record the templates received in an IPFIX packet. `previous` is the
+    /// templates map before the packet was decoded, to restore the withdrawn
+    /// templates.
+    fn refresh_ipfix_templates(
+        &mut self,
+        pkt: &ipfix::IpfixPacket,
+        previous: &HashMap<u16, Rc<ipfix::DecodingTemplate>>,
+        now: Instant,
+    ) {
+        let mut received = vec![];
+        for set in pkt.sets() {
+            match set {
+                ipfix::Set::Template(templates) => received.extend(
+                    templates
+                        .iter()
+                        .map(|template| (template.id(), template.field_specifiers().is_empty())),
+                ),
+                ipfix::Set::OptionsTemplate(templates) => {
+                    received.extend(templates.iter().map(|template| {
+                        (
+                            template.id(),
+                            template.scope_field_specifiers().is_empty()
+                                && template.field_specifiers().is_empty(),
+                        )
+                    }))
+                }
+                ipfix::Set::Data { .. } => {}
+            }
+        }
+        let mut templates = self.ipfix_templates.borrow_mut();
+        for (id, withdrawal) in received {
+            let known = previous.get(&id);
+            if withdrawal {
+                self.stats.ignored_withdrawals += 1;
+                match known {
+                    Some(template) => templates.insert(id, Rc::clone(template)),
+                    None => templates.remove(&id),
+                };
+                continue;
+            }
+            if let (Some(known), Some(template)) = (known, templates.get(&id)) {
+                if known != template {
+                    self.stats.redefined_templates += 1;
+                }
+            }
+            self.refreshed.insert(id, now);
+        }
+    }
+
+    /// Record the templates received in a NetFlow v9 packet
+    fn refresh_netflow_v9_templates(&mut self, pkt: &netflow::NetFlowV9Packet, now: Instant) {
+        for set in pkt.sets() {
+            let ids: Vec<u16> = match set {
+                netflow::Set::Template(templates) => {
+                    templates.iter().map(|template| template.id()).collect()
+                }
+                netflow::Set::OptionsTemplate(templates) => {
+                    templates.iter().map(|template| template.id()).collect()
+                }
+                netflow::Set::Data { .. } => vec![],
+            };
+            for id in ids {
+                self.refreshed.insert(id, now);
+            }
+        }
+    }
+}
+
+/// Whether the IPFIX packet carries template or options template sets
+fn has_ipfix_template_sets(packet: &[u8]) -> bool {
+    let mut offset = IPFIX_HEADER_LENGTH as usize;
+    while offset + 4 <= packet.len() {
+        let id = u16::from_be_bytes([packet[offset], packet[offset + 1]]);
+        if id == ipfix::IPFIX_TEMPLATE_SET_ID || id == ipfix::IPFIX_OPTIONS_TEMPLATE_SET_ID {
+            return true;
+        }
+        let length = u16::from_be_bytes([packet[offset + 2], packet[offset + 3]]) as usize;
+        if length < 4 {
+            return false;
+        }
+        offset += length;
+    }
+    false
+}
+
+/// Decodes IPFIX and NetFlow v9 packets received over UDP, each datagram
+/// carrying a single packet, see the [module documentation](self) for the
+/// handling of templates and sequence numbers.
+///
+/// Data sets referencing a template that is not known yet fail to decode,
+/// packets are not buffered until the template is received as with
+/// [`crate::codec::FlowInfoCodec`].
+#[derive(Debug)]
+pub struct UdpSessionManager {
+    template_lifetime: Duration,
+    sessions: HashMap<UdpSessionKey, UdpSession>,
+}
+
+impl Default for UdpSessionManager {
+    fn default() -> Self {
+        Self {
+            template_lifetime: DEFAULT_TEMPLATE_LIFETIME,
+            sessions: HashMap::new(),
+        }
+    }
+}
+
+impl UdpSessionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lifetime of the templates, it should be at least three times the
+    /// template refresh timeout configured on the exporters
+    pub const fn with_template_lifetime(mut self, template_lifetime: Duration) -> Self {
+        self.template_lifetime = template_lifetime;
+        self
+    }
+
+    pub const fn template_lifetime(&self) -> Duration {
+        self.template_lifetime
+    }
+
+    /// Decode a datagram received from `exporter`
+    pub fn decode(
+        &mut self,
+        exporter: SocketAddr,
+        datagram: &[u8],
+    ) -> Result<FlowInfo, UdpDecodingError> {
+        self.decode_at(exporter, datagram, Instant::now())
+    }
+
+    /// Decode a datagram received from `exporter` at the given time
+    pub fn decode_at(
+        &mut self,
+        exporter: SocketAddr,
+        datagram: &[u8],
+        now: Instant,
+    ) -> Result<FlowInfo, UdpDecodingError> {
+        if datagram.len() < 2 {
+            return Err(UdpDecodingError::Truncated(datagram.len()));
+        }
+        let version = u16::from_be_bytes([datagram[0], datagram[1]]);
+        match version {
+            ipfix::IPFIX_VERSION => self.decode_ipfix(exporter, datagram, now),
+            netflow::NETFLOW_V9_VERSION => self.decode_netflow_v9(exporter, datagram, now),
+            version => Err(UdpDecodingError::UnsupportedVersion(version)),
+        }
+    }
+
+    fn session(&mut self, key: UdpSessionKey, version: u16, now: Instant) -> &mut UdpSession {
+        let lifetime = self.template_lifetime;
+        let session = self
+            .sessions
+            .entry(key)
+            .or_insert_with(|| UdpSession::new(version));
+        if session.version != version {
+            *session = UdpSession::new(version);
+        }
+        session.expire_templates(now, lifetime);
+        session
+    }
+
+    fn decode_ipfix(
+        &mut self,
+        exporter: SocketAddr,
+        datagram: &[u8],
+        now: Instant,
+    ) -> Result<FlowInfo, UdpDecodingError> {
+        if datagram.len() < IPFIX_HEADER_LENGTH as usize {
+            return Err(UdpDecodingError::Truncated(datagram.len()));
+        }
+        let length = u16::from_be_bytes([datagram[2], datagram[3]]) as usize;
+        if datagram.len() < length {
+            return Err(UdpDecodingError::Truncated(datagram.len()));
+        }
+        let packet = &datagram[..length];
+        let observation_domain_id =
+            u32::from_be_bytes([packet[12], packet[13], packet[14], packet[15]]);
+        let key = UdpSessionKey::new(exporter, observation_domain_id);
+        let session = self.session(key, ipfix::IPFIX_VERSION, now);
+        let previous = if has_ipfix_template_sets(packet) {
+            session.ipfix_templates.borrow().clone()
+        } else {
+            HashMap::new()
+        };
+        let pkt =
+            match ipfix::IpfixPacket::from_wire(Span::new(packet), session.ipfix_templates.clone())
+            {
+                Ok((_, pkt)) => pkt,
+                Err(nom::Err::Incomplete(_)) => {
+                    return Err(UdpDecodingError::Truncated(datagram.len()))
+                }
+                Err(nom::Err::Error(err) | nom::Err::Failure(err)) => {
+                    return Err(UdpDecodingError::IpfixParsingError(err.error().clone()))
+                }
+            };
+        session.refresh_ipfix_templates(&pkt, &previous, now);
+        let records = pkt
+            .sets()
+            .iter()
+            .map(|set| match set {
+                ipfix::Set::Data { records, .. } => records.len(),
+                _ => 0,
+            })
+            .sum::<usize>();
+        session
+            .stats
+            .observe_sequence_number(pkt.sequence_number(), records as u32);
+        Ok(FlowInfo::IPFIX(pkt))
+    }
+
+    fn decode_netflow_v9(
+        &mut self,
+        exporter: SocketAddr,
+        datagram: &[u8],
+        now: Instant,
+    ) -> Result<FlowInfo, UdpDecodingError> {
+        if datagram.len() < NETFLOW_V9_HEADER_LENGTH {
+            return Err(UdpDecodingError::Truncated(datagram.len()));
+        }
+        let source_id =
+            u32::from_be_bytes([datagram[16], datagram[17], datagram[18], datagram[19]]);
+        let key = UdpSessionKey::new(exporter, source_id);
+        let session = self.session(key, netflow::NETFLOW_V9_VERSION, now);
+        let pkt = match netflow::NetFlowV9Packet::from_wire(
+            Span::new(datagram),
+            session.netflow_v9_templates.clone(),
+        ) {
+            Ok((_, pkt)) => pkt,
+            Err(nom::Err::Incomplete(_)) => {
+                return Err(UdpDecodingError::Truncated(datagram.len()))
+            }
+            Err(nom::Err::Error(err) | nom::Err::Failure(err)) => {
+                return Err(UdpDecodingError::NetFlowV9ParsingError(err.error().clone()))
+            }
+        };
+        session.refresh_netflow_v9_templates(&pkt, now);
+        session
+            .stats
+            .observe_sequence_number(pkt.sequence_number(), 1);
+        Ok(FlowInfo::NetFlowV9(pkt))
+    }
+
+    /// Drop the templates of all the sessions that weren't refreshed within
+    /// their lifetime, returns the number of expired templates. Templates are
+    /// also expired when decoding, this is meant to be called periodically to
+    /// release the templates of inactive sessions.
+    pub fn expire_templates(&mut self, now: Instant) -> usize {
+        let lifetime = self.template_lifetime;
+        self.sessions
+            .values_mut()
+            .map(|session| session.expire_templates(now, lifetime))
+            .sum()
+    }
+
+    /// Number of templates currently known for the session
+    pub fn templates_count(&self, key: &UdpSessionKey) -> Option<usize> {
+        self.sessions
+            .get(key)
+            .map(|session| session.refreshed.len())
+    }
+
+    pub fn stats(&self, key: &UdpSessionKey) -> Option<&SessionStats> {
+        self.sessions.get(key).map(|session| &session.stats)
+    }
+
+    /// Known sessions along with their counters
+    pub fn sessions(&self) -> impl Iterator<Item = (&UdpSessionKey, &SessionStats)> {
+        self.sessions
+            .iter()
+            .map(|(key, session)| (key, &session.stats))
+    }
+
+    /// Remove all the sessions of an exporter, returns the number of removed
+    /// sessions
+    pub fn remove_exporter(&mut self, exporter: &SocketAddr) -> usize {
+        let before = self.sessions.len();
+        self.sessions.retain(|key, _| key.exporter != *exporter);
+        before - self.sessions.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_vectors::ipfix_packets, wire::deserializer::ipfix::SetParsingError};
+
+    fn exporter() -> SocketAddr {
+        SocketAddr::from(([192, 0, 2, 1], 4739))
+    }
+
+    /// Data set vector with the given sequence number
+    fn data_packet(sequence_number: u32) -> Vec<u8> {
+        let mut packet = ipfix_packets()[1].wire().to_vec();
+        packet[8..12].copy_from_slice(&sequence_number.to_be_bytes());
+        packet
+    }
+
+    #[test]
+    fn test_sequence_gaps() {
+        let vectors = ipfix_packets();
+        let now = Instant::now();
+        let mut manager = UdpSessionManager::new();
+        let key = UdpSessionKey::new(exporter(), 0);
+        manager
+            .decode_at(exporter(), vectors[0].wire(), now)
+            .unwrap();
+        // The template packet carries no data records
+        assert_eq!(
+            manager.stats(&key).unwrap().next_sequence_number(),
+            Some(3812)
+        );
+        for sequence_number in [3812, 3815, 3813, 3816] {
+            manager
+                .decode_at(exporter(), &data_packet(sequence_number), now)
+                .unwrap();
+        }
+        let stats = manager.stats(&key).unwrap();
+        assert_eq!(stats.packets(), 5);
+        assert_eq!(stats.gaps(), 1);
+        assert_eq!(stats.missing(), 2);
+        assert_eq!(stats.reordered(), 1);
+        assert_eq!(stats.next_sequence_number(), Some(3817));
+
+        // Further behind than the reorder window, e.g., the exporter restarted
+        let restarted = 3817u32.wrapping_sub(100_000);
+        manager
+            .decode_at(exporter(), &data_packet(restarted), now)
+            .unwrap();
+        let stats = manager.stats(&key).unwrap();
+        assert_eq!(stats.sequence_resets(), 1);
+        assert_eq!(stats.next_sequence_number(), Some(restarted + 1));
+    }
+
+    #[test]
+    fn test_template_expiry() {
+        let vectors = ipfix_packets();
+        let now = Instant::now();
+        let mut manager = UdpSessionManager::new().with_template_lifetime(Duration::from_secs(60));
+        let key = UdpSessionKey::new(exporter(), 0);
+        manager
+            .decode_at(exporter(), vectors[0].wire(), now)
+            .unwrap();
+        assert_eq!(manager.templates_count(&key), Some(1));
+        assert_eq!(
+            manager.decode_at(exporter(), vectors[1].wire(), now + Duration::from_secs(30)),
+            Ok(FlowInfo::IPFIX(vectors[1].expected().clone()))
+        );
+        assert_eq!(
+            manager.decode_at(exporter(), vectors[1].wire(), now + Duration::from_secs(61)),
+            Err(UdpDecodingError::IpfixParsingError(
+                IpfixPacketParsingError::SetParsingError(SetParsingError::NoTemplateDefinedFor(
+                    307
+                ))
+            ))
+        );
+        assert_eq!(manager.templates_count(&key), Some(0));
+        assert_eq!(manager.stats(&key).unwrap().expired_templates(), 1);
+    }
+
+    #[test]
+    fn test_withdrawal_ignored() {
+        let vectors = ipfix_packets();
+        let now = Instant::now();
+        let mut manager = UdpSessionManager::new();
+        let key = UdpSessionKey::new(exporter(), 0);
+        manager
+            .decode_at(exporter(), vectors[0].wire(), now)
+            .unwrap();
+        let withdrawal = [
+            0x00, 0x0a, 0x00, 0x18, 0x58, 0x3d, 0xe0, 0x59, 0x00, 0x00, 0x0e, 0xe4, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x02, 0x00, 0x08, 0x01, 0x33, 0x00, 0x00,
+        ];
+        manager.decode_at(exporter(), &withdrawal, now).unwrap();
+        assert_eq!(manager.stats(&key).unwrap().ignored_withdrawals(), 1);
+        assert_eq!(
+            manager.decode_at(exporter(), vectors[1].wire(), now),
+            Ok(FlowInfo::IPFIX(vectors[1].expected().clone()))
+        );
+    }
+
+    #[test]
+    fn test_sessions_per_observation_domain() {
+        let vectors = ipfix_packets();
+        let now = Instant::now();
+        let mut manager = UdpSessionManager::new();
+        manager
+            .decode_at(exporter(), vectors[0].wire(), now)
+            .unwrap();
+        let mut other_domain = vectors[1].wire().to_vec();
+        other_domain[12..16].copy_from_slice(&7u32.to_be_bytes());
+        assert!(manager.decode_at(exporter(), &other_domain, now).is_err());
+        assert_eq!(manager.sessions().count(), 2);
+        assert_eq!(manager.remove_exporter(&exporter()), 2);
+    }
+}
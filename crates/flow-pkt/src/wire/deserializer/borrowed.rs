@@ -0,0 +1,463 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Zero-copy decoding of IPFIX packets, as a borrowed alternative to
+//! [`IpfixPacket`].
+//!
+//! The data sets are decoded lazily: [`IpfixPacketRef::sets`] and
+//! [`DataSetRef::records`] are iterators, and the fields of a record are
+//! [`FieldRef`]s that borrow their value from the input buffer. String and
+//! octetArray values are read without allocating, and a field is only
+//! converted to an owned [`Field`] when [`FieldRef::to_field`] is called.
+//!
+//! Template sets are still decoded into their owned records, since they're
+//! kept in the [`TemplatesMap`] anyway.
+//!
+//! ```ignore
+//! let packet = IpfixPacketRef::from_wire(Span::new(&buf))?.1;
+//! for set in packet.sets(templates_map.clone()) {
+//!     if let SetRef::Data(data_set) = set? {
+//!         for record in data_set.records() {
+//!             for field in record?.fields() {
+//!                 if field.ie() == IE::applicationName {
+//!                     println!("{:?}", field.as_str());
+//!                 }
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::{rc::Rc, str::Utf8Error};
+
+use chrono::{DateTime, Utc};
+use nom::{error::ErrorKind, number::complete::be_u16, IResult};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ie::{Field, IE},
+    ipfix::*,
+    wire::deserializer::{
+        ie::FieldParsingError,
+        ipfix::{
+            min_record_length, parse_ipfix_header, LocatedIpfixPacketParsingError,
+            LocatedSetParsingError, SetParsingError,
+        },
+        parse_variable_length,
+    },
+    DataSetId, FieldSpecifier, DATA_SET_MIN_ID,
+};
+use netgauze_parse_utils::{
+    LocatedParsingError, ReadablePdu, ReadablePduWithOneInput, ReadablePduWithTwoInputs, Span,
+};
+use netgauze_serde_macros::LocatedError;
+
+/// IPFIX packet whose sets are decoded on demand from the input buffer
+#[derive(Debug, Clone, PartialEq)]
+pub struct IpfixPacketRef<'a> {
+    export_time: DateTime<Utc>,
+    sequence_number: u32,
+    observation_domain_id: u32,
+    sets: Span<'a>,
+}
+
+impl<'a> IpfixPacketRef<'a> {
+    pub const fn export_time(&self) -> DateTime<Utc> {
+        self.export_time
+    }
+
+    pub const fn sequence_number(&self) -> u32 {
+        self.sequence_number
+    }
+
+    pub const fn observation_domain_id(&self) -> u32 {
+        self.observation_domain_id
+    }
+
+    /// Decode the sets one by one, the template sets update `templates_map`
+    /// as they're decoded. The iterator stops after the first error.
+    pub fn sets(&self, templates_map: TemplatesMap) -> SetRefIter<'a> {
+        SetRefIter {
+            buf: self.sets,
+            templates_map,
+        }
+    }
+}
+
+impl<'a> ReadablePdu<'a, LocatedIpfixPacketParsingError<'a>> for IpfixPacketRef<'a> {
+    fn from_wire(buf: Span<'a>) -> IResult<Span<'a>, Self, LocatedIpfixPacketParsingError<'a>> {
+        let (reminder, (export_time, sequence_number, observation_domain_id, sets)) =
+            parse_ipfix_header(buf)?;
+        Ok((
+            reminder,
+            IpfixPacketRef {
+                export_time,
+                sequence_number,
+                observation_domain_id,
+                sets,
+            },
+        ))
+    }
+}
+
+/// Iterator over the sets of an [`IpfixPacketRef`]
+#[derive(Debug)]
+pub struct SetRefIter<'a> {
+    buf: Span<'a>,
+    templates_map: TemplatesMap,
+}
+
+impl<'a> Iterator for SetRefIter<'a> {
+    type Item = Result<SetRef<'a>, LocatedSetParsingError<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() {
+            return None;
+        }
+        match SetRef::from_wire(self.buf, Rc::clone(&self.templates_map)) {
+            Ok((buf, set)) => {
+                self.buf = buf;
+                Some(Ok(set))
+            }
+            Err(err) => {
+                let buf = self.buf;
+                self.buf = Span::new(&[]);
+                Some(Err(match err {
+                    nom::Err::Incomplete(_) => {
+                        LocatedSetParsingError::new(buf, SetParsingError::NomError(ErrorKind::Eof))
+                    }
+                    nom::Err::Error(err) | nom::Err::Failure(err) => err,
+                }))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetRef<'a> {
+    Template(Vec<TemplateRecord>),
+    OptionsTemplate(Vec<OptionsTemplateRecord>),
+    Data(DataSetRef<'a>),
+}
+
+impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedSetParsingError<'a>> for SetRef<'a> {
+    fn from_wire(
+        buf: Span<'a>,
+        templates_map: TemplatesMap,
+    ) -> IResult<Span<'a>, Self, LocatedSetParsingError<'a>> {
+        let input = buf;
+        let (_, id) = nom::combinator::peek(be_u16)(buf)?;
+        if id < DATA_SET_MIN_ID {
+            // Template sets, and invalid set IDs, are left to the owned decoder
+            let (reminder, set) = Set::from_wire(buf, templates_map)?;
+            let set = match set {
+                Set::Template(templates) => SetRef::Template(templates),
+                Set::OptionsTemplate(templates) => SetRef::OptionsTemplate(templates),
+                Set::Data { .. } => unreachable!("data sets have an ID of 256 or above"),
+            };
+            return Ok((reminder, set));
+        }
+        let (buf, _) = be_u16(buf)?;
+        let (buf, length) = nom::combinator::map_res(be_u16, |length| {
+            if length < 4 {
+                Err(SetParsingError::InvalidLength(length))
+            } else {
+                Ok(length)
+            }
+        })(buf)?;
+        let (reminder, records) = nom::bytes::complete::take(length - 4)(buf)?;
+        let template = match templates_map.borrow().get(&id) {
+            Some(template) => Rc::clone(template),
+            None => {
+                return Err(nom::Err::Error(LocatedSetParsingError::new(
+                    input,
+                    SetParsingError::NoTemplateDefinedFor(id),
+                )));
+            }
+        };
+        Ok((
+            reminder,
+            SetRef::Data(DataSetRef {
+                // The range is already checked
+                id: DataSetId::new(id).unwrap(),
+                template,
+                records,
+            }),
+        ))
+    }
+}
+
+/// Data set whose records are decoded on demand
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataSetRef<'a> {
+    id: DataSetId,
+    template: Rc<DecodingTemplate>,
+    records: Span<'a>,
+}
+
+impl<'a> DataSetRef<'a> {
+    pub const fn id(&self) -> DataSetId {
+        self.id
+    }
+
+    /// Template the records are decoded with
+    pub fn template(&self) -> &DecodingTemplate {
+        &self.template
+    }
+
+    /// Decode the records one by one, the trailing padding is skipped. The
+    /// iterator stops after the first error.
+    pub fn records(&self) -> DataRecordRefIter<'a> {
+        DataRecordRefIter {
+            buf: self.records,
+            min_record_length: min_record_length(&self.template),
+            template: Rc::clone(&self.template),
+        }
+    }
+}
+
+/// Iterator over the records of a [`DataSetRef`]
+#[derive(Debug)]
+pub struct DataRecordRefIter<'a> {
+    buf: Span<'a>,
+    min_record_length: usize,
+    template: Rc<DecodingTemplate>,
+}
+
+impl<'a> Iterator for DataRecordRefIter<'a> {
+    type Item = Result<DataRecordRef<'a>, LocatedDataRecordRefParsingError<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.len() < self.min_record_length {
+            return None;
+        }
+        match DataRecordRef::from_wire(self.buf, Rc::clone(&self.template)) {
+            Ok((buf, _)) if buf.len() == self.buf.len() => {
+                let buf = self.buf;
+                self.buf = Span::new(&[]);
+                Some(Err(LocatedDataRecordRefParsingError::new(
+                    buf,
+                    DataRecordRefParsingError::EmptyRecord,
+                )))
+            }
+            Ok((buf, record)) => {
+                self.buf = buf;
+                Some(Ok(record))
+            }
+            Err(err) => {
+                let buf = self.buf;
+                self.buf = Span::new(&[]);
+                Some(Err(match err {
+                    nom::Err::Incomplete(_) => LocatedDataRecordRefParsingError::new(
+                        buf,
+                        DataRecordRefParsingError::NomError(ErrorKind::Eof),
+                    ),
+                    nom::Err::Error(err) | nom::Err::Failure(err) => err,
+                }))
+            }
+        }
+    }
+}
+
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DataRecordRefParsingError {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
+    NomError(#[from_nom] ErrorKind),
+    /// The template has no fields, or only fields of zero length
+    EmptyRecord,
+}
+
+/// Data record borrowed from the input buffer, its fields are walked without
+/// allocating
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataRecordRef<'a> {
+    template: Rc<DecodingTemplate>,
+    scope_fields: Span<'a>,
+    fields: Span<'a>,
+}
+
+impl<'a> DataRecordRef<'a> {
+    pub fn scope_fields(&self) -> FieldRefIter<'_, 'a> {
+        FieldRefIter {
+            specs: self.template.0.iter(),
+            buf: self.scope_fields,
+        }
+    }
+
+    pub fn fields(&self) -> FieldRefIter<'_, 'a> {
+        FieldRefIter {
+            specs: self.template.1.iter(),
+            buf: self.fields,
+        }
+    }
+
+    /// Decode the owned version of the record
+    pub fn to_data_record(&self) -> Result<DataRecord, FieldParsingError> {
+        Ok(DataRecord::new(
+            self.scope_fields()
+                .map(|field| field.to_field())
+                .collect::<Result<_, _>>()?,
+            self.fields()
+                .map(|field| field.to_field())
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+}
+
+impl<'a> ReadablePduWithOneInput<'a, Rc<DecodingTemplate>, LocatedDataRecordRefParsingError<'a>>
+    for DataRecordRef<'a>
+{
+    fn from_wire(
+        buf: Span<'a>,
+        template: Rc<DecodingTemplate>,
+    ) -> IResult<Span<'a>, Self, LocatedDataRecordRefParsingError<'a>> {
+        let (scope_field_specs, field_specs) = template.as_ref();
+        let scope_fields = buf;
+        let mut buf = buf;
+        for spec in scope_field_specs {
+            buf = parse_field_ref(buf, spec)?.0;
+        }
+        let scope_fields_len = buf.location_offset() - scope_fields.location_offset();
+        let fields = buf;
+        for spec in field_specs {
+            buf = parse_field_ref(buf, spec)?.0;
+        }
+        let fields_len = buf.location_offset() - fields.location_offset();
+        let (_, scope_fields) = nom::bytes::complete::take(scope_fields_len)(scope_fields)?;
+        let (_, fields) = nom::bytes::complete::take(fields_len)(fields)?;
+        Ok((
+            buf,
+            DataRecordRef {
+                template,
+                scope_fields,
+                fields,
+            },
+        ))
+    }
+}
+
+#[inline]
+fn parse_field_ref<'a>(
+    buf: Span<'a>,
+    spec: &FieldSpecifier,
+) -> IResult<Span<'a>, FieldRef<'a>, LocatedDataRecordRefParsingError<'a>> {
+    let input = buf;
+    let (buf, length) = if spec.length() == u16::MAX {
+        parse_variable_length(buf)?
+    } else {
+        (buf, spec.length())
+    };
+    let (buf, value) = nom::bytes::complete::take(length)(buf)?;
+    let raw_len = buf.location_offset() - input.location_offset();
+    let input: &'a [u8] = *input.fragment();
+    Ok((
+        buf,
+        FieldRef {
+            ie: spec.element_id(),
+            length: spec.length(),
+            raw: &input[..raw_len],
+            value: *value.fragment(),
+        },
+    ))
+}
+
+/// Iterator over the fields of a [`DataRecordRef`]
+#[derive(Debug)]
+pub struct FieldRefIter<'s, 'a> {
+    specs: std::slice::Iter<'s, FieldSpecifier>,
+    buf: Span<'a>,
+}
+
+impl<'a> Iterator for FieldRefIter<'_, 'a> {
+    type Item = FieldRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let spec = self.specs.next()?;
+        // The record is already checked to hold all the fields of the template
+        let (buf, field) = parse_field_ref(self.buf, spec).ok()?;
+        self.buf = buf;
+        Some(field)
+    }
+}
+
+/// Field of a data record, its value borrows the input buffer
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FieldRef<'a> {
+    ie: IE,
+    /// Length of the field specifier, [`u16::MAX`] for variable length fields
+    length: u16,
+    /// Value including the length prefix of variable length fields
+    raw: &'a [u8],
+    value: &'a [u8],
+}
+
+impl<'a> FieldRef<'a> {
+    pub const fn ie(&self) -> IE {
+        self.ie
+    }
+
+    pub const fn is_variable_length(&self) -> bool {
+        self.length == u16::MAX
+    }
+
+    /// Value of the field as it's on the wire, without the length prefix of
+    /// variable length fields
+    pub const fn value(&self) -> &'a [u8] {
+        self.value
+    }
+
+    /// Value of string fields, fixed length values are cut at the first NUL
+    /// like in [`Field`]
+    pub fn as_str(&self) -> Result<&'a str, Utf8Error> {
+        let value = if self.is_variable_length() {
+            self.value
+        } else {
+            let end = self
+                .value
+                .iter()
+                .position(|&c| c == b'\0')
+                .unwrap_or(self.value.len());
+            &self.value[..end]
+        };
+        std::str::from_utf8(value)
+    }
+
+    /// Value of unsigned fields, including the ones with a reduced size
+    /// encoding. `None` for values longer than eight octets.
+    pub fn as_unsigned(&self) -> Option<u64> {
+        if self.value.len() > 8 {
+            return None;
+        }
+        Some(
+            self.value
+                .iter()
+                .fold(0u64, |acc, octet| (acc << 8) | *octet as u64),
+        )
+    }
+
+    /// Decode the owned version of the field
+    pub fn to_field(&self) -> Result<Field, FieldParsingError> {
+        match Field::from_wire(Span::new(self.raw), &self.ie, self.length) {
+            Ok((_, field)) => Ok(field),
+            Err(nom::Err::Incomplete(_)) => Err(FieldParsingError::NomError(ErrorKind::Eof)),
+            Err(nom::Err::Error(err) | nom::Err::Failure(err)) => Err(err.error().clone()),
+        }
+    }
+}
@@ -15,12 +15,13 @@
 
 use std::{cell::RefMut, rc::Rc};
 
-use chrono::{LocalResult, TimeZone, Utc};
+use chrono::{DateTime, LocalResult, TimeZone, Utc};
 use nom::{
     error::ErrorKind,
     number::complete::{be_u16, be_u32, be_u8},
     IResult,
 };
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -30,8 +31,7 @@ use crate::{
 };
 use netgauze_parse_utils::{
     parse_into_located, parse_into_located_one_input, parse_into_located_two_inputs,
-    parse_till_empty_into_with_one_input_located, ErrorKindSerdeDeref, ReadablePduWithOneInput,
-    Span,
+    parse_till_empty_into_with_one_input_located, ReadablePduWithOneInput, Span,
 };
 use netgauze_serde_macros::LocatedError;
 
@@ -39,10 +39,15 @@ use netgauze_serde_macros::LocatedError;
 /// observation domain id)
 pub const IPFIX_HEADER_LENGTH: u16 = 16;
 
-#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum IpfixPacketParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(Unsupported)]
     UnsupportedVersion(u16),
     InvalidLength(u16),
     InvalidExportTime(u32),
@@ -56,35 +61,8 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedIpfixPacketParsingErro
         buf: Span<'a>,
         templates_map: TemplatesMap,
     ) -> IResult<Span<'a>, Self, LocatedIpfixPacketParsingError<'a>> {
-        let input = buf;
-        let (buf, version) = be_u16(buf)?;
-        if version != IPFIX_VERSION {
-            return Err(nom::Err::Error(LocatedIpfixPacketParsingError::new(
-                input,
-                IpfixPacketParsingError::UnsupportedVersion(version),
-            )));
-        }
-        let input = buf;
-        let (buf, length) = be_u16(buf)?;
-        if length < IPFIX_HEADER_LENGTH {
-            return Err(nom::Err::Error(LocatedIpfixPacketParsingError::new(
-                input,
-                IpfixPacketParsingError::InvalidLength(length),
-            )));
-        }
-        let (reminder, buf) = nom::bytes::complete::take(length - 4)(buf)?;
-        let (buf, export_time) = be_u32(buf)?;
-        let export_time = match Utc.timestamp_opt(export_time as i64, 0) {
-            LocalResult::Single(time) => time,
-            _ => {
-                return Err(nom::Err::Error(LocatedIpfixPacketParsingError::new(
-                    input,
-                    IpfixPacketParsingError::InvalidExportTime(export_time),
-                )));
-            }
-        };
-        let (buf, sequence_number) = be_u32(buf)?;
-        let (buf, observation_domain_id) = be_u32(buf)?;
+        let (reminder, (export_time, sequence_number, observation_domain_id, buf)) =
+            parse_ipfix_header(buf)?;
         let (_, payload) = parse_till_empty_into_with_one_input_located(buf, templates_map)?;
         Ok((
             reminder,
@@ -93,14 +71,62 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedIpfixPacketParsingErro
     }
 }
 
-#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+/// Parse the IPFIX message header, returns the export time, sequence number,
+/// observation domain id and the sets of the message
+#[allow(clippy::type_complexity)]
+pub(crate) fn parse_ipfix_header(
+    buf: Span<'_>,
+) -> IResult<Span<'_>, (DateTime<Utc>, u32, u32, Span<'_>), LocatedIpfixPacketParsingError<'_>> {
+    let input = buf;
+    let (buf, version) = be_u16(buf)?;
+    if version != IPFIX_VERSION {
+        return Err(nom::Err::Error(LocatedIpfixPacketParsingError::new(
+            input,
+            IpfixPacketParsingError::UnsupportedVersion(version),
+        )));
+    }
+    let input = buf;
+    let (buf, length) = be_u16(buf)?;
+    if length < IPFIX_HEADER_LENGTH {
+        return Err(nom::Err::Error(LocatedIpfixPacketParsingError::new(
+            input,
+            IpfixPacketParsingError::InvalidLength(length),
+        )));
+    }
+    let (reminder, buf) = nom::bytes::complete::take(length - 4)(buf)?;
+    let (buf, export_time) = be_u32(buf)?;
+    let export_time = match Utc.timestamp_opt(export_time as i64, 0) {
+        LocalResult::Single(time) => time,
+        _ => {
+            return Err(nom::Err::Error(LocatedIpfixPacketParsingError::new(
+                input,
+                IpfixPacketParsingError::InvalidExportTime(export_time),
+            )));
+        }
+    };
+    let (buf, sequence_number) = be_u32(buf)?;
+    let (buf, observation_domain_id) = be_u32(buf)?;
+    Ok((
+        reminder,
+        (export_time, sequence_number, observation_domain_id, buf),
+    ))
+}
+
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SetParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidLength(u16),
     InvalidSetId(u16),
     NoTemplateDefinedFor(u16),
     InvalidPaddingValue(u8),
+    /// The template of the data set has no fields, or only fields of zero
+    /// length, so its records don't take any octet of the set
+    EmptyDataRecord(u16),
     TemplateRecordError(#[from_located(module = "self")] TemplateRecordParsingError),
     OptionsTemplateRecordError(#[from_located(module = "self")] OptionsTemplateRecordParsingError),
     DataRecordError(#[from_located(module = "self")] DataRecordParsingError),
@@ -164,21 +190,22 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedSetParsingError<'a>> f
                         SetParsingError::NoTemplateDefinedFor(id),
                     )));
                 };
-                let (scope_field_specs, field_specs) = template.as_ref();
-                let mut total_record_count = scope_field_specs.len() + field_specs.len();
+                let min_record_length = min_record_length(template);
                 let mut records = Vec::new();
-                while total_record_count > 0 {
+                while buf.len() >= min_record_length {
                     let (t, record): (Span<'_>, DataRecord) =
                         parse_into_located_one_input(buf, Rc::clone(template))?;
+                    if t.len() == buf.len() {
+                        return Err(nom::Err::Error(LocatedSetParsingError::new(
+                            buf,
+                            SetParsingError::EmptyDataRecord(id),
+                        )));
+                    }
                     buf = t;
-                    total_record_count -= record.scope_fields().len() + record.fields().len();
                     records.push(record);
                 }
                 // buf could be a non zero value for padding
-                while buf.len() > 0 && nom::combinator::peek(be_u8)(buf)?.1 == 0 {
-                    let (t, _) = be_u8(buf)?;
-                    buf = t;
-                }
+                check_padding_value(buf)?;
 
                 // We can safely unwrap DataSetId here since we already checked the range
                 Set::Data {
@@ -191,6 +218,25 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedSetParsingError<'a>> f
     }
 }
 
+/// Shortest length a record of the template can have, anything shorter that
+/// is left in a data set is padding
+pub(crate) fn min_record_length(template: &DecodingTemplate) -> usize {
+    let (scope_field_specs, field_specs) = template;
+    // Variable length fields take at least the one octet of their length
+    scope_field_specs
+        .iter()
+        .chain(field_specs.iter())
+        .map(|x| {
+            if x.length() == u16::MAX {
+                1
+            } else {
+                x.length() as usize
+            }
+        })
+        .sum::<usize>()
+        .max(1)
+}
+
 #[inline]
 fn check_padding_value(mut buf: Span<'_>) -> IResult<Span<'_>, (), LocatedSetParsingError<'_>> {
     while buf.len() > 0 {
@@ -206,9 +252,13 @@ fn check_padding_value(mut buf: Span<'_>) -> IResult<Span<'_>, (), LocatedSetPar
     Ok((buf, ()))
 }
 
-#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum OptionsTemplateRecordParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidTemplateId(u16),
     /// Scope fields count must be less than the total fields count
@@ -261,7 +311,12 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedOptionsTemplateRecordP
         }
         {
             let mut map: RefMut<'_, _> = templates_map.borrow_mut();
-            map.insert(template_id, Rc::new((scope_fields.clone(), fields.clone())));
+            // A record without any field withdraws the template
+            if scope_fields.is_empty() && fields.is_empty() {
+                map.remove(&template_id);
+            } else {
+                map.insert(template_id, Rc::new((scope_fields.clone(), fields.clone())));
+            }
         }
         Ok((
             buf,
@@ -270,7 +325,8 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedOptionsTemplateRecordP
     }
 }
 
-#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum DataRecordParsingError {
     FieldError(#[from_located(module = "")] ie::FieldParsingError),
 }
@@ -303,9 +359,13 @@ impl<'a> ReadablePduWithOneInput<'a, Rc<DecodingTemplate>, LocatedDataRecordPars
     }
 }
 
-#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TemplateRecordParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidTemplateId(u16),
     FieldSpecifierError(
@@ -339,7 +399,12 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedTemplateRecordParsingE
         }
         {
             let mut map: RefMut<'_, _> = templates_map.borrow_mut();
-            map.insert(template_id, Rc::new((vec![], fields.clone())));
+            // A record without any field withdraws the template
+            if fields.is_empty() {
+                map.remove(&template_id);
+            } else {
+                map.insert(template_id, Rc::new((vec![], fields.clone())));
+            }
         }
         Ok((buf, TemplateRecord::new(template_id, fields)))
     }
@@ -14,31 +14,38 @@
 // limitations under the License.
 
 use nom::{
-    error::ErrorKind,
-    number::complete::{be_u16, be_u32},
+    error::{ErrorKind, ParseError},
+    number::complete::{be_u16, be_u32, be_u8},
     IResult,
 };
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use crate::{FieldSpecifier, FieldSpecifierError};
-use netgauze_parse_utils::{ErrorKindSerdeDeref, ReadablePdu, Span};
+use netgauze_parse_utils::{ReadablePdu, Span};
 use netgauze_serde_macros::LocatedError;
 
 use crate::ie::{IEError, IE};
 
+pub mod borrowed;
 pub mod ie;
 pub mod ipfix;
 pub mod netflow;
 
-#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FlowParsingError {
     IpfixParsingError(ipfix::IpfixPacketParsingError),
     NetFlowV9ParsingError(netflow::NetFlowV9PacketParsingError),
 }
 
-#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FieldSpecifierParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     FieldSpecifierError(FieldSpecifierError),
     IEError(IEError),
@@ -76,3 +83,18 @@ impl<'a> ReadablePdu<'a, LocatedFieldSpecifierParsingError<'a>> for FieldSpecifi
         Ok((buf, spec))
     }
 }
+
+/// Parse the length of a variable-length Information Element, that is
+/// encoded before its value in one octet, or in three octets starting with
+/// 255 for lengths of 255 or more
+/// ([RFC 7011 section 7](https://datatracker.ietf.org/doc/html/rfc7011#section-7))
+pub(crate) fn parse_variable_length<'a, E: ParseError<Span<'a>>>(
+    buf: Span<'a>,
+) -> IResult<Span<'a>, u16, E> {
+    let (buf, length) = be_u8(buf)?;
+    if length == u8::MAX {
+        be_u16(buf)
+    } else {
+        Ok((buf, length as u16))
+    }
+}
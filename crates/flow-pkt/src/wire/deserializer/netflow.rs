@@ -21,12 +21,13 @@ use nom::{
     number::complete::{be_u16, be_u32, be_u8},
     IResult, InputIter, InputLength, Slice,
 };
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use netgauze_parse_utils::{
     parse_into_located, parse_into_located_one_input, parse_into_located_two_inputs,
-    parse_till_empty_into_located, parse_till_empty_into_with_one_input_located,
-    ErrorKindSerdeDeref, ReadablePdu, ReadablePduWithOneInput, ReadablePduWithTwoInputs, Span,
+    parse_till_empty_into_located, parse_till_empty_into_with_one_input_located, ReadablePdu,
+    ReadablePduWithOneInput, ReadablePduWithTwoInputs, Span,
 };
 use netgauze_serde_macros::LocatedError;
 
@@ -39,10 +40,15 @@ use crate::{
 /// no, source id)
 pub const NETFLOW_V9_HEADER_LENGTH: u16 = 20;
 
-#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum NetFlowV9PacketParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
+    #[category(Unsupported)]
     UnsupportedVersion(u16),
     InvalidCount(u16),
     InvalidUnixTime(u32),
@@ -99,9 +105,13 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedNetFlowV9PacketParsing
     }
 }
 
-#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SetParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidLength(u16),
     InvalidSetId(u16),
@@ -213,9 +223,13 @@ fn check_padding_value(mut buf: Span<'_>) -> IResult<Span<'_>, (), LocatedSetPar
     Ok((buf, ()))
 }
 
-#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum OptionsTemplateRecordParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidTemplateId(u16),
     /// Scope fields count must be less than the total fields count
@@ -272,9 +286,13 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedOptionsTemplateRecordP
     }
 }
 
-#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TemplateRecordParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidTemplateId(u16),
     FieldSpecifierError(
@@ -314,9 +332,13 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedTemplateRecordParsingE
     }
 }
 
-#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ScopeFieldSpecifierParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidLength(ScopeIE, u16),
 }
@@ -352,7 +374,8 @@ impl<'a> ReadablePdu<'a, LocatedScopeFieldSpecifierParsingError<'a>> for ScopeFi
     }
 }
 
-#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum DataRecordParsingError {
     FieldError(#[from_located(module = "")] crate::wire::deserializer::ie::FieldParsingError),
     ScopeFieldError(#[from_located(module = "self")] ScopeFieldParsingError),
@@ -386,9 +409,13 @@ impl<'a> ReadablePduWithOneInput<'a, Rc<DecodingTemplate>, LocatedDataRecordPars
     }
 }
 
-#[derive(LocatedError, Eq, PartialEq, Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ScopeFieldParsingError {
-    #[serde(with = "ErrorKindSerdeDeref")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "netgauze_parse_utils::ErrorKindSerdeDeref")
+    )]
     NomError(#[from_nom] ErrorKind),
     InvalidLength(u16),
     Utf8Error(String),
@@ -53,3 +53,34 @@ impl WritablePdu<FieldSpecifierWritingError> for FieldSpecifier {
         Ok(())
     }
 }
+
+/// Number of octets used to encode the length of a variable-length
+/// Information Element of `length` octets
+pub(crate) const fn variable_length_prefix_len(length: usize) -> usize {
+    if length < u8::MAX as usize {
+        1
+    } else {
+        3
+    }
+}
+
+/// Write the length of a variable-length Information Element, in one octet,
+/// or in three octets starting with 255 for lengths of 255 or more
+/// ([RFC 7011 section 7](https://datatracker.ietf.org/doc/html/rfc7011#section-7))
+pub(crate) fn write_variable_length<T: Write>(
+    writer: &mut T,
+    length: usize,
+) -> Result<(), std::io::Error> {
+    if length < u8::MAX as usize {
+        writer.write_u8(length as u8)
+    } else {
+        let length = u16::try_from(length).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("variable-length value of {length} octets is too long"),
+            )
+        })?;
+        writer.write_u8(u8::MAX)?;
+        writer.write_u16::<NetworkEndian>(length)
+    }
+}
@@ -20,7 +20,9 @@ use crate::{
     DataSetId, FieldSpecifier,
 };
 use chrono::{TimeZone, Timelike, Utc};
-use netgauze_parse_utils::{test_helpers::*, ReadablePduWithOneInput, Span};
+use netgauze_parse_utils::{
+    test_helpers::*, ReadablePduWithOneInput, Span, WritablePduWithOneInput,
+};
 use std::{cell::RefCell, collections::HashMap, net::Ipv4Addr, rc::Rc};
 
 #[test]
@@ -590,3 +592,237 @@ fn test_with_variable_string_length() -> Result<(), IpfixPacketWritingError> {
     test_write_with_one_input(&good_data, Some(templates_map.clone()), &good_data_wire)?;
     Ok(())
 }
+
+#[test]
+fn test_ipfix_exporter_round_trip() -> Result<(), IpfixPacketWritingError> {
+    let vectors = crate::test_vectors::ipfix_packets();
+
+    // Exporter side: the templates map is filled from the template records
+    // being sent, then used to write the data records
+    let exporter_templates = Rc::new(RefCell::new(HashMap::new()));
+    for set in vectors[0].expected().sets() {
+        if let Set::Template(records) = set {
+            for record in records {
+                exporter_templates.borrow_mut().insert(
+                    record.id(),
+                    Rc::new((vec![], record.field_specifiers().clone())),
+                );
+            }
+        }
+    }
+    let mut wire = vec![];
+    for vector in &vectors[..2] {
+        vector
+            .expected()
+            .write(&mut wire, Some(exporter_templates.clone()))?;
+    }
+    assert_eq!(
+        wire,
+        [vectors[0].wire().clone(), vectors[1].wire().clone()].concat()
+    );
+
+    // Collector side: templates are learned while decoding
+    let collector_templates = Rc::new(RefCell::new(HashMap::new()));
+    let (rest, template) =
+        IpfixPacket::from_wire(Span::new(&wire), collector_templates.clone()).unwrap();
+    let (rest, data) = IpfixPacket::from_wire(rest, collector_templates).unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(&template, vectors[0].expected());
+    assert_eq!(&data, vectors[1].expected());
+    Ok(())
+}
+
+#[test]
+fn test_variable_length_fields() -> Result<(), IpfixPacketWritingError> {
+    let good_template_wire = [
+        0x00, 0x0a, 0x00, 0x20, 0x65, 0x85, 0xa8, 0xdd, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x01, 0x00, 0x02, 0x00, 0x10, 0x01, 0x2c, 0x00, 0x02, 0x01, 0x39, 0xff, 0xff, 0x01, 0x4f,
+        0xff, 0xff,
+    ];
+    // 301 octets long selectorName, its length is encoded in three octets
+    let selector_name = "a".repeat(301);
+    let mut good_data_wire = vec![
+        0x00, 0x0a, 0x01, 0x48, 0x65, 0x85, 0xa8, 0xdd, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x01, 0x01, 0x2c, 0x01, 0x38, 0x03, 0x01, 0x02, 0x03, 0xff, 0x01, 0x2d,
+    ];
+    good_data_wire.extend_from_slice(selector_name.as_bytes());
+
+    let good_template = IpfixPacket::new(
+        Utc.with_ymd_and_hms(2023, 12, 22, 15, 18, 53).unwrap(),
+        0,
+        1,
+        vec![Set::Template(vec![TemplateRecord::new(
+            300,
+            vec![
+                FieldSpecifier::new(ie::IE::ipHeaderPacketSection, u16::MAX).unwrap(),
+                FieldSpecifier::new(ie::IE::selectorName, u16::MAX).unwrap(),
+            ],
+        )])],
+    );
+    let good_data = IpfixPacket::new(
+        Utc.with_ymd_and_hms(2023, 12, 22, 15, 18, 53).unwrap(),
+        0,
+        1,
+        vec![Set::Data {
+            id: DataSetId::new(300).unwrap(),
+            records: vec![DataRecord::new(
+                vec![],
+                vec![
+                    ie::Field::ipHeaderPacketSection(ie::ipHeaderPacketSection(vec![1, 2, 3])),
+                    ie::Field::selectorName(ie::selectorName(selector_name)),
+                ],
+            )],
+        }],
+    );
+
+    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    test_parsed_completely_with_one_input(
+        &good_template_wire,
+        templates_map.clone(),
+        &good_template,
+    );
+    test_parsed_completely_with_one_input(&good_data_wire, templates_map.clone(), &good_data);
+    test_write_with_one_input(
+        &good_template,
+        Some(templates_map.clone()),
+        &good_template_wire,
+    )?;
+    test_write_with_one_input(&good_data, Some(templates_map.clone()), &good_data_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_borrowed_data_packet() {
+    use crate::wire::deserializer::borrowed::*;
+    use netgauze_parse_utils::ReadablePdu;
+
+    let template_wire = [
+        0x00, 0x0a, 0x00, 0x38, 0x65, 0x85, 0xa8, 0xdd, 0x00, 0x01, 0xce, 0x06, 0x00, 0x00, 0x82,
+        0x20, 0x00, 0x03, 0x00, 0x28, 0x01, 0x01, 0x00, 0x07, 0x00, 0x01, 0x01, 0x2e, 0x00, 0x04,
+        0x01, 0x31, 0x00, 0x04, 0x01, 0x30, 0x00, 0x02, 0x01, 0x35, 0x00, 0x04, 0x01, 0x36, 0x00,
+        0x04, 0x00, 0x54, 0x00, 0x5a, 0x01, 0x4f, 0xff, 0xff, 0x00, 0x00,
+    ];
+    let data_wire = [
+        0x00, 0x0a, 0x00, 0x94, 0x65, 0x85, 0xa8, 0xdd, 0x00, 0x01, 0xce, 0x06, 0x00, 0x00, 0x82,
+        0x20, 0x01, 0x01, 0x00, 0x84, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x03,
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x4e, 0x45, 0x54, 0x46, 0x4c, 0x4f, 0x57,
+        0x2d, 0x53, 0x41, 0x4d, 0x50, 0x4c, 0x45, 0x52, 0x2d, 0x4d, 0x41, 0x50, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x13, 0x4e, 0x45, 0x54, 0x46, 0x4c, 0x4f,
+        0x57, 0x2d, 0x53, 0x41, 0x4d, 0x50, 0x4c, 0x45, 0x52, 0x2d, 0x4d, 0x41, 0x50,
+    ];
+    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    let owned_templates_map = Rc::new(RefCell::new(HashMap::new()));
+
+    let (_, template) = IpfixPacketRef::from_wire(Span::new(&template_wire)).unwrap();
+    let template_sets = template
+        .sets(templates_map.clone())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert!(matches!(
+        template_sets.as_slice(),
+        [SetRef::OptionsTemplate(_)]
+    ));
+
+    let (_, data) = IpfixPacketRef::from_wire(Span::new(&data_wire)).unwrap();
+    assert_eq!(data.sequence_number(), 118278);
+    assert_eq!(data.observation_domain_id(), 33312);
+    let data_sets = data
+        .sets(templates_map.clone())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let data_set = match data_sets.as_slice() {
+        [SetRef::Data(data_set)] => data_set,
+        sets => panic!("expected one data set, got {sets:?}"),
+    };
+    assert_eq!(data_set.id(), DataSetId::new(257).unwrap());
+    let records = data_set.records().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(records.len(), 1);
+
+    let record = &records[0];
+    assert_eq!(
+        record
+            .scope_fields()
+            .map(|x| x.as_unsigned())
+            .collect::<Vec<_>>(),
+        vec![Some(1)]
+    );
+    let fields = record.fields().collect::<Vec<_>>();
+    assert_eq!(fields[1].ie(), ie::IE::selectorAlgorithm);
+    assert_eq!(fields[1].as_unsigned(), Some(3));
+    // Fixed length strings are cut at the first NUL, like the owned fields
+    assert_eq!(fields[4].value().len(), 90);
+    assert_eq!(fields[4].as_str(), Ok("NETFLOW-SAMPLER-MAP"));
+    assert!(fields[5].is_variable_length());
+    assert_eq!(fields[5].as_str(), Ok("NETFLOW-SAMPLER-MAP"));
+
+    // The borrowed records decode to the same records as the owned decoder
+    IpfixPacket::from_wire(Span::new(&template_wire), owned_templates_map.clone()).unwrap();
+    let (_, owned) =
+        IpfixPacket::from_wire(Span::new(&data_wire), owned_templates_map.clone()).unwrap();
+    let owned_records = match owned.sets().as_slice() {
+        [Set::Data { records, .. }] => records.clone(),
+        sets => panic!("expected one data set, got {sets:?}"),
+    };
+    assert_eq!(
+        records
+            .iter()
+            .map(|record| record.to_data_record().unwrap())
+            .collect::<Vec<_>>(),
+        owned_records
+    );
+}
+
+#[test]
+fn test_template_withdrawal() {
+    use netgauze_parse_utils::LocatedParsingError;
+
+    let templates_map = Rc::new(RefCell::new(HashMap::from([(
+        307,
+        Rc::new((
+            vec![],
+            vec![FieldSpecifier::new(ie::IE::octetDeltaCount, 4).unwrap()],
+        )),
+    )])));
+    let data_wire = [
+        0x01, 0x33, // Set ID
+        0x00, 0x0c, // Set Length
+        0x00, 0x00, 0x00, 0x01, // octetDeltaCount
+        0x00, 0x00, 0x00, 0x02, // octetDeltaCount
+    ];
+    let (_, set) = Set::from_wire(Span::new(&data_wire), templates_map.clone()).unwrap();
+    assert!(matches!(set, Set::Data { records, .. } if records.len() == 2));
+
+    // Trailing octets shorter than a record must be zero padding
+    let bad_padding_wire = [
+        0x01, 0x33, // Set ID
+        0x00, 0x0b, // Set Length
+        0x00, 0x00, 0x00, 0x01, // octetDeltaCount
+        0x00, 0x00, 0x07, // Padding
+    ];
+    match Set::from_wire(Span::new(&bad_padding_wire), templates_map.clone()) {
+        Err(nom::Err::Error(err)) => {
+            assert_eq!(err.error(), &SetParsingError::InvalidPaddingValue(7))
+        }
+        other => panic!("expected invalid padding, got {other:?}"),
+    }
+
+    let withdrawal_wire = [
+        0x00, 0x02, // Set ID
+        0x00, 0x08, // Set Length
+        0x01, 0x33, // Template ID
+        0x00, 0x00, // Field Count
+    ];
+    let (_, set) = Set::from_wire(Span::new(&withdrawal_wire), templates_map.clone()).unwrap();
+    assert_eq!(set, Set::Template(vec![TemplateRecord::new(307, vec![])]));
+    assert!(!templates_map.borrow().contains_key(&307));
+    match Set::from_wire(Span::new(&data_wire), templates_map) {
+        Err(nom::Err::Error(err)) => {
+            assert_eq!(err.error(), &SetParsingError::NoTemplateDefinedFor(307))
+        }
+        other => panic!("expected a missing template, got {other:?}"),
+    }
+}
@@ -18,6 +18,8 @@ mod netflow;
 
 #[cfg(feature = "codec")]
 pub mod pcap_tests;
+#[cfg(feature = "serde")]
+mod yaml_tests;
 
 use chrono::{TimeZone, Timelike, Utc};
 use netgauze_parse_utils::{test_helpers::*, Span};
@@ -65,6 +67,53 @@ fn test_template_record() -> Result<(), TemplateRecordWritingError> {
     Ok(())
 }
 
+#[test]
+fn test_options_template_record() -> Result<(), OptionsTemplateRecordWritingError> {
+    let good_wire = [
+        0x01, 0x01, 0x00, 0x02, 0x00, 0x01, 0x01, 0x2e, 0x00, 0x04, 0x01, 0x31, 0x00, 0x04,
+    ];
+    let bad_template_id_wire = [
+        0x00, 0x03, 0x00, 0x02, 0x00, 0x01, 0x01, 0x2e, 0x00, 0x04, 0x01, 0x31, 0x00, 0x04,
+    ];
+    let bad_scope_count_wire = [
+        0x01, 0x01, 0x00, 0x02, 0x00, 0x03, 0x01, 0x2e, 0x00, 0x04, 0x01, 0x31, 0x00, 0x04,
+    ];
+
+    let good = OptionsTemplateRecord::new(
+        257,
+        vec![FieldSpecifier::new(ie::IE::selectorId, 4).unwrap()],
+        vec![FieldSpecifier::new(ie::IE::samplingPacketInterval, 4).unwrap()],
+    );
+
+    let bad_template_id = LocatedOptionsTemplateRecordParsingError::new(
+        Span::new(&bad_template_id_wire),
+        OptionsTemplateRecordParsingError::InvalidTemplateId(3),
+    );
+    let bad_scope_count = LocatedOptionsTemplateRecordParsingError::new(
+        unsafe { Span::new_from_raw_offset(4, &bad_scope_count_wire[4..]) },
+        OptionsTemplateRecordParsingError::InvalidScopeFieldsCount(3),
+    );
+    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    test_parsed_completely_with_one_input(&good_wire, templates_map.clone(), &good);
+    assert_eq!(templates_map.borrow().get(&257).map(|x| x.0.len()), Some(1));
+    test_parse_error_with_one_input::<
+        OptionsTemplateRecord,
+        Rc<RefCell<HashMap<u16, Rc<(Vec<FieldSpecifier>, Vec<FieldSpecifier>)>>>>,
+        LocatedOptionsTemplateRecordParsingError<'_>,
+    >(
+        &bad_template_id_wire,
+        templates_map.clone(),
+        &bad_template_id,
+    );
+    test_parse_error_with_one_input::<
+        OptionsTemplateRecord,
+        Rc<RefCell<HashMap<u16, Rc<(Vec<FieldSpecifier>, Vec<FieldSpecifier>)>>>>,
+        LocatedOptionsTemplateRecordParsingError<'_>,
+    >(&bad_scope_count_wire, templates_map, &bad_scope_count);
+    test_write(&good, &good_wire)?;
+    Ok(())
+}
+
 #[test]
 fn test_field() -> Result<(), FieldSpecifierWritingError> {
     let good_ipv4_src_wire = [0x00, 0x08, 0x00, 0x04];
@@ -78,7 +78,8 @@ fn test_flow_pcap(overwrite: bool, pcap_path: PathBuf) {
     let pcap_reader = Box::new(LegacyPcapReader::new(165536, pcap_file).unwrap());
     let iter = PcapIter::new(pcap_reader);
     let mut peers = HashMap::new();
-    for (src_ip, src_port, dst_ip, dst_port, protocol, value) in iter {
+    for packet in iter {
+        let (src_ip, src_port, dst_ip, dst_port, protocol, value) = packet.unwrap();
         if protocol != TransportProtocol::UDP || dst_port != 9991 {
             continue;
         }
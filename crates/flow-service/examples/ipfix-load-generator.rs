@@ -0,0 +1,33 @@
+use std::{net::SocketAddr, time::Duration};
+
+use tokio::net::UdpSocket;
+
+use netgauze_flow_service::loadgen::IpfixLoadGenerator;
+
+/// Usage: ipfix-load-generator <collector> [records/sec] [seconds] [flow keys]
+/// [template churn]
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let args: Vec<String> = std::env::args().collect();
+    let collector: SocketAddr = args
+        .get(1)
+        .map(|x| x.as_str())
+        .unwrap_or("127.0.0.1:9991")
+        .parse()?;
+    let records_per_second = args.get(2).map(|x| x.parse()).transpose()?.unwrap_or(1000);
+    let seconds = args.get(3).map(|x| x.parse()).transpose()?.unwrap_or(10);
+    let flow_keys = args.get(4).map(|x| x.parse()).transpose()?.unwrap_or(1000);
+    let template_churn = args.get(5).map(|x| x.parse()).transpose()?.unwrap_or(0);
+
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    let mut generator = IpfixLoadGenerator::new(1)
+        .with_records_per_second(records_per_second)
+        .with_flow_keys(flow_keys)
+        .with_template_churn(template_churn);
+    println!("Sending {records_per_second} records/sec to {collector} for {seconds} seconds");
+    let stats = generator
+        .run(&socket, collector, Duration::from_secs(seconds))
+        .await?;
+    println!("{}", serde_json::to_string(&stats)?);
+    Ok(())
+}
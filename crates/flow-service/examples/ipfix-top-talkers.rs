@@ -0,0 +1,179 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! IPFIX/Netflow V9 collector printing the source addresses that sent the
+//! most octets every ten seconds, while archiving the records as JSON Lines
+//! files.
+//!
+//! ```text
+//! cargo run --example ipfix-top-talkers --features file -- [listen address] [output directory] [exporter IPs...]
+//! ```
+//!
+//! When exporter IPs are given, the records of the other exporters are
+//! dropped.
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use bytes::BytesMut;
+use tokio::net::UdpSocket;
+use tokio_util::codec::Decoder;
+
+use netgauze_flow_pkt::codec::FlowInfoCodec;
+use netgauze_flow_service::{
+    pipeline::{ExporterFilter, FilterStage, Pipeline, Stage},
+    sink::{file::FileSink, BatchingSink, FlowRecord, DEFAULT_BATCH_SIZE},
+};
+
+const REPORT_INTERVAL: Duration = Duration::from_secs(10);
+const TOP_COUNT: usize = 10;
+
+fn init_tracing() {
+    let subscriber = tracing_subscriber::FmtSubscriber::builder()
+        .with_max_level(tracing::Level::INFO)
+        .finish();
+    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
+}
+
+/// Value of the first field named `name` in the record
+fn field<'a>(record: &'a FlowRecord, name: &str) -> Option<&'a serde_json::Value> {
+    record
+        .fields()
+        .as_array()?
+        .iter()
+        .find_map(|field| field.get(name))
+}
+
+fn source_address(record: &FlowRecord) -> Option<IpAddr> {
+    field(record, "sourceIPv4Address")
+        .or_else(|| field(record, "sourceIPv6Address"))?
+        .as_str()?
+        .parse()
+        .ok()
+}
+
+/// Counts the octets sent by each source address, the records are passed
+/// through unchanged
+#[derive(Debug, Clone, Default)]
+struct TopTalkers {
+    octets: Arc<Mutex<HashMap<IpAddr, u64>>>,
+}
+
+impl TopTalkers {
+    /// The addresses that sent the most octets since the last report, in
+    /// descending order
+    fn report(&self) -> Vec<(IpAddr, u64)> {
+        let mut talkers: Vec<_> = self.octets.lock().unwrap().drain().collect();
+        talkers.sort_by(|a, b| b.1.cmp(&a.1));
+        talkers.truncate(TOP_COUNT);
+        talkers
+    }
+}
+
+#[async_trait]
+impl Stage for TopTalkers {
+    fn name(&self) -> &str {
+        "top-talkers"
+    }
+
+    async fn process(&mut self, batch: Vec<FlowRecord>) -> Vec<FlowRecord> {
+        let mut octets = self.octets.lock().unwrap();
+        for record in &batch {
+            let count = field(record, "octetDeltaCount").and_then(|value| value.as_u64());
+            if let (Some(address), Some(count)) = (source_address(record), count) {
+                *octets.entry(address).or_default() += count;
+            }
+        }
+        batch
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    init_tracing();
+    let mut args = std::env::args().skip(1);
+    let listen_addr: SocketAddr = args
+        .next()
+        .unwrap_or_else(|| "0.0.0.0:9995".to_string())
+        .parse()?;
+    let root = PathBuf::from(args.next().unwrap_or_else(|| "flows".to_string()));
+    let exporters = args
+        .map(|arg| arg.parse::<IpAddr>())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut file_sink = FileSink::new(root, "flows".to_string());
+    file_sink.bootstrap().await?;
+    let top_talkers = TopTalkers::default();
+    let mut pipeline = Pipeline::new(BatchingSink::new(file_sink, DEFAULT_BATCH_SIZE));
+    if !exporters.is_empty() {
+        pipeline = pipeline.with_stage(ExporterFilter::new(exporters));
+    }
+    // Drop the records without traffic, e.g., the options data records
+    let mut pipeline = pipeline
+        .with_stage(FilterStage::new("with-octets", |record: &FlowRecord| {
+            field(record, "octetDeltaCount").is_some()
+        }))
+        .with_stage(top_talkers.clone());
+
+    let socket = UdpSocket::bind(listen_addr).await?;
+    tracing::info!("collecting IPFIX/Netflow V9 on {listen_addr}");
+    // Templates are learned per exporter
+    let mut codecs: HashMap<SocketAddr, FlowInfoCodec> = HashMap::new();
+    let mut report = tokio::time::interval(REPORT_INTERVAL);
+    let mut recv_buf = vec![0; u16::MAX as usize];
+    loop {
+        tokio::select! {
+            received = socket.recv_from(&mut recv_buf) => {
+                let (len, exporter) = received?;
+                let mut buf = BytesMut::from(&recv_buf[..len]);
+                let codec = codecs.entry(exporter).or_default();
+                while !buf.is_empty() {
+                    match codec.decode(&mut buf) {
+                        Ok(Some(info)) => {
+                            // Failed writes are retried by the periodic flushes
+                            if let Err(err) = pipeline.process_flow_info(exporter, &info).await {
+                                tracing::warn!("failed writing flow records: {err}");
+                            }
+                        }
+                        Ok(None) => break,
+                        Err(err) => {
+                            tracing::warn!("error decoding packet from {exporter}: {err:?}");
+                            break;
+                        }
+                    }
+                }
+            }
+            _ = report.tick() => {
+                for (rank, (address, octets)) in top_talkers.report().into_iter().enumerate() {
+                    println!("{:>2}. {address:<40} {octets:>16} octets", rank + 1);
+                }
+                if let Err(err) = pipeline.flush().await {
+                    tracing::warn!("failed flushing flow records: {err}");
+                }
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+    tracing::info!("shutting down");
+    let mut file_sink = pipeline.into_inner().await?;
+    file_sink.close().await?;
+    Ok(())
+}
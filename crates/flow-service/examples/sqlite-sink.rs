@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+use futures_util::{stream::SplitSink, StreamExt};
+use tokio::net::UdpSocket;
+use tokio_util::{
+    codec::{BytesCodec, Decoder},
+    udp::UdpFramed,
+};
+
+use netgauze_flow_pkt::codec::FlowInfoCodec;
+use netgauze_flow_service::sink::{
+    sqlite::SqliteSink, BatchingSink, FlowRecord, DEFAULT_BATCH_SIZE,
+};
+use netgauze_sink::RecordSink;
+
+/// Store the data records received on UDP port 8080 in `flows.db`
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let mut sqlite = SqliteSink::open("flows.db", "flows".to_string())?;
+    sqlite.bootstrap().await?;
+    let mut sink = BatchingSink::new(sqlite, DEFAULT_BATCH_SIZE);
+
+    let listen_addr = "0.0.0.0:8080";
+    let socket = UdpSocket::bind(&listen_addr).await?;
+    println!("Listening on addr: {listen_addr}");
+
+    let framed = UdpFramed::new(socket, BytesCodec::default());
+    let (_tx, mut stream): (SplitSink<_, (Bytes, _)>, _) = framed.split();
+    let mut clients = HashMap::new();
+    while let Some(next) = stream.next().await {
+        let (mut buf, addr) = next?;
+        let result = clients
+            .entry(addr)
+            .or_insert(FlowInfoCodec::default())
+            .decode(&mut buf);
+        match result {
+            Ok(Some(pkt)) => {
+                sink.extend(FlowRecord::from_flow_info(addr, &pkt)?).await?;
+            }
+            Ok(None) => break,
+            Err(err) => eprintln!("Error decoding packet: {err:?}"),
+        }
+    }
+    sink.flush().await?;
+    Ok(())
+}
@@ -0,0 +1,205 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conformance analyzer of the packets sent by a flow exporter.
+//!
+//! Besides the malformed packets rejected by the parser, the analyzer checks
+//! the sequence numbers of each IPFIX observation domain, the data sets
+//! referring to templates not yet received, and reports the unknown
+//! Information Elements. A captured stream of packets is analyzed at once with
+//! [`analyze`].
+
+use std::collections::HashMap;
+
+use bytes::BytesMut;
+use tokio_util::codec::Decoder;
+
+use netgauze_flow_pkt::{
+    codec::{FlowInfoCodec, FlowInfoCodecDecoderError, TemplatesState},
+    ipfix,
+    wire::deserializer::ipfix::{IpfixPacketParsingError, SetParsingError},
+    FlowInfo,
+};
+use netgauze_parse_utils::conformance::{ConformanceReport, FindingKind};
+
+use crate::source::unknown_ies;
+
+/// Checks the packets of a single exporter, in the order they're received
+#[derive(Debug)]
+pub struct IpfixConformanceAnalyzer {
+    report: ConformanceReport,
+    /// Expected sequence number of the next packet of each observation domain
+    next_sequence_numbers: HashMap<u32, u32>,
+}
+
+impl IpfixConformanceAnalyzer {
+    /// `source` identifies the exporter in the report
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            report: ConformanceReport::new(source),
+            next_sequence_numbers: HashMap::new(),
+        }
+    }
+
+    /// Check a decoded packet
+    pub fn observe(&mut self, info: &FlowInfo) {
+        self.report.next_message();
+        if let FlowInfo::IPFIX(pkt) = info {
+            self.check_sequence_number(pkt);
+        }
+        for ie in unknown_ies(info) {
+            self.report.record(
+                FindingKind::UnknownCode,
+                format!("ipfix.ie.{}.{}", ie.pen, ie.id),
+                None,
+                format!(
+                    "template uses unknown information element {} of enterprise {}",
+                    ie.id, ie.pen
+                ),
+            );
+        }
+    }
+
+    /// Check a packet rejected by the codec
+    pub fn observe_error(&mut self, error: &FlowInfoCodecDecoderError) {
+        self.report.next_message();
+        match error {
+            FlowInfoCodecDecoderError::IpfixParsingError {
+                error:
+                    IpfixPacketParsingError::SetParsingError(SetParsingError::NoTemplateDefinedFor(id)),
+                ..
+            } => self.report.record(
+                FindingKind::Violation,
+                "ipfix.missing-template",
+                Some("RFC 7011 section 8"),
+                format!("data set refers to template {id} not received before"),
+            ),
+            FlowInfoCodecDecoderError::Incomplete(_) => self.report.record(
+                FindingKind::Violation,
+                "ipfix.truncated",
+                Some("RFC 7011 section 3"),
+                format!("packet is truncated: {error:?}"),
+            ),
+            _ => self.report.record(
+                FindingKind::Violation,
+                "ipfix.malformed",
+                Some("RFC 7011 section 3"),
+                format!("packet is malformed: {error:?}"),
+            ),
+        }
+    }
+
+    /// The sequence number is the count of data records sent by the
+    /// observation domain before the packet
+    fn check_sequence_number(&mut self, pkt: &ipfix::IpfixPacket) {
+        let domain = pkt.observation_domain_id();
+        let sequence_number = pkt.sequence_number();
+        if let Some(expected) = self.next_sequence_numbers.get(&domain) {
+            if *expected != sequence_number {
+                self.report.record(
+                    FindingKind::Violation,
+                    "ipfix.sequence-number",
+                    Some("RFC 7011 section 3.1"),
+                    format!(
+                        "observation domain {domain} sent sequence number {sequence_number}, \
+                         expected {expected}"
+                    ),
+                );
+            }
+        }
+        let data_records = pkt
+            .sets()
+            .iter()
+            .map(|set| match set {
+                ipfix::Set::Data { records, .. } => records.len() as u32,
+                _ => 0,
+            })
+            .fold(0u32, u32::wrapping_add);
+        self.next_sequence_numbers
+            .insert(domain, sequence_number.wrapping_add(data_records));
+    }
+
+    /// Findings of the packets checked so far
+    pub fn report(&self) -> ConformanceReport {
+        self.report.clone()
+    }
+}
+
+/// Analyze the packets of an exporter in `data`, decoded with the already
+/// known `templates`
+pub fn analyze(
+    source: impl Into<String>,
+    templates: TemplatesState,
+    data: &[u8],
+) -> ConformanceReport {
+    let mut analyzer = IpfixConformanceAnalyzer::new(source);
+    let mut codec = FlowInfoCodec::default();
+    codec.set_templates_state(templates);
+    let mut buf = BytesMut::from(data);
+    loop {
+        let remaining = buf.len();
+        match codec.decode(&mut buf) {
+            Ok(Some(info)) => analyzer.observe(&info),
+            Ok(None) => break,
+            Err(error) => {
+                analyzer.observe_error(&error);
+                // The codec skips malformed packets, make sure it's not stuck
+                if buf.len() == remaining {
+                    break;
+                }
+            }
+        }
+    }
+    if !buf.is_empty() {
+        analyzer.observe_error(&FlowInfoCodecDecoderError::Incomplete(Some(buf.len())));
+    }
+    analyzer.report()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netgauze_flow_pkt::test_vectors::ipfix_packets;
+
+    #[test]
+    fn test_ipfix_conformance() {
+        let vectors = ipfix_packets();
+        let (template, data) = (&vectors[0], &vectors[1]);
+
+        let mut wire = template.wire().clone();
+        wire.extend_from_slice(data.wire());
+        let report = analyze("192.0.2.1", TemplatesState::default(), &wire);
+        assert_eq!(report.messages(), 2);
+        assert!(report.is_conformant());
+
+        // The data set is received before its template
+        let report = analyze("192.0.2.1", TemplatesState::default(), data.wire());
+        assert_eq!(report.messages(), 1);
+        let finding = report.finding("ipfix.missing-template").unwrap();
+        assert_eq!(finding.kind(), FindingKind::Violation);
+
+        // The data records of the first packet are not accounted for
+        wire.extend_from_slice(data.wire());
+        let report = analyze("192.0.2.1", TemplatesState::default(), &wire);
+        assert_eq!(report.messages(), 3);
+        let finding = report.finding("ipfix.sequence-number").unwrap();
+        assert_eq!(finding.count(), 1);
+        assert_eq!(finding.first_seen(), 2);
+
+        // The trailing bytes are not a complete packet
+        let report = analyze("192.0.2.1", TemplatesState::default(), &wire[..20]);
+        assert!(report.finding("ipfix.truncated").is_some());
+    }
+}
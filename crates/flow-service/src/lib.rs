@@ -13,4 +13,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-// TODO
+pub mod conformance;
+pub mod loadgen;
+pub mod mediator;
+pub mod pipeline;
+pub mod sink;
+pub mod source;
+pub mod template_store;
@@ -0,0 +1,420 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Synthetic IPFIX streams to stress-test collectors.
+//!
+//! [`IpfixLoadGenerator`] produces IPFIX messages carrying IPv4 5-tuple flow
+//! records, cycling through a fixed number of flow keys, and optionally
+//! replacing its template every few messages to exercise the template
+//! handling of collectors. [`IpfixLoadGenerator::run`] sends the messages
+//! over UDP at a given rate of records per second.
+
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use bytes::{BufMut, BytesMut};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::{net::UdpSocket, time::MissedTickBehavior};
+
+use netgauze_flow_pkt::{
+    ie::{self, Field, IE},
+    ipfix::{DataRecord, IpfixPacket, Set, TemplateRecord},
+    wire::serializer::ipfix::IpfixPacketWritingError,
+    DataSetId, FieldSpecifier,
+};
+use netgauze_parse_utils::WritablePduWithOneInput;
+
+/// Template ID of the first template, following ones are allocated
+/// sequentially as the template churns
+pub const LOAD_GENERATOR_FIRST_TEMPLATE_ID: u16 = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadGeneratorError {
+    IoError(String),
+    WritingError(IpfixPacketWritingError),
+}
+
+impl std::fmt::Display for LoadGeneratorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::error::Error for LoadGeneratorError {}
+
+impl From<std::io::Error> for LoadGeneratorError {
+    fn from(value: std::io::Error) -> Self {
+        Self::IoError(value.to_string())
+    }
+}
+
+impl From<IpfixPacketWritingError> for LoadGeneratorError {
+    fn from(value: IpfixPacketWritingError) -> Self {
+        Self::WritingError(value)
+    }
+}
+
+/// Totals of what was generated by an [`IpfixLoadGenerator`]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadGeneratorStats {
+    pub packets: u64,
+    pub records: u64,
+    pub templates: u64,
+    pub bytes: u64,
+}
+
+/// Generates IPFIX messages with synthetic flow records, see the
+/// [module documentation](self).
+///
+/// Flow keys are derived from a counter, hence the number of distinct keys is
+/// exactly [`IpfixLoadGenerator::flow_keys`] once enough records are
+/// generated and a given configuration always generates the same records.
+#[derive(Debug, Clone)]
+pub struct IpfixLoadGenerator {
+    observation_domain_id: u32,
+    records_per_second: u32,
+    records_per_packet: u16,
+    flow_keys: u32,
+    template_churn: u32,
+    template_refresh: u32,
+    template_id: u16,
+    template_sent: bool,
+    sequence_number: u32,
+    flow: u32,
+    stats: LoadGeneratorStats,
+}
+
+impl IpfixLoadGenerator {
+    pub const fn new(observation_domain_id: u32) -> Self {
+        Self {
+            observation_domain_id,
+            records_per_second: 1000,
+            records_per_packet: 30,
+            flow_keys: 1000,
+            template_churn: 0,
+            template_refresh: 0,
+            template_id: LOAD_GENERATOR_FIRST_TEMPLATE_ID,
+            template_sent: false,
+            sequence_number: 0,
+            flow: 0,
+            stats: LoadGeneratorStats {
+                packets: 0,
+                records: 0,
+                templates: 0,
+                bytes: 0,
+            },
+        }
+    }
+
+    /// Rate at which [`IpfixLoadGenerator::run`] sends records, defaults to
+    /// 1,000
+    pub fn with_records_per_second(mut self, records_per_second: u32) -> Self {
+        self.records_per_second = records_per_second.max(1);
+        self
+    }
+
+    /// Data records in each message, defaults to 30 to keep messages within
+    /// a 1500 bytes MTU
+    pub fn with_records_per_packet(mut self, records_per_packet: u16) -> Self {
+        self.records_per_packet = records_per_packet.max(1);
+        self
+    }
+
+    /// Number of distinct flow keys records cycle through, defaults to 1,000
+    pub fn with_flow_keys(mut self, flow_keys: u32) -> Self {
+        self.flow_keys = flow_keys.max(1);
+        self
+    }
+
+    /// Replace the template with a new template ID every `template_churn`
+    /// messages, `0` (the default) keeps the same template
+    pub fn with_template_churn(mut self, template_churn: u32) -> Self {
+        self.template_churn = template_churn;
+        self
+    }
+
+    /// Resend the current template every `template_refresh` messages as
+    /// exporters do over UDP, `0` (the default) sends it only when the
+    /// template changes
+    pub fn with_template_refresh(mut self, template_refresh: u32) -> Self {
+        self.template_refresh = template_refresh;
+        self
+    }
+
+    pub const fn observation_domain_id(&self) -> u32 {
+        self.observation_domain_id
+    }
+
+    pub const fn records_per_second(&self) -> u32 {
+        self.records_per_second
+    }
+
+    pub const fn records_per_packet(&self) -> u16 {
+        self.records_per_packet
+    }
+
+    pub const fn flow_keys(&self) -> u32 {
+        self.flow_keys
+    }
+
+    pub const fn template_churn(&self) -> u32 {
+        self.template_churn
+    }
+
+    pub const fn template_refresh(&self) -> u32 {
+        self.template_refresh
+    }
+
+    /// ID of the template used by the next data set
+    pub const fn template_id(&self) -> u16 {
+        self.template_id
+    }
+
+    pub const fn stats(&self) -> LoadGeneratorStats {
+        self.stats
+    }
+
+    /// Template describing the generated records
+    pub fn template(&self) -> TemplateRecord {
+        let field_specifiers = [
+            (IE::sourceIPv4Address, 4),
+            (IE::destinationIPv4Address, 4),
+            (IE::sourceTransportPort, 2),
+            (IE::destinationTransportPort, 2),
+            (IE::protocolIdentifier, 1),
+            (IE::octetDeltaCount, 8),
+            (IE::packetDeltaCount, 8),
+            (IE::flowStartMilliseconds, 8),
+            (IE::flowEndMilliseconds, 8),
+        ]
+        .into_iter()
+        .map(|(ie, length)| FieldSpecifier::new(ie, length).unwrap())
+        .collect();
+        TemplateRecord::new(self.template_id, field_specifiers)
+    }
+
+    /// Record of the next flow key
+    fn next_record(&mut self, export_time: DateTime<Utc>) -> DataRecord {
+        let key = self.flow % self.flow_keys;
+        self.flow = self.flow.wrapping_add(1);
+        let protocol = if key.is_multiple_of(2) { 6 } else { 17 };
+        let packets = 1 + u64::from(key % 100);
+        DataRecord::new(
+            vec![],
+            vec![
+                Field::sourceIPv4Address(ie::sourceIPv4Address(Ipv4Addr::from(
+                    0x0a00_0000 | (key >> 8),
+                ))),
+                Field::destinationIPv4Address(ie::destinationIPv4Address(Ipv4Addr::from(
+                    0xc612_0000 | (key & 0xffff),
+                ))),
+                Field::sourceTransportPort(ie::sourceTransportPort(1024 + (key % 60000) as u16)),
+                Field::destinationTransportPort(ie::destinationTransportPort(
+                    [53, 80, 443, 8080][(key % 4) as usize],
+                )),
+                Field::protocolIdentifier(ie::protocolIdentifier(protocol)),
+                Field::octetDeltaCount(ie::octetDeltaCount(packets * 512)),
+                Field::packetDeltaCount(ie::packetDeltaCount(packets)),
+                Field::flowStartMilliseconds(ie::flowStartMilliseconds(
+                    export_time - chrono::Duration::seconds(1),
+                )),
+                Field::flowEndMilliseconds(ie::flowEndMilliseconds(export_time)),
+            ],
+        )
+    }
+
+    /// Generate the next message. The template is included in the first
+    /// message and whenever it churns or is refreshed.
+    pub fn next_packet(&mut self, export_time: DateTime<Utc>) -> IpfixPacket {
+        let packet_index = self.stats.packets;
+        if self.template_churn > 0
+            && packet_index > 0
+            && packet_index.is_multiple_of(u64::from(self.template_churn))
+        {
+            self.template_id = self
+                .template_id
+                .checked_add(1)
+                .unwrap_or(LOAD_GENERATOR_FIRST_TEMPLATE_ID);
+            self.template_sent = false;
+        }
+        let refresh = self.template_refresh > 0
+            && packet_index > 0
+            && packet_index.is_multiple_of(u64::from(self.template_refresh));
+
+        let mut sets = Vec::with_capacity(2);
+        if !self.template_sent || refresh {
+            sets.push(Set::Template(vec![self.template()]));
+            self.template_sent = true;
+            self.stats.templates += 1;
+        }
+        let records = (0..self.records_per_packet)
+            .map(|_| self.next_record(export_time))
+            .collect();
+        sets.push(Set::Data {
+            id: DataSetId::new(self.template_id).unwrap(),
+            records,
+        });
+
+        let packet = IpfixPacket::new(
+            export_time,
+            self.sequence_number,
+            self.observation_domain_id,
+            sets,
+        );
+        self.sequence_number = self
+            .sequence_number
+            .wrapping_add(u32::from(self.records_per_packet));
+        self.stats.packets += 1;
+        self.stats.records += u64::from(self.records_per_packet);
+        packet
+    }
+
+    /// Generate the next message in its wire format
+    pub fn next_packet_bytes(
+        &mut self,
+        export_time: DateTime<Utc>,
+    ) -> Result<BytesMut, LoadGeneratorError> {
+        let packet = self.next_packet(export_time);
+        let mut buf = BytesMut::with_capacity(packet.len(None));
+        packet.write(&mut (&mut buf).writer(), None)?;
+        self.stats.bytes += buf.len() as u64;
+        Ok(buf)
+    }
+
+    /// Send messages to `collector` for `duration` at the configured rate of
+    /// records per second, and return the totals of this run. When sending
+    /// falls behind, the missed messages are sent in a burst to catch up
+    /// with the rate.
+    pub async fn run(
+        &mut self,
+        socket: &UdpSocket,
+        collector: SocketAddr,
+        duration: Duration,
+    ) -> Result<LoadGeneratorStats, LoadGeneratorError> {
+        let before = self.stats;
+        let period = Duration::from_secs_f64(
+            f64::from(self.records_per_packet) / f64::from(self.records_per_second),
+        );
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Burst);
+        let mut tick = interval.tick().await;
+        let deadline = tick + duration;
+        while tick < deadline {
+            let buf = self.next_packet_bytes(Utc::now())?;
+            socket.send_to(&buf, collector).await?;
+            tick = interval.tick().await;
+        }
+        Ok(LoadGeneratorStats {
+            packets: self.stats.packets - before.packets,
+            records: self.stats.records - before.records,
+            templates: self.stats.templates - before.templates,
+            bytes: self.stats.bytes - before.bytes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::FlowDecoder;
+    use chrono::TimeZone;
+    use netgauze_flow_pkt::FlowInfo;
+    use std::collections::HashSet;
+
+    fn data_records(info: &FlowInfo) -> (u16, Vec<DataRecord>) {
+        let FlowInfo::IPFIX(packet) = info else {
+            panic!("expected IPFIX packet");
+        };
+        packet
+            .sets()
+            .iter()
+            .find_map(|set| match set {
+                Set::Data { id, records } => Some((id.id(), records.clone())),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_load_generator() {
+        let export_time = Utc.timestamp_opt(1700000000, 0).unwrap();
+        let exporter: SocketAddr = "192.0.2.1:4739".parse().unwrap();
+        let mut generator = IpfixLoadGenerator::new(7)
+            .with_records_per_packet(10)
+            .with_flow_keys(25)
+            .with_template_churn(4)
+            .with_template_refresh(3);
+        let mut decoder = FlowDecoder::new();
+
+        let mut keys = HashSet::new();
+        let mut template_ids = vec![];
+        for _ in 0..8 {
+            let buf = generator.next_packet_bytes(export_time).unwrap();
+            let decoded = decoder.decode(exporter, buf).unwrap();
+            assert_eq!(decoded.len(), 1);
+            let (template_id, records) = data_records(&decoded[0]);
+            assert_eq!(records.len(), 10);
+            template_ids.push(template_id);
+            keys.extend(records.into_iter().map(|record| {
+                let fields = record.fields();
+                format!("{:?}", &fields[..3])
+            }));
+        }
+
+        assert_eq!(keys.len(), 25);
+        assert_eq!(template_ids, vec![256, 256, 256, 256, 257, 257, 257, 257]);
+        assert_eq!(
+            generator.stats(),
+            LoadGeneratorStats {
+                packets: 8,
+                records: 80,
+                // Sent at packets 0, 3, 4 (churn), and 6
+                templates: 4,
+                bytes: generator.stats().bytes,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_generator_run() {
+        let collector = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut generator = IpfixLoadGenerator::new(1)
+            .with_records_per_second(300)
+            .with_records_per_packet(30);
+
+        // Messages are sent every 100ms, the count is deterministic since the
+        // interval ticks are scheduled regardless of delays
+
+        let stats = generator
+            .run(
+                &socket,
+                collector.local_addr().unwrap(),
+                Duration::from_millis(300),
+            )
+            .await
+            .unwrap();
+        assert_eq!(stats.packets, 3);
+        assert_eq!(stats.records, 90);
+        assert_eq!(stats.templates, 1);
+
+        let mut buf = vec![0; 65535];
+        let (len, _) = collector.recv_from(&mut buf).await.unwrap();
+        assert!(len > 0);
+    }
+}
@@ -0,0 +1,534 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Re-export of the received IPFIX messages to downstream collectors, as an
+//! IPFIX Mediator ([RFC6183](https://datatracker.ietf.org/doc/html/rfc6183)).
+//!
+//! [`IpfixMediator`] forwards every decoded IPFIX message to a list of
+//! [`MediatorDestination`]s. Each destination has an [`IeProjection`]
+//! selecting the Information Elements it receives: the templates are rebuilt
+//! with the selected fields only, and the data records are trimmed to match,
+//! so collectors that only need, e.g., the 5-tuple and the counters don't
+//! receive the rest of the records. Templates left without any field are not
+//! sent, along with their data records.
+//!
+//! Scope fields of the options templates are always kept, since they identify
+//! what the options data records apply to.
+//!
+//! The templates and sequence numbers are kept per exporter and observation
+//! domain. Messages are sent from a single socket, hence exporters forwarded
+//! to the same destination should use distinct observation domain IDs.
+
+use std::{cell::RefCell, collections::HashMap, net::SocketAddr, rc::Rc};
+
+use bytes::{BufMut, BytesMut};
+use serde::{Deserialize, Serialize};
+use tokio::net::UdpSocket;
+
+use netgauze_flow_pkt::{
+    ie::IE,
+    ipfix::{
+        DataRecord, DecodingTemplate, IpfixPacket, OptionsTemplateRecord, Set, TemplateRecord,
+    },
+    wire::serializer::ipfix::IpfixPacketWritingError,
+    FieldSpecifier,
+};
+use netgauze_parse_utils::WritablePduWithOneInput;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MediatorError {
+    IoError(String),
+    WritingError(IpfixPacketWritingError),
+}
+
+impl std::fmt::Display for MediatorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::error::Error for MediatorError {}
+
+impl From<std::io::Error> for MediatorError {
+    fn from(value: std::io::Error) -> Self {
+        Self::IoError(value.to_string())
+    }
+}
+
+impl From<IpfixPacketWritingError> for MediatorError {
+    fn from(value: IpfixPacketWritingError) -> Self {
+        Self::WritingError(value)
+    }
+}
+
+/// Information Elements forwarded to a destination
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum IeProjection {
+    /// Forward the records as received
+    #[default]
+    All,
+
+    /// Forward only the fields of these Information Elements
+    Only(Vec<IE>),
+}
+
+impl IeProjection {
+    pub fn only(ies: impl IntoIterator<Item = IE>) -> Self {
+        Self::Only(ies.into_iter().collect())
+    }
+
+    pub fn contains(&self, ie: &IE) -> bool {
+        match self {
+            Self::All => true,
+            Self::Only(ies) => ies.contains(ie),
+        }
+    }
+}
+
+/// Totals of what was forwarded to a [`MediatorDestination`]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MediatorStats {
+    pub packets: u64,
+    pub records: u64,
+    pub bytes: u64,
+}
+
+/// Template as received, along with which of its fields are forwarded
+#[derive(Debug, Clone)]
+struct ProjectedTemplate {
+    keep: Vec<bool>,
+    template: DecodingTemplate,
+}
+
+/// Templates and sequence numbers are scoped by exporter and observation
+/// domain
+type DomainKey = (SocketAddr, u32);
+
+/// A downstream collector and the [`IeProjection`] of what it receives
+#[derive(Debug, Clone)]
+pub struct MediatorDestination {
+    collector: SocketAddr,
+    projection: IeProjection,
+    templates: HashMap<(DomainKey, u16), ProjectedTemplate>,
+    sequence_numbers: HashMap<DomainKey, u32>,
+    stats: MediatorStats,
+}
+
+impl MediatorDestination {
+    pub fn new(collector: SocketAddr, projection: IeProjection) -> Self {
+        Self {
+            collector,
+            projection,
+            templates: HashMap::new(),
+            sequence_numbers: HashMap::new(),
+            stats: MediatorStats::default(),
+        }
+    }
+
+    pub const fn collector(&self) -> SocketAddr {
+        self.collector
+    }
+
+    pub const fn projection(&self) -> &IeProjection {
+        &self.projection
+    }
+
+    pub const fn stats(&self) -> MediatorStats {
+        self.stats
+    }
+
+    fn project_specifiers(
+        &self,
+        specifiers: &[FieldSpecifier],
+    ) -> (Vec<bool>, Vec<FieldSpecifier>) {
+        let keep: Vec<bool> = specifiers
+            .iter()
+            .map(|spec| self.projection.contains(&spec.element_id()))
+            .collect();
+        let projected = specifiers
+            .iter()
+            .zip(&keep)
+            .filter(|(_, keep)| **keep)
+            .map(|(spec, _)| spec.clone())
+            .collect();
+        (keep, projected)
+    }
+
+    /// Rebuild `packet` received from `exporter` with the projected templates
+    /// and data records. Returns `None` when nothing is left to forward.
+    ///
+    /// Data sets of templates this destination didn't see are dropped, since
+    /// their fields can't be matched to Information Elements.
+    pub fn project(&mut self, exporter: SocketAddr, packet: &IpfixPacket) -> Option<IpfixPacket> {
+        let domain = (exporter, packet.observation_domain_id());
+        let mut sets = vec![];
+        let mut records_count: u32 = 0;
+        for set in packet.sets() {
+            match set {
+                Set::Template(records) => {
+                    let mut projected_records = vec![];
+                    for record in records {
+                        let (keep, projected) = self.project_specifiers(record.field_specifiers());
+                        if projected.is_empty() {
+                            self.templates.remove(&(domain, record.id()));
+                            continue;
+                        }
+                        projected_records.push(TemplateRecord::new(record.id(), projected.clone()));
+                        self.templates.insert(
+                            (domain, record.id()),
+                            ProjectedTemplate {
+                                keep,
+                                template: (vec![], projected),
+                            },
+                        );
+                    }
+                    if !projected_records.is_empty() {
+                        sets.push(Set::Template(projected_records));
+                    }
+                }
+                Set::OptionsTemplate(records) => {
+                    let mut projected_records = vec![];
+                    for record in records {
+                        let (keep, projected) = self.project_specifiers(record.field_specifiers());
+                        let scope = record.scope_field_specifiers().clone();
+                        projected_records.push(OptionsTemplateRecord::new(
+                            record.id(),
+                            scope.clone(),
+                            projected.clone(),
+                        ));
+                        self.templates.insert(
+                            (domain, record.id()),
+                            ProjectedTemplate {
+                                keep,
+                                template: (scope, projected),
+                            },
+                        );
+                    }
+                    if !projected_records.is_empty() {
+                        sets.push(Set::OptionsTemplate(projected_records));
+                    }
+                }
+                Set::Data { id, records } => {
+                    let Some(template) = self.templates.get(&(domain, id.id())) else {
+                        continue;
+                    };
+                    let projected_records: Vec<_> = records
+                        .iter()
+                        .map(|record| {
+                            let fields = record
+                                .fields()
+                                .iter()
+                                .zip(&template.keep)
+                                .filter(|(_, keep)| **keep)
+                                .map(|(field, _)| field.clone())
+                                .collect();
+                            DataRecord::new(record.scope_fields().clone(), fields)
+                        })
+                        .collect();
+                    if projected_records.is_empty() {
+                        continue;
+                    }
+                    records_count = records_count.wrapping_add(projected_records.len() as u32);
+                    sets.push(Set::Data {
+                        id: *id,
+                        records: projected_records,
+                    });
+                }
+            }
+        }
+        if sets.is_empty() {
+            return None;
+        }
+        let sequence_number = self.sequence_numbers.entry(domain).or_insert(0);
+        let projected = IpfixPacket::new(
+            packet.export_time(),
+            *sequence_number,
+            packet.observation_domain_id(),
+            sets,
+        );
+        *sequence_number = sequence_number.wrapping_add(records_count);
+        self.stats.packets += 1;
+        self.stats.records += u64::from(records_count);
+        Some(projected)
+    }
+
+    /// [`Self::project`] `packet` and serialize the result in its wire format
+    pub fn project_bytes(
+        &mut self,
+        exporter: SocketAddr,
+        packet: &IpfixPacket,
+    ) -> Result<Option<BytesMut>, MediatorError> {
+        let Some(projected) = self.project(exporter, packet) else {
+            return Ok(None);
+        };
+        let domain = (exporter, packet.observation_domain_id());
+        let templates_map = Rc::new(RefCell::new(
+            self.templates
+                .iter()
+                .filter(|((template_domain, _), _)| *template_domain == domain)
+                .map(|((_, id), template)| (*id, Rc::new(template.template.clone())))
+                .collect::<HashMap<_, _>>(),
+        ));
+        let mut buf = BytesMut::with_capacity(projected.len(Some(templates_map.clone())));
+        projected.write(&mut (&mut buf).writer(), Some(templates_map))?;
+        self.stats.bytes += buf.len() as u64;
+        Ok(Some(buf))
+    }
+}
+
+/// Forwards IPFIX messages to [`MediatorDestination`]s, see the
+/// [module documentation](self)
+#[derive(Debug, Clone, Default)]
+pub struct IpfixMediator {
+    destinations: Vec<MediatorDestination>,
+}
+
+impl IpfixMediator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_destination(mut self, collector: SocketAddr, projection: IeProjection) -> Self {
+        self.destinations
+            .push(MediatorDestination::new(collector, projection));
+        self
+    }
+
+    pub fn destinations(&self) -> &[MediatorDestination] {
+        &self.destinations
+    }
+
+    /// Send the projection of `packet`, received from `exporter`, to every
+    /// destination from `socket`
+    pub async fn forward(
+        &mut self,
+        socket: &UdpSocket,
+        exporter: SocketAddr,
+        packet: &IpfixPacket,
+    ) -> Result<(), MediatorError> {
+        for destination in &mut self.destinations {
+            let Some(buf) = destination.project_bytes(exporter, packet)? else {
+                continue;
+            };
+            socket.send_to(&buf, destination.collector).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{loadgen::IpfixLoadGenerator, source::FlowDecoder};
+    use chrono::{TimeZone, Utc};
+    use netgauze_flow_pkt::{
+        ie::{self, Field},
+        DataSetId, FlowInfo,
+    };
+
+    fn exporter() -> SocketAddr {
+        "192.0.2.1:4739".parse().unwrap()
+    }
+
+    fn five_tuple() -> IeProjection {
+        IeProjection::only([
+            IE::sourceIPv4Address,
+            IE::destinationIPv4Address,
+            IE::sourceTransportPort,
+            IE::destinationTransportPort,
+            IE::protocolIdentifier,
+            IE::octetDeltaCount,
+        ])
+    }
+
+    fn decode(decoder: &mut FlowDecoder, buf: BytesMut) -> IpfixPacket {
+        let mut decoded = decoder.decode(exporter(), buf).unwrap();
+        assert_eq!(decoded.len(), 1);
+        match decoded.remove(0) {
+            FlowInfo::IPFIX(packet) => packet,
+            other => panic!("expected IPFIX packet, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_projection() {
+        let export_time = Utc.timestamp_opt(1700000000, 0).unwrap();
+        let mut generator = IpfixLoadGenerator::new(7).with_records_per_packet(3);
+        let packet = generator.next_packet(export_time);
+        let template = generator.template();
+
+        let mut destination = MediatorDestination::new(exporter(), five_tuple());
+        let projected = destination.project(exporter(), &packet).unwrap();
+        assert_eq!(projected.sequence_number(), 0);
+        let Set::Template(templates) = &projected.sets()[0] else {
+            panic!("expected template set");
+        };
+        let kept: Vec<_> = template
+            .field_specifiers()
+            .iter()
+            .filter(|spec| five_tuple().contains(&spec.element_id()))
+            .cloned()
+            .collect();
+        assert!(kept.len() < template.field_specifiers().len());
+        assert_eq!(templates[0].field_specifiers(), &kept);
+        let Set::Data { records, .. } = &projected.sets()[1] else {
+            panic!("expected data set");
+        };
+        assert_eq!(records.len(), 3);
+        assert!(records
+            .iter()
+            .all(|record| record.fields().len() == kept.len()));
+
+        let next = destination
+            .project(exporter(), &generator.next_packet(export_time))
+            .unwrap();
+        assert_eq!(next.sequence_number(), 3);
+        assert_eq!(
+            destination.stats(),
+            MediatorStats {
+                packets: 2,
+                records: 6,
+                bytes: 0,
+            }
+        );
+
+        // Round trip through the wire format
+        let mut source_decoder = FlowDecoder::new();
+        let mut destination_decoder = FlowDecoder::new();
+        let mut destination = MediatorDestination::new(exporter(), five_tuple());
+        let mut generator = IpfixLoadGenerator::new(7).with_records_per_packet(3);
+        let received = decode(
+            &mut source_decoder,
+            generator.next_packet_bytes(export_time).unwrap(),
+        );
+        let expected = destination.clone().project(exporter(), &received);
+        let buf = destination
+            .project_bytes(exporter(), &received)
+            .unwrap()
+            .unwrap();
+        let forwarded = decode(&mut destination_decoder, buf);
+        assert_eq!(Some(forwarded), expected);
+    }
+
+    #[test]
+    fn test_projection_drops_empty_templates() {
+        let export_time = Utc.timestamp_opt(1700000000, 0).unwrap();
+        let mut generator = IpfixLoadGenerator::new(7).with_records_per_packet(3);
+        let packet = generator.next_packet(export_time);
+
+        let mut destination =
+            MediatorDestination::new(exporter(), IeProjection::only([IE::bgpNextHopIPv4Address]));
+        assert_eq!(destination.project(exporter(), &packet), None);
+        assert_eq!(destination.stats(), MediatorStats::default());
+
+        // Data sets of unknown templates are dropped
+        let mut destination = MediatorDestination::new(exporter(), IeProjection::All);
+        let data_only = IpfixPacket::new(
+            export_time,
+            0,
+            7,
+            vec![Set::Data {
+                id: DataSetId::new(400).unwrap(),
+                records: vec![DataRecord::new(
+                    vec![],
+                    vec![Field::octetDeltaCount(ie::octetDeltaCount(100))],
+                )],
+            }],
+        );
+        assert_eq!(destination.project(exporter(), &data_only), None);
+        let projected = destination.project(exporter(), &packet).unwrap();
+        assert_eq!(projected, packet);
+    }
+
+    #[test]
+    fn test_options_template_scope_kept() {
+        let export_time = Utc.timestamp_opt(1700000000, 0).unwrap();
+        let packet = IpfixPacket::new(
+            export_time,
+            0,
+            7,
+            vec![
+                Set::OptionsTemplate(vec![OptionsTemplateRecord::new(
+                    300,
+                    vec![FieldSpecifier::new(IE::exporterIPv4Address, 4).unwrap()],
+                    vec![
+                        FieldSpecifier::new(IE::samplingInterval, 4).unwrap(),
+                        FieldSpecifier::new(IE::octetDeltaCount, 8).unwrap(),
+                    ],
+                )]),
+                Set::Data {
+                    id: DataSetId::new(300).unwrap(),
+                    records: vec![DataRecord::new(
+                        vec![Field::exporterIPv4Address(ie::exporterIPv4Address(
+                            std::net::Ipv4Addr::new(192, 0, 2, 1),
+                        ))],
+                        vec![
+                            Field::samplingInterval(ie::samplingInterval(100)),
+                            Field::octetDeltaCount(ie::octetDeltaCount(1000)),
+                        ],
+                    )],
+                },
+            ],
+        );
+        let mut destination =
+            MediatorDestination::new(exporter(), IeProjection::only([IE::octetDeltaCount]));
+        let projected = destination.project(exporter(), &packet).unwrap();
+        assert_eq!(
+            projected.sets(),
+            &vec![
+                Set::OptionsTemplate(vec![OptionsTemplateRecord::new(
+                    300,
+                    vec![FieldSpecifier::new(IE::exporterIPv4Address, 4).unwrap()],
+                    vec![FieldSpecifier::new(IE::octetDeltaCount, 8).unwrap()],
+                )]),
+                Set::Data {
+                    id: DataSetId::new(300).unwrap(),
+                    records: vec![DataRecord::new(
+                        vec![Field::exporterIPv4Address(ie::exporterIPv4Address(
+                            std::net::Ipv4Addr::new(192, 0, 2, 1),
+                        ))],
+                        vec![Field::octetDeltaCount(ie::octetDeltaCount(1000))],
+                    )],
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forward() {
+        let full = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let projected = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut mediator = IpfixMediator::new()
+            .with_destination(full.local_addr().unwrap(), IeProjection::All)
+            .with_destination(projected.local_addr().unwrap(), five_tuple());
+        let mut generator = IpfixLoadGenerator::new(7).with_records_per_packet(3);
+        let packet = generator.next_packet(Utc.timestamp_opt(1700000000, 0).unwrap());
+        mediator
+            .forward(&socket, exporter(), &packet)
+            .await
+            .unwrap();
+
+        let mut buf = vec![0; 65535];
+        let (full_len, _) = full.recv_from(&mut buf).await.unwrap();
+        let (projected_len, _) = projected.recv_from(&mut buf).await.unwrap();
+        assert!(projected_len < full_len);
+        assert_eq!(mediator.destinations()[0].stats().bytes, full_len as u64);
+        assert_eq!(
+            mediator.destinations()[1].stats().bytes,
+            projected_len as u64
+        );
+    }
+}
@@ -0,0 +1,695 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transformation stages between the decoded flows and the sink.
+//!
+//! A [`Pipeline`] flattens decoded [`FlowInfo`] packets into [`FlowRecord`]s,
+//! passes them through a chain of [`Stage`]s in the order they were added,
+//! and writes what's left to a [`BatchingSink`]. Stages filter, enrich, or
+//! rewrite the records, so custom processing is plugged in by implementing
+//! [`Stage`] instead of changing the service code.
+//!
+//! Built-in stages:
+//! - [`FilterStage`] keeps the records matching a predicate.
+//! - [`MapStage`] rewrites every record, e.g., to add or rename fields.
+//! - [`ExporterFilter`] keeps the records of a set of exporters.
+//! - [`CommunityLabels`] labels the records with the traffic classes mapped
+//!   to their BGP communities.
+//!
+//! The stages and the sink of a running pipeline are replaced at runtime with
+//! a [`PipelineHandle`], e.g., when reloading the configuration. Reloads are
+//! applied between batches, so each batch is processed entirely with either
+//! the previous or the new configuration. The decoder state, such as the
+//! templates, lives outside the pipeline and is kept.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Debug, Formatter},
+    net::{IpAddr, SocketAddr},
+};
+
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+use netgauze_flow_pkt::FlowInfo;
+
+use crate::sink::{BatchingSink, FlowRecord, FlowSink, FlowSinkError};
+
+/// A transformation applied to batches of [`FlowRecord`]s
+#[async_trait]
+pub trait Stage: Send {
+    /// Name used to identify the stage in logs
+    fn name(&self) -> &str;
+
+    /// Transform a batch of records, the returned batch is passed to the next
+    /// stage. Records can be dropped, modified, or added.
+    async fn process(&mut self, batch: Vec<FlowRecord>) -> Vec<FlowRecord>;
+}
+
+/// Keeps the records for which the predicate returns `true`
+pub struct FilterStage<F> {
+    name: String,
+    predicate: F,
+}
+
+impl<F: Fn(&FlowRecord) -> bool + Send> FilterStage<F> {
+    pub fn new(name: impl Into<String>, predicate: F) -> Self {
+        Self {
+            name: name.into(),
+            predicate,
+        }
+    }
+}
+
+impl<F> Debug for FilterStage<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FilterStage")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<F: Fn(&FlowRecord) -> bool + Send> Stage for FilterStage<F> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn process(&mut self, mut batch: Vec<FlowRecord>) -> Vec<FlowRecord> {
+        batch.retain(|record| (self.predicate)(record));
+        batch
+    }
+}
+
+/// Replaces every record with the result of the function
+pub struct MapStage<F> {
+    name: String,
+    function: F,
+}
+
+impl<F: Fn(FlowRecord) -> FlowRecord + Send> MapStage<F> {
+    pub fn new(name: impl Into<String>, function: F) -> Self {
+        Self {
+            name: name.into(),
+            function,
+        }
+    }
+}
+
+impl<F> Debug for MapStage<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MapStage")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<F: Fn(FlowRecord) -> FlowRecord + Send> Stage for MapStage<F> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn process(&mut self, batch: Vec<FlowRecord>) -> Vec<FlowRecord> {
+        batch.into_iter().map(&self.function).collect()
+    }
+}
+
+/// Keeps only the records exported from the given IP addresses, regardless
+/// of the source port
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExporterFilter {
+    exporters: HashSet<IpAddr>,
+}
+
+impl ExporterFilter {
+    pub fn new(exporters: impl IntoIterator<Item = IpAddr>) -> Self {
+        Self {
+            exporters: exporters.into_iter().collect(),
+        }
+    }
+
+    pub const fn exporters(&self) -> &HashSet<IpAddr> {
+        &self.exporters
+    }
+}
+
+#[async_trait]
+impl Stage for ExporterFilter {
+    fn name(&self) -> &str {
+        "exporter-filter"
+    }
+
+    async fn process(&mut self, mut batch: Vec<FlowRecord>) -> Vec<FlowRecord> {
+        batch.retain(|record| self.exporters.contains(&record.exporter().ip()));
+        batch
+    }
+}
+
+/// Labels the records with business-level traffic classes, e.g.,
+/// `65000:100` for "peering" and `65000:200` for "transit", from the BGP
+/// communities carried in the record. The communities are read from the
+/// community fields, as exported by the router or added by a route-flow
+/// correlation stage, either as numbers or as `asn:value` strings, alone or
+/// in arrays.
+///
+/// The labels of all the mapped communities are added, without duplicates
+/// and in the order of the communities, as an array under the label field.
+/// Records without any mapped community are left unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommunityLabels {
+    labels: HashMap<u32, String>,
+    community_fields: Vec<String>,
+    label_field: String,
+}
+
+impl Default for CommunityLabels {
+    fn default() -> Self {
+        Self {
+            labels: HashMap::new(),
+            community_fields: vec![
+                "bgpSourceCommunityList".to_string(),
+                "bgpDestinationCommunityList".to_string(),
+            ],
+            label_field: "trafficClasses".to_string(),
+        }
+    }
+}
+
+impl CommunityLabels {
+    /// Read the communities from the `bgpSourceCommunityList` and
+    /// `bgpDestinationCommunityList` fields and add the `trafficClasses` field
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Label of the records carrying `community`
+    pub fn with_label(mut self, community: u32, label: impl Into<String>) -> Self {
+        self.labels.insert(community, label.into());
+        self
+    }
+
+    /// Replace the fields the communities are read from
+    pub fn with_community_fields(mut self, fields: Vec<String>) -> Self {
+        self.community_fields = fields;
+        self
+    }
+
+    pub fn with_label_field(mut self, field: impl Into<String>) -> Self {
+        self.label_field = field.into();
+        self
+    }
+
+    pub const fn labels(&self) -> &HashMap<u32, String> {
+        &self.labels
+    }
+
+    /// Parse a community written as `asn:value` or as a single number
+    pub fn parse_community(value: &str) -> Option<u32> {
+        match value.split_once(':') {
+            Some((asn, value)) => {
+                let asn = asn.trim().parse::<u16>().ok()?;
+                let value = value.trim().parse::<u16>().ok()?;
+                Some((u32::from(asn) << 16) | u32::from(value))
+            }
+            None => value.trim().parse().ok(),
+        }
+    }
+
+    fn community_of(value: &serde_json::Value) -> Option<u32> {
+        match value {
+            serde_json::Value::Number(number) => {
+                number.as_u64().and_then(|x| u32::try_from(x).ok())
+            }
+            serde_json::Value::String(value) => Self::parse_community(value),
+            _ => None,
+        }
+    }
+
+    /// Labels of the communities carried in the record, each label is returned
+    /// once
+    pub fn labels_of<'a, 'r>(&'a self, record: &'r FlowRecord) -> impl Iterator<Item = &'a str> + 'r
+    where
+        'a: 'r,
+    {
+        let mut seen = HashSet::new();
+        record
+            .fields()
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|field| field.as_object())
+            .flat_map(move |field| {
+                self.community_fields
+                    .iter()
+                    .filter_map(move |name| field.get(name))
+            })
+            .flat_map(|value| match value {
+                serde_json::Value::Array(values) => values.as_slice(),
+                value => std::slice::from_ref(value),
+            })
+            .filter_map(Self::community_of)
+            .filter_map(move |community| self.labels.get(&community))
+            .map(String::as_str)
+            .filter(move |label| seen.insert(*label))
+    }
+}
+
+#[async_trait]
+impl Stage for CommunityLabels {
+    fn name(&self) -> &str {
+        "community-labels"
+    }
+
+    async fn process(&mut self, mut batch: Vec<FlowRecord>) -> Vec<FlowRecord> {
+        for record in &mut batch {
+            let labels = self.labels_of(record).collect::<Vec<_>>();
+            if labels.is_empty() {
+                continue;
+            }
+            let mut field = serde_json::Map::new();
+            field.insert(self.label_field.clone(), labels.into());
+            if let Some(fields) = record.fields_mut().as_array_mut() {
+                fields.push(field.into());
+            }
+        }
+        batch
+    }
+}
+
+/// New stages and/or sink for a running [`Pipeline`], the parts that are not
+/// set are kept
+pub struct PipelineReload<S> {
+    stages: Option<Vec<Box<dyn Stage>>>,
+    sink: Option<S>,
+}
+
+impl<S> Default for PipelineReload<S> {
+    fn default() -> Self {
+        Self {
+            stages: None,
+            sink: None,
+        }
+    }
+}
+
+impl<S> Debug for PipelineReload<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PipelineReload")
+            .field(
+                "stages",
+                &self
+                    .stages
+                    .as_ref()
+                    .map(|stages| stages.iter().map(|stage| stage.name()).collect::<Vec<_>>()),
+            )
+            .field("sink", &self.sink.is_some())
+            .finish()
+    }
+}
+
+impl<S> PipelineReload<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace all the stages of the pipeline with `stages`, an empty list
+    /// removes all of them
+    pub fn with_stages(mut self, stages: Vec<Box<dyn Stage>>) -> Self {
+        self.stages = Some(stages);
+        self
+    }
+
+    /// Append a stage to the new stages
+    pub fn with_stage(mut self, stage: impl Stage + 'static) -> Self {
+        self.stages
+            .get_or_insert_with(Vec::new)
+            .push(Box::new(stage));
+        self
+    }
+
+    /// Write the next records to `sink`, see [`BatchingSink::replace_sink`]
+    pub fn with_sink(mut self, sink: S) -> Self {
+        self.sink = Some(sink);
+        self
+    }
+}
+
+/// Sends [`PipelineReload`]s to a [`Pipeline`], can be cloned and moved to
+/// another task, e.g., one watching the configuration file
+#[derive(Debug)]
+pub struct PipelineHandle<S> {
+    tx: mpsc::UnboundedSender<PipelineReload<S>>,
+}
+
+impl<S> Clone for PipelineHandle<S> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+        }
+    }
+}
+
+impl<S> PipelineHandle<S> {
+    /// Queue `reload` to be applied before the next batch. Returns the reload
+    /// back when the pipeline was dropped.
+    pub fn reload(&self, reload: PipelineReload<S>) -> Result<(), PipelineReload<S>> {
+        self.tx.send(reload).map_err(|err| err.0)
+    }
+}
+
+/// Runs the records through the stages, then writes them to the sink
+pub struct Pipeline<S> {
+    stages: Vec<Box<dyn Stage>>,
+    sink: BatchingSink<S>,
+    reloads: Option<(
+        mpsc::UnboundedSender<PipelineReload<S>>,
+        mpsc::UnboundedReceiver<PipelineReload<S>>,
+    )>,
+}
+
+impl<S> Debug for Pipeline<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pipeline")
+            .field(
+                "stages",
+                &self
+                    .stages
+                    .iter()
+                    .map(|stage| stage.name())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<S: FlowSink + Send> Pipeline<S> {
+    pub fn new(sink: BatchingSink<S>) -> Self {
+        Self {
+            stages: vec![],
+            sink,
+            reloads: None,
+        }
+    }
+
+    /// Handle to replace the stages and the sink while the pipeline runs
+    pub fn handle(&mut self) -> PipelineHandle<S> {
+        let (tx, _) = self.reloads.get_or_insert_with(mpsc::unbounded_channel);
+        PipelineHandle { tx: tx.clone() }
+    }
+
+    /// Apply the reloads queued by the [`PipelineHandle`]s. A sink that fails
+    /// to bootstrap, or when the buffered records can't be flushed to the
+    /// current sink, is discarded and the current sink is kept, while the new
+    /// stages are applied regardless.
+    pub async fn apply_reloads(&mut self) {
+        let Some((_, rx)) = &mut self.reloads else {
+            return;
+        };
+        while let Ok(reload) = rx.try_recv() {
+            if let Some(stages) = reload.stages {
+                tracing::info!(
+                    stages = ?stages.iter().map(|stage| stage.name()).collect::<Vec<_>>(),
+                    "reloading pipeline stages"
+                );
+                self.stages = stages;
+            }
+            if let Some(sink) = reload.sink {
+                match self.sink.replace_sink(sink).await {
+                    Ok(_) => tracing::info!("reloaded pipeline sink"),
+                    Err(err) => tracing::warn!("failed reloading pipeline sink: {err}"),
+                }
+            }
+        }
+    }
+
+    /// Append a stage, stages are run in the order they are added
+    pub fn with_stage(mut self, stage: impl Stage + 'static) -> Self {
+        self.stages.push(Box::new(stage));
+        self
+    }
+
+    /// Names of the stages in the order they are run
+    pub fn stages(&self) -> impl Iterator<Item = &str> {
+        self.stages.iter().map(|stage| stage.name())
+    }
+
+    pub const fn sink(&self) -> &BatchingSink<S> {
+        &self.sink
+    }
+
+    /// Run a batch of records through the stages and push the result to the
+    /// sink
+    pub async fn process(&mut self, mut batch: Vec<FlowRecord>) -> Result<(), FlowSinkError> {
+        self.apply_reloads().await;
+        for stage in &mut self.stages {
+            if batch.is_empty() {
+                break;
+            }
+            batch = stage.process(batch).await;
+        }
+        self.sink.extend(batch).await
+    }
+
+    /// Flatten the data records of a packet received from `exporter` and
+    /// process them as one batch
+    pub async fn process_flow_info(
+        &mut self,
+        exporter: SocketAddr,
+        info: &FlowInfo,
+    ) -> Result<(), FlowSinkError> {
+        let batch = FlowRecord::from_flow_info(exporter, info)?;
+        self.process(batch).await
+    }
+
+    pub async fn flush(&mut self) -> Result<(), FlowSinkError> {
+        self.sink.flush().await
+    }
+
+    /// Flush the buffered records and return the inner sink
+    pub async fn into_inner(self) -> Result<S, FlowSinkError> {
+        self.sink.into_inner().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use netgauze_sink::RecordSink;
+
+    #[derive(Debug, Default)]
+    struct VecSink {
+        records: Vec<FlowRecord>,
+    }
+
+    #[async_trait]
+    impl RecordSink<FlowRecord> for VecSink {
+        async fn bootstrap(&mut self) -> Result<(), FlowSinkError> {
+            Ok(())
+        }
+
+        async fn write(&mut self, records: &[FlowRecord]) -> Result<(), FlowSinkError> {
+            self.records.extend_from_slice(records);
+            Ok(())
+        }
+    }
+
+    fn record(exporter: &str, octets: u64) -> FlowRecord {
+        FlowRecord::new(
+            exporter.parse().unwrap(),
+            Utc.timestamp_opt(1680000000, 0).unwrap(),
+            0,
+            400,
+            serde_json::json!([]),
+            serde_json::json!([{ "octetDeltaCount": octets }]),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_pipeline() {
+        let exporter: IpAddr = "192.0.2.1".parse().unwrap();
+        let mut pipeline = Pipeline::new(BatchingSink::new(VecSink::default(), 10))
+            .with_stage(ExporterFilter::new([exporter]))
+            .with_stage(FilterStage::new("drop-small", |record: &FlowRecord| {
+                record.fields()[0]["octetDeltaCount"].as_u64() >= Some(100)
+            }))
+            .with_stage(MapStage::new("tag-site", |mut record: FlowRecord| {
+                if let Some(fields) = record.fields_mut().as_array_mut() {
+                    fields.push(serde_json::json!({ "site": "lab" }));
+                }
+                record
+            }));
+        assert_eq!(
+            pipeline.stages().collect::<Vec<_>>(),
+            vec!["exporter-filter", "drop-small", "tag-site"]
+        );
+
+        pipeline
+            .process(vec![
+                record("192.0.2.1:4739", 100),
+                record("192.0.2.1:4740", 10),
+                record("192.0.2.2:4739", 100),
+                record("192.0.2.1:4741", 200),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(pipeline.sink().pending(), 2);
+
+        let sink = pipeline.into_inner().await.unwrap();
+        assert_eq!(
+            sink.records
+                .iter()
+                .map(|record| record.fields().clone())
+                .collect::<Vec<_>>(),
+            vec![
+                serde_json::json!([{ "octetDeltaCount": 100 }, { "site": "lab" }]),
+                serde_json::json!([{ "octetDeltaCount": 200 }, { "site": "lab" }]),
+            ]
+        );
+    }
+
+    /// Sink sharing its records with the test, to check them after the sink
+    /// was replaced
+    #[derive(Debug, Default, Clone)]
+    struct SharedSink {
+        records: std::sync::Arc<std::sync::Mutex<Vec<FlowRecord>>>,
+    }
+
+    impl SharedSink {
+        fn octets(&self) -> Vec<Option<u64>> {
+            self.records
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|record| record.fields()[0]["octetDeltaCount"].as_u64())
+                .collect()
+        }
+    }
+
+    #[async_trait]
+    impl RecordSink<FlowRecord> for SharedSink {
+        async fn bootstrap(&mut self) -> Result<(), FlowSinkError> {
+            Ok(())
+        }
+
+        async fn write(&mut self, records: &[FlowRecord]) -> Result<(), FlowSinkError> {
+            self.records.lock().unwrap().extend_from_slice(records);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_reload() {
+        let first = SharedSink::default();
+        let second = SharedSink::default();
+        let mut pipeline = Pipeline::new(BatchingSink::new(first.clone(), 10))
+            .with_stage(ExporterFilter::new(["192.0.2.1".parse().unwrap()]));
+        let handle = pipeline.handle();
+
+        pipeline
+            .process(vec![
+                record("192.0.2.1:4739", 100),
+                record("192.0.2.2:4739", 200),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(pipeline.sink().pending(), 1);
+
+        handle
+            .reload(
+                PipelineReload::new()
+                    .with_stage(ExporterFilter::new(["192.0.2.2".parse().unwrap()]))
+                    .with_sink(second.clone()),
+            )
+            .unwrap();
+        pipeline
+            .process(vec![
+                record("192.0.2.1:4739", 300),
+                record("192.0.2.2:4739", 400),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(
+            pipeline.stages().collect::<Vec<_>>(),
+            vec!["exporter-filter"]
+        );
+        // The records buffered before the reload are written to the previous
+        // sink
+        assert_eq!(first.octets(), vec![Some(100)]);
+
+        // Stages are removed, while the sink is kept
+        handle
+            .reload(PipelineReload::new().with_stages(vec![]))
+            .unwrap();
+        pipeline
+            .process(vec![record("192.0.2.3:4739", 500)])
+            .await
+            .unwrap();
+        assert_eq!(pipeline.stages().count(), 0);
+        pipeline.flush().await.unwrap();
+        assert_eq!(second.octets(), vec![Some(400), Some(500)]);
+
+        drop(pipeline);
+        assert!(handle.reload(PipelineReload::new()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_community_labels() {
+        assert_eq!(
+            CommunityLabels::parse_community("65000:100"),
+            Some(0xfde8_0064)
+        );
+        assert_eq!(
+            CommunityLabels::parse_community("4259840100"),
+            Some(4259840100)
+        );
+        assert_eq!(CommunityLabels::parse_community("65536:100"), None);
+
+        let mut stage = CommunityLabels::new()
+            .with_label(
+                CommunityLabels::parse_community("65000:100").unwrap(),
+                "peering",
+            )
+            .with_label(
+                CommunityLabels::parse_community("65000:200").unwrap(),
+                "transit",
+            )
+            .with_label(
+                CommunityLabels::parse_community("65000:201").unwrap(),
+                "transit",
+            );
+        let labeled = FlowRecord::new(
+            "192.0.2.1:4739".parse().unwrap(),
+            Utc.timestamp_opt(1680000000, 0).unwrap(),
+            0,
+            400,
+            serde_json::json!([]),
+            serde_json::json!([
+                { "octetDeltaCount": 100 },
+                { "bgpSourceCommunityList": ["65000:200", "65000:999", "65000:201"] },
+                { "bgpDestinationCommunityList": [0xfde8_0064u32] },
+            ]),
+        );
+        let unlabeled = record("192.0.2.1:4739", 100);
+        let batch = stage.process(vec![labeled, unlabeled.clone()]).await;
+
+        assert_eq!(
+            batch[0].fields()[3],
+            serde_json::json!({ "trafficClasses": ["transit", "peering"] })
+        );
+        assert_eq!(batch[1], unlabeled);
+    }
+}
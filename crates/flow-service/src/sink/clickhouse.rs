@@ -0,0 +1,123 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! [`crate::sink::FlowSink`] writing to ClickHouse over its
+//! [HTTP interface](https://clickhouse.com/docs/en/interfaces/http)
+
+use netgauze_sink::clickhouse::ClickHouseRow;
+
+use crate::sink::{FlowRecord, FlowSinkError};
+
+/// Writes flow records to a ClickHouse table, deduplicated by their
+/// [`FlowRecord::idempotency_key`] in a `record_id` column, see
+/// [`netgauze_sink::clickhouse::ClickHouseSink`]
+pub type ClickHouseSink = netgauze_sink::clickhouse::ClickHouseSink<FlowRecord>;
+
+impl ClickHouseRow for FlowRecord {
+    const COLUMNS: &'static str = "exporter String, \
+         export_time DateTime64(6, 'UTC'), \
+         observation_domain_id UInt32, \
+         template_id UInt16, \
+         scope_fields String, \
+         fields String";
+
+    const ORDER_BY: &'static str = "exporter, export_time";
+
+    const KEY_COLUMN: &'static str = "record_id";
+
+    fn row(&self) -> Result<serde_json::Value, FlowSinkError> {
+        Ok(serde_json::json!({
+            "exporter": self.exporter().to_string(),
+            "export_time": self
+                .export_time()
+                .format("%Y-%m-%d %H:%M:%S%.6f")
+                .to_string(),
+            "observation_domain_id": self.observation_domain_id(),
+            "template_id": self.template_id(),
+            "scope_fields": self.scope_fields().to_string(),
+            "fields": self.fields().to_string(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_insert_body() {
+        let sink =
+            ClickHouseSink::new("http://localhost:8123/".to_string(), "db.flows".to_string())
+                .unwrap();
+        assert_eq!(
+            sink.insert_query(),
+            "INSERT INTO db.flows FORMAT JSONEachRow"
+        );
+        let record = FlowRecord::new(
+            "192.0.2.1:4739".parse().unwrap(),
+            Utc.timestamp_opt(1680000000, 5000).unwrap(),
+            1,
+            400,
+            serde_json::json!([]),
+            serde_json::json!([{"octetDeltaCount": 100}]),
+        );
+        let body = sink.insert_body(&[record.clone(), record]).unwrap();
+        let lines = body.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(lines[0]).unwrap(),
+            serde_json::json!({
+                "exporter": "192.0.2.1:4739",
+                "export_time": "2023-03-28 10:40:00.000005",
+                "observation_domain_id": 1,
+                "template_id": 400,
+                "scope_fields": "[]",
+                "fields": r#"[{"octetDeltaCount":100}]"#,
+            })
+        );
+    }
+    #[test]
+    fn test_deduplication() {
+        let sink =
+            ClickHouseSink::new("http://localhost:8123/".to_string(), "db.flows".to_string())
+                .unwrap();
+        assert!(!sink.deduplication());
+        assert!(sink
+            .create_table_query()
+            .ends_with("fields String) ENGINE = MergeTree ORDER BY (exporter, export_time)"));
+
+        let sink = sink.with_deduplication();
+        assert!(sink.deduplication());
+        assert!(sink.create_table_query().ends_with(
+            "fields String, record_id FixedString(32)) \
+             ENGINE = ReplacingMergeTree ORDER BY (exporter, export_time, record_id)"
+        ));
+        let record = FlowRecord::new(
+            "192.0.2.1:4739".parse().unwrap(),
+            Utc.timestamp_opt(1680000000, 5000).unwrap(),
+            1,
+            400,
+            serde_json::json!([]),
+            serde_json::json!([{"octetDeltaCount": 100}]),
+        );
+        let body = sink.insert_body(std::slice::from_ref(&record)).unwrap();
+        let row = serde_json::from_str::<serde_json::Value>(body.trim_end()).unwrap();
+        assert_eq!(
+            row["record_id"],
+            serde_json::json!(record.idempotency_key().unwrap())
+        );
+    }
+}
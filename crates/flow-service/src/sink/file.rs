@@ -0,0 +1,89 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! [`crate::sink::FlowSink`] writing JSON Lines files into time-bucketed
+//! directories
+
+pub use netgauze_sink::file::{COMPLETION_MARKER, DEFAULT_BUCKET_INTERVAL, DEFAULT_BUCKET_LAYOUT};
+
+use crate::sink::FlowRecord;
+
+/// Writes flow records as JSON Lines into one directory per time bucket, e.g.,
+/// `root/2024/03/28/13/flows-1711630800.jsonl` for hourly buckets, see
+/// [`netgauze_sink::file::FileSink`]
+pub type FileSink = netgauze_sink::file::FileSink<FlowRecord>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::SchemaVersion;
+    use chrono::{TimeZone, Utc};
+
+    fn test_record() -> FlowRecord {
+        FlowRecord::new(
+            "192.0.2.1:4739".parse().unwrap(),
+            Utc.timestamp_opt(1680000000, 0).unwrap(),
+            1,
+            400,
+            serde_json::json!([]),
+            serde_json::json!([{"octetDeltaCount": 100}]),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_file_sink() {
+        let root = std::env::temp_dir().join(format!("netgauze-flow-file-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let mut sink = FileSink::new(root.clone(), "flows".to_string());
+        sink.bootstrap().await.unwrap();
+
+        let now = Utc.with_ymd_and_hms(2024, 3, 28, 13, 5, 0).unwrap();
+        sink.write_at(&[test_record(), test_record()], now)
+            .await
+            .unwrap();
+        sink.close().await.unwrap();
+
+        let dir = root.join("2024/03/28/13");
+        assert!(dir.join(COMPLETION_MARKER).exists());
+        let content = std::fs::read_to_string(dir.join("flows-1711630800.jsonl")).unwrap();
+        let lines = content.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        let value: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(value["schema_version"], serde_json::json!(2));
+        assert_eq!(FlowRecord::from_json(value), Ok(test_record()));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_file_sink_schema_version() {
+        let root =
+            std::env::temp_dir().join(format!("netgauze-flow-file-v1-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let mut sink =
+            FileSink::new(root.clone(), "flows".to_string()).with_schema_version(SchemaVersion::V1);
+        let now = Utc.with_ymd_and_hms(2024, 3, 28, 13, 5, 0).unwrap();
+        sink.write_at(&[test_record()], now).await.unwrap();
+        sink.close().await.unwrap();
+
+        let content =
+            std::fs::read_to_string(root.join("2024/03/28/13/flows-1711630800.jsonl")).unwrap();
+        let value: serde_json::Value = serde_json::from_str(content.trim_end()).unwrap();
+        assert_eq!(value.get("schema_version"), None);
+        assert_eq!(value, serde_json::to_value(test_record()).unwrap());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
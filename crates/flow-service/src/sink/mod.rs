@@ -0,0 +1,386 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage sinks for flow data records.
+//!
+//! Each data record of a decoded [`FlowInfo`] is flattened into a
+//! [`FlowRecord`], tagged with the exporter and packet header information.
+//! Records are written to a [`FlowSink`], usually through a [`BatchingSink`]
+//! to insert them in batches and retry failed writes.
+//!
+//! Serialized records carry a [`SchemaVersion`], so consumers can be migrated
+//! to a new layout after upgrading the service, see [`FlowRecord::to_json`].
+//!
+//! Records are identified by a hash of their content, see
+//! [`FlowRecord::idempotency_key`], so sinks can drop the records written again
+//! when a batch is replayed after a crash.
+//!
+//! Sinks are enabled with crate features:
+//! - `file`: [`file::FileSink`] writes JSON Lines files into time-bucketed
+//!   directories.
+//! - `sqlite`: [`sqlite::SqliteSink`] writes to a SQLite database.
+//! - `clickhouse`: [`clickhouse::ClickHouseSink`] writes to ClickHouse over its
+//!   HTTP interface.
+
+#[cfg(feature = "clickhouse")]
+pub mod clickhouse;
+#[cfg(feature = "file")]
+pub mod file;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+use std::net::SocketAddr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use netgauze_flow_pkt::{ipfix, netflow, FlowInfo};
+use netgauze_sink::{
+    content_key, take_schema_version, to_versioned_json, RecordSink, SinkError, SinkRecord,
+};
+
+pub use netgauze_sink::{
+    batching::{DEFAULT_BATCH_SIZE, DEFAULT_MAX_PENDING},
+    SchemaVersion, SCHEMA_VERSION_FIELD,
+};
+
+/// Errors of the flow record sinks, see [`SinkError`]
+pub type FlowSinkError = SinkError;
+
+/// A single flow data record along with the information of the packet that
+/// carried it.
+///
+/// The (scope) fields are kept as JSON arrays of the serialized information
+/// elements, so records of any template share the same storage schema.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlowRecord {
+    exporter: SocketAddr,
+    export_time: DateTime<Utc>,
+    observation_domain_id: u32,
+    template_id: u16,
+    /// Records serialized by earlier releases don't have an index
+    #[serde(default)]
+    record_index: u32,
+    scope_fields: serde_json::Value,
+    fields: serde_json::Value,
+}
+
+impl FlowRecord {
+    pub const fn new(
+        exporter: SocketAddr,
+        export_time: DateTime<Utc>,
+        observation_domain_id: u32,
+        template_id: u16,
+        scope_fields: serde_json::Value,
+        fields: serde_json::Value,
+    ) -> Self {
+        Self {
+            exporter,
+            export_time,
+            observation_domain_id,
+            template_id,
+            record_index: 0,
+            scope_fields,
+            fields,
+        }
+    }
+
+    /// Position of the record among the data records of its packet
+    pub const fn with_record_index(mut self, record_index: u32) -> Self {
+        self.record_index = record_index;
+        self
+    }
+
+    /// Flatten all the data records in a packet received from `exporter`,
+    /// numbered in the order of the packet. For NetFlow v9 packets, the source
+    /// id is used as the observation domain id.
+    pub fn from_flow_info(
+        exporter: SocketAddr,
+        info: &FlowInfo,
+    ) -> Result<Vec<FlowRecord>, FlowSinkError> {
+        let mut records = vec![];
+        match info {
+            FlowInfo::IPFIX(pkt) => {
+                for set in pkt.sets() {
+                    if let ipfix::Set::Data { id, records: data } = set {
+                        for record in data {
+                            let record_index = records.len() as u32;
+                            records.push(
+                                Self::new(
+                                    exporter,
+                                    pkt.export_time(),
+                                    pkt.observation_domain_id(),
+                                    id.id(),
+                                    to_json(record.scope_fields())?,
+                                    to_json(record.fields())?,
+                                )
+                                .with_record_index(record_index),
+                            );
+                        }
+                    }
+                }
+            }
+            FlowInfo::NetFlowV9(pkt) => {
+                for set in pkt.sets() {
+                    if let netflow::Set::Data { id, records: data } = set {
+                        for record in data {
+                            let record_index = records.len() as u32;
+                            records.push(
+                                Self::new(
+                                    exporter,
+                                    pkt.unix_time(),
+                                    pkt.source_id(),
+                                    id.id(),
+                                    to_json(record.scope_fields())?,
+                                    to_json(record.fields())?,
+                                )
+                                .with_record_index(record_index),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        Ok(records)
+    }
+
+    pub const fn exporter(&self) -> SocketAddr {
+        self.exporter
+    }
+
+    pub const fn export_time(&self) -> DateTime<Utc> {
+        self.export_time
+    }
+
+    pub const fn observation_domain_id(&self) -> u32 {
+        self.observation_domain_id
+    }
+
+    pub const fn template_id(&self) -> u16 {
+        self.template_id
+    }
+
+    /// Position of the record among the data records of its packet
+    pub const fn record_index(&self) -> u32 {
+        self.record_index
+    }
+
+    pub const fn scope_fields(&self) -> &serde_json::Value {
+        &self.scope_fields
+    }
+
+    pub const fn fields(&self) -> &serde_json::Value {
+        &self.fields
+    }
+
+    /// Serialize the record in the layout of `version`. Older versions are
+    /// kept to feed consumers that are not migrated yet.
+    pub fn to_json(&self, version: SchemaVersion) -> Result<serde_json::Value, FlowSinkError> {
+        Ok(to_versioned_json(self, version)?)
+    }
+
+    /// Parse a record serialized with any of the supported schema versions,
+    /// records without a version are read as [`SchemaVersion::V1`]
+    pub fn from_json(mut value: serde_json::Value) -> Result<Self, FlowSinkError> {
+        take_schema_version(&mut value)?;
+        serde_json::from_value(value)
+            .map_err(|err| FlowSinkError::SerializationError(err.to_string()))
+    }
+
+    /// Deterministic 128-bit hash of the content of the record, as 32
+    /// lowercase hex digits, to deduplicate the records delivered more than
+    /// once to a sink, e.g., as the Kafka message key or a ClickHouse column.
+    ///
+    /// The key only depends on the fields of [`SchemaVersion::V1`], so it's
+    /// the same for a record read back with [`Self::from_json`] from any
+    /// schema version. The key includes the [`Self::record_index`], so
+    /// identical data records exported in the same packet, e.g., the counters
+    /// of two idle flows, are kept apart.
+    pub fn idempotency_key(&self) -> Result<String, FlowSinkError> {
+        Ok(content_key(&self.to_json(SchemaVersion::V1)?))
+    }
+
+    pub fn scope_fields_mut(&mut self) -> &mut serde_json::Value {
+        &mut self.scope_fields
+    }
+
+    pub fn fields_mut(&mut self) -> &mut serde_json::Value {
+        &mut self.fields
+    }
+}
+
+impl SinkRecord for FlowRecord {
+    type Error = FlowSinkError;
+
+    fn to_json(&self, version: SchemaVersion) -> Result<serde_json::Value, FlowSinkError> {
+        FlowRecord::to_json(self, version)
+    }
+
+    fn idempotency_key(&self) -> Result<String, FlowSinkError> {
+        FlowRecord::idempotency_key(self)
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<serde_json::Value, FlowSinkError> {
+    serde_json::to_value(value).map_err(|err| FlowSinkError::SerializationError(err.to_string()))
+}
+
+/// Storage backend for [`FlowRecord`]s, implemented by all the
+/// [`RecordSink`]s of flow records
+pub trait FlowSink: RecordSink<FlowRecord> {}
+
+impl<S: RecordSink<FlowRecord>> FlowSink for S {}
+
+/// Buffers flow records and writes them to the inner [`FlowSink`] in batches,
+/// see [`netgauze_sink::batching::BatchingSink`]
+pub type BatchingSink<S> = netgauze_sink::batching::BatchingSink<S, FlowRecord>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+    use netgauze_flow_pkt::{
+        ie::{self, Field},
+        ipfix::{DataRecord, IpfixPacket, Set},
+        DataSetId,
+    };
+
+    #[derive(Debug, Default)]
+    struct VecSink {
+        batches: Vec<Vec<FlowRecord>>,
+    }
+
+    #[async_trait]
+    impl RecordSink<FlowRecord> for VecSink {
+        async fn bootstrap(&mut self) -> Result<(), FlowSinkError> {
+            Ok(())
+        }
+
+        async fn write(&mut self, records: &[FlowRecord]) -> Result<(), FlowSinkError> {
+            self.batches.push(records.to_vec());
+            Ok(())
+        }
+    }
+
+    fn test_records() -> Vec<FlowRecord> {
+        let export_time = Utc.timestamp_opt(1680000000, 0).unwrap();
+        let pkt = FlowInfo::IPFIX(IpfixPacket::new(
+            export_time,
+            1,
+            0,
+            vec![Set::Data {
+                id: DataSetId::new(400).unwrap(),
+                records: vec![
+                    DataRecord::new(
+                        vec![],
+                        vec![Field::octetDeltaCount(ie::octetDeltaCount(100))],
+                    ),
+                    DataRecord::new(
+                        vec![],
+                        vec![Field::octetDeltaCount(ie::octetDeltaCount(200))],
+                    ),
+                ],
+            }],
+        ));
+        FlowRecord::from_flow_info("192.0.2.1:4739".parse().unwrap(), &pkt).unwrap()
+    }
+
+    #[test]
+    fn test_from_flow_info() {
+        let records = test_records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].template_id(), 400);
+        assert_eq!(records[0].record_index(), 0);
+        assert_eq!(records[1].record_index(), 1);
+        assert_eq!(records[0].observation_domain_id(), 0);
+        assert_eq!(records[0].scope_fields(), &serde_json::json!([]));
+        assert_eq!(
+            records[1].fields(),
+            &serde_json::json!([{"octetDeltaCount": 200}])
+        );
+    }
+
+    #[test]
+    fn test_schema_versions() {
+        let record = test_records().remove(0);
+        let v1 = record.to_json(SchemaVersion::V1).unwrap();
+        let v2 = record.to_json(SchemaVersion::V2).unwrap();
+        assert_eq!(v1.get(SCHEMA_VERSION_FIELD), None);
+        assert_eq!(v2[SCHEMA_VERSION_FIELD], serde_json::json!(2));
+        assert_eq!(FlowRecord::from_json(v1), Ok(record.clone()));
+        assert_eq!(FlowRecord::from_json(v2.clone()), Ok(record));
+
+        let mut v3 = v2;
+        v3[SCHEMA_VERSION_FIELD] = serde_json::json!(3);
+        assert_eq!(
+            FlowRecord::from_json(v3),
+            Err(FlowSinkError::UnsupportedSchemaVersion(serde_json::json!(
+                3
+            )))
+        );
+    }
+
+    #[test]
+    fn test_idempotency_key() {
+        let mut records = test_records();
+        let key = records[0].idempotency_key().unwrap();
+        assert_eq!(key.len(), 32);
+        assert_ne!(key, records[1].idempotency_key().unwrap());
+
+        let v2 = FlowRecord::from_json(records[0].to_json(SchemaVersion::V2).unwrap()).unwrap();
+        assert_eq!(v2.idempotency_key().unwrap(), key);
+        let reparsed: serde_json::Value =
+            serde_json::from_str(&records[0].to_json(SchemaVersion::V1).unwrap().to_string())
+                .unwrap();
+        assert_eq!(
+            FlowRecord::from_json(reparsed)
+                .unwrap()
+                .idempotency_key()
+                .unwrap(),
+            key
+        );
+
+        records[0].fields_mut()[0]["octetDeltaCount"] = serde_json::json!(101);
+        assert_ne!(records[0].idempotency_key().unwrap(), key);
+
+        // Identical records of the same packet
+        records[1] = records[0].clone().with_record_index(1);
+        assert_ne!(
+            records[0].idempotency_key().unwrap(),
+            records[1].idempotency_key().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batching_sink() {
+        let mut sink = BatchingSink::new(VecSink::default(), 3);
+        sink.extend(test_records()).await.unwrap();
+        assert_eq!(sink.pending(), 2);
+        assert!(sink.sink().batches.is_empty());
+        sink.extend(test_records()).await.unwrap();
+        assert_eq!(sink.pending(), 1);
+        assert_eq!(sink.sink().batches.len(), 1);
+        let inner = sink.into_inner().await.unwrap();
+        assert_eq!(
+            inner
+                .batches
+                .iter()
+                .map(|batch| batch.len())
+                .collect::<Vec<_>>(),
+            vec![3, 1]
+        );
+    }
+}
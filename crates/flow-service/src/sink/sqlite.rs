@@ -0,0 +1,141 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! [`FlowSink`] writing to a SQLite database
+
+use std::path::Path;
+
+use async_trait::async_trait;
+use rusqlite::{params, Connection};
+
+use netgauze_sink::{check_table_name, RecordSink};
+
+use crate::sink::{FlowRecord, FlowSinkError};
+
+fn sqlite_error(err: rusqlite::Error) -> FlowSinkError {
+    FlowSinkError::DatabaseError(err.to_string())
+}
+
+/// Writes flow records to a SQLite table, each batch is inserted in a single
+/// transaction.
+///
+/// Note: SQLite calls are blocking, so the sink is meant for low to moderate
+/// record rates, e.g., labs and examples.
+#[derive(Debug)]
+pub struct SqliteSink {
+    connection: Connection,
+    table: String,
+}
+
+impl SqliteSink {
+    pub fn new(connection: Connection, table: String) -> Result<Self, FlowSinkError> {
+        check_table_name(&table)?;
+        Ok(Self { connection, table })
+    }
+
+    /// Open (or create) the database file at the given path
+    pub fn open<P: AsRef<Path>>(path: P, table: String) -> Result<Self, FlowSinkError> {
+        Self::new(Connection::open(path).map_err(sqlite_error)?, table)
+    }
+
+    pub const fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+}
+
+#[async_trait]
+impl RecordSink<FlowRecord> for SqliteSink {
+    async fn bootstrap(&mut self) -> Result<(), FlowSinkError> {
+        self.connection
+            .execute_batch(&format!(
+                "CREATE TABLE IF NOT EXISTS {} (
+                exporter TEXT NOT NULL,
+                export_time TEXT NOT NULL,
+                observation_domain_id INTEGER NOT NULL,
+                template_id INTEGER NOT NULL,
+                scope_fields TEXT NOT NULL,
+                fields TEXT NOT NULL
+            )",
+                self.table
+            ))
+            .map_err(sqlite_error)?;
+        Ok(())
+    }
+
+    async fn write(&mut self, records: &[FlowRecord]) -> Result<(), FlowSinkError> {
+        let tx = self.connection.transaction().map_err(sqlite_error)?;
+        {
+            let mut stmt = tx
+                .prepare_cached(&format!(
+                    "INSERT INTO {} (exporter, export_time, observation_domain_id, template_id, \
+                 scope_fields, fields) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    self.table
+                ))
+                .map_err(sqlite_error)?;
+            for record in records {
+                stmt.execute(params![
+                    record.exporter().to_string(),
+                    record.export_time().to_rfc3339(),
+                    record.observation_domain_id(),
+                    record.template_id(),
+                    record.scope_fields().to_string(),
+                    record.fields().to_string(),
+                ])
+                .map_err(sqlite_error)?;
+            }
+        }
+        tx.commit().map_err(sqlite_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[tokio::test]
+    async fn test_sqlite_sink() {
+        let mut sink =
+            SqliteSink::new(Connection::open_in_memory().unwrap(), "flows".to_string()).unwrap();
+        sink.bootstrap().await.unwrap();
+        // Bootstrapping an existing schema is a no-op
+        sink.bootstrap().await.unwrap();
+        let record = FlowRecord::new(
+            "192.0.2.1:4739".parse().unwrap(),
+            Utc.timestamp_opt(1680000000, 0).unwrap(),
+            1,
+            400,
+            serde_json::json!([]),
+            serde_json::json!([{"octetDeltaCount": 100}]),
+        );
+        sink.write(&[record.clone(), record]).await.unwrap();
+
+        let (count, fields): (u32, String) = sink
+            .connection()
+            .query_row(
+                "SELECT COUNT(*), MAX(fields) FROM flows WHERE template_id = 400",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(fields, r#"[{"octetDeltaCount":100}]"#);
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Consume raw exported packets from Kafka topics
+
+use std::net::SocketAddr;
+
+use bytes::BytesMut;
+use rdkafka::{
+    config::ClientConfig,
+    consumer::{Consumer, StreamConsumer},
+    Message,
+};
+use serde::{Deserialize, Serialize};
+
+use netgauze_flow_pkt::{codec::TemplateBufferPolicy, FlowInfo};
+
+use crate::source::{ExporterQuota, FlowDecoder, FlowDecoderError};
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KafkaSourceError {
+    KafkaError(String),
+    /// The message has no key identifying the exporter
+    MissingExporter {
+        topic: String,
+        partition: i32,
+        offset: i64,
+    },
+    /// The message key is not a valid UTF-8 `ip:port` exporter address
+    InvalidExporter {
+        topic: String,
+        partition: i32,
+        offset: i64,
+        key: Vec<u8>,
+    },
+    DecodingError {
+        exporter: SocketAddr,
+        error: FlowDecoderError,
+    },
+}
+
+impl std::fmt::Display for KafkaSourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::error::Error for KafkaSourceError {}
+
+impl From<rdkafka::error::KafkaError> for KafkaSourceError {
+    fn from(value: rdkafka::error::KafkaError) -> Self {
+        Self::KafkaError(value.to_string())
+    }
+}
+
+/// Consumes raw IPFIX/NetFlow v9 packets from Kafka topics.
+///
+/// Each Kafka message carries the bytes of one or more whole packets as
+/// exported, and is keyed by the exporter address formatted as `ip:port`,
+/// e.g., `192.0.2.1:4739`. The key scopes the templates, so messages of the
+/// same exporter must be produced with the same key, which also keeps them in
+/// order on a single partition.
+///
+/// Errors are returned per message, the source can keep on receiving after
+/// any of them.
+pub struct KafkaSource {
+    consumer: StreamConsumer,
+    decoder: FlowDecoder,
+}
+
+impl KafkaSource {
+    /// Create a consumer with the given `config`, which must at least set
+    /// `bootstrap.servers` and `group.id`, and subscribe it to `topics`.
+    pub fn new(config: &ClientConfig, topics: &[&str]) -> Result<Self, KafkaSourceError> {
+        let consumer: StreamConsumer = config.create()?;
+        consumer.subscribe(topics)?;
+        Ok(Self {
+            consumer,
+            decoder: FlowDecoder::default(),
+        })
+    }
+
+    pub fn with_template_buffer_policy(
+        mut self,
+        template_buffer_policy: TemplateBufferPolicy,
+    ) -> Self {
+        self.decoder = self
+            .decoder
+            .with_template_buffer_policy(template_buffer_policy);
+        self
+    }
+
+    pub fn with_quota(mut self, quota: ExporterQuota) -> Self {
+        self.decoder = self.decoder.with_quota(quota);
+        self
+    }
+
+    pub const fn consumer(&self) -> &StreamConsumer {
+        &self.consumer
+    }
+
+    pub const fn decoder(&self) -> &FlowDecoder {
+        &self.decoder
+    }
+
+    /// Wait for the next Kafka message and decode the packets it carries
+    pub async fn recv(&mut self) -> Result<(SocketAddr, Vec<FlowInfo>), KafkaSourceError> {
+        let msg = self.consumer.recv().await?;
+        let exporter = exporter(msg.topic(), msg.partition(), msg.offset(), msg.key())?;
+        let buf = BytesMut::from(msg.payload().unwrap_or_default());
+        let decoded = self
+            .decoder
+            .decode(exporter, buf)
+            .map_err(|error| KafkaSourceError::DecodingError { exporter, error })?;
+        Ok((exporter, decoded))
+    }
+}
+
+fn exporter(
+    topic: &str,
+    partition: i32,
+    offset: i64,
+    key: Option<&[u8]>,
+) -> Result<SocketAddr, KafkaSourceError> {
+    let key = key.ok_or_else(|| KafkaSourceError::MissingExporter {
+        topic: topic.to_string(),
+        partition,
+        offset,
+    })?;
+    std::str::from_utf8(key)
+        .ok()
+        .and_then(|key| key.parse().ok())
+        .ok_or_else(|| KafkaSourceError::InvalidExporter {
+            topic: topic.to_string(),
+            partition,
+            offset,
+            key: key.to_vec(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exporter_key() {
+        assert_eq!(
+            exporter("flows", 1, 10, Some(b"192.0.2.1:4739")),
+            Ok("192.0.2.1:4739".parse().unwrap())
+        );
+        assert_eq!(
+            exporter("flows", 1, 10, Some(b"[2001:db8::1]:2055")),
+            Ok("[2001:db8::1]:2055".parse().unwrap())
+        );
+        assert_eq!(
+            exporter("flows", 1, 10, None),
+            Err(KafkaSourceError::MissingExporter {
+                topic: "flows".to_string(),
+                partition: 1,
+                offset: 10,
+            })
+        );
+        assert_eq!(
+            exporter("flows", 1, 10, Some(b"192.0.2.1")),
+            Err(KafkaSourceError::InvalidExporter {
+                topic: "flows".to_string(),
+                partition: 1,
+                offset: 10,
+                key: b"192.0.2.1".to_vec(),
+            })
+        );
+    }
+}
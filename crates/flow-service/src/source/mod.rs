@@ -0,0 +1,563 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sources of exported flow packets that are not received directly from the
+//! exporters.
+//!
+//! Regardless of how the raw packets are transported, templates are scoped
+//! per exporter, hence every source decodes the packets with a
+//! [`FlowDecoder`] keeping one [`FlowInfoCodec`] per exporter. The decoder
+//! also isolates the exporters sharing a collector from each other with an
+//! [`ExporterQuota`] and [`ExporterMetrics`] per exporter IP address.
+//!
+//! Sources are enabled with crate features:
+//! - `kafka`: [`kafka::KafkaSource`] consumes raw packets from Kafka topics,
+//!   e.g., shipped by a lightweight relay running close to the exporters.
+
+#[cfg(feature = "kafka")]
+pub mod kafka;
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+};
+
+use bytes::BytesMut;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio_util::codec::Decoder;
+
+use netgauze_flow_pkt::{
+    codec::{
+        FlowInfoCodec, FlowInfoCodecDecoderError, TemplateBufferPolicy, TemplateSummary,
+        TemplatesState,
+    },
+    ie::IE,
+    ipfix, netflow, FlowInfo,
+};
+use netgauze_parse_utils::raw_frame::RawFramePolicy;
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlowDecoderError {
+    DecodingError(FlowInfoCodecDecoderError),
+    /// The exporter IP address already has as many sessions, i.e., source
+    /// ports, as allowed by [`ExporterQuota::max_sessions`]
+    TooManySessions {
+        exporter: IpAddr,
+        limit: usize,
+    },
+    /// The exporter sent more templates than allowed by
+    /// [`ExporterQuota::max_templates`], the templates learned from it are
+    /// forgotten
+    TooManyTemplates {
+        exporter: SocketAddr,
+        limit: usize,
+    },
+}
+
+impl std::fmt::Display for FlowDecoderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::error::Error for FlowDecoderError {}
+
+impl From<FlowInfoCodecDecoderError> for FlowDecoderError {
+    fn from(value: FlowInfoCodecDecoderError) -> Self {
+        Self::DecodingError(value)
+    }
+}
+
+/// Limits applied to every exporter IP address, so a misconfigured exporter
+/// sharing the collector can't exhaust its memory. Exceeding a limit only
+/// affects the offending exporter.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExporterQuota {
+    max_templates: usize,
+    max_sessions: usize,
+}
+
+impl ExporterQuota {
+    pub const fn new(max_templates: usize, max_sessions: usize) -> Self {
+        Self {
+            max_templates,
+            max_sessions,
+        }
+    }
+
+    /// Max number of templates kept for each session of the exporter
+    pub const fn max_templates(&self) -> usize {
+        self.max_templates
+    }
+
+    /// Max number of source ports the exporter can send from at the same time,
+    /// each with its own templates
+    pub const fn max_sessions(&self) -> usize {
+        self.max_sessions
+    }
+}
+
+/// Unlimited quota
+impl Default for ExporterQuota {
+    fn default() -> Self {
+        Self::new(usize::MAX, usize::MAX)
+    }
+}
+
+/// Counters kept per exporter IP address
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExporterMetrics {
+    pub received_bytes: u64,
+    pub decoded_packets: u64,
+    pub decoding_errors: u64,
+    pub quota_violations: u64,
+}
+
+/// Templates and buffering state of one exporter session, see
+/// [`FlowDecoder::snapshot`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub exporter: SocketAddr,
+    pub templates: Vec<TemplateSummary>,
+    pub pending_packets: usize,
+    pub dropped_packets: u64,
+}
+
+/// Serializable state of one exporter IP address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExporterSnapshot {
+    pub exporter: IpAddr,
+    pub metrics: ExporterMetrics,
+    pub sessions: Vec<SessionSnapshot>,
+}
+
+/// Information Element with an enterprise number unknown to NetGauze, its
+/// values are decoded as raw bytes
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct UnknownIe {
+    pub pen: u32,
+    pub id: u16,
+}
+
+/// Sessions and counters of one exporter IP address
+#[derive(Debug, Default)]
+struct ExporterState {
+    codecs: HashMap<SocketAddr, FlowInfoCodec>,
+    /// Sessions restored with [`FlowDecoder::restore`] that didn't send any
+    /// packet since, with the time their templates were saved
+    restored: HashMap<SocketAddr, DateTime<Utc>>,
+    metrics: ExporterMetrics,
+    unknown_ies: HashMap<UnknownIe, u64>,
+}
+
+/// Unknown Information Elements of the templates carried by `info`
+pub(crate) fn unknown_ies(info: &FlowInfo) -> Vec<UnknownIe> {
+    let unknown = |ie: IE| match ie {
+        IE::Unknown { pen, id } => Some(UnknownIe { pen, id }),
+        _ => None,
+    };
+    match info {
+        FlowInfo::IPFIX(pkt) => pkt
+            .sets()
+            .iter()
+            .flat_map(|set| match set {
+                ipfix::Set::Template(records) => records
+                    .iter()
+                    .flat_map(|record| record.field_specifiers().iter())
+                    .filter_map(|field| unknown(field.element_id()))
+                    .collect(),
+                ipfix::Set::OptionsTemplate(records) => records
+                    .iter()
+                    .flat_map(|record| {
+                        record
+                            .scope_field_specifiers()
+                            .iter()
+                            .chain(record.field_specifiers().iter())
+                    })
+                    .filter_map(|field| unknown(field.element_id()))
+                    .collect(),
+                ipfix::Set::Data { .. } => vec![],
+            })
+            .collect(),
+        FlowInfo::NetFlowV9(pkt) => pkt
+            .sets()
+            .iter()
+            .flat_map(|set| match set {
+                netflow::Set::Template(records) => records
+                    .iter()
+                    .flat_map(|record| record.field_specifiers().iter())
+                    .filter_map(|field| unknown(field.element_id()))
+                    .collect(),
+                netflow::Set::OptionsTemplate(records) => records
+                    .iter()
+                    .flat_map(|record| record.field_specifiers().iter())
+                    .filter_map(|field| unknown(field.element_id()))
+                    .collect(),
+                netflow::Set::Data { .. } => vec![],
+            })
+            .collect(),
+    }
+}
+
+/// Decodes the raw packets of many exporters, keeping the templates learned
+/// from each exporter apart.
+///
+/// Exporters are identified by their IP address; each source port is a
+/// session with its own templates. The [`ExporterQuota`] and the
+/// [`ExporterMetrics`] apply to the exporter IP address as a whole.
+#[derive(Debug, Default)]
+pub struct FlowDecoder {
+    template_buffer_policy: TemplateBufferPolicy,
+    raw_frame_policy: RawFramePolicy,
+    quota: ExporterQuota,
+    exporters: HashMap<IpAddr, ExporterState>,
+}
+
+impl FlowDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Policy used for the codecs of exporters seen for the first time
+    pub fn with_template_buffer_policy(
+        mut self,
+        template_buffer_policy: TemplateBufferPolicy,
+    ) -> Self {
+        self.template_buffer_policy = template_buffer_policy;
+        self
+    }
+
+    pub const fn template_buffer_policy(&self) -> TemplateBufferPolicy {
+        self.template_buffer_policy
+    }
+
+    /// Attach the packets that fail to parse to the decoding errors, within
+    /// the limits of `policy` for each exporter session, see
+    /// [`FlowInfoCodec::set_raw_frame_policy`]
+    pub const fn with_raw_frame_policy(mut self, raw_frame_policy: RawFramePolicy) -> Self {
+        self.raw_frame_policy = raw_frame_policy;
+        self
+    }
+
+    pub const fn raw_frame_policy(&self) -> RawFramePolicy {
+        self.raw_frame_policy
+    }
+
+    /// Codec for a new exporter session
+    fn new_codec(
+        template_buffer_policy: TemplateBufferPolicy,
+        raw_frame_policy: RawFramePolicy,
+    ) -> FlowInfoCodec {
+        let mut codec = FlowInfoCodec::with_template_buffer_policy(template_buffer_policy);
+        codec.set_raw_frame_policy(raw_frame_policy);
+        codec
+    }
+
+    pub const fn with_quota(mut self, quota: ExporterQuota) -> Self {
+        self.quota = quota;
+        self
+    }
+
+    pub const fn quota(&self) -> ExporterQuota {
+        self.quota
+    }
+
+    /// Decode all the packets in `buf` received from `exporter`. The result
+    /// might be empty when the packets are buffered waiting for their
+    /// templates, or include previously buffered packets once their templates
+    /// are received.
+    ///
+    /// `buf` is expected to contain whole packets, trailing bytes that don't
+    /// make up a packet are reported as [`FlowInfoCodecDecoderError::Incomplete`].
+    pub fn decode(
+        &mut self,
+        exporter: SocketAddr,
+        mut buf: BytesMut,
+    ) -> Result<Vec<FlowInfo>, FlowDecoderError> {
+        let template_buffer_policy = self.template_buffer_policy;
+        let raw_frame_policy = self.raw_frame_policy;
+        let quota = self.quota;
+        let state = self.exporters.entry(exporter.ip()).or_default();
+        state.metrics.received_bytes += buf.len() as u64;
+        if !state.codecs.contains_key(&exporter) && state.codecs.len() >= quota.max_sessions {
+            state.metrics.quota_violations += 1;
+            tracing::warn!(%exporter, limit = quota.max_sessions, "too many exporter sessions");
+            return Err(FlowDecoderError::TooManySessions {
+                exporter: exporter.ip(),
+                limit: quota.max_sessions,
+            });
+        }
+        state.restored.remove(&exporter);
+        let codec = state
+            .codecs
+            .entry(exporter)
+            .or_insert_with(|| Self::new_codec(template_buffer_policy, raw_frame_policy));
+        let mut decoded = vec![];
+        let result = loop {
+            match codec.decode(&mut buf) {
+                Ok(Some(info)) => decoded.push(info),
+                Ok(None) if buf.is_empty() => break Ok(()),
+                Ok(None) => break Err(FlowInfoCodecDecoderError::Incomplete(Some(buf.len()))),
+                Err(err) => break Err(err),
+            }
+        };
+        if codec.templates_count() > quota.max_templates {
+            state.codecs.remove(&exporter);
+            state.metrics.quota_violations += 1;
+            tracing::warn!(%exporter, limit = quota.max_templates, "too many templates, forgetting the exporter templates");
+            return Err(FlowDecoderError::TooManyTemplates {
+                exporter,
+                limit: quota.max_templates,
+            });
+        }
+        if let Err(err) = result {
+            state.metrics.decoding_errors += 1;
+            return Err(err.into());
+        }
+        state.metrics.decoded_packets += decoded.len() as u64;
+        for ie in decoded.iter().flat_map(unknown_ies) {
+            let count = state.unknown_ies.entry(ie).or_insert(0);
+            if *count == 0 {
+                tracing::warn!(%exporter, pen = ie.pen, id = ie.id, "received unknown information element for the first time");
+            }
+            *count += 1;
+        }
+        Ok(decoded)
+    }
+
+    /// Forget the templates learned from `exporter`
+    pub fn remove(&mut self, exporter: &SocketAddr) -> Option<FlowInfoCodec> {
+        self.exporters.get_mut(&exporter.ip()).and_then(|state| {
+            state.restored.remove(exporter);
+            state.codecs.remove(exporter)
+        })
+    }
+
+    /// Start the session of `exporter` with templates learned before, e.g.,
+    /// by a previous run of the collector, see
+    /// [`crate::template_store::TemplateStore`]. Sessions that are already
+    /// known keep their templates, in that case `false` is returned.
+    pub fn restore(
+        &mut self,
+        exporter: SocketAddr,
+        templates: TemplatesState,
+        saved_at: DateTime<Utc>,
+    ) -> bool {
+        let template_buffer_policy = self.template_buffer_policy;
+        let raw_frame_policy = self.raw_frame_policy;
+        let state = self.exporters.entry(exporter.ip()).or_default();
+        if state.codecs.contains_key(&exporter) {
+            return false;
+        }
+        let mut codec = Self::new_codec(template_buffer_policy, raw_frame_policy);
+        codec.set_templates_state(templates);
+        state.codecs.insert(exporter, codec);
+        state.restored.insert(exporter, saved_at);
+        true
+    }
+
+    /// When the templates of a restored session were saved, `None` once the
+    /// exporter sent a packet to the session or for sessions not restored
+    pub fn restored_at(&self, exporter: &SocketAddr) -> Option<DateTime<Utc>> {
+        self.exporters
+            .get(&exporter.ip())
+            .and_then(|state| state.restored.get(exporter))
+            .copied()
+    }
+
+    /// Forget the templates and the metrics of all the sessions of `exporter`
+    pub fn remove_exporter(&mut self, exporter: &IpAddr) -> Option<ExporterMetrics> {
+        self.exporters.remove(exporter).map(|state| state.metrics)
+    }
+
+    pub fn exporters(&self) -> impl Iterator<Item = &SocketAddr> {
+        self.exporters
+            .values()
+            .flat_map(|state| state.codecs.keys())
+    }
+
+    pub fn codec(&self, exporter: &SocketAddr) -> Option<&FlowInfoCodec> {
+        self.exporters
+            .get(&exporter.ip())
+            .and_then(|state| state.codecs.get(exporter))
+    }
+
+    pub fn metrics(&self, exporter: &IpAddr) -> Option<&ExporterMetrics> {
+        self.exporters.get(exporter).map(|state| &state.metrics)
+    }
+
+    /// Number of times each unknown Information Element is received in the
+    /// templates of `exporter`
+    pub fn unknown_ies(&self, exporter: &IpAddr) -> Option<&HashMap<UnknownIe, u64>> {
+        self.exporters.get(exporter).map(|state| &state.unknown_ies)
+    }
+
+    /// Templates learned from each exporter session along with the exporter
+    /// counters, sorted by address, to inspect a running collector
+    pub fn snapshot(&self) -> Vec<ExporterSnapshot> {
+        let mut exporters = self
+            .exporters
+            .iter()
+            .map(|(exporter, state)| {
+                let mut sessions = state
+                    .codecs
+                    .iter()
+                    .map(|(session, codec)| SessionSnapshot {
+                        exporter: *session,
+                        templates: codec.templates(),
+                        pending_packets: codec.pending_packets(),
+                        dropped_packets: codec.dropped_packets(),
+                    })
+                    .collect::<Vec<_>>();
+                sessions.sort_by_key(|session| session.exporter);
+                ExporterSnapshot {
+                    exporter: *exporter,
+                    metrics: state.metrics,
+                    sessions,
+                }
+            })
+            .collect::<Vec<_>>();
+        exporters.sort_by_key(|exporter| exporter.exporter);
+        exporters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netgauze_flow_pkt::test_vectors::ipfix_packets;
+
+    #[test]
+    fn test_flow_decoder() {
+        let vectors = ipfix_packets();
+        let (template, data) = (&vectors[0], &vectors[1]);
+        let exporter: SocketAddr = "192.0.2.1:4739".parse().unwrap();
+        let other: SocketAddr = "192.0.2.2:4739".parse().unwrap();
+        let mut decoder = FlowDecoder::new();
+
+        let decoded = decoder
+            .decode(exporter, BytesMut::from(template.wire().as_slice()))
+            .unwrap();
+        assert_eq!(decoded, vec![FlowInfo::IPFIX(template.expected().clone())]);
+        let decoded = decoder
+            .decode(exporter, BytesMut::from(data.wire().as_slice()))
+            .unwrap();
+        assert_eq!(decoded, vec![FlowInfo::IPFIX(data.expected().clone())]);
+
+        // Templates are not shared between exporters
+        assert!(decoder
+            .decode(other, BytesMut::from(data.wire().as_slice()))
+            .is_err());
+        assert_eq!(decoder.exporters().count(), 2);
+        let snapshot = decoder.snapshot();
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].exporter, exporter.ip());
+        assert_eq!(snapshot[0].metrics.decoded_packets, 2);
+        let templates = &snapshot[0].sessions[0].templates;
+        assert_eq!(templates.len(), 1);
+        assert_eq!((templates[0].version, templates[0].template_id), (10, 307));
+        assert!(snapshot[1].sessions[0].templates.is_empty());
+
+        // Both packets in a single buffer
+        decoder.remove(&exporter);
+        let mut buf = BytesMut::from(template.wire().as_slice());
+        buf.extend_from_slice(data.wire());
+        assert_eq!(decoder.decode(exporter, buf).unwrap().len(), 2);
+
+        // Truncated packet
+        let truncated = &data.wire()[..data.wire().len() - 1];
+        assert_eq!(
+            decoder.decode(exporter, BytesMut::from(truncated)),
+            Err(FlowDecoderError::DecodingError(
+                FlowInfoCodecDecoderError::Incomplete(Some(truncated.len()))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_exporter_quota() {
+        let vectors = ipfix_packets();
+        let (template, data) = (&vectors[0], &vectors[1]);
+        let exporter: SocketAddr = "192.0.2.1:4739".parse().unwrap();
+        let other_port: SocketAddr = "192.0.2.1:4740".parse().unwrap();
+        let other: SocketAddr = "192.0.2.2:4739".parse().unwrap();
+        let mut decoder = FlowDecoder::new().with_quota(ExporterQuota::new(0, 1));
+
+        // The template is forgotten since the exporter is allowed none
+        assert_eq!(
+            decoder.decode(exporter, BytesMut::from(template.wire().as_slice())),
+            Err(FlowDecoderError::TooManyTemplates { exporter, limit: 0 })
+        );
+        assert!(decoder.codec(&exporter).is_none());
+        // Another session of the same exporter doesn't know the template either
+        assert!(matches!(
+            decoder.decode(other_port, BytesMut::from(data.wire().as_slice())),
+            Err(FlowDecoderError::DecodingError(_))
+        ));
+        assert_eq!(
+            decoder.decode(exporter, BytesMut::from(data.wire().as_slice())),
+            Err(FlowDecoderError::TooManySessions {
+                exporter: exporter.ip(),
+                limit: 1
+            })
+        );
+        assert_eq!(
+            decoder.metrics(&exporter.ip()),
+            Some(&ExporterMetrics {
+                received_bytes: (template.wire().len() + 2 * data.wire().len()) as u64,
+                decoded_packets: 0,
+                decoding_errors: 1,
+                quota_violations: 2,
+            })
+        );
+
+        // Other exporters are not affected
+        let mut decoder = decoder.with_quota(ExporterQuota::new(1, 1));
+        let mut buf = BytesMut::from(template.wire().as_slice());
+        buf.extend_from_slice(data.wire());
+        assert_eq!(decoder.decode(other, buf).unwrap().len(), 2);
+        assert_eq!(
+            decoder
+                .metrics(&other.ip())
+                .map(|metrics| metrics.decoded_packets),
+            Some(2)
+        );
+        assert!(decoder.remove_exporter(&exporter.ip()).is_some());
+        assert_eq!(decoder.exporters().collect::<Vec<_>>(), vec![&other]);
+    }
+
+    #[test]
+    fn test_unknown_ies() {
+        // IPFIX template 256 with a single field of the unknown enterprise 12345
+        let template = [
+            0x00, 0x0a, 0x00, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x02, 0x00, 0x10, 0x01, 0x00, 0x00, 0x01, 0x80, 0x01, 0x00, 0x04,
+            0x00, 0x00, 0x30, 0x39,
+        ];
+        let exporter: SocketAddr = "192.0.2.1:4739".parse().unwrap();
+        let mut decoder = FlowDecoder::new();
+        assert_eq!(decoder.unknown_ies(&exporter.ip()), None);
+        for _ in 0..2 {
+            decoder
+                .decode(exporter, BytesMut::from(template.as_slice()))
+                .unwrap();
+        }
+        assert_eq!(
+            decoder.unknown_ies(&exporter.ip()),
+            Some(&HashMap::from([(UnknownIe { pen: 12345, id: 1 }, 2)]))
+        );
+    }
+}
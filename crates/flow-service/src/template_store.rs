@@ -0,0 +1,217 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! On-disk persistence of the templates learned by a [`FlowDecoder`].
+//!
+//! Exporters over UDP only resend their templates periodically, so a
+//! collector that restarts can't decode any data set until the next refresh.
+//! [`TemplateStore`] saves the templates of every exporter session to a JSON
+//! file and restores them on start. Templates saved longer than the store TTL
+//! ago are discarded, since the exporter may have redefined them in the
+//! meantime.
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use netgauze_flow_service::{source::FlowDecoder, template_store::TemplateStore};
+//! let store = TemplateStore::new("templates.json", Duration::from_secs(1800));
+//! let mut decoder = FlowDecoder::new();
+//! store.restore(&mut decoder).unwrap();
+//! // Decode packets, saving the templates from time to time and on shutdown
+//! store.save(&decoder).unwrap();
+//! ```
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use netgauze_flow_pkt::codec::TemplatesState;
+
+use crate::source::FlowDecoder;
+
+/// Default time templates are trusted after being saved, matching the
+/// template timeout commonly configured on exporters
+pub const DEFAULT_TEMPLATE_TTL: Duration = Duration::from_secs(1800);
+
+/// Templates of one exporter session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionTemplates {
+    pub exporter: SocketAddr,
+    /// When the templates were last known to be used by the exporter
+    pub saved_at: DateTime<Utc>,
+    pub templates: TemplatesState,
+}
+
+/// Content of the template store file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplatesSnapshot {
+    pub sessions: Vec<SessionTemplates>,
+}
+
+impl TemplatesSnapshot {
+    /// Templates of all the sessions of `decoder`. The sessions restored from
+    /// a previous snapshot that didn't send any packet since keep their
+    /// original save time, so they still expire.
+    pub fn from_decoder(decoder: &FlowDecoder, now: DateTime<Utc>) -> Self {
+        let mut sessions = decoder
+            .exporters()
+            .filter_map(|exporter| {
+                decoder.codec(exporter).map(|codec| SessionTemplates {
+                    exporter: *exporter,
+                    saved_at: decoder.restored_at(exporter).unwrap_or(now),
+                    templates: codec.templates_state(),
+                })
+            })
+            .filter(|session| {
+                !session.templates.ipfix.is_empty() || !session.templates.netflow_v9.is_empty()
+            })
+            .collect::<Vec<_>>();
+        sessions.sort_by_key(|session| session.exporter);
+        Self { sessions }
+    }
+
+    /// Drop the sessions saved longer than `ttl` before `now`
+    pub fn expire(&mut self, ttl: Duration, now: DateTime<Utc>) -> usize {
+        let before = self.sessions.len();
+        let ttl = chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::MAX);
+        self.sessions
+            .retain(|session| now.signed_duration_since(session.saved_at) <= ttl);
+        before - self.sessions.len()
+    }
+}
+
+/// Saves and restores the templates of a [`FlowDecoder`] to a JSON file
+#[derive(Debug, Clone)]
+pub struct TemplateStore {
+    path: PathBuf,
+    ttl: Duration,
+}
+
+impl TemplateStore {
+    pub fn new<P: Into<PathBuf>>(path: P, ttl: Duration) -> Self {
+        Self {
+            path: path.into(),
+            ttl,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// How long saved templates are trusted, see [`DEFAULT_TEMPLATE_TTL`]
+    pub const fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Write the templates of `decoder`. The file is replaced atomically, so a
+    /// crash while saving leaves the previous snapshot intact.
+    pub fn save(&self, decoder: &FlowDecoder) -> std::io::Result<()> {
+        self.save_snapshot(&TemplatesSnapshot::from_decoder(decoder, Utc::now()))
+    }
+
+    pub fn save_snapshot(&self, snapshot: &TemplatesSnapshot) -> std::io::Result<()> {
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        serde_json::to_writer(&mut writer, snapshot)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        std::fs::rename(&tmp_path, &self.path)
+    }
+
+    /// Read the saved templates, dropping the expired ones. An empty snapshot
+    /// is returned when nothing is saved yet.
+    pub fn load(&self, now: DateTime<Utc>) -> std::io::Result<TemplatesSnapshot> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(TemplatesSnapshot::default())
+            }
+            Err(err) => return Err(err),
+        };
+        let mut snapshot: TemplatesSnapshot = serde_json::from_reader(BufReader::new(file))?;
+        let expired = snapshot.expire(self.ttl, now);
+        if expired > 0 {
+            tracing::info!(
+                path = %self.path.display(),
+                expired,
+                "discarded expired templates"
+            );
+        }
+        Ok(snapshot)
+    }
+
+    /// Start the sessions of `decoder` with the saved templates that are not
+    /// expired, returns the number of restored sessions
+    pub fn restore(&self, decoder: &mut FlowDecoder) -> std::io::Result<usize> {
+        let snapshot = self.load(Utc::now())?;
+        Ok(snapshot
+            .sessions
+            .into_iter()
+            .map(|session| decoder.restore(session.exporter, session.templates, session.saved_at))
+            .filter(|restored| *restored)
+            .count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+    use netgauze_flow_pkt::{test_vectors::ipfix_packets, FlowInfo};
+
+    #[test]
+    fn test_template_store() {
+        let vectors = ipfix_packets();
+        let (template, data) = (&vectors[0], &vectors[1]);
+        let exporter: SocketAddr = "192.0.2.1:4739".parse().unwrap();
+        let path = std::env::temp_dir().join(format!(
+            "netgauze-template-store-{}.json",
+            std::process::id()
+        ));
+        let store = TemplateStore::new(&path, DEFAULT_TEMPLATE_TTL);
+
+        let mut decoder = FlowDecoder::new();
+        assert_eq!(store.restore(&mut decoder).unwrap(), 0);
+        decoder
+            .decode(exporter, BytesMut::from(template.wire().as_slice()))
+            .unwrap();
+        store.save(&decoder).unwrap();
+
+        // The data set is decoded by a new decoder without the template packet
+        let mut decoder = FlowDecoder::new();
+        assert_eq!(store.restore(&mut decoder).unwrap(), 1);
+        let saved_at = decoder.restored_at(&exporter).unwrap();
+        assert_eq!(
+            decoder
+                .decode(exporter, BytesMut::from(data.wire().as_slice()))
+                .unwrap(),
+            vec![FlowInfo::IPFIX(data.expected().clone())]
+        );
+        assert_eq!(decoder.restored_at(&exporter), None);
+
+        // Expired templates aren't restored
+        let later = saved_at + chrono::Duration::seconds(DEFAULT_TEMPLATE_TTL.as_secs() as i64 + 1);
+        assert!(store.load(later).unwrap().sessions.is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -41,6 +41,7 @@
 //! );
 //! ```
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use strum_macros::{Display, FromRepr};
 
@@ -56,7 +57,8 @@ use strum_macros::{Display, FromRepr};
 /// assert_eq!(undefined, Err(UndefinedAddressFamily(65000)));
 /// ```
 #[repr(u16)]
-#[derive(FromRepr, Display, Hash, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(FromRepr, Display, Hash, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum AddressFamily {
     IPv4 = 1,
@@ -182,7 +184,8 @@ pub enum AddressFamily {
 /// Error type used in [`TryFrom`] for [`AddressFamily`].
 /// The value carried is the undefined value being parsed
 #[repr(C)]
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UndefinedAddressFamily(pub u16);
 
 impl From<AddressFamily> for u16 {
@@ -216,7 +219,8 @@ impl TryFrom<u16> for AddressFamily {
 /// assert_eq!(undefined, Err(UndefinedSubsequentAddressFamily(0)));
 /// ```
 #[repr(u8)]
-#[derive(FromRepr, Display, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(FromRepr, Display, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum SubsequentAddressFamily {
     /// Network Layer Reachability Information used for unicast forwarding
@@ -374,7 +378,8 @@ pub enum SubsequentAddressFamily {
 /// Error type used in [`TryFrom`] for [`SubsequentAddressFamily`].
 /// The value carried is the undefined value being parsed
 #[repr(C)]
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UndefinedSubsequentAddressFamily(pub u8);
 
 impl From<SubsequentAddressFamily> for u8 {
@@ -397,7 +402,8 @@ impl TryFrom<u8> for SubsequentAddressFamily {
 /// Since not all [`AddressFamily`] and [`SubsequentAddressFamily`] are valid
 /// combinations, this enum defines a set of valid combination to ensure only
 /// valid AFI/SAFI are used at compile time.
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum AddressType {
     Ipv4Unicast,
@@ -424,7 +430,8 @@ pub enum AddressType {
 
 /// Error type used in [`TryFrom`] for [`AddressType`].
 /// The value carried is the undefined value being parsed
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct InvalidAddressType {
     address_family: AddressFamily,
     subsequent_address_family: SubsequentAddressFamily,
@@ -558,6 +565,21 @@ impl AddressType {
     }
 }
 
+/// Address types are ordered by their numeric AFI then SAFI values, as they're
+/// sorted in the IANA registries
+impl PartialOrd for AddressType {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AddressType {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.address_family(), self.subsequent_address_family())
+            .cmp(&(other.address_family(), other.subsequent_address_family()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
@@ -701,6 +723,30 @@ mod tests {
             SubsequentAddressFamily::BgpLs
         );
     }
+    #[test]
+    fn test_address_type_ord() {
+        let mut address_types = vec![
+            AddressType::BgpLs,
+            AddressType::Ipv6Unicast,
+            AddressType::RouteTargetConstrains,
+            AddressType::Ipv4Unicast,
+            AddressType::Ipv4MplsLabeledVpn,
+        ];
+        address_types.sort();
+        assert_eq!(
+            address_types,
+            vec![
+                AddressType::Ipv4Unicast,
+                AddressType::Ipv4MplsLabeledVpn,
+                AddressType::RouteTargetConstrains,
+                AddressType::Ipv6Unicast,
+                AddressType::BgpLs,
+            ]
+        );
+        assert!(AddressFamily::IPv4 < AddressFamily::IPv6);
+        assert!(SubsequentAddressFamily::Unicast < SubsequentAddressFamily::Multicast);
+    }
+
     #[test]
     fn test_address_type_try_from() {
         let invalid = AddressType::from_afi_safi(
@@ -16,6 +16,11 @@
 //! Generate Rust code for the given Netflow/IPFIX definitions
 use crate::{InformationElement, SimpleRegistry, Xref};
 
+/// Serde derives are only generated when the `serde` feature of the crate
+/// including the generated code is enabled
+const SERDE_DERIVE: &str =
+    "#[cfg_attr(feature = \"serde\", derive(serde::Serialize, serde::Deserialize))]\n";
+
 fn generate_derive(num_enum: bool, copy: bool, eq: bool) -> String {
     let mut base = "".to_string();
     if num_enum {
@@ -27,8 +32,8 @@ fn generate_derive(num_enum: bool, copy: bool, eq: bool) -> String {
     if eq {
         base.push_str("Eq, ");
     }
-    base.push_str("Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize");
-    format!("#[derive({base})]\n")
+    base.push_str("Clone, PartialEq, Debug");
+    format!("#[derive({base})]\n{SERDE_DERIVE}")
 }
 
 /// Convert [Xref] to markdown link
@@ -474,6 +479,7 @@ fn generate_ie_field_enum_for_ie(
         ret.push_str(format!("    {}({}),\n", ie.name, ie.name).as_str());
     }
     ret.push_str("}\n\n");
+    ret.push_str(generate_field_json_value(iana_ies, vendors, true).as_str());
     ret
 }
 
@@ -628,9 +634,10 @@ fn generate_ie_value_converters(rust_type: &str, ie_name: &String) -> String {
 fn get_std_deserializer_error(ty_name: &str) -> String {
     let mut ret = String::new();
     ret.push_str("#[allow(non_camel_case_types)]\n");
-    ret.push_str("#[derive(netgauze_serde_macros::LocatedError, Eq, PartialEq, Clone, Debug, serde::Serialize, serde::Deserialize)]\n");
+    ret.push_str("#[derive(netgauze_serde_macros::LocatedError, Eq, PartialEq, Clone, Debug)]\n");
+    ret.push_str(SERDE_DERIVE);
     ret.push_str(format!("pub enum {ty_name}ParsingError {{\n").as_str());
-    ret.push_str("    #[serde(with = \"netgauze_parse_utils::ErrorKindSerdeDeref\")]\n");
+    ret.push_str("    #[cfg_attr(feature = \"serde\", serde(with = \"netgauze_parse_utils::ErrorKindSerdeDeref\"))]\n");
     ret.push_str("    NomError(#[from_nom] nom::error::ErrorKind),\n");
     ret.push_str("    InvalidLength(u16),\n");
     ret.push_str("}\n\n");
@@ -640,9 +647,10 @@ fn get_std_deserializer_error(ty_name: &str) -> String {
 fn get_time_millis_deserializer_error(ty_name: &str) -> String {
     let mut ret = String::new();
     ret.push_str("#[allow(non_camel_case_types)]\n");
-    ret.push_str("#[derive(netgauze_serde_macros::LocatedError, Eq, PartialEq, Clone, Debug, serde::Serialize, serde::Deserialize)]\n");
+    ret.push_str("#[derive(netgauze_serde_macros::LocatedError, Eq, PartialEq, Clone, Debug)]\n");
+    ret.push_str(SERDE_DERIVE);
     ret.push_str(format!("pub enum {ty_name}ParsingError {{\n").as_str());
-    ret.push_str("    #[serde(with = \"netgauze_parse_utils::ErrorKindSerdeDeref\")]\n");
+    ret.push_str("    #[cfg_attr(feature = \"serde\", serde(with = \"netgauze_parse_utils::ErrorKindSerdeDeref\"))]\n");
     ret.push_str("    NomError(#[from_nom] nom::error::ErrorKind),\n");
     ret.push_str("    InvalidLength(u16),\n");
     ret.push_str("    InvalidTimestampMillis(u64),\n");
@@ -653,9 +661,10 @@ fn get_time_millis_deserializer_error(ty_name: &str) -> String {
 fn get_timestamp_deserializer_error(ty_name: &str) -> String {
     let mut ret = String::new();
     ret.push_str("#[allow(non_camel_case_types)]\n");
-    ret.push_str("#[derive(netgauze_serde_macros::LocatedError, Eq, PartialEq, Clone, Debug, serde::Serialize, serde::Deserialize)]\n");
+    ret.push_str("#[derive(netgauze_serde_macros::LocatedError, Eq, PartialEq, Clone, Debug)]\n");
+    ret.push_str(SERDE_DERIVE);
     ret.push_str(format!("pub enum {ty_name}ParsingError {{\n").as_str());
-    ret.push_str("    #[serde(with = \"netgauze_parse_utils::ErrorKindSerdeDeref\")]\n");
+    ret.push_str("    #[cfg_attr(feature = \"serde\", serde(with = \"netgauze_parse_utils::ErrorKindSerdeDeref\"))]\n");
     ret.push_str("    NomError(#[from_nom] nom::error::ErrorKind),\n");
     ret.push_str("    InvalidLength(u16),\n");
     ret.push_str("    InvalidTimestamp(u32),\n");
@@ -666,9 +675,10 @@ fn get_timestamp_deserializer_error(ty_name: &str) -> String {
 fn get_timestamp_fraction_deserializer_error(ty_name: &str) -> String {
     let mut ret = String::new();
     ret.push_str("#[allow(non_camel_case_types)]\n");
-    ret.push_str("#[derive(netgauze_serde_macros::LocatedError, Eq, PartialEq, Clone, Debug, serde::Serialize, serde::Deserialize)]\n");
+    ret.push_str("#[derive(netgauze_serde_macros::LocatedError, Eq, PartialEq, Clone, Debug)]\n");
+    ret.push_str(SERDE_DERIVE);
     ret.push_str(format!("pub enum {ty_name}ParsingError {{\n").as_str());
-    ret.push_str("    #[serde(with = \"netgauze_parse_utils::ErrorKindSerdeDeref\")]\n");
+    ret.push_str("    #[cfg_attr(feature = \"serde\", serde(with = \"netgauze_parse_utils::ErrorKindSerdeDeref\"))]\n");
     ret.push_str("    NomError(#[from_nom] nom::error::ErrorKind),\n");
     ret.push_str("    InvalidLength(u16),\n");
     ret.push_str("    InvalidTimestamp(u32, u32),\n");
@@ -930,9 +940,11 @@ fn generate_string_deserializer(ie_name: &String) -> String {
     let header = get_deserializer_header(ie_name.as_str());
     let mut string_error = String::new();
     string_error.push_str("#[allow(non_camel_case_types)]\n");
-    string_error.push_str("#[derive(netgauze_serde_macros::LocatedError, Eq, PartialEq, Clone, Debug, serde::Serialize, serde::Deserialize)]\n");
+    string_error
+        .push_str("#[derive(netgauze_serde_macros::LocatedError, Eq, PartialEq, Clone, Debug)]\n");
+    string_error.push_str(SERDE_DERIVE);
     string_error.push_str(format!("pub enum {ie_name}ParsingError {{\n").as_str());
-    string_error.push_str("    #[serde(with = \"netgauze_parse_utils::ErrorKindSerdeDeref\")]\n");
+    string_error.push_str("    #[cfg_attr(feature = \"serde\", serde(with = \"netgauze_parse_utils::ErrorKindSerdeDeref\"))]\n");
     string_error.push_str("    NomError(#[from_nom] nom::error::ErrorKind),\n");
     string_error.push_str("    Utf8Error(String),\n");
     string_error.push_str("}\n\n");
@@ -954,19 +966,7 @@ fn generate_string_deserializer(ie_name: &String) -> String {
     ret.push_str(header.as_str());
 
     ret.push_str("        if length == u16::MAX {\n");
-    ret.push_str("            let (buf, short_length) = nom::number::complete::be_u8(buf)?;\n");
-    ret.push_str("            let (buf, variable_length) = if short_length == u8::MAX {\n");
-    ret.push_str("                let mut variable_length: u32= 0;\n");
-    ret.push_str("                let (buf, part1) = nom::number::complete::be_u8(buf)?;\n");
-    ret.push_str("                let (buf, part2) = nom::number::complete::be_u8(buf)?;\n");
-    ret.push_str("                let (buf, part3) = nom::number::complete::be_u8(buf)?;\n");
-    ret.push_str("                variable_length = (variable_length << 8) + part1  as u32;\n");
-    ret.push_str("                variable_length = (variable_length << 8) + part2  as u32;\n");
-    ret.push_str("                variable_length = (variable_length << 8) + part3  as u32;\n");
-    ret.push_str("                (buf, variable_length)\n");
-    ret.push_str("            } else {\n");
-    ret.push_str("                (buf, short_length as u32)\n");
-    ret.push_str("            };\n");
+    ret.push_str(format!("            let (buf, variable_length) = crate::wire::deserializer::parse_variable_length::<Located{ie_name}ParsingError<'_>>(buf)?;\n").as_str());
     ret.push_str("            let (buf, value) = nom::combinator::map_res(nom::bytes::complete::take(variable_length), |str_buf: netgauze_parse_utils::Span<'_>| {\n");
     ret.push_str("                let result = ::std::str::from_utf8(&str_buf);\n");
     ret.push_str("                result.map(|x| x.to_string())\n");
@@ -1102,6 +1102,11 @@ fn generate_vec_u8_deserializer(ie_name: &String) -> String {
     let header = get_deserializer_header(ie_name.as_str());
     ret.push_str(std_error.as_str());
     ret.push_str(header.as_str());
+    ret.push_str("        let (buf, length) = if length == u16::MAX {\n");
+    ret.push_str(format!("            crate::wire::deserializer::parse_variable_length::<Located{ie_name}ParsingError<'_>>(buf)?\n").as_str());
+    ret.push_str("        } else {\n");
+    ret.push_str("            (buf, length)\n");
+    ret.push_str("        };\n");
     ret.push_str("        let (buf, value) = nom::multi::count(nom::number::complete::be_u8, length as usize)(buf)?;\n");
     ret.push_str(format!("        Ok((buf, {ie_name}(value)))\n").as_str());
     ret.push_str("    }\n");
@@ -1240,7 +1245,8 @@ pub(crate) fn generate_fields_enum(ies: &Vec<InformationElement>) -> String {
     for ie in ies {
         ret.push_str(format!("    {}({}),\n", ie.name, ie.name).as_str());
     }
-    ret.push_str("}\n");
+    ret.push_str("}\n\n");
+    ret.push_str(generate_field_json_value(ies, &vec![], false).as_str());
     ret
 }
 
@@ -1272,6 +1278,94 @@ fn get_rust_type(data_type: &str) -> String {
     rust_type.to_string()
 }
 
+/// Rust expression rendering the IE `value` to a `serde_json::Value` according
+/// to its abstract data type, rather than the derived Serialize of its Rust
+/// representation
+fn get_json_value_expr(data_type: &str) -> String {
+    let expr = match data_type {
+        "unsigned8" | "unsigned16" | "unsigned32" | "unsigned64" | "signed8" | "signed16"
+        | "signed32" | "signed64" | "float32" | "float64" | "boolean" => {
+            "serde_json::Value::from(value.0)"
+        }
+        "string" => "serde_json::Value::from(value.0.as_str())",
+        "macAddress" => "crate::ie::json::mac_address(&value.0)",
+        "dateTimeSeconds" => {
+            "serde_json::Value::from(value.0.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))"
+        }
+        "dateTimeMilliseconds" => {
+            "serde_json::Value::from(value.0.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))"
+        }
+        "dateTimeMicroseconds" => {
+            "serde_json::Value::from(value.0.to_rfc3339_opts(chrono::SecondsFormat::Micros, true))"
+        }
+        "dateTimeNanoseconds" => {
+            "serde_json::Value::from(value.0.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true))"
+        }
+        "ipv4Address" | "ipv6Address" => "serde_json::Value::from(value.0.to_string())",
+        "basicList" => "crate::ie::json::basic_list(&value.0)",
+        "subTemplateList" => "crate::ie::json::sub_template_list(&value.0)",
+        "subTemplateMultiList" => "crate::ie::json::sub_template_multi_list(&value.0)",
+        // Octet arrays, and any abstract type without a dedicated rendering, are kept
+        // as hex encoded octets
+        _ => "crate::ie::json::octets(&value.0)",
+    };
+    expr.to_string()
+}
+
+/// Generate the `name` and `to_json_value` methods of a `Field` enum, the
+/// `Unknown` and vendor variants are generated when `main` is set
+fn generate_field_json_value(
+    ies: &Vec<InformationElement>,
+    vendors: &Vec<(String, String, u32)>,
+    main: bool,
+) -> String {
+    let mut ret = String::new();
+    ret.push_str("impl Field {\n");
+    ret.push_str("    /// Name of the Information Element of the field\n");
+    ret.push_str("    pub const fn name(&self) -> &'static str {\n");
+    ret.push_str("        match self {\n");
+    if main {
+        ret.push_str("            Self::Unknown(_) => \"Unknown\",\n");
+    }
+    for (name, _, _) in vendors {
+        ret.push_str(format!("            Self::{name}(value) => value.name(),\n").as_str());
+    }
+    for ie in ies {
+        ret.push_str(format!("            Self::{}(_) => \"{}\",\n", ie.name, ie.name).as_str());
+    }
+    ret.push_str("        }\n");
+    ret.push_str("    }\n\n");
+
+    ret.push_str("    /// Render the value according to the abstract data type of its\n");
+    ret.push_str("    /// Information Element: numbers, booleans, strings for addresses,\n");
+    ret.push_str("    /// RFC3339 timestamps and hex encoded octets, and arrays for lists\n");
+    ret.push_str("    #[cfg(feature = \"serde\")]\n");
+    ret.push_str("    pub fn to_json_value(&self) -> serde_json::Value {\n");
+    ret.push_str("        match self {\n");
+    if main {
+        ret.push_str("            Self::Unknown(value) => crate::ie::json::octets(value),\n");
+    }
+    for (name, _, _) in vendors {
+        ret.push_str(
+            format!("            Self::{name}(value) => value.to_json_value(),\n").as_str(),
+        );
+    }
+    for ie in ies {
+        ret.push_str(
+            format!(
+                "            Self::{}(value) => {},\n",
+                ie.name,
+                get_json_value_expr(&ie.data_type)
+            )
+            .as_str(),
+        );
+    }
+    ret.push_str("        }\n");
+    ret.push_str("    }\n");
+    ret.push_str("}\n\n");
+    ret
+}
+
 pub(crate) fn generate_ie_values(ies: &Vec<InformationElement>) -> String {
     let mut ret = String::new();
     for ie in ies {
@@ -1296,9 +1390,10 @@ fn generate_ie_values_deserializers(ies: &Vec<InformationElement>) -> String {
     let mut ret = String::new();
     let ty_name = "Field";
     ret.push_str("#[allow(non_camel_case_types)]\n");
-    ret.push_str("#[derive(netgauze_serde_macros::LocatedError, Eq, PartialEq, Clone, Debug, serde::Serialize, serde::Deserialize)]\n");
+    ret.push_str("#[derive(netgauze_serde_macros::LocatedError, Eq, PartialEq, Clone, Debug)]\n");
+    ret.push_str(SERDE_DERIVE);
     ret.push_str(format!("pub enum {ty_name}ParsingError {{\n").as_str());
-    ret.push_str("    #[serde(with = \"netgauze_parse_utils::ErrorKindSerdeDeref\")]\n");
+    ret.push_str("    #[cfg_attr(feature = \"serde\", serde(with = \"netgauze_parse_utils::ErrorKindSerdeDeref\"))]\n");
     ret.push_str("    NomError(#[from_nom] nom::error::ErrorKind),\n");
     for ie in ies {
         ret.push_str(
@@ -1349,9 +1444,10 @@ pub(crate) fn generate_ie_deser_main(
 
     let ty_name = "Field";
     ret.push_str("#[allow(non_camel_case_types)]\n");
-    ret.push_str("#[derive(netgauze_serde_macros::LocatedError, Eq, PartialEq, Clone, Debug, serde::Serialize, serde::Deserialize)]\n");
+    ret.push_str("#[derive(netgauze_serde_macros::LocatedError, Eq, PartialEq, Clone, Debug)]\n");
+    ret.push_str(SERDE_DERIVE);
     ret.push_str(format!("pub enum {ty_name}ParsingError {{\n").as_str());
-    ret.push_str("    #[serde(with = \"netgauze_parse_utils::ErrorKindSerdeDeref\")]\n");
+    ret.push_str("    #[cfg_attr(feature = \"serde\", serde(with = \"netgauze_parse_utils::ErrorKindSerdeDeref\"))]\n");
     ret.push_str("    NomError(#[from_nom] nom::error::ErrorKind),\n");
     for (name, pkg, _) in vendor_prefixes {
         let value_name = format!("{pkg}::FieldParsingError");
@@ -1485,10 +1581,18 @@ fn generate_array_serializer(ie_name: &str) -> String {
         .as_str(),
     );
     ret.push_str("    const BASE_LENGTH: usize = 0;\n\n");
-    ret.push_str("     fn len(&self, _length: Option<u16>) -> usize {\n");
-    ret.push_str("         self.0.len()\n");
+    ret.push_str("     fn len(&self, length: Option<u16>) -> usize {\n");
+    ret.push_str("         match length {\n");
+    ret.push_str("             Some(u16::MAX) => self.0.len() + crate::wire::serializer::variable_length_prefix_len(self.0.len()),\n");
+    ret.push_str("             _ => self.0.len(),\n");
+    ret.push_str("         }\n");
     ret.push_str("     }\n\n");
-    ret.push_str(format!("     fn write<T:  std::io::Write>(&self, writer: &mut T, _length: Option<u16>) -> Result<(), {ie_name}WritingError> {{\n").as_str());
+    ret.push_str(format!("     fn write<T:  std::io::Write>(&self, writer: &mut T, length: Option<u16>) -> Result<(), {ie_name}WritingError> {{\n").as_str());
+    ret.push_str("         if length == Some(u16::MAX) {\n");
+    ret.push_str(
+        "             crate::wire::serializer::write_variable_length(writer, self.0.len())?;\n",
+    );
+    ret.push_str("         }\n");
     ret.push_str("         writer.write_all(&self.0)?;\n");
     ret.push_str("         Ok(())\n");
     ret.push_str("     }\n");
@@ -1532,13 +1636,7 @@ fn generate_string_serializer(ie_name: &str) -> String {
     ret.push_str("        match length {\n");
     ret.push_str("            None => self.0.len(),\n");
     ret.push_str("            Some(len) => if len == u16::MAX {\n");
-    ret.push_str("                if self.0.len() < u8::MAX as usize {\n");
-    ret.push_str("                    // One octet for the length field\n");
-    ret.push_str("                    self.0.len() + 1\n");
-    ret.push_str("                } else {\n");
-    ret.push_str("                    // 4 octets for the length field, first is 255 and other three carries the len\n");
-    ret.push_str("                    self.0.len() + 4\n");
-    ret.push_str("                }\n");
+    ret.push_str("                self.0.len() + crate::wire::serializer::variable_length_prefix_len(self.0.len())\n");
     ret.push_str("            } else {\n");
     ret.push_str("                len as usize\n");
     ret.push_str("            },\n");
@@ -1548,13 +1646,9 @@ fn generate_string_serializer(ie_name: &str) -> String {
     ret.push_str(format!("    fn write<T:  std::io::Write>(&self, writer: &mut T, length: Option<u16>) -> Result<(), {ie_name}WritingError> {{\n").as_str());
     ret.push_str("        match length {\n");
     ret.push_str("            Some(u16::MAX) | None => {\n");
-    ret.push_str("                let bytes = self.0.as_bytes();\n");
-    ret.push_str("                if bytes.len() < u8::MAX as usize {\n");
-    ret.push_str("                    writer.write_u8(bytes.len() as u8)?;\n");
-    ret.push_str("                } else {\n");
-    ret.push_str("                    writer.write_u8(u8::MAX)?;\n");
-    ret.push_str("                    writer.write_all(&bytes.len().to_be_bytes()[1..])?;\n");
-    ret.push_str("                }\n");
+    ret.push_str(
+        "                crate::wire::serializer::write_variable_length(writer, self.0.len())?;\n",
+    );
     ret.push_str("                writer.write_all(self.0.as_bytes())?;\n");
     ret.push_str("            }\n");
     ret.push_str("            Some(len) => {\n");
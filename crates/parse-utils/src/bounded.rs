@@ -0,0 +1,191 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bounds checked helpers for length fields and length prefixed values.
+//!
+//! Serializers compute lengths as `usize`, while the wire encodes them in a
+//! fixed width field. Narrowing with `as` silently truncates values that
+//! don't fit and produces a corrupted message, the helpers here refuse to
+//! write such values instead. Overflows are reported as
+//! [`std::io::ErrorKind::InvalidInput`] errors, so they surface through the
+//! `StdIOError` variant every writing error already has.
+
+use crate::Span;
+use nom::{
+    bytes::complete::take,
+    number::complete::{be_u16, be_u8},
+    IResult,
+};
+use std::io::Write;
+
+/// A length that doesn't fit in the width of its field on the wire
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct LengthOverflowError {
+    length: usize,
+    max: usize,
+}
+
+impl LengthOverflowError {
+    pub const fn new(length: usize, max: usize) -> Self {
+        Self { length, max }
+    }
+
+    /// The length that failed to fit
+    pub const fn length(&self) -> usize {
+        self.length
+    }
+
+    /// The maximum value the field can hold
+    pub const fn max(&self) -> usize {
+        self.max
+    }
+}
+
+impl std::fmt::Display for LengthOverflowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "length {} exceeds the maximum field value {}",
+            self.length, self.max
+        )
+    }
+}
+
+impl std::error::Error for LengthOverflowError {}
+
+impl From<LengthOverflowError> for std::io::Error {
+    fn from(err: LengthOverflowError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, err)
+    }
+}
+
+/// Narrow a length to a one octet field
+#[inline]
+pub fn checked_u8(length: usize) -> Result<u8, LengthOverflowError> {
+    u8::try_from(length).map_err(|_| LengthOverflowError::new(length, u8::MAX as usize))
+}
+
+/// Narrow a length to a two octets field
+#[inline]
+pub fn checked_u16(length: usize) -> Result<u16, LengthOverflowError> {
+    u16::try_from(length).map_err(|_| LengthOverflowError::new(length, u16::MAX as usize))
+}
+
+/// Narrow a length to a four octets field
+#[inline]
+pub fn checked_u32(length: usize) -> Result<u32, LengthOverflowError> {
+    u32::try_from(length).map_err(|_| LengthOverflowError::new(length, u32::MAX as usize))
+}
+
+/// Write a length as a one octet field
+#[inline]
+pub fn write_u8_length<T: Write>(writer: &mut T, length: usize) -> std::io::Result<()> {
+    writer.write_all(&[checked_u8(length)?])
+}
+
+/// Write a length as a two octets field in network byte order
+#[inline]
+pub fn write_u16_length<T: Write>(writer: &mut T, length: usize) -> std::io::Result<()> {
+    writer.write_all(&checked_u16(length)?.to_be_bytes())
+}
+
+/// Write a length as a four octets field in network byte order
+#[inline]
+pub fn write_u32_length<T: Write>(writer: &mut T, length: usize) -> std::io::Result<()> {
+    writer.write_all(&checked_u32(length)?.to_be_bytes())
+}
+
+/// Write `value` preceded by its length in one octet
+#[inline]
+pub fn write_u8_length_prefixed<T: Write>(writer: &mut T, value: &[u8]) -> std::io::Result<()> {
+    write_u8_length(writer, value.len())?;
+    writer.write_all(value)
+}
+
+/// Write `value` preceded by its length in two octets
+#[inline]
+pub fn write_u16_length_prefixed<T: Write>(writer: &mut T, value: &[u8]) -> std::io::Result<()> {
+    write_u16_length(writer, value.len())?;
+    writer.write_all(value)
+}
+
+/// Read a value preceded by its length in one octet. Fails with
+/// [`nom::Err::Error`] when the buffer is shorter than the encoded length.
+#[inline]
+pub fn parse_u8_length_prefixed<'a, E: nom::error::ParseError<Span<'a>>>(
+    buf: Span<'a>,
+) -> IResult<Span<'a>, Span<'a>, E> {
+    let (buf, length) = be_u8(buf)?;
+    take(length)(buf)
+}
+
+/// Read a value preceded by its length in two octets. Fails with
+/// [`nom::Err::Error`] when the buffer is shorter than the encoded length.
+#[inline]
+pub fn parse_u16_length_prefixed<'a, E: nom::error::ParseError<Span<'a>>>(
+    buf: Span<'a>,
+) -> IResult<Span<'a>, Span<'a>, E> {
+    let (buf, length) = be_u16(buf)?;
+    take(length)(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_narrowing() {
+        assert_eq!(checked_u8(255), Ok(255));
+        assert_eq!(checked_u8(256), Err(LengthOverflowError::new(256, 255)));
+        assert_eq!(checked_u16(65535), Ok(65535));
+        assert_eq!(
+            checked_u16(65536),
+            Err(LengthOverflowError::new(65536, 65535))
+        );
+        assert_eq!(checked_u32(65536), Ok(65536));
+    }
+
+    #[test]
+    fn test_write_length_prefixed() {
+        let mut buf = vec![];
+        write_u8_length_prefixed(&mut buf, &[0xaa, 0xbb]).unwrap();
+        write_u16_length_prefixed(&mut buf, &[0xcc]).unwrap();
+        assert_eq!(buf, vec![0x02, 0xaa, 0xbb, 0x00, 0x01, 0xcc]);
+    }
+
+    #[test]
+    fn test_write_length_overflow() {
+        let mut buf = vec![];
+        let err = write_u8_length_prefixed(&mut buf, &[0; 256]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        // Nothing is written when the length doesn't fit
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_parse_length_prefixed() {
+        let input = [0x00, 0x02, 0xaa, 0xbb, 0xcc];
+        let (rest, value) =
+            parse_u16_length_prefixed::<nom::error::Error<Span<'_>>>(Span::new(&input)).unwrap();
+        assert_eq!(*value.fragment(), &[0xaa, 0xbb]);
+        assert_eq!(*rest.fragment(), &[0xcc]);
+
+        let truncated = [0x03, 0xaa];
+        assert!(matches!(
+            parse_u8_length_prefixed::<nom::error::Error<Span<'_>>>(Span::new(&truncated)),
+            Err(nom::Err::Error(_))
+        ));
+    }
+}
@@ -0,0 +1,205 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Self-describing container bundling raw protocol bytes with the parsing
+//! state they depend on, e.g., the negotiated BGP capabilities of a BMP peer
+//! or the templates of an IPFIX exporter. A captured message can then be
+//! decoded again in isolation, without replaying the whole session from its
+//! start.
+//!
+//! The container is protocol agnostic, each protocol crate serializes its
+//! own parsing state into the context. All integers are in network byte
+//! order:
+//!
+//! ```text
+//! +--------+---------+------+----------------+---------+-------------+------+
+//! | "NGZC" | version | kind | context length | context | data length | data |
+//! |   4    |    1    |  1   |       4        |    n    |      4      |  m   |
+//! +--------+---------+------+----------------+---------+-------------+------+
+//! ```
+
+use std::io::{Read, Write};
+
+/// First bytes of every capture
+pub const CAPTURE_MAGIC: [u8; 4] = *b"NGZC";
+
+/// Version of the container layout written by [`Capture::write`]
+pub const CAPTURE_VERSION: u8 = 1;
+
+/// Protocol of the captured bytes, which defines how the context is encoded
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum CaptureKind {
+    Bmp,
+    Flow,
+}
+
+impl CaptureKind {
+    pub const fn code(&self) -> u8 {
+        match self {
+            Self::Bmp => 1,
+            Self::Flow => 2,
+        }
+    }
+
+    pub const fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(Self::Bmp),
+            2 => Some(Self::Flow),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum CaptureError {
+    IoError(String),
+    /// The input doesn't start with [`CAPTURE_MAGIC`]
+    InvalidMagic([u8; 4]),
+    UnsupportedVersion(u8),
+    UndefinedKind(u8),
+    /// The capture is of another protocol than the one being read
+    UnexpectedKind(CaptureKind),
+    /// The context can't be encoded or decoded by the protocol crate
+    ContextError(String),
+}
+
+impl From<std::io::Error> for CaptureError {
+    fn from(error: std::io::Error) -> Self {
+        Self::IoError(error.to_string())
+    }
+}
+
+impl std::fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+/// Raw bytes along with the serialized parsing state needed to decode them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    kind: CaptureKind,
+    context: Vec<u8>,
+    data: Vec<u8>,
+}
+
+impl Capture {
+    pub const fn new(kind: CaptureKind, context: Vec<u8>, data: Vec<u8>) -> Self {
+        Self {
+            kind,
+            context,
+            data,
+        }
+    }
+
+    pub const fn kind(&self) -> CaptureKind {
+        self.kind
+    }
+
+    /// Parsing state, encoded by the protocol crate
+    pub fn context(&self) -> &[u8] {
+        &self.context
+    }
+
+    /// Captured bytes, exactly as received on the wire
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), CaptureError> {
+        writer.write_all(&CAPTURE_MAGIC)?;
+        writer.write_all(&[CAPTURE_VERSION, self.kind.code()])?;
+        for section in [&self.context, &self.data] {
+            let len = u32::try_from(section.len()).map_err(|_| {
+                CaptureError::IoError(format!("section of {} bytes is too large", section.len()))
+            })?;
+            writer.write_all(&len.to_be_bytes())?;
+            writer.write_all(section)?;
+        }
+        Ok(())
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, CaptureError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != CAPTURE_MAGIC {
+            return Err(CaptureError::InvalidMagic(magic));
+        }
+        let mut header = [0u8; 2];
+        reader.read_exact(&mut header)?;
+        if header[0] != CAPTURE_VERSION {
+            return Err(CaptureError::UnsupportedVersion(header[0]));
+        }
+        let kind =
+            CaptureKind::from_code(header[1]).ok_or(CaptureError::UndefinedKind(header[1]))?;
+        let context = read_section(reader)?;
+        let data = read_section(reader)?;
+        Ok(Self::new(kind, context, data))
+    }
+}
+
+fn read_section<R: Read>(reader: &mut R) -> Result<Vec<u8>, CaptureError> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len) as u64;
+    let mut section = vec![];
+    // Don't trust the length for the allocation, truncated inputs fail below
+    reader.take(len).read_to_end(&mut section)?;
+    if section.len() as u64 != len {
+        return Err(CaptureError::IoError(format!(
+            "expected {len} bytes, found {}",
+            section.len()
+        )));
+    }
+    Ok(section)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture() {
+        let capture = Capture::new(CaptureKind::Bmp, b"{}".to_vec(), vec![3, 0, 0, 0, 6, 4]);
+        let mut buf = vec![];
+        capture.write(&mut buf).unwrap();
+        assert_eq!(
+            buf,
+            vec![
+                b'N', b'G', b'Z', b'C', 1, 1, 0, 0, 0, 2, b'{', b'}', 0, 0, 0, 6, 3, 0, 0, 0, 6, 4
+            ]
+        );
+        assert_eq!(Capture::read(&mut buf.as_slice()), Ok(capture));
+
+        assert_eq!(
+            Capture::read(&mut &buf[..buf.len() - 1]),
+            Err(CaptureError::IoError(
+                "expected 6 bytes, found 5".to_string()
+            ))
+        );
+        buf[4] = 2;
+        assert_eq!(
+            Capture::read(&mut buf.as_slice()),
+            Err(CaptureError::UnsupportedVersion(2))
+        );
+        buf[0] = b'X';
+        assert_eq!(
+            Capture::read(&mut buf.as_slice()),
+            Err(CaptureError::InvalidMagic(*b"XGZC"))
+        );
+    }
+}
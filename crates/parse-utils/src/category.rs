@@ -0,0 +1,99 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Coarse categories of the parsing and writing errors.
+//!
+//! [`Categorize`] is derived along with `LocatedError` and `WritingError` of
+//! `netgauze-serde-macros`:
+//! - `#[from_nom]` variants are categorized by their nom
+//!   [`nom::error::ErrorKind`], see [`nom_error_category`].
+//! - `#[from_located]` and `#[from]` variants take the category of the error
+//!   they carry.
+//! - `#[from_std_io_error]` variants are [`ErrorCategory::Io`].
+//! - Any other variant is a [`ErrorCategory::SemanticViolation`], unless
+//!   annotated otherwise, e.g., `#[category(UnknownCode)]`.
+
+use std::fmt::{Display, Formatter};
+
+use nom::error::ErrorKind;
+use serde::{Deserialize, Serialize};
+
+/// Coarse category of an error, to decide how to handle it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ErrorCategory {
+    /// The input ended before the end of the PDU
+    Truncated,
+    /// A type or code that is not defined in the relevant registry, or not
+    /// known to NetGauze
+    UnknownCode,
+    /// The input is malformed or violates the protocol
+    SemanticViolation,
+    /// A known, but not supported, feature such as a protocol version
+    Unsupported,
+    /// Reading from or writing to the underlying I/O failed
+    Io,
+}
+
+impl Display for ErrorCategory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// Errors that can be mapped into an [`ErrorCategory`]
+pub trait Categorize {
+    fn category(&self) -> ErrorCategory;
+
+    /// Name of the variant of the error
+    fn variant(&self) -> &'static str;
+
+    /// The nested error carried by the variant, if any
+    fn cause(&self) -> Option<&dyn Categorize> {
+        None
+    }
+
+    /// The nom error kind, when the variant is raised by nom
+    fn nom_error_kind(&self) -> Option<ErrorKind> {
+        None
+    }
+}
+
+/// Running out of input is reported by nom as [`ErrorKind::Eof`] or
+/// [`ErrorKind::Complete`], any other kind is a malformed input
+pub const fn nom_error_category(kind: ErrorKind) -> ErrorCategory {
+    match kind {
+        ErrorKind::Eof | ErrorKind::Complete => ErrorCategory::Truncated,
+        _ => ErrorCategory::SemanticViolation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nom_error_category() {
+        assert_eq!(nom_error_category(ErrorKind::Eof), ErrorCategory::Truncated);
+        assert_eq!(
+            nom_error_category(ErrorKind::Complete),
+            ErrorCategory::Truncated
+        );
+        assert_eq!(
+            nom_error_category(ErrorKind::Verify),
+            ErrorCategory::SemanticViolation
+        );
+        assert_eq!(ErrorCategory::UnknownCode.to_string(), "UnknownCode");
+    }
+}
@@ -0,0 +1,205 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Structured conformance reports produced by the protocol analyzers, e.g.,
+//! when validating the messages captured from a router or a flow exporter.
+//!
+//! The report is protocol agnostic: each analyzer records [`Finding`]s
+//! identified by a stable code, and findings with the same code are counted
+//! together.
+
+use serde::{Deserialize, Serialize};
+
+/// How a finding deviates from the specifications
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum FindingKind {
+    /// A requirement of the specification is violated, including malformed
+    /// messages
+    Violation,
+    /// A code point or usage deprecated by the specifications
+    Deprecated,
+    /// A code point that is not defined, or not known to NetGauze
+    UnknownCode,
+}
+
+/// A deviation found by an analyzer, counted each time it's seen
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    kind: FindingKind,
+    code: String,
+    reference: Option<String>,
+    description: String,
+    count: u64,
+    first_seen: u64,
+}
+
+impl Finding {
+    pub const fn kind(&self) -> FindingKind {
+        self.kind
+    }
+
+    /// Stable identifier of the finding, e.g., `bmp.first-message`
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Specification defining the requirement, e.g., `RFC 7854 section 4.3`
+    pub fn reference(&self) -> Option<&str> {
+        self.reference.as_deref()
+    }
+
+    /// Description of the first occurrence
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub const fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Index of the message of the first occurrence
+    pub const fn first_seen(&self) -> u64 {
+        self.first_seen
+    }
+}
+
+/// Findings of an analyzer for a single source, e.g., a router or an exporter
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConformanceReport {
+    source: String,
+    messages: u64,
+    findings: Vec<Finding>,
+}
+
+impl ConformanceReport {
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            messages: 0,
+            findings: vec![],
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Number of messages analyzed, including the malformed ones
+    pub const fn messages(&self) -> u64 {
+        self.messages
+    }
+
+    /// Findings in the order they're first seen
+    pub fn findings(&self) -> &[Finding] {
+        &self.findings
+    }
+
+    pub fn finding(&self, code: &str) -> Option<&Finding> {
+        self.findings.iter().find(|finding| finding.code == code)
+    }
+
+    /// No finding was recorded
+    pub fn is_conformant(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    /// Count a new message, the findings recorded next are attributed to it
+    pub fn next_message(&mut self) {
+        self.messages += 1;
+    }
+
+    /// Record an occurrence of the finding `code` for the current message
+    pub fn record(
+        &mut self,
+        kind: FindingKind,
+        code: impl Into<String>,
+        reference: Option<&str>,
+        description: impl Into<String>,
+    ) {
+        self.record_count(kind, code, reference, description, 1);
+    }
+
+    /// Record `count` occurrences of the finding `code` at once, e.g., from
+    /// counters maintained by the analyzer
+    pub fn record_count(
+        &mut self,
+        kind: FindingKind,
+        code: impl Into<String>,
+        reference: Option<&str>,
+        description: impl Into<String>,
+        count: u64,
+    ) {
+        let code = code.into();
+        match self
+            .findings
+            .iter_mut()
+            .find(|finding| finding.code == code)
+        {
+            Some(finding) => finding.count += count,
+            None => self.findings.push(Finding {
+                kind,
+                code,
+                reference: reference.map(str::to_string),
+                description: description.into(),
+                count,
+                first_seen: self.messages.saturating_sub(1),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conformance_report() {
+        let mut report = ConformanceReport::new("192.0.2.1");
+        assert!(report.is_conformant());
+        report.next_message();
+        report.next_message();
+        report.record(
+            FindingKind::Violation,
+            "bmp.first-message",
+            Some("RFC 7854 section 4.3"),
+            "first message is PeerUpNotification",
+        );
+        report.next_message();
+        report.record(
+            FindingKind::UnknownCode,
+            "bgp.path-attribute.250",
+            None,
+            "a",
+        );
+        report.record(
+            FindingKind::UnknownCode,
+            "bgp.path-attribute.250",
+            None,
+            "b",
+        );
+
+        assert!(!report.is_conformant());
+        assert_eq!(report.messages(), 3);
+        assert_eq!(report.findings().len(), 2);
+        let finding = report.finding("bmp.first-message").unwrap();
+        assert_eq!(finding.reference(), Some("RFC 7854 section 4.3"));
+        assert_eq!(finding.first_seen(), 1);
+        let finding = report.finding("bgp.path-attribute.250").unwrap();
+        assert_eq!(finding.kind(), FindingKind::UnknownCode);
+        assert_eq!(finding.count(), 2);
+        assert_eq!(finding.description(), "a");
+        assert_eq!(finding.first_seen(), 2);
+    }
+}
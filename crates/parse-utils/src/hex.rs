@@ -0,0 +1,72 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hex strings as pasted from packet dissectors and hex dumps, e.g., in the
+//! wasm bindings or the YAML test vectors.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HexParsingError {
+    InvalidDigit(char),
+    OddLength,
+}
+
+impl std::fmt::Display for HexParsingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::error::Error for HexParsingError {}
+
+/// Parse a hex string, ignoring the separators used by packet dissectors and
+/// hex dumps, i.e., white spaces, `:`, `-`, `,`, and `0x` prefixes.
+pub fn parse_hex(input: &str) -> Result<Vec<u8>, HexParsingError> {
+    let mut digits = vec![];
+    for token in input.split(|c: char| c.is_whitespace() || matches!(c, ':' | '-' | ',')) {
+        let token = token
+            .strip_prefix("0x")
+            .or_else(|| token.strip_prefix("0X"))
+            .unwrap_or(token);
+        for c in token.chars() {
+            let digit = c.to_digit(16).ok_or(HexParsingError::InvalidDigit(c))?;
+            digits.push(digit as u8);
+        }
+    }
+    if digits.len() % 2 != 0 {
+        return Err(HexParsingError::OddLength);
+    }
+    Ok(digits
+        .chunks_exact(2)
+        .map(|pair| (pair[0] << 4) | pair[1])
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(parse_hex(""), Ok(vec![]));
+        assert_eq!(parse_hex("ff0013 04"), Ok(vec![0xff, 0x00, 0x13, 0x04]));
+        assert_eq!(parse_hex("FF:00:13:04"), Ok(vec![0xff, 0x00, 0x13, 0x04]));
+        assert_eq!(
+            parse_hex("0xff, 0x00,\n0x13, 0x04"),
+            Ok(vec![0xff, 0x00, 0x13, 0x04])
+        );
+        assert_eq!(parse_hex("ff-0"), Err(HexParsingError::OddLength));
+        assert_eq!(parse_hex("fg"), Err(HexParsingError::InvalidDigit('g')));
+    }
+}
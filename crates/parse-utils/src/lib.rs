@@ -15,8 +15,19 @@
 
 //! Traits for Ser/Deser wire protocols
 
+pub mod bounded;
+pub mod capture;
+pub mod category;
+pub mod conformance;
+pub mod hex;
+pub mod raw_frame;
 #[cfg(feature = "test-helpers")]
 pub mod test_helpers;
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
+
+pub use category::{Categorize, ErrorCategory};
+pub use hex::{parse_hex, HexParsingError};
 
 use netgauze_locate::BinarySpan;
 use nom::IResult;
@@ -61,6 +72,20 @@ pub trait ReadablePduWithThreeInputs<'a, I1, I2, I3, ErrorType> {
         Self: Sized;
 }
 
+/// Generic trait for Readable Protocol Data Unit that does need four external
+/// inputs
+pub trait ReadablePduWithFourInputs<'a, I1, I2, I3, I4, ErrorType> {
+    fn from_wire(
+        buf: Span<'a>,
+        input1: I1,
+        input2: I2,
+        input3: I3,
+        input4: I4,
+    ) -> IResult<Span<'a>, Self, ErrorType>
+    where
+        Self: Sized;
+}
+
 /// Generic trait for Writable Protocol Data Unit that doesn't need any external
 /// input while writing the packet.
 #[allow(clippy::len_without_is_empty)]
@@ -120,6 +145,41 @@ pub trait WritablePduWithTwoInputs<I1, I2, ErrorType> {
         Self: Sized;
 }
 
+/// How strings received on the wire that are not valid UTF-8 are decoded.
+///
+/// Some implementations send free-form strings in Latin-1 or with broken
+/// UTF-8 sequences, this allows to still parse the messages carrying them.
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub enum StringDecodingPolicy {
+    /// Fail parsing with the UTF-8 error
+    #[default]
+    Strict,
+
+    /// Replace invalid sequences with `U+FFFD REPLACEMENT CHARACTER`
+    Lossy,
+
+    /// Keep the raw bytes, the parsed type must have a representation for
+    /// them
+    Raw,
+}
+
+impl StringDecodingPolicy {
+    /// Decode `value` as UTF-8 according to this policy. `Ok(None)` is
+    /// returned when the raw bytes are to be kept.
+    pub fn decode(&self, value: &[u8]) -> Result<Option<String>, std::string::FromUtf8Error> {
+        match String::from_utf8(value.to_vec()) {
+            Ok(str) => Ok(Some(str)),
+            Err(err) => match self {
+                Self::Strict => Err(err),
+                Self::Lossy => Ok(Some(String::from_utf8_lossy(err.as_bytes()).into_owned())),
+                Self::Raw => Ok(None),
+            },
+        }
+    }
+}
+
 /// Located Parsing error is the error raised by parsing a given buffer and a
 /// reference to the location where it occurred. The offset of the buffer in the
 /// [`Span`] should refer (as much as possible) to the first byte where the
@@ -215,6 +275,33 @@ pub fn parse_into_located_three_inputs<
     }
 }
 
+#[inline]
+pub fn parse_into_located_four_inputs<
+    'a,
+    I1,
+    I2,
+    I3,
+    I4,
+    Lin: Debug,
+    L: From<Lin>,
+    T: ReadablePduWithFourInputs<'a, I1, I2, I3, I4, Lin>,
+>(
+    buf: Span<'a>,
+    input1: I1,
+    input2: I2,
+    input3: I3,
+    input4: I4,
+) -> IResult<Span<'a>, T, L> {
+    match T::from_wire(buf, input1, input2, input3, input4) {
+        Ok((buf, value)) => Ok((buf, value)),
+        Err(err) => match err {
+            nom::Err::Incomplete(needed) => Err(nom::Err::Incomplete(needed)),
+            nom::Err::Error(error) => Err(nom::Err::Error(error.into())),
+            nom::Err::Failure(failure) => Err(nom::Err::Failure(failure.into())),
+        },
+    }
+}
+
 /// Keep repeating the parser till the buf is empty
 #[inline]
 pub fn parse_till_empty<'a, T: ReadablePdu<'a, E>, E: Debug>(
@@ -230,6 +317,28 @@ pub fn parse_till_empty<'a, T: ReadablePdu<'a, E>, E: Debug>(
     Ok((buf, ret))
 }
 
+/// Keep repeating the parser till the buf is empty, collecting the parsed
+/// elements into `C`, e.g., a `SmallVec` instead of a [`Vec`]
+#[inline]
+pub fn parse_till_empty_into_collection_located<
+    'a,
+    Lin: Debug,
+    L: From<Lin>,
+    T: ReadablePdu<'a, Lin>,
+    C: Default + Extend<T>,
+>(
+    buf: Span<'a>,
+) -> IResult<Span<'a>, C, L> {
+    let mut buf = buf;
+    let mut ret = C::default();
+    while !buf.is_empty() {
+        let (tmp, element) = parse_into_located(buf)?;
+        ret.extend(std::iter::once(element));
+        buf = tmp;
+    }
+    Ok((buf, ret))
+}
+
 /// Keep repeating the parser till the buf is empty
 #[inline]
 pub fn parse_till_empty_into_located<'a, Lin: Debug, L: From<Lin>, T: ReadablePdu<'a, Lin>>(
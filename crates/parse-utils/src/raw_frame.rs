@@ -0,0 +1,210 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bounded copies of the raw frames that failed to parse, carried in the
+//! decoding errors of the codecs so they land in the logs and sinks for
+//! offline analysis.
+//!
+//! Copying and encoding every failed frame would let a misbehaving peer flood
+//! the logs, hence a [`RawFrameSampler`] only captures up to
+//! [`RawFramePolicy::max_frames`] frames per [`RawFramePolicy::interval`], each
+//! truncated to [`RawFramePolicy::max_bytes`]. The frames are base64 encoded,
+//! so they can be embedded as is in JSON or text logs.
+
+use std::{
+    fmt::{Display, Formatter},
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+// `std::time::Instant::now` panics on `wasm32-unknown-unknown`, `web_time`
+// re-exports it on the other targets
+use web_time::Instant;
+
+/// Leading bytes of a frame that failed to parse, base64 encoded
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RawFrame {
+    length: usize,
+    data: String,
+}
+
+impl RawFrame {
+    /// Capture at most `max_bytes` bytes of `frame`
+    pub fn new(frame: &[u8], max_bytes: usize) -> Self {
+        Self {
+            length: frame.len(),
+            data: STANDARD.encode(&frame[..frame.len().min(max_bytes)]),
+        }
+    }
+
+    /// Length of the whole frame, including the bytes that were not captured
+    pub const fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Base64 encoding of the captured bytes
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    /// The captured bytes, `None` if the encoding is corrupted, e.g., when the
+    /// frame is deserialized from an edited log
+    pub fn bytes(&self) -> Option<Vec<u8>> {
+        STANDARD.decode(&self.data).ok()
+    }
+
+    /// The frame is longer than the captured bytes
+    pub fn is_truncated(&self) -> bool {
+        self.bytes().is_none_or(|bytes| bytes.len() < self.length)
+    }
+}
+
+impl Display for RawFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} bytes, base64: {}", self.length, self.data)?;
+        if self.is_truncated() {
+            write!(f, "...")?;
+        }
+        Ok(())
+    }
+}
+
+/// How many failed frames are captured and how much of each. The default
+/// policy disables capturing.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RawFramePolicy {
+    max_bytes: usize,
+    max_frames: u32,
+    interval: Duration,
+}
+
+impl RawFramePolicy {
+    pub const fn new(max_bytes: usize, max_frames: u32, interval: Duration) -> Self {
+        Self {
+            max_bytes,
+            max_frames,
+            interval,
+        }
+    }
+
+    /// Max number of bytes captured from each frame
+    pub const fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Max number of frames captured per interval
+    pub const fn max_frames(&self) -> u32 {
+        self.max_frames
+    }
+
+    pub const fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub const fn is_enabled(&self) -> bool {
+        self.max_bytes > 0 && self.max_frames > 0
+    }
+}
+
+/// Captures the failed frames within the limits of a [`RawFramePolicy`]
+#[derive(Debug, Default)]
+pub struct RawFrameSampler {
+    policy: RawFramePolicy,
+    window_start: Option<Instant>,
+    captured: u32,
+    suppressed: u64,
+}
+
+impl RawFrameSampler {
+    pub fn new(policy: RawFramePolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    pub const fn policy(&self) -> RawFramePolicy {
+        self.policy
+    }
+
+    /// Number of frames not captured so far because of the rate limit
+    pub const fn suppressed(&self) -> u64 {
+        self.suppressed
+    }
+
+    /// Capture `frame` unless capturing is disabled or the rate limit is
+    /// reached
+    pub fn capture(&mut self, frame: &[u8]) -> Option<RawFrame> {
+        self.capture_at(frame, Instant::now())
+    }
+
+    pub fn capture_at(&mut self, frame: &[u8], now: Instant) -> Option<RawFrame> {
+        if !self.policy.is_enabled() {
+            return None;
+        }
+        match self.window_start {
+            Some(start) if now.duration_since(start) < self.policy.interval => {}
+            _ => {
+                self.window_start = Some(now);
+                self.captured = 0;
+            }
+        }
+        if self.captured >= self.policy.max_frames {
+            self.suppressed += 1;
+            return None;
+        }
+        self.captured += 1;
+        Some(RawFrame::new(frame, self.policy.max_bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_frame() {
+        let frame = RawFrame::new(&[0x03, 0x00, 0x00, 0x00, 0x06, 0xc8], 4);
+        assert_eq!(frame.length(), 6);
+        assert_eq!(frame.data(), "AwAAAA==");
+        assert_eq!(frame.bytes(), Some(vec![0x03, 0x00, 0x00, 0x00]));
+        assert!(frame.is_truncated());
+        assert_eq!(frame.to_string(), "6 bytes, base64: AwAAAA==...");
+        assert!(!RawFrame::new(&[0xff], 4).is_truncated());
+    }
+
+    #[test]
+    fn test_raw_frame_sampler() {
+        let mut disabled = RawFrameSampler::default();
+        assert_eq!(disabled.capture(&[1, 2, 3]), None);
+        assert_eq!(disabled.suppressed(), 0);
+
+        let mut sampler = RawFrameSampler::new(RawFramePolicy::new(2, 2, Duration::from_secs(1)));
+        let start = Instant::now();
+        for _ in 0..2 {
+            assert_eq!(
+                sampler.capture_at(&[1, 2, 3], start),
+                Some(RawFrame::new(&[1, 2, 3], 2))
+            );
+        }
+        assert_eq!(sampler.capture_at(&[1, 2, 3], start), None);
+        assert_eq!(sampler.suppressed(), 1);
+        // A new interval starts
+        assert!(sampler
+            .capture_at(&[1, 2, 3], start + Duration::from_secs(1))
+            .is_some());
+    }
+}
@@ -17,8 +17,9 @@
 //! serializing/deserializing wire protocols
 
 use crate::{
-    ReadablePdu, ReadablePduWithOneInput, ReadablePduWithThreeInputs, ReadablePduWithTwoInputs,
-    Span, WritablePdu, WritablePduWithOneInput, WritablePduWithTwoInputs,
+    LocatedParsingError, ReadablePdu, ReadablePduWithFourInputs, ReadablePduWithOneInput,
+    ReadablePduWithThreeInputs, ReadablePduWithTwoInputs, Span, WritablePdu,
+    WritablePduWithOneInput, WritablePduWithTwoInputs,
 };
 use netgauze_locate::BinarySpan;
 use nom::IResult;
@@ -128,6 +129,36 @@ where
     value
 }
 
+pub fn test_parsed_completely_with_four_inputs<'a, T, I1, I2, I3, I4, E>(
+    input: &'a [u8],
+    parser_input1: I1,
+    parser_input2: I2,
+    parser_input3: I3,
+    parser_input4: I4,
+    expected: &T,
+) -> T
+where
+    T: ReadablePduWithFourInputs<'a, I1, I2, I3, I4, E> + PartialEq + Debug,
+    E: Debug,
+{
+    let parsed = <T as ReadablePduWithFourInputs<I1, I2, I3, I4, E>>::from_wire(
+        Span::new(input),
+        parser_input1,
+        parser_input2,
+        parser_input3,
+        parser_input4,
+    );
+    assert!(parsed.is_ok(), "Message failed parsing, while expecting it to pass.\n\tExpected : {expected:?}\n\tParsed msg: {parsed:?}");
+    let (span, value) = parsed.unwrap();
+    assert_eq!(&value, expected);
+    assert_eq!(
+        span.fragment().len(),
+        0,
+        "Not all the input is consumed by the parser, didn't consume: {span:?}",
+    );
+    value
+}
+
 /// Fancier assert to for more meaningful error messages
 pub fn test_parse_error<'a, T, E>(input: &'a [u8], expected_err: &E)
 where
@@ -286,3 +317,123 @@ pub fn test_write_with_two_inputs<
     );
     Ok(())
 }
+
+/// Test vector described in a YAML file, see [`test_yaml_vectors`]
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct YamlTestVector {
+    /// Short description of the vector, reported when the test fails
+    pub name: String,
+
+    /// Wire input, see [`crate::parse_hex`] for the accepted formats
+    pub hex: String,
+
+    /// `serde` representation of the expected decoded value. Kept as JSON
+    /// value, since enums are represented as single key maps, while YAML
+    /// values would represent them as tags.
+    #[serde(default)]
+    pub expected: Option<serde_json::Value>,
+
+    /// `serde` representation of the expected error
+    #[serde(default)]
+    pub error: Option<serde_json::Value>,
+}
+
+/// Load the list of test vectors in a YAML file
+pub fn load_yaml_vectors(path: &std::path::Path) -> Vec<YamlTestVector> {
+    let file = std::fs::File::open(path)
+        .unwrap_or_else(|err| panic!("Couldn't open test vectors file {path:?}: {err}"));
+    serde_yaml_ng::from_reader(file)
+        .unwrap_or_else(|err| panic!("Couldn't parse test vectors file {path:?}: {err}"))
+}
+
+/// Drop the location of the error of a parser that must consume the whole
+/// input, to be used by the `decode` function of [`test_yaml_vectors`]:
+///
+/// ```ignore
+/// test_yaml_vectors(&path, |wire| {
+///     parsed_completely(BgpMessage::from_wire(Span::new(wire), &mut ctx))
+/// });
+/// ```
+pub fn parsed_completely<T, L>(result: IResult<Span<'_>, T, L>) -> Result<T, L::Error>
+where
+    L: LocatedParsingError,
+    L::Error: Clone,
+{
+    match result {
+        Ok((remaining, value)) => {
+            assert!(
+                remaining.is_empty(),
+                "Input is not parsed completely, remaining: {remaining:?}"
+            );
+            Ok(value)
+        }
+        Err(nom::Err::Error(err) | nom::Err::Failure(err)) => Err(err.error().clone()),
+        Err(nom::Err::Incomplete(needed)) => panic!("Incomplete input, needed: {needed:?}"),
+    }
+}
+
+/// Run the test vectors of a YAML file in order, so stateful decoders (e.g.,
+/// IPFIX templates) can be fed previous vectors. Each vector is either:
+///
+/// ```yaml
+/// - name: keepalive
+///   hex: ffffffff ffffffff ffffffff ffffffff 0013 04
+///   expected: KeepAlive
+/// - name: bad length
+///   hex: ffffffff ffffffff ffffffff ffffffff 0012 04
+///   error:
+///     BadMessageLength: 18
+/// ```
+///
+/// The expected values are deserialized using the `serde` representation of
+/// `T` and `E`. On failure, the actual result is printed as JSON, which is
+/// valid YAML, to be copied into the vector once verified.
+pub fn test_yaml_vectors<T, E, F>(path: &std::path::Path, mut decode: F)
+where
+    T: Debug + PartialEq + serde::Serialize + serde::de::DeserializeOwned,
+    E: Debug + PartialEq + serde::Serialize + serde::de::DeserializeOwned,
+    F: FnMut(&[u8]) -> Result<T, E>,
+{
+    for vector in load_yaml_vectors(path) {
+        let name = &vector.name;
+        let wire = crate::parse_hex(&vector.hex)
+            .unwrap_or_else(|err| panic!("Invalid hex input in {path:?} vector `{name}`: {err}"));
+        let result = decode(&wire);
+        let actual = match &result {
+            Ok(value) => serde_json::to_string_pretty(value),
+            Err(err) => serde_json::to_string_pretty(err),
+        }
+        .expect("Couldn't serialize the decoded result");
+        match (vector.expected, vector.error, result) {
+            (Some(expected), None, Ok(value)) => {
+                let expected: T = serde_json::from_value(expected).unwrap_or_else(|err| {
+                    panic!("Invalid expected value in {path:?} vector `{name}`: {err}")
+                });
+                assert_eq!(
+                    value, expected,
+                    "Unexpected value for {path:?} vector `{name}`, decoded:\n{actual}"
+                );
+            }
+            (None, Some(expected), Err(err)) => {
+                let expected: E = serde_json::from_value(expected).unwrap_or_else(|err| {
+                    panic!("Invalid expected error in {path:?} vector `{name}`: {err}")
+                });
+                assert_eq!(
+                    err, expected,
+                    "Unexpected error for {path:?} vector `{name}`, decoded:\n{actual}"
+                );
+            }
+            (Some(_), None, Err(_)) => {
+                panic!("Failed to decode {path:?} vector `{name}`, error:\n{actual}")
+            }
+            (None, Some(_), Ok(_)) => {
+                panic!("Expected an error for {path:?} vector `{name}`, decoded:\n{actual}")
+            }
+            _ => panic!(
+                "{path:?} vector `{name}` must have exactly one of `expected` or `error`, \
+                decoded:\n{actual}"
+            ),
+        }
+    }
+}
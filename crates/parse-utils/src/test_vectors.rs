@@ -0,0 +1,51 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Common representation for the protocol conformance test vectors exposed by
+//! the NetGauze packet crates under the `test-vectors` feature.
+
+/// A wire encoded PDU along with the value it's expected to decode to.
+///
+/// Unless stated otherwise by the vector provider, encoding `expected` must
+/// produce exactly `wire`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestVector<T> {
+    name: &'static str,
+    wire: Vec<u8>,
+    expected: T,
+}
+
+impl<T> TestVector<T> {
+    pub const fn new(name: &'static str, wire: Vec<u8>, expected: T) -> Self {
+        Self {
+            name,
+            wire,
+            expected,
+        }
+    }
+
+    /// Short unique (within a set of vectors) description of the vector
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub const fn wire(&self) -> &Vec<u8> {
+        &self.wire
+    }
+
+    pub const fn expected(&self) -> &T {
+        &self.expected
+    }
+}
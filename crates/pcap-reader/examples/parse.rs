@@ -15,7 +15,8 @@ fn main() {
     let reader = Box::new(reader);
     let iter = PcapIter::new(reader);
     let mut peers = HashMap::new();
-    for (src_ip, src_port, dst_ip, dst_port, protocol, value) in iter {
+    for packet in iter {
+        let (src_ip, src_port, dst_ip, dst_port, protocol, value) = packet.unwrap();
         if protocol != TransportProtocol::TCP {
             continue;
         }
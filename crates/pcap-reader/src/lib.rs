@@ -13,11 +13,27 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::{
+    fs::File,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path::Path,
+};
 
-use pcap_parser::{data::PacketData, traits::PcapReaderIterator, *};
+use pcap_parser::{
+    data::{PacketData, ETHERTYPE_IPV4, ETHERTYPE_IPV6},
+    traits::PcapReaderIterator,
+    *,
+};
 use pdu::{Ethernet, Ipv4, Ipv4Pdu, Ipv6, Ipv6Pdu, Tcp, Udp};
 
+#[cfg(any(feature = "bmp", feature = "flow"))]
+pub mod replay;
+
+/// Size of the read buffer of [`PcapIter::from_path`], the largest block of
+/// the capture must fit in it. 128KiB leaves room for a packet of the usual
+/// maximum snapshot length, 65535 octets, along with its block framing.
+pub const DEFAULT_BUFFER_CAPACITY: usize = 128 * 1024;
+
 /// Transport Protocol
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum TransportProtocol {
@@ -25,10 +41,20 @@ pub enum TransportProtocol {
     UDP,
 }
 
-/// Iterator over pcap files
+/// Source address and port, destination address and port, transport protocol
+/// and payload of a captured packet
+pub type Packet = (IpAddr, u16, IpAddr, u16, TransportProtocol, Vec<u8>);
+
+/// Iterator over the TCP and UDP packets of pcap files.
+///
+/// Frames that aren't IPv4 or IPv6 over Ethernet or raw IP links, or that
+/// don't carry TCP or UDP, are skipped. When the capture can't be read, e.g.,
+/// it's truncated, the error is returned and the iteration ends.
 pub struct PcapIter<'a> {
     reader: Box<dyn PcapReaderIterator + 'a>,
     link_types: Vec<Linktype>,
+    legacy_link_type: Linktype,
+    done: bool,
 }
 
 impl<'a> PcapIter<'a> {
@@ -36,107 +62,110 @@ impl<'a> PcapIter<'a> {
         Self {
             reader,
             link_types: vec![],
+            legacy_link_type: Linktype::ETHERNET,
+            done: false,
         }
     }
 }
 
+impl PcapIter<'static> {
+    /// Open a pcap or pcapng file, the format is detected from its header
+    pub fn from_path<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let file = File::open(path)?;
+        let reader = create_reader(DEFAULT_BUFFER_CAPACITY, file).map_err(|err| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{err:?}"))
+        })?;
+        Ok(Self::new(reader))
+    }
+}
+
 impl<'a> Iterator for PcapIter<'a> {
-    type Item = (IpAddr, u16, IpAddr, u16, TransportProtocol, Vec<u8>);
+    type Item = Result<Packet, PcapError<&'static [u8]>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            match self.reader.next() {
+        while !self.done {
+            let (offset, packet) = match self.reader.next() {
                 Ok((offset, block)) => {
-                    match block {
-                        PcapBlockOwned::Legacy(legacy_packet) => {
-                            let packet_data = data::get_packetdata(
-                                legacy_packet.data,
-                                Linktype::ETHERNET,
-                                legacy_packet.caplen as usize,
-                            );
-                            let result = PcapIter::parse_packet(packet_data);
-                            self.reader.consume(offset);
-                            return result;
-                        }
-                        PcapBlockOwned::LegacyHeader(_) => {
-                            self.reader.consume(offset);
-                            continue;
+                    let packet = match block {
+                        PcapBlockOwned::Legacy(packet) => Self::parse_packet(data::get_packetdata(
+                            packet.data,
+                            self.legacy_link_type,
+                            packet.caplen as usize,
+                        )),
+                        PcapBlockOwned::LegacyHeader(header) => {
+                            self.legacy_link_type = header.network;
+                            None
                         }
                         PcapBlockOwned::NG(Block::InterfaceDescription(description)) => {
                             // Memorize link type for that given interface
                             self.link_types.push(description.linktype);
-                            self.reader.consume(offset);
-                            continue;
+                            None
                         }
-                        PcapBlockOwned::NG(Block::EnhancedPacket(packet)) => {
-                            let link_type = self.link_types[packet.if_id as usize];
-                            let packet_data = data::get_packetdata(
-                                packet.data,
-                                link_type,
-                                packet.caplen as usize,
-                            );
-                            let result = PcapIter::parse_packet(packet_data);
-                            self.reader.consume(offset);
-                            return result;
-                        }
-                        PcapBlockOwned::NG(Block::SimplePacket(_)) => {
-                            todo!()
-                        }
-                        PcapBlockOwned::NG(_) => {
-                            self.reader.consume(offset);
-                            continue;
+                        PcapBlockOwned::NG(Block::EnhancedPacket(packet)) => self
+                            .link_types
+                            .get(packet.if_id as usize)
+                            .and_then(|link_type| {
+                                Self::parse_packet(data::get_packetdata(
+                                    packet.data,
+                                    *link_type,
+                                    packet.caplen as usize,
+                                ))
+                            }),
+                        // Simple packets are captured on the first interface
+                        PcapBlockOwned::NG(Block::SimplePacket(packet)) => {
+                            self.link_types.first().and_then(|link_type| {
+                                Self::parse_packet(data::get_packetdata(
+                                    packet.data,
+                                    *link_type,
+                                    packet.data.len(),
+                                ))
+                            })
                         }
+                        PcapBlockOwned::NG(_) => None,
                     };
+                    (offset, packet)
                 }
+                Err(PcapError::Eof) => return None,
                 Err(PcapError::Incomplete(_)) => {
-                    self.reader.refill().unwrap();
+                    if let Err(err) = self.reader.refill() {
+                        self.done = true;
+                        return Some(Err(err.to_owned_vec()));
+                    }
+                    continue;
                 }
-                Err(PcapError::Eof) => return None,
-                Err(PcapError::ReadError) => todo!(),
-                Err(PcapError::HeaderNotRecognized) => todo!(),
-                Err(PcapError::NomError(_, _)) => {
-                    todo!()
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err.to_owned_vec()));
                 }
-                Err(PcapError::OwnedNomError(_, _)) => todo!(),
-                Err(PcapError::BufferTooSmall) => todo!(),
-                Err(PcapError::UnexpectedEof) => todo!(),
+            };
+            self.reader.consume(offset);
+            if let Some(packet) = packet {
+                return Some(Ok(packet));
             }
         }
+        None
     }
 }
 
 impl<'a> PcapIter<'a> {
-    fn parse_packet(
-        data: Option<PacketData<'a>>,
-    ) -> Option<(IpAddr, u16, IpAddr, u16, TransportProtocol, Vec<u8>)> {
-        match data {
-            None => None,
-            Some(PacketData::L2(l2_pkt)) => Self::parse_ethernet(l2_pkt),
-            Some(PacketData::L3(_, _)) => unimplemented!("Only Ethernet packets are supported"),
-            Some(PacketData::L4(_, _)) => unimplemented!("Only Ethernet packets are supported"),
-            Some(PacketData::Unsupported(_)) => {
-                unimplemented!("Only Ethernet packets are supported")
-            }
+    fn parse_packet(data: Option<PacketData<'_>>) -> Option<Packet> {
+        match data? {
+            PacketData::L2(l2_pkt) => Self::parse_ethernet(l2_pkt),
+            PacketData::L3(ETHERTYPE_IPV4, l3_pkt) => Self::parse_ipv4(Ipv4Pdu::new(l3_pkt).ok()?),
+            PacketData::L3(ETHERTYPE_IPV6, l3_pkt) => Self::parse_ipv6(Ipv6Pdu::new(l3_pkt).ok()?),
+            PacketData::L3(_, _) | PacketData::L4(_, _) | PacketData::Unsupported(_) => None,
         }
     }
 
-    fn parse_ethernet(
-        l2_pkt: &'a [u8],
-    ) -> Option<(IpAddr, u16, IpAddr, u16, TransportProtocol, Vec<u8>)> {
-        pdu::EthernetPdu::new(l2_pkt)
-            .map(|eth_pdu| match eth_pdu.inner() {
-                Err(_) => None,
-                Ok(Ethernet::Raw(_)) => unimplemented!(),
-                Ok(Ethernet::Arp(_)) => None,
-                Ok(Ethernet::Ipv4(ipv4_pdu)) => Self::parse_ipv4(ipv4_pdu),
-                Ok(Ethernet::Ipv6(ipv6_pdu)) => Self::parse_ipv6(ipv6_pdu),
-            })
-            .unwrap()
+    fn parse_ethernet(l2_pkt: &[u8]) -> Option<Packet> {
+        match pdu::EthernetPdu::new(l2_pkt).ok()?.inner() {
+            Ok(Ethernet::Ipv4(ipv4_pdu)) => Self::parse_ipv4(ipv4_pdu),
+            Ok(Ethernet::Ipv6(ipv6_pdu)) => Self::parse_ipv6(ipv6_pdu),
+            Ok(Ethernet::Raw(_)) | Ok(Ethernet::Arp(_)) | Err(_) => None,
+        }
     }
 
-    fn parse_ipv4(
-        ipv4_pdu: Ipv4Pdu<'_>,
-    ) -> Option<(IpAddr, u16, IpAddr, u16, TransportProtocol, Vec<u8>)> {
+    fn parse_ipv4(ipv4_pdu: Ipv4Pdu<'_>) -> Option<Packet> {
         let src_ip = IpAddr::V4(Ipv4Addr::from(ipv4_pdu.source_address()));
         let dst_ip = IpAddr::V4(Ipv4Addr::from(ipv4_pdu.destination_address()));
         match ipv4_pdu.inner() {
@@ -173,15 +202,13 @@ impl<'a> PcapIter<'a> {
                         )),
                     }
                 }
-                Ipv4::Icmp(_) => None,
-                Ipv4::Gre(_) => unimplemented!("GRE Protocol is not supported!"),
+                // GRE tunnels aren't decapsulated
+                Ipv4::Icmp(_) | Ipv4::Gre(_) => None,
             },
         }
     }
 
-    fn parse_ipv6(
-        ipv6_pdu: Ipv6Pdu<'_>,
-    ) -> Option<(IpAddr, u16, IpAddr, u16, TransportProtocol, Vec<u8>)> {
+    fn parse_ipv6(ipv6_pdu: Ipv6Pdu<'_>) -> Option<Packet> {
         let src_ip = IpAddr::V6(Ipv6Addr::from(ipv6_pdu.source_address()));
         let dst_ip = IpAddr::V6(Ipv6Addr::from(ipv6_pdu.destination_address()));
         match ipv6_pdu.inner() {
@@ -218,8 +245,8 @@ impl<'a> PcapIter<'a> {
                         )),
                     }
                 }
-                Ipv6::Icmp(_) => None,
-                Ipv6::Gre(_) => unimplemented!("GRE Protocol is not supported!"),
+                // GRE tunnels aren't decapsulated
+                Ipv6::Icmp(_) | Ipv6::Gre(_) => None,
             },
         }
     }
@@ -242,7 +269,7 @@ mod tests {
         let mut iter = PcapIter::new(reader);
         let mut results = vec![];
         while let Some(val) = iter.next() {
-            results.push(val);
+            results.push(val.unwrap());
         }
         assert_eq!(results.len(), 20)
     }
@@ -258,8 +285,97 @@ mod tests {
         let mut iter = PcapIter::new(reader);
         let mut results = vec![];
         while let Some(val) = iter.next() {
-            results.push(val);
+            results.push(val.unwrap());
         }
         assert_eq!(results.len(), 9)
     }
+
+    /// Legacy pcap file of the given link type and frames
+    fn legacy_pcap(link_type: u32, frames: &[&[u8]]) -> Vec<u8> {
+        let mut pcap = vec![];
+        pcap.extend_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+        pcap.extend_from_slice(&2u16.to_le_bytes());
+        pcap.extend_from_slice(&4u16.to_le_bytes());
+        pcap.extend_from_slice(&[0; 8]);
+        pcap.extend_from_slice(&65535u32.to_le_bytes());
+        pcap.extend_from_slice(&link_type.to_le_bytes());
+        for frame in frames {
+            pcap.extend_from_slice(&[0; 8]);
+            pcap.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            pcap.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            pcap.extend_from_slice(frame);
+        }
+        pcap
+    }
+
+    /// IPv4 packet carrying a UDP datagram from 192.0.2.1:4739 to
+    /// 192.0.2.2:9991
+    fn ipv4_udp(payload: &[u8]) -> Vec<u8> {
+        let mut pkt = vec![
+            0x45, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0x00, 0x00, 192, 0, 2, 1,
+            192, 0, 2, 2, 0x12, 0x83, 0x27, 0x07, 0x00, 0x00, 0x00, 0x00,
+        ];
+        pkt[2..4].copy_from_slice(&((28 + payload.len()) as u16).to_be_bytes());
+        pkt[24..26].copy_from_slice(&((8 + payload.len()) as u16).to_be_bytes());
+        pkt.extend_from_slice(payload);
+        pkt
+    }
+
+    fn ethernet(ether_type: u16, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0x02, 0, 0, 0, 0, 0x02, 0x02, 0, 0, 0, 0, 0x01];
+        frame.extend_from_slice(&ether_type.to_be_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    #[test]
+    fn it_skips_unsupported_frames() {
+        let udp = ipv4_udp(&[1, 2, 3]);
+        let expected = (
+            IpAddr::from([192, 0, 2, 1]),
+            4739,
+            IpAddr::from([192, 0, 2, 2]),
+            9991,
+            TransportProtocol::UDP,
+            vec![1, 2, 3],
+        );
+
+        // ARP and garbage frames before and between the UDP datagrams
+        let arp = ethernet(0x0806, &[0; 28]);
+        let udp_frame = ethernet(0x0800, &udp);
+        let pcap = legacy_pcap(1, &[&arp, &udp_frame, &[0xff; 3], &udp_frame]);
+        let reader = LegacyPcapReader::new(DEFAULT_BUFFER_CAPACITY, pcap.as_slice()).unwrap();
+        let packets = PcapIter::new(Box::new(reader)).collect::<Vec<_>>();
+        assert_eq!(packets, vec![Ok(expected.clone()), Ok(expected.clone())]);
+
+        // Raw IP link type
+        let pcap = legacy_pcap(101, &[&udp]);
+        let reader = LegacyPcapReader::new(DEFAULT_BUFFER_CAPACITY, pcap.as_slice()).unwrap();
+        let packets = PcapIter::new(Box::new(reader)).collect::<Vec<_>>();
+        assert_eq!(packets, vec![Ok(expected.clone())]);
+
+        // Truncated capture
+        let mut pcap = legacy_pcap(1, &[&udp_frame, &udp_frame]);
+        pcap.truncate(pcap.len() - 1);
+        let reader = LegacyPcapReader::new(DEFAULT_BUFFER_CAPACITY, pcap.as_slice()).unwrap();
+        let packets = PcapIter::new(Box::new(reader)).collect::<Vec<_>>();
+        assert_eq!(packets, vec![Ok(expected), Err(PcapError::UnexpectedEof)]);
+    }
+
+    #[test]
+    fn it_from_path() {
+        let path = format!("{}/data", env!("CARGO_MANIFEST_DIR"));
+        assert_eq!(
+            PcapIter::from_path(format!("{path}/bgp.pcap"))
+                .unwrap()
+                .count(),
+            20
+        );
+        assert_eq!(
+            PcapIter::from_path(format!("{path}/bmp.pcapng"))
+                .unwrap()
+                .count(),
+            9
+        );
+    }
 }
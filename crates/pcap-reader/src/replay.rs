@@ -0,0 +1,272 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Replay of captured BMP and IPFIX/Netflow V9 traffic through the decoders
+//! of NetGauze, e.g., to build regression corpora from captures of real
+//! routers.
+//!
+//! [`BmpReplay`] (feature `bmp`) appends the payloads of each TCP connection
+//! in capture order and decodes them with a `BmpCodec` per connection.
+//! Retransmitted or missing segments aren't detected, captures are expected
+//! to be complete. [`FlowReplay`] (feature `flow`) decodes each UDP datagram
+//! with a `FlowInfoCodec` per exporter, so templates are learned from the
+//! capture.
+//!
+//! The replays read the results of a [`crate::PcapIter`], packets that
+//! couldn't be read are skipped.
+//!
+//! ```no_run
+//! # #[cfg(feature = "bmp")]
+//! # {
+//! use netgauze_pcap_reader::{replay::BmpReplay, PcapIter};
+//!
+//! let packets = PcapIter::from_path("bmp.pcap").unwrap();
+//! for (router, msg) in BmpReplay::new(packets).with_port(1790) {
+//!     println!("{router}: {msg:?}");
+//! }
+//! # }
+//! ```
+
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+};
+
+use bytes::BytesMut;
+use tokio_util::codec::Decoder;
+
+use crate::{Packet, TransportProtocol};
+
+/// Decode the payloads in `buf` with `codec` until more data is needed.
+/// Errors are returned along the decoded values, decoding stops if the codec
+/// doesn't consume anything on error to avoid looping on the same bytes.
+fn decode_all<C: Decoder>(codec: &mut C, buf: &mut BytesMut) -> Vec<Result<C::Item, C::Error>> {
+    let mut decoded = vec![];
+    while !buf.is_empty() {
+        let len = buf.len();
+        match codec.decode(buf) {
+            Ok(Some(value)) => decoded.push(Ok(value)),
+            Ok(None) => break,
+            Err(err) => {
+                decoded.push(Err(err));
+                if buf.len() == len {
+                    buf.clear();
+                }
+            }
+        }
+    }
+    decoded
+}
+
+/// Iterator over the BMP messages of the TCP connections in a capture,
+/// returned with the address of the router that sent them
+#[cfg(feature = "bmp")]
+pub struct BmpReplay<I> {
+    packets: I,
+    port: Option<u16>,
+    connections: HashMap<(SocketAddr, SocketAddr), (netgauze_bmp_pkt::codec::BmpCodec, BytesMut)>,
+    ready: VecDeque<(
+        SocketAddr,
+        Result<netgauze_bmp_pkt::BmpMessage, netgauze_bmp_pkt::codec::BmpCodecDecoderError>,
+    )>,
+}
+
+#[cfg(feature = "bmp")]
+impl<I, E> BmpReplay<I>
+where
+    I: Iterator<Item = Result<Packet, E>>,
+{
+    pub fn new(packets: I) -> Self {
+        Self {
+            packets,
+            port: None,
+            connections: HashMap::new(),
+            ready: VecDeque::new(),
+        }
+    }
+
+    /// Only decode the segments sent to the given collector port, by default
+    /// all the TCP segments of the capture are decoded as BMP
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+}
+
+#[cfg(feature = "bmp")]
+impl<I, E> Iterator for BmpReplay<I>
+where
+    I: Iterator<Item = Result<Packet, E>>,
+{
+    type Item = (
+        SocketAddr,
+        Result<netgauze_bmp_pkt::BmpMessage, netgauze_bmp_pkt::codec::BmpCodecDecoderError>,
+    );
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.ready.pop_front() {
+                return Some(item);
+            }
+            let Ok((src_ip, src_port, dst_ip, dst_port, protocol, payload)) =
+                self.packets.next()?
+            else {
+                continue;
+            };
+            if protocol != TransportProtocol::TCP
+                || payload.is_empty()
+                || self.port.is_some_and(|port| port != dst_port)
+            {
+                continue;
+            }
+            let router = SocketAddr::new(src_ip, src_port);
+            let (codec, buf) = self
+                .connections
+                .entry((router, SocketAddr::new(dst_ip, dst_port)))
+                .or_default();
+            buf.extend_from_slice(&payload);
+            self.ready.extend(
+                decode_all(codec, buf)
+                    .into_iter()
+                    .map(|result| (router, result)),
+            );
+        }
+    }
+}
+
+/// Iterator over the IPFIX and Netflow V9 packets of the UDP datagrams in a
+/// capture, returned with the address of the exporter that sent them
+#[cfg(feature = "flow")]
+pub struct FlowReplay<I> {
+    packets: I,
+    port: Option<u16>,
+    exporters: HashMap<SocketAddr, netgauze_flow_pkt::codec::FlowInfoCodec>,
+    ready: VecDeque<(
+        SocketAddr,
+        Result<netgauze_flow_pkt::FlowInfo, netgauze_flow_pkt::codec::FlowInfoCodecDecoderError>,
+    )>,
+}
+
+#[cfg(feature = "flow")]
+impl<I, E> FlowReplay<I>
+where
+    I: Iterator<Item = Result<Packet, E>>,
+{
+    pub fn new(packets: I) -> Self {
+        Self {
+            packets,
+            port: None,
+            exporters: HashMap::new(),
+            ready: VecDeque::new(),
+        }
+    }
+
+    /// Only decode the datagrams sent to the given collector port, by default
+    /// all the UDP datagrams of the capture are decoded as IPFIX/Netflow V9
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+}
+
+#[cfg(feature = "flow")]
+impl<I, E> Iterator for FlowReplay<I>
+where
+    I: Iterator<Item = Result<Packet, E>>,
+{
+    type Item = (
+        SocketAddr,
+        Result<netgauze_flow_pkt::FlowInfo, netgauze_flow_pkt::codec::FlowInfoCodecDecoderError>,
+    );
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.ready.pop_front() {
+                return Some(item);
+            }
+            let Ok((src_ip, src_port, _, dst_port, protocol, payload)) = self.packets.next()?
+            else {
+                continue;
+            };
+            if protocol != TransportProtocol::UDP
+                || payload.is_empty()
+                || self.port.is_some_and(|port| port != dst_port)
+            {
+                continue;
+            }
+            let exporter = SocketAddr::new(src_ip, src_port);
+            let codec = self.exporters.entry(exporter).or_default();
+            // Datagrams are self-contained, a truncated one is dropped rather
+            // than joined with the next one
+            let mut buf = BytesMut::from(payload.as_slice());
+            self.ready.extend(
+                decode_all(codec, &mut buf)
+                    .into_iter()
+                    .map(|result| (exporter, result)),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PcapIter;
+
+    #[cfg(feature = "bmp")]
+    #[test]
+    fn test_bmp_replay() {
+        let path = format!("{}/data/bmp.pcapng", env!("CARGO_MANIFEST_DIR"));
+        let messages = BmpReplay::new(PcapIter::from_path(&path).unwrap()).collect::<Vec<_>>();
+        assert!(!messages.is_empty());
+        assert!(messages.iter().all(|(_, msg)| msg.is_ok()));
+
+        // No BMP traffic to that port
+        let messages = BmpReplay::new(PcapIter::from_path(&path).unwrap()).with_port(1);
+        assert_eq!(messages.count(), 0);
+    }
+
+    #[cfg(feature = "bmp")]
+    #[test]
+    fn test_bmp_replay_skips_packets() {
+        let router = std::net::IpAddr::from([192, 0, 2, 1]);
+        let collector = std::net::IpAddr::from([192, 0, 2, 2]);
+        // BMP Initiation message without any TLV
+        let initiation = vec![0x03, 0x00, 0x00, 0x00, 0x06, 0x04];
+        let packets: Vec<Result<Packet, ()>> = vec![
+            Err(()),
+            Ok((
+                router,
+                40000,
+                collector,
+                1790,
+                TransportProtocol::UDP,
+                initiation.clone(),
+            )),
+            Ok((
+                router,
+                40000,
+                collector,
+                1790,
+                TransportProtocol::TCP,
+                initiation,
+            )),
+        ];
+        let messages = BmpReplay::new(packets.into_iter()).collect::<Vec<_>>();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].0, SocketAddr::new(router, 40000));
+        assert!(messages[0].1.is_ok());
+    }
+}
@@ -115,6 +115,160 @@ fn filter_attribute_by_name_with_module(
     (variants, idents)
 }
 
+fn has_attribute(attrs: &[syn::Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| attr.path().is_ident(name))
+}
+
+/// Categories that can be set with the `#[category(...)]` attribute of a
+/// variant
+const ERROR_CATEGORIES: [&str; 5] = [
+    "Truncated",
+    "UnknownCode",
+    "SemanticViolation",
+    "Unsupported",
+    "Io",
+];
+
+fn get_category(variant: &syn::Variant) -> syn::Result<Option<syn::Ident>> {
+    let Some(attr) = variant
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("category"))
+    else {
+        return Ok(None);
+    };
+    let category = attr.parse_args::<syn::Ident>()?;
+    if !ERROR_CATEGORIES.iter().any(|name| category == name) {
+        return Err(syn::Error::new(
+            category.span(),
+            format!("Unknown error category, expected one of {ERROR_CATEGORIES:?}"),
+        ));
+    }
+    Ok(Some(category))
+}
+
+/// Generate `netgauze_parse_utils::Categorize` for an error enum, with an arm
+/// per variant:
+/// - `#[category(...)]` variants are of the given category.
+/// - `nom_attr` variants are categorized by their nom `ErrorKind`.
+/// - `cause_attrs` variants carry another error, and take its category.
+/// - `io_attr` variants are I/O errors.
+/// - Any other variant is a semantic violation.
+fn categorize(
+    ident: &syn::Ident,
+    enum_data: &syn::DataEnum,
+    nom_attr: Option<&str>,
+    cause_attrs: &[&str],
+    io_attr: Option<&str>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut category_arms = vec![];
+    let mut variant_arms = vec![];
+    let mut cause_arms = vec![];
+    let mut nom_arms = vec![];
+    for variant in &enum_data.variants {
+        let variant_ident = &variant.ident;
+        let variant_name = variant_ident.to_string();
+        let field_with = |name: Option<&str>| {
+            variant
+                .fields
+                .iter()
+                .enumerate()
+                .find(|(_, field)| name.is_some_and(|name| has_attribute(&field.attrs, name)))
+        };
+        let nom_field = field_with(nom_attr);
+        let cause_field = variant.fields.iter().enumerate().find(|(_, field)| {
+            cause_attrs
+                .iter()
+                .any(|name| has_attribute(&field.attrs, name))
+        });
+        let io_field = field_with(io_attr);
+        // Pattern binding the field at `index` to `value`, if any
+        let pattern = |index: Option<usize>| match (&variant.fields, index) {
+            (syn::Fields::Unit, _) => quote! { #ident::#variant_ident },
+            (syn::Fields::Named(_), None) | (syn::Fields::Unnamed(_), None) => {
+                quote! { #ident::#variant_ident { .. } }
+            }
+            (syn::Fields::Named(fields), Some(index)) => {
+                let name = fields.named[index].ident.as_ref();
+                quote! { #ident::#variant_ident { #name: value, .. } }
+            }
+            (syn::Fields::Unnamed(_), Some(index)) => {
+                let skip = (0..index).map(|_| quote! { _ });
+                quote! { #ident::#variant_ident(#(#skip,)* value, ..) }
+            }
+        };
+        let any = pattern(None);
+        let category = match (get_category(variant)?, nom_field, cause_field, io_field) {
+            (Some(category), _, _, _) => {
+                quote! { #any => netgauze_parse_utils::ErrorCategory::#category }
+            }
+            (None, Some((index, _)), _, _) => {
+                let pattern = pattern(Some(index));
+                quote! { #pattern => netgauze_parse_utils::category::nom_error_category(*value) }
+            }
+            (None, None, Some((index, _)), _) => {
+                let pattern = pattern(Some(index));
+                quote! { #pattern => netgauze_parse_utils::Categorize::category(value) }
+            }
+            (None, None, None, Some(_)) => {
+                quote! { #any => netgauze_parse_utils::ErrorCategory::Io }
+            }
+            (None, None, None, None) => {
+                quote! { #any => netgauze_parse_utils::ErrorCategory::SemanticViolation }
+            }
+        };
+        category_arms.push(category);
+        variant_arms.push(quote! { #any => #variant_name });
+        if let Some((index, _)) = cause_field {
+            let pattern = pattern(Some(index));
+            cause_arms.push(quote! { #pattern => Some(value) });
+        }
+        if let Some((index, _)) = nom_field {
+            let pattern = pattern(Some(index));
+            nom_arms.push(quote! { #pattern => Some(*value) });
+        }
+    }
+    // Enums without variants can't be matched with a wildcard arm
+    let cause_default = (!enum_data.variants.is_empty()).then(|| quote! { _ => None });
+    let nom_error_kind = nom_attr.map(|_| {
+        quote! {
+            #[allow(unreachable_patterns)]
+            fn nom_error_kind(&self) -> Option<nom::error::ErrorKind> {
+                match self {
+                    #(#nom_arms,)*
+                    #cause_default
+                }
+            }
+        }
+    });
+    Ok(quote! {
+        #[automatically_derived]
+        impl netgauze_parse_utils::Categorize for #ident {
+            fn category(&self) -> netgauze_parse_utils::ErrorCategory {
+                match self {
+                    #(#category_arms,)*
+                }
+            }
+
+            fn variant(&self) -> &'static str {
+                match self {
+                    #(#variant_arms,)*
+                }
+            }
+
+            #[allow(unreachable_patterns)]
+            fn cause(&self) -> Option<&dyn netgauze_parse_utils::Categorize> {
+                match self {
+                    #(#cause_arms,)*
+                    #cause_default
+                }
+            }
+
+            #nom_error_kind
+        }
+    })
+}
+
 #[derive(Debug)]
 struct LocatedError {}
 
@@ -236,8 +390,11 @@ impl LocatedError {
         let (from_external_variants, from_external_ident) =
             filter_attribute_by_name(en, "from_external")?;
         let from_located = LocatedError::get_from_located(en)?;
+        let categorize = categorize(&ident, en, Some("from_nom"), &["from_located"], None)?;
 
         let mut output = quote! {
+            #categorize
+
             #[derive(PartialEq, Clone, Debug)]
             #[automatically_derived]
             pub struct #located_struct_name<'a> {
@@ -322,7 +479,9 @@ impl LocatedError {
 /// For a given error enum {Name} generate a struct called Located{Name} that
 /// carries the `Span` (the error location in the input stream) info along the
 /// error. Additionally, generates [`From`] for `nom` library errors, external,
-/// and another located errors.
+/// and another located errors, and `netgauze_parse_utils::Categorize` for the
+/// error enum. Variants are semantic violations unless they are `#[from_nom]`,
+/// `#[from_located]`, or annotated with `#[category(...)]`.
 ///
 /// Example:
 /// ```no_compile
@@ -332,6 +491,7 @@ impl LocatedError {
 /// pub enum ExtendedCommunityParsingError {
 ///     NomError(#[from_nom] nom::error::ErrorKind),
 ///     CommunityError(#[from_located(module = "self")] CommunityParsingError),
+///     #[category(UnknownCode)]
 ///     UndefinedCapabilityCode(#[from_external] UndefinedBgpCapabilityCode),
 /// }
 ///
@@ -343,7 +503,10 @@ impl LocatedError {
 /// #[derive(Copy, Clone, PartialEq, Debug)]
 /// pub struct UndefinedBgpCapabilityCode(pub u8);
 /// ```
-#[proc_macro_derive(LocatedError, attributes(from_nom, from_external, from_located))]
+#[proc_macro_derive(
+    LocatedError,
+    attributes(from_nom, from_external, from_located, category)
+)]
 pub fn located_error(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let ast = syn::parse_macro_input!(input as syn::DeriveInput);
     LocatedError::from(&ast)
@@ -364,8 +527,11 @@ impl WritingError {
         let ident = input.ident.clone();
         let (from_variants, from_idents) = filter_attribute_by_name_with_module(en, "from");
         let (from_std_io_error_variants, _) = filter_attribute_by_name(en, "from_std_io_error")?;
+        let categorize = categorize(&ident, en, None, &["from"], Some("from_std_io_error"))?;
 
         let mut output = quote! {
+            #categorize
+
             #(
                 #[automatically_derived]
                 impl From<std::io::Error> for #ident {
@@ -402,6 +568,11 @@ impl WritingError {
 /// 2. `#[from]`, automatically generates a [`From`] implementation for a given
 /// type.
 ///
+/// 3. `#[category(...)]` on a variant sets its category in the generated
+///    `netgauze_parse_utils::Categorize` implementation. By default,
+///    `#[from_std_io_error]` variants are I/O errors, `#[from]` variants take
+///    the category of the error they carry, and any other variant is a
+///    semantic violation.
 ///
 /// Example:
 /// ```no_compile
@@ -422,7 +593,7 @@ impl WritingError {
 ///     OpenError(#[from] BgpOpenMessageWritingError),
 /// }
 /// ```
-#[proc_macro_derive(WritingError, attributes(from_std_io_error, from))]
+#[proc_macro_derive(WritingError, attributes(from_std_io_error, from, category))]
 pub fn writing_error(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let ast = syn::parse_macro_input!(input as syn::DeriveInput);
     WritingError::from(&ast)
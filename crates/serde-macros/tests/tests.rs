@@ -10,4 +10,6 @@ fn macro_tests() {
     test_cases.pass("tests/trybuild/07-writing-plain.rs");
     test_cases.pass("tests/trybuild/08-writing-from-std-io-error.rs");
     test_cases.pass("tests/trybuild/09-writing-from.rs");
+    test_cases.pass("tests/trybuild/10-categorize.rs");
+    test_cases.compile_fail("tests/trybuild/11-unknown-category.rs");
 }
@@ -10,7 +10,7 @@ pub enum TestError {
     B(#[from] BError),
 }
 
-#[derive(Eq, PartialEq, Debug, Clone)]
+#[derive(WritingError, Eq, PartialEq, Debug, Clone)]
 pub enum BError {
     B,
 }
@@ -0,0 +1,52 @@
+#[allow(unused_imports)]
+#[allow(unused_variables)]
+
+use netgauze_serde_macros::{LocatedError, WritingError};
+use netgauze_parse_utils::{Categorize, ErrorCategory};
+
+use nom::error::ErrorKind;
+
+
+#[derive(LocatedError, Eq, PartialEq, Debug, Clone)]
+pub enum TestError {
+    NomError(#[from_nom] ErrorKind),
+    A(#[from_located(module = "self")] AError),
+    #[category(UnknownCode)]
+    Undefined(#[from_external] u8),
+    #[category(Unsupported)]
+    Version { version: u8 },
+    Invalid,
+}
+
+#[derive(LocatedError, Eq, PartialEq, Debug, Clone)]
+pub enum AError {
+    #[category(Truncated)]
+    A
+}
+
+#[derive(WritingError, Eq, PartialEq, Debug, Clone)]
+pub enum TestWritingError {
+    StdIOError(#[from_std_io_error] String),
+    B(#[from] BWritingError),
+}
+
+#[derive(WritingError, Eq, PartialEq, Debug, Clone)]
+pub enum BWritingError {
+    #[category(Unsupported)]
+    B,
+}
+
+fn main() {
+    assert_eq!(TestError::NomError(ErrorKind::Eof).category(), ErrorCategory::Truncated);
+    assert_eq!(TestError::NomError(ErrorKind::Eof).nom_error_kind(), Some(ErrorKind::Eof));
+    assert_eq!(TestError::NomError(ErrorKind::Verify).category(), ErrorCategory::SemanticViolation);
+    assert_eq!(TestError::A(AError::A).category(), ErrorCategory::Truncated);
+    assert_eq!(TestError::A(AError::A).cause().map(|cause| cause.variant()), Some("A"));
+    assert_eq!(TestError::Undefined(1).category(), ErrorCategory::UnknownCode);
+    assert_eq!(TestError::Version { version: 1 }.category(), ErrorCategory::Unsupported);
+    assert_eq!(TestError::Invalid.category(), ErrorCategory::SemanticViolation);
+    assert_eq!(TestError::Invalid.variant(), "Invalid");
+    assert!(TestError::Invalid.cause().is_none());
+    assert_eq!(TestWritingError::StdIOError(String::new()).category(), ErrorCategory::Io);
+    assert_eq!(TestWritingError::B(BWritingError::B).category(), ErrorCategory::Unsupported);
+}
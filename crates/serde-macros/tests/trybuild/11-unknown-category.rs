@@ -0,0 +1,16 @@
+#[allow(unused_imports)]
+#[allow(unused_variables)]
+
+use netgauze_serde_macros::LocatedError;
+
+use nom::error::ErrorKind;
+
+
+#[derive(LocatedError, Eq, PartialEq, Debug, Clone)]
+pub enum TestError {
+    NomError(#[from_nom] ErrorKind),
+    #[category(Undefined)]
+    Undefined(u8),
+}
+
+fn main() {}
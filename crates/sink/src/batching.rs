@@ -0,0 +1,340 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Writing records in batches to a [`RecordSink`]
+
+use std::time::Duration;
+
+use crate::{RecordSink, SinkRecord};
+
+/// Default number of records buffered by [`BatchingSink`] before they're
+/// written to the sink
+pub const DEFAULT_BATCH_SIZE: usize = 1000;
+
+/// Default number of records [`BatchingSink`] keeps to retry after failed
+/// writes, the oldest records beyond it are dropped
+pub const DEFAULT_MAX_PENDING: usize = 100 * DEFAULT_BATCH_SIZE;
+
+/// Buffers records and writes them to the inner [`RecordSink`] once the batch
+/// size is reached.
+///
+/// Pushing never waits between retries, so a slow or unavailable sink doesn't
+/// stall the task producing the records: a full batch is written with a single
+/// attempt, and once a write failed the records are only buffered until
+/// [`BatchingSink::flush`] succeeds, see [`BatchingSink::backlogged`]. `flush`
+/// is meant to be called periodically by a separate task, it retries failed
+/// writes `max_retries` times, doubling the delay between attempts starting
+/// from `retry_delay`.
+///
+/// The records of the failed writes are kept up to `max_pending` records. When
+/// the inner sink stays unavailable, the oldest records beyond that are dropped
+/// and counted in [`BatchingSink::dropped`].
+#[derive(Debug)]
+pub struct BatchingSink<S, R> {
+    sink: S,
+    batch_size: usize,
+    max_retries: usize,
+    retry_delay: Duration,
+    max_pending: usize,
+    dropped: u64,
+    backlogged: bool,
+    buffer: Vec<R>,
+}
+
+impl<S, R> BatchingSink<S, R>
+where
+    S: RecordSink<R> + Send,
+    R: SinkRecord + Send + Sync,
+    R::Error: std::fmt::Display,
+{
+    pub fn new(sink: S, batch_size: usize) -> Self {
+        Self {
+            sink,
+            batch_size,
+            max_retries: 3,
+            retry_delay: Duration::from_millis(100),
+            max_pending: DEFAULT_MAX_PENDING.max(batch_size),
+            dropped: 0,
+            backlogged: false,
+            buffer: Vec::with_capacity(batch_size),
+        }
+    }
+
+    /// Retries of [`Self::flush`]
+    pub const fn with_retries(mut self, max_retries: usize, retry_delay: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Maximum number of records kept after failed writes, at least the
+    /// batch size
+    pub fn with_max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending.max(self.batch_size);
+        self
+    }
+
+    pub const fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    pub const fn max_pending(&self) -> usize {
+        self.max_pending
+    }
+
+    /// Number of records dropped because more than `max_pending` records were
+    /// waiting to be retried
+    pub const fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub const fn max_retries(&self) -> usize {
+        self.max_retries
+    }
+
+    pub const fn retry_delay(&self) -> Duration {
+        self.retry_delay
+    }
+
+    /// The last write failed, the records are buffered without being written
+    /// until [`Self::flush`] succeeds
+    pub const fn backlogged(&self) -> bool {
+        self.backlogged
+    }
+
+    /// Number of records waiting to be written
+    pub fn pending(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub const fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    /// Buffer `record`, and write the buffer with a single attempt once the
+    /// batch size is reached. Returns the error of that attempt, the records
+    /// are then kept for [`Self::flush`].
+    pub async fn push(&mut self, record: R) -> Result<(), R::Error> {
+        self.buffer.push(record);
+        if self.backlogged {
+            self.drop_overflow();
+            return Ok(());
+        }
+        if self.buffer.len() >= self.batch_size {
+            self.write().await?;
+        }
+        Ok(())
+    }
+
+    pub async fn extend(
+        &mut self,
+        records: impl IntoIterator<Item = R> + Send,
+    ) -> Result<(), R::Error> {
+        for record in records {
+            self.push(record).await?;
+        }
+        Ok(())
+    }
+
+    /// Write all buffered records. When all the attempts fail, the records are
+    /// kept in the buffer to be retried by the next flush, except for the
+    /// oldest ones beyond `max_pending`.
+    pub async fn flush(&mut self) -> Result<(), R::Error> {
+        if self.buffer.is_empty() {
+            self.backlogged = false;
+            return Ok(());
+        }
+        let mut delay = self.retry_delay;
+        let mut attempt = 0;
+        loop {
+            match self.write().await {
+                Ok(()) => return Ok(()),
+                Err(err) if attempt >= self.max_retries => return Err(err),
+                Err(err) => {
+                    tracing::warn!(
+                        "failed writing {} records, retrying in {delay:?}: {err}",
+                        self.buffer.len()
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    async fn write(&mut self) -> Result<(), R::Error> {
+        match self.sink.write(&self.buffer).await {
+            Ok(()) => {
+                self.buffer.clear();
+                self.backlogged = false;
+                Ok(())
+            }
+            Err(err) => {
+                self.backlogged = true;
+                self.drop_overflow();
+                Err(err)
+            }
+        }
+    }
+
+    fn drop_overflow(&mut self) {
+        let overflow = self.buffer.len().saturating_sub(self.max_pending);
+        if overflow > 0 {
+            self.buffer.drain(..overflow);
+            self.dropped += overflow as u64;
+            tracing::warn!(
+                "dropped {overflow} records, more than {} records are waiting to be retried",
+                self.max_pending
+            );
+        }
+    }
+
+    /// Flush the buffered records and return the inner sink
+    pub async fn into_inner(mut self) -> Result<S, R::Error> {
+        self.flush().await?;
+        Ok(self.sink)
+    }
+
+    /// Bootstrap `sink`, flush the buffered records to the current sink, then
+    /// write the next records to `sink`. Returns the previous sink. On error,
+    /// the current sink is kept.
+    pub async fn replace_sink(&mut self, mut sink: S) -> Result<S, R::Error> {
+        sink.bootstrap().await?;
+        self.flush().await?;
+        Ok(std::mem::replace(&mut self.sink, sink))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SchemaVersion, SinkError};
+    use async_trait::async_trait;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Record(u32);
+
+    impl SinkRecord for Record {
+        type Error = SinkError;
+
+        fn to_json(&self, _version: SchemaVersion) -> Result<serde_json::Value, SinkError> {
+            Ok(self.0.into())
+        }
+
+        fn idempotency_key(&self) -> Result<String, SinkError> {
+            Ok(self.0.to_string())
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct FlakySink {
+        failures: usize,
+        batches: Vec<Vec<Record>>,
+    }
+
+    #[async_trait]
+    impl RecordSink<Record> for FlakySink {
+        async fn bootstrap(&mut self) -> Result<(), SinkError> {
+            Ok(())
+        }
+
+        async fn write(&mut self, records: &[Record]) -> Result<(), SinkError> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err(SinkError::IoError("test".to_string()));
+            }
+            self.batches.push(records.to_vec());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batching_sink_retry() {
+        let flaky = FlakySink {
+            failures: 2,
+            batches: vec![],
+        };
+        let mut sink = BatchingSink::new(flaky, 2).with_retries(2, Duration::from_millis(1));
+        // A full batch is written with a single attempt
+        assert!(sink.extend([Record(0), Record(1)]).await.is_err());
+        assert!(sink.backlogged());
+        assert_eq!(sink.pending(), 2);
+        // Backlogged records are only written by the flushes
+        sink.push(Record(2)).await.unwrap();
+        assert_eq!(sink.pending(), 3);
+        assert_eq!(sink.sink().failures, 1);
+        sink.flush().await.unwrap();
+        assert!(!sink.backlogged());
+        assert_eq!(sink.pending(), 0);
+        assert_eq!(
+            sink.sink().batches,
+            vec![vec![Record(0), Record(1), Record(2)]]
+        );
+
+        let flaky = FlakySink {
+            failures: 2,
+            batches: vec![],
+        };
+        let mut sink = BatchingSink::new(flaky, 2).with_retries(0, Duration::from_millis(1));
+        sink.extend([Record(0)]).await.unwrap();
+        assert!(sink.flush().await.is_err());
+        // Records are kept to be written by the next flush
+        assert_eq!(sink.pending(), 1);
+        assert_eq!(sink.dropped(), 0);
+        assert_eq!(
+            sink.into_inner().await.unwrap_err(),
+            SinkError::IoError("test".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batching_sink_max_pending() {
+        let flaky = FlakySink {
+            failures: usize::MAX,
+            batches: vec![],
+        };
+        let mut sink = BatchingSink::new(flaky, 1)
+            .with_retries(0, Duration::from_millis(1))
+            .with_max_pending(3);
+        assert_eq!(sink.max_pending(), 3);
+        assert!(sink.push(Record(0)).await.is_err());
+        for index in 1..5 {
+            sink.push(Record(index)).await.unwrap();
+        }
+        assert_eq!(sink.pending(), 3);
+        assert_eq!(sink.dropped(), 2);
+
+        sink.sink.failures = 0;
+        sink.flush().await.unwrap();
+        // The oldest records are dropped
+        assert_eq!(
+            sink.sink().batches,
+            vec![vec![Record(2), Record(3), Record(4)]]
+        );
+        assert_eq!(sink.pending(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_batching_sink_replace_sink() {
+        let mut sink = BatchingSink::new(FlakySink::default(), 10);
+        sink.push(Record(0)).await.unwrap();
+        let previous = sink.replace_sink(FlakySink::default()).await.unwrap();
+        assert_eq!(previous.batches, vec![vec![Record(0)]]);
+        sink.push(Record(1)).await.unwrap();
+        let current = sink.into_inner().await.unwrap();
+        assert_eq!(current.batches, vec![vec![Record(1)]]);
+    }
+}
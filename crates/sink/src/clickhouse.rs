@@ -0,0 +1,261 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sink writing to ClickHouse over its
+//! [HTTP interface](https://clickhouse.com/docs/en/interfaces/http)
+
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+
+use crate::{check_table_name, RecordSink, SinkError, SinkRecord};
+
+impl From<reqwest::Error> for SinkError {
+    fn from(value: reqwest::Error) -> Self {
+        Self::HttpError(value.to_string())
+    }
+}
+
+/// Records stored as the rows of a ClickHouse table
+pub trait ClickHouseRow: SinkRecord {
+    /// Column definitions of the table, e.g., `prefix String, med UInt32`
+    const COLUMNS: &'static str;
+
+    /// Sorting key of the table, e.g., `prefix, med`
+    const ORDER_BY: &'static str;
+
+    /// Column holding the [`SinkRecord::idempotency_key`] of the deduplicated
+    /// tables
+    const KEY_COLUMN: &'static str;
+
+    /// The record as a JSON object with one field per column
+    fn row(&self) -> Result<serde_json::Value, Self::Error>;
+}
+
+/// Writes records to a ClickHouse table, each batch is sent as a single
+/// `INSERT` in the `JSONEachRow` format.
+///
+/// With deduplication, records are inserted along with their
+/// [`SinkRecord::idempotency_key`] in the [`ClickHouseRow::KEY_COLUMN`], and
+/// the table is created as a `ReplacingMergeTree` keyed by it. The records
+/// written more than once are then collapsed by the background merges, queries
+/// that must not see the duplicates before then use `FINAL`.
+#[derive(Debug, Clone)]
+pub struct ClickHouseSink<T> {
+    client: reqwest::Client,
+    url: String,
+    table: String,
+    credentials: Option<(String, String)>,
+    deduplication: bool,
+    _row: PhantomData<fn(&T)>,
+}
+
+impl<T: ClickHouseRow> ClickHouseSink<T> {
+    /// `url` is the base URL of the HTTP interface, e.g.,
+    /// `http://localhost:8123/`, and `table` can be qualified with the
+    /// database name.
+    pub fn new(url: String, table: String) -> Result<Self, SinkError> {
+        check_table_name(&table)?;
+        Ok(Self {
+            client: reqwest::Client::new(),
+            url,
+            table,
+            credentials: None,
+            deduplication: false,
+            _row: PhantomData,
+        })
+    }
+
+    pub fn with_credentials(mut self, user: String, password: String) -> Self {
+        self.credentials = Some((user, password));
+        self
+    }
+
+    /// Store the idempotency key of the records and deduplicate them, only
+    /// applies to the tables created by [`ClickHouseSink::bootstrap`]
+    pub fn with_deduplication(mut self) -> Self {
+        self.deduplication = true;
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    pub const fn deduplication(&self) -> bool {
+        self.deduplication
+    }
+
+    pub fn create_table_query(&self) -> String {
+        let (engine, key_column, order_by) = if self.deduplication {
+            (
+                "ReplacingMergeTree",
+                format!(", {} FixedString(32)", T::KEY_COLUMN),
+                format!("{}, {}", T::ORDER_BY, T::KEY_COLUMN),
+            )
+        } else {
+            ("MergeTree", String::new(), T::ORDER_BY.to_string())
+        };
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} ({}{key_column}) ENGINE = {engine} ORDER BY ({order_by})",
+            self.table,
+            T::COLUMNS,
+        )
+    }
+
+    pub fn insert_query(&self) -> String {
+        format!("INSERT INTO {} FORMAT JSONEachRow", self.table)
+    }
+
+    /// Body of the `INSERT` request, one JSON object per line
+    pub fn insert_body(&self, records: &[T]) -> Result<String, T::Error> {
+        let mut body = String::new();
+        for record in records {
+            let mut row = record.row()?;
+            if self.deduplication {
+                row[T::KEY_COLUMN] = record.idempotency_key()?.into();
+            }
+            body.push_str(&row.to_string());
+            body.push('\n');
+        }
+        Ok(body)
+    }
+
+    /// Create the table if it doesn't exist yet
+    pub async fn bootstrap(&self) -> Result<(), T::Error> {
+        Ok(self
+            .execute(self.create_table_query(), String::new())
+            .await?)
+    }
+
+    /// Insert a batch of records
+    pub async fn insert(&self, records: &[T]) -> Result<(), T::Error> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let body = self.insert_body(records)?;
+        Ok(self.execute(self.insert_query(), body).await?)
+    }
+
+    async fn execute(&self, query: String, body: String) -> Result<(), SinkError> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .query(&[("query", query)])
+            .body(body);
+        if let Some((user, password)) = &self.credentials {
+            request = request
+                .header("X-ClickHouse-User", user)
+                .header("X-ClickHouse-Key", password);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(SinkError::ClickHouseError {
+                status: status.as_u16(),
+                body: response.text().await.unwrap_or_default(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T: ClickHouseRow + Sync> RecordSink<T> for ClickHouseSink<T> {
+    async fn bootstrap(&mut self) -> Result<(), T::Error> {
+        ClickHouseSink::bootstrap(self).await
+    }
+
+    async fn write(&mut self, records: &[T]) -> Result<(), T::Error> {
+        self.insert(records).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SchemaVersion;
+
+    struct Route(&'static str);
+
+    impl SinkRecord for Route {
+        type Error = SinkError;
+
+        fn to_json(&self, _version: SchemaVersion) -> Result<serde_json::Value, SinkError> {
+            Ok(serde_json::json!({"prefix": self.0}))
+        }
+
+        fn idempotency_key(&self) -> Result<String, SinkError> {
+            Ok(format!("{:0>32}", self.0.len()))
+        }
+    }
+
+    impl ClickHouseRow for Route {
+        const COLUMNS: &'static str = "prefix String";
+        const ORDER_BY: &'static str = "prefix";
+        const KEY_COLUMN: &'static str = "route_id";
+
+        fn row(&self) -> Result<serde_json::Value, SinkError> {
+            self.to_json(SchemaVersion::V1)
+        }
+    }
+
+    #[test]
+    fn test_queries() {
+        let sink = ClickHouseSink::<Route>::new(
+            "http://localhost:8123/".to_string(),
+            "db.routes".to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            sink.create_table_query(),
+            "CREATE TABLE IF NOT EXISTS db.routes (prefix String) \
+             ENGINE = MergeTree ORDER BY (prefix)"
+        );
+        assert_eq!(
+            sink.insert_query(),
+            "INSERT INTO db.routes FORMAT JSONEachRow"
+        );
+        assert_eq!(
+            sink.insert_body(&[Route("192.0.2.0/24"), Route("2001:db8::/32")])
+                .unwrap(),
+            "{\"prefix\":\"192.0.2.0/24\"}\n{\"prefix\":\"2001:db8::/32\"}\n"
+        );
+        assert!(ClickHouseSink::<Route>::new(String::new(), "routes;".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_deduplication() {
+        let sink = ClickHouseSink::<Route>::new(
+            "http://localhost:8123/".to_string(),
+            "db.routes".to_string(),
+        )
+        .unwrap()
+        .with_deduplication();
+        assert!(sink.deduplication());
+        assert_eq!(
+            sink.create_table_query(),
+            "CREATE TABLE IF NOT EXISTS db.routes (prefix String, route_id FixedString(32)) \
+             ENGINE = ReplacingMergeTree ORDER BY (prefix, route_id)"
+        );
+        let body = sink.insert_body(&[Route("192.0.2.0/24")]).unwrap();
+        let row = serde_json::from_str::<serde_json::Value>(body.trim_end()).unwrap();
+        assert_eq!(row["route_id"], serde_json::json!(format!("{:0>32}", 12)));
+    }
+}
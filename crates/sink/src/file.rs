@@ -0,0 +1,410 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sink writing JSON Lines files into time-bucketed directories
+
+use std::{
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use tokio::{
+    fs::{self, File},
+    io::AsyncWriteExt,
+};
+
+use crate::{RecordSink, SchemaVersion, SinkError, SinkRecord};
+
+/// Default length of a time bucket
+pub const DEFAULT_BUCKET_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Default [`chrono::format::strftime`] layout of the bucket directories
+pub const DEFAULT_BUCKET_LAYOUT: &str = "%Y/%m/%d/%H";
+
+/// Name of the empty file created in a bucket directory once it's complete
+pub const COMPLETION_MARKER: &str = "_SUCCESS";
+
+const IN_PROGRESS_SUFFIX: &str = ".inprogress";
+
+impl From<std::io::Error> for SinkError {
+    fn from(value: std::io::Error) -> Self {
+        Self::IoError(value.to_string())
+    }
+}
+
+#[derive(Debug)]
+struct BucketFile {
+    start: DateTime<Utc>,
+    dir: PathBuf,
+    name: String,
+    path: PathBuf,
+    file: File,
+}
+
+/// Writes records as JSON Lines into one directory per time bucket, e.g.,
+/// `root/2024/03/28/13/routes-1711630800.jsonl` for hourly buckets.
+///
+/// Records are appended to a hidden in-progress file that is renamed to its
+/// final name when the bucket is over, then the bucket directory is marked as
+/// complete with an empty [`COMPLETION_MARKER`] file. Consumers should only
+/// pick up directories with a marker. Buckets are based on the time records are
+/// written, not on their own timestamps.
+///
+/// A completed file is never overwritten: when the sink is closed and the same
+/// bucket is written again, e.g., after a restart, the records go to a new file
+/// with a sequence number, `routes-1711630800.1.jsonl`, and the marker is
+/// removed until the bucket is completed again. Consumers of such buckets
+/// should keep track of the files they already processed.
+///
+/// Records are written with the current [`SchemaVersion`] unless pinned to an
+/// older one with [`FileSink::with_schema_version`].
+///
+/// Buckets are rotated when writing, [`FileSink::rotate`] should be called
+/// periodically to complete buckets when no records are received, and
+/// [`FileSink::close`] on shutdown.
+#[derive(Debug)]
+pub struct FileSink<T> {
+    root: PathBuf,
+    prefix: String,
+    interval: Duration,
+    layout: String,
+    schema_version: SchemaVersion,
+    current: Option<BucketFile>,
+    _record: PhantomData<fn(&T)>,
+}
+
+impl<T: SinkRecord> FileSink<T> {
+    /// Create a sink writing under `root` with hourly buckets, files are named
+    /// `{prefix}-{bucket start unix seconds}.jsonl`.
+    pub fn new(root: PathBuf, prefix: String) -> Self {
+        Self {
+            root,
+            prefix,
+            interval: DEFAULT_BUCKET_INTERVAL,
+            layout: DEFAULT_BUCKET_LAYOUT.to_string(),
+            schema_version: SchemaVersion::default(),
+            current: None,
+            _record: PhantomData,
+        }
+    }
+
+    /// Length of each bucket, rounded down to whole seconds and at least one
+    /// second.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = Duration::from_secs(interval.as_secs().max(1));
+        self
+    }
+
+    /// [`chrono::format::strftime`] layout of the bucket directories relative
+    /// to the root, it should be at least as fine grained as the interval.
+    pub fn with_layout(mut self, layout: String) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Layout of the written records, to keep feeding consumers that don't
+    /// support the current one yet
+    pub const fn with_schema_version(mut self, schema_version: SchemaVersion) -> Self {
+        self.schema_version = schema_version;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub const fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub fn layout(&self) -> &str {
+        &self.layout
+    }
+
+    pub const fn schema_version(&self) -> SchemaVersion {
+        self.schema_version
+    }
+
+    /// Path of the file currently being written, if any
+    pub fn in_progress(&self) -> Option<&Path> {
+        self.current.as_ref().map(|file| file.path.as_path())
+    }
+
+    /// Create the root directory
+    pub async fn bootstrap(&mut self) -> Result<(), T::Error> {
+        fs::create_dir_all(&self.root)
+            .await
+            .map_err(SinkError::from)?;
+        Ok(())
+    }
+
+    /// Append `records` to the file of the current bucket
+    pub async fn write(&mut self, records: &[T]) -> Result<(), T::Error> {
+        self.write_at(records, Utc::now()).await
+    }
+
+    /// Complete the current bucket if it's over
+    pub async fn rotate(&mut self) -> Result<(), T::Error> {
+        self.rotate_at(Utc::now()).await
+    }
+
+    /// Complete the current bucket regardless of the time
+    pub async fn close(&mut self) -> Result<(), T::Error> {
+        match self.current.take() {
+            Some(file) => Ok(Self::complete(file).await?),
+            None => Ok(()),
+        }
+    }
+
+    /// Same as [`Self::write`], with `now` as the current time
+    pub async fn write_at(&mut self, records: &[T], now: DateTime<Utc>) -> Result<(), T::Error> {
+        self.rotate_at(now).await?;
+        if self.current.is_none() {
+            self.current = Some(self.open(self.bucket_start(now)).await?);
+        }
+        let mut buf = vec![];
+        for record in records {
+            serde_json::to_writer(&mut buf, &record.to_json(self.schema_version)?)
+                .map_err(SinkError::from)?;
+            buf.push(b'\n');
+        }
+        let current = self.current.as_mut().expect("bucket file is opened above");
+        current
+            .file
+            .write_all(&buf)
+            .await
+            .map_err(SinkError::from)?;
+        Ok(())
+    }
+
+    /// Same as [`Self::rotate`], with `now` as the current time
+    pub async fn rotate_at(&mut self, now: DateTime<Utc>) -> Result<(), T::Error> {
+        let start = self.bucket_start(now);
+        match &self.current {
+            Some(file) if file.start != start => self.close().await,
+            _ => Ok(()),
+        }
+    }
+
+    fn bucket_start(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let secs = self.interval.as_secs() as i64;
+        let start = time.timestamp().div_euclid(secs) * secs;
+        Utc.timestamp_opt(start, 0).unwrap()
+    }
+
+    /// Open the in-progress file of the bucket starting at `start`. The first
+    /// name that isn't taken by a completed file is used, an in-progress file
+    /// left over by a crash is appended to.
+    async fn open(&self, start: DateTime<Utc>) -> Result<BucketFile, SinkError> {
+        let dir = self.root.join(start.format(&self.layout).to_string());
+        fs::create_dir_all(&dir).await?;
+        let base = format!("{}-{}", self.prefix, start.timestamp());
+        let mut name = format!("{base}.jsonl");
+        let mut sequence = 0;
+        while fs::metadata(dir.join(&name)).await.is_ok() {
+            sequence += 1;
+            name = format!("{base}.{sequence}.jsonl");
+        }
+        match fs::remove_file(dir.join(COMPLETION_MARKER)).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+        let path = dir.join(format!(".{name}{IN_PROGRESS_SUFFIX}"));
+        let file = File::options()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+        Ok(BucketFile {
+            start,
+            dir,
+            name,
+            path,
+            file,
+        })
+    }
+
+    async fn complete(file: BucketFile) -> Result<(), SinkError> {
+        let BucketFile {
+            dir,
+            name,
+            path,
+            file,
+            ..
+        } = file;
+        file.sync_all().await?;
+        fs::rename(&path, dir.join(name)).await?;
+        File::create(dir.join(COMPLETION_MARKER))
+            .await?
+            .sync_all()
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T: SinkRecord + Sync> RecordSink<T> for FileSink<T> {
+    async fn bootstrap(&mut self) -> Result<(), T::Error> {
+        FileSink::bootstrap(self).await
+    }
+
+    async fn write(&mut self, records: &[T]) -> Result<(), T::Error> {
+        FileSink::write(self, records).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+    struct Route {
+        prefix: String,
+    }
+
+    impl SinkRecord for Route {
+        type Error = SinkError;
+
+        fn to_json(&self, version: SchemaVersion) -> Result<serde_json::Value, SinkError> {
+            crate::to_versioned_json(self, version)
+        }
+
+        fn idempotency_key(&self) -> Result<String, SinkError> {
+            Ok(self.prefix.clone())
+        }
+    }
+
+    fn routes(prefixes: &[&str]) -> Vec<Route> {
+        prefixes
+            .iter()
+            .map(|prefix| Route {
+                prefix: prefix.to_string(),
+            })
+            .collect()
+    }
+
+    fn read_routes(path: &Path) -> Vec<Route> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let mut value = serde_json::from_str(line).unwrap();
+                crate::take_schema_version(&mut value).unwrap();
+                serde_json::from_value(value).unwrap()
+            })
+            .collect()
+    }
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("netgauze-sink-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        root
+    }
+
+    #[tokio::test]
+    async fn test_file_sink() {
+        let root = temp_root("file");
+        let mut sink = FileSink::<Route>::new(root.clone(), "routes".to_string())
+            .with_interval(Duration::from_secs(900))
+            .with_layout("%Y/%m/%d/%H%M".to_string());
+        sink.bootstrap().await.unwrap();
+
+        let first = Utc.with_ymd_and_hms(2024, 3, 28, 13, 5, 0).unwrap();
+        let second = Utc.with_ymd_and_hms(2024, 3, 28, 13, 15, 0).unwrap();
+        let first_dir = root.join("2024/03/28/1300");
+        let second_dir = root.join("2024/03/28/1315");
+
+        sink.write_at(&routes(&["192.0.2.0/24", "198.51.100.0/24"]), first)
+            .await
+            .unwrap();
+        sink.write_at(&routes(&["203.0.113.0/24"]), first)
+            .await
+            .unwrap();
+        assert_eq!(
+            sink.in_progress(),
+            Some(
+                first_dir
+                    .join(".routes-1711630800.jsonl.inprogress")
+                    .as_path()
+            )
+        );
+        // Rotating within the bucket is a no-op
+        sink.rotate_at(first).await.unwrap();
+        assert!(!first_dir.join(COMPLETION_MARKER).exists());
+
+        sink.rotate_at(second).await.unwrap();
+        assert!(sink.in_progress().is_none());
+        assert!(first_dir.join(COMPLETION_MARKER).exists());
+        assert_eq!(
+            read_routes(&first_dir.join("routes-1711630800.jsonl")),
+            routes(&["192.0.2.0/24", "198.51.100.0/24", "203.0.113.0/24"])
+        );
+
+        // Nothing is written until the next records arrive
+        assert!(!second_dir.exists());
+        sink.write_at(&routes(&["192.0.2.0/24"]), second)
+            .await
+            .unwrap();
+        sink.close().await.unwrap();
+        assert!(second_dir.join("routes-1711631700.jsonl").exists());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_file_sink_reopen_bucket() {
+        let root = temp_root("reopen");
+        let now = Utc.with_ymd_and_hms(2024, 3, 28, 13, 5, 0).unwrap();
+        let dir = root.join("2024/03/28/13");
+
+        let mut sink = FileSink::<Route>::new(root.clone(), "routes".to_string());
+        sink.write_at(&routes(&["192.0.2.0/24"]), now)
+            .await
+            .unwrap();
+        sink.close().await.unwrap();
+        assert!(dir.join(COMPLETION_MARKER).exists());
+
+        // A restarted sink doesn't overwrite the completed file of the bucket
+        let mut sink = FileSink::<Route>::new(root.clone(), "routes".to_string())
+            .with_schema_version(SchemaVersion::V1);
+        sink.write_at(&routes(&["198.51.100.0/24"]), now)
+            .await
+            .unwrap();
+        assert_eq!(
+            sink.in_progress(),
+            Some(dir.join(".routes-1711630800.1.jsonl.inprogress").as_path())
+        );
+        assert!(!dir.join(COMPLETION_MARKER).exists());
+        sink.close().await.unwrap();
+        assert!(dir.join(COMPLETION_MARKER).exists());
+
+        assert_eq!(
+            read_routes(&dir.join("routes-1711630800.jsonl")),
+            routes(&["192.0.2.0/24"])
+        );
+        let v1 = std::fs::read_to_string(dir.join("routes-1711630800.1.jsonl")).unwrap();
+        assert_eq!(v1, "{\"prefix\":\"198.51.100.0/24\"}\n");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage sinks shared by the BMP and flow services.
+//!
+//! The services flatten the decoded messages into their own record types,
+//! e.g., route events or flow records, and implement [`SinkRecord`] for them.
+//! Storage backends implement [`RecordSink`], and are usually written to
+//! through a [`batching::BatchingSink`] to insert the records in batches and
+//! retry failed writes. The sinks of this crate are generic over the record
+//! type:
+//! - `file`: [`file::FileSink`] writes JSON Lines files into time-bucketed
+//!   directories.
+//! - `clickhouse`: [`clickhouse::ClickHouseSink`] writes to ClickHouse over its
+//!   HTTP interface.
+//!
+//! Serialized records carry a [`SchemaVersion`], so consumers can be migrated
+//! to a new layout after upgrading the services.
+
+pub mod batching;
+#[cfg(feature = "clickhouse")]
+pub mod clickhouse;
+#[cfg(feature = "file")]
+pub mod file;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SinkError {
+    /// Table names are interpolated in the SQL statements, hence only ASCII
+    /// alphanumeric, `_`, and `.` characters are accepted
+    InvalidTableName(String),
+    SerializationError(String),
+    /// Serialized record with a `schema_version` this release can't read
+    UnsupportedSchemaVersion(serde_json::Value),
+    IoError(String),
+    /// Error of an embedded database, e.g., SQLite
+    DatabaseError(String),
+    HttpError(String),
+    /// ClickHouse server replied with a non-success status code
+    ClickHouseError {
+        status: u16,
+        body: String,
+    },
+}
+
+impl std::fmt::Display for SinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::error::Error for SinkError {}
+
+impl From<serde_json::Error> for SinkError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::SerializationError(value.to_string())
+    }
+}
+
+pub fn check_table_name(table: &str) -> Result<(), SinkError> {
+    let valid = !table.is_empty()
+        && table
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+    if valid {
+        Ok(())
+    } else {
+        Err(SinkError::InvalidTableName(table.to_string()))
+    }
+}
+
+/// Name of the field holding the schema version of serialized records
+pub const SCHEMA_VERSION_FIELD: &str = "schema_version";
+
+/// Layout of the JSON serialization of the records
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SchemaVersion {
+    /// Unversioned layout of the earlier releases, the record fields only
+    V1,
+    /// The record fields along with a [`SCHEMA_VERSION_FIELD`] field
+    #[default]
+    V2,
+}
+
+impl SchemaVersion {
+    pub const fn number(&self) -> u64 {
+        match self {
+            Self::V1 => 1,
+            Self::V2 => 2,
+        }
+    }
+
+    pub const fn from_number(number: u64) -> Option<Self> {
+        match number {
+            1 => Some(Self::V1),
+            2 => Some(Self::V2),
+            _ => None,
+        }
+    }
+}
+
+/// Serialize `record` and add the [`SCHEMA_VERSION_FIELD`] of `version`,
+/// except for [`SchemaVersion::V1`] that isn't versioned
+pub fn to_versioned_json<T: Serialize>(
+    record: &T,
+    version: SchemaVersion,
+) -> Result<serde_json::Value, SinkError> {
+    let mut value = serde_json::to_value(record)?;
+    if version != SchemaVersion::V1 {
+        if let Some(object) = value.as_object_mut() {
+            object.insert(SCHEMA_VERSION_FIELD.to_string(), version.number().into());
+        }
+    }
+    Ok(value)
+}
+
+/// Remove the [`SCHEMA_VERSION_FIELD`] of a serialized record, records without
+/// a version are [`SchemaVersion::V1`]
+pub fn take_schema_version(value: &mut serde_json::Value) -> Result<SchemaVersion, SinkError> {
+    match value
+        .as_object_mut()
+        .and_then(|object| object.remove(SCHEMA_VERSION_FIELD))
+    {
+        None => Ok(SchemaVersion::V1),
+        Some(version) => version
+            .as_u64()
+            .and_then(SchemaVersion::from_number)
+            .ok_or(SinkError::UnsupportedSchemaVersion(version)),
+    }
+}
+
+const FNV_128_OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+const FNV_128_PRIME: u128 = 0x0000000001000000000000000000013b;
+
+/// 128-bit FNV-1a hash, unlike [`std::hash::Hasher`]s it's stable across
+/// releases and platforms
+pub fn content_hash(data: &[u8]) -> u128 {
+    data.iter().fold(FNV_128_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u128::from(*byte)).wrapping_mul(FNV_128_PRIME)
+    })
+}
+
+/// [`content_hash`] of a serialized record as 32 lowercase hex digits, to be
+/// used as its [`SinkRecord::idempotency_key`]
+pub fn content_key(content: &serde_json::Value) -> String {
+    format!("{:032x}", content_hash(content.to_string().as_bytes()))
+}
+
+/// Records written by the sinks of this crate
+pub trait SinkRecord {
+    type Error: From<SinkError> + Send;
+
+    /// Serialize the record in the layout of `version`
+    fn to_json(&self, version: SchemaVersion) -> Result<serde_json::Value, Self::Error>;
+
+    /// Key identifying the content of the record, to deduplicate the records
+    /// written more than once
+    fn idempotency_key(&self) -> Result<String, Self::Error>;
+}
+
+/// Storage backend for records
+#[async_trait]
+pub trait RecordSink<R: SinkRecord> {
+    /// Create the storage schema if it doesn't exist yet
+    async fn bootstrap(&mut self) -> Result<(), R::Error>;
+
+    /// Write a batch of records
+    async fn write(&mut self, records: &[R]) -> Result<(), R::Error>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_table_name() {
+        assert_eq!(check_table_name("netgauze.flows"), Ok(()));
+        assert_eq!(
+            check_table_name("flows; DROP TABLE flows"),
+            Err(SinkError::InvalidTableName(
+                "flows; DROP TABLE flows".to_string()
+            ))
+        );
+        assert!(check_table_name("").is_err());
+    }
+
+    #[test]
+    fn test_content_hash() {
+        // FNV-1a 128 test vectors
+        assert_eq!(content_hash(b""), 0x6c62272e07bb014262b821756295c58d);
+        assert_eq!(content_hash(b"a"), 0xd228cb696f1a8caf78912b704e4a8964);
+
+        let key = content_key(&serde_json::json!({"prefix": "192.0.2.0/24"}));
+        assert_eq!(key.len(), 32);
+        assert_ne!(
+            key,
+            content_key(&serde_json::json!({"prefix": "192.0.2.0/25"}))
+        );
+    }
+
+    #[test]
+    fn test_schema_versions() {
+        let record = serde_json::json!({"prefix": "192.0.2.0/24"});
+        let v1 = to_versioned_json(&record, SchemaVersion::V1).unwrap();
+        let mut v2 = to_versioned_json(&record, SchemaVersion::V2).unwrap();
+        assert_eq!(v1, record);
+        assert_eq!(v2[SCHEMA_VERSION_FIELD], serde_json::json!(2));
+        assert_eq!(take_schema_version(&mut v1.clone()), Ok(SchemaVersion::V1));
+        assert_eq!(take_schema_version(&mut v2), Ok(SchemaVersion::V2));
+        assert_eq!(v2, record);
+
+        let mut v3 = serde_json::json!({"schema_version": 3});
+        assert_eq!(
+            take_schema_version(&mut v3),
+            Err(SinkError::UnsupportedSchemaVersion(serde_json::json!(3)))
+        );
+    }
+}
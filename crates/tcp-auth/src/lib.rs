@@ -0,0 +1,500 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Install TCP MD5 signature
+//! ([RFC2385](https://datatracker.ietf.org/doc/html/rfc2385)) and TCP
+//! Authentication Option
+//! ([RFC5925](https://datatracker.ietf.org/doc/html/rfc5925)) keys on
+//! sockets.
+//!
+//! The kernel signs and verifies the segments of the connections with a
+//! neighbor once a key for the neighbor's address is installed on the socket:
+//! on the listening socket for the accepted connections, or on the socket of
+//! an outgoing connection before connecting. Segments of a neighbor with a
+//! key that are not signed, or signed with another key, are dropped, so a
+//! key mismatch shows up as a connection timeout rather than an error.
+//!
+//! Only Linux is supported, on other platforms installing a key fails with
+//! [`io::ErrorKind::Unsupported`].
+//!
+//! ```no_run
+//! use netgauze_tcp_auth::{set_tcp_auth_key, TcpAuthKey};
+//! use std::net::{IpAddr, Ipv4Addr};
+//!
+//! let listener = std::net::TcpListener::bind("0.0.0.0:179").unwrap();
+//! let neighbor = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+//! set_tcp_auth_key(&listener, neighbor, &TcpAuthKey::md5("secret")).unwrap();
+//! ```
+
+use std::{
+    fmt::{Debug, Formatter},
+    io,
+    net::IpAddr,
+};
+
+#[cfg(unix)]
+use std::os::fd::AsFd;
+
+/// Maximum length of an MD5 or TCP-AO key accepted by Linux
+pub const MAX_KEY_LEN: usize = 80;
+
+/// MAC algorithms of TCP-AO, see
+/// [RFC5926](https://datatracker.ietf.org/doc/html/rfc5926)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TcpAoAlgorithm {
+    /// HMAC-SHA-1-96
+    HmacSha1,
+
+    /// AES-128-CMAC-96, the key must be 16 octets
+    AesCmac128,
+}
+
+impl TcpAoAlgorithm {
+    /// Name of the algorithm in the Linux crypto API
+    pub const fn kernel_name(&self) -> &'static str {
+        match self {
+            Self::HmacSha1 => "hmac(sha1)",
+            Self::AesCmac128 => "cmac(aes128)",
+        }
+    }
+
+    /// Length of the MAC in octets, both algorithms truncate it to 96 bits
+    pub const fn mac_len(&self) -> u8 {
+        12
+    }
+}
+
+/// Key authenticating the TCP segments exchanged with a neighbor.
+///
+/// The key itself is not printed by [`Debug`].
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum TcpAuthKey {
+    /// TCP MD5 signature option key
+    Md5(Vec<u8>),
+
+    /// TCP-AO Master Key Tuple, with the key IDs of the outgoing and incoming
+    /// segments
+    Ao {
+        send_id: u8,
+        recv_id: u8,
+        algorithm: TcpAoAlgorithm,
+        key: Vec<u8>,
+    },
+}
+
+impl TcpAuthKey {
+    pub fn md5(key: impl Into<Vec<u8>>) -> Self {
+        Self::Md5(key.into())
+    }
+
+    pub fn ao(
+        send_id: u8,
+        recv_id: u8,
+        algorithm: TcpAoAlgorithm,
+        key: impl Into<Vec<u8>>,
+    ) -> Self {
+        Self::Ao {
+            send_id,
+            recv_id,
+            algorithm,
+            key: key.into(),
+        }
+    }
+
+    pub fn key(&self) -> &[u8] {
+        match self {
+            Self::Md5(key) => key,
+            Self::Ao { key, .. } => key,
+        }
+    }
+}
+
+impl Debug for TcpAuthKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Md5(_) => f.debug_tuple("Md5").field(&"<redacted>").finish(),
+            Self::Ao {
+                send_id,
+                recv_id,
+                algorithm,
+                ..
+            } => f
+                .debug_struct("Ao")
+                .field("send_id", send_id)
+                .field("recv_id", recv_id)
+                .field("algorithm", algorithm)
+                .field("key", &"<redacted>")
+                .finish(),
+        }
+    }
+}
+
+/// Install `key` on `socket` for the connections with `peer`, replacing the
+/// MD5 key already installed for the same address.
+///
+/// IPv4 neighbors of IPv6 sockets are matched by their IPv4-mapped address,
+/// while IPv6 neighbors can't be configured on IPv4 sockets.
+#[cfg(unix)]
+pub fn set_tcp_auth_key<S: AsFd>(socket: &S, peer: IpAddr, key: &TcpAuthKey) -> io::Result<()> {
+    if key.key().len() > MAX_KEY_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("TCP authentication keys are at most {MAX_KEY_LEN} octets"),
+        ));
+    }
+    sys::set_key(socket.as_fd(), peer, key)
+}
+
+/// Remove `key` from `socket`, connections with `peer` are no longer
+/// authenticated with it
+#[cfg(unix)]
+pub fn remove_tcp_auth_key<S: AsFd>(socket: &S, peer: IpAddr, key: &TcpAuthKey) -> io::Result<()> {
+    sys::remove_key(socket.as_fd(), peer, key)
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::{
+        io, mem,
+        net::IpAddr,
+        os::fd::{AsRawFd, BorrowedFd},
+    };
+
+    use libc::{c_char, c_int, c_void, sockaddr_storage, socklen_t};
+
+    use crate::{TcpAuthKey, MAX_KEY_LEN};
+
+    const TCP_AO_ADD_KEY: c_int = 38;
+    const TCP_AO_DEL_KEY: c_int = 39;
+
+    /// `struct tcp_md5sig` of `linux/tcp.h`
+    #[repr(C)]
+    struct TcpMd5Sig {
+        addr: sockaddr_storage,
+        flags: u8,
+        prefix_len: u8,
+        key_len: u16,
+        ifindex: c_int,
+        key: [u8; MAX_KEY_LEN],
+    }
+
+    /// `struct tcp_ao_add` of `linux/tcp.h`
+    #[repr(C, align(8))]
+    struct TcpAoAdd {
+        addr: sockaddr_storage,
+        alg_name: [c_char; 64],
+        ifindex: i32,
+        /// `set_current` and `set_rnext` bit fields
+        flags: u32,
+        reserved: u16,
+        prefix: u8,
+        send_id: u8,
+        recv_id: u8,
+        mac_len: u8,
+        key_flags: u8,
+        key_len: u8,
+        key: [u8; MAX_KEY_LEN],
+    }
+
+    /// `struct tcp_ao_del` of `linux/tcp.h`
+    #[repr(C, align(8))]
+    struct TcpAoDel {
+        addr: sockaddr_storage,
+        ifindex: i32,
+        /// `set_current`, `set_rnext`, and `del_async` bit fields
+        flags: u32,
+        reserved: u16,
+        prefix: u8,
+        send_id: u8,
+        recv_id: u8,
+        current_key: u8,
+        rnext: u8,
+        key_flags: u8,
+    }
+
+    fn socket_domain(fd: BorrowedFd<'_>) -> io::Result<c_int> {
+        let mut domain: c_int = 0;
+        let mut len = mem::size_of::<c_int>() as socklen_t;
+        // SAFETY: `domain` and `len` are valid for writes of the given length
+        let ret = unsafe {
+            libc::getsockopt(
+                fd.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_DOMAIN,
+                &mut domain as *mut c_int as *mut c_void,
+                &mut len,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(domain)
+    }
+
+    /// Address of `peer` in the family of the socket, and its prefix length
+    fn peer_addr(fd: BorrowedFd<'_>, peer: IpAddr) -> io::Result<(sockaddr_storage, u8)> {
+        let peer = match (socket_domain(fd)?, peer) {
+            (libc::AF_INET6, IpAddr::V4(addr)) => IpAddr::V6(addr.to_ipv6_mapped()),
+            (libc::AF_INET, IpAddr::V6(addr)) => match addr.to_ipv4_mapped() {
+                Some(addr) => IpAddr::V4(addr),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("IPv6 neighbor {addr} can't be configured on an IPv4 socket"),
+                    ))
+                }
+            },
+            (_, peer) => peer,
+        };
+        // SAFETY: all zeros is a valid sockaddr_storage
+        let mut storage: sockaddr_storage = unsafe { mem::zeroed() };
+        let prefix = match peer {
+            IpAddr::V4(addr) => {
+                // SAFETY: sockaddr_storage is large and aligned enough for
+                // any socket address
+                let sin = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in) };
+                sin.sin_family = libc::AF_INET as libc::sa_family_t;
+                sin.sin_addr.s_addr = u32::from_ne_bytes(addr.octets());
+                32
+            }
+            IpAddr::V6(addr) => {
+                // SAFETY: sockaddr_storage is large and aligned enough for
+                // any socket address
+                let sin6 = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in6) };
+                sin6.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+                sin6.sin6_addr.s6_addr = addr.octets();
+                128
+            }
+        };
+        Ok((storage, prefix))
+    }
+
+    fn set_option<T>(fd: BorrowedFd<'_>, option: c_int, value: &T) -> io::Result<()> {
+        // SAFETY: `value` is a valid `T` of the given length
+        let ret = unsafe {
+            libc::setsockopt(
+                fd.as_raw_fd(),
+                libc::IPPROTO_TCP,
+                option,
+                value as *const T as *const c_void,
+                mem::size_of::<T>() as socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn md5sig(addr: sockaddr_storage, key: &[u8]) -> TcpMd5Sig {
+        let mut md5sig = TcpMd5Sig {
+            addr,
+            flags: 0,
+            prefix_len: 0,
+            key_len: key.len() as u16,
+            ifindex: 0,
+            key: [0; MAX_KEY_LEN],
+        };
+        md5sig.key[..key.len()].copy_from_slice(key);
+        md5sig
+    }
+
+    pub(crate) fn set_key(fd: BorrowedFd<'_>, peer: IpAddr, key: &TcpAuthKey) -> io::Result<()> {
+        let (addr, prefix) = peer_addr(fd, peer)?;
+        match key {
+            TcpAuthKey::Md5(key) => set_option(fd, libc::TCP_MD5SIG, &md5sig(addr, key)),
+            TcpAuthKey::Ao {
+                send_id,
+                recv_id,
+                algorithm,
+                key,
+            } => {
+                let mut add = TcpAoAdd {
+                    addr,
+                    alg_name: [0; 64],
+                    ifindex: 0,
+                    flags: 0,
+                    reserved: 0,
+                    prefix,
+                    send_id: *send_id,
+                    recv_id: *recv_id,
+                    mac_len: algorithm.mac_len(),
+                    key_flags: 0,
+                    key_len: key.len() as u8,
+                    key: [0; MAX_KEY_LEN],
+                };
+                for (dst, src) in add.alg_name.iter_mut().zip(algorithm.kernel_name().bytes()) {
+                    *dst = src as c_char;
+                }
+                add.key[..key.len()].copy_from_slice(key);
+                set_option(fd, TCP_AO_ADD_KEY, &add)
+            }
+        }
+    }
+
+    pub(crate) fn remove_key(fd: BorrowedFd<'_>, peer: IpAddr, key: &TcpAuthKey) -> io::Result<()> {
+        let (addr, prefix) = peer_addr(fd, peer)?;
+        match key {
+            // An empty key removes the key of the address
+            TcpAuthKey::Md5(_) => set_option(fd, libc::TCP_MD5SIG, &md5sig(addr, &[])),
+            TcpAuthKey::Ao {
+                send_id, recv_id, ..
+            } => {
+                let del = TcpAoDel {
+                    addr,
+                    ifindex: 0,
+                    flags: 0,
+                    reserved: 0,
+                    prefix,
+                    send_id: *send_id,
+                    recv_id: *recv_id,
+                    current_key: 0,
+                    rnext: 0,
+                    key_flags: 0,
+                };
+                set_option(fd, TCP_AO_DEL_KEY, &del)
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_struct_layout() {
+            assert_eq!(mem::size_of::<TcpMd5Sig>(), 216);
+            assert_eq!(mem::size_of::<TcpAoAdd>(), 288);
+            assert_eq!(mem::size_of::<TcpAoDel>(), 144);
+        }
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+mod sys {
+    use std::{io, net::IpAddr, os::fd::BorrowedFd};
+
+    use crate::TcpAuthKey;
+
+    fn unsupported() -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "TCP authentication keys are only supported on Linux",
+        )
+    }
+
+    pub(crate) fn set_key(_fd: BorrowedFd<'_>, _peer: IpAddr, _key: &TcpAuthKey) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub(crate) fn remove_key(
+        _fd: BorrowedFd<'_>,
+        _peer: IpAddr,
+        _key: &TcpAuthKey,
+    ) -> io::Result<()> {
+        Err(unsupported())
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::{
+        net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+        time::Duration,
+    };
+    use tokio::net::{TcpListener, TcpSocket, TcpStream};
+
+    const LOCALHOST: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    async fn connect(addr: SocketAddr, key: Option<&TcpAuthKey>) -> io::Result<TcpStream> {
+        let socket = TcpSocket::new_v4()?;
+        if let Some(key) = key {
+            set_tcp_auth_key(&socket, LOCALHOST, key)?;
+        }
+        match tokio::time::timeout(Duration::from_millis(500), socket.connect(addr)).await {
+            Ok(stream) => stream,
+            Err(_) => Err(io::ErrorKind::TimedOut.into()),
+        }
+    }
+
+    #[test]
+    fn test_debug_redacts_key() {
+        let md5 = TcpAuthKey::md5("secret");
+        assert_eq!(format!("{md5:?}"), r#"Md5("<redacted>")"#);
+        let ao = TcpAuthKey::ao(1, 2, TcpAoAlgorithm::HmacSha1, "secret");
+        assert!(!format!("{ao:?}").contains("secret"));
+        assert_eq!(ao.key(), b"secret");
+    }
+
+    #[tokio::test]
+    async fn test_md5() {
+        let listener = TcpListener::bind((LOCALHOST, 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let key = TcpAuthKey::md5("secret");
+        set_tcp_auth_key(&listener, LOCALHOST, &key).unwrap();
+
+        let stream = connect(addr, Some(&key)).await.unwrap();
+        let (accepted, _) = listener.accept().await.unwrap();
+        assert_eq!(accepted.peer_addr().unwrap(), stream.local_addr().unwrap());
+
+        // Segments without or with another signature are dropped
+        let err = connect(addr, None).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        let err = connect(addr, Some(&TcpAuthKey::md5("other")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+        remove_tcp_auth_key(&listener, LOCALHOST, &key).unwrap();
+        connect(addr, None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_invalid_keys() {
+        let listener = TcpListener::bind((LOCALHOST, 0)).await.unwrap();
+        let err = set_tcp_auth_key(
+            &listener,
+            LOCALHOST,
+            &TcpAuthKey::md5(vec![0; MAX_KEY_LEN + 1]),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = set_tcp_auth_key(
+            &listener,
+            IpAddr::V6(Ipv6Addr::LOCALHOST),
+            &TcpAuthKey::md5("secret"),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[tokio::test]
+    async fn test_ao() {
+        let listener = TcpListener::bind((LOCALHOST, 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let key = TcpAuthKey::ao(1, 1, TcpAoAlgorithm::HmacSha1, "secret");
+        match set_tcp_auth_key(&listener, LOCALHOST, &key) {
+            Ok(()) => {}
+            // The kernel is built without TCP-AO
+            Err(err) if err.raw_os_error() == Some(libc::ENOPROTOOPT) => return,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+        connect(addr, Some(&key)).await.unwrap();
+        let err = connect(addr, None).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        remove_tcp_auth_key(&listener, LOCALHOST, &key).unwrap();
+    }
+}
@@ -0,0 +1,543 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Open the outgoing TCP connections of BGP and BMP sessions.
+//!
+//! [`TcpConnectConfig`] holds the per-neighbor transport options: the source
+//! address or interface of the connection, the TTL of the outgoing segments,
+//! or the Generalized TTL Security Mechanism
+//! ([RFC5082](https://datatracker.ietf.org/doc/html/rfc5082)), and, with the
+//! `tcp-auth` feature, the TCP MD5 or TCP-AO key of the neighbor.
+//!
+//! Neighbors with several addresses are connected with Happy Eyeballs
+//! ([RFC8305](https://datatracker.ietf.org/doc/html/rfc8305)), see
+//! [`TcpConnectConfig::connect_any`].
+//!
+//! ```no_run
+//! use netgauze_tcp_connect::TcpConnectConfig;
+//! use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+//!
+//! # async fn connect() -> std::io::Result<()> {
+//! let config = TcpConnectConfig::default()
+//!     .with_source_address(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)))
+//!     .with_gtsm(1);
+//! let neighbor = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)), 179);
+//! let _stream = config.connect(neighbor).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::VecDeque,
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+
+#[cfg(unix)]
+use std::os::fd::AsFd;
+
+use futures_util::{stream::FuturesUnordered, StreamExt};
+use tokio::net::{lookup_host, TcpSocket, TcpStream, ToSocketAddrs};
+
+/// Delay before starting the next connection attempt while the previous ones
+/// are still pending, as recommended by RFC8305
+pub const DEFAULT_CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// TTL of the segments sent with GTSM enabled
+pub const GTSM_TTL: u8 = 255;
+
+/// Options of the outgoing TCP connections to a neighbor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcpConnectConfig {
+    source_v4: Option<Ipv4Addr>,
+    source_v6: Option<Ipv6Addr>,
+    interface: Option<String>,
+    ttl: Option<u8>,
+    min_ttl: Option<u8>,
+    connection_attempt_delay: Duration,
+    #[cfg(feature = "tcp-auth")]
+    tcp_auth_key: Option<netgauze_tcp_auth::TcpAuthKey>,
+}
+
+impl Default for TcpConnectConfig {
+    fn default() -> Self {
+        Self {
+            source_v4: None,
+            source_v6: None,
+            interface: None,
+            ttl: None,
+            min_ttl: None,
+            connection_attempt_delay: DEFAULT_CONNECTION_ATTEMPT_DELAY,
+            #[cfg(feature = "tcp-auth")]
+            tcp_auth_key: None,
+        }
+    }
+}
+
+impl TcpConnectConfig {
+    /// Bind the connections of the address family of `address` to it. Call it
+    /// once per address family to configure both.
+    pub fn with_source_address(mut self, address: IpAddr) -> Self {
+        match address {
+            IpAddr::V4(address) => self.source_v4 = Some(address),
+            IpAddr::V6(address) => self.source_v6 = Some(address),
+        }
+        self
+    }
+
+    /// Send the connections out of the given interface, regardless of the
+    /// routing table. Only supported on Linux.
+    pub fn with_interface(mut self, interface: impl Into<String>) -> Self {
+        self.interface = Some(interface.into());
+        self
+    }
+
+    /// TTL (or hop limit for IPv6) of the outgoing segments, e.g. for multihop
+    /// eBGP sessions
+    pub fn with_ttl(mut self, ttl: u8) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Enable GTSM for a neighbor `hops` hops away (1 for directly connected
+    /// neighbors): segments are sent with a TTL of [`GTSM_TTL`], and the
+    /// received ones with a TTL lower than `256 - hops` are dropped. Only
+    /// supported on Linux.
+    pub fn with_gtsm(mut self, hops: u8) -> Self {
+        self.ttl = Some(GTSM_TTL);
+        self.min_ttl = Some(GTSM_TTL - (hops.max(1) - 1));
+        self
+    }
+
+    pub fn with_connection_attempt_delay(mut self, delay: Duration) -> Self {
+        self.connection_attempt_delay = delay;
+        self
+    }
+
+    /// Authenticate the connections with TCP MD5 or TCP-AO, see
+    /// [`netgauze_tcp_auth`]
+    #[cfg(feature = "tcp-auth")]
+    pub fn with_tcp_auth_key(mut self, key: netgauze_tcp_auth::TcpAuthKey) -> Self {
+        self.tcp_auth_key = Some(key);
+        self
+    }
+
+    pub const fn source_v4(&self) -> Option<Ipv4Addr> {
+        self.source_v4
+    }
+
+    pub const fn source_v6(&self) -> Option<Ipv6Addr> {
+        self.source_v6
+    }
+
+    pub fn interface(&self) -> Option<&str> {
+        self.interface.as_deref()
+    }
+
+    pub const fn ttl(&self) -> Option<u8> {
+        self.ttl
+    }
+
+    pub const fn min_ttl(&self) -> Option<u8> {
+        self.min_ttl
+    }
+
+    pub const fn connection_attempt_delay(&self) -> Duration {
+        self.connection_attempt_delay
+    }
+
+    #[cfg(feature = "tcp-auth")]
+    pub const fn tcp_auth_key(&self) -> Option<&netgauze_tcp_auth::TcpAuthKey> {
+        self.tcp_auth_key.as_ref()
+    }
+
+    /// Create a socket configured to connect to `peer`
+    pub fn socket(&self, peer: SocketAddr) -> io::Result<TcpSocket> {
+        let (socket, source) = match peer {
+            SocketAddr::V4(_) => (TcpSocket::new_v4()?, self.source_v4.map(IpAddr::V4)),
+            SocketAddr::V6(_) => (TcpSocket::new_v6()?, self.source_v6.map(IpAddr::V6)),
+        };
+        if let Some(interface) = &self.interface {
+            #[cfg(target_os = "linux")]
+            socket.bind_device(Some(interface.as_bytes()))?;
+            #[cfg(not(target_os = "linux"))]
+            return Err(unsupported(format!("binding to interface {interface}")));
+        }
+        if let Some(source) = source {
+            socket.bind(SocketAddr::new(source, 0))?;
+        }
+        if let Some(ttl) = self.ttl {
+            #[cfg(unix)]
+            set_ttl(&socket, ttl)?;
+            #[cfg(not(unix))]
+            return Err(unsupported(format!("setting the TTL to {ttl}")));
+        }
+        if let Some(min_ttl) = self.min_ttl {
+            #[cfg(unix)]
+            set_min_ttl(&socket, min_ttl)?;
+            #[cfg(not(unix))]
+            return Err(unsupported(format!("setting the minimum TTL to {min_ttl}")));
+        }
+        #[cfg(feature = "tcp-auth")]
+        if let Some(key) = &self.tcp_auth_key {
+            netgauze_tcp_auth::set_tcp_auth_key(&socket, peer.ip(), key)?;
+        }
+        Ok(socket)
+    }
+
+    /// Connect to a single address of the neighbor
+    pub async fn connect(&self, peer: SocketAddr) -> io::Result<TcpStream> {
+        self.socket(peer)?.connect(peer).await
+    }
+
+    /// Connect to the first reachable address of `peers` with Happy Eyeballs:
+    /// the attempts alternate between the address families, starting with the
+    /// family of the first address, and a new attempt is started every
+    /// [`Self::connection_attempt_delay`], or as soon as the previous attempt
+    /// failed. The first established connection is returned and the pending
+    /// attempts are dropped. If all the attempts fail, the error of the last
+    /// one is returned.
+    pub async fn connect_any(&self, peers: &[SocketAddr]) -> io::Result<TcpStream> {
+        let mut candidates = VecDeque::from(interleave_address_families(peers));
+        let mut attempts = FuturesUnordered::new();
+        let mut last_err = None;
+        loop {
+            if attempts.is_empty() {
+                match candidates.pop_front() {
+                    Some(peer) => attempts.push(self.connect(peer)),
+                    None => {
+                        return Err(last_err.unwrap_or_else(|| {
+                            io::Error::new(io::ErrorKind::InvalidInput, "no address to connect to")
+                        }))
+                    }
+                }
+            }
+            let next_attempt = tokio::time::sleep(self.connection_attempt_delay);
+            tokio::select! {
+                Some(result) = attempts.next() => match result {
+                    Ok(stream) => return Ok(stream),
+                    Err(err) => {
+                        last_err = Some(err);
+                        if let Some(peer) = candidates.pop_front() {
+                            attempts.push(self.connect(peer));
+                        }
+                    }
+                },
+                _ = next_attempt, if !candidates.is_empty() => {
+                    if let Some(peer) = candidates.pop_front() {
+                        attempts.push(self.connect(peer));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Resolve `host` and connect to its addresses with
+    /// [`Self::connect_any`]
+    pub async fn connect_host<A: ToSocketAddrs>(&self, host: A) -> io::Result<TcpStream> {
+        let peers: Vec<SocketAddr> = lookup_host(host).await?.collect();
+        self.connect_any(&peers).await
+    }
+}
+
+/// Order `addrs` for connection attempts as in RFC8305: alternate between the
+/// address families, starting with the family of the first address and
+/// otherwise keeping the order of the addresses within each family.
+pub fn interleave_address_families(addrs: &[SocketAddr]) -> Vec<SocketAddr> {
+    let Some(first) = addrs.first() else {
+        return vec![];
+    };
+    let (mut preferred, mut other): (VecDeque<_>, VecDeque<_>) = addrs
+        .iter()
+        .copied()
+        .partition(|addr| addr.is_ipv4() == first.is_ipv4());
+    let mut ordered = Vec::with_capacity(addrs.len());
+    while !preferred.is_empty() || !other.is_empty() {
+        ordered.extend(preferred.pop_front());
+        ordered.extend(other.pop_front());
+    }
+    ordered
+}
+
+#[cfg(not(unix))]
+fn unsupported(what: String) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{what} is not supported on this platform"),
+    )
+}
+
+/// Set the TTL, or the hop limit for IPv6 sockets, of the segments sent on
+/// `socket`
+#[cfg(unix)]
+pub fn set_ttl<S: AsFd>(socket: &S, ttl: u8) -> io::Result<()> {
+    sys::set_ttl(socket.as_fd(), ttl)
+}
+
+/// Drop the segments received on `socket` with a TTL, or hop limit for IPv6
+/// sockets, lower than `min_ttl`. Set on a listening socket, it applies to the
+/// accepted connections as well.
+#[cfg(unix)]
+pub fn set_min_ttl<S: AsFd>(socket: &S, min_ttl: u8) -> io::Result<()> {
+    sys::set_min_ttl(socket.as_fd(), min_ttl)
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::{
+        io,
+        os::fd::{AsRawFd, BorrowedFd},
+    };
+
+    use libc::c_int;
+
+    fn is_ipv6(fd: BorrowedFd<'_>) -> io::Result<bool> {
+        // SAFETY: sockaddr_storage is plain old data
+        let mut addr: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+        let mut len = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+        // SAFETY: addr is large enough for any socket address, and len holds
+        // its size
+        let ret = unsafe {
+            libc::getsockname(
+                fd.as_raw_fd(),
+                &mut addr as *mut libc::sockaddr_storage as *mut libc::sockaddr,
+                &mut len,
+            )
+        };
+        if ret == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(c_int::from(addr.ss_family) == libc::AF_INET6)
+    }
+
+    fn set_option(fd: BorrowedFd<'_>, level: c_int, option: c_int, value: c_int) -> io::Result<()> {
+        // SAFETY: value outlives the call and the length is its size
+        let ret = unsafe {
+            libc::setsockopt(
+                fd.as_raw_fd(),
+                level,
+                option,
+                &value as *const c_int as *const libc::c_void,
+                std::mem::size_of::<c_int>() as libc::socklen_t,
+            )
+        };
+        if ret == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn set_ttl(fd: BorrowedFd<'_>, ttl: u8) -> io::Result<()> {
+        if is_ipv6(fd)? {
+            set_option(
+                fd,
+                libc::IPPROTO_IPV6,
+                libc::IPV6_UNICAST_HOPS,
+                c_int::from(ttl),
+            )
+        } else {
+            set_option(fd, libc::IPPROTO_IP, libc::IP_TTL, c_int::from(ttl))
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn set_min_ttl(fd: BorrowedFd<'_>, min_ttl: u8) -> io::Result<()> {
+        if is_ipv6(fd)? {
+            set_option(
+                fd,
+                libc::IPPROTO_IPV6,
+                libc::IPV6_MINHOPCOUNT,
+                c_int::from(min_ttl),
+            )
+        } else {
+            set_option(fd, libc::IPPROTO_IP, libc::IP_MINTTL, c_int::from(min_ttl))
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn set_min_ttl(_fd: BorrowedFd<'_>, _min_ttl: u8) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "minimum TTL is only supported on Linux",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn addr(value: &str) -> SocketAddr {
+        value.parse().unwrap()
+    }
+
+    /// Port on the loopback that refuses connections
+    fn closed_port() -> SocketAddr {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap()
+    }
+
+    #[test]
+    fn test_interleave_address_families() {
+        assert_eq!(interleave_address_families(&[]), vec![]);
+        assert_eq!(
+            interleave_address_families(&[
+                addr("[2001:db8::1]:179"),
+                addr("[2001:db8::2]:179"),
+                addr("[2001:db8::3]:179"),
+                addr("192.0.2.1:179"),
+            ]),
+            vec![
+                addr("[2001:db8::1]:179"),
+                addr("192.0.2.1:179"),
+                addr("[2001:db8::2]:179"),
+                addr("[2001:db8::3]:179"),
+            ]
+        );
+        assert_eq!(
+            interleave_address_families(&[
+                addr("192.0.2.1:179"),
+                addr("192.0.2.2:179"),
+                addr("[2001:db8::1]:179"),
+                addr("[2001:db8::2]:179"),
+            ]),
+            vec![
+                addr("192.0.2.1:179"),
+                addr("[2001:db8::1]:179"),
+                addr("192.0.2.2:179"),
+                addr("[2001:db8::2]:179"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gtsm() {
+        let config = TcpConnectConfig::default().with_gtsm(1);
+        assert_eq!(config.ttl(), Some(255));
+        assert_eq!(config.min_ttl(), Some(255));
+        let config = TcpConnectConfig::default().with_gtsm(3);
+        assert_eq!(config.min_ttl(), Some(253));
+        let config = TcpConnectConfig::default().with_gtsm(0);
+        assert_eq!(config.min_ttl(), Some(255));
+    }
+
+    #[tokio::test]
+    async fn test_connect_any() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = listener.local_addr().unwrap();
+        let closed = closed_port();
+        let config = TcpConnectConfig::default();
+
+        let stream = config.connect_any(&[closed, open]).await.unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), open);
+
+        let err = config.connect_any(&[closed]).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+        let err = config.connect_any(&[]).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[tokio::test]
+    async fn test_connect_any_pending_attempt() {
+        let open_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = open_listener.local_addr().unwrap();
+        // A listener with a full backlog that is never accepted from keeps the
+        // next connection attempts pending
+        let full_socket = TcpSocket::new_v4().unwrap();
+        full_socket.bind(addr("127.0.0.1:0")).unwrap();
+        let full_listener = full_socket.listen(0).unwrap();
+        let full = full_listener.local_addr().unwrap();
+        let mut backlog = vec![];
+        for _ in 0..4 {
+            if let Ok(Ok(stream)) =
+                tokio::time::timeout(Duration::from_millis(100), TcpStream::connect(full)).await
+            {
+                backlog.push(stream);
+            }
+        }
+
+        let config =
+            TcpConnectConfig::default().with_connection_attempt_delay(Duration::from_millis(50));
+        let stream =
+            tokio::time::timeout(Duration::from_secs(5), config.connect_any(&[full, open]))
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), open);
+    }
+
+    #[tokio::test]
+    async fn test_source_address() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = TcpConnectConfig::default()
+            .with_source_address(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)));
+        let stream = config
+            .connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (_, remote) = listener.accept().await.unwrap();
+        assert_eq!(stream.local_addr().unwrap().ip(), remote.ip());
+        assert_eq!(remote.ip(), IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)));
+    }
+
+    #[tokio::test]
+    async fn test_ttl() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = TcpConnectConfig::default().with_ttl(7);
+        let stream = config
+            .connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(stream.ttl().unwrap(), 7);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_gtsm_connect() {
+        let config = TcpConnectConfig::default()
+            .with_gtsm(1)
+            .with_connection_attempt_delay(Duration::from_millis(50));
+
+        // The SYN-ACK of a listener with the default TTL is dropped
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let result = tokio::time::timeout(
+            Duration::from_millis(500),
+            config.connect(listener.local_addr().unwrap()),
+        )
+        .await;
+        assert!(result.is_err());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        set_ttl(&listener, GTSM_TTL).unwrap();
+        set_min_ttl(&listener, GTSM_TTL).unwrap();
+        let stream = tokio::time::timeout(
+            Duration::from_secs(5),
+            config.connect(listener.local_addr().unwrap()),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(stream.ttl().unwrap(), u32::from(GTSM_TTL));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_unknown_interface() {
+        let config = TcpConnectConfig::default().with_interface("netgauze-none");
+        assert!(config.socket(addr("127.0.0.1:179")).is_err());
+    }
+}
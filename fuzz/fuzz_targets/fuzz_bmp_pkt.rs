@@ -21,7 +21,9 @@ use std::collections::HashMap;
 use libfuzzer_sys::fuzz_target;
 
 use netgauze_bgp_pkt::wire::deserializer::BgpParsingContext;
-use netgauze_bmp_pkt::{BmpMessage, PeerKey};
+use netgauze_bmp_pkt::{
+    wire::deserializer::BmpParsingContext, BmpMessage, PeerKey, PeerKeyStrategy,
+};
 use netgauze_iana::address_family::AddressType;
 use netgauze_parse_utils::{ReadablePduWithOneInput, Span};
 
@@ -29,6 +31,7 @@ use netgauze_parse_utils::{ReadablePduWithOneInput, Span};
 // generate BgpParsingContext::parsing_errors.
 fuzz_target!(|data: (
     &[u8],
+    PeerKeyStrategy,
     HashMap<
         PeerKey,
         (
@@ -42,37 +45,35 @@ fuzz_target!(|data: (
         ),
     >,
 )| {
-    let (mut buf, ctx_params) = data;
-    let mut ctx = ctx_params
-        .iter()
-        .map(
-            |(
-                k,
-                (
-                    asn4,
-                    multiple_labels,
-                    add_path,
-                    fail_on_non_unicast_withdraw_nlri,
-                    fail_on_non_unicast_update_nlri,
-                    fail_on_capability_error,
-                    fail_on_malformed_path_attr,
+    let (mut buf, peer_key_strategy, ctx_params) = data;
+    let mut ctx = BmpParsingContext::new(peer_key_strategy);
+    ctx.peers_mut().extend(ctx_params.iter().map(
+        |(
+            k,
+            (
+                asn4,
+                multiple_labels,
+                add_path,
+                fail_on_non_unicast_withdraw_nlri,
+                fail_on_non_unicast_update_nlri,
+                fail_on_capability_error,
+                fail_on_malformed_path_attr,
+            ),
+        )| {
+            (
+                *k,
+                BgpParsingContext::new(
+                    *asn4,
+                    multiple_labels.clone(),
+                    add_path.clone(),
+                    *fail_on_non_unicast_withdraw_nlri,
+                    *fail_on_non_unicast_update_nlri,
+                    *fail_on_capability_error,
+                    *fail_on_malformed_path_attr,
                 ),
-            )| {
-                (
-                    *k,
-                    BgpParsingContext::new(
-                        *asn4,
-                        multiple_labels.clone(),
-                        add_path.clone(),
-                        *fail_on_non_unicast_withdraw_nlri,
-                        *fail_on_non_unicast_update_nlri,
-                        *fail_on_capability_error,
-                        *fail_on_malformed_path_attr,
-                    ),
-                )
-            },
-        )
-        .collect();
+            )
+        },
+    ));
     while let Ok((retbuf, _msg)) = BmpMessage::from_wire(Span::new(buf), &mut ctx) {
         buf = retbuf.fragment();
     }